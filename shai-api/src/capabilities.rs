@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// What the server and its agents can do, returned by GET /v1/capabilities
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesResponse {
+    pub server: ServerCapabilities,
+    /// The "default" agent first, then the agents found in the config directory
+    pub agents: Vec<AgentCapabilities>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerCapabilities {
+    pub version: String,
+    /// Supported APIs, e.g. "chat_completions" or "responses"
+    pub apis: Vec<String>,
+    /// Sessions are destroyed after a single query
+    pub ephemeral: bool,
+    pub max_sessions: Option<usize>,
+    /// Agents run tools without asking for permission
+    pub sudo: bool,
+    pub permission_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentCapabilities {
+    /// Name to pass as `model` in requests
+    pub name: String,
+    pub description: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub tools: Vec<ToolInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub name: String,
+    /// Empty when unknown before connecting, as for MCP tools
+    pub capabilities: Vec<ToolCapability>,
+    /// "builtin" or "mcp:<server name>"
+    pub source: String,
+}
+
+/// What calling a tool may do, as the server lists it for each tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ToolCapability {
+    Read,
+    Write,
+    Network,
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Error of a refused request, sent as `{"error": {...}}`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    pub message: String,
+    pub r#type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
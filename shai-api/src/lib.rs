@@ -0,0 +1,6 @@
+pub mod capabilities;
+pub mod error;
+pub mod simple;
+
+pub use capabilities::ToolCapability;
+pub use error::ErrorDetail;
@@ -39,6 +39,8 @@ pub struct UserMessage {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub attached_files: Option<HashMap<String, String>>, // { filename: base64file, ... }
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<String>>, // http(s) URL, data: URL or raw base64
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,6 +131,9 @@ pub struct MultiModalQuery {
 pub struct MultiModalStreamingResponse {
     pub id: String,
     pub model: String,
+    /// model reasoning, apart from the answer in `assistant`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub assistant: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -149,4 +154,4 @@ pub struct MultiModalResponse {
     pub id: String,
     pub model: String,
     pub result: Vec<ResponseMessage>,
-}
\ No newline at end of file
+}
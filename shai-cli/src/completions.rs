@@ -0,0 +1,87 @@
+use clap::builder::PossibleValuesParser;
+use clap::Command;
+use clap_complete::{generate, Shell};
+
+use crate::headless::tools::ToolName;
+
+/// Completion script of `shai completions <shell>`. clap only knows the static command line,
+/// so the agents (name, description) and the tool names are added as real values before generating
+pub fn completion_script(mut cmd: Command, shell: Shell, agents: &[(String, String)]) -> String {
+    let tools: Vec<&'static str> = ToolName::all().iter().chain([&ToolName::Git]).map(ToolName::name).collect();
+    let agent_names: Vec<String> = agents.iter().map(|(name, _)| name.clone()).collect();
+
+    for flag in ["tools", "remove"] {
+        let tools = tools.clone();
+        cmd = cmd.mut_arg(flag, |arg| arg.value_parser(PossibleValuesParser::new(tools)));
+    }
+    cmd = cmd.mut_subcommand("agent", |mut agent| {
+        for sub in ["edit", "delete"] {
+            let names = agent_names.clone();
+            agent = agent.mut_subcommand(sub, |c| c.mut_arg("name", |arg| arg.value_parser(PossibleValuesParser::new(names))));
+        }
+        for side in ["left", "right"] {
+            let names = agent_names.clone();
+            agent = agent.mut_subcommand("diff", |c| c.mut_arg(side, |arg| arg.value_parser(PossibleValuesParser::new(names))));
+        }
+        // `shai agent <name> [prompt]` is an external subcommand, each agent becomes one for the script
+        for (name, description) in agents {
+            if agent.find_subcommand(name).is_none() {
+                agent = agent.subcommand(Command::new(name.clone()).about(description.clone()));
+            }
+        }
+        agent
+    });
+
+    let mut script = Vec::new();
+    generate(shell, &mut cmd, "shai", &mut script);
+    let script = String::from_utf8_lossy(&script).into_owned();
+
+    let header = header(shell);
+    match shell {
+        // #compdef must stay the first line for zsh to autoload the file from $fpath
+        Shell::Zsh => match script.split_once('\n') {
+            Some((first, rest)) if first.starts_with("#compdef") => format!("{}\n{}{}", first, header, rest),
+            _ => format!("{}{}", header, script),
+        },
+        _ => format!("{}{}", header, script),
+    }
+}
+
+fn header(shell: Shell) -> String {
+    let (file, line) = match shell {
+        Shell::Bash => ("~/.bashrc", "source <(shai completions bash)"),
+        Shell::Zsh => ("~/.zshrc, after compinit", "source <(shai completions zsh)"),
+        Shell::Fish => ("~/.config/fish/config.fish", "shai completions fish | source"),
+        Shell::PowerShell => ("your $PROFILE", "shai completions powershell | Out-String | Invoke-Expression"),
+        Shell::Elvish => ("~/.config/elvish/rc.elv", "eval (shai completions elvish | slurp)"),
+        _ => ("your shell startup file", "shai completions <shell>"),
+    };
+    format!("# shai completions for {shell}\n\
+        #\n\
+        # Load them in every new shell by adding this line to {file}:\n\
+        #   {line}\n\
+        #\n\
+        # Agent names are read when the script is generated: loaded this way, the agents\n\
+        # created with `shai agent new` complete in the next shells.\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_completion_script_lists_agents_and_tools() {
+        let agents = vec![("reviewer".to_string(), "reviews code".to_string())];
+
+        let bash = completion_script(crate::Cli::command(), Shell::Bash, &agents);
+        assert!(bash.starts_with("# shai completions for bash"), "{}", bash);
+        assert!(bash.contains("source <(shai completions bash)"));
+        assert!(bash.contains("reviewer"));
+        assert!(bash.contains("apply_patch"));
+
+        let zsh = completion_script(crate::Cli::command(), Shell::Zsh, &agents);
+        assert!(zsh.starts_with("#compdef shai\n# shai completions for zsh"), "{}", &zsh[..200.min(zsh.len())]);
+        assert!(zsh.contains("reviews code"));
+    }
+}
@@ -2,18 +2,26 @@ use std::sync::Arc;
 
 use crate::headless::tools::ToolConfig;
 
+use super::ask::answer_permissions;
+use super::exit::ExitStatus;
+use super::output::{render_output, OutputFormat};
 use super::tools::{ToolName, list_all_tools, parse_tools_list};
-use shai_core::agent::{Agent, AgentBuilder, AgentError, AgentResult, Brain, LoggingConfig, StdoutEventManager};
+use shai_core::agent::{Agent, AgentBuilder, AgentError, AgentResult, Brain, LoggingConfig, StdoutEventManager, JsonEventManager};
+use shai_core::agent::output::Verbosity;
 use shai_core::config::config::ShaiConfig;
 use shai_core::config::agent::AgentConfig;
+use shai_core::config::pricing::ModelPricing;
 use shai_core::runners::coder::coder::CoderBrain;
 use shai_core::runners::searcher::searcher::SearcherBrain;
-use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
-use shai_llm::LlmClient;
+use shai_core::tools::{AnyTool, TodoStorage};
+use openai_dive::v1::resources::chat::ChatMessage;
+use shai_llm::{LlmClient, ToolDescription};
 
 pub enum AgentKind {
     Coder,
     Searcher,
+    /// coder that writes a todo list first and works until it is done
+    Planner,
 }
 
 pub struct AppHeadless {
@@ -27,13 +35,25 @@ impl AppHeadless {
         }
     }
 
+    /// Searcher runs read-only: only the tools that cannot change anything are kept,
+    /// Planner runs the coder brain held to a todo list
+    pub fn with_kind(kind: AgentKind) -> Self {
+        Self { kind }
+    }
+
     pub async fn run(&self,
         initial_trace: Vec<ChatMessage>,
         tools: Option<String>, 
         remove: Option<String>,
         trace: bool,
-        agent_name: Option<String>
-    ) -> Result<(), Box<dyn std::error::Error>> {   
+        output: OutputFormat,
+        verbosity: Verbosity,
+        dry_run: bool,
+        ask: bool,
+        json: bool,
+        agent_name: Option<String>,
+        profile: Option<String>
+    ) -> Result<ExitStatus, Box<dyn std::error::Error>> {   
         // Configure internal debug logging to file
         /*
         let _ = LoggingConfig::default()
@@ -46,83 +66,136 @@ impl AppHeadless {
         if initial_trace.is_empty() {
             eprintln!("Error: Please provide a prompt for the coder agent");
             eprintln!("Usage: shai \"your prompt here\" or using pipe echo \"your prompt here\" | shai");
-            return Ok(());
+            return Ok(ExitStatus::ConfigurationError);
         }
 
-        let agent = if let Some(agent_name) = agent_name {
+        let builder = if let Some(agent_name) = agent_name {
+            if let Some(profile) = &profile {
+                eprintln!("\x1b[2m⚠ profile {} ignored, agent {} sets its own provider\x1b[0m", profile, agent_name);
+            }
             // Use custom agent from config
-            AgentBuilder::create(Some(agent_name)).await
-                .map_err(|e| format!("Failed to create agent: {}", e))?
-                .with_traces(initial_trace)
-                .sudo()
-                .build()
+            match AgentBuilder::create(Some(agent_name)).await {
+                Ok(builder) => builder,
+                Err(e) => {
+                    eprintln!("Failed to create agent: {}", e);
+                    return Ok(ExitStatus::from_agent_error(&e));
+                }
+            }
         } else {
             // Use default agent with provided tools
-            let (llm_client, model) = ShaiConfig::get_llm().await?;
+            let (llm_client, model) = match ShaiConfig::get_llm_with_profile(profile.as_deref()).await {
+                Ok(llm) => llm,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return Ok(ExitStatus::ConfigurationError);
+                }
+            };
             eprintln!("\x1b[2m░ {} on {}\x1b[0m", model, llm_client.provider().name());
 
             // Handle tool selection if needed
             if tools.is_some() || remove.is_some() {
                 // Need custom tool configuration
                 let tools = match (tools, remove) {
-                    (Some(tools_str), _) => {
-                        let selected_tools = parse_tools_list(&tools_str)?;
-                        ToolConfig::new().add_tools(selected_tools)
-                    }
-                    (None, Some(remove_str)) => {
-                        let tools_to_remove = parse_tools_list(&remove_str)?;
-                        ToolConfig::new().remove_tools(tools_to_remove)
+                    (Some(tools_str), _) => parse_tools_list(&tools_str)
+                        .map(|selected_tools| ToolConfig::new().add_tools(selected_tools)),
+                    (None, Some(remove_str)) => parse_tools_list(&remove_str)
+                        .map(|tools_to_remove| ToolConfig::new().remove_tools(tools_to_remove)),
+                    (None, None) => Ok(ToolConfig::new()),
+                };
+                let tools = match tools {
+                    Ok(tools) => tools,
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        return Ok(ExitStatus::ConfigurationError);
                     }
-                    (None, None) => ToolConfig::new(),
                 };
 
-                let toolbox = tools.build_toolbox();
+                let todo_storage = Arc::new(TodoStorage::new());
+                let toolbox = tools.build_toolbox(todo_storage.clone());
+                let pricing = ModelPricing::from_config(&model);
                 let brain: Box<dyn Brain> = match self.kind {
-                    AgentKind::Coder => Box::new(CoderBrain::new(Arc::new(llm_client), model)),
+                    AgentKind::Coder | AgentKind::Planner => Box::new(CoderBrain::new(Arc::new(llm_client), model)),
                     AgentKind::Searcher => Box::new(SearcherBrain::new(Arc::new(llm_client), model)),
                 };
 
                 AgentBuilder::with_brain(brain)
+                    .user_settings()
                     .tools(toolbox)
-                    .with_traces(initial_trace)
-                    .sudo()
-                    .build()
+                    .todos(todo_storage)
+                    .tool_method(ShaiConfig::load_tool_method(profile.as_deref()))
+                    .pricing(pricing)
             } else {
                 // Use default agent
-                AgentBuilder::default().await
-                    .map_err(|e| format!("Failed to create default agent: {}", e))?
-                    .with_traces(initial_trace)
-                    .sudo()
-                    .build()
+                match AgentBuilder::from_profile(profile.as_deref()).await {
+                    Ok(builder) => match self.kind {
+                        AgentKind::Coder | AgentKind::Planner => builder,
+                        AgentKind::Searcher => builder.brain(Box::new(SearcherBrain::new(Arc::new(llm_client), model))),
+                    },
+                    Err(e) => {
+                        eprintln!("Failed to create default agent: {}", e);
+                        return Ok(ExitStatus::from_agent_error(&e));
+                    }
+                }
+            }
+        };
+
+        let builder = match self.kind {
+            AgentKind::Coder => builder,
+            AgentKind::Planner => builder.planned(),
+            AgentKind::Searcher => {
+                let left_out: Vec<String> = builder.available_tools.iter()
+                    .filter(|tool| !tool.is_read_only())
+                    .map(|tool| tool.name())
+                    .collect();
+                if !left_out.is_empty() {
+                    eprintln!("\x1b[2m░ read-only: {} left out\x1b[0m", left_out.join(", "));
+                }
+                builder.read_only()
             }
         };
+        let builder = if dry_run { builder.dry_run() } else { builder };
+        let builder = builder.with_traces(initial_trace);
+        let mut agent = if ask { builder.persistent_permissions().build() } else { builder.sudo().build() };
+        if ask {
+            tokio::spawn(answer_permissions(agent.controller(), agent.watch()));
+        }
 
-        let result = agent
-            .with_event_handler(StdoutEventManager::new())
-            .run().await;
+        let agent = if json {
+            agent.with_event_handler(JsonEventManager::new())
+        } else {
+            agent.with_event_handler(StdoutEventManager::with_config(verbosity, ShaiConfig::load_output()))
+        };
+        let result = agent.run().await;
 
         match result {
-            Ok(AgentResult { success, message, trace: agent_trace }) => {
-                if trace {
+            Ok(AgentResult { success, message, trace: agent_trace, verification, token_usage, cost }) => {
+                if let Some(verification) = verification.filter(|v| !v.success) {
+                    eprintln!("\x1b[2m⚠ verification `{}` failed after {} attempt(s)\x1b[0m", verification.command, verification.attempts);
+                }
+                if let Some(cost) = cost.filter(|_| !json) {
+                    eprintln!("\x1b[2m░ tokens: {} in, {} out, {}\x1b[0m", token_usage.input_tokens, token_usage.output_tokens, cost);
+                    if let Some(note) = cost.note() {
+                        eprintln!("\x1b[2m⚠ {}\x1b[0m", note);
+                    }
+                }
+                if json {
+                    // the stream already ended with the completed event, stdout stays NDJSON
+                } else if trace {
                     println!("{}", serde_json::to_string_pretty(&agent_trace)?);
+                } else if let Some(text) = render_output(output, success, &agent_trace) {
+                    println!("{}", text);
+                }
+                if success {
+                    Ok(ExitStatus::Success)
                 } else {
-                    if let Some(message) = agent_trace.last() {
-                        match message {
-                            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(content)), .. } => {
-                                println!("{}",content);
-                            }
-                            ChatMessage::Tool { content, .. } => {
-                                println!("{}",content);
-                            }
-                            _ => {}
-                        }
-                    }
+                    eprintln!("Agent failed: {}", message);
+                    Ok(ExitStatus::AgentFailed)
                 }
             },
             Err(e) => {
                 eprintln!("Agent failed: {}", e);
+                Ok(ExitStatus::from_agent_error(&e))
             }
         }
-        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,75 @@
+use std::io::{self, IsTerminal, Write};
+
+use shai_core::agent::{AgentController, AgentEvent, PermissionRequest, PermissionResponse, PublicAgentState};
+use shai_core::agent::output::PrettyFormatter;
+use shai_core::config::config::ShaiConfig;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Answer the permission requests of a headless run from the terminal (`--ask`).
+/// Requests are denied when stdin is not a terminal, since nobody can answer them
+pub async fn answer_permissions(mut controller: AgentController, mut events: broadcast::Receiver<AgentEvent>) {
+    let interactive = io::stdin().is_terminal();
+    let formatter = PrettyFormatter::with_config(&ShaiConfig::load_output());
+
+    loop {
+        match events.recv().await {
+            Ok(AgentEvent::PermissionRequired { request_id, request }) => {
+                let response = if interactive {
+                    ask(&formatter, &request).await
+                } else {
+                    eprintln!("\x1b[2m⚠ {} denied: stdin is not a terminal, nobody can answer the permission request\x1b[0m",
+                        PrettyFormatter::tool_display_name(&request.call.tool_name));
+                    PermissionResponse::Deny
+                };
+                let _ = controller.response_permission_request(request_id, response).await;
+            }
+            Ok(AgentEvent::StatusChanged { new_status: PublicAgentState::Paused, .. }) => {
+                // the task is done: without a controller left the agent completes, as it does without --ask
+                let _ = controller.drop().await;
+                break;
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Show the call and its preview, then read the answer until it is one of y/n/always/dir
+async fn ask(formatter: &PrettyFormatter, request: &PermissionRequest) -> PermissionResponse {
+    let mut prompt = format!("\n🔐 {}", formatter.format_tool_started(&request.call));
+    let details = formatter.format_toolcall(&request.call, request.preview.as_ref());
+    for line in details.lines().filter(|line| !line.trim().is_empty()) {
+        prompt.push_str(&format!("\n  {}", line));
+    }
+    eprintln!("{}", prompt);
+
+    loop {
+        eprint!("Allow? [y]es / [n]o / [a]lways / always in this [d]irectory: ");
+        let _ = io::stderr().flush();
+
+        let line = tokio::task::spawn_blocking(|| {
+            let mut line = String::new();
+            io::stdin().read_line(&mut line).map(|read| (read > 0).then_some(line))
+        }).await;
+
+        match line {
+            Ok(Ok(Some(line))) => match parse_answer(&line) {
+                Some(response) => return response,
+                None => continue,
+            },
+            // end of input or unreadable terminal
+            _ => return PermissionResponse::Deny,
+        }
+    }
+}
+
+/// Answer typed at the prompt, an empty line denies
+pub fn parse_answer(line: &str) -> Option<PermissionResponse> {
+    match line.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(PermissionResponse::Allow),
+        "a" | "always" => Some(PermissionResponse::AllowAlways),
+        "d" | "dir" | "directory" => Some(PermissionResponse::AllowDirectory),
+        "" | "n" | "no" => Some(PermissionResponse::Deny),
+        _ => None,
+    }
+}
@@ -0,0 +1,41 @@
+use std::process::ExitCode;
+
+use shai_core::agent::AgentError;
+
+/// Outcome of a headless run, turned into the process exit code so scripts and CI can gate on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// the agent completed its task (also informational commands like --list-tools)
+    Success,
+    /// the agent ran but returned success: false, or stopped on an unexpected error
+    AgentFailed,
+    /// missing prompt, missing provider, unknown agent or model...
+    ConfigurationError,
+    /// the LLM provider could not be reached or rejected the calls
+    ProviderUnreachable,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> u8 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::AgentFailed => 1,
+            ExitStatus::ConfigurationError => 2,
+            ExitStatus::ProviderUnreachable => 3,
+        }
+    }
+
+    pub fn from_agent_error(error: &AgentError) -> Self {
+        match error {
+            AgentError::ConfigurationError(_) => ExitStatus::ConfigurationError,
+            AgentError::LlmError(_) => ExitStatus::ProviderUnreachable,
+            _ => ExitStatus::AgentFailed,
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status.code())
+    }
+}
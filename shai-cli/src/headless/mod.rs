@@ -1,2 +1,9 @@
 pub mod tools;
-pub mod app;
\ No newline at end of file
+pub mod app;
+pub mod exit;
+pub mod output;
+pub mod ask;
+pub mod replay;
+
+#[cfg(test)]
+mod tests;
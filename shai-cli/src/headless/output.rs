@@ -0,0 +1,60 @@
+use clap::ValueEnum;
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+
+/// What headless mode prints on stdout once the agent is done
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Text of the last assistant message, skipping any trailing tool results
+    #[default]
+    LastAssistant,
+    /// Text of every assistant message in the run, in order
+    FullText,
+    /// A JSON object with the success flag and the final answer
+    Json,
+}
+
+/// Render the final output of a headless run, None if there is nothing to print
+pub fn render_output(format: OutputFormat, success: bool, trace: &[ChatMessage]) -> Option<String> {
+    match format {
+        OutputFormat::LastAssistant => last_assistant_text(trace),
+        OutputFormat::FullText => {
+            let texts: Vec<String> = trace.iter().filter_map(assistant_text).collect();
+            if texts.is_empty() { None } else { Some(texts.join("\n\n")) }
+        }
+        OutputFormat::Json => {
+            let json = serde_json::json!({
+                "success": success,
+                "answer": last_assistant_text(trace),
+            });
+            serde_json::to_string_pretty(&json).ok()
+        }
+    }
+}
+
+/// Walk back from the end of the trace to the last assistant message carrying text
+pub fn last_assistant_text(trace: &[ChatMessage]) -> Option<String> {
+    trace.iter().rev().find_map(assistant_text)
+}
+
+fn assistant_text(message: &ChatMessage) -> Option<String> {
+    match message {
+        ChatMessage::Assistant { content: Some(content), .. } => content_text(content),
+        _ => None,
+    }
+}
+
+fn content_text(content: &ChatMessageContent) -> Option<String> {
+    let text = match content {
+        ChatMessageContent::Text(text) => text.clone(),
+        ChatMessageContent::ContentPart(parts) => parts.iter()
+            .filter_map(|p| match p {
+                openai_dive::v1::resources::chat::ChatMessageContentPart::Text(t) => Some(t.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ChatMessageContent::None => String::new(),
+    };
+    let text = text.trim();
+    if text.is_empty() { None } else { Some(text.to_string()) }
+}
@@ -0,0 +1,50 @@
+use std::path::Path;
+use std::time::Duration;
+
+use openai_dive::v1::resources::chat::ChatMessage;
+use serde::Deserialize;
+use shai_core::agent::output::{parse_event_log, replay_events, trace_events, JsonEventManager, StdoutEventManager, Verbosity};
+use shai_core::agent::AgentEvent;
+use shai_core::config::config::ShaiConfig;
+
+use super::exit::ExitStatus;
+
+/// A session saved by `shai serve`, only its trace is replayed
+#[derive(Deserialize)]
+struct SavedSession {
+    trace: Vec<ChatMessage>,
+}
+
+/// `shai replay <file>`: render a captured run again, without calling the LLM
+pub async fn replay_file(path: &Path, verbosity: Verbosity, json: bool, pace: Option<Duration>) -> ExitStatus {
+    let events = match std::fs::read_to_string(path) {
+        Ok(content) => load_events(&content),
+        Err(e) => Err(e.to_string()),
+    };
+    let events = match events {
+        Ok(events) => events,
+        Err(e) => {
+            eprintln!("Error: cannot replay {}: {}", path.display(), e);
+            return ExitStatus::ConfigurationError;
+        }
+    };
+
+    if json {
+        replay_events(events, &JsonEventManager::new(), pace).await;
+    } else {
+        replay_events(events, &StdoutEventManager::with_config(verbosity, ShaiConfig::load_output()), pace).await;
+    }
+    ExitStatus::Success
+}
+
+/// Events of a `--trace` dump, a saved session or a `--json` event log
+pub fn load_events(content: &str) -> Result<Vec<AgentEvent>, String> {
+    if let Ok(trace) = serde_json::from_str::<Vec<ChatMessage>>(content) {
+        return Ok(trace_events(&trace));
+    }
+    if let Ok(session) = serde_json::from_str::<SavedSession>(content) {
+        return Ok(trace_events(&session.trace));
+    }
+    parse_event_log(content)
+        .map_err(|e| format!("neither a trace, a saved session nor an event log ({})", e))
+}
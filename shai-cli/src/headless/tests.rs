@@ -0,0 +1,110 @@
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ToolCall, Function};
+
+use shai_core::agent::{AgentError, AgentEvent, PermissionResponse};
+
+use super::ask::parse_answer;
+use super::exit::ExitStatus;
+use super::output::{render_output, OutputFormat};
+use super::replay::load_events;
+
+fn user(text: &str) -> ChatMessage {
+    ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }
+}
+
+fn assistant(text: &str) -> ChatMessage {
+    ChatMessage::Assistant {
+        content: Some(ChatMessageContent::Text(text.to_string())),
+        reasoning_content: None,
+        tool_calls: None,
+        name: None,
+        audio: None,
+        refusal: None,
+    }
+}
+
+fn assistant_calling(tool: &str) -> ChatMessage {
+    ChatMessage::Assistant {
+        content: None,
+        reasoning_content: None,
+        tool_calls: Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            r#type: "function".to_string(),
+            function: Function { name: tool.to_string(), arguments: "{}".to_string() },
+        }]),
+        name: None,
+        audio: None,
+        refusal: None,
+    }
+}
+
+fn tool_result(text: &str) -> ChatMessage {
+    ChatMessage::Tool { content: ChatMessageContent::Text(text.to_string()), tool_call_id: "call_1".to_string() }
+}
+
+#[test]
+fn test_last_assistant_on_trace_ending_with_assistant() {
+    let trace = vec![user("hi"), assistant_calling("ls"), tool_result("a.txt"), assistant("there is one file")];
+    assert_eq!(render_output(OutputFormat::LastAssistant, true, &trace), Some("there is one file".to_string()));
+}
+
+#[test]
+fn test_last_assistant_skips_trailing_tool_messages() {
+    let trace = vec![user("hi"), assistant("let me look"), assistant_calling("ls"), tool_result("a.txt")];
+    assert_eq!(render_output(OutputFormat::LastAssistant, true, &trace), Some("let me look".to_string()));
+}
+
+#[test]
+fn test_last_assistant_with_no_answer() {
+    let trace = vec![user("hi"), assistant_calling("ls"), tool_result("a.txt")];
+    assert_eq!(render_output(OutputFormat::LastAssistant, true, &trace), None);
+}
+
+#[test]
+fn test_full_text_joins_all_assistant_messages() {
+    let trace = vec![user("hi"), assistant("let me look"), assistant_calling("ls"), tool_result("a.txt"), assistant("one file")];
+    assert_eq!(render_output(OutputFormat::FullText, true, &trace), Some("let me look\n\none file".to_string()));
+}
+
+#[test]
+fn test_json_output() {
+    let trace = vec![user("hi"), assistant("done"), assistant_calling("ls"), tool_result("a.txt")];
+    let output = render_output(OutputFormat::Json, false, &trace).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["success"], false);
+    assert_eq!(json["answer"], "done");
+}
+
+#[test]
+fn test_exit_status_from_agent_error() {
+    assert_eq!(ExitStatus::from_agent_error(&AgentError::ConfigurationError("no such agent".to_string())), ExitStatus::ConfigurationError);
+    assert_eq!(ExitStatus::from_agent_error(&AgentError::LlmError("connection refused".to_string())), ExitStatus::ProviderUnreachable);
+    assert_eq!(ExitStatus::from_agent_error(&AgentError::TimeoutError), ExitStatus::AgentFailed);
+    assert_eq!(ExitStatus::Success.code(), 0);
+}
+
+#[test]
+fn test_parse_permission_answer() {
+    assert_eq!(parse_answer("y\n"), Some(PermissionResponse::Allow));
+    assert_eq!(parse_answer(" Always "), Some(PermissionResponse::AllowAlways));
+    assert_eq!(parse_answer("d"), Some(PermissionResponse::AllowDirectory));
+    assert_eq!(parse_answer("\n"), Some(PermissionResponse::Deny));
+    assert_eq!(parse_answer("no"), Some(PermissionResponse::Deny));
+    assert_eq!(parse_answer("maybe"), None);
+}
+
+#[test]
+fn test_replay_loads_traces_sessions_and_event_logs() {
+    let trace = vec![user("hello"), assistant("hi there")];
+    let dump = serde_json::to_string_pretty(&trace).unwrap();
+    let events = load_events(&dump).unwrap();
+    assert!(matches!(&events[0], AgentEvent::UserInput { input } if input == "hello"));
+    assert!(matches!(&events[1], AgentEvent::BrainResult { .. }));
+
+    let session = serde_json::json!({"session_id": "s1", "trace": trace}).to_string();
+    assert_eq!(load_events(&session).unwrap().len(), 2);
+
+    let log = events.iter().map(|event| serde_json::to_string(event).unwrap()).collect::<Vec<_>>().join("\n");
+    assert_eq!(load_events(&log).unwrap().len(), 2);
+
+    assert!(load_events("not json").is_err());
+}
@@ -1,65 +1,90 @@
 use std::sync::Arc;
-use shai_core::tools::{AnyTool, BashTool, EditTool, FetchTool, FindTool, LsTool, 
-                     MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, WriteTool,
+use shai_core::tools::{AnyTool, ApplyPatchTool, BashJobs, BashStatusTool, BashTool, EditTool, EnvTool, FetchTool, FindTool, GitTool, GrepTool, LsTool, 
+                     MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, UndoTool, WriteTool,
                      TodoStorage, FsOperationLog};
+use shai_core::config::config::ShaiConfig;
 
 /// Available tools for the coder agent
 #[derive(Debug, Clone, PartialEq)]
 pub enum ToolName {
+    ApplyPatch,
     Bash,
+    BashStatus,
     Edit,
+    Env,
     Fetch,
     Find,
+    Git,
+    Grep,
     Ls,
     MultiEdit,
     Read,
     TodoRead,
     TodoWrite,
+    Undo,
     Write,
 }
 
 impl ToolName {
+    /// The default tools, git is added on request
     pub fn all() -> Vec<ToolName> {
         vec![
+            ToolName::ApplyPatch,
             ToolName::Bash,
+            ToolName::BashStatus,
             ToolName::Edit,
+            ToolName::Env,
             ToolName::Fetch,
             ToolName::Find,
+            ToolName::Grep,
             ToolName::Ls,
             ToolName::MultiEdit,
             ToolName::Read,
             ToolName::TodoRead,
             ToolName::TodoWrite,
+            ToolName::Undo,
             ToolName::Write,
         ]
     }
 
     pub fn name(&self) -> &'static str {
         match self {
+            ToolName::ApplyPatch => "apply_patch",
             ToolName::Bash => "bash",
+            ToolName::BashStatus => "bash_status",
             ToolName::Edit => "edit",
+            ToolName::Env => "env",
             ToolName::Fetch => "fetch",
             ToolName::Find => "find",
+            ToolName::Git => "git",
+            ToolName::Grep => "grep",
             ToolName::Ls => "ls",
             ToolName::MultiEdit => "multiedit",
             ToolName::Read => "read",
             ToolName::TodoRead => "todoread",
             ToolName::TodoWrite => "todowrite",
+            ToolName::Undo => "undo",
             ToolName::Write => "write",
         }
     }
 
     pub fn from_str(s: &str) -> Option<ToolName> {
         match s.to_lowercase().as_str() {
+            "apply_patch" => Some(ToolName::ApplyPatch),
             "bash" => Some(ToolName::Bash),
+            "bash_status" => Some(ToolName::BashStatus),
             "edit" => Some(ToolName::Edit),
+            "env" => Some(ToolName::Env),
             "fetch" => Some(ToolName::Fetch),
             "find" => Some(ToolName::Find),
+            "git" => Some(ToolName::Git),
+            "grep" => Some(ToolName::Grep),
             "ls" => Some(ToolName::Ls),
             "multiedit" => Some(ToolName::MultiEdit),
             "read" => Some(ToolName::Read),
             "todoread" => Some(ToolName::TodoRead),
             "todowrite" => Some(ToolName::TodoWrite),
+            "undo" => Some(ToolName::Undo),
             "write" => Some(ToolName::Write),
             _ => None,
         }
@@ -112,21 +137,28 @@ impl ToolConfig {
         self.tools.iter().map(|t| t.name().to_string()).collect()
     }
 
-    pub fn build_toolbox(&self) -> Vec<Box<dyn AnyTool>> {
-        let todo_storage = Arc::new(TodoStorage::new());
-        let fs_log = Arc::new(FsOperationLog::new());
+    /// Tools of the config, the todo tools use `todo_storage`
+    pub fn build_toolbox(&self, todo_storage: Arc<TodoStorage>) -> Vec<Box<dyn AnyTool>> {
+        let fs_log = Arc::new(FsOperationLog::persistent());
+        let bash_jobs = Arc::new(BashJobs::new());
         let mut toolbox: Vec<Box<dyn AnyTool>> = Vec::new();
         for tool_name in &self.tools {
             match tool_name {
-                ToolName::Bash => toolbox.push(Box::new(BashTool::new())),
+                ToolName::ApplyPatch => toolbox.push(Box::new(ApplyPatchTool::new(fs_log.clone()))),
+                ToolName::Bash => toolbox.push(Box::new(BashTool::with_config(ShaiConfig::load_bash()).with_jobs(bash_jobs.clone()))),
+                ToolName::BashStatus => toolbox.push(Box::new(BashStatusTool::new(bash_jobs.clone()))),
                 ToolName::Edit => toolbox.push(Box::new(EditTool::new(fs_log.clone()))),
-                ToolName::Fetch => toolbox.push(Box::new(FetchTool::new())),
+                ToolName::Env => toolbox.push(Box::new(EnvTool::with_shell(ShaiConfig::load_bash().shell_program()))),
+                ToolName::Fetch => toolbox.push(Box::new(FetchTool::with_max_bytes(ShaiConfig::load_fetch_max_bytes()))),
                 ToolName::Find => toolbox.push(Box::new(FindTool::new())),
+                ToolName::Git => toolbox.push(Box::new(GitTool::new())),
+                ToolName::Grep => toolbox.push(Box::new(GrepTool::new())),
                 ToolName::Ls => toolbox.push(Box::new(LsTool::new())),
                 ToolName::MultiEdit => toolbox.push(Box::new(MultiEditTool::new(fs_log.clone()))),
-                ToolName::Read => toolbox.push(Box::new(ReadTool::new(fs_log.clone()))),
+                ToolName::Read => toolbox.push(Box::new(ReadTool::with_max_bytes(fs_log.clone(), ShaiConfig::load_read_max_bytes()))),
                 ToolName::TodoRead => toolbox.push(Box::new(TodoReadTool::new(todo_storage.clone()))),
                 ToolName::TodoWrite => toolbox.push(Box::new(TodoWriteTool::new(todo_storage.clone()))),
+                ToolName::Undo => toolbox.push(Box::new(UndoTool::new(fs_log.clone()))),
                 ToolName::Write => toolbox.push(Box::new(WriteTool::new(fs_log.clone()))),
             }
         }
@@ -140,6 +172,7 @@ pub fn list_all_tools() {
     for tool in ToolName::all() {
         eprintln!("  {}", tool.name());
     }
+    eprintln!("  {} (only with --tools)", ToolName::Git.name());
 }
 
 pub fn parse_tools_list(tools_str: &str) -> Result<Vec<ToolName>, String> {
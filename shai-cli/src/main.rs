@@ -1,8 +1,8 @@
-use headless::app::AppHeadless;
-use clap::{Parser, Subcommand};
+use headless::app::{AgentKind, AppHeadless};
+use clap::{CommandFactory, Parser, Subcommand};
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers, EventStream},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, EventStream},
     terminal::{disable_raw_mode, enable_raw_mode},
     ExecutableCommand,
 };
@@ -10,9 +10,15 @@ use crossterm::{
 use ringbuffer::RingBuffer;
 use console::strip_ansi_codes;
 use shai_core::agent::LoggingConfig;
+use shai_core::agent::output::Verbosity;
 use shai_core::config::config::ShaiConfig;
-use shai_core::config::agent::AgentConfig;
+use shai_core::config::agent::{AgentConfig, AgentProviderConfig, AgentTools, ALL_BUILTIN_TOOLS, OPT_IN_BUILTIN_TOOLS};
+use shai_core::config::diff::{AgentConfigDiff, LineChange};
+use shai_core::config::bundle::ConfigBundle;
+use shai_core::config::check::{ConfigReport, Severity};
 use shai_core::agent::builder::AgentBuilder;
+use shai_core::agent::ClaimManager;
+use shai_core::tools::{create_mcp_client, FsOperationLog, McpConfig};
 use shai_core::runners::clifixer::fix::clifix;
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
 use shai_llm::LlmClient;
@@ -22,13 +28,15 @@ use tui::App;
 use std::env;
 use std::sync::Arc;
 use std::io::{self, IsTerminal, Read, Write};
-use std::process::Command;
+use std::process::{Command, ExitCode};
 use std::time::Duration;
 use tokio::time::{sleep, interval};
 use futures::StreamExt;
 use tracing_subscriber;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
 
 mod headless;
+mod completions;
 #[cfg(unix)]
 mod fc;
 #[cfg(unix)]
@@ -43,8 +51,12 @@ use shell::pty::ShaiPtyManager;
 #[cfg(unix)]
 use shell::rc::{ShellType, get_shell};
 #[cfg(unix)]
+use shell::edit::LineEditor;
+#[cfg(unix)]
 use fc::client::ShaiSessionClient;
 
+use crate::headless::exit::ExitStatus;
+use crate::headless::output::OutputFormat;
 use crate::headless::tools::list_all_tools;
 
 #[derive(Parser)]
@@ -57,6 +69,9 @@ struct Cli {
     /// Dump entire trace upon completion (headless mode only)
     #[arg(long, global = true)]
     trace: bool,
+    /// What to print once the agent is done (headless mode only)
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::LastAssistant)]
+    output: OutputFormat,
     /// the url to pull the default shai config
     #[arg(long)]
     default_shai_config_url: Option<String>,
@@ -72,6 +87,30 @@ struct Cli {
     /// Show version information
     #[arg(short, long)]
     version: bool,
+    /// Show full tool parameters and longer previews (headless mode only)
+    #[arg(short = 'V', long, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Only show tool names and answers (headless mode only)
+    #[arg(short, long)]
+    quiet: bool,
+    /// Plan only: tools that write or use the network show what they would do instead of running (headless mode only)
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Ask in the terminal before running tools that need permission, instead of allowing everything (headless mode only)
+    #[arg(long, global = true)]
+    ask: bool,
+    /// Stream every agent event as a JSON line on stdout instead of the pretty output (headless mode only)
+    #[arg(long, global = true, conflicts_with = "trace")]
+    json: bool,
+    /// Answer with the searcher agent and read-only tools: nothing that writes runs, MCP tools not annotated read-only are left out (headless mode only)
+    #[arg(long, global = true)]
+    read_only: bool,
+    /// Make the agent write a todo list before acting and keep it working until every item is done (headless mode only)
+    #[arg(long, global = true, conflicts_with = "read_only")]
+    plan: bool,
+    /// Provider profile of the config to use, e.g. fast or local (default: SHAI_PROFILE, else the selected provider)
+    #[arg(long, global = true)]
+    profile: Option<String>,
     /// Auto-fix mode: if no subcommand provided, these args go to fix
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     args: Vec<String>,
@@ -81,11 +120,85 @@ struct Cli {
 enum AgentAction {
     /// List all available agents
     List,
+    /// Show the differences between two agent configs
+    Diff {
+        /// First agent name
+        left: String,
+        /// Second agent name
+        right: String,
+    },
+    /// Create an agent config on the current provider, asking for its description and tools
+    New {
+        /// Name of the agent
+        name: String,
+    },
+    /// Open an agent config in $EDITOR
+    Edit {
+        /// Name of the agent
+        name: String,
+    },
+    /// Delete an agent config
+    Delete {
+        /// Name of the agent
+        name: String,
+    },
     #[command(external_subcommand)]
     /// Run a specific agent by name
     Agent(Vec<String>),
 }
 
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the auth config, every agent config and the MCP servers as one JSON bundle
+    Export {
+        /// Empty the API keys and tokens, to share the bundle as a template
+        #[arg(long)]
+        no_secrets: bool,
+    },
+    /// Restore a bundle written by `shai config export`
+    Import {
+        /// Bundle file, `-` to read it from stdin
+        file: String,
+        /// Overwrite the local configs that differ from the bundle
+        #[arg(long)]
+        force: bool,
+        /// Fill the keys missing from the bundle with the ones already configured on this machine
+        #[arg(long)]
+        keep_local_secrets: bool,
+    },
+    /// Validate the auth config, the project .shai.config and every agent config, exits with an error code if one is broken
+    Check,
+}
+
+#[derive(Subcommand)]
+enum PermissionsAction {
+    /// List the tool calls saved with "always allow"
+    List,
+    /// Remove saved permissions
+    Revoke {
+        /// Number of the permission, as printed by `shai permissions list`
+        #[arg(required_unless_present = "all")]
+        number: Option<usize>,
+        /// Remove every saved permission
+        #[arg(long, conflicts_with = "number")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum McpAction {
+    /// Connect to an MCP server and print its tools with their description and parameters
+    List {
+        /// Name of the server in auth.config or in an agent config
+        name: String,
+    },
+    /// Connect to an MCP server, signing in with OAuth if needed, and report whether it answers
+    Test {
+        /// Name of the server in auth.config or in an agent config
+        name: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum Commands {
     #[cfg(unix)]
@@ -111,6 +224,46 @@ enum Commands {
         #[command(subcommand)]
         action: AgentAction,
     },
+    /// Export or import the whole configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Inspect the MCP servers of the configs
+    Mcp {
+        #[command(subcommand)]
+        action: McpAction,
+    },
+    /// List or revoke the tool permissions saved with "always allow"
+    Permissions {
+        #[command(subcommand)]
+        action: PermissionsAction,
+    },
+    /// Revert the last file changes of the agents run in this directory
+    Undo {
+        /// Number of changes to revert, the most recent first
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+        /// Show the diff that would be reverted without changing any file
+        #[arg(long)]
+        preview: bool,
+        /// Also revert the files changed since, losing those later changes
+        #[arg(long)]
+        force: bool,
+    },
+    /// Render a saved run again without calling the LLM: a --trace dump, a saved session or a --json event log
+    Replay {
+        /// File to replay
+        file: std::path::PathBuf,
+        /// Milliseconds to wait between two events, to watch the run unfold
+        #[arg(long)]
+        pace: Option<u64>,
+    },
+    /// Print the completion script of a shell (bash, zsh, fish, powershell, elvish)
+    #[command(hide = true)]
+    Completions {
+        shell: clap_complete::Shell,
+    },
     #[cfg(unix)]
     /// Send pre-command hook (before command execution)
     #[command(hide = true)]
@@ -145,14 +298,60 @@ enum Commands {
         /// Maximum number of concurrent sessions (None = unlimited)
         #[arg(long)]
         max_sessions: Option<usize>,
+        /// Allow browser requests from this origin (repeatable, CORS is disabled by default)
+        #[arg(long = "cors-origin", value_name = "ORIGIN")]
+        cors_origins: Vec<String>,
+        /// Allow any origin, method and header (local development only)
+        #[arg(long, conflicts_with = "cors_origins")]
+        cors_permissive: bool,
+        /// Number of events each session keeps for clients polling /v1/sessions/{id}/events
+        #[arg(long, value_name = "N", default_value_t = shai_http::session::DEFAULT_EVENT_BUFFER_SIZE)]
+        event_buffer_size: usize,
+        /// Save background sessions to disk after every N completed tool calls, not only when a request ends
+        #[arg(long, value_name = "N")]
+        checkpoint_every: Option<u32>,
+        /// Let a background session run its request to the end when the client disconnects, instead of stopping it
+        #[arg(long)]
+        keep_running_on_disconnect: bool,
+        /// Require "Authorization: Bearer <KEY>" on the /v1 routes (default: SHAI_API_KEY)
+        #[arg(long, value_name = "KEY")]
+        api_key: Option<String>,
+        /// Seconds running tool calls get to finish when the server receives SIGINT or SIGTERM
+        #[arg(long, value_name = "SECS", default_value_t = shai_http::http::DEFAULT_SHUTDOWN_GRACE.as_secs())]
+        shutdown_grace: u64,
+        /// Don't print the logo and the startup summary
+        #[arg(long)]
+        no_banner: bool,
+        /// Serve Prometheus counters (sessions, requests, tool calls, tokens, LLM latency) on GET /metrics
+        #[arg(long)]
+        metrics: bool,
+        /// The `instructions` of a /v1/responses request replace the agent's system prompt instead of being added after it
+        #[arg(long)]
+        replace_system_prompt: bool,
+        /// Log level of the server (info, warn...) or a full filter like "shai_http=info,tower_http=debug" (default: RUST_LOG, else shai_http=debug)
+        #[arg(long, value_name = "FILTER")]
+        log_level: Option<String>,
     }
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
     let cli = Cli::parse();
     default_config(cli.default_shai_config_url).await;
 
+    // only headless runs report an agent outcome, every other command exits 0 unless it errors
+    let mut status = ExitStatus::Success;
+    let verbosity = match (cli.verbose, cli.quiet) {
+        (true, _) => Verbosity::Verbose,
+        (_, true) => Verbosity::Quiet,
+        _ => Verbosity::Normal,
+    };
+    let kind = match (cli.read_only, cli.plan) {
+        (true, _) => AgentKind::Searcher,
+        (_, true) => AgentKind::Planner,
+        _ => AgentKind::Coder,
+    };
+
     match cli.command {
         #[cfg(unix)]
         Some(Commands::On { shell, quiet }) => {
@@ -170,7 +369,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             handle_config().await?;
         },
         Some(Commands::Agent { action }) => {
-            handle_agent_command(action).await?;
+            status = handle_agent_command(action, cli.output, verbosity, cli.dry_run, cli.ask, cli.json, kind, cli.profile).await?;
+        },
+        Some(Commands::Config { action }) => {
+            status = handle_config_command(action)?;
+        },
+        Some(Commands::Mcp { action }) => {
+            status = handle_mcp_command(action).await?;
+        },
+        Some(Commands::Permissions { action }) => {
+            status = handle_permissions_command(action)?;
+        },
+        Some(Commands::Undo { count, preview, force }) => {
+            status = handle_undo_command(count, preview, force).await?;
+        },
+        Some(Commands::Replay { file, pace }) => {
+            status = headless::replay::replay_file(&file, verbosity, cli.json, pace.map(Duration::from_millis)).await;
+        },
+        Some(Commands::Completions { shell }) => {
+            let agents: Vec<(String, String)> = AgentConfig::list_agents().unwrap_or_default().into_iter()
+                .map(|name| {
+                    let description = AgentConfig::load(&name).map(|config| config.description).unwrap_or_default();
+                    (name, description)
+                })
+                .collect();
+            print!("{}", completions::completion_script(Cli::command(), shell, &agents));
         },
         #[cfg(unix)]
         Some(Commands::Precmd { command }) => {
@@ -182,8 +405,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let command_str = command.join(" ");
             handle_postcmd(exit_code, command_str).await?;
         },
-        Some(Commands::Serve { host, port, agent, ephemeral, max_sessions }) => {
-            handle_serve(host, port, agent, ephemeral, max_sessions).await?;
+        Some(Commands::Serve { host, port, agent, ephemeral, max_sessions, cors_origins, cors_permissive, event_buffer_size, checkpoint_every, keep_running_on_disconnect, api_key, shutdown_grace, no_banner, metrics, replace_system_prompt, log_level }) => {
+            let cors = if cors_permissive {
+                shai_http::CorsConfig::permissive()
+            } else {
+                shai_http::CorsConfig::default().with_origins(cors_origins)
+            };
+            let config = shai_http::ServerConfig::new(format!("{}:{}", host, port))
+                .with_ephemeral(ephemeral)
+                .with_max_sessions(max_sessions)
+                .with_cors(cors)
+                .with_event_buffer_size(event_buffer_size)
+                .with_checkpoint_every(checkpoint_every)
+                .with_stop_on_disconnect(!keep_running_on_disconnect)
+                .with_api_key(api_key.or_else(|| env::var("SHAI_API_KEY").ok()))
+                .with_shutdown_grace(Duration::from_secs(shutdown_grace))
+                .with_banner(!no_banner)
+                .with_metrics(metrics)
+                .with_replace_system_prompt(replace_system_prompt);
+            handle_serve(config, agent, log_level).await?;
         },
         None => {
             // Check for stdin input or trailing arguments
@@ -210,30 +450,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             // Handle --list-tools flag
             if cli.list_tools {
                 list_all_tools();
-                return Ok(());
+                return Ok(ExitCode::SUCCESS);
             }
 
             // Handle --version flag
             if cli.version {
                 show_version()?;
-                return Ok(());
+                return Ok(ExitCode::SUCCESS);
             }
 
             if !messages.is_empty() || cli.list_tools {
                 // Route to fix command with combined messages and global options
-                handle_fix(messages, cli.tools, cli.remove, cli.trace, None).await?;
+                status = handle_fix(messages, cli.tools, cli.remove, cli.trace, cli.output, verbosity, cli.dry_run, cli.ask, cli.json, kind, None, cli.profile).await?;
             } else {
                 // No input, show TUI
-                handle_main(None).await?;
+                handle_main(None, cli.profile).await?;
             }
         }
     }
 
-    Ok(())
+    Ok(status.into())
 }
 
 async fn default_config(default_config_url: Option<String>) {
-    if ShaiConfig::load().is_ok() {
+    if ShaiConfig::load_global().is_ok() {
         return;
     }
 
@@ -242,20 +482,23 @@ async fn default_config(default_config_url: Option<String>) {
         None => "https://raw.githubusercontent.com/ovh/shai/refs/heads/main/.shai.config".to_string()
     };
 
-    let config = if let Ok(parsed_url) = default_url.parse() {
+    let mut config = if let Ok(parsed_url) = default_url.parse() {
         ShaiConfig::pull_from_url(parsed_url).await.unwrap_or_else(|_| ShaiConfig::default())
     } else {
         ShaiConfig::default()
     };
 
+    // first run, the TUI greets the user until the panel is dismissed
+    config.onboarding.pending = true;
+
     let _ = config.save();
 }
 
-async fn handle_main(agent_name: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+async fn handle_main(agent_name: Option<String>, profile: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
     let logo = logo();
     println!("{}", apply_gradient(&logo, SHAI_YELLOW, SHAI_YELLOW));
     let mut app = App::new();
-    match app.run(agent_name).await {
+    match app.run(agent_name, profile).await {
         Err(e) => eprintln!("error: {}",e),
         _ => {}
     }
@@ -277,8 +520,15 @@ async fn handle_fix(
     tools: Option<String>, 
     remove: Option<String>,
     trace: bool,
-    agent_name: Option<String>
-) -> Result<(), Box<dyn std::error::Error>> {
+    output: OutputFormat,
+    verbosity: Verbosity,
+    dry_run: bool,
+    ask: bool,
+    json: bool,
+    kind: AgentKind,
+    agent_name: Option<String>,
+    profile: Option<String>
+) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     let initial_trace: Vec<ChatMessage> = prompt.into_iter()
         .map(|p| ChatMessage::User { 
             content: ChatMessageContent::Text(p), 
@@ -286,7 +536,8 @@ async fn handle_fix(
         })
         .collect();
     
-    AppHeadless::new().run(initial_trace, tools, remove, trace, agent_name).await
+    let app = AppHeadless::with_kind(kind);
+    app.run(initial_trace, tools, remove, trace, output, verbosity, dry_run, ask, json, agent_name, profile).await
 }
 
 fn show_version() -> Result<(), Box<dyn std::error::Error>> {
@@ -411,34 +662,60 @@ pub async fn handle_postcmd(exit_code: i32, command: String) -> Result<(), Box<d
                 
                 match result {
                     Ok(res) => {
-                        if let Some(rational) = &res.short_rational {
-                            eprintln!("\n\x1b[2m{}\x1b[0m\n", rational);
+                        let fixes = res.fixes;
+                        if let [fix] = fixes.as_slice() {
+                            if let Some(rational) = &fix.short_rational {
+                                eprintln!("\n\x1b[2m{}\x1b[0m\n", rational);
+                            }
+                        } else {
+                            // the candidates and their rationale stay listed, the prompt line shows the chosen one
+                            eprintln!();
+                            for (i, fix) in fixes.iter().enumerate() {
+                                match &fix.short_rational {
+                                    Some(rational) => eprintln!("\x1b[2m {}.\x1b[0m {} \x1b[2m— {}\x1b[0m", i + 1, fix.fixed_cli, rational),
+                                    None => eprintln!("\x1b[2m {}.\x1b[0m {}", i + 1, fix.fixed_cli),
+                                }
+                            }
+                            eprintln!();
+                        }
+                        eprint!("\x1b[38;5;206m❯\x1b[0m \x1b[1m{}\x1b[0m\n", &fixes[0].fixed_cli);
+                        if fixes.len() > 1 {
+                            eprintln!("\n\x1b[2m ↑↓ Choose • ↵ Run • e / Tab Edit • Esc / Ctrl+C Cancel\x1b[0m");
+                        } else {
+                            eprintln!("\n\x1b[2m ↵ Run • e / Tab Edit • Esc / Ctrl+C Cancel\x1b[0m");
                         }
-                        eprint!("\x1b[38;5;206m❯\x1b[0m \x1b[1m{}\x1b[0m\n", &res.fixed_cli);
-                        eprintln!("\n\x1b[2m ↵ Run • Esc / Ctrl+C Cancel\x1b[0m");
                         
                         io::stdout().execute(cursor::MoveUp(3)).unwrap();
-                        io::stdout().execute(cursor::MoveToColumn((res.fixed_cli.len() + 3) as u16)).unwrap();
+                        io::stdout().execute(cursor::MoveToColumn((fixes[0].fixed_cli.chars().count() + 2) as u16)).unwrap();
                         io::stdout().flush().unwrap();
                         enable_raw_mode().unwrap();
                         
+                        let mut selected = 0;
+                        // Some once the user pressed e / Tab, the chosen fix is then edited in place
+                        let mut editor: Option<LineEditor> = None;
                         loop {
-                            if let Ok(Event::Key(KeyEvent { code, modifiers, .. })) = event::read() {
+                            if let Ok(Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press, .. })) = event::read() {
                                 match (code, modifiers) {
                                     (KeyCode::Enter, _) => {
+                                        let fixed_cli = editor.as_ref().map_or_else(|| fixes[selected].fixed_cli.clone(), |editor| editor.line());
                                         disable_raw_mode().unwrap();
                                         io::stdout().execute(cursor::MoveDown(3)).unwrap();
                                         io::stdout().execute(cursor::MoveToColumn(0)).unwrap();
                                         println!();
+                                        if fixed_cli.trim().is_empty() {
+                                            break;
+                                        }
                                         
-                                        let mut cmd = Command::new("sh");
-                                        cmd.arg("-c").arg(&res.fixed_cli);
+                                        // the configured shell, /bin/sh may be dash when the fix assumes bash
+                                        let mut cmd = Command::new(ShaiConfig::load_bash().shell_program());
+                                        cmd.arg("-c").arg(&fixed_cli);
                                         cmd.envs(env::vars());
                                         
                                         match cmd.status() {
                                             Ok(status) => {
+                                                // the edited command is what ran, not the suggestion
                                                 if status.success() {
-                                                    shell::rc::write_to_shell_history(&res.fixed_cli);
+                                                    shell::rc::write_to_shell_history(&fixed_cli);
                                                 }
                                             }
                                             Err(e) => eprintln!("Failed to execute command: {}\n", e),
@@ -447,6 +724,8 @@ pub async fn handle_postcmd(exit_code: i32, command: String) -> Result<(), Box<d
                                     }
                                     (KeyCode::Esc, _) => {
                                         disable_raw_mode().unwrap();
+                                        io::stdout().execute(cursor::MoveDown(3)).unwrap();
+                                        io::stdout().execute(cursor::MoveToColumn(0)).unwrap();
                                         println!();
                                         break;
                                     }
@@ -456,7 +735,24 @@ pub async fn handle_postcmd(exit_code: i32, command: String) -> Result<(), Box<d
                                         eprintln!("Exiting...");
                                         std::process::exit(0);
                                     }
-                                    _ => {}
+                                    (KeyCode::Char('e'), KeyModifiers::NONE) | (KeyCode::Tab, _) if editor.is_none() => {
+                                        redraw_clifix_line(editor.insert(LineEditor::new(&fixes[selected].fixed_cli)));
+                                    }
+                                    (KeyCode::Up, _) | (KeyCode::Down, _) if editor.is_none() && fixes.len() > 1 => {
+                                        selected = if code == KeyCode::Up {
+                                            (selected + fixes.len() - 1) % fixes.len()
+                                        } else {
+                                            (selected + 1) % fixes.len()
+                                        };
+                                        redraw_clifix_line(&LineEditor::new(&fixes[selected].fixed_cli));
+                                    }
+                                    _ => {
+                                        if let Some(editor) = editor.as_mut() {
+                                            if editor.handle_key(code, modifiers) {
+                                                redraw_clifix_line(editor);
+                                            }
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -470,33 +766,394 @@ pub async fn handle_postcmd(exit_code: i32, command: String) -> Result<(), Box<d
     Ok(())
 }
 
-async fn handle_serve(host: String, port: u16, agent: Option<String>, ephemeral: bool, max_sessions: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+/// Redraw the clifix suggestion line with the edit buffer, the cursor at its position
+#[cfg(unix)]
+fn redraw_clifix_line(editor: &LineEditor) {
+    eprint!("\r\x1b[2K\x1b[38;5;206m❯\x1b[0m \x1b[1m{}\x1b[0m", editor.line());
+    io::stderr().flush().unwrap();
+    io::stdout().execute(cursor::MoveToColumn((editor.cursor() + 2) as u16)).unwrap();
+    io::stdout().flush().unwrap();
+}
+
+async fn handle_serve(config: shai_http::ServerConfig, agent: Option<String>, log_level: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
     // Initialize tracing for HTTP server logs
     tracing_subscriber::fmt()
         .with_target(false)
         .with_level(true)
-        .with_env_filter("shai_http=debug")
+        .with_env_filter(serve_log_filter(log_level.as_deref())?)
         .init();
 
-    println!("{}", logo_cyan());
-
-    let addr = format!("{}:{}", host, port);
-    let config = shai_http::ServerConfig::new(addr)
-        .with_ephemeral(ephemeral)
-        .with_max_sessions(max_sessions);
+    if config.banner {
+        println!("{}", logo_cyan());
+    }
 
     shai_http::start_server(config).await?;
 
     Ok(())
 }
 
-async fn handle_agent_command(action: AgentAction) -> Result<(), Box<dyn std::error::Error>> {
+/// --log-level wins over RUST_LOG, the verbose shai_http=debug is only used when neither is set.
+/// A bare level only applies to the server logs, anything else is used as a full filter.
+fn serve_log_filter(log_level: Option<&str>) -> Result<EnvFilter, Box<dyn std::error::Error>> {
+    let filter = match log_level {
+        Some(level) if level.parse::<LevelFilter>().is_ok() => EnvFilter::try_new(format!("shai_http={}", level))?,
+        Some(filter) => EnvFilter::try_new(filter)
+            .map_err(|e| format!("invalid --log-level {:?}: {}", filter, e))?,
+        None => EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new("shai_http=debug")),
+    };
+    Ok(filter)
+}
+
+fn print_agent_diff(left: &str, right: &str, diff: &AgentConfigDiff) {
+    println!("\x1b[31m--- {}\x1b[0m", left);
+    println!("\x1b[32m+++ {}\x1b[0m", right);
+
+    if diff.is_empty() {
+        println!("\x1b[2mno differences\x1b[0m");
+        return;
+    }
+
+    for change in &diff.scalars {
+        println!("  \x1b[1m{}\x1b[0m: \x1b[31m{}\x1b[0m -> \x1b[32m{}\x1b[0m", change.field, change.left, change.right);
+    }
+
+    for change in &diff.lists {
+        println!("  \x1b[1m{}\x1b[0m:", change.field);
+        for removed in &change.removed {
+            println!("    \x1b[31m- {}\x1b[0m", removed);
+        }
+        for added in &change.added {
+            println!("    \x1b[32m+ {}\x1b[0m", added);
+        }
+    }
+
+    if !diff.system_prompt.is_empty() {
+        println!("  \x1b[1msystem_prompt\x1b[0m:");
+        for line in &diff.system_prompt {
+            match line {
+                LineChange::Removed(text) => println!("    \x1b[31m- {}\x1b[0m", text),
+                LineChange::Added(text) => println!("    \x1b[32m+ {}\x1b[0m", text),
+            }
+        }
+    }
+}
+
+/// Subcommands of `shai agent`, an agent with one of these names could not be run
+const AGENT_SUBCOMMANDS: [&str; 5] = ["list", "diff", "new", "edit", "delete"];
+
+fn new_agent(name: &str) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    if let Err(e) = AgentConfig::validate_name(name) {
+        eprintln!("Error: {}", e);
+        return Ok(ExitStatus::ConfigurationError);
+    }
+    if AGENT_SUBCOMMANDS.contains(&name) {
+        eprintln!("Error: {} is a `shai agent` subcommand, pick another name", name);
+        return Ok(ExitStatus::ConfigurationError);
+    }
+    if AgentConfig::exists(name) {
+        eprintln!("Error: agent {} already exists, change it with `shai agent edit {}`", name, name);
+        return Ok(ExitStatus::ConfigurationError);
+    }
+    let llm_provider = match AgentProviderConfig::selected() {
+        Ok(provider) => provider,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return Ok(ExitStatus::ConfigurationError);
+        }
+    };
+
+    let description = prompt_line("Description: ")?;
+    let builtin = loop {
+        let tools = prompt_line(&format!("Builtin tools, comma-separated ({}, {}) [*]: ",
+            ALL_BUILTIN_TOOLS.join(", "), OPT_IN_BUILTIN_TOOLS.join(", ")))?;
+        match AgentTools::parse_builtin(&tools) {
+            Ok(builtin) => break builtin,
+            Err(e) => eprintln!("{}", e),
+        }
+    };
+
+    let config = AgentConfig::new(name, &description, llm_provider, builtin);
+    config.save()?;
+    println!("Created agent {} on {} ({})", name, config.llm_provider.model, AgentConfig::agent_config_path(name)?.display());
+    println!("\x1b[2mRun it with `shai agent {}`, change its prompt or MCP servers with `shai agent edit {}`\x1b[0m", name, name);
+    Ok(ExitStatus::Success)
+}
+
+fn edit_agent(name: &str) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    if !AgentConfig::exists(name) {
+        eprintln!("Error: no agent named {}, create it with `shai agent new {}`", name, name);
+        return Ok(ExitStatus::ConfigurationError);
+    }
+    let path = AgentConfig::agent_config_path(name)?;
+
+    // $EDITOR may carry arguments, like "code --wait"
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program).args(words).arg(&path).status()
+        .map_err(|e| format!("could not start {}: {}", program, e))?;
+    if !status.success() {
+        eprintln!("Error: {} exited with {}", program, status);
+        return Ok(ExitStatus::ConfigurationError);
+    }
+
+    match AgentConfig::load(name) {
+        Ok(_) => {
+            println!("Saved agent {}", name);
+            Ok(ExitStatus::Success)
+        }
+        Err(e) => {
+            eprintln!("Error: agent {} no longer loads, run `shai agent edit {}` again to fix it: {}", name, name, e);
+            Ok(ExitStatus::ConfigurationError)
+        }
+    }
+}
+
+/// Print a question and read the answer, trimmed
+fn prompt_line(question: &str) -> io::Result<String> {
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+fn handle_permissions_command(action: PermissionsAction) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let path = ClaimManager::default_config_file()?;
+    let mut claims = ClaimManager::with_config_file(path.clone());
+    claims.load_from_file()?;
+
+    match action {
+        PermissionsAction::List => {
+            if claims.is_empty() {
+                println!("No saved permissions ({})", path.display());
+            }
+            for (index, permission) in claims.get_all_permissions().iter().enumerate() {
+                println!("{:>3}. {:<10} {}  \x1b[2m{}\x1b[0m",
+                    index + 1,
+                    permission.tool_name,
+                    permission.parameters,
+                    permission.granted_at.format("%Y-%m-%d %H:%M"));
+            }
+        }
+        PermissionsAction::Revoke { number, all } => {
+            if all {
+                let count = claims.len();
+                claims.clear();
+                claims.save_to_file()?;
+                println!("Revoked {} permission(s)", count);
+            } else {
+                let number = number.unwrap_or_default();
+                let Some(permission) = number.checked_sub(1).and_then(|index| claims.remove_permission(index)) else {
+                    eprintln!("No saved permission number {}, see `shai permissions list`", number);
+                    return Ok(ExitStatus::ConfigurationError);
+                };
+                claims.save_to_file()?;
+                println!("Revoked {} {}", permission.tool_name, permission.parameters);
+            }
+        }
+    }
+    Ok(ExitStatus::Success)
+}
+
+/// Where an MCP server is configured, to save the OAuth token it gets there
+enum McpServerSource {
+    ShaiConfig,
+    Agent(AgentConfig),
+}
+
+/// The MCP server named `name` in auth.config (and the project config), then in the agent configs
+fn find_mcp_server(name: &str) -> Option<(McpConfig, McpServerSource)> {
+    if let Some(config) = ShaiConfig::load().ok().and_then(|config| config.mcp_configs.get(name).cloned()) {
+        return Some((config, McpServerSource::ShaiConfig));
+    }
+    AgentConfig::list_agents().unwrap_or_default().into_iter()
+        .filter_map(|agent| AgentConfig::load(&agent).ok())
+        .find_map(|agent| {
+            let config = agent.tools.mcp.get(name)?.config.clone();
+            Some((config, McpServerSource::Agent(agent)))
+        })
+}
+
+/// Names of the MCP servers of every config, for the error of an unknown name
+fn list_mcp_servers() -> Vec<String> {
+    let mut names: Vec<String> = ShaiConfig::load().map(|config| config.mcp_configs.into_keys().collect()).unwrap_or_default();
+    for agent in AgentConfig::list_agents().unwrap_or_default() {
+        if let Ok(agent) = AgentConfig::load(&agent) {
+            names.extend(agent.tools.mcp.into_keys().map(|name| format!("{} ({})", name, agent.name)));
+        }
+    }
+    names.sort();
+    names
+}
+
+async fn handle_mcp_command(action: McpAction) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let (McpAction::List { name } | McpAction::Test { name }) = &action;
+    let Some((mut config, source)) = find_mcp_server(name) else {
+        let known = list_mcp_servers();
+        eprintln!("No MCP server named '{}'", name);
+        if !known.is_empty() {
+            eprintln!("Configured servers: {}", known.join(", "));
+        }
+        return Ok(ExitStatus::ConfigurationError);
+    };
+
+    match AgentBuilder::mcp_check_oauth(name, &mut config).await {
+        Ok(false) => {}
+        // keep the new token where the server is configured
+        Ok(true) => match source {
+            McpServerSource::ShaiConfig => {
+                let mut global = ShaiConfig::load_global()?;
+                if let Some(stored) = global.mcp_configs.get_mut(name) {
+                    *stored = config.clone();
+                    global.save()?;
+                }
+            }
+            McpServerSource::Agent(mut agent) => {
+                if let Some(stored) = agent.tools.mcp.get_mut(name) {
+                    stored.config = config.clone();
+                    agent.save()?;
+                }
+            }
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(ExitStatus::ConfigurationError);
+        }
+    }
+
+    let started = std::time::Instant::now();
+    let mut client = create_mcp_client(config);
+    if let Err(e) = client.connect().await {
+        eprintln!("MCP '{}' failed to connect: {}", name, e);
+        return Ok(ExitStatus::ConfigurationError);
+    }
+    let tools = client.list_tools().await;
+    let _ = client.disconnect().await;
+    let mut tools = match tools {
+        Ok(tools) => tools,
+        Err(e) => {
+            eprintln!("MCP '{}' connected but failed to list its tools: {}", name, e);
+            return Ok(ExitStatus::ConfigurationError);
+        }
+    };
+
+    match action {
+        McpAction::Test { name } => {
+            println!("MCP '{}' is reachable: connected in {}ms, {} tool(s)", name, started.elapsed().as_millis(), tools.len());
+        }
+        McpAction::List { .. } => {
+            tools.sort_by(|a, b| a.name.cmp(&b.name));
+            for tool in &tools {
+                println!("\x1b[1m{}\x1b[0m{}", tool.name, if tool.read_only { " \x1b[2m(read-only)\x1b[0m" } else { "" });
+                for line in tool.description.lines() {
+                    println!("  {}", line);
+                }
+                let schema = serde_json::to_string_pretty(&tool.parameters_schema)?;
+                for line in schema.lines() {
+                    println!("  \x1b[2m{}\x1b[0m", line);
+                }
+                println!();
+            }
+        }
+    }
+    Ok(ExitStatus::Success)
+}
+
+async fn handle_undo_command(count: usize, preview: bool, force: bool) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    let journal = FsOperationLog::default_journal().ok_or("no cache directory to find the undo journal in")?;
+    let log = FsOperationLog::load_journal(&journal)?;
+    let steps = if preview { log.undo_plan(count).await } else { log.undo(count, force).await? };
+    if steps.is_empty() {
+        eprintln!("No file change to undo ({})", journal.display());
+        return Ok(ExitStatus::Success);
+    }
+
+    for step in &steps {
+        if preview {
+            println!("{}", step.diff());
+        } else {
+            println!("{}", step.summary());
+        }
+    }
+    if preview {
+        eprintln!("\x1b[2m{} change(s) would be reverted, run without --preview to revert them\x1b[0m", steps.len());
+    }
+    Ok(ExitStatus::Success)
+}
+
+fn handle_config_command(action: ConfigAction) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+    match action {
+        ConfigAction::Export { no_secrets } => {
+            let bundle = ConfigBundle::collect()?;
+            let bundle = if no_secrets { bundle.without_secrets() } else { bundle };
+            println!("{}", serde_json::to_string_pretty(&bundle)?);
+        }
+        ConfigAction::Import { file, force, keep_local_secrets } => {
+            let content = if file == "-" {
+                let mut buffer = String::new();
+                io::stdin().read_to_string(&mut buffer)?;
+                buffer
+            } else {
+                std::fs::read_to_string(&file)?
+            };
+            let mut bundle = ConfigBundle::from_json(&content)?;
+
+            if let Err(errors) = bundle.validate() {
+                eprintln!("Invalid bundle:");
+                for error in errors {
+                    eprintln!("  - {}", error);
+                }
+                return Ok(ExitStatus::ConfigurationError);
+            }
+
+            let local = ConfigBundle::collect()?;
+            if keep_local_secrets {
+                bundle.fill_secrets_from(&local.config);
+            }
+
+            let conflicts = bundle.conflicts_with(&local);
+            if !conflicts.is_empty() {
+                let header = if force { "Overwriting:" } else { "The bundle differs from the local config:" };
+                eprintln!("{}", header);
+                for conflict in &conflicts {
+                    eprintln!("  - {}", conflict);
+                }
+                if !force {
+                    eprintln!("Nothing was written, run again with --force to overwrite.");
+                    return Ok(ExitStatus::ConfigurationError);
+                }
+            }
+
+            bundle.save()?;
+            println!("Imported the auth config and {} agent(s)", bundle.agents.len());
+            for missing in bundle.missing_secrets() {
+                eprintln!("\x1b[33m⚠ no value for {}\x1b[0m", missing);
+            }
+        }
+        ConfigAction::Check => {
+            let report = ConfigReport::run()?;
+            for issue in &report.issues {
+                eprintln!("{}", issue);
+            }
+            println!("Checked {} file(s): {} error(s), {} warning(s)",
+                report.files.len(), report.count(Severity::Error), report.count(Severity::Warning));
+            if report.has_errors() {
+                return Ok(ExitStatus::ConfigurationError);
+            }
+        }
+    }
+    Ok(ExitStatus::Success)
+}
+
+async fn handle_agent_command(action: AgentAction, output: OutputFormat, verbosity: Verbosity, dry_run: bool, ask: bool, json: bool, kind: AgentKind, profile: Option<String>) -> Result<ExitStatus, Box<dyn std::error::Error>> {
     match action {
         AgentAction::List => {
             let agents = AgentConfig::list_agents()?;
             if agents.is_empty() {
                 println!("No custom agents found.");
-                println!("Create agent configs in ~/.config/shai/agents/");
+                println!("Create one with `shai agent new <name>`, configs live in ~/.config/shai/agents/");
             } else {
                 println!("Available agents:");
                 
@@ -522,11 +1179,27 @@ async fn handle_agent_command(action: AgentAction) -> Result<(), Box<dyn std::er
                 }
             }
         }
+        AgentAction::Diff { left, right } => {
+            let left_config = AgentConfig::load(&left)?;
+            let right_config = AgentConfig::load(&right)?;
+            print_agent_diff(&left, &right, &AgentConfigDiff::between(&left_config, &right_config));
+        }
+        AgentAction::New { name } => return new_agent(&name),
+        AgentAction::Edit { name } => return edit_agent(&name),
+        AgentAction::Delete { name } => {
+            if !AgentConfig::exists(&name) {
+                eprintln!("Error: no agent named {}, see `shai agent list`", name);
+                return Ok(ExitStatus::ConfigurationError);
+            }
+            let path = AgentConfig::agent_config_path(&name)?;
+            AgentConfig::delete(&name)?;
+            println!("Deleted agent {} ({})", name, path.display());
+        }
         AgentAction::Agent(args) => {
             if args.is_empty() {
                 eprintln!("Error: Please specify an agent name");
                 eprintln!("Usage: shai agent <agent_name> [prompt]");
-                return Ok(());
+                return Ok(ExitStatus::ConfigurationError);
             }
             
             let agent_name = &args[0];
@@ -534,14 +1207,14 @@ async fn handle_agent_command(action: AgentAction) -> Result<(), Box<dyn std::er
             
             if prompt_args.is_empty() {
                 // No prompt provided, start TUI mode with the agent
-                handle_main(Some(agent_name.clone())).await?;
+                handle_main(Some(agent_name.clone()), profile).await?;
             } else {
                 // Prompt provided, run in headless mode
                 let prompt = prompt_args.join(" ");
-                handle_fix(vec![prompt], None, None, false, Some(agent_name.clone())).await?;
+                return handle_fix(vec![prompt], None, None, false, output, verbosity, dry_run, ask, json, kind, Some(agent_name.clone()), profile).await;
             }
         }
     }
-    Ok(())
+    Ok(ExitStatus::Success)
 }
 
@@ -0,0 +1,124 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Readline-style buffer the clifix suggestion is edited in before it runs
+#[derive(Debug, Clone)]
+pub struct LineEditor {
+    chars: Vec<char>,
+    /// Cursor position, in chars
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// Buffer holding `line` with the cursor at its end
+    pub fn new(line: &str) -> Self {
+        let chars: Vec<char> = line.chars().collect();
+        let cursor = chars.len();
+        Self { chars, cursor }
+    }
+
+    pub fn line(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Apply a key, false when the key is not an editing key
+    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        match (code, modifiers) {
+            (KeyCode::Char('a'), KeyModifiers::CONTROL) | (KeyCode::Home, _) => self.cursor = 0,
+            (KeyCode::Char('e'), KeyModifiers::CONTROL) | (KeyCode::End, _) => self.cursor = self.chars.len(),
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                self.chars.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            (KeyCode::Char('k'), KeyModifiers::CONTROL) => self.chars.truncate(self.cursor),
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
+                // the word before the cursor and the spaces that follow it
+                let mut start = self.cursor;
+                while start > 0 && self.chars[start - 1] == ' ' {
+                    start -= 1;
+                }
+                while start > 0 && self.chars[start - 1] != ' ' {
+                    start -= 1;
+                }
+                self.chars.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            (KeyCode::Char(_), modifiers) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => return false,
+            (KeyCode::Char(c), _) => {
+                self.chars.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            (KeyCode::Left, _) => self.cursor = self.cursor.saturating_sub(1),
+            (KeyCode::Right, _) => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            (KeyCode::Backspace, _) => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.chars.remove(self.cursor);
+                }
+            }
+            (KeyCode::Delete, _) => {
+                if self.cursor < self.chars.len() {
+                    self.chars.remove(self.cursor);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_keys(editor: &mut LineEditor, keys: &[(KeyCode, KeyModifiers)]) {
+        for (code, modifiers) in keys {
+            editor.handle_key(*code, *modifiers);
+        }
+    }
+
+    #[test]
+    fn test_insert_and_delete_around_the_cursor() {
+        let mut editor = LineEditor::new("git psuh");
+        type_keys(&mut editor, &[
+            (KeyCode::Backspace, KeyModifiers::NONE),
+            (KeyCode::Backspace, KeyModifiers::NONE),
+            (KeyCode::Backspace, KeyModifiers::NONE),
+            (KeyCode::Char('u'), KeyModifiers::NONE),
+            (KeyCode::Char('s'), KeyModifiers::NONE),
+            (KeyCode::Char('h'), KeyModifiers::NONE),
+            (KeyCode::Home, KeyModifiers::NONE),
+            (KeyCode::Delete, KeyModifiers::NONE),
+            (KeyCode::Char('G'), KeyModifiers::SHIFT),
+        ]);
+        assert_eq!(editor.line(), "Git push");
+        assert_eq!(editor.cursor(), 1);
+    }
+
+    #[test]
+    fn test_kill_word_and_line() {
+        let mut editor = LineEditor::new("ls -la  /tmp");
+        assert!(editor.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(editor.line(), "ls -la  ");
+        editor.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(editor.line(), "ls ");
+        editor.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        editor.handle_key(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(editor.line(), "ls");
+        editor.handle_key(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(editor.line(), "");
+        assert_eq!(editor.cursor(), 0);
+    }
+
+    #[test]
+    fn test_multibyte_chars_move_as_one() {
+        let mut editor = LineEditor::new("echo é");
+        editor.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        editor.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(editor.line(), "echo xé");
+        assert!(!editor.handle_key(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    }
+}
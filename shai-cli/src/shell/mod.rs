@@ -1,3 +1,4 @@
 pub mod pty;
 pub mod rc;
-pub mod terminal;
\ No newline at end of file
+pub mod terminal;
+pub mod edit;
\ No newline at end of file
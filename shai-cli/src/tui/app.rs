@@ -1,5 +1,4 @@
 use std::io::{self, stdin, stdout};
-use std::sync::Arc;
 use std::time::Instant;
 
 use chrono::Utc;
@@ -12,16 +11,16 @@ use ratatui::prelude::CrosstermBackend;
 use ratatui::style::Stylize;
 use ratatui::text::{Line, Span, Text};
 use ratatui::Terminal;
-use shai_core::agent::{Agent, AgentRequest, AgentEvent, AgentController, PublicAgentState};
-use shai_core::agent::events::{PermissionRequest, PermissionResponse};
+use shai_core::agent::{Agent, AgentRequest, AgentEvent, AgentController, PublicAgentState, TokenTotals};
+use shai_core::agent::events::PermissionRequest;
 use shai_core::agent::output::PrettyFormatter;
 use shai_core::config::config::ShaiConfig;
 use shai_core::config::agent::AgentConfig;
 use shai_core::agent::builder::AgentBuilder;
 use shai_core::logging::LoggingConfig;
-use shai_core::runners::coder::coder::coder;
+use shai_core::runners::gerund::gerund::gerund;
 use shai_core::tools::{ToolCall, ToolResult};
-use shai_llm::{LlmClient, ToolCallMethod};
+use shai_llm::{LlmClient, LlmError, ToolCallMethod};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Style},
@@ -34,12 +33,14 @@ use tokio::time::{interval, Duration};
 use tui_textarea::Input;
 use ansi_to_tui::IntoText;
 use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
 
 use crate::tui::input::InputArea;
 use super::input::UserAction;
 use crate::tui::perm::PermissionWidget;
 use crate::tui::perm_alt_screen::AlternateScreenPermissionModal;
 use super::perm::PermissionModalAction;
+use super::onboarding::{OnboardingAction, OnboardingWidget};
 use super::theme::Theme;
 
 
@@ -47,6 +48,9 @@ pub enum AppModalState<'a> {
     InputShown,
     PermissionModal {
         widget: PermissionWidget<'a>   
+    },
+    Onboarding {
+        widget: OnboardingWidget<'a>
     }
 }
 
@@ -71,8 +75,10 @@ pub struct App<'a> {
     pub(crate) exit: bool,
     pub(crate) permission_queue: VecDeque<(String, PermissionRequest)>, // (request_id, request)
 
-    pub(crate) total_input_tokens: u32,
-    pub(crate) total_output_tokens: u32,
+    pub(crate) model_label: Option<String>, // "<model> on <provider>" of the running agent
+    pub(crate) gerund_llm: Option<(Arc<LlmClient>, String)>, // (llm, model) naming the spinner after the user input
+    pub(crate) pending_gerund: Option<JoinHandle<Result<String, LlmError>>>,
+    pub(crate) session_tokens: TokenTotals, // tokens reported by the agent's events, kept once it stopped
     
     pub(crate) theme: Theme, // UI theme (dark/light)
 }
@@ -80,22 +86,38 @@ pub struct App<'a> {
 
 // Agent-related Internals
 impl App<'_> {
-    pub async fn start_agent(&mut self, agent_name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn start_agent(&mut self, agent_name: Option<&str>, profile: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Ok(ShaiConfig { origin, .. }) = ShaiConfig::load() {
+            if let Some(path) = origin.project_file.filter(|_| !origin.project_keys.is_empty()) {
+                println!("\x1b[2m░ project config {} ({})\x1b[0m", path.display(), origin.project_keys.join(", "));
+            }
+        }
+
         let mut agent: Box<dyn Agent> = if let Some(agent_name) = agent_name {
             // Load custom agent config
             let config = AgentConfig::load(agent_name)?;
+            if let Some(profile) = profile {
+                println!("\x1b[2m⚠ profile {} ignored, agent {} sets its own provider\x1b[0m", profile, agent_name);
+            }
             
             println!("\x1b[2m░ agent {} - {} on {}\x1b[0m", agent_name, config.llm_provider.model, config.llm_provider.provider);
+            self.model_label = Some(format!("{} on {}", config.llm_provider.model, config.llm_provider.provider));
             
             // Create agent from config
             let agent_builder = AgentBuilder::from_config(config).await?;
-            Box::new(agent_builder.build())
+            Box::new(agent_builder.persistent_permissions().build())
         } else {
             // Use default coder agent
-            let (llm, model) = ShaiConfig::get_llm().await?;
+            let (llm, model) = ShaiConfig::get_llm_with_profile(profile).await?;
             println!("\x1b[2m░ {} on {}\x1b[0m", model, llm.provider().name());
-            
-            Box::new(coder(Arc::new(llm), model))
+            self.model_label = Some(format!("{} on {}", model, llm.provider().name()));
+
+            let llm = Arc::new(llm);
+            self.gerund_llm = Some((llm.clone(), model.clone()));
+            Box::new(AgentBuilder::from_shared_llm(llm, model).await
+                .tool_method(ShaiConfig::load_tool_method(profile))
+                .persistent_permissions()
+                .build())
         };
         
         // Get Agent I/O
@@ -110,6 +132,10 @@ impl App<'_> {
             }
         });
 
+        if let Ok(method) = controller.set_method(None).await {
+            self.input.set_tool_call_method(method);
+        }
+
         self.agent = Some(AppRunningAgent{
             handle,
             controller,
@@ -132,6 +158,11 @@ impl App<'_> {
             self.input.set_agent_running(!matches!(new_status, PublicAgentState::Paused));
         }
 
+        if let AgentEvent::TokenUsage { input_tokens, output_tokens } = &event {
+            self.session_tokens.input_tokens += *input_tokens as u64;
+            self.session_tokens.output_tokens += *output_tokens as u64;
+        }
+
         // updated inprogress list
         if let AgentEvent::ToolCallStarted { call, .. }= &event {
             self.running_tools.insert(call.tool_call_id.clone(), call.clone());
@@ -157,10 +188,12 @@ impl App<'_> {
             self.permission_queue.push_back((request_id.clone(), request.clone()));
         }
 
-        // Handle token usage tracking
-        if let AgentEvent::TokenUsage { input_tokens, output_tokens } = &event {
-            self.total_input_tokens += input_tokens;
-            self.total_output_tokens += output_tokens;
+        // The agent gave up waiting: drop the request, closing its modal if it is shown
+        if let AgentEvent::PermissionTimedOut { request_id, .. } = &event {
+            self.permission_queue.retain(|(id, _)| id != request_id);
+            if matches!(&self.state, AppModalState::PermissionModal { widget } if widget.request_id == *request_id) {
+                self.state = AppModalState::InputShown;
+            }
         }
         
         Ok(())
@@ -179,21 +212,23 @@ impl App<'_> {
             terminal_height: 5,
             agent: None,
             custom_agent: None,
-            formatter: PrettyFormatter::new(),
+            formatter: PrettyFormatter::with_config(&ShaiConfig::load_output()),
             state: AppModalState::InputShown,
             input: InputArea::new(palette),
             commands: Self::list_command(),
             exit: false,
             running_tools: HashMap::new(),
             permission_queue: VecDeque::new(),
-            total_input_tokens: 0,
-            total_output_tokens: 0,
+            model_label: None,
+            gerund_llm: None,
+            pending_gerund: None,
+            session_tokens: TokenTotals::default(),
             theme,
         }
     }
 
-    pub async fn run(&mut self, agent_name: Option<String>) -> io::Result<()> {
-        let x = self.try_run(agent_name).await;
+    pub async fn run(&mut self, agent_name: Option<String>, profile: Option<String>) -> io::Result<()> {
+        let x = self.try_run(agent_name, profile).await;
         let _ = disable_raw_mode();
 
         if let Err(e) = x {
@@ -207,12 +242,14 @@ impl App<'_> {
         Ok(())
     }
 
-    async fn try_run(&mut self, agent_name: Option<String>) ->Result<(), Box<dyn std::error::Error>> {
+    async fn try_run(&mut self, agent_name: Option<String>, profile: Option<String>) ->Result<(), Box<dyn std::error::Error>> {
         // Start the agent (custom or default)
         let agent_name_ref = agent_name.as_deref();
-        self.start_agent(agent_name_ref).await.map_err(|e| -> Box<dyn std::error::Error> { 
+        self.start_agent(agent_name_ref, profile.as_deref()).await.map_err(|e| -> Box<dyn std::error::Error> { 
             if agent_name_ref.is_some() {
                 format!("could not start custom agent '{}': {}", agent_name_ref.unwrap(), e).into()
+            } else if let Some(profile) = profile.as_deref() {
+                format!("could not start shai agent with profile '{}': {}", profile, e).into()
             } else {
                 format!("could not start shai agent, run shai auth first").into()
             }
@@ -223,6 +260,12 @@ impl App<'_> {
             viewport: Viewport::Inline(8)
         }));
 
+        // Greet first-time users until they dismiss the panel
+        if let Some(onboarding) = ShaiConfig::load().ok().map(|c| c.onboarding).filter(|o| o.pending) {
+            let widget = OnboardingWidget::new(&onboarding, &self.commands, self.model_label.as_deref(), self.theme.palette());
+            self.state = AppModalState::Onboarding { widget };
+        }
+
         // Create a timer for animation updates
         let mut animation_timer = interval(Duration::from_millis(100));
         let mut reader = crossterm::event::EventStream::new();
@@ -253,6 +296,7 @@ impl App<'_> {
                     if let Some(action) = self.input.check_pending_enter() {
                         self.handle_user_action(action).await?;
                     }
+                    self.check_pending_gerund();
                     // Timer ticked, UI will be redrawn in next iteration
                 }
             }
@@ -301,6 +345,27 @@ impl App<'_> {
                 let action = widget.handle_key_event(key_event).await;
                 self.handle_permission_action(action).await?;
             }
+            AppModalState::Onboarding { widget } => {
+                let action = widget.handle_key_event(key_event);
+                self.handle_onboarding_action(action).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_onboarding_action(&mut self, action: OnboardingAction) -> io::Result<()> {
+        let OnboardingAction::Dismiss { forward } = action;
+        self.state = AppModalState::InputShown;
+
+        // never show it again
+        if let Ok(mut config) = ShaiConfig::load_global() {
+            config.onboarding.pending = false;
+            let _ = config.save();
+        }
+
+        if let Some(key_event) = forward {
+            let action = self.input.handle_event(key_event).await;
+            self.handle_user_action(action).await?;
         }
         Ok(())
     }
@@ -311,9 +376,6 @@ impl App<'_> {
             PermissionModalAction::Response { request_id, choice } => {
                 // Send response to agent
                 if let Some(ref agent) = self.agent {     
-                    if matches!(choice, PermissionResponse::AllowAlways) {
-                        let _ = agent.controller.sudo().await;
-                    }        
                     match agent.controller.response_permission_request(request_id, choice).await {
                         Err(e) => {
                             self.input.alert_msg("channel with agent closed. Please restart the app", Duration::from_secs(3));
@@ -342,6 +404,7 @@ impl App<'_> {
                     request_id.clone(), 
                     request.clone(), 
                     self.permission_queue.len(),
+                    &self.formatter,
                     palette
                 );
                 
@@ -352,7 +415,7 @@ impl App<'_> {
                 
                 if widget.height() > terminal_height.saturating_sub(5) {
                     // Use alternate screen for large modals
-                    if let Ok(mut modal) = AlternateScreenPermissionModal::new(&widget, palette) {
+                    if let Ok(mut modal) = AlternateScreenPermissionModal::new(&widget) {
                         let action = modal.run().await.unwrap_or(PermissionModalAction::Nope);
                         self.handle_permission_action(action).await?;
                     }
@@ -379,6 +442,7 @@ impl App<'_> {
                 }
             }
             UserAction::UserInput { input } => {
+                self.ask_gerund(&input);
                 if let Some(ref agent) = self.agent {                                
                     match agent.controller.send_user_input(input.clone()).await {
                         Err(e) => {
@@ -396,10 +460,31 @@ impl App<'_> {
     }
 
 
+    /// Ask the model for a gerund of the input to show by the spinner, in the background
+    fn ask_gerund(&mut self, input: &str) {
+        self.input.set_thinking_label(None);
+        if let Some(handle) = self.pending_gerund.take() {
+            handle.abort();
+        }
+        if let Some((llm, model)) = &self.gerund_llm {
+            self.pending_gerund = Some(tokio::spawn(gerund(llm.clone(), model.clone(), input.to_string())));
+        }
+    }
+
+    fn check_pending_gerund(&mut self) {
+        if !self.pending_gerund.as_ref().is_some_and(|handle| handle.is_finished()) {
+            return;
+        }
+        if let Some(Ok(Ok(label))) = self.pending_gerund.take().and_then(|handle| handle.now_or_never()) {
+            self.input.set_thinking_label(Some(label));
+        }
+    }
+
     fn draw_ui(&mut self) -> io::Result<()> {
         let modal_height = match &self.state {
             AppModalState::InputShown => self.input.height(),
             AppModalState::PermissionModal { widget } => widget.height(),
+            AppModalState::Onboarding { widget } => widget.height(),
         }.max(5);
         let height = modal_height
         + 1 
@@ -434,6 +519,9 @@ impl App<'_> {
                     AppModalState::PermissionModal { widget } => {
                         widget.draw(frame, modal)
                     }
+                    AppModalState::Onboarding { widget } => {
+                        widget.draw(frame, modal)
+                    }
                 }
             })?;
         }
@@ -136,7 +136,7 @@ impl AppAuth {
                     }
                     NavAction::Next => {
                         // Load fresh config and start provider selection
-                        let config = ShaiConfig::load().unwrap_or_default();
+                        let config = ShaiConfig::load_global().unwrap_or_default();
                         let providers = LlmClient::list_providers();
                         let modal_providers = ModalProviders::new(config, providers);
                         self.state = AuthState::SelectProvider(modal_providers);
@@ -9,7 +9,7 @@ use ratatui::{
     widgets::{Block, Borders, Padding, Paragraph},
     Frame,
 };
-use shai_core::config::config::ShaiConfig;
+use shai_core::config::config::{resolve_env_vars, ShaiConfig};
 use shai_llm::provider::ProviderInfo;
 use shai_llm::client::LlmClient;
 use tui_textarea::TextArea;
@@ -97,6 +97,7 @@ impl ModalEnvs {
         let env_values = self.env_values.clone();
         
         self.fetch_task = Some(tokio::spawn(async move {
+            let env_values = resolve_env_vars(&env_values)?;
             match LlmClient::create_provider(&provider_name, &env_values) {
                 Ok(client) => {
                     match client.models().await {
@@ -241,7 +242,7 @@ impl ModalEnvs {
 
                 match env_var.name.as_str() {
                         "OVH_BASE_URL" => self.input_fields[i].set_placeholder_text("https://oai.endpoints.kepler.ai.cloud.ovh.net/v1"),
-                        "OLLAMA_BASE_URL" => self.input_fields[i].set_placeholder_text("http://localhost:11434/v1"),
+                        "OLLAMA_BASE_URL" => self.input_fields[i].set_placeholder_text("http://localhost:11434"),
                         _ => {}
                 }
 
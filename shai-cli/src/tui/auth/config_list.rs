@@ -22,7 +22,7 @@ pub struct ModalConfig {
 
 impl ModalConfig {
     pub fn new() -> Self {
-        let config = ShaiConfig::load()
+        let config = ShaiConfig::load_global()
             .unwrap_or_default();
 
         Self {
@@ -9,7 +9,7 @@ impl App<'_> {
         HashMap::from([
             (("/exit","exit from the tui"), vec![]),
             (("/auth","select a provider"), vec![]),
-            (("/tc","set the tool call method: [fc | fc2 | so]"), vec!["method"]),
+            (("/tc","set the tool call method: [fc | fc2 | so | xml]"), vec!["method"]),
             (("/tokens","display token usage (input/output)"), vec![]),
             (("/theme","set theme: [dark | light | toggle]"), vec!["mode"]),
         ])
@@ -54,17 +54,52 @@ impl App<'_> {
                                 self.input.set_tool_call_method(method);
                             }
                         }
+                        Some("xml") => {
+                            if let Ok(method) = agent.controller.set_method(Some(ToolCallMethod::Xml)).await {
+                                self.input.alert_msg("llm will now write tool calls as xml tags", Duration::from_secs(3));
+                                self.input.set_tool_call_method(method);
+                            }
+                        }
                         _ => {}
                     }
                 }
             }
             "/tokens" => {
-                let msg = format!(
-                    "Token Usage - Input: {}, Output: {}, Total: {}",
-                    self.total_input_tokens,
-                    self.total_output_tokens,
-                    self.total_input_tokens + self.total_output_tokens
-                );
+                let usage = match self.agent {
+                    Some(ref agent) => match agent.controller.get_token_usage().await {
+                        Ok(usage) => Some((usage, agent.controller.get_cost_estimate().await.ok().flatten())),
+                        Err(_) => None,
+                    },
+                    None => None,
+                };
+                let msg = match usage {
+                    Some(((usage, budget), cost)) => {
+                        let mut msg = format!(
+                            "Token Usage - Input: {}, Output: {}, Total: {}",
+                            usage.input_tokens,
+                            usage.output_tokens,
+                            usage.total()
+                        );
+                        if let Some(budget) = budget {
+                            msg.push_str(&format!(" / Budget: {}", budget));
+                        }
+                        if let Some(cost) = cost {
+                            msg.push_str(&format!(" / Cost: {}", cost));
+                            if let Some(note) = cost.note() {
+                                msg.push_str(&format!(" ({})", note));
+                            }
+                        }
+                        msg
+                    }
+                    // the agent stopped, or did not start yet: what its events reported
+                    None if self.session_tokens.total() > 0 => format!(
+                        "Token Usage of the last session - Input: {}, Output: {}, Total: {}",
+                        self.session_tokens.input_tokens,
+                        self.session_tokens.output_tokens,
+                        self.session_tokens.total()
+                    ),
+                    None => "No token used yet, the agent starts with your first message".to_string(),
+                };
                 self.input.alert_msg(&msg, Duration::from_secs(5));
             }
             "/theme" => {
@@ -46,6 +46,7 @@ pub struct InputArea<'a> {
     // alert top left
     animation_start: Option<Instant>,
     status_message: Option<String>,
+    thinking_label: Option<String>, // gerund of the last user input, shown by the spinner
 
     // status bottom left
     last_keystroke_time: Option<Instant>,
@@ -86,6 +87,7 @@ impl InputArea<'_> {
             current_draft: None,
             animation_start: None,
             status_message: None,
+            thinking_label: None,
             last_keystroke_time: None,
             pending_enter: None,
             helper_msg: None,
@@ -254,8 +256,8 @@ impl InputArea<'_> {
             ToolCallMethod::StructuredOutput => {
                 "🛠️ structured output"
             }
-            ToolCallMethod::Parsing => {
-                "🛠️ parsing"
+            ToolCallMethod::Xml => {
+                "🛠️ xml tags"
             }
         }
     } 
@@ -279,6 +281,10 @@ impl InputArea<'_> {
         self
     }
 
+    pub fn set_thinking_label(&mut self, label: Option<String>) {
+        self.thinking_label = label;
+    }
+
     pub fn set_status(&mut self, text: &str) {
         self.status_message = Some(text.to_string());
     }
@@ -296,7 +302,8 @@ impl InputArea<'_> {
             let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
             let elapsed = animation_start.elapsed().as_millis();
             let index = (elapsed / 100) % spinner_chars.len() as u128;
-            format!(" {} Agent is working... (press esc to cancel)", spinner_chars[index as usize])
+            let label = self.thinking_label.as_deref().unwrap_or("Agent is working");
+            format!(" {} {}... (press esc to cancel)", spinner_chars[index as usize], label)
         } else {
             // Agent is waiting for input, no status to show
             String::new()
@@ -7,5 +7,6 @@ pub mod theme;
 pub mod command;
 pub mod helper;
 pub mod cmdnav;
+pub mod onboarding;
 
 pub use app::App;
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    symbols::border,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Padding, Paragraph},
+    Frame
+};
+use shai_core::config::config::OnboardingConfig;
+
+use super::theme::ThemePalette;
+
+pub enum OnboardingAction {
+    /// panel dismissed, the key is also meant for the input (None for enter / esc)
+    Dismiss { forward: Option<KeyEvent> },
+}
+
+/// First-run panel: example prompts, slash commands and the active model
+pub struct OnboardingWidget<'a> {
+    text: Text<'a>,
+    palette: ThemePalette,
+}
+
+impl OnboardingWidget<'_> {
+    pub fn new(config: &OnboardingConfig, commands: &HashMap<(String, String), Vec<String>>, model: Option<&str>, palette: ThemePalette) -> Self {
+        let mut lines = vec![Line::from(Span::styled(config.title.clone(), Style::default().fg(palette.input_text).bold()))];
+        if let Some(model) = model {
+            lines.push(Line::from(Span::styled(format!("running {}", model), palette.placeholder)));
+        }
+
+        if !config.example_prompts.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Try asking:", palette.input_text)));
+            for prompt in &config.example_prompts {
+                lines.push(Line::from(vec![
+                    Span::styled("  › ", palette.placeholder),
+                    Span::styled(prompt.clone(), palette.suggestion_normal),
+                ]));
+            }
+        }
+
+        let mut commands: Vec<_> = commands.keys().collect();
+        commands.sort();
+        if !commands.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Commands:", palette.input_text)));
+            for (cmd, desc) in commands {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {:<10}", cmd), palette.suggestion_normal),
+                    Span::styled(desc.clone(), palette.placeholder),
+                ]));
+            }
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("press any key to start, this panel won't show again", palette.placeholder)));
+
+        Self { text: Text::from(lines), palette }
+    }
+
+    pub fn handle_key_event(&self, key_event: KeyEvent) -> OnboardingAction {
+        match key_event.code {
+            KeyCode::Enter | KeyCode::Esc => OnboardingAction::Dismiss { forward: None },
+            _ => OnboardingAction::Dismiss { forward: Some(key_event) },
+        }
+    }
+
+    pub fn height(&self) -> u16 {
+        self.text.lines.len() as u16 + 2 // content + borders
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .padding(Padding{left: 1, right: 1, top: 0, bottom: 0})
+            .border_style(Style::default().fg(self.palette.border));
+
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        f.render_widget(Paragraph::new(self.text.clone()), inner);
+    }
+}
@@ -38,8 +38,7 @@ pub struct PermissionWidget<'a> {
 }
 
 impl PermissionWidget<'_> {
-    pub fn new(request_id: String, request: PermissionRequest, total: usize, palette: ThemePalette) -> Self {
-        let formatter = PrettyFormatter::new();
+    pub fn new(request_id: String, request: PermissionRequest, total: usize, formatter: &PrettyFormatter, palette: ThemePalette) -> Self {
         let formatted_request = formatter.format_toolcall(&request.call, request.preview.as_ref());
         let preview_text = formatted_request.into_text().unwrap();
         let content_length = preview_text.lines.len();
@@ -59,11 +58,11 @@ impl PermissionWidget<'_> {
 
 
     pub fn move_up(&mut self) {
-        self.selected_index = if self.selected_index == 0 { 2 } else { self.selected_index - 1 };
+        self.selected_index = if self.selected_index == 0 { 3 } else { self.selected_index - 1 };
     }
 
     pub fn move_down(&mut self) {
-        self.selected_index = (self.selected_index + 1) % 3;
+        self.selected_index = (self.selected_index + 1) % 4;
     }
 
     pub fn scroll_up(&mut self) {
@@ -83,7 +82,8 @@ impl PermissionWidget<'_> {
         match self.selected_index {
             0 => PermissionResponse::Allow,
             1 => PermissionResponse::AllowAlways,
-            2 => PermissionResponse::Deny,
+            2 => PermissionResponse::AllowDirectory,
+            3 => PermissionResponse::Deny,
             _ => PermissionResponse::Deny,
         }
     }
@@ -144,7 +144,7 @@ impl PermissionWidget<'_> {
        4 // outer permission block 2 + 1 top padding
        + 2 // inner tool preview block 2 (0 padding)
        + self.preview_text.lines.len() as u16  // preview content
-       + 4 // allow, yolo, deny + 1 top space
+       + 5 // allow, always, always in the directory, deny + 1 top space
     }
 
     pub fn draw(&self, f: &mut Frame, area: Rect) {
@@ -162,10 +162,10 @@ impl PermissionWidget<'_> {
         let inner = block.inner(area);
         f.render_widget(block, area);
 
-        let [tool, modal] = Layout::vertical([Constraint::Length(self.preview_text.lines.len() as u16 + 2), Constraint::Length(4)]).areas(inner);
+        let [tool, modal] = Layout::vertical([Constraint::Length(self.preview_text.lines.len() as u16 + 2), Constraint::Length(5)]).areas(inner);
 
         let call = self.request.call.clone();
-        let tool_name = PrettyFormatter::capitalize_first(&call.tool_name);
+        let tool_name = PrettyFormatter::tool_display_name(&call.tool_name);
         let context = PrettyFormatter::extract_primary_param(&call.parameters, &call.tool_name);
         let mut title = Line::from(vec![
             Span::styled("🔧 ", self.palette.input_text),
@@ -198,7 +198,7 @@ impl PermissionWidget<'_> {
             f.render_stateful_widget(scrollbar, inner, &mut self.scroll_state.clone());
         }
 
-        let items = ["Allow", "Allow all tools and don't ask again for this session", "Deny"];
+        let items = ["Allow", "Allow, and don't ask again for calls like this one here", "Allow, and don't ask again for this tool in this directory", "Deny"];
         let mut lines = vec![Line::from("Do you want to run this tool?")];
         for (i,s) in items.into_iter().enumerate() {
             if i == self.selected_index {
@@ -0,0 +1,123 @@
+use openai_dive::v1::resources::chat::{ChatCompletionChunkResponse, ChatCompletionParameters, ChatCompletionResponse};
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use shai_api::capabilities::CapabilitiesResponse;
+use shai_api::simple::{Message, MultiModalQuery, MultiModalStreamingResponse, UserMessage};
+
+use crate::error::ClientError;
+use crate::sse::{json_events, EventStream};
+
+/// Agent of the server used when none is picked, the one `shai serve` runs without `--agent`
+pub const DEFAULT_AGENT: &str = "default";
+
+/// Client of a `shai serve` server: the multimodal API with its sessions and the OpenAI chat
+/// completions, with their streamed answers read as typed events
+#[derive(Debug, Clone)]
+pub struct ShaiClient {
+    base_url: String,
+    api_key: Option<String>,
+    agent: String,
+    http: reqwest::Client,
+}
+
+impl ShaiClient {
+    /// Client of the server at `base_url`, e.g. "http://127.0.0.1:8080"
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            agent: DEFAULT_AGENT.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Send `api_key` as `Authorization: Bearer <key>`, for servers started with an API key
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string()).filter(|key| !key.is_empty());
+        self
+    }
+
+    /// Agent the multimodal queries go to, the `model` of the request
+    pub fn with_agent(mut self, agent: &str) -> Self {
+        self.agent = agent.to_string();
+        self
+    }
+
+    /// Use this HTTP client, e.g. one with timeouts or a proxy
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Settings of the server and the agents it serves
+    pub async fn capabilities(&self) -> Result<CapabilitiesResponse, ClientError> {
+        self.json(self.request(Method::GET, "/v1/capabilities")).await
+    }
+
+    /// Send `messages` to the agent and stream what it does: its answers, tool calls and results.
+    /// With a `session_id` the session keeps its trace across queries, without it the session
+    /// only lives for this query
+    pub async fn query(&self, session_id: Option<&str>, messages: Vec<Message>) -> Result<EventStream<MultiModalStreamingResponse>, ClientError> {
+        let path = match session_id {
+            Some(session_id) => format!("/v1/multimodal/{}", session_id),
+            None => "/v1/multimodal".to_string(),
+        };
+        let query = MultiModalQuery {
+            model: self.agent.clone(),
+            stream: true,
+            messages: Some(messages),
+            tools: None,
+        };
+        let response = self.send(self.request(Method::POST, &path).json(&query)).await?;
+        Ok(json_events(response))
+    }
+
+    /// Chat completion of the agent named by the `model` of the request, once it is done
+    pub async fn chat_completion(&self, mut request: ChatCompletionParameters) -> Result<ChatCompletionResponse, ClientError> {
+        request.stream = Some(false);
+        self.json(self.request(Method::POST, "/v1/chat/completions").json(&request)).await
+    }
+
+    /// Chat completion chunks of the agent named by the `model` of the request, as it generates them
+    pub async fn chat_completion_stream(&self, mut request: ChatCompletionParameters) -> Result<EventStream<ChatCompletionChunkResponse>, ClientError> {
+        request.stream = Some(true);
+        let response = self.send(self.request(Method::POST, "/v1/chat/completions").json(&request)).await?;
+        Ok(json_events(response))
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    /// Send the request, a non-success answer is turned into the API error it carries
+    async fn send(&self, request: RequestBuilder) -> Result<Response, ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(ClientError::from_body(status.as_u16(), &body))
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+        let body = self.send(request).await?.text().await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+}
+
+/// User message of a query, without attachments
+pub fn user_message(text: &str) -> Message {
+    Message::User(UserMessage {
+        message: text.to_string(),
+        attached_files: None,
+        images: None,
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,231 @@
+use axum::extract::Path;
+use axum::middleware;
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::stream::{self, StreamExt};
+use openai_dive::v1::resources::chat::{
+    ChatCompletionChoice, ChatCompletionChunkChoice, ChatCompletionChunkResponse, ChatCompletionParameters,
+    ChatCompletionParametersBuilder, ChatCompletionResponse, ChatMessage, ChatMessageContent, DeltaChatMessage,
+};
+use openai_dive::v1::resources::shared::FinishReason;
+use shai_api::capabilities::{AgentCapabilities, CapabilitiesResponse, ServerCapabilities, ToolCapability, ToolInfo};
+use shai_api::simple::{AssistantMessage, Message, MultiModalQuery, MultiModalStreamingResponse};
+use shai_http::auth::api_key_middleware;
+use shai_http::ApiKey;
+
+use super::{user_message, ShaiClient};
+use crate::error::ClientError;
+
+const API_KEY: &str = "test-key";
+
+fn capabilities() -> CapabilitiesResponse {
+    CapabilitiesResponse {
+        server: ServerCapabilities {
+            version: "test".to_string(),
+            apis: vec!["multimodal".to_string()],
+            ephemeral: false,
+            max_sessions: None,
+            sudo: true,
+            permission_timeout_secs: None,
+        },
+        agents: vec![AgentCapabilities {
+            name: "default".to_string(),
+            description: None,
+            provider: None,
+            model: None,
+            tools: vec![ToolInfo {
+                name: "write".to_string(),
+                capabilities: vec![ToolCapability::Write],
+                source: "builtin".to_string(),
+            }],
+        }],
+    }
+}
+
+/// Answers each user message of the query with an event echoing it, from the agent and session of the request
+async fn echo_query(Path(session_id): Path<String>, Json(query): Json<MultiModalQuery>) -> Response {
+    let events: Vec<_> = query.messages.unwrap_or_default().into_iter()
+        .filter_map(|message| match message {
+            Message::User(user) => Some(user.message),
+            _ => None,
+        })
+        .map(|text| Event::default().json_data(MultiModalStreamingResponse {
+            id: session_id.clone(),
+            model: query.model.clone(),
+            reasoning: None,
+            assistant: Some(text),
+            call: None,
+            result: None,
+        }))
+        .collect();
+    Sse::new(stream::iter(events)).into_response()
+}
+
+/// Last user message of the request, as text
+fn last_user_text(request: &ChatCompletionParameters) -> String {
+    request.messages.iter().rev()
+        .find_map(|message| match message {
+            ChatMessage::User { content: ChatMessageContent::Text(text), .. } => Some(text.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Answers with the last user message, whole or one word per chunk as the request asks
+async fn echo_completion(Json(request): Json<ChatCompletionParameters>) -> Response {
+    let text = last_user_text(&request);
+    if request.stream != Some(true) {
+        return Json(ChatCompletionResponse {
+            id: Some("chatcmpl-test".to_string()),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: request.model,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(text)),
+                    reasoning_content: None,
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: None,
+                },
+                finish_reason: Some(FinishReason::StopSequenceReached),
+                logprobs: None,
+            }],
+            usage: None,
+            system_fingerprint: None,
+            service_tier: None,
+        }).into_response();
+    }
+
+    let words: Vec<String> = text.split_inclusive(' ').map(str::to_string).collect();
+    let last = words.len().saturating_sub(1);
+    let events: Vec<_> = words.into_iter().enumerate()
+        .map(|(index, word)| Event::default().json_data(ChatCompletionChunkResponse {
+            id: Some("chatcmpl-test".to_string()),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: request.model.clone(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: Some(0),
+                delta: DeltaChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(word)),
+                    reasoning_content: None,
+                    refusal: None,
+                    name: None,
+                    tool_calls: None,
+                },
+                finish_reason: (index == last).then_some(FinishReason::StopSequenceReached),
+                logprobs: None,
+            }],
+            usage: None,
+            system_fingerprint: None,
+        }))
+        .collect();
+    Sse::new(stream::iter(events)).into_response()
+}
+
+fn completion_request(text: &str) -> ChatCompletionParameters {
+    ChatCompletionParametersBuilder::default()
+        .model("reviewer".to_string())
+        .messages(vec![ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }])
+        .build()
+        .unwrap()
+}
+
+/// A server with the API key middleware of shai serve in front of stub routes, and its URL
+async fn serve() -> String {
+    let app = Router::new()
+        .route("/v1/capabilities", get(|| async { Json(capabilities()) }))
+        .route("/v1/multimodal/{session_id}", post(echo_query))
+        .route("/v1/chat/completions", post(echo_completion))
+        .route_layer(middleware::from_fn_with_state(ApiKey::new(API_KEY), api_key_middleware));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{}", address)
+}
+
+#[tokio::test]
+async fn test_refused_request_carries_the_api_error() {
+    let url = serve().await;
+
+    let error = ShaiClient::new(&url).capabilities().await.unwrap_err();
+    assert_eq!(error.status(), Some(401));
+    assert!(matches!(&error, ClientError::Api { error, .. } if error.r#type == "authentication_error"), "{:?}", error);
+
+    let error = ShaiClient::new(&url).with_api_key("wrong-key").capabilities().await.unwrap_err();
+    assert!(matches!(&error, ClientError::Api { error, .. } if error.message == "Incorrect API key provided"), "{:?}", error);
+}
+
+#[tokio::test]
+async fn test_capabilities() {
+    let url = serve().await;
+    // the trailing slash is not doubled in the paths
+    let client = ShaiClient::new(&format!("{}/", url)).with_api_key(API_KEY);
+
+    let capabilities = client.capabilities().await.unwrap();
+    assert_eq!(capabilities.agents[0].name, "default");
+    assert_eq!(capabilities.agents[0].tools[0].capabilities, vec![ToolCapability::Write]);
+}
+
+#[tokio::test]
+async fn test_query_streams_the_events_in_order() {
+    let url = serve().await;
+    let client = ShaiClient::new(&url).with_api_key(API_KEY).with_agent("reviewer");
+
+    let messages = vec![
+        user_message("first"),
+        Message::Assistant(AssistantMessage { assistant: "noted".to_string() }),
+        user_message("second"),
+    ];
+    let events: Vec<MultiModalStreamingResponse> = client.query(Some("my-session"), messages).await.unwrap()
+        .map(|event| event.unwrap())
+        .collect()
+        .await;
+    let answers: Vec<(&str, &str, Option<&str>)> = events.iter()
+        .map(|event| (event.id.as_str(), event.model.as_str(), event.assistant.as_deref()))
+        .collect();
+    assert_eq!(answers, vec![("my-session", "reviewer", Some("first")), ("my-session", "reviewer", Some("second"))]);
+}
+
+#[tokio::test]
+async fn test_chat_completion() {
+    let url = serve().await;
+    let client = ShaiClient::new(&url).with_api_key(API_KEY);
+
+    // asked for a single response, even when the request said to stream
+    let mut request = completion_request("hello there");
+    request.stream = Some(true);
+    let response = client.chat_completion(request).await.unwrap();
+    assert_eq!(response.model, "reviewer");
+    assert!(matches!(&response.choices[0].message,
+        ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } if text == "hello there"),
+        "{:?}", response.choices[0].message);
+}
+
+#[tokio::test]
+async fn test_chat_completion_stream_reads_the_chunks_in_order() {
+    let url = serve().await;
+    let client = ShaiClient::new(&url).with_api_key(API_KEY);
+
+    let chunks: Vec<ChatCompletionChunkResponse> = client.chat_completion_stream(completion_request("one two three")).await.unwrap()
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+    let words: Vec<String> = chunks.iter()
+        .flat_map(|chunk| &chunk.choices)
+        .filter_map(|choice| match &choice.delta {
+            DeltaChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } => Some(text.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(words, vec!["one ", "two ", "three"]);
+    assert!(chunks.last().unwrap().choices[0].finish_reason.is_some());
+
+    let error = ShaiClient::new(&url).chat_completion_stream(completion_request("hello")).await.err().unwrap();
+    assert_eq!(error.status(), Some(401));
+}
@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::Deserialize;
+use shai_api::ErrorDetail;
+
+/// Error of a call to a shai server
+#[derive(Debug)]
+pub enum ClientError {
+    /// The server could not be reached, or the connection broke during the answer
+    Http(reqwest::Error),
+    /// The server refused the request, with the error its API reports
+    Api { status: u16, error: ErrorDetail },
+    /// An answer or an event that does not have the expected shape
+    Parse(String),
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+impl ClientError {
+    /// Error of a non-success answer, its body is `{"error": {...}}` unless a proxy answered
+    pub(crate) fn from_body(status: u16, body: &str) -> Self {
+        let error = match serde_json::from_str::<ErrorBody>(body) {
+            Ok(response) => response.error,
+            Err(_) => ErrorDetail {
+                message: if body.is_empty() { format!("HTTP {}", status) } else { body.to_string() },
+                r#type: "http_error".to_string(),
+                code: None,
+            },
+        };
+        ClientError::Api { status, error }
+    }
+
+    /// HTTP status the server answered with, when the error comes from one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(error) => error.status().map(|status| status.as_u16()),
+            ClientError::Parse(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Http(error) => write!(f, "{}", error),
+            ClientError::Api { status, error } => write!(f, "{} ({}): {}", error.r#type, status, error.message),
+            ClientError::Parse(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClientError::Http(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(error: reqwest::Error) -> Self {
+        ClientError::Http(error)
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(error: serde_json::Error) -> Self {
+        ClientError::Parse(error.to_string())
+    }
+}
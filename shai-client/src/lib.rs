@@ -0,0 +1,20 @@
+//! Typed client of a `shai serve` server.
+//!
+//! `ShaiClient::query` streams the events of the multimodal API, `MultiModalStreamingResponse`:
+//! the answers, tool calls and tool results of the agent as the server sends them. They are not
+//! the `AgentEvent`s of shai-core, which would tie the client to the agent runtime and its
+//! dependencies. Those are served by `GET /v1/sessions/{id}/events` and the `/v1/ws/{id}`
+//! WebSocket, which this crate does not wrap.
+
+pub mod client;
+pub mod error;
+pub mod sse;
+
+pub use client::{user_message, ShaiClient, DEFAULT_AGENT};
+pub use error::ClientError;
+pub use sse::{json_events, EventStream, SseDecoder};
+
+// Request and response types, shared with the server
+pub use shai_api::simple::{AssistantMessage, Message, MultiModalStreamingResponse, PreviousCall, ToolCall, ToolCallResult, UserMessage};
+pub use shai_api::capabilities::{AgentCapabilities, CapabilitiesResponse, ServerCapabilities, ToolCapability, ToolInfo};
+pub use shai_api::ErrorDetail;
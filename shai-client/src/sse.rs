@@ -0,0 +1,83 @@
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::error::ClientError;
+
+/// Events of a streamed answer, in the order the server sent them
+pub type EventStream<T> = BoxStream<'static, Result<T, ClientError>>;
+
+/// Splits a Server-Sent Events body into the data of its events, whatever the size of the chunks it comes in
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Data of the events completed by `chunk`, the lines of a multi-line data joined with '\n'.
+    /// Events without data (comments, keep-alives) are skipped
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend(chunk.iter().filter(|&&byte| byte != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+            let event: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let event = String::from_utf8_lossy(&event);
+            let data: Vec<&str> = event.lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            if !data.is_empty() {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+}
+
+/// The events of an SSE response, each one read as a `T`
+pub fn json_events<T>(response: reqwest::Response) -> EventStream<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let mut decoder = SseDecoder::new();
+    response.bytes_stream()
+        .flat_map(move |chunk| {
+            let events: Vec<Result<T, ClientError>> = match chunk {
+                Ok(bytes) => decoder.push(&bytes).iter()
+                    .map(|data| serde_json::from_str(data).map_err(ClientError::from))
+                    .collect(),
+                Err(error) => vec![Err(error.into())],
+            };
+            stream::iter(events)
+        })
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_split_across_chunks() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.push(b"data: {\"a\":").is_empty());
+        assert_eq!(decoder.push(b" 1}\n\ndata: {\"b\": 2}\n"), vec![r#"{"a": 1}"#]);
+        assert_eq!(decoder.push(b"\n"), vec![r#"{"b": 2}"#]);
+
+        // a character cut in two halves by the chunks
+        let text = "data: é\n\n".as_bytes();
+        assert!(decoder.push(&text[..7]).is_empty());
+        assert_eq!(decoder.push(&text[7..]), vec!["é"]);
+    }
+
+    #[test]
+    fn test_comments_fields_and_line_endings() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.push(b": keep-alive\n\nevent: message\r\ndata: one\r\ndata:two\r\n\r\nid: 3\n\n");
+        assert_eq!(events, vec!["one\ntwo"]);
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use shai_core::agent::{Agent, AgentBuilder, InterceptorDecision, StdoutEventManager, ToolCallInterceptor};
+use shai_core::tools::ToolCall;
+
+/// Keeps file writes inside a directory, and logs every tool call
+struct ProjectOnlyPolicy {
+    root: PathBuf,
+}
+
+#[async_trait]
+impl ToolCallInterceptor for ProjectOnlyPolicy {
+    async fn intercept(&self, call: &ToolCall) -> InterceptorDecision {
+        println!("🔎 {} {}", call.tool_name, call.parameters);
+
+        if !matches!(call.tool_name.as_str(), "write" | "edit" | "multiedit") {
+            return InterceptorDecision::Allow;
+        }
+
+        // multiedit names its path file_path
+        let path = call.parameters.get("path").or_else(|| call.parameters.get("file_path"));
+        let Some(path) = path.and_then(|p| p.as_str()) else {
+            return InterceptorDecision::Deny { reason: "missing path".to_string() };
+        };
+        if Path::new(path).starts_with(&self.root) {
+            InterceptorDecision::Allow
+        } else {
+            InterceptorDecision::Deny { reason: format!("{} is outside of {}", path, self.root.display()) }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let root = std::env::current_dir().expect("no current directory");
+
+    let mut agent = match AgentBuilder::default().await {
+        Ok(builder) => builder
+            .goal("write a hello world in /tmp/hello.py")
+            .interceptor(ProjectOnlyPolicy { root })
+            .sudo()
+            .build(),
+        Err(e) => {
+            println!("❌ Failed to create agent: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match agent.with_event_handler(StdoutEventManager::new()).run().await {
+        Ok(result) => println!("✅ {}", result.message),
+        Err(e) => {
+            println!("❌ Agent failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn decide(tool_name: &str, parameters: serde_json::Value) -> InterceptorDecision {
+        let policy = ProjectOnlyPolicy { root: PathBuf::from("/project") };
+        policy.intercept(&ToolCall {
+            tool_call_id: "call_1".to_string(),
+            tool_name: tool_name.to_string(),
+            parameters,
+        }).await
+    }
+
+    #[tokio::test]
+    async fn test_policy_reads_the_path_of_each_write_tool() {
+        let calls = [
+            ("write", json!({"path": "/project/main.py", "content": ""})),
+            ("edit", json!({"path": "/project/main.py", "old_string": "a", "new_string": "b"})),
+            ("multiedit", json!({"file_path": "/project/main.py", "edits": []})),
+        ];
+        for (tool_name, parameters) in calls {
+            assert!(matches!(decide(tool_name, parameters.clone()).await, InterceptorDecision::Allow), "{}", tool_name);
+
+            let mut outside = parameters;
+            let key = if tool_name == "multiedit" { "file_path" } else { "path" };
+            outside[key] = json!("/etc/passwd");
+            assert!(matches!(decide(tool_name, outside).await, InterceptorDecision::Deny { .. }), "{}", tool_name);
+        }
+    }
+}
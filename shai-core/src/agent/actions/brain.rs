@@ -1,31 +1,58 @@
 use chrono::Utc;
-use openai_dive::v1::resources::chat::ChatMessage;
-use tracing::info;
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ToolCall as LlmToolCall};
+use std::sync::Arc;
+use shai_llm::{with_throttle_hook, ThrottleHook};
+use tracing::{debug, info};
 use tokio_util::sync::CancellationToken;
-use crate::agent::{AgentCore, AgentError, AgentEvent, InternalAgentEvent, InternalAgentState, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
+use crate::agent::{AgentCore, AgentError, AgentEvent, BrainDeltaSink, InternalAgentEvent, InternalAgentState, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
 
 impl AgentCore {
     /// Launch a brain task to decide next step
     pub async fn spawn_next_step(&mut self) {         
+        // a budget spent in an earlier turn stops the agent before it calls the LLM again
+        if let Some(limit) = self.token_budget.filter(|limit| self.budget_used() > *limit) {
+            let error = format!("token budget of {} exceeded ({} tokens used), the agent was stopped", limit, self.budget_used());
+            debug!(target: "agent::tokens", used = self.budget_used(), limit, "token budget exceeded before the next step");
+            self.stop_without_running(Vec::new(), error).await;
+            return;
+        }
+
         let cancellation_token = CancellationToken::new();
         let cancel_token_clone = cancellation_token.clone();
         let trace = self.trace.clone();
         let tx_clone = self.internal_tx.clone();
         let available_tools = self.available_tools.clone();
         let method = self.method.clone();
+        let tool_choice = std::mem::take(&mut self.tool_choice);
+        let sampling = self.sampling;
+        let system_prompt = self.system_prompt.clone();
+        let deltas = self.socket.tx_event.clone()
+            .filter(|_| self.stream_deltas)
+            .map(|tx| BrainDeltaSink::new(tx, cancellation_token.clone()));
         let context = ThinkerContext {
             trace,
             available_tools,
-            method
+            method,
+            tool_choice,
+            sampling,
+            system_prompt,
+            deltas
         };
         let brain = self.brain.clone();
+        // tell the UI when the LLM call waits for its provider's rate limit
+        let public_event_tx = self.socket.tx_event.clone();
+        let throttle_hook: ThrottleHook = Arc::new(move |throttle| {
+            if let Some(tx) = &public_event_tx {
+                let _ = tx.send(AgentEvent::RateLimited { provider: throttle.provider, wait: throttle.wait });
+            }
+        });
         
         //////////////////////// TOKIO SPAWN
         tokio::spawn(async move {
             tokio::select! {
-                result = async {
+                result = with_throttle_hook(throttle_hook, async {
                     brain.write().await.next_step(context).await
-                } => {
+                }) => {
                     let _ = tx_clone.send(InternalAgentEvent::BrainResult {
                         result
                     });
@@ -59,7 +86,13 @@ impl AgentCore {
         let trace = self.trace.clone();
         trace.write().await.push(message.clone());
         
-        // Emit event to external consumers
+        // Emit event to external consumers, the reasoning on its own first
+        if let Some(text) = reasoning_content.as_ref().filter(|r| !r.trim().is_empty()) {
+            let _ = self.emit_event(AgentEvent::Reasoning {
+                timestamp: Utc::now(),
+                text: text.clone()
+            }).await;
+        }
         let _ = self.emit_event(AgentEvent::BrainResult {
             timestamp: Utc::now(),
             thought: Ok(message.clone())
@@ -71,11 +104,36 @@ impl AgentCore {
                 input_tokens,
                 output_tokens
             }).await;
+
+            self.token_usage.input_tokens += input_tokens as u64;
+            self.token_usage.output_tokens += output_tokens as u64;
+            if let Some(limit) = self.token_budget {
+                let _ = self.emit_event(AgentEvent::TokenBudget { used: self.budget_used(), limit }).await;
+            }
+            if let Some(cost) = self.cost_estimate() {
+                let _ = self.emit_event(AgentEvent::CostEstimate { model: cost.model, usd: cost.usd, priced: cost.priced }).await;
+            }
         }
     
-        // run tool call if any
+        // a spent budget stops the agent unless it is stopping anyway
         let tool_calls_from_brain = tool_calls.unwrap_or(vec![]);
+        let keeps_going = !tool_calls_from_brain.is_empty() || matches!(flow, ThinkerFlowControl::AgentContinue);
+        if let Some(limit) = self.token_budget.filter(|limit| keeps_going && self.budget_used() > *limit) {
+            let error = format!("token budget of {} exceeded ({} tokens used), the agent was stopped", limit, self.budget_used());
+            debug!(target: "agent::tokens", used = self.budget_used(), limit, "token budget exceeded");
+            self.stop_without_running(tool_calls_from_brain, error).await;
+            return Ok(())
+        }
+
+        // run tool call if any
         if !tool_calls_from_brain.is_empty() {
+            if self.steps >= self.max_steps {
+                let error = format!("maximum of {} tool steps reached without user input, the agent may be stuck in a loop", self.max_steps);
+                debug!(target: "agent::steps", max_steps = self.max_steps, "step limit reached");
+                self.stop_without_running(tool_calls_from_brain, error).await;
+                return Ok(())
+            }
+            self.steps += 1;
             self.spawn_tools(tool_calls_from_brain).await;
             return Ok(())
         }
@@ -86,17 +144,46 @@ impl AgentCore {
                 self.set_state(InternalAgentState::Running).await;
             }
             ThinkerFlowControl::AgentPause => {
-                self.set_state(InternalAgentState::Paused).await;
+                if self.request_self_check().await {
+                    return Ok(())
+                }
+                let files = self.files_to_format().await;
+                if files.is_empty() {
+                    self.pause_or_verify().await;
+                } else {
+                    self.spawn_formatting(files).await;
+                }
             }
         }
         Ok(())
     }
 
+    /// A limit stopped the agent (max_steps, token budget): the calls it asked for get a result
+    /// saying they were not run, so the trace stays valid, and the agent waits for the user
+    async fn stop_without_running(&mut self, tool_calls: Vec<LlmToolCall>, error: String) {
+        self.trace.write().await.extend(tool_calls.into_iter().map(|call| ChatMessage::Tool {
+            tool_call_id: call.id,
+            content: ChatMessageContent::Text(format!("not run: {}", error)),
+        }));
+        let _ = self.emit_event(AgentEvent::Error { error: error.clone() }).await;
+
+        if self.has_io() {
+            self.set_state(InternalAgentState::Paused).await;
+        } else {
+            self.completion_message = Some(error);
+            self.set_state(InternalAgentState::Completed { success: false }).await;
+        }
+    }
+
     // Helper method that emits error events before returning the error
     async fn handle_brain_error<T>(&mut self, result: Result<T, AgentError>) -> Result<T, AgentError> {
         match result {
-            Ok(value) => Ok(value),
+            Ok(value) => {
+                self.brain_error = None;
+                Ok(value)
+            },
             Err(error) => {
+                self.brain_error = Some(error.clone());
                 self.set_state(InternalAgentState::Paused).await;
                 let _ = self.emit_event(AgentEvent::BrainResult { 
                     timestamp: Utc::now(),
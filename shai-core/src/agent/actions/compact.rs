@@ -0,0 +1,32 @@
+use tracing::debug;
+use crate::agent::{AgentCore, AgentEvent};
+use crate::agent::trace::{compact_trace, estimate_tokens};
+
+impl AgentCore {
+    /// Fold the older part of the trace into a summary when it grows past the compaction budget,
+    /// so the next brain step doesn't overflow the model context window
+    pub(crate) async fn compact_if_needed(&mut self) {
+        let Some(compaction) = self.compaction else {
+            return;
+        };
+
+        let compacted = {
+            let mut trace = self.trace.write().await;
+            let tokens_before = estimate_tokens(&trace);
+            if tokens_before <= compaction.max_tokens {
+                return;
+            }
+            let Some((compacted, messages)) = compact_trace(&trace, compaction.keep_recent) else {
+                debug!(target: "agent::compaction", tokens_before, "trace over budget but nothing left to fold");
+                return;
+            };
+            let tokens_after = estimate_tokens(&compacted);
+            *trace = compacted;
+            (tokens_before, tokens_after, messages)
+        };
+
+        let (tokens_before, tokens_after, messages) = compacted;
+        debug!(target: "agent::compaction", tokens_before, tokens_after, messages, "trace compacted");
+        let _ = self.emit_event(AgentEvent::TraceCompacted { tokens_before, tokens_after, messages }).await;
+    }
+}
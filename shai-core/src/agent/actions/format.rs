@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use chrono::Utc;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+use crate::agent::{AgentCore, AgentError, AgentEvent, FormatResult, InternalAgentEvent, InternalAgentState};
+use crate::tools::{AnyTool, BashTool, ToolResult};
+
+impl AgentCore {
+    /// Files written since the last formatting pass that have a formatter for their extension
+    pub(crate) async fn files_to_format(&self) -> Vec<(String, String)> {
+        let Some(fs_log) = self.fs_log.as_ref().filter(|_| !self.formatters.is_empty()) else {
+            return Vec::new();
+        };
+
+        fs_log.modified_since(self.formatted_at).await
+            .into_iter()
+            .filter_map(|file| {
+                let ext = Path::new(&file).extension()?.to_str()?;
+                let command = formatter_command(self.formatters.get(ext)?, &file);
+                Some((file, command))
+            })
+            .collect()
+    }
+
+    /// Run the formatters one file after the other, they report back with a FormattingCompleted event
+    pub async fn spawn_formatting(&mut self, files: Vec<(String, String)>) {
+        self.formatted_at = Utc::now();
+
+        let cancellation_token = CancellationToken::new();
+        let cancel_token_clone = cancellation_token.clone();
+        let tx_clone = self.internal_tx.clone();
+
+        //////////////////////// TOKIO SPAWN
+        tokio::spawn(async move {
+            let bash = BashTool::new();
+            let mut results = Vec::new();
+            for (file, command) in files {
+                let result = tokio::select! {
+                    result = bash.execute_json(json!({ "command": command }), Some(cancel_token_clone.clone())) => result,
+                    _ = cancel_token_clone.cancelled() => return,
+                };
+                results.push(FormatResult {
+                    file,
+                    command,
+                    success: result.is_success(),
+                    output: match result {
+                        ToolResult::Error { error, .. } => error,
+                        _ => String::new(),
+                    },
+                });
+            }
+            let _ = tx_clone.send(InternalAgentEvent::FormattingCompleted { results });
+        });
+        //////////////////////// TOKIO SPAWN
+
+        self.set_state(InternalAgentState::Processing {
+            task_name: "format".to_string(),
+            tools_exec_at: Utc::now(),
+            cancellation_token
+        }).await;
+    }
+
+    /// Report the formatting outcome, then carry on with the verification if any
+    pub async fn process_formatting(&mut self, results: Vec<FormatResult>) -> Result<(), AgentError> {
+        for result in &results {
+            debug!(target: "agent::format", file = ?result.file, command = ?result.command, success = result.success);
+        }
+        let _ = self.emit_event(AgentEvent::FilesFormatted { results }).await;
+
+        self.pause_or_verify().await;
+        Ok(())
+    }
+}
+
+/// `{file}` in the configured command is replaced by the quoted path, otherwise the path is appended
+fn formatter_command(command: &str, file: &str) -> String {
+    let quoted = format!("'{}'", file.replace('\'', r"'\''"));
+    if command.contains("{file}") {
+        command.replace("{file}", &quoted)
+    } else {
+        format!("{} {}", command, quoted)
+    }
+}
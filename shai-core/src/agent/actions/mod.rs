@@ -1,2 +1,6 @@
 pub mod brain;
 pub mod tools;
+pub mod format;
+pub mod verify;
+pub mod self_check;
+pub mod compact;
@@ -0,0 +1,68 @@
+use std::sync::atomic::Ordering;
+
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+use tracing::debug;
+use crate::agent::{AgentCore, AgentEvent, InternalAgentState};
+use crate::tools::ToolResult;
+
+/// Times the model is asked to check its changes in one turn, it may stop unchecked after that
+pub const MAX_SELF_CHECK_PROMPTS: u32 = 2;
+
+const SELF_CHECK_PROMPT: &str = "Before you stop: you modified files but did not check the result. \
+Run the tests, the build or the linter of the project with your tools to confirm that your changes work, \
+fix what fails, then stop again. If the project has no way to check the changes, say so and stop.";
+
+/// Tools that can run the tests, the build or the linter: reading files again is not a check
+const CHECK_TOOLS: &[&str] = &["bash"];
+
+impl AgentCore {
+    /// Completion gate of `verify_before_complete`: when the model stops after modifying files,
+    /// ask it to check them with its tools first. Passes once it ran one of the CHECK_TOOLS after
+    /// the prompt or ignored MAX_SELF_CHECK_PROMPTS of them; true when the agent goes on instead of stopping
+    pub(crate) async fn request_self_check(&mut self) -> bool {
+        if !self.verify_before_complete || !self.files_modified.load(Ordering::SeqCst) {
+            return false;
+        }
+        let checked = self.self_check_prompts > 0 && self.ran_check_since_prompt().await;
+        if checked || self.self_check_prompts >= MAX_SELF_CHECK_PROMPTS {
+            debug!(target: "agent::self_check", checked, prompts = self.self_check_prompts, "letting the agent stop");
+            self.self_check_prompts = 0;
+            // without verify command, nothing else looks at the changes once they were checked
+            if self.verify.is_none() {
+                self.files_modified.store(false, Ordering::SeqCst);
+            }
+            return false;
+        }
+
+        self.self_check_prompts += 1;
+        debug!(target: "agent::self_check", attempt = self.self_check_prompts, "asking the agent to check its changes");
+        self.trace.write().await.push(ChatMessage::User {
+            content: ChatMessageContent::Text(SELF_CHECK_PROMPT.to_string()),
+            name: None
+        });
+        let _ = self.emit_event(AgentEvent::SelfCheckRequested { attempt: self.self_check_prompts }).await;
+        self.set_state(InternalAgentState::Running).await;
+        true
+    }
+
+    /// Whether a call to one of the CHECK_TOOLS ran (was not denied) since the last self-check prompt
+    async fn ran_check_since_prompt(&self) -> bool {
+        let trace = self.trace.read().await;
+        let since_prompt: Vec<&ChatMessage> = trace.iter().rev()
+            .take_while(|message| !matches!(message,
+                ChatMessage::User { content: ChatMessageContent::Text(text), .. } if text == SELF_CHECK_PROMPT))
+            .collect();
+        let check_calls: Vec<&str> = since_prompt.iter()
+            .filter_map(|message| match message {
+                ChatMessage::Assistant { tool_calls: Some(calls), .. } => Some(calls),
+                _ => None,
+            })
+            .flatten()
+            .filter(|call| CHECK_TOOLS.contains(&call.function.name.as_str()))
+            .map(|call| call.id.as_str())
+            .collect();
+        since_prompt.iter().any(|message| matches!(message,
+            ChatMessage::Tool { tool_call_id, content: ChatMessageContent::Text(text), .. }
+                if check_calls.contains(&tool_call_id.as_str()) && !ToolResult::from_trace_text(text).is_denied()))
+    }
+}
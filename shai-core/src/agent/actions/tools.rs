@@ -1,20 +1,53 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use chrono::{TimeDelta, Utc};
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ToolCall as LlmToolCall};
-use tokio::sync::{broadcast, RwLock};
+use shai_llm::image::{image_part, text_part};
+use tokio::sync::{broadcast, RwLock, Semaphore};
 use tokio::task::JoinHandle;
+use tokio::time::Duration;
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{info, warn};
 use serde_json::from_str;
 use uuid::Uuid;
-use crate::agent::{AgentCore, AgentEvent, ClaimManager, InternalAgentEvent, InternalAgentState, PermissionRequest, PermissionResponse};
-use crate::tools::{AnyTool, ToolCall, ToolCapability, ToolResult};
+use crate::agent::{forbidden_pattern, forbidden_result, AgentCore, AgentEvent, ClaimManager, CommandDenyList, InterceptorDecision, InternalAgentEvent, InternalAgentState, PermissionRequest, PermissionResponse, ToolCallInterceptor, ToolTimeTotals};
+use crate::tools::{mcp_reconnect, normalize_arguments, prefixed_tool_name, AnyTool, TodoStorage, ToolCall, ToolCapability, ToolOutputLimit, ToolResult};
 use tracing::debug;
 
+/// Read-only calls may be repeated this many times more, re-reading a file after an edit is expected
+const READ_ONLY_REPEAT_FACTOR: u32 = 3;
+
+/// Read-only calls of a batch may run this many times more at once than the ones that write
+const READ_ONLY_PARALLEL_FACTOR: usize = 2;
+
+/// Slots of a batch of tool calls: calls that write wait on a smaller pool than reads
+#[derive(Clone)]
+struct ToolSlots {
+    reads: Arc<Semaphore>,
+    writes: Arc<Semaphore>,
+}
+
+impl ToolSlots {
+    fn new(max_parallel_tools: usize) -> Self {
+        let writes = max_parallel_tools.max(1);
+        Self {
+            reads: Arc::new(Semaphore::new(writes * READ_ONLY_PARALLEL_FACTOR)),
+            writes: Arc::new(Semaphore::new(writes)),
+        }
+    }
+}
+
 impl AgentCore {
 
-    /// Spawn a cancellable coroutine that runs all tool call in parrallel and waits for them to finish
+    /// Spawn a cancellable coroutine that runs all tool call in parrallel and waits for them to finish.
+    /// Each call gets a child of the batch token, so it can be cancelled alone by its tool_call_id.
+    /// At most `max_parallel_tools` calls that may write run at once, and READ_ONLY_PARALLEL_FACTOR
+    /// times as many read-only calls on their own pool, the others wait for a slot. Without
+    /// `parallel_tool_calls`, each call starts once the previous one completed, in the order of the batch
     pub async fn spawn_tools(&mut self, tool_calls: Vec<LlmToolCall>) {
         let cancellation_token = CancellationToken::new();
         let cancel_clone = cancellation_token.clone();
@@ -25,23 +58,88 @@ impl AgentCore {
         let available_tools = self.available_tools.clone();
         let claims = self.permissions.clone();
         let trace = self.trace.clone();
+        let todos = self.todos.clone();
+        let permission_timeout = self.permission_timeout
+            .map(|timeout| (timeout, self.permission_timeout_response.clone()));
+        let files_modified = self.files_modified.clone();
+        let interceptors = self.interceptors.clone();
+        let command_deny_list = self.command_deny_list.clone();
+        let dry_run = self.dry_run;
+        let read_only = self.read_only;
+        let normalize_tool_args = self.normalize_tool_args;
+        let tool_output_limit = self.tool_output_limit.clone();
+        let repeat_limit = self.repeat_limit;
+        let call_counts = self.call_counts.clone();
+        let running_tools = self.running_tools.clone();
+        let tool_time = self.tool_time.clone();
+        let slots = ToolSlots::new(self.max_parallel_tools);
+        let trace_for_spawn = trace.clone();
+        let internal_tx_for_spawn = internal_tx.clone();
+        let running_tools_for_spawn = running_tools.clone();
+        let trace_for_batch = trace.clone();
+        let event_tx_for_batch = public_event_tx.clone();
+        let running_tools_for_batch = running_tools.clone();
+        let spawn = move |tc: LlmToolCall, call_token: CancellationToken| Self::spawn_tool_static(
+            tc,
+            call_token,
+            public_event_tx.clone(),
+            available_tools.clone(),
+            claims.clone(),
+            internal_tx_for_spawn.clone(),
+            trace_for_spawn.clone(),
+            todos.clone(),
+            permission_timeout.clone(),
+            files_modified.clone(),
+            interceptors.clone(),
+            command_deny_list.clone(),
+            dry_run,
+            read_only,
+            normalize_tool_args,
+            tool_output_limit.clone(),
+            repeat_limit,
+            call_counts.clone(),
+            running_tools_for_spawn.clone(),
+            tool_time.clone(),
+            slots.clone(),
+        );
 
-        // Spawn a task to wait for all tool executions
-        let mut join_handles = Vec::new();
-        
-        // Spawn all tool executions
+        // every call of the batch can be cancelled from now, even one still waiting for its turn
+        let mut calls = Vec::new();
         for tc in tool_calls {
-            let handle = Self::spawn_tool_static(
-                tc,
-                cancel_clone.clone(),
-                public_event_tx.clone(),
-                available_tools.clone(),
-                claims.clone(),
-                internal_tx.clone(),
-                trace.clone(),
-            );
-            join_handles.push(handle);
+            let call_token = cancel_clone.child_token();
+            running_tools.lock().unwrap().insert(tc.id.clone(), call_token.clone());
+            calls.push((tc, call_token));
         }
+
+        // parallel calls all start now, sequential ones each once the previous one completed. The batch
+        // goes on after a cancellation, so that the calls still queued record that they were cancelled
+        let parallel_calls: Vec<_> = if self.parallel_tool_calls {
+            calls.drain(..).map(|(tc, call_token)| spawn(tc, call_token)).collect()
+        } else {
+            Vec::new()
+        };
+        let batch = tokio::spawn(async move {
+            let mut outcomes = Vec::new();
+            for handle in parallel_calls {
+                outcomes.extend(handle.await);
+            }
+            for (tc, call_token) in calls {
+                // a call still queued when the batch was cancelled does not run at all
+                if call_token.is_cancelled() {
+                    let call = ToolCall {
+                        tool_call_id: tc.id.clone(),
+                        tool_name: tc.function.name.clone(),
+                        parameters: from_str(&tc.function.arguments).unwrap_or_default(),
+                    };
+                    Self::record_cancelled_call(call, &trace_for_batch, &event_tx_for_batch).await;
+                    running_tools_for_batch.lock().unwrap().remove(&tc.id);
+                    outcomes.push((false, None));
+                    continue;
+                }
+                outcomes.extend(spawn(tc, call_token).await);
+            }
+            outcomes
+        });
             
         // Wait for all tools to complete or be cancelled
         tokio::spawn(async move {
@@ -49,16 +147,25 @@ impl AgentCore {
                 _ = cancel_clone.cancelled() => {
                     // Tools were cancelled, no need to send completion event
                 }
-                any_denied = async {
+                (any_denied, images) = async {
                     // wait for all tools completion and collect denial status
                     let mut result = false;
-                    for handle in join_handles {
-                        if let Ok(was_denied) = handle.await {
-                            result = result || was_denied;
-                        }
+                    let mut images = Vec::new();
+                    for (was_denied, image) in batch.await.unwrap_or_default() {
+                        result = result || was_denied;
+                        images.extend(image);
                     }
-                    result
+                    (result, images)
                 } => {
+                    // tool messages only hold text, the images follow all the results in a user message
+                    if !images.is_empty() {
+                        let mut parts = vec![text_part("Images returned by the tool calls above:")];
+                        parts.extend(images.into_iter().map(image_part));
+                        trace.write().await.push(ChatMessage::User {
+                            content: ChatMessageContent::ContentPart(parts),
+                            name: None,
+                        });
+                    }
                     // All tools completed, move to Running state
                     let _ = internal_tx.send(InternalAgentEvent::ToolsCompleted { any_denied });
                 }
@@ -74,7 +181,8 @@ impl AgentCore {
     }
 
     /// Spawn a cancellable coroutine that runs a single tool call
-    /// coordinating the appropriate tool specific event (start/completed)
+    /// coordinating the appropriate tool specific event (start/completed).
+    /// Resolves to whether the call was denied and the image the result carries, if any
     fn spawn_tool_static(
         tc: LlmToolCall,
         cancel_token: CancellationToken,
@@ -83,10 +191,24 @@ impl AgentCore {
         claims: Arc<RwLock<ClaimManager>>,
         internal_tx: broadcast::Sender<InternalAgentEvent>,
         trace: Arc<RwLock<Vec<ChatMessage>>>,
-    ) -> tokio::task::JoinHandle<bool> {
+        todos: Option<Arc<TodoStorage>>,
+        permission_timeout: Option<(Duration, PermissionResponse)>,
+        files_modified: Arc<AtomicBool>,
+        interceptors: Vec<Arc<dyn ToolCallInterceptor>>,
+        command_deny_list: Arc<CommandDenyList>,
+        dry_run: bool,
+        read_only: bool,
+        normalize_tool_args: bool,
+        tool_output_limit: Arc<ToolOutputLimit>,
+        repeat_limit: Option<u32>,
+        call_counts: Arc<Mutex<HashMap<u64, u32>>>,
+        running_tools: Arc<Mutex<HashMap<String, CancellationToken>>>,
+        tool_time: Arc<Mutex<ToolTimeTotals>>,
+        slots: ToolSlots,
+    ) -> tokio::task::JoinHandle<(bool, Option<String>)> {
         tokio::spawn(async move {
             let tc_for_error = tc.clone();
-            match Self::tool_exist(available_tools, tc) {
+            let outcome = match Self::tool_exist(available_tools, tc, normalize_tool_args) {
                 // tool does not exist, we fail immediately
                 Err(tool_result) => {
                     if let Some(tx) = public_event_tx.clone() {
@@ -100,13 +222,26 @@ impl AgentCore {
                             result: tool_result
                         });
                     }
-                    false
+                    (false, None)
                 }
 
                 // emit tool call
                 // execute tool
                 // emit tool result
                 Ok((tool, call)) => {
+                    // wait for a slot, a call cancelled meanwhile never starts
+                    let may_write = tool.call_capabilities(&call.parameters).contains(&ToolCapability::Write);
+                    let pool = if may_write { &slots.writes } else { &slots.reads };
+                    let slot = tokio::select! {
+                        biased;
+                        _ = cancel_token.cancelled() => None,
+                        slot = pool.clone().acquire_owned() => slot.ok(),
+                    };
+                    if slot.is_none() {
+                        Self::record_cancelled_call(call, &trace, &public_event_tx).await;
+                        running_tools.lock().unwrap().remove(&tc_for_error.id);
+                        return (false, None);
+                    }
                     let start = Utc::now();
 
                     // Emit tool call started event
@@ -117,31 +252,62 @@ impl AgentCore {
                         });
                     }
                     
-                    // execute tool
-                    let tool_handle = Self::spawn_tool_exec(
-                        tool, call.clone(), 
-                        cancel_token.clone(), 
-                        claims, 
-                        public_event_tx.clone(), 
-                        internal_tx.subscribe());
-
-                    // wait for result (or for cancellation)
-                    let result: ToolResult = tokio::select! {
-                        join_result = tool_handle => {
-                            match join_result {
-                                Ok(tool_result) => tool_result,
-                                Err(join_error) => {
-                                    debug!(target: "agent::tool_completed", "tool execution task failed: {}", join_error);
-                                    ToolResult::error(format!("tool execution task failed: {}", join_error))
+                    // execute tool, unless the model already made this exact call too many times
+                    let may_write = may_write && !dry_run && !read_only;
+                    let mut ran = true;
+                    let mut result: ToolResult = match Self::repeated_call(&tool, &call, repeat_limit, &call_counts) {
+                        Some(repeated) => {
+                            ran = false;
+                            repeated
+                        }
+                        None => {
+                            let tool_handle = Self::spawn_tool_exec(
+                                tool, call.clone(), 
+                                cancel_token.clone(), 
+                                claims, 
+                                public_event_tx.clone(), 
+                                internal_tx.subscribe(),
+                                permission_timeout,
+                                interceptors,
+                                command_deny_list,
+                                dry_run,
+                                read_only);
+
+                            // wait for result (or for cancellation)
+                            tokio::select! {
+                                join_result = tool_handle => {
+                                    match join_result {
+                                        Ok(tool_result) => tool_result,
+                                        Err(join_error) => {
+                                            debug!(target: "agent::tool_completed", "tool execution task failed: {}", join_error);
+                                            ToolResult::error(format!("tool execution task failed: {}", join_error))
+                                        }
+                                    }
+                                 },
+                                _ = cancel_token.cancelled() => {
+                                    debug!(target: "agent::tool_completed", "cancelled by user");
+                                    ran = false;
+                                    ToolResult::error("tool call was cancelled by the user".to_string())
                                 }
                             }
-                         },
-                        _ = cancel_token.cancelled() => {
-                            debug!(target: "agent::tool_completed", "cancelled by user");
-                            ToolResult::error("tool call was cancelled by the user".to_string())
                         }
                     };
 
+                    // the image goes to the model only, not into the events and logs of the result
+                    let image = result.take_image();
+
+                    // an output too long for the context loses its middle
+                    tool_output_limit.apply(&call.tool_name, &mut result);
+
+                    // only the calls that ran have a duration, the others complete in no time
+                    let ran = ran && !result.is_denied() && forbidden_pattern(&result).is_none();
+                    let duration = if ran { Utc::now() - start } else { TimeDelta::zero() };
+                    if ran {
+                        let elapsed = duration.to_std().unwrap_or_default();
+                        result.set_duration(elapsed);
+                        tool_time.lock().unwrap().record(&call.tool_name, elapsed);
+                    }
+
                     // let's first add tool result to trace
                     let _ = {
                         trace.write().await.push(ChatMessage::Tool {
@@ -152,18 +318,33 @@ impl AgentCore {
 
                     // Emit tool call finish event
                     let tool_was_denied = result.is_denied();
+                    let todos_changed = call.tool_name == "todo_write" && result.is_success();
+                    // a call that failed after its attempts never got its connection back
+                    let reconnect = mcp_reconnect(&result).filter(|_| result.is_success());
+                    if may_write && result.is_success() {
+                        files_modified.store(true, Ordering::SeqCst);
+                    }
                     info!(target: "agent::tool_completed", call = ?tc_for_error.function.name.clone(), result = ?result);
                     if let Some(tx) = public_event_tx.clone() {
+                        if let Some((server, attempts)) = reconnect {
+                            let _ = tx.send(AgentEvent::McpReconnected { server, tool_name: call.tool_name.clone(), attempts });
+                        }
                         let _ = tx.send(AgentEvent::ToolCallCompleted { 
-                            duration: Utc::now() - start, 
+                            duration, 
                             call: call, 
                             result 
                         });   
+
+                        if let (true, Some(todos)) = (todos_changed, &todos) {
+                            let _ = tx.send(AgentEvent::TodoUpdated { todos: todos.get_all().await });
+                        }
                     }
 
-                    tool_was_denied                    
+                    (tool_was_denied, image)
                 }
-            }
+            };
+            running_tools.lock().unwrap().remove(&tc_for_error.id);
+            outcome
         })
     }
 
@@ -175,16 +356,66 @@ impl AgentCore {
         cancel_token: CancellationToken,
         claims: Arc<RwLock<ClaimManager>>, 
         public_event_tx: Option<broadcast::Sender<AgentEvent>>, 
-        mut internal_rx: broadcast::Receiver<InternalAgentEvent>) -> JoinHandle<ToolResult> {
+        mut internal_rx: broadcast::Receiver<InternalAgentEvent>,
+        permission_timeout: Option<(Duration, PermissionResponse)>,
+        interceptors: Vec<Arc<dyn ToolCallInterceptor>>,
+        command_deny_list: Arc<CommandDenyList>,
+        dry_run: bool,
+        read_only: bool) -> JoinHandle<ToolResult> {
         tokio::spawn(async move {
+            // policy hooks go first, they may block the call or rewrite its parameters
+            let call = match Self::intercept(&interceptors, call).await {
+                Ok(call) => call,
+                Err(blocked) => return blocked,
+            };
+
+            // then the deny-list, on the command that would actually run, sudo mode or not
+            if let Some(pattern) = command_deny_list.forbids(&call) {
+                info!(target: "agent::permission", tool = ?call.tool_name, pattern = ?pattern, "command forbidden by the deny-list");
+                if let Some(tx) = &public_event_tx {
+                    let _ = tx.send(AgentEvent::CommandForbidden {
+                        call: call.clone(),
+                        pattern: pattern.to_string(),
+                        response: PermissionResponse::Forbidden,
+                    });
+                }
+                return forbidden_result(&call, pattern);
+            }
+
+            let capabilities = tool.call_capabilities(&call.parameters);
+            if read_only && capabilities.contains(&ToolCapability::Write) {
+                debug!(target: "agent::read_only", tool = ?call.tool_name, "write refused");
+                return ToolResult::error(format!(
+                    "read-only run: this {} call would change things and was not executed", call.tool_name));
+            }
+
+            // in dry-run nothing that changes things runs, so there is nothing to ask permission for
+            let changes_things = capabilities.iter()
+                .any(|capability| matches!(capability, ToolCapability::Write | ToolCapability::Network));
+            if dry_run && changes_things {
+                return Self::dry_run_result(&tool, &call).await;
+            }
+
             // check permission, we allow all Read Tool
-            let can_run = tool.capabilities().is_empty()  
-            || tool.capabilities() == &[ToolCapability::Read]
+            let can_run = capabilities.is_empty()  
+            || capabilities == &[ToolCapability::Read]
             || claims.read().await.is_permitted(&tool.name(), &call.parameters);
 
             // request permission if needed (|| is short-circuiting, so won't call if can_run is true)
-            let can_run = can_run || match Self::request_permission_if_needed(&call, &tool, &public_event_tx, &mut internal_rx, &cancel_token).await {
-                Ok(permission_granted) => permission_granted,
+            let can_run = can_run || match Self::request_permission_if_needed(&call, &tool, &public_event_tx, &mut internal_rx, &cancel_token, &permission_timeout).await {
+                Ok(response @ (PermissionResponse::AllowAlways | PermissionResponse::AllowDirectory)) => {
+                    let mut claims = claims.write().await;
+                    let saved = if response == PermissionResponse::AllowDirectory {
+                        claims.allow_directory(&tool.name(), &call.parameters)
+                    } else {
+                        claims.allow_always(&tool.name(), &call.parameters)
+                    };
+                    if let Err(e) = saved {
+                        warn!(target: "agent::permission", "could not save the permission for {}: {}", tool.name(), e);
+                    }
+                    true
+                }
+                Ok(response) => matches!(response, PermissionResponse::Allow),
                 Err(preview_error) => return preview_error, // Return preview error immediately
             };
 
@@ -192,28 +423,47 @@ impl AgentCore {
                 return ToolResult::denied()
             }
             
-            // Execute tool with cancellation support
+            // Execute tool with cancellation support, a cancelled call does not even start
             tokio::select! {
-                result = tool.execute_json(call.parameters.clone(), Some(cancel_token.clone())) => result,
+                biased;
                 _ = cancel_token.cancelled() => {
                     ToolResult::error("tool call was cancelled by the user".to_string())
                 }
+                result = tool.execute_json(call.parameters.clone(), Some(cancel_token.clone())) => result,
             }
         })
     }
 
-    /// send a permission request (if necessary) and wait for the answer
-    /// Returns Ok(true) if permission granted, Ok(false) if denied, Err(ToolResult) if preview failed
+    /// Record the result of a call cancelled before it started, it completes in no time
+    async fn record_cancelled_call(
+        call: ToolCall,
+        trace: &RwLock<Vec<ChatMessage>>,
+        public_event_tx: &Option<broadcast::Sender<AgentEvent>>,
+    ) {
+        debug!(target: "agent::tool_completed", call = ?call.tool_name, "cancelled before it started");
+        let result = ToolResult::error("tool call was cancelled by the user".to_string());
+        trace.write().await.push(ChatMessage::Tool {
+            tool_call_id: call.tool_call_id.clone(),
+            content: ChatMessageContent::Text(result.to_string())
+        });
+        if let Some(tx) = public_event_tx {
+            let _ = tx.send(AgentEvent::ToolCallCompleted { duration: TimeDelta::zero(), call, result });
+        }
+    }
+
+    /// send a permission request (if necessary) and wait for the answer, or for the timeout
+    /// Returns Ok with the answer, Err(ToolResult) if preview failed
     async fn request_permission_if_needed(
         call: &ToolCall,
         tool: &Arc<dyn AnyTool>,
         public_event_tx: &Option<broadcast::Sender<AgentEvent>>,
         internal_rx: &mut broadcast::Receiver<InternalAgentEvent>,
         cancel_token: &CancellationToken,
-    ) -> Result<bool, ToolResult> {
+        permission_timeout: &Option<(Duration, PermissionResponse)>,
+    ) -> Result<PermissionResponse, ToolResult> {
         // Session is not interactive so we cannot ask for permission
         let Some(tx) = public_event_tx.as_ref() else {
-            return Ok(false); 
+            return Ok(PermissionResponse::NoPermissionSystem); 
         };
         
         // Try to get preview from tool
@@ -238,29 +488,146 @@ impl AgentCore {
             }
         });
 
-        // Wait for permission response
+        // Wait for permission response, an answer arriving first wins over the timeout
+        let timeout = async {
+            match permission_timeout {
+                Some((delay, _)) => tokio::time::sleep(*delay).await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+        tokio::pin!(timeout);
+
         loop {
             tokio::select! {
                 recv_result = internal_rx.recv() => {
                     match recv_result {
                         Ok(InternalAgentEvent::PermissionResponseReceived { request_id, response }) if request_id == req_id => {
-                            return Ok(matches!(response, PermissionResponse::Allow | PermissionResponse::AllowAlways));
+                            return Ok(response);
                         }
                         Ok(_) => continue,
-                        Err(_) => return Ok(false), // Channel closed
+                        Err(_) => return Ok(PermissionResponse::Deny), // Channel closed
                     }
                 }
                 _ = cancel_token.cancelled() => {
-                    return Ok(false); // Cancelled during permission wait
+                    return Ok(PermissionResponse::Deny); // Cancelled during permission wait
+                }
+                _ = &mut timeout => {
+                    let response = permission_timeout.as_ref()
+                        .map_or(PermissionResponse::Deny, |(_, response)| response.clone());
+                    debug!(target: "agent::permission", "permission request {} timed out, resolved as {:?}", req_id, response);
+                    let _ = tx.send(AgentEvent::PermissionTimedOut {
+                        request_id: req_id.clone(),
+                        call: call.clone(),
+                        response: response.clone(),
+                    });
+                    // nobody answered, so nothing is remembered past this call
+                    return Ok(match response {
+                        PermissionResponse::AllowAlways | PermissionResponse::AllowDirectory => PermissionResponse::Allow,
+                        response => response,
+                    });
                 }
             }
         }
     }
 
+    /// what the model gets instead of running a tool in dry-run mode: the preview when the tool has one
+    async fn dry_run_result(tool: &Arc<dyn AnyTool>, call: &ToolCall) -> ToolResult {
+        debug!(target: "agent::dry_run", tool = ?call.tool_name, "tool not executed");
+        match tool.execute_preview_json(call.parameters.clone()).await {
+            Some(ToolResult::Success { output, metadata }) => ToolResult::Success {
+                output: format!("dry-run: not executed, nothing was changed. Preview of what the call would do:\n{}", output),
+                metadata,
+            },
+            // a failing preview tells the model the call would fail too
+            Some(error @ ToolResult::Error { .. }) => error,
+            Some(ToolResult::Denied) | None => ToolResult::success(format!(
+                "dry-run: not executed, nothing was changed. {} has no preview, assume the call would have succeeded",
+                call.tool_name)),
+        }
+    }
+
+    /// run the interceptors in order, Err holds the result of a blocked call
+    async fn intercept(interceptors: &[Arc<dyn ToolCallInterceptor>], mut call: ToolCall) -> Result<ToolCall, ToolResult> {
+        for interceptor in interceptors {
+            match interceptor.intercept(&call).await {
+                InterceptorDecision::Allow => {}
+                InterceptorDecision::Deny { reason } => {
+                    info!(target: "agent::interceptor", tool = ?call.tool_name, reason = ?reason, "tool call blocked");
+                    return Err(ToolResult::error(format!("tool call blocked by policy: {}", reason)));
+                }
+                InterceptorDecision::Modify { parameters } => {
+                    debug!(target: "agent::interceptor", tool = ?call.tool_name, parameters = ?parameters, "tool call modified");
+                    call.parameters = parameters;
+                }
+            }
+        }
+        Ok(call)
+    }
+
+    /// count the call, and answer it with an error when the same tool already ran with the
+    /// same parameters as many times as the limit allows since the last user input. Other calls
+    /// in between don't start the count over, a loop of a few calls is still a loop
+    fn repeated_call(
+        tool: &Arc<dyn AnyTool>,
+        call: &ToolCall,
+        repeat_limit: Option<u32>,
+        call_counts: &Mutex<HashMap<u64, u32>>,
+    ) -> Option<ToolResult> {
+        let limit = repeat_limit?;
+        if tool.polls() {
+            return None;
+        }
+        let capabilities = tool.call_capabilities(&call.parameters);
+        let read_only = capabilities.is_empty() || capabilities == &[ToolCapability::Read];
+        let limit = if read_only { limit.saturating_mul(READ_ONLY_REPEAT_FACTOR) } else { limit };
+
+        let mut hasher = DefaultHasher::new();
+        call.tool_name.hash(&mut hasher);
+        Self::hash_parameters(&call.parameters, &mut hasher);
+        let hash = hasher.finish();
+
+        let mut call_counts = call_counts.lock().unwrap();
+        let times = call_counts.entry(hash).or_insert(0);
+        if *times >= limit {
+            info!(target: "agent::tool_repeat", tool = ?call.tool_name, times = *times, "repeated tool call not run");
+            return Some(ToolResult::error(format!(
+                "not run: {} was already called {} times with these exact arguments since the last user input. \
+                 Running it again will not change the outcome, try a different approach or ask the user",
+                call.tool_name, times)));
+        }
+        *times += 1;
+        None
+    }
+
+    /// hash a JSON value with object keys in sorted order, the same arguments sent in another
+    /// order are the same call
+    fn hash_parameters(value: &serde_json::Value, hasher: &mut DefaultHasher) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                '{'.hash(hasher);
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_by_key(|(name, _)| *name);
+                for (name, field) in fields {
+                    name.hash(hasher);
+                    Self::hash_parameters(field, hasher);
+                }
+            }
+            serde_json::Value::Array(items) => {
+                '['.hash(hasher);
+                items.len().hash(hasher);
+                for item in items {
+                    Self::hash_parameters(item, hasher);
+                }
+            }
+            other => other.to_string().hash(hasher),
+        }
+    }
+
     // utility method
     fn tool_exist(
         tools: Vec<Arc<dyn AnyTool>>, 
-        tc: LlmToolCall
+        tc: LlmToolCall,
+        normalize_tool_args: bool,
     ) -> Result<(Arc<dyn AnyTool>, ToolCall), ToolResult>{
         from_str(&tc.function.arguments)
         .map_err(|_e| 
@@ -274,13 +641,40 @@ impl AgentCore {
             };
             
             // Find the tool
-            tools.iter()
-                .find(|t| t.name() == tool_call.tool_name)
-                .cloned()
+            Self::find_tool(&tools, &tool_call.tool_name)
                 .ok_or_else(||
                     ToolResult::error(format!("tool not found: {}", tool_call.tool_name))
                 )
-                .map(|tool| (tool, tool_call))
+                .map(|tool| {
+                    let tool_call = ToolCall { tool_name: tool.name(), ..tool_call };
+                    let tool_call = if normalize_tool_args { Self::normalize_call(&tool, tool_call) } else { tool_call };
+                    (tool, tool_call)
+                })
         })
     }
+
+    /// the tool with this exact name, otherwise the only MCP tool registered as `<mcp_name>__<name>`,
+    /// for models that drop the prefix. A builtin is never shadowed by a prefixed MCP tool
+    fn find_tool(tools: &[Arc<dyn AnyTool>], name: &str) -> Option<Arc<dyn AnyTool>> {
+        if let Some(tool) = tools.iter().find(|t| t.name() == name) {
+            return Some(tool.clone());
+        }
+        let mut prefixed = tools.iter()
+            .filter(|t| t.group().is_some_and(|mcp_name| t.name() == prefixed_tool_name(mcp_name, name)));
+        match (prefixed.next(), prefixed.next()) {
+            (Some(tool), None) => Some(tool.clone()),
+            _ => None,
+        }
+    }
+
+    /// coerce the mistyped arguments of a call to the tool schema, so the events, the
+    /// interceptors and the tool all see the same parameters
+    fn normalize_call(tool: &Arc<dyn AnyTool>, mut call: ToolCall) -> ToolCall {
+        let (parameters, coercions) = normalize_arguments(&tool.parameters_schema(), call.parameters);
+        for coercion in &coercions {
+            debug!(target: "agent::tool_args", tool = ?call.tool_name, call_id = ?call.tool_call_id, "coerced {}", coercion);
+        }
+        call.parameters = parameters;
+        call
+    }
 }
\ No newline at end of file
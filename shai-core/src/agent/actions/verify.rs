@@ -0,0 +1,103 @@
+use std::sync::atomic::Ordering;
+
+use chrono::Utc;
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+use crate::agent::{AgentCore, AgentError, AgentEvent, InternalAgentEvent, InternalAgentState, VerificationResult};
+use crate::tools::{AnyTool, BashTool, ToolResult};
+
+impl AgentCore {
+    /// Whether the agent stopped after modifying files and a verify command is configured
+    pub(crate) fn needs_verification(&self) -> bool {
+        self.verify.is_some() && self.files_modified.swap(false, Ordering::SeqCst)
+    }
+
+    /// Last step of a turn: verify the changes when needed, otherwise pause
+    pub(crate) async fn pause_or_verify(&mut self) {
+        if self.needs_verification() {
+            self.spawn_verification().await;
+        } else {
+            self.set_state(InternalAgentState::Paused).await;
+        }
+    }
+
+    /// Launch the verify command, it reports back with a VerificationCompleted event
+    pub async fn spawn_verification(&mut self) {
+        let Some(verify) = self.verify.clone() else {
+            self.set_state(InternalAgentState::Paused).await;
+            return;
+        };
+
+        let cancellation_token = CancellationToken::new();
+        let cancel_token_clone = cancellation_token.clone();
+        let tx_clone = self.internal_tx.clone();
+
+        //////////////////////// TOKIO SPAWN
+        tokio::spawn(async move {
+            let bash = BashTool::new();
+            tokio::select! {
+                result = bash.execute_json(json!({ "command": verify.command }), Some(cancel_token_clone.clone())) => {
+                    let _ = tx_clone.send(InternalAgentEvent::VerificationCompleted { result });
+                }
+                _ = cancel_token_clone.cancelled() => {
+                    // Verification was cancelled, no need to send result
+                }
+            }
+        });
+        //////////////////////// TOKIO SPAWN
+
+        self.set_state(InternalAgentState::Processing {
+            task_name: "verify".to_string(),
+            tools_exec_at: Utc::now(),
+            cancellation_token
+        }).await;
+    }
+
+    /// Record the verify outcome, and hand failures back to the agent when auto_fix allows it
+    pub async fn process_verification(&mut self, result: ToolResult) -> Result<(), AgentError> {
+        let Some(verify) = self.verify.clone() else {
+            self.set_state(InternalAgentState::Paused).await;
+            return Ok(());
+        };
+
+        self.verify_attempts += 1;
+        let success = result.is_success();
+        let output = match &result {
+            ToolResult::Success { output, .. } => output.clone(),
+            ToolResult::Error { error, .. } => error.clone(),
+            ToolResult::Denied => String::new(),
+        };
+        debug!(target: "agent::verify", command = ?verify.command, success = success, attempt = self.verify_attempts);
+
+        let _ = self.emit_event(AgentEvent::VerificationCompleted {
+            command: verify.command.clone(),
+            success,
+            output: output.clone(),
+            attempt: self.verify_attempts,
+        }).await;
+
+        self.verification = Some(VerificationResult {
+            command: verify.command.clone(),
+            success,
+            output: output.clone(),
+            attempts: self.verify_attempts,
+        });
+
+        if !success && verify.auto_fix && self.verify_attempts < verify.max_attempts {
+            self.trace.write().await.push(ChatMessage::User {
+                content: ChatMessageContent::Text(format!(
+                    "The verification command `{}` failed after your changes:\n{}\nFix the problems, then stop again once done.",
+                    verify.command, output)),
+                name: None
+            });
+            self.set_state(InternalAgentState::Running).await;
+            return Ok(());
+        }
+
+        self.verify_attempts = 0;
+        self.set_state(InternalAgentState::Paused).await;
+        Ok(())
+    }
+}
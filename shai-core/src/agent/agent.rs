@@ -1,22 +1,32 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::boxed::Box;
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Utc};
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
 use shai_llm::ToolCallMethod;
 use tokio::sync::{mpsc, broadcast, RwLock, oneshot};
+use tokio::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
 use async_trait::async_trait;
-use crate::tools::AnyTool;
+use tokio_util::sync::CancellationToken;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::config::agent::VerifyConfig;
+use crate::config::pricing::{CostEstimate, ModelPricing};
+use crate::tools::{AnyTool, FsOperationLog, TodoStorage, ToolOutputLimit};
 use crate::agent::ClaimManager;
+use crate::agent::ToolCallInterceptor;
+use crate::agent::CommandDenyList;
 
 // Helper functions to make the main loop more readable
 
-use crate::agent::{Brain, InternalAgentEvent};
+use crate::agent::{Brain, InternalAgentEvent, SamplingOverrides, StepToolChoice, SystemPromptOverride};
 use crate::agent::AgentError;
+use crate::agent::PermissionResponse;
 use crate::agent::{AgentRequest, AgentEvent};
 use crate::agent::InternalAgentState;
 use tracing::debug;
 
-use super::protocol::{AgentController, SentCommand};
+use super::protocol::{AgentCheckpoint, AgentController, SentCommand};
 use super::{AgentResponse, AgentEventHandler};
 
 /// Trait defining the public interface for agents
@@ -51,6 +61,104 @@ pub struct AgentResult {
     pub success: bool,
     pub message: String,
     pub trace:   Vec<ChatMessage>,
+    /// Last run of the verify command, None when not configured or no file was modified
+    #[serde(default)]
+    pub verification: Option<VerificationResult>,
+    /// Tokens the LLM used over the whole session
+    #[serde(default)]
+    pub token_usage: TokenTotals,
+    /// Estimated cost of these tokens, None when the agent was built without a model to price
+    #[serde(default)]
+    pub cost: Option<CostEstimate>,
+}
+
+/// Restart the whole task from its original trace when the run fails on an infrastructure error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaskRetryPolicy {
+    pub max_retries: u32,
+    /// delay before the first restart, doubled for each following one
+    pub backoff: Duration,
+}
+
+impl TaskRetryPolicy {
+    const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+    /// delay before the given restart, starting at 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(Self::MAX_BACKOFF)
+    }
+}
+
+/// Fold the older part of the trace into a summary before a brain step once it gets too long
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactionConfig {
+    /// estimated trace size, in tokens, above which the trace is compacted
+    pub max_tokens: usize,
+    /// last messages always kept verbatim
+    pub keep_recent: usize,
+}
+
+/// Outcome of the verify command run after the agent modified files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationResult {
+    pub command: String,
+    pub success: bool,
+    pub output:  String,
+    /// Runs during the turn, more than one when failures were sent back to the agent to fix
+    pub attempts: u32,
+}
+
+/// Tokens the LLM used since the agent started, summed over every brain step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenTotals {
+    pub input_tokens:  u64,
+    pub output_tokens: u64,
+}
+
+impl TokenTotals {
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Calls of one tool that ran and the time they took
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolTime {
+    pub calls:    u64,
+    pub total_ms: u64,
+}
+
+/// Time spent running tools since the agent started, by tool. Calls that did not run
+/// (denied, cancelled, repeated) are not counted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolTimeTotals {
+    pub by_tool: BTreeMap<String, ToolTime>,
+}
+
+impl ToolTimeTotals {
+    pub fn record(&mut self, tool: &str, duration: Duration) {
+        let time = self.by_tool.entry(tool.to_string()).or_default();
+        time.calls += 1;
+        time.total_ms += duration.as_millis() as u64;
+    }
+
+    pub fn total(&self) -> ToolTime {
+        self.by_tool.values().fold(ToolTime::default(), |total, time| ToolTime {
+            calls: total.calls + time.calls,
+            total_ms: total.total_ms + time.total_ms,
+        })
+    }
+}
+
+/// Outcome of running the configured formatter on one file the agent wrote
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormatResult {
+    pub file:    String,
+    pub command: String,
+    pub success: bool,
+    /// formatter error output, empty on success
+    pub output:  String,
 }
 
 /// Core agent implementation that orchestrates any Thinker implementation
@@ -63,13 +171,86 @@ pub struct AgentCore {
     /// big brain
     pub brain: Arc<RwLock<Box<dyn Brain>>>,
     pub method: ToolCallMethod,
+    /// constraint on the tool calls of the next brain step only, back to auto once used
+    pub tool_choice: StepToolChoice,
+    /// sampling parameters of every brain step until replaced, set per request by API callers
+    pub sampling: SamplingOverrides,
+    /// instructions of every brain step until replaced, set per request by API callers
+    pub system_prompt: Option<SystemPromptOverride>,
 
     /// agent state (manipulated by main looper + brain/tool coroutines)
     pub trace:           Arc<RwLock<Vec<ChatMessage>>>,
     pub available_tools: Vec<Arc<dyn AnyTool>>,
     pub permissions:     Arc<RwLock<ClaimManager>>,
+    pub todos:           Option<Arc<TodoStorage>>,
     pub state:           InternalAgentState,
 
+    /// wall-clock limit for the whole run, measured from the start of the main loop
+    pub max_duration:       Option<Duration>,
+    pub started_at:         Option<Instant>,
+    pub completion_message: Option<String>,
+    /// error of the last brain step, a run without controller cannot recover from it and returns it
+    pub brain_error:        Option<AgentError>,
+
+    /// permission requests nobody answers resolve to `permission_timeout_response` after this delay
+    pub permission_timeout:          Option<Duration>,
+    pub permission_timeout_response: PermissionResponse,
+
+    /// whole-task retry on infrastructure failures, None never retries
+    pub task_retry: Option<TaskRetryPolicy>,
+    /// trace compaction before each brain step, None lets the trace grow
+    pub compaction: Option<CompactionConfig>,
+    /// tool runs allowed between two user inputs, and the runs since the last one
+    pub max_steps: u32,
+    pub steps:     u32,
+    /// tokens used so far, and the total above which the agent stops, None never stops
+    pub token_usage:  TokenTotals,
+    pub token_budget: Option<u64>,
+    /// usage the budget counts from, moved up when user input follows a spent budget
+    pub budget_start: u64,
+    /// model the tokens are priced with, None estimates no cost
+    pub pricing: Option<ModelPricing>,
+    /// identical tool calls allowed between two user inputs, and the times each call ran since the last one
+    pub repeat_limit: Option<u32>,
+    pub call_counts: Arc<Mutex<HashMap<u64, u32>>>,
+    /// tool calls of a batch running at once, read-only calls get more
+    pub max_parallel_tools: usize,
+    /// false runs the tool calls of a batch one after the other, in the order the model made them
+    pub parallel_tool_calls: bool,
+    /// token of each tool call still running, by tool_call_id, to cancel one call of the batch
+    pub running_tools: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    /// time spent in the tool calls that ran
+    pub tool_time: Arc<Mutex<ToolTimeTotals>>,
+
+    /// policy hooks run on every tool call before the permission check
+    pub interceptors: Vec<Arc<dyn ToolCallInterceptor>>,
+    /// bash commands refused after the interceptors, whatever the permissions
+    pub command_deny_list: Arc<CommandDenyList>,
+    /// tools that write or use the network return their preview instead of running
+    pub dry_run: bool,
+    /// calls that would write are refused, on top of the toolbox being read-only
+    pub read_only: bool,
+    /// coerce mistyped tool arguments to the tool schema before anything looks at them
+    pub normalize_tool_args: bool,
+    /// size of the tool outputs that go into the trace, the middle of longer ones is cut
+    pub tool_output_limit: Arc<ToolOutputLimit>,
+    /// emit BrainDelta events while the brain generates its message
+    pub stream_deltas: bool,
+
+    /// optional verify command, run when the agent stops after a write-capable tool succeeded
+    pub verify:         Option<VerifyConfig>,
+    pub verification:   Option<VerificationResult>,
+    pub verify_attempts: u32,
+    pub files_modified: Arc<AtomicBool>,
+    /// ask the model to check its changes with its tools before it stops, and the prompts given this turn
+    pub verify_before_complete: bool,
+    pub self_check_prompts: u32,
+
+    /// formatter command per file extension, run on the files written (per fs_log) when the agent stops
+    pub formatters:   HashMap<String, String>,
+    pub fs_log:       Option<Arc<FsOperationLog>>,
+    pub formatted_at: DateTime<Utc>,
+
     /// internal event
     pub internal_tx: broadcast::Sender<InternalAgentEvent>,   // event may be produced from many part of the agent
     pub internal_rx: broadcast::Receiver<InternalAgentEvent>, // events are mostly consumed by the main event loop, but also in spawn tool to monitor permissions
@@ -89,6 +270,7 @@ impl AgentCore {
         trace: Vec<ChatMessage>,
         available_tools: Vec<Box<dyn AnyTool>>,
         permissions: ClaimManager,
+        todos: Option<Arc<TodoStorage>>,
     ) -> Self {
         let (internal_tx, internal_rx) = broadcast::channel(1024);
         Self {
@@ -101,10 +283,50 @@ impl AgentCore {
             },
             brain: Arc::new(RwLock::new(brain)),
             method: ToolCallMethod::FunctionCall,
+            tool_choice: StepToolChoice::default(),
+            sampling: SamplingOverrides::default(),
+            system_prompt: None,
             trace: Arc::new(RwLock::new(trace)),
             available_tools: available_tools.into_iter().map(|t| Arc::from(t) as Arc<dyn AnyTool>).collect(),
             permissions: Arc::new(RwLock::new(permissions)),
+            todos,
             state: InternalAgentState::Starting,
+            max_duration: None,
+            started_at: None,
+            completion_message: None,
+            brain_error: None,
+            permission_timeout: None,
+            permission_timeout_response: PermissionResponse::Deny,
+            task_retry: None,
+            compaction: None,
+            max_steps: super::builder::DEFAULT_MAX_STEPS,
+            steps: 0,
+            token_usage: TokenTotals::default(),
+            token_budget: None,
+            budget_start: 0,
+            pricing: None,
+            repeat_limit: Some(super::builder::DEFAULT_REPEAT_LIMIT),
+            call_counts: Arc::new(Mutex::new(HashMap::new())),
+            max_parallel_tools: super::builder::DEFAULT_MAX_PARALLEL_TOOLS,
+            parallel_tool_calls: true,
+            running_tools: Arc::new(Mutex::new(HashMap::new())),
+            tool_time: Arc::new(Mutex::new(ToolTimeTotals::default())),
+            interceptors: Vec::new(),
+            command_deny_list: Arc::new(CommandDenyList::defaults()),
+            dry_run: false,
+            read_only: false,
+            normalize_tool_args: true,
+            tool_output_limit: Arc::new(ToolOutputLimit::default()),
+            stream_deltas: false,
+            verify: None,
+            verification: None,
+            verify_attempts: 0,
+            files_modified: Arc::new(AtomicBool::new(false)),
+            verify_before_complete: false,
+            self_check_prompts: 0,
+            formatters: HashMap::new(),
+            fs_log: None,
+            formatted_at: Utc::now(),
             internal_tx,
             internal_rx,
         }
@@ -134,7 +356,29 @@ impl AgentCore {
 impl Agent for AgentCore {
     /// Start the agent execution (blocking until completion)
     async fn run(&mut self) -> Result<AgentResult, AgentError> {
-        self.start().await
+        let initial_trace = self.trace.read().await.clone();
+        let mut attempt = 0;
+        loop {
+            let error = match self.start().await {
+                Err(error) if error.is_retryable() => error,
+                result => return result,
+            };
+            let Some(policy) = self.task_retry.filter(|policy| attempt < policy.max_retries) else {
+                return Err(error);
+            };
+
+            attempt += 1;
+            let delay = policy.delay(attempt);
+            debug!(target: "agent::retry", attempt, ?delay, %error, "restarting the task");
+            let _ = self.emit_event(AgentEvent::TaskRestarting {
+                attempt,
+                max_retries: policy.max_retries,
+                delay,
+                error: error.to_string(),
+            }).await;
+            tokio::time::sleep(delay).await;
+            self.reset_for_restart(initial_trace.clone()).await;
+        }
     }
     
     /// Get a controller to send commands to the agent
@@ -274,12 +518,71 @@ impl AgentCore {
         }
     }
     
+    /// Instant at which the run exceeds max_duration, if any
+    fn deadline(&self) -> Option<Instant> {
+        Some(self.started_at? + self.max_duration?)
+    }
+
+    /// Estimated cost of the tokens used so far, None without a model to price
+    pub(crate) fn cost_estimate(&self) -> Option<CostEstimate> {
+        self.pricing.as_ref().map(|pricing| pricing.estimate(&self.token_usage))
+    }
+
+    /// Tokens counted against the budget: the usage since it was last re-armed
+    pub(crate) fn budget_used(&self) -> u64 {
+        self.token_usage.total().saturating_sub(self.budget_start)
+    }
+
+    /// The user goes on after the budget stopped the agent, it gets a new budget from here
+    fn rearm_token_budget(&mut self) {
+        if self.token_budget.is_some_and(|limit| self.budget_used() > limit) {
+            debug!(target: "agent::tokens", used = self.budget_used(), "token budget re-armed by user input");
+            self.budget_start = self.token_usage.total();
+        }
+    }
+
+    /// Cancel whatever is running (brain step or tools) and stop the agent
+    async fn handle_time_limit(&mut self) {
+        let limit = self.max_duration.unwrap_or_default();
+        debug!(target: "agent::loop", ?limit, "time limit reached");
+
+        let _ = self.emit_event(AgentEvent::TimeLimitReached { limit }).await;
+        let _ = self.handle_event(InternalAgentEvent::CancelTask).await;
+        self.completion_message = Some(format!("time limit reached ({}s)", limit.as_secs()));
+        self.set_state(InternalAgentState::Completed { success: false }).await;
+    }
+
+    /// Go back to the state the run started from, keeping started_at so max_duration covers every attempt
+    async fn reset_for_restart(&mut self, trace: Vec<ChatMessage>) {
+        *self.trace.write().await = trace;
+        self.brain_error = None;
+        self.completion_message = None;
+        self.verification = None;
+        self.verify_attempts = 0;
+        self.steps = 0;
+        self.self_check_prompts = 0;
+        self.call_counts.lock().unwrap().clear();
+        self.files_modified.store(false, Ordering::SeqCst);
+        self.set_state(InternalAgentState::Starting).await;
+    }
+
     /// Main execution loop with single command receiver
     async fn start(&mut self) -> Result<AgentResult, AgentError> {
+        self.started_at.get_or_insert_with(Instant::now);
         self.handle_event(InternalAgentEvent::AgentInitialized).await?;
         
         loop {
+            let terminal = matches!(self.state, InternalAgentState::Completed { .. } | InternalAgentState::Failed { .. });
+            if !terminal && self.deadline().is_some_and(|deadline| Instant::now() >= deadline) {
+                self.handle_time_limit().await;
+            }
+
             if matches!(self.state, InternalAgentState::Paused) && !self.has_io() {
+                if let Some(error) = self.brain_error.take() {
+                    debug!(target: "agent::loop", "state is paused on a brain error and has no more controller, failing");
+                    self.set_state(InternalAgentState::Failed { error: error.to_string() }).await;
+                    return Err(error);
+                }
                 debug!(target: "agent::loop", "state is paused but has no more controller, moving to completion");
                 self.set_state(InternalAgentState::Completed { success: true }).await;
             }
@@ -292,8 +595,11 @@ impl AgentCore {
                     let guard = trace.read().await;
                     return Ok(AgentResult {
                         success: success.clone(),
-                        message: "Agent completed".to_string(),
+                        message: self.completion_message.clone().unwrap_or_else(|| "Agent completed".to_string()),
                         trace: guard.clone(),
+                        verification: self.verification.clone(),
+                        token_usage: self.token_usage,
+                        cost: self.cost_estimate(),
                     });
                 },
                 InternalAgentState::Failed { error } => {
@@ -322,7 +628,18 @@ impl AgentCore {
 
             ///////////// MAIN LOOP SLEEPER - LISTEN FOR COMMAND AND INTERNAL EVENTS
             debug!(target: "agent::loop", status = ?self.state.to_public(), "Entering event loop");
+            let deadline = self.deadline();
             tokio::select! {
+                // wake up when the time limit is reached, even if a brain step or tool is running
+                _ = async {
+                    match deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await
+                    }
+                } => {
+                    self.handle_time_limit().await;
+                }
+
                 // only listen to command if there's a controller
                 command_result = async {
                     match &mut self.socket.rx_command {
@@ -371,6 +688,21 @@ impl AgentCore {
                 let trace = self.trace.read().await.clone();
                 Ok(AgentResponse::Trace { trace })
             }
+            AgentRequest::GetTokenUsage => {
+                Ok(AgentResponse::TokenUsage { usage: self.token_usage, budget: self.token_budget, cost: self.cost_estimate() })
+            }
+            AgentRequest::GetToolTime => {
+                Ok(AgentResponse::ToolTime { totals: self.tool_time.lock().unwrap().clone() })
+            }
+            AgentRequest::Checkpoint => {
+                let checkpoint = AgentCheckpoint {
+                    trace: self.trace.read().await.clone(),
+                    state: self.state.to_public(),
+                    token_usage: self.token_usage,
+                    taken_at: Utc::now(),
+                };
+                Ok(AgentResponse::Checkpoint { checkpoint })
+            }
             AgentRequest::Sudo(operation) => {
                 let mut guard = self.permissions.write().await;
                 match operation {
@@ -395,12 +727,43 @@ impl AgentCore {
                     Ok(AgentResponse::Ack)
                 })
             }
+            AgentRequest::CancelTool { tool_call_id } => {
+                // the tool ends with a cancelled result, the other calls of the batch keep running
+                match self.running_tools.lock().unwrap().get(&tool_call_id) {
+                    Some(token) => {
+                        debug!(target: "agent::command", tool_call_id = ?tool_call_id, "cancelling tool call");
+                        token.cancel();
+                        Ok(AgentResponse::Ack)
+                    }
+                    None => Err(AgentError::InvalidState(format!("no tool call {} is running", tool_call_id))),
+                }
+            }
             AgentRequest::SwitchToolCallMethod { method } => {
                 if let Some(method) = method {
                     self.method = method;   
                 }
                 Ok(AgentResponse::Method { method: self.method })
             }
+            AgentRequest::ForceToolChoice { choice } => {
+                debug!(target: "agent::command", choice = ?choice, "tool choice of the next step");
+                self.tool_choice = choice;
+                Ok(AgentResponse::Ack)
+            }
+            AgentRequest::SetSampling { sampling } => {
+                debug!(target: "agent::command", sampling = ?sampling, "sampling of the next steps");
+                self.sampling = sampling;
+                Ok(AgentResponse::Ack)
+            }
+            AgentRequest::SetSystemPrompt { system_prompt } => {
+                debug!(target: "agent::command", system_prompt = ?system_prompt, "system prompt of the next steps");
+                self.system_prompt = system_prompt;
+                Ok(AgentResponse::Ack)
+            }
+            AgentRequest::SetParallelToolCalls { parallel } => {
+                debug!(target: "agent::command", parallel = parallel, "tool calls of the next batches in parallel");
+                self.parallel_tool_calls = parallel;
+                Ok(AgentResponse::Ack)
+            }
             AgentRequest::SendUserInput{ input } => {
                 self.handle_event(InternalAgentEvent::CancelTask).await
                 .and({
@@ -413,6 +776,10 @@ impl AgentCore {
                         content: ChatMessageContent::Text(input),
                         name: None
                     });
+                    self.steps = 0;
+                    self.self_check_prompts = 0;
+                    self.call_counts.lock().unwrap().clear();
+                    self.rearm_token_budget();
 
                     self.set_state(InternalAgentState::Running).await;
                     Ok(AgentResponse::Ack)
@@ -423,6 +790,10 @@ impl AgentCore {
                 .and({
                     // Add all messages to trace at once
                     self.trace.write().await.extend(messages);
+                    self.steps = 0;
+                    self.self_check_prompts = 0;
+                    self.call_counts.lock().unwrap().clear();
+                    self.rearm_token_budget();
 
                     self.set_state(InternalAgentState::Running).await;
                     Ok(AgentResponse::Ack)
@@ -444,6 +815,12 @@ impl AgentCore {
                 }).map_err(|_| AgentError::SessionClosed)?;
                 Ok(AgentResponse::Ack)
             }
+            AgentRequest::GetTodos
+            | AgentRequest::AddTodo { .. }
+            | AgentRequest::SetTodoStatus { .. }
+            | AgentRequest::RemoveTodo { .. } => {
+                self.handle_todo_command(command).await
+            }
             AgentRequest::WaitTurn => {
                 self.handle_wait_turn(backchannel).await;
                 return Ok(()); // We handle the response in the spawned task
@@ -456,6 +833,34 @@ impl AgentCore {
     }
 
 
+    /// Apply a todo command from a controller. The model sees the change on its next step
+    /// since the todo list is rendered into the system prompt.
+    async fn handle_todo_command(&mut self, command: AgentRequest) -> Result<AgentResponse, AgentError> {
+        let storage = self.todos.clone()
+            .ok_or_else(|| AgentError::ExecutionError("this agent has no todo list".to_string()))?;
+
+        match command {
+            AgentRequest::AddTodo { content } => {
+                storage.add(content).await;
+            }
+            AgentRequest::SetTodoStatus { id, status } => {
+                storage.set_status(&id, status).await
+                    .ok_or_else(|| AgentError::ExecutionError(format!("no todo with id {}", id)))?;
+            }
+            AgentRequest::RemoveTodo { id } => {
+                storage.remove(&id).await
+                    .ok_or_else(|| AgentError::ExecutionError(format!("no todo with id {}", id)))?;
+            }
+            _ => {
+                return Ok(AgentResponse::Todos { todos: storage.get_all().await });
+            }
+        }
+
+        let todos = storage.get_all().await;
+        let _ = self.emit_event(AgentEvent::TodoUpdated { todos: todos.clone() }).await;
+        Ok(AgentResponse::Todos { todos })
+    }
+
     /// Handle an event
     async fn handle_event(&mut self, event: InternalAgentEvent) -> Result<(), AgentError> {
         debug!(target: "agent::internal_event", event = ?event);
@@ -1,18 +1,119 @@
 use std::sync::Arc;
 use async_trait::async_trait;
-use openai_dive::v1::resources::chat::ChatMessage;
+use openai_dive::v1::resources::chat::{ChatCompletionToolChoice, ChatCompletionToolChoiceFunction, ChatCompletionToolChoiceFunctionName, ChatCompletionToolType, ChatMessage};
 use shai_llm::ToolCallMethod;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+use tokio_util::sync::CancellationToken;
 
 use crate::tools::types::AnyToolBox;
 use super::error::AgentError;
+use super::events::AgentEvent;
 
 
 /// ThinkerContext is the agent internal state
 pub struct ThinkerContext {
     pub trace:           Arc<RwLock<Vec<ChatMessage>>>,
     pub available_tools: AnyToolBox,
-    pub method:          ToolCallMethod
+    pub method:          ToolCallMethod,
+    /// tool calls the LLM is held to for this step
+    pub tool_choice:     StepToolChoice,
+    /// sampling parameters of the request being handled, over the brain's own
+    pub sampling:        SamplingOverrides,
+    /// instructions of the request being handled, added to or in place of the brain's system prompt
+    pub system_prompt:   Option<SystemPromptOverride>,
+    /// set when the agent streams deltas, a brain that can stream reports its output here as it comes
+    pub deltas:          Option<BrainDeltaSink>
+}
+
+/// Constraint on the tool calls of one step, for models that answer in prose instead of acting
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepToolChoice {
+    /// the step must call at least one tool
+    pub required: bool,
+    /// only these tools are offered for the step, every available tool when None
+    pub allowed_tools: Option<Vec<String>>,
+}
+
+impl StepToolChoice {
+    /// at least one call, to any available tool
+    pub fn required() -> Self {
+        Self { required: true, allowed_tools: None }
+    }
+
+    /// only these tools are offered, the model may still answer without calling them
+    pub fn only<S: Into<String>>(tools: impl IntoIterator<Item = S>) -> Self {
+        Self { required: false, allowed_tools: Some(tools.into_iter().map(Into::into).collect()) }
+    }
+
+    pub fn is_auto(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// whether the step offers this tool
+    pub fn allows(&self, tool: &str) -> bool {
+        self.allowed_tools.as_ref().map_or(true, |tools| tools.iter().any(|t| t == tool))
+    }
+
+    /// `tool_choice` of the LLM request: `required`, or the function itself when a single tool is
+    /// allowed. None keeps the provider's default
+    pub fn llm_tool_choice(&self) -> Option<ChatCompletionToolChoice> {
+        if !self.required {
+            return None;
+        }
+        Some(match self.allowed_tools.as_deref() {
+            Some([tool]) => ChatCompletionToolChoice::ChatCompletionToolChoiceFunction(ChatCompletionToolChoiceFunction {
+                r#type: Some(ChatCompletionToolType::Function),
+                function: ChatCompletionToolChoiceFunctionName { name: tool.clone() },
+            }),
+            _ => ChatCompletionToolChoice::Required,
+        })
+    }
+}
+
+/// Sampling parameters a caller sets for its request, None keeps the value of the brain
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SamplingOverrides {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+}
+
+impl SamplingOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Instructions a caller sets for its request. Either way the brain still adds what it
+/// appends to its own prompt (todo status, tool documentation...)
+#[derive(Debug, Clone, PartialEq)]
+pub enum SystemPromptOverride {
+    /// added after the brain's system prompt
+    Append(String),
+    /// used as the system prompt template, placeholders like {{CODER_ENV}} still render
+    Replace(String),
+}
+
+/// Handle a brain uses to report the text of its next message before the message is complete.
+/// Deltas go straight to the public event bus: they are many, and on the internal bus they could
+/// push out the events the agent needs to move on
+#[derive(Clone)]
+pub struct BrainDeltaSink {
+    tx: broadcast::Sender<AgentEvent>,
+    cancelled: CancellationToken,
+}
+
+impl BrainDeltaSink {
+    /// Sink of a step, deltas are dropped once `cancelled` is
+    pub fn new(tx: broadcast::Sender<AgentEvent>, cancelled: CancellationToken) -> Self {
+        Self { tx, cancelled }
+    }
+
+    pub fn emit(&self, text: String, reasoning: bool) {
+        if !text.is_empty() && !self.cancelled.is_cancelled() {
+            let _ = self.tx.send(AgentEvent::BrainDelta { text, reasoning });
+        }
+    }
 }
 
 /// ThinkerFlowControl drives the agentic flow
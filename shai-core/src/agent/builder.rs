@@ -1,17 +1,38 @@
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
-use shai_llm::LlmClient;
+use shai_llm::{LlmClient, ToolCallMethod};
 use uuid::Uuid;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::tools::mcp::mcp_oauth::signin_oauth;
-use crate::tools::{create_mcp_client, get_mcp_tools, AnyTool, BashTool, EditTool, FetchTool, FindTool, FsOperationLog, LsTool, McpConfig, MultiEditTool, ReadTool, TodoReadTool, TodoStorage, TodoWriteTool, WriteTool};
-use crate::config::agent::AgentConfig;
-use crate::config::config::ShaiConfig;
+use crate::tools::{create_mcp_client, get_mcp_tools, prefixed_tool_name, AnyTool, ApplyPatchTool, BashJobs, BashStatusTool, BashTool, EditTool, EnvTool, FetchTool, FindTool, FsOperationLog, GitTool, GrepTool, LsTool, McpConfig, MultiEditTool, ReadTool, SemanticSearchTool, TodoReadTool, TodoStorage, TodoWriteTool, ToolOutputLimit, UndoTool, WriteTool};
+use crate::config::agent::{AgentConfig, VerifyConfig, ALL_BUILTIN_TOOLS};
+use crate::config::config::{resolve_env_vars, resolve_model, ShaiConfig, ToolFilter};
+use crate::config::pricing::ModelPricing;
 use crate::runners::coder::CoderBrain;
-use super::Brain;
+use crate::runners::planner::PlannerBrain;
+use super::{Brain, SamplingOverrides, StepToolChoice, SystemPromptOverride};
 use super::AgentCore;
 use super::claims::ClaimManager;
 use super::AgentError;
+use super::PermissionResponse;
+use super::ToolCallInterceptor;
+use super::CommandDenyList;
+use super::TaskRetryPolicy;
+use super::CompactionConfig;
+
+/// How long a permission request waits for an answer before resolving to the default response
+pub const DEFAULT_PERMISSION_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How many times the agent may run tools between two user messages
+pub const DEFAULT_MAX_STEPS: u32 = 50;
+
+/// How many times the same tool call may run between two user messages, read-only tools get more
+pub const DEFAULT_REPEAT_LIMIT: u32 = 3;
+
+/// How many tool calls of a batch may run at once, read-only calls get more
+pub const DEFAULT_MAX_PARALLEL_TOOLS: usize = 4;
 
 /// Builder for AgentCore
 pub struct AgentBuilder {
@@ -21,6 +42,34 @@ pub struct AgentBuilder {
     pub trace: Vec<ChatMessage>,
     pub available_tools: Vec<Box<dyn AnyTool>>,
     pub permissions: ClaimManager,
+    pub todos: Option<Arc<TodoStorage>>,
+    pub max_duration: Option<Duration>,
+    pub permission_timeout: Option<Duration>,
+    pub permission_timeout_response: PermissionResponse,
+    pub verify: Option<VerifyConfig>,
+    pub verify_before_complete: bool,
+    pub interceptors: Vec<Arc<dyn ToolCallInterceptor>>,
+    pub command_deny_list: CommandDenyList,
+    pub dry_run: bool,
+    pub read_only: bool,
+    pub normalize_tool_args: bool,
+    pub tool_output_limit: ToolOutputLimit,
+    pub stream_deltas: bool,
+    pub task_retry: Option<TaskRetryPolicy>,
+    pub compaction: Option<CompactionConfig>,
+    pub max_steps: u32,
+    pub repeat_limit: Option<u32>,
+    pub max_parallel_tools: usize,
+    pub parallel_tool_calls: bool,
+    pub token_budget: Option<u64>,
+    pub pricing: Option<ModelPricing>,
+    pub tool_filter: ToolFilter,
+    pub tool_method: ToolCallMethod,
+    pub tool_choice: StepToolChoice,
+    pub sampling: SamplingOverrides,
+    pub system_prompt: Option<SystemPromptOverride>,
+    pub formatters: HashMap<String, String>,
+    pub fs_log: Option<Arc<FsOperationLog>>,
 }
 
 impl AgentBuilder {
@@ -40,21 +89,58 @@ impl AgentBuilder {
 
     /// Create a default AgentBuilder using ShaiConfig LLM and default tools
     pub async fn default() -> Result<Self, AgentError> {
+        Self::from_profile(None).await
+    }
+
+    /// Default agent on the LLM of a named ShaiConfig profile, None behaves like `default`
+    pub async fn from_profile(profile: Option<&str>) -> Result<Self, AgentError> {
         // Get LLM from ShaiConfig
-        let (llm_client, model) = ShaiConfig::get_llm().await
+        let (llm_client, model) = ShaiConfig::get_llm_with_profile(profile).await
             .map_err(|e| AgentError::ConfigurationError(format!("Failed to get LLM from config: {}", e)))?;
 
-        // Create default brain
-        let brain = Box::new(CoderBrain::new(Arc::new(llm_client), model));
+        Ok(Self::from_llm(llm_client, model).await.tool_method(ShaiConfig::load_tool_method(profile)))
+    }
+
+    /// Default agent on the given LLM: coder brain, builtin tools and the tools of the MCP servers
+    /// of ShaiConfig (auth.config and the project .shai.config)
+    pub async fn from_llm(llm_client: LlmClient, model: String) -> Self {
+        Self::from_shared_llm(Arc::new(llm_client), model).await
+    }
+
+    /// Same as `from_llm`, for a client the caller keeps using on the side
+    pub async fn from_shared_llm(llm_client: Arc<LlmClient>, model: String) -> Self {
+        let pricing = ModelPricing::from_config(&model);
+        let brain = Box::new(CoderBrain::new(llm_client, model));
+
+        let todo_storage = Arc::new(TodoStorage::new());
+        let mut tools = Self::create_default_tools(todo_storage.clone());
+        let mcp_configs = ShaiConfig::load().map(|config| config.mcp_configs).unwrap_or_default();
+        tools.extend(Self::config_mcp_tools(mcp_configs).await);
 
-        // Create default toolbox (using ToolConfig from shai-cli)
-        // For now, create basic tools - we can expand this later
-        let tools = Self::create_default_tools();
+        Self::with_brain(brain).user_settings().tools(tools).todos(todo_storage).pricing(pricing)
+    }
 
-        Ok(Self::with_brain(brain).tools(tools))
+    /// Tools of the MCP servers of ShaiConfig. Unlike agent configs there is no OAuth flow here,
+    /// the stored token is used as is and a server that fails is skipped with a warning
+    async fn config_mcp_tools(mcp_configs: HashMap<String, McpConfig>) -> Vec<Box<dyn AnyTool>> {
+        let mut servers: Vec<_> = mcp_configs.into_iter().collect();
+        servers.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut tools = Vec::new();
+        for (mcp_name, mcp_config) in servers {
+            match get_mcp_tools(create_mcp_client(mcp_config), &mcp_name, false).await {
+                Ok(mcp_tools) => {
+                    eprintln!("\x1b[2m░ mcp({}): {}\x1b[0m", mcp_name, mcp_tools.iter().map(|t| t.name()).collect::<Vec<_>>().join(", "));
+                    tools.extend(mcp_tools);
+                }
+                Err(e) => eprintln!("\x1b[2m⚠ MCP '{}' failed to get tools: {}. Skipping.\x1b[0m", mcp_name, e),
+            }
+        }
+        tools
     }
 
-    /// Create AgentBuilder with a specific brain
+    /// Create AgentBuilder with a specific brain, with the built-in defaults and nothing read
+    /// from the config files (see `user_settings`)
     pub fn with_brain(brain: Box<dyn Brain>) -> Self {
         Self {
             session_id: Uuid::new_v4().to_string(),
@@ -63,26 +149,63 @@ impl AgentBuilder {
             trace: vec![],
             available_tools: vec![],
             permissions: ClaimManager::new(),
+            todos: None,
+            max_duration: None,
+            permission_timeout: Some(DEFAULT_PERMISSION_TIMEOUT),
+            permission_timeout_response: PermissionResponse::Deny,
+            verify: None,
+            verify_before_complete: false,
+            interceptors: Vec::new(),
+            command_deny_list: CommandDenyList::defaults(),
+            dry_run: false,
+            read_only: false,
+            normalize_tool_args: true,
+            tool_output_limit: ToolOutputLimit::default(),
+            stream_deltas: false,
+            task_retry: None,
+            compaction: None,
+            max_steps: DEFAULT_MAX_STEPS,
+            repeat_limit: Some(DEFAULT_REPEAT_LIMIT),
+            max_parallel_tools: DEFAULT_MAX_PARALLEL_TOOLS,
+            parallel_tool_calls: true,
+            token_budget: None,
+            pricing: None,
+            tool_filter: ToolFilter::default(),
+            tool_method: ToolCallMethod::FunctionCall,
+            tool_choice: StepToolChoice::default(),
+            sampling: SamplingOverrides::default(),
+            system_prompt: None,
+            formatters: HashMap::new(),
+            fs_log: None,
         }
     }
 
     /// Create default set of tools
-    fn create_default_tools() -> Vec<Box<dyn AnyTool>> {
-        let fs_log = Arc::new(FsOperationLog::new());
-        let todo_storage = Arc::new(TodoStorage::new());
+    pub fn create_default_tools(todo_storage: Arc<TodoStorage>) -> Vec<Box<dyn AnyTool>> {
+        let fs_log = Arc::new(FsOperationLog::persistent());
+        let bash_jobs = Arc::new(BashJobs::new());
 
-        vec![
-            Box::new(BashTool::new()),
+        let mut tools: Vec<Box<dyn AnyTool>> = vec![
+            Box::new(BashTool::with_config(ShaiConfig::load_bash()).with_jobs(bash_jobs.clone())),
+            Box::new(BashStatusTool::new(bash_jobs)),
             Box::new(EditTool::new(fs_log.clone())),
+            Box::new(EnvTool::with_shell(ShaiConfig::load_bash().shell_program())),
             Box::new(MultiEditTool::new(fs_log.clone())),
-            Box::new(FetchTool::new()),
+            Box::new(ApplyPatchTool::new(fs_log.clone())),
+            Box::new(FetchTool::with_max_bytes(ShaiConfig::load_fetch_max_bytes())),
             Box::new(FindTool::new()),
+            Box::new(GrepTool::new()),
             Box::new(LsTool::new()),
-            Box::new(ReadTool::new(fs_log.clone())),
+            Box::new(ReadTool::with_max_bytes(fs_log.clone(), ShaiConfig::load_read_max_bytes())),
             Box::new(TodoReadTool::new(todo_storage.clone())),
             Box::new(TodoWriteTool::new(todo_storage.clone())),
+            Box::new(UndoTool::new(fs_log.clone())),
             Box::new(WriteTool::new(fs_log)),
-        ]
+        ];
+        if ShaiConfig::load_git_tool() {
+            tools.push(Box::new(GitTool::new()));
+        }
+        tools
     }
 }
 
@@ -117,6 +240,215 @@ impl AgentBuilder {
         self
     }
 
+    /// Use the "always allow" rules saved in ~/.config/shai/permissions.json, and save new ones there
+    pub fn persistent_permissions(mut self) -> Self {
+        let sudo = self.permissions.is_sudo();
+        self.permissions = ClaimManager::persistent();
+        if sudo {
+            self.permissions.sudo();
+        }
+        self
+    }
+
+    /// Share the todo list backing the todo tools, so controllers can edit it
+    pub fn todos(mut self, todos: Arc<TodoStorage>) -> Self {
+        self.todos = Some(todos);
+        self
+    }
+
+    /// Stop the agent with `Completed { success: false }` once it has run for this long,
+    /// cancelling any brain step or tool in flight
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Resolve unanswered permission requests after this delay, None waits forever
+    pub fn permission_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.permission_timeout = timeout;
+        self
+    }
+
+    /// Response applied when a permission request times out, Deny by default
+    pub fn permission_timeout_response(mut self, response: PermissionResponse) -> Self {
+        self.permission_timeout_response = response;
+        self
+    }
+
+    /// Inspect, veto or rewrite every tool call before the permission check, in registration order
+    pub fn interceptor(mut self, interceptor: impl ToolCallInterceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Plan-only run: tools that write or use the network are not executed, the model gets their
+    /// preview instead (or a note that they were not run), so nothing is changed
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Keep only the tools that cannot change anything: built-in tools that never write and MCP
+    /// tools their server annotates as read-only. Calls of the kept tools that would still write,
+    /// e.g. a fetch POST, are refused
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Make the brain plan before acting, see `PlannerBrain`. The plan is read from the todo list
+    /// shared with `todos`, without it the brain is left as is
+    pub fn planned(mut self) -> Self {
+        match self.todos.clone() {
+            Some(todos) => self.brain = Box::new(PlannerBrain::new(self.brain, todos)),
+            None => eprintln!("\x1b[2m⚠ no todo list to plan with, the agent runs without planning\x1b[0m"),
+        }
+        self
+    }
+
+    /// Tool filter, bash deny-list and tool output limit of the user's config file
+    pub fn user_settings(self) -> Self {
+        self.tool_filter(ShaiConfig::load_tool_filter())
+            .command_deny_list(CommandDenyList::new(&ShaiConfig::load_bash_deny_list()))
+            .tool_output_limit(ShaiConfig::load_tool_output_limit())
+    }
+
+    /// Bash commands refused even in sudo mode, the default deny-list unless set
+    pub fn command_deny_list(mut self, deny_list: CommandDenyList) -> Self {
+        self.command_deny_list = deny_list;
+        self
+    }
+
+    /// Coerce tool arguments sent with the wrong type to the tool schema (on by default),
+    /// so a "5" for an integer doesn't cost the model a turn
+    pub fn normalize_tool_args(mut self, enabled: bool) -> Self {
+        self.normalize_tool_args = enabled;
+        self
+    }
+
+    /// Cut the middle of the tool outputs longer than the limit of their tool before they go into
+    /// the trace, keeping their start and end; the original size is in the `output_bytes` metadata
+    pub fn tool_output_limit(mut self, limit: ToolOutputLimit) -> Self {
+        self.tool_output_limit = limit;
+        self
+    }
+
+    /// Emit BrainDelta events with the reasoning and answer text while the model generates them,
+    /// for clients that render the output live. Brains that can't stream only emit BrainResult
+    pub fn stream_deltas(mut self, enabled: bool) -> Self {
+        self.stream_deltas = enabled;
+        self
+    }
+
+    /// Run this check whenever the agent stops after modifying files
+    pub fn verify(mut self, verify: VerifyConfig) -> Self {
+        self.verify = Some(verify);
+        self
+    }
+
+    /// When the model stops after modifying files, ask it to check its changes by running the
+    /// tests or the build with its tools, and only let it stop once it ran a bash command (it is asked
+    /// at most twice per turn). Unlike `verify`, the model picks the check itself
+    pub fn verify_before_complete(mut self) -> Self {
+        self.verify_before_complete = true;
+        self
+    }
+
+    /// Format the files written through `fs_log` when the agent stops, with the formatter
+    /// configured for their extension; files without formatter are left untouched
+    pub fn formatters(mut self, formatters: HashMap<String, String>, fs_log: Arc<FsOperationLog>) -> Self {
+        self.formatters = formatters;
+        self.fs_log = Some(fs_log);
+        self
+    }
+
+    /// Restart the task from its original trace, up to max_retries times, when the run fails
+    /// on a provider error; the delay starts at backoff and doubles on each restart
+    pub fn retry_task(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.task_retry = Some(TaskRetryPolicy { max_retries, backoff });
+        self
+    }
+
+    /// Before each brain step, once the trace is estimated above max_tokens, fold everything
+    /// but the task and the keep_recent last messages into a summary (TraceCompacted event)
+    pub fn with_compaction(mut self, max_tokens: usize, keep_recent: usize) -> Self {
+        self.compaction = Some(CompactionConfig { max_tokens, keep_recent });
+        self
+    }
+
+    /// Stop a model stuck in a tool loop: after this many tool runs without new user input the
+    /// agent pauses (or completes unsuccessfully without controller) with an Error event
+    pub fn max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Answer a tool call repeated with the same arguments more than `limit` times without new
+    /// user input with an error instead of running it again, other calls in between or not.
+    /// Read-only tools get a higher limit, None turns the check off
+    pub fn repeated_call_limit(mut self, limit: Option<u32>) -> Self {
+        self.repeat_limit = limit;
+        self
+    }
+
+    /// Run at most `n` tool calls of a batch that write (bash, edit, MCP tools...) at once, the others
+    /// wait for a slot. Read-only calls have their own pool of twice as many slots
+    pub fn max_parallel_tools(mut self, n: usize) -> Self {
+        self.max_parallel_tools = n.max(1);
+        self
+    }
+
+    /// Run the tool calls of a batch one after the other, each waiting for the previous one to
+    /// complete, for models whose calls depend on each other (an edit then the tests)
+    pub fn parallel_tool_calls(mut self, parallel: bool) -> Self {
+        self.parallel_tool_calls = parallel;
+        self
+    }
+
+    /// Stop the agent with an error once the LLM used more than this many tokens (input and
+    /// output, summed over the session). User input after that gives it a new budget
+    pub fn token_budget(mut self, tokens: u64) -> Self {
+        self.token_budget = Some(tokens);
+        self
+    }
+
+    /// Estimate the cost of the tokens used with this model's price, sent as CostEstimate events
+    /// and in the agent result
+    pub fn pricing(mut self, pricing: ModelPricing) -> Self {
+        self.pricing = Some(pricing);
+        self
+    }
+
+    /// Keep only the tools the filter allows, all of them by default
+    pub fn tool_filter(mut self, filter: ToolFilter) -> Self {
+        self.tool_filter = filter;
+        self
+    }
+
+    /// How the LLM is asked for tool calls, the provider's `tool_method` when built from config
+    pub fn tool_method(mut self, method: ToolCallMethod) -> Self {
+        self.tool_method = method;
+        self
+    }
+
+    /// Constraint on the tool calls of the first step, e.g. to make the model act before it answers
+    pub fn tool_choice(mut self, choice: StepToolChoice) -> Self {
+        self.tool_choice = choice;
+        self
+    }
+
+    /// Temperature, max tokens and top_p of every step, over the ones of the brain
+    pub fn sampling(mut self, sampling: SamplingOverrides) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Instructions added to or in place of the system prompt of the brain
+    pub fn system_prompt(mut self, system_prompt: SystemPromptOverride) -> Self {
+        self.system_prompt = Some(system_prompt);
+        self
+    }
+
     /// Enable sudo mode - bypasses all permission checks
     pub fn sudo(mut self) -> Self {
         self.permissions.sudo();
@@ -125,28 +457,68 @@ impl AgentBuilder {
 
     /// Build the AgentCore with required runtime fields
     pub fn build(mut self) -> AgentCore {        
+        let tool_filter = &self.tool_filter;
+        self.available_tools.retain(|tool| tool_filter.allows(&tool.name()));
+        if self.read_only {
+            self.available_tools.retain(|tool| tool.is_read_only());
+        }
         if let Some(goal) = self.goal {
             self.trace.push(ChatMessage::User { content: ChatMessageContent::Text(goal.clone()), name: None });
         }
 
 
-        AgentCore::new(
+        let mut agent = AgentCore::new(
             self.session_id.clone(),
             self.brain,
             self.trace,
             self.available_tools,
-            self.permissions
-        )
+            self.permissions,
+            self.todos
+        );
+        agent.max_duration = self.max_duration;
+        agent.permission_timeout = self.permission_timeout;
+        agent.permission_timeout_response = self.permission_timeout_response;
+        agent.verify = self.verify;
+        agent.verify_before_complete = self.verify_before_complete;
+        agent.interceptors = self.interceptors;
+        agent.command_deny_list = Arc::new(self.command_deny_list);
+        agent.dry_run = self.dry_run;
+        agent.read_only = self.read_only;
+        agent.normalize_tool_args = self.normalize_tool_args;
+        agent.tool_output_limit = Arc::new(self.tool_output_limit);
+        agent.stream_deltas = self.stream_deltas;
+        agent.task_retry = self.task_retry;
+        agent.compaction = self.compaction;
+        agent.max_steps = self.max_steps;
+        agent.repeat_limit = self.repeat_limit;
+        agent.max_parallel_tools = self.max_parallel_tools;
+        agent.parallel_tool_calls = self.parallel_tool_calls;
+        agent.token_budget = self.token_budget;
+        agent.pricing = self.pricing;
+        agent.method = self.tool_method;
+        agent.tool_choice = self.tool_choice;
+        agent.sampling = self.sampling;
+        agent.system_prompt = self.system_prompt;
+        agent.formatters = self.formatters;
+        agent.fs_log = self.fs_log;
+        agent
     }
 
     /// Create an AgentBuilder from an AgentConfig
     pub async fn from_config(mut config: AgentConfig) -> Result<Self, AgentError> {
         // Create LLM client from provider config using the utility method
         let llm_client = Arc::new(
-            LlmClient::create_provider(&config.llm_provider.provider, &config.llm_provider.env_vars)
+            LlmClient::create_provider(&config.llm_provider.provider, &resolve_env_vars(&config.llm_provider.env_vars)
+                .map_err(AgentError::ConfigurationError)?)
                 .map_err(|e| AgentError::LlmError(e.to_string()))?
+                .with_rate_limit(ShaiConfig::load_rate_limit(&config.llm_provider.provider))
         );
 
+        // Catch a wrong or retired model name now rather than on the first LLM call
+        let policy = ShaiConfig::load().map(|c| c.unavailable_model).unwrap_or_default();
+        config.llm_provider.model = resolve_model(&llm_client, &config.llm_provider.model, policy).await
+            .map_err(AgentError::ConfigurationError)?;
+
         // Create brain with custom system prompt and temperature
         let brain = Box::new(CoderBrain::with_custom_prompt(
             llm_client.clone(),
@@ -156,7 +528,9 @@ impl AgentBuilder {
         ));
 
         // Create tools
-        let tools = Self::create_tools_from_config(&mut config).await?;
+        let todo_storage = Arc::new(TodoStorage::new());
+        let fs_log = Arc::new(FsOperationLog::persistent());
+        let tools = Self::create_tools_from_config(&mut config, llm_client.clone(), todo_storage.clone(), fs_log.clone()).await?;
         
         // Display available tools by category
         let mut tool_groups: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
@@ -178,25 +552,42 @@ impl AgentBuilder {
             }
         }
 
-        Ok(Self::with_brain(brain)
+        let mut builder = Self::with_brain(brain)
+            .user_settings()
             .tools(tools)
-            .id(&format!("agent-{}", config.name)))
+            .todos(todo_storage)
+            .id(&format!("agent-{}", config.name))
+            .normalize_tool_args(config.normalize_tool_args)
+            .tool_method(config.llm_provider.tool_method)
+            .pricing(ModelPricing::from_config(&config.llm_provider.model));
+        if let Some(tokens) = config.token_budget {
+            builder = builder.token_budget(tokens);
+        }
+        if let Some(verify) = config.verify {
+            eprintln!("\x1b[2m░ verify: {}\x1b[0m", verify.command);
+            builder = builder.verify(verify);
+        }
+        if config.verify_before_complete {
+            builder = builder.verify_before_complete();
+        }
+        if !config.formatters.is_empty() {
+            let mut exts: Vec<&String> = config.formatters.keys().collect();
+            exts.sort();
+            eprintln!("\x1b[2m░ format: {}\x1b[0m", exts.iter().map(|e| format!(".{}", e)).collect::<Vec<_>>().join(", "));
+            builder = builder.formatters(config.formatters, fs_log);
+        }
+        Ok(builder)
     }
 
     /// Create tools from config
-    async fn create_tools_from_config(config: &mut AgentConfig) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
+    async fn create_tools_from_config(config: &mut AgentConfig, llm_client: Arc<LlmClient>, todo_storage: Arc<TodoStorage>, fs_log: Arc<FsOperationLog>) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
         let mut tools: Vec<Box<dyn AnyTool>> = Vec::new();
-
-        // Create shared storage for todo tools
-        let todo_storage = Arc::new(TodoStorage::new());
-        
-        // Create shared operation log for file system tools
-        let fs_log = Arc::new(FsOperationLog::new());
+        let bash_jobs = Arc::new(BashJobs::new());
 
         // Add builtin tools based on config
         let builtin_tools_to_add = if config.tools.builtin.contains(&"*".to_string()) {
             // Add all builtin tools
-            vec!["bash", "edit", "multiedit", "fetch", "find", "ls", "read", "todo_read", "todo_write", "write"]
+            ALL_BUILTIN_TOOLS.to_vec()
         } else {
             // Add only specified tools
             config.tools.builtin.iter().map(|s| s.as_str()).collect()
@@ -209,16 +600,30 @@ impl AgentBuilder {
             }
             
             match tool_name {
-                "bash" => tools.push(Box::new(BashTool::new())),
+                "bash" => tools.push(Box::new(BashTool::with_config(ShaiConfig::load_bash().overridden_by(&config.bash)).with_jobs(bash_jobs.clone()))),
+                "bash_status" => tools.push(Box::new(BashStatusTool::new(bash_jobs.clone()))),
                 "edit" => tools.push(Box::new(EditTool::new(fs_log.clone()))),
+                "env" => tools.push(Box::new(EnvTool::with_shell(ShaiConfig::load_bash().overridden_by(&config.bash).shell_program()))),
                 "multiedit" => tools.push(Box::new(MultiEditTool::new(fs_log.clone()))),
-                "fetch" => tools.push(Box::new(FetchTool::new())),
+                "apply_patch" => tools.push(Box::new(ApplyPatchTool::new(fs_log.clone()))),
+                "fetch" => tools.push(Box::new(FetchTool::with_max_bytes(ShaiConfig::load_fetch_max_bytes()))),
                 "find" => tools.push(Box::new(FindTool::new())),
+                "grep" => tools.push(Box::new(GrepTool::new())),
                 "ls" => tools.push(Box::new(LsTool::new())),
-                "read" => tools.push(Box::new(ReadTool::new(fs_log.clone()))),
+                "read" => tools.push(Box::new(ReadTool::with_max_bytes(fs_log.clone(), ShaiConfig::load_read_max_bytes()))),
                 "todo_read" => tools.push(Box::new(TodoReadTool::new(todo_storage.clone()))),
                 "todo_write" => tools.push(Box::new(TodoWriteTool::new(todo_storage.clone()))),
+                "undo" => tools.push(Box::new(UndoTool::new(fs_log.clone()))),
                 "write" => tools.push(Box::new(WriteTool::new(fs_log.clone()))),
+                // opt-in only, like the git_tool flag of the default agent
+                "git" => tools.push(Box::new(GitTool::new())),
+                // opt-in only: needs an embedding model, so it is not part of the "*" set
+                "semantic_search" => match SemanticSearchTool::from_env(llm_client.clone()) {
+                    Some(tool) => tools.push(Box::new(tool)),
+                    None => return Err(AgentError::ConfigurationError(
+                        "semantic_search requires a provider with embeddings support and SHAI_EMBEDDING_MODEL to be set".to_string()
+                    )),
+                },
                 _ => return Err(AgentError::ConfigurationError(format!("Unknown builtin tool: {}", tool_name))),
             }
         }
@@ -247,7 +652,7 @@ impl AgentBuilder {
 
             // Get all tools from MCP client
             let mcp_client = create_mcp_client(mcp_tool_config.config.clone());
-            let mcp_tools_result = get_mcp_tools(mcp_client, mcp_name).await;
+            let mcp_tools_result = get_mcp_tools(mcp_client, mcp_name, mcp_tool_config.prefix_tools).await;
 
             let all_mcp_tools = match mcp_tools_result {
                 Ok(tools) => tools,
@@ -261,27 +666,24 @@ impl AgentBuilder {
                 }
             };
 
-            // Check if we should add all tools or filter by enabled_tools
-            if mcp_tool_config.enabled_tools.contains(&"*".to_string()) {
-                // Add all tools from this MCP client (except excluded ones)
-                for tool in all_mcp_tools {
-                    let tool_name = tool.name();
-                    if !mcp_tool_config.excluded_tools.contains(&tool_name) {
-                        tools.push(tool);
-                    }
-                }
-            } else {
-                // Filter and add only enabled tools (except excluded ones)
-                for tool in all_mcp_tools {
-                    let tool_name = tool.name();
-                    if mcp_tool_config.enabled_tools.contains(&tool_name) && !mcp_tool_config.excluded_tools.contains(&tool_name) {
-                        tools.push(tool);
+            // enabled_tools and excluded_tools use the server-side names
+            let prefix = if mcp_tool_config.prefix_tools { prefixed_tool_name(mcp_name, "") } else { String::new() };
+            let all_tools = mcp_tool_config.enabled_tools.contains(&"*".to_string());
+            for tool in all_mcp_tools {
+                let tool_name = tool.name()[prefix.len()..].to_string();
+                // Add all tools from this MCP client with the wildcard, otherwise only the enabled ones (except excluded ones)
+                if (all_tools || mcp_tool_config.enabled_tools.contains(&tool_name)) && !mcp_tool_config.excluded_tools.contains(&tool_name) {
+                    if tools.iter().any(|t| t.name() == tool.name()) {
+                        eprintln!("\x1b[2m⚠ MCP '{}' tool '{}' has the same name as another tool, set prefix_tools to tell them apart\x1b[0m", mcp_name, tool_name);
                     }
+                    tools.push(tool);
                 }
+            }
 
+            if !all_tools {
                 // Check if all enabled tools were found (only when not using wildcard)
                 for enabled_tool in &mcp_tool_config.enabled_tools {
-                    let found = tools.iter().any(|t| t.name() == *enabled_tool);
+                    let found = tools.iter().any(|t| t.name() == format!("{}{}", prefix, enabled_tool));
                     if !found {
                         if mcp_tool_config.required {
                             return Err(AgentError::ConfigurationError(format!("Tool '{}' not found in MCP client '{}'", enabled_tool, mcp_name)));
@@ -301,8 +703,8 @@ impl AgentBuilder {
         Ok(tools)
     }
 
-    /// Handle OAuth flow for MCP connections if needed
-    async fn mcp_check_oauth(mcp_name: &str, mcp_config: &mut McpConfig) -> Result<bool, AgentError> {
+    /// Handle OAuth flow for MCP connections if needed, true when a new token was stored in `mcp_config`
+    pub async fn mcp_check_oauth(mcp_name: &str, mcp_config: &mut McpConfig) -> Result<bool, AgentError> {
         use crate::tools::mcp::McpConfig;
         let mut config_changed = false;
 
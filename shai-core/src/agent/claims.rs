@@ -1,7 +1,14 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use regex::Regex;
+use tracing::warn;
+
+/// Parameters naming the file or directory a tool works on
+const FILE_PARAMETERS: &[&str] = &["path", "file_path", "directory"];
+
+/// Parameters holding a path, a saved rule only covers the path approved for each of them
+const PATH_PARAMETERS: &[&str] = &["path", "file_path", "directory", "cwd"];
 
 /// Match strategy for permission checking
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -12,6 +19,11 @@ pub enum MatchStrategy {
     Partial,
     /// Glob match - each permission field is a regex pattern
     Glob,
+    /// Scoped match - path fields hold the path the call's path must resolve to,
+    /// the other fields must match exactly, calls with a path the rule doesn't scope are refused
+    Scoped,
+    /// Same as Scoped, but path fields hold a directory the call's path must be inside
+    Directory,
 }
 
 impl Default for MatchStrategy {
@@ -53,6 +65,46 @@ impl Permission {
         self
     }
 
+    /// The rule saved when a call is always allowed. A path argument only grants that exact path,
+    /// once resolved. For calls on a file the other arguments (edits, content, patterns)
+    /// are left out, for the others (a bash command, a url) they must be the same.
+    /// A call without any path is tied to the working directory.
+    pub fn from_call(tool_name: &str, parameters: &serde_json::Value) -> Self {
+        Self::scoped(tool_name, parameters, MatchStrategy::Scoped)
+    }
+
+    /// The rule saved when a call is always allowed in its directory: same as `from_call`, but a
+    /// path argument grants the directory it is in and everything below
+    pub fn for_directory(tool_name: &str, parameters: &serde_json::Value) -> Self {
+        Self::scoped(tool_name, parameters, MatchStrategy::Directory)
+    }
+
+    fn scoped(tool_name: &str, parameters: &serde_json::Value, strategy: MatchStrategy) -> Self {
+        let scope = |path: &str| match strategy {
+            MatchStrategy::Directory => scope_of(path),
+            _ => normalize_path(path),
+        };
+        let call = parameters.as_object().cloned().unwrap_or_default();
+        let mut rule = serde_json::Map::new();
+        for (key, value) in &call {
+            if let (true, Some(path)) = (is_path_parameter(key), value.as_str()) {
+                rule.insert(key.clone(), serde_json::json!(scope(path).to_string_lossy()));
+            }
+        }
+        if !FILE_PARAMETERS.iter().any(|key| rule.contains_key(*key)) {
+            for (key, value) in &call {
+                if !is_path_parameter(key) {
+                    rule.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        if !rule.keys().any(|key| is_path_parameter(key)) {
+            rule.insert("cwd".to_string(), serde_json::json!(scope(".").to_string_lossy()));
+        }
+
+        Self::new(tool_name.to_string(), strategy, serde_json::Value::Object(rule), false)
+    }
+
     /// Check if this permission matches the given tool call parameters
     pub fn matches(&self, tool_name: &str, call_params: &serde_json::Value) -> bool {
         if self.tool_name != tool_name {
@@ -63,6 +115,8 @@ impl Permission {
             MatchStrategy::Exact => self.matches_exact(call_params),
             MatchStrategy::Partial => self.matches_partial(call_params),
             MatchStrategy::Glob => self.matches_glob(call_params),
+            MatchStrategy::Scoped => self.matches_scoped(call_params, false),
+            MatchStrategy::Directory => self.matches_scoped(call_params, true),
         }
     }
 
@@ -119,6 +173,92 @@ impl Permission {
         }
         true
     }
+
+    fn matches_scoped(&self, call_params: &serde_json::Value, below: bool) -> bool {
+        let Some(perm_obj) = self.parameters.as_object() else {
+            return false;
+        };
+        let Some(call_obj) = call_params.as_object() else {
+            return false;
+        };
+
+        // a path the rule doesn't know about could point anywhere
+        if call_obj.iter().any(|(key, value)| is_path_parameter(key) && !value.is_null() && !perm_obj.contains_key(key)) {
+            return false;
+        }
+
+        perm_obj.iter().all(|(key, perm_value)| {
+            if !is_path_parameter(key) {
+                return call_obj.get(key) == Some(perm_value);
+            }
+            let Some(scope) = perm_value.as_str() else {
+                return false;
+            };
+            // tools run in the working directory when they are given no path
+            let path = match call_obj.get(key) {
+                Some(serde_json::Value::String(path)) => path.as_str(),
+                None | Some(serde_json::Value::Null) => ".",
+                Some(_) => return false,
+            };
+            let path = normalize_path(path);
+            if below { path.starts_with(scope) } else { path == Path::new(scope) }
+        })
+    }
+}
+
+fn is_path_parameter(key: &str) -> bool {
+    PATH_PARAMETERS.contains(&key)
+}
+
+/// Directory a rule grants for an approved path: the path itself for a directory, its parent otherwise
+fn scope_of(path: &str) -> PathBuf {
+    let path = normalize_path(path);
+    if path.is_dir() {
+        return path;
+    }
+    path.parent().map(Path::to_path_buf).unwrap_or(path)
+}
+
+/// Absolute form of a path argument, with `.`, `..` and symlinks resolved so that neither can lead
+/// out of a scope. Symlinks are resolved as far as the path exists.
+fn normalize_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                // `..` goes up from where a symlink leads, not from the link
+                if let Ok(real) = resolved.canonicalize() {
+                    resolved = real;
+                }
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+
+    // the longest existing ancestor says where the path really is
+    let mut existing = resolved.as_path();
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(real) = existing.canonicalize() {
+            return missing.iter().rev().fold(real, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return resolved.clone(),
+        }
+    }
 }
 
 /// Permission Manager for storing and retrieving permissions
@@ -167,6 +307,35 @@ impl ClaimManager {
         }
     }
     
+    /// Create a permission manager backed by ~/.config/shai/permissions.json, with the rules
+    /// saved there loaded. A file that can't be read is left untouched and no rule is loaded.
+    pub fn persistent() -> Self {
+        let path = match Self::default_config_file() {
+            Ok(path) => path,
+            Err(e) => {
+                warn!(target: "agent::permission", "saved permissions unavailable: {}", e);
+                return Self::new();
+            }
+        };
+
+        let mut manager = Self::with_config_file(path);
+        if let Err(e) = manager.load_from_file() {
+            warn!(target: "agent::permission", "could not load the saved permissions: {}", e);
+            return Self::new();
+        }
+        manager
+    }
+
+    /// Where the "always allow" rules are saved
+    pub fn default_config_file() -> Result<PathBuf, PermissionError> {
+        let config_dir = std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .ok()
+            .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+            .ok_or(PermissionError::NoConfigFile)?;
+        Ok(config_dir.join("shai").join("permissions.json"))
+    }
+
     /// Enable sudo mode - bypasses all permission checks
     pub fn sudo(&mut self) {
         self.sudo_mode = true;
@@ -187,6 +356,38 @@ impl ClaimManager {
         self.permissions.push(permission);
     }
     
+    /// Remember that calls like this one are always allowed, and save the rule when there is a config file
+    pub fn allow_always(&mut self, tool_name: &str, parameters: &serde_json::Value) -> Result<(), PermissionError> {
+        self.remember(Permission::from_call(tool_name, parameters))
+    }
+
+    /// Remember that calls of this tool are always allowed in the directory of this call's paths and below
+    pub fn allow_directory(&mut self, tool_name: &str, parameters: &serde_json::Value) -> Result<(), PermissionError> {
+        self.remember(Permission::for_directory(tool_name, parameters))
+    }
+
+    fn remember(&mut self, permission: Permission) -> Result<(), PermissionError> {
+        let known = self.permissions.iter().any(|perm| {
+            perm.tool_name == permission.tool_name
+                && perm.match_strategy == permission.match_strategy
+                && perm.parameters == permission.parameters
+        });
+        if known {
+            return Ok(());
+        }
+
+        self.permissions.push(permission);
+        match self.config_file {
+            Some(_) => self.save_to_file(),
+            None => Ok(()),
+        }
+    }
+
+    /// Remove a permission by its position in `get_all_permissions`
+    pub fn remove_permission(&mut self, index: usize) -> Option<Permission> {
+        (index < self.permissions.len()).then(|| self.permissions.remove(index))
+    }
+
     /// Check if a tool call is permitted
     pub fn is_permitted(&self, tool_name: &str, parameters: &serde_json::Value) -> bool {
         // Sudo mode bypasses all permission checks
@@ -240,8 +441,18 @@ impl ClaimManager {
             let json_str = serde_json::to_string_pretty(&persistent_permissions)
                 .map_err(PermissionError::Serialization)?;
             
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
             std::fs::write(path, json_str)
                 .map_err(PermissionError::FileAccess)?;
+
+            // the rules say what may run without asking, only the user should change them
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+            }
                 
             Ok(())
         } else {
@@ -536,4 +747,106 @@ mod tests {
         assert_eq!(manager.len(), cloned.len());
         assert_eq!(manager.config_file, cloned.config_file);
     }
+
+    #[test]
+    fn test_scoped_permission_stays_in_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("nested")).unwrap();
+        std::fs::create_dir_all(dir.path().join("src-other")).unwrap();
+        std::fs::write(src.join("main.rs"), "fn main() {}").unwrap();
+        let path = |p: &Path| serde_json::json!(p.to_string_lossy());
+
+        let permission = Permission::for_directory("edit", &serde_json::json!({
+            "path": path(&src.join("main.rs")),
+            "old_string": "main",
+            "new_string": "start"
+        }));
+        assert_eq!(permission.match_strategy, MatchStrategy::Directory);
+        assert!(permission.parameters.get("old_string").is_none());
+
+        // any edit of a file in the approved directory or below
+        assert!(permission.matches("edit", &serde_json::json!({"path": path(&src.join("lib.rs")), "old_string": "a", "new_string": "b"})));
+        assert!(permission.matches("edit", &serde_json::json!({"path": path(&src.join("nested/new.rs")), "old_string": "a", "new_string": "b"})));
+
+        // but nothing outside of it
+        assert!(!permission.matches("edit", &serde_json::json!({"path": path(&dir.path().join("Cargo.toml"))})));
+        assert!(!permission.matches("edit", &serde_json::json!({"path": path(&src.join("../Cargo.toml"))})));
+        assert!(!permission.matches("edit", &serde_json::json!({"path": path(&dir.path().join("src-other/main.rs"))})));
+        assert!(!permission.matches("edit", &serde_json::json!({"path": "/etc/passwd"})));
+        assert!(!permission.matches("write", &serde_json::json!({"path": path(&src.join("lib.rs"))})));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("src-other"), src.join("link")).unwrap();
+            assert!(!permission.matches("edit", &serde_json::json!({"path": path(&src.join("link/main.rs"))})));
+            assert!(!permission.matches("edit", &serde_json::json!({"path": path(&src.join("link/../../Cargo.toml"))})));
+        }
+    }
+
+    #[test]
+    fn test_saved_file_permission_only_covers_that_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".bashrc"), "").unwrap();
+        let path = |name: &str| serde_json::json!(dir.path().join(name).to_string_lossy());
+
+        let permission = Permission::from_call("write", &serde_json::json!({"path": path(".bashrc"), "content": "alias ll='ls -l'"}));
+        assert_eq!(permission.match_strategy, MatchStrategy::Scoped);
+        assert!(permission.matches("write", &serde_json::json!({"path": path(".bashrc"), "content": "anything"})));
+        assert!(permission.matches("write", &serde_json::json!({"path": path("sub/../.bashrc"), "content": "anything"})));
+
+        // a sibling file is still asked about
+        let mut manager = ClaimManager::new();
+        manager.allow_always("write", &serde_json::json!({"path": path(".bashrc"), "content": ""})).unwrap();
+        assert!(!manager.is_permitted("write", &serde_json::json!({"path": path(".profile"), "content": ""})));
+        assert!(!manager.is_permitted("write", &serde_json::json!({"path": path(".bashrc/x"), "content": ""})));
+        assert!(manager.is_permitted("write", &serde_json::json!({"path": path(".bashrc"), "content": ""})));
+    }
+
+    #[test]
+    fn test_scoped_permission_for_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().to_string_lossy().to_string();
+        let elsewhere = std::env::temp_dir().to_string_lossy().to_string();
+
+        let permission = Permission::from_call("bash", &serde_json::json!({"command": "cargo test", "cwd": cwd}));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "cargo test", "cwd": cwd})));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "cargo test", "cwd": format!("{}/.", cwd)})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo test; rm -rf ~", "cwd": cwd})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo test", "cwd": elsewhere})));
+
+        // without a cwd the rule is tied to the working directory
+        let permission = Permission::from_call("bash", &serde_json::json!({"command": "ls"}));
+        let here = std::env::current_dir().unwrap();
+        assert_eq!(permission.parameters["cwd"], serde_json::json!(normalize_path(".").to_string_lossy()));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "ls"})));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "ls", "cwd": here.to_string_lossy()})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "ls", "cwd": cwd})));
+    }
+
+    #[test]
+    fn test_allow_always_is_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("shai").join("permissions.json");
+        let call = serde_json::json!({"command": "cargo build", "cwd": dir.path().to_string_lossy()});
+
+        let mut manager = ClaimManager::with_config_file(file.clone());
+        manager.allow_always("bash", &call).unwrap();
+        manager.allow_always("bash", &call).unwrap();
+        assert_eq!(manager.len(), 1);
+        assert!(file.exists());
+
+        let mut reloaded = ClaimManager::with_config_file(file.clone());
+        reloaded.load_from_file().unwrap();
+        assert!(reloaded.is_permitted("bash", &call));
+        assert!(!reloaded.is_permitted("bash", &serde_json::json!({"command": "cargo clean", "cwd": dir.path().to_string_lossy()})));
+
+        assert!(reloaded.remove_permission(3).is_none());
+        assert!(reloaded.remove_permission(0).is_some());
+        reloaded.save_to_file().unwrap();
+
+        let mut revoked = ClaimManager::with_config_file(file);
+        revoked.load_from_file().unwrap();
+        assert!(revoked.is_empty());
+    }
 }
\ No newline at end of file
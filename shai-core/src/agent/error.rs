@@ -1,7 +1,9 @@
 use shai_llm::provider::LlmError;
+use serde::{Serialize, Deserialize};
 use thiserror::Error;
 
-#[derive(Error, Debug, Clone)]
+#[derive(Error, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
 pub enum AgentError {
     #[error("Agent execution error: {0}")]
     ExecutionError(String),
@@ -31,6 +33,14 @@ pub enum AgentError {
     InvalidStateTransition(String),
 }
 
+impl AgentError {
+    /// Infrastructure failures (provider outage...) that may go away if the task is run again,
+    /// as opposed to the task itself failing
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AgentError::LlmError(_))
+    }
+}
+
 #[derive(Debug)]
 pub enum AgentExecutionError {
     LlmError(LlmError),
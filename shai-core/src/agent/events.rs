@@ -2,12 +2,12 @@ use std::sync::Arc;
 use std::future::Future;
 use futures::future::BoxFuture;
 use openai_dive::v1::resources::chat::ChatMessage;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use async_trait::async_trait;
 use super::brain::ThinkerDecision;
 use super::AgentError;
-use crate::agent::PublicAgentState;
-use crate::tools::{ToolResult, ToolCall};
+use crate::agent::{FormatResult, PublicAgentState};
+use crate::tools::{ToolResult, ToolCall, TodoItem};
 use chrono::{DateTime, TimeDelta, Utc};
 
 /// Internal events for agent state machine communication
@@ -48,12 +48,23 @@ pub enum InternalAgentEvent {
     PermissionResponseReceived { 
         request_id: String,
         response: PermissionResponse
+    },
+    /// The verify command finished
+    VerificationCompleted {
+        result: ToolResult
+    },
+    /// The formatters ran on the files written during the turn
+    FormattingCompleted {
+        results: Vec<FormatResult>
     }
 }
 
 /// Public events emitted to external controllers/UI
-/// These events are what external consumers receive and can respond to
-#[derive(Clone)]
+/// These events are what external consumers receive and can respond to.
+/// They serialize as `{"type": "tool_call_completed", ...}`, timestamps in RFC 3339 and
+/// durations in milliseconds, so a stream of events can be stored and read back
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum AgentEvent {
     /// Agent status has changed
     StatusChanged { 
@@ -62,9 +73,23 @@ pub enum AgentEvent {
     },
     /// Thinking Start
     ThinkingStart,
+    /// Piece of the message the brain is generating, `reasoning` tells the model reasoning from
+    /// the answer. Only emitted when delta streaming is enabled, the BrainResult that follows
+    /// carries the whole message.
+    BrainDelta {
+        text: String,
+        reasoning: bool
+    },
+    /// Reasoning of the message the brain produced, emitted right before the BrainResult that
+    /// carries the same message. Lets clients show the thinking apart from the answer
+    Reasoning {
+        timestamp: DateTime<Utc>,
+        text: String
+    },
     /// Agent is thinking - provides the thought content to display to user
     BrainResult { 
         timestamp: DateTime<Utc>,
+        #[serde(with = "thought_serde")]
         thought: Result<ChatMessage, AgentError>
     },
     /// Agent started executing a tool
@@ -74,6 +99,7 @@ pub enum AgentEvent {
     },
     /// Tool execution completed and returned a result
     ToolCallCompleted {
+        #[serde(rename = "duration_ms", with = "time_delta_ms")]
         duration: TimeDelta,
         call: ToolCall,
         result: ToolResult
@@ -101,6 +127,134 @@ pub enum AgentEvent {
         input_tokens: u32,
         output_tokens: u32
     },
+    /// Tokens used against the budget since the agent started, or since the user input that
+    /// followed a spent budget. Sent after each step when a budget is set
+    TokenBudget {
+        used: u64,
+        limit: u64
+    },
+    /// Estimated cost of the tokens used since the agent started, after each step when the model is known.
+    /// `priced` is false when no price is known for the model, the estimate is then zero
+    CostEstimate {
+        model: String,
+        usd: f64,
+        priced: bool
+    },
+    /// The todo list changed, either from the agent or from a controller
+    TodoUpdated {
+        todos: Vec<TodoItem>
+    },
+    /// The agent ran longer than its max_duration and is being stopped
+    TimeLimitReached {
+        #[serde(rename = "limit_ms", with = "duration_ms")]
+        limit: std::time::Duration
+    },
+    /// Nobody answered a permission request in time, it was resolved with the default response
+    PermissionTimedOut {
+        request_id: String,
+        call: ToolCall,
+        response: PermissionResponse,
+    },
+    /// A bash command matched the deny-list, it was resolved as Forbidden without asking
+    CommandForbidden {
+        call: ToolCall,
+        pattern: String,
+        response: PermissionResponse,
+    },
+    /// The verify command ran after the agent modified files
+    VerificationCompleted {
+        command: String,
+        success: bool,
+        output: String,
+        attempt: u32,
+    },
+    /// The configured formatters ran on the files the agent wrote, failures are not fatal
+    FilesFormatted {
+        results: Vec<FormatResult>,
+    },
+    /// The agent tried to stop after modifying files without checking them, it was asked to
+    /// run a test or a build first
+    SelfCheckRequested {
+        attempt: u32,
+    },
+    /// The run failed on an infrastructure error, the task restarts from its original trace after `delay`
+    TaskRestarting {
+        attempt: u32,
+        max_retries: u32,
+        #[serde(rename = "delay_ms", with = "duration_ms")]
+        delay: std::time::Duration,
+        error: String,
+    },
+    /// The trace went over the compaction budget, `messages` older messages were folded into a summary
+    TraceCompacted {
+        tokens_before: usize,
+        tokens_after: usize,
+        messages: usize,
+    },
+    /// A tool call lost its connection to an MCP server, reconnected after `attempts` attempts and
+    /// succeeded. A call that could not reconnect only ends with its error
+    McpReconnected {
+        server: String,
+        tool_name: String,
+        attempts: u32,
+    },
+    /// An LLM call waits for the rate limit of its provider, sent each time the wait starts
+    RateLimited {
+        provider: String,
+        #[serde(rename = "wait_ms", with = "duration_ms")]
+        wait: std::time::Duration,
+    },
+}
+
+/// `BrainResult.thought`: a message serializes as itself, a brain error as `{"error": {"kind": ..., "message": ...}}`
+mod thought_serde {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Thought<M, E> {
+        Err { error: E },
+        Ok(M),
+    }
+
+    pub fn serialize<S: Serializer>(thought: &Result<ChatMessage, AgentError>, serializer: S) -> Result<S::Ok, S::Error> {
+        match thought {
+            Ok(message) => Thought::<_, &AgentError>::Ok(message).serialize(serializer),
+            Err(error) => Thought::<&ChatMessage, _>::Err { error }.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Result<ChatMessage, AgentError>, D::Error> {
+        Ok(match Thought::<ChatMessage, AgentError>::deserialize(deserializer)? {
+            Thought::Ok(message) => Ok(message),
+            Thought::Err { error } => Err(error),
+        })
+    }
+}
+
+/// Durations as a number of milliseconds
+mod time_delta_ms {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &TimeDelta, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_milliseconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TimeDelta, D::Error> {
+        Ok(TimeDelta::milliseconds(i64::deserialize(deserializer)?))
+    }
+}
+
+mod duration_ms {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &std::time::Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<std::time::Duration, D::Error> {
+        Ok(std::time::Duration::from_millis(u64::deserialize(deserializer)?))
+    }
 }
 
 /// Types of user input that an agent can request
@@ -152,6 +306,8 @@ pub enum PermissionResponse {
     Allow,
     /// Allow this type of operation always
     AllowAlways,
+    /// Allow this type of operation always, in the directories of its paths and below
+    AllowDirectory,
     /// Operation Forbidden
     Forbidden,
     /// Operation was denied
@@ -219,6 +375,18 @@ impl std::fmt::Debug for AgentEvent {
                 f.debug_struct("ThinkingStart")
                     .finish()
             }
+            AgentEvent::BrainDelta { text, reasoning } => {
+                f.debug_struct("BrainDelta")
+                    .field("text", text)
+                    .field("reasoning", reasoning)
+                    .finish()
+            }
+            AgentEvent::Reasoning { timestamp, text } => {
+                f.debug_struct("Reasoning")
+                    .field("timestamp", timestamp)
+                    .field("text", text)
+                    .finish()
+            }
             AgentEvent::BrainResult { timestamp, thought } => {
                 f.debug_struct("BrainResult")
                     .field("timestamp", timestamp)
@@ -274,6 +442,89 @@ impl std::fmt::Debug for AgentEvent {
                     .field("output_tokens", output_tokens)
                     .finish()
             }
+            AgentEvent::TokenBudget { used, limit } => {
+                f.debug_struct("TokenBudget")
+                    .field("used", used)
+                    .field("limit", limit)
+                    .finish()
+            }
+            AgentEvent::CostEstimate { model, usd, priced } => {
+                f.debug_struct("CostEstimate")
+                    .field("model", model)
+                    .field("usd", usd)
+                    .field("priced", priced)
+                    .finish()
+            }
+            AgentEvent::TodoUpdated { todos } => {
+                f.debug_struct("TodoUpdated")
+                    .field("todos", todos)
+                    .finish()
+            }
+            AgentEvent::TimeLimitReached { limit } => {
+                f.debug_struct("TimeLimitReached")
+                    .field("limit", limit)
+                    .finish()
+            }
+            AgentEvent::PermissionTimedOut { request_id, call, response } => {
+                f.debug_struct("PermissionTimedOut")
+                    .field("request_id", request_id)
+                    .field("call", call)
+                    .field("response", response)
+                    .finish()
+            }
+            AgentEvent::CommandForbidden { call, pattern, response } => {
+                f.debug_struct("CommandForbidden")
+                    .field("call", call)
+                    .field("pattern", pattern)
+                    .field("response", response)
+                    .finish()
+            }
+            AgentEvent::VerificationCompleted { command, success, output, attempt } => {
+                f.debug_struct("VerificationCompleted")
+                    .field("command", command)
+                    .field("success", success)
+                    .field("output", output)
+                    .field("attempt", attempt)
+                    .finish()
+            }
+            AgentEvent::FilesFormatted { results } => {
+                f.debug_struct("FilesFormatted")
+                    .field("results", results)
+                    .finish()
+            }
+            AgentEvent::SelfCheckRequested { attempt } => {
+                f.debug_struct("SelfCheckRequested")
+                    .field("attempt", attempt)
+                    .finish()
+            }
+            AgentEvent::TaskRestarting { attempt, max_retries, delay, error } => {
+                f.debug_struct("TaskRestarting")
+                    .field("attempt", attempt)
+                    .field("max_retries", max_retries)
+                    .field("delay", delay)
+                    .field("error", error)
+                    .finish()
+            }
+            AgentEvent::TraceCompacted { tokens_before, tokens_after, messages } => {
+                f.debug_struct("TraceCompacted")
+                    .field("tokens_before", tokens_before)
+                    .field("tokens_after", tokens_after)
+                    .field("messages", messages)
+                    .finish()
+            }
+            AgentEvent::McpReconnected { server, tool_name, attempts } => {
+                f.debug_struct("McpReconnected")
+                    .field("server", server)
+                    .field("tool_name", tool_name)
+                    .field("attempts", attempts)
+                    .finish()
+            }
+            AgentEvent::RateLimited { provider, wait } => {
+                f.debug_struct("RateLimited")
+                    .field("provider", provider)
+                    .field("wait", wait)
+                    .finish()
+            }
         }
     }
 }
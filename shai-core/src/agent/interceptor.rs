@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::tools::ToolCall;
+
+/// What an interceptor decided about a tool call
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterceptorDecision {
+    /// let the call go through (to the permission check, then to the tool)
+    Allow,
+    /// block the call, the reason is returned to the LLM as the tool error
+    Deny { reason: String },
+    /// run the call with these parameters instead
+    Modify { parameters: Value },
+}
+
+/// Policy hook called before every tool call, ahead of the permission system.
+/// Interceptors run in registration order, the first Deny wins and each one sees
+/// the parameters modified by the previous ones.
+#[async_trait]
+pub trait ToolCallInterceptor: Send + Sync {
+    async fn intercept(&self, call: &ToolCall) -> InterceptorDecision;
+}
@@ -8,27 +8,33 @@ pub mod events;
 pub mod states;
 pub mod actions;
 pub mod output;
+pub mod interceptor;
+pub mod denylist;
+pub mod trace;
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 pub use agent::{
     Agent, AgentCore,
     TaskAgentResponse, 
-    AgentResult
+    AgentResult, VerificationResult, FormatResult, TaskRetryPolicy, CompactionConfig, TokenTotals, ToolTime, ToolTimeTotals
 };
 pub use states::{InternalAgentState, PublicAgentState};
 
-pub use protocol::{AgentRequest, AgentResponse, AgentController};
+pub use protocol::{AgentRequest, AgentResponse, AgentController, AgentCheckpoint};
 
 pub use events::{
     InternalAgentEvent, AgentEvent,
     ClosureHandler, AgentEventHandler, DynEventHandler, closure_handler,
     UserRequest, UserResponse, PermissionRequest, PermissionResponse};
-pub use output::StdoutEventManager;
+pub use output::{StdoutEventManager, JsonEventManager};
     
 pub use builder::AgentBuilder;
+pub use interceptor::{InterceptorDecision, ToolCallInterceptor};
+pub use denylist::{forbidden_pattern, forbidden_result, CommandDenyList, DEFAULT_BASH_DENY_LIST};
+pub use trace::{TraceIssue, check_tool_call_ids, repair_tool_call_ids, compact_trace, estimate_tokens};
 pub use claims::{ClaimManager, PermissionError};
 pub use error::{AgentError, AgentExecutionError};
-pub use brain::{Brain, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
+pub use brain::{Brain, BrainDeltaSink, SamplingOverrides, StepToolChoice, SystemPromptOverride, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
 pub use crate::logging::LoggingConfig;
\ No newline at end of file
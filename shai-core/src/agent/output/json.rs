@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+use async_trait::async_trait;
+use crate::agent::{AgentEvent, AgentEventHandler};
+
+/// Event manager that writes every event as one JSON line (NDJSON) on stdout, for scripts.
+/// Each line is an object with a `type` field, e.g. `{"type":"token_usage","input_tokens":12,...}`
+pub struct JsonEventManager;
+
+impl JsonEventManager {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn render(event: &AgentEvent) -> Option<String> {
+        match event {
+            // the deltas are also in the brain_result that follows
+            AgentEvent::BrainDelta { .. } => None,
+            _ => serde_json::to_string(event).ok(),
+        }
+    }
+}
+
+#[async_trait]
+impl AgentEventHandler for JsonEventManager {
+    async fn handle_event(&self, event: AgentEvent) {
+        if let Some(line) = Self::render(&event) {
+            let mut stdout = io::stdout().lock();
+            let _ = writeln!(stdout, "{}", line);
+            let _ = stdout.flush();
+        }
+    }
+}
+
+impl Default for JsonEventManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use serde_json::{json, Value};
+    use crate::agent::PublicAgentState;
+    use crate::tools::{ToolCall, ToolResult};
+
+    fn parse(event: AgentEvent) -> Value {
+        serde_json::from_str(&JsonEventManager::render(&event).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_events_serialize_as_json_lines() {
+        let completed = parse(AgentEvent::ToolCallCompleted {
+            duration: TimeDelta::milliseconds(1500),
+            call: ToolCall {
+                tool_call_id: "call_1".to_string(),
+                tool_name: "bash".to_string(),
+                parameters: json!({"command": "ls"}),
+            },
+            result: ToolResult::success("a.txt".to_string()),
+        });
+        assert_eq!(completed["type"], "tool_call_completed");
+        assert_eq!(completed["duration_ms"], 1500);
+        assert_eq!(completed["call"]["tool_name"], "bash");
+        assert!(completed["result"].to_string().contains("a.txt"), "{}", completed);
+
+        let usage = parse(AgentEvent::TokenUsage { input_tokens: 12, output_tokens: 3 });
+        assert_eq!(usage, json!({"type": "token_usage", "input_tokens": 12, "output_tokens": 3}));
+
+        let status = parse(AgentEvent::StatusChanged {
+            old_status: PublicAgentState::Running,
+            new_status: PublicAgentState::Completed { success: true },
+        });
+        assert_eq!(status["old_status"], json!({"state": "running"}));
+        assert_eq!(status["new_status"], json!({"state": "completed", "success": true}));
+
+        let done = parse(AgentEvent::Completed { success: true, message: "done".to_string() });
+        assert_eq!(done, json!({"type": "completed", "success": true, "message": "done"}));
+
+        assert!(JsonEventManager::render(&AgentEvent::BrainDelta { text: "he".to_string(), reasoning: false }).is_none());
+    }
+}
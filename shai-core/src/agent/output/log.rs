@@ -29,6 +29,12 @@ impl FileEventLogger {
             AgentEvent::ThinkingStart => {
                 format!("ThinkingStart")
             }
+            AgentEvent::BrainDelta { text, reasoning } => {
+                format!("BrainDelta: reasoning={} {:?}", reasoning, text)
+            }
+            AgentEvent::Reasoning { timestamp: event_time, text } => {
+                format!("Reasoning: {:?} - {:?}", event_time, text)
+            }
             AgentEvent::BrainResult { timestamp: event_time, thought } => {
                 format!("BrainResult: {:?} - {:?}", event_time, thought)
             }
@@ -56,6 +62,46 @@ impl FileEventLogger {
             AgentEvent::TokenUsage { input_tokens, output_tokens } => {
                 format!("Token Usage: input={} output={} total={}", input_tokens, output_tokens, input_tokens + output_tokens)
             }
+            AgentEvent::TokenBudget { used, limit } => {
+                format!("Token Budget: used={} limit={}", used, limit)
+            }
+            AgentEvent::CostEstimate { model, usd, priced } => {
+                format!("Cost Estimate: ~${:.3} model={}{}", usd, model, if *priced { "" } else { " (no known price)" })
+            }
+            AgentEvent::TodoUpdated { todos } => {
+                format!("TodoUpdated: {} items", todos.len())
+            }
+            AgentEvent::TimeLimitReached { limit } => {
+                format!("TimeLimitReached: {}s", limit.as_secs())
+            }
+            AgentEvent::PermissionTimedOut { request_id, call, response } => {
+                format!("PermissionTimedOut: {} - {} resolved as {:?}", request_id, call.tool_name, response)
+            }
+            AgentEvent::CommandForbidden { call, pattern, response } => {
+                format!("CommandForbidden: {} matched {:?}, resolved as {:?}", call.tool_name, pattern, response)
+            }
+            AgentEvent::VerificationCompleted { command, success, attempt, .. } => {
+                format!("VerificationCompleted: {} success={} attempt={}", command, success, attempt)
+            }
+            AgentEvent::FilesFormatted { results } => {
+                let failed = results.iter().filter(|r| !r.success).count();
+                format!("FilesFormatted: {} files, {} failed", results.len(), failed)
+            }
+            AgentEvent::SelfCheckRequested { attempt } => {
+                format!("SelfCheckRequested: attempt {}", attempt)
+            }
+            AgentEvent::TaskRestarting { attempt, max_retries, delay, error } => {
+                format!("TaskRestarting: attempt {}/{} in {}s after {}", attempt, max_retries, delay.as_secs(), error)
+            }
+            AgentEvent::TraceCompacted { tokens_before, tokens_after, messages } => {
+                format!("TraceCompacted: {} messages, ~{} -> ~{} tokens", messages, tokens_before, tokens_after)
+            }
+            AgentEvent::McpReconnected { server, tool_name, attempts } => {
+                format!("McpReconnected: {} for {} after {} attempt(s)", server, tool_name, attempts)
+            }
+            AgentEvent::RateLimited { provider, wait } => {
+                format!("RateLimited: {} waiting {}ms", provider, wait.as_millis())
+            }
         };
 
         let log_line = format!("[{}] {}\n", timestamp.format("%Y-%m-%d %H:%M:%S%.3f"), event_str);
@@ -1,7 +1,11 @@
 pub mod stdout;
 pub mod pretty;
 pub mod log;
+pub mod json;
+pub mod replay;
 
-pub use stdout::StdoutEventManager;
+pub use stdout::{StdoutEventManager, Verbosity};
 pub use pretty::PrettyFormatter;
-pub use log::FileEventLogger;
\ No newline at end of file
+pub use log::FileEventLogger;
+pub use json::JsonEventManager;
+pub use replay::{trace_events, parse_event_log, replay_events};
\ No newline at end of file
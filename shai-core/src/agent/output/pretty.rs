@@ -1,25 +1,75 @@
 use chrono::Utc;
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
 use termimad::crossterm::style::Color;
-use termimad::{rgb, MadSkin};
+use termimad::{rgb, CompoundStyle, MadSkin};
 use crate::agent::{AgentError, AgentEvent};
 use crate::tools::{ToolCall, ToolResult};
+use crate::tools::mcp::MCP_TOOL_SEPARATOR;
+use crate::config::config::{OutputConfig, OutputPalette};
+
+/// Outputs bigger than this are never parsed as JSON for display
+const MAX_JSON_PARSE_BYTES: usize = 512 * 1024;
+/// Longer JSON preview lines are cut
+const MAX_JSON_LINE_CHARS: usize = 160;
+
+/// Styles of the JSON previews, keys in the bold of the skin
+struct JsonSkin {
+    key: CompoundStyle,
+    string: CompoundStyle,
+    number: CompoundStyle,
+    literal: CompoundStyle,
+}
+
+impl JsonSkin {
+    fn new(skin: &MadSkin) -> Self {
+        let mut key = skin.bold.clone();
+        key.set_fg(Color::Cyan);
+        Self {
+            key,
+            string: CompoundStyle::with_fg(Color::Green),
+            number: CompoundStyle::with_fg(Color::Yellow),
+            literal: CompoundStyle::with_fg(Color::Magenta),
+        }
+    }
+}
 
 /// Pretty formatter that formats agent events into strings for display
 pub struct PrettyFormatter {
     skin: MadSkin,
+    json_skin: JsonSkin,
     max_preview_lines: usize,
+    show_reasoning: bool,
+    palette: OutputPalette,
 }
 
 impl PrettyFormatter {
     pub fn new() -> Self {
-        Self::with_max_preview_lines(10)
+        Self::with_config(&OutputConfig::default())
     }
 
     pub fn with_max_preview_lines(max_preview_lines: usize) -> Self {
+        Self::with_config(&OutputConfig { max_preview_lines, ..OutputConfig::default() })
+    }
+
+    /// Formatter with the preview length, reasoning and colors of the `output` config section
+    pub fn with_config(config: &OutputConfig) -> Self {
         let mut skin = MadSkin::default_dark();
         skin.code_block.set_fgbg(Color::DarkGrey, Color::Reset);
-        Self { skin, max_preview_lines }
+        Self {
+            json_skin: JsonSkin::new(&skin),
+            skin,
+            max_preview_lines: config.max_preview_lines,
+            show_reasoning: config.show_reasoning,
+            palette: config.palette,
+        }
+    }
+
+    /// The skin with its paragraphs in `color`, and bold text a bit lighter
+    fn colored_skin(&self, [r, g, b]: [u8; 3]) -> MadSkin {
+        let mut skin = self.skin.clone();
+        skin.paragraph.set_fg(rgb(r, g, b));
+        skin.bold.set_fg(rgb(r.saturating_add(50), g.saturating_add(50), b.saturating_add(50)));
+        skin
     }
 
     /// Format an agent event into a displayable string
@@ -28,6 +78,14 @@ impl PrettyFormatter {
             AgentEvent::ThinkingStart => {
                 None
             },
+            AgentEvent::BrainDelta { .. } => {
+                // the whole message is displayed once the BrainResult arrives
+                None
+            },
+            AgentEvent::Reasoning { .. } => {
+                // shown with the ✻ glyph as part of the BrainResult that follows
+                None
+            },
             AgentEvent::BrainResult { thought, .. } => {
                 self.format_thinking(thought)
             },
@@ -50,8 +108,7 @@ impl PrettyFormatter {
                 if lines.len() == 1 {
                     // Single line: ANSI prefix + markdown content
                     output.push_str("\x1b[2m> \x1b[0m");
-                    let mut user_skin = self.skin.clone();
-                    user_skin.paragraph.set_fg(rgb(120, 120, 120)); // Dark grey
+                    let user_skin = self.colored_skin(self.palette.dim);
                     output.push_str(&user_skin.term_text(input).to_string());
                 } else {
                     // Multi-line: ANSI prefix for first line, then markdown for rest
@@ -61,8 +118,7 @@ impl PrettyFormatter {
                         let remaining_content = lines[1..].join("\n");
                         if !remaining_content.trim().is_empty() {
                             output.push('\n');
-                            let mut user_skin = self.skin.clone();
-                            user_skin.paragraph.set_fg(rgb(120, 120, 120)); // Dark grey
+                            let user_skin = self.colored_skin(self.palette.dim);
                             let formatted_content = user_skin.term_text(&remaining_content).to_string();
                             // Add 2-space indent to each line
                             for line in formatted_content.lines() {
@@ -87,9 +143,7 @@ impl PrettyFormatter {
             },
             AgentEvent::Error { error } => {
                 let markdown = format!("❌ **Error:** {}", error);
-                let mut error_skin = self.skin.clone();
-                error_skin.paragraph.set_fg(rgb(255, 100, 100)); // Red for errors
-                error_skin.bold.set_fg(rgb(255, 150, 150)); // Light red for bold
+                let error_skin = self.colored_skin(self.palette.error);
                 Some(error_skin.term_text(&markdown).to_string())
             },
             AgentEvent::Completed { success, message } => {
@@ -98,21 +152,83 @@ impl PrettyFormatter {
                 } else {
                     format!("❌ **Failed:** {}", message)
                 };
-                
-                let mut completion_skin = self.skin.clone();
-                if *success {
-                    completion_skin.paragraph.set_fg(rgb(100, 255, 100)); // Green for success
-                    completion_skin.bold.set_fg(rgb(150, 255, 150)); // Light green for bold
-                } else {
-                    completion_skin.paragraph.set_fg(rgb(255, 100, 100)); // Red for failure
-                    completion_skin.bold.set_fg(rgb(255, 150, 150)); // Light red for bold
-                }
+
+                let completion_skin = self.colored_skin(if *success { self.palette.success } else { self.palette.error });
                 
                 Some(completion_skin.term_text(&markdown).to_string())
             },
             AgentEvent::TokenUsage { .. } => {
                 // Don't display token usage in the main output - it's handled by /tokens command
                 None
+            }
+            AgentEvent::TokenBudget { .. } => {
+                // same, only the error sent when the budget runs out is shown
+                None
+            },
+            AgentEvent::CostEstimate { .. } => {
+                // shown by /tokens and in the summary at the end of a headless run
+                None
+            },
+            AgentEvent::TodoUpdated { .. } => {
+                // agent-side changes are already shown through the todo_write tool result
+                None
+            },
+            AgentEvent::TimeLimitReached { limit } => {
+                let markdown = format!("⏱ **Time limit reached** after {}s, stopping the agent", limit.as_secs());
+                let error_skin = self.colored_skin(self.palette.error);
+                Some(error_skin.term_text(&markdown).to_string())
+            },
+            AgentEvent::PermissionTimedOut { call, response, .. } => {
+                Some(format!("\x1b[2;31m⏱ Permission request for {} timed out, resolved as {:?}\x1b[0m",
+                    Self::tool_display_name(&call.tool_name), response))
+            },
+            AgentEvent::CommandForbidden { call, pattern, .. } => {
+                let command = call.parameters.get("command").and_then(|c| c.as_str()).unwrap_or_default();
+                Some(format!("\x1b[2;31m⛔ {} forbidden by the deny-list ({}): {}\x1b[0m",
+                    Self::tool_display_name(&call.tool_name), pattern, command))
+            },
+            AgentEvent::VerificationCompleted { command, success, output, attempt } => {
+                let mut text = if *success {
+                    format!("\x1b[32m●\x1b[0m \x1b[1mVerify\x1b[0m({})\n  ⎿ \x1b[1mPassed\x1b[0m", command)
+                } else {
+                    format!("\x1b[31m●\x1b[0m \x1b[1mVerify\x1b[0m({})\n  ⎿ \x1b[2;31mFailed (attempt {})\x1b[0m", command, attempt)
+                };
+                if !success {
+                    for line in output.lines().take(self.max_preview_lines) {
+                        text.push_str(&format!("\n      {}", line));
+                    }
+                }
+                Some(text)
+            },
+            AgentEvent::FilesFormatted { results } => {
+                let failed: Vec<_> = results.iter().filter(|r| !r.success).collect();
+                let mut text = if failed.is_empty() {
+                    format!("\x1b[32m●\x1b[0m \x1b[1mFormat\x1b[0m\n  ⎿ {} file(s) formatted", results.len())
+                } else {
+                    format!("\x1b[33m●\x1b[0m \x1b[1mFormat\x1b[0m\n  ⎿ \x1b[2;33m{} of {} file(s) could not be formatted\x1b[0m", failed.len(), results.len())
+                };
+                for result in failed {
+                    text.push_str(&format!("\n      {} ({}): {}", result.file, result.command,
+                        result.output.lines().next().unwrap_or_default()));
+                }
+                Some(text)
+            },
+            AgentEvent::SelfCheckRequested { .. } => {
+                Some("\x1b[2m✓ Asked the agent to check its changes with a test or a build before stopping\x1b[0m".to_string())
+            },
+            AgentEvent::TaskRestarting { attempt, max_retries, delay, error } => {
+                Some(format!("\x1b[2;33m↻ Restarting the task in {}s (retry {}/{}) after: {}\x1b[0m",
+                    delay.as_secs(), attempt, max_retries, error))
+            },
+            AgentEvent::TraceCompacted { tokens_before, tokens_after, messages } => {
+                Some(format!("\x1b[2m⇣ Compacted {} older messages to stay within the context window (~{} → ~{} tokens)\x1b[0m",
+                    messages, tokens_before, tokens_after))
+            },
+            AgentEvent::McpReconnected { server, tool_name, attempts } => {
+                Some(format!("\x1b[2;33m↻ MCP '{}' reconnected for {} (attempt {})\x1b[0m", server, tool_name, attempts))
+            },
+            AgentEvent::RateLimited { provider, wait } => {
+                Some(format!("\x1b[2;33m⏳ rate-limited on {}, waiting {:.1}s…\x1b[0m", provider, wait.as_secs_f64()))
             },
         }.map(|s| format!("\n{}", s))
     }
@@ -122,15 +238,14 @@ impl PrettyFormatter {
         match thought {
             Ok(ChatMessage::Assistant { content, reasoning_content, .. }) => {
                 let content_empty = content.as_ref().map_or(true, |c| matches!(c, ChatMessageContent::Text(t) if t.trim().is_empty()));
-                let reasoning_empty = reasoning_content.as_deref().map_or(true, |r| r.trim().is_empty());
+                let reasoning_empty = !self.show_reasoning || reasoning_content.as_deref().map_or(true, |r| r.trim().is_empty());
                 if content_empty && reasoning_empty { return None; }
                 
                 let parts: Vec<_> = [
                     reasoning_content.as_deref()
-                        .filter(|r| !r.trim().is_empty())
+                        .filter(|r| self.show_reasoning && !r.trim().is_empty())
                         .map(|r| {
-                            let mut reasoning_skin = self.skin.clone();
-                            reasoning_skin.paragraph.set_fg(rgb(120, 120, 120)); // Dim text
+                            let reasoning_skin = self.colored_skin(self.palette.dim);
                             format!("\x1b[2m✻ {}\x1b[0m", reasoning_skin.term_text(r).to_string())
                         }),
                     content.as_ref().and_then(|c| match c {
@@ -142,9 +257,7 @@ impl PrettyFormatter {
                 (!parts.is_empty()).then(|| parts.join("\n"))
             }
             Err(err) => {
-                let mut error_skin = self.skin.clone();
-                error_skin.paragraph.set_fg(rgb(255, 100, 100));
-                error_skin.bold.set_fg(rgb(255, 150, 150));
+                let error_skin = self.colored_skin(self.palette.error);
                 Some(error_skin.text(&format!("● **Error:** {}", err), None).to_string())
             }
             _ => None,
@@ -153,7 +266,7 @@ impl PrettyFormatter {
 
     /// Format tool started
     pub fn format_tool_started(&self, call: &ToolCall) -> String {
-        let tool_name = Self::capitalize_first(&call.tool_name);
+        let tool_name = Self::tool_display_name(&call.tool_name);
         let context = Self::extract_primary_param(&call.parameters, &call.tool_name);
         
         let mut output = String::new();
@@ -167,7 +280,7 @@ impl PrettyFormatter {
 
     /// Format tool started
     pub fn format_tool_running(&self, call: &ToolCall) -> String {
-        let tool_name = Self::capitalize_first(&call.tool_name);
+        let tool_name = Self::tool_display_name(&call.tool_name);
         let context = Self::extract_primary_param(&call.parameters, &call.tool_name);
         
         let mut output = String::new();
@@ -181,18 +294,35 @@ impl PrettyFormatter {
     }
 
 
-    /// Format tool result
-    fn format_tool_result(&self, call: &ToolCall, result: &ToolResult) -> String {
-        let tool_name = Self::capitalize_first(&call.tool_name);
+    /// Format the first line of a tool result, bullet colored by outcome
+    pub fn format_tool_header(&self, call: &ToolCall, result: &ToolResult) -> String {
+        let tool_name = Self::tool_display_name(&call.tool_name);
         let context = Self::extract_primary_param(&call.parameters, &call.tool_name);
         
         let color = if matches!(result, ToolResult::Success{..}) { "\x1b[32m" } else { "\x1b[31m" };
-        let mut output = String::new();
         if let Some((_,ctx)) = context {
-            output.push_str(&format!("{}●\x1b[0m \x1b[1m{}\x1b[0m({})\n", color, tool_name, ctx));
+            format!("{}●\x1b[0m \x1b[1m{}\x1b[0m({})", color, tool_name, ctx)
         } else {
-            output.push_str(&format!("{}●\x1b[0m \x1b[1m{}\x1b[0m\n", color, tool_name));
+            format!("{}●\x1b[0m \x1b[1m{}\x1b[0m", color, tool_name)
         }
+    }
+
+    /// Time a tool call took: milliseconds under a second, else seconds with one decimal
+    pub fn format_elapsed(duration: std::time::Duration) -> String {
+        if duration.as_millis() < 1000 {
+            format!("{}ms", duration.as_millis())
+        } else {
+            format!("{:.1}s", duration.as_secs_f64())
+        }
+    }
+
+    /// Format tool result
+    fn format_tool_result(&self, call: &ToolCall, result: &ToolResult) -> String {
+        let mut output = self.format_tool_header(call, result);
+        if let Some(duration) = result.duration() {
+            output.push_str(&format!(" \x1b[2m({})\x1b[0m", Self::format_elapsed(duration)));
+        }
+        output.push('\n');
 
         match result {
             ToolResult::Success { output: tool_output, .. } => {
@@ -210,8 +340,10 @@ impl PrettyFormatter {
                         output.push_str(&format!("  ⎿ \x1b[1m{}\x1b[0m lines, \x1b[1m{}\x1b[0m chars", lines, chars));
                     }
                     
-                    // Show first N lines for user display only for specific tools
-                    if matches!(call.tool_name.as_str(), "ls" | "bash" | "edit" | "multiedit" | "find" | "todo_read" | "todo_write") {
+                    // JSON results (often from MCP tools) are shown indented whatever the tool
+                    if let Some(json) = Self::parse_json_output(tool_output) {
+                        output.push_str(&self.format_json_preview(&json));
+                    } else if matches!(call.tool_name.as_str(), "ls" | "bash" | "bash_status" | "edit" | "env" | "multiedit" | "apply_patch" | "undo" | "find" | "grep" | "todo_read" | "todo_write") {
                         let preview_lines: Vec<&str> = tool_output.lines().take(self.max_preview_lines).collect();
                         if !preview_lines.is_empty() {
                             let mut markdown_content = String::new();
@@ -242,11 +374,81 @@ impl PrettyFormatter {
         output
     }
 
+    /// Only outputs that fully parse as a JSON object or array are treated as JSON,
+    /// huge outputs are not parsed at all since only a preview is shown
+    fn parse_json_output(output: &str) -> Option<serde_json::Value> {
+        let trimmed = output.trim();
+        if trimmed.len() > MAX_JSON_PARSE_BYTES || !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+            return None;
+        }
+        serde_json::from_str::<serde_json::Value>(trimmed).ok()
+            .filter(|value| value.is_object() || value.is_array())
+    }
+
+    /// Indented and colored JSON, capped to the preview lines and to a max line width
+    fn format_json_preview(&self, json: &serde_json::Value) -> String {
+        let pretty = serde_json::to_string_pretty(json).unwrap_or_default();
+        let total = pretty.lines().count();
+
+        let mut preview = String::from("\n");
+        for line in pretty.lines().take(self.max_preview_lines) {
+            let line = match line.char_indices().nth(MAX_JSON_LINE_CHARS) {
+                Some((cut, _)) => format!("{}…", &line[..cut]),
+                None => line.to_string(),
+            };
+            preview.push_str(&format!("      {}\n", self.colorize_json_line(&line)));
+        }
+        if total > self.max_preview_lines {
+            let more = format!("... {} more lines", total - self.max_preview_lines);
+            preview.push_str(&format!("      {}", self.colored_skin(self.palette.dim).term_text(&more)));
+        }
+        preview
+    }
+
+    /// Color one line of pretty-printed JSON: keys, strings, numbers and literals.
+    /// Lines cut in the middle of a string simply keep the string color until the end.
+    fn colorize_json_line(&self, line: &str) -> String {
+        let mut out = String::new();
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            match c {
+                '"' => {
+                    let mut end = line.len();
+                    let mut escaped = false;
+                    for (i, c) in chars.by_ref() {
+                        match c {
+                            _ if escaped => escaped = false,
+                            '\\' => escaped = true,
+                            '"' => { end = i + 1; break; }
+                            _ => {}
+                        }
+                    }
+                    let is_key = line[end..].trim_start().starts_with(':');
+                    let style = if is_key { &self.json_skin.key } else { &self.json_skin.string };
+                    out.push_str(&style.apply_to(&line[start..end]).to_string());
+                }
+                '-' | '0'..='9' | 't' | 'f' | 'n' => {
+                    let mut end = start + c.len_utf8();
+                    while let Some(&(i, c)) = chars.peek() {
+                        if !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')) { break; }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    let style = if c == '-' || c.is_ascii_digit() { &self.json_skin.number } else { &self.json_skin.literal };
+                    out.push_str(&style.apply_to(&line[start..end]).to_string());
+                }
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+
     /// Extract the most relevant parameter for display context
     pub fn extract_primary_param(args: &serde_json::Value, tool_name: &str) -> Option<(String,String)> {
         if let Some(obj) = args.as_object() {
             
             // Common parameter names to look for, in order of preference
+            let tool_name = tool_name.split_once(MCP_TOOL_SEPARATOR).map_or(tool_name, |(_, tool)| tool);
             let param_names = match tool_name {
                 "read" | "write" | "edit" | "multiedit" => vec!["file_path", "path"],
                 "ls" | "glob" => vec!["path", "pattern"],
@@ -288,6 +490,14 @@ impl PrettyFormatter {
         path.to_string()
     }
 
+    /// Name of a tool as shown to the user, `server › Tool` for a prefixed MCP tool
+    pub fn tool_display_name(tool_name: &str) -> String {
+        match tool_name.split_once(MCP_TOOL_SEPARATOR) {
+            Some((server, tool)) => format!("{} › {}", server, Self::capitalize_first(tool)),
+            None => Self::capitalize_first(tool_name),
+        }
+    }
+
     /// Capitalize first letter of string
     pub fn capitalize_first(s: &str) -> String {
         let mut chars = s.chars();
@@ -351,4 +561,81 @@ impl Default for PrettyFormatter {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_output_requires_full_document() {
+        assert!(PrettyFormatter::parse_json_output("  {\"a\": [1, 2]}\n").is_some());
+        assert!(PrettyFormatter::parse_json_output("[]").is_some());
+        assert!(PrettyFormatter::parse_json_output("{\"a\": 1} trailing text").is_none());
+        assert!(PrettyFormatter::parse_json_output("{\"a\": ").is_none());
+        assert!(PrettyFormatter::parse_json_output("42").is_none());
+        assert!(PrettyFormatter::parse_json_output("[INFO] build ok").is_none());
+    }
+
+    #[test]
+    fn test_json_preview_is_capped() {
+        let formatter = PrettyFormatter::with_max_preview_lines(3);
+        let json = PrettyFormatter::parse_json_output(r#"{"name": "shai", "tags": ["a", "b", "c"], "ok": true}"#).unwrap();
+        let preview = formatter.format_json_preview(&json);
+
+        assert_eq!(preview.lines().filter(|l| !l.is_empty()).count(), 4);
+        let key = formatter.json_skin.key.apply_to("\"name\"");
+        let value = formatter.json_skin.string.apply_to("\"shai\"");
+        assert!(preview.contains(&format!("{}: {},", key, value)), "{:?}", preview);
+        assert!(preview.contains("... 6 more lines"));
+    }
+
+    #[test]
+    fn test_prefixed_mcp_tools_show_their_server() {
+        assert_eq!(PrettyFormatter::tool_display_name("github__search_issues"), "github › Search_issues");
+        assert_eq!(PrettyFormatter::tool_display_name("read"), "Read");
+
+        let args = serde_json::json!({"other": "x", "file_path": "notes.txt"});
+        assert_eq!(PrettyFormatter::extract_primary_param(&args, "fs__read").unwrap().0, "file_path");
+    }
+
+    #[test]
+    fn test_output_config_hides_reasoning_and_sets_colors() {
+        let thought = Ok(ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("the answer".to_string())),
+            reasoning_content: Some("let me think".to_string()),
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        });
+
+        let shown = PrettyFormatter::new().format_thinking(&thought).unwrap();
+        assert!(shown.contains("let me think"), "{}", shown);
+
+        let config: OutputConfig = serde_json::from_str(r#"{"show_reasoning": false, "palette": {"error": [180, 0, 0]}}"#).unwrap();
+        assert_eq!(config.max_preview_lines, 10);
+        assert_eq!(config.palette.dim, OutputPalette::default().dim);
+        let formatter = PrettyFormatter::with_config(&config);
+        let hidden = formatter.format_thinking(&thought).unwrap();
+        assert!(!hidden.contains("let me think"), "{}", hidden);
+        assert!(hidden.contains("the answer"));
+
+        let error = formatter.format_event(&AgentEvent::Error { error: "boom".to_string() }).unwrap();
+        assert!(error.contains("180;0;0"), "{:?}", error);
+    }
+
+    #[test]
+    fn test_tool_result_shows_its_duration() {
+        assert_eq!(PrettyFormatter::format_elapsed(std::time::Duration::from_millis(340)), "340ms");
+        assert_eq!(PrettyFormatter::format_elapsed(std::time::Duration::from_millis(2345)), "2.3s");
+
+        let call = ToolCall { tool_call_id: "call_1".to_string(), tool_name: "bash".to_string(), parameters: serde_json::json!({"command": "make"}) };
+        let mut result = ToolResult::success("done".to_string());
+        result.set_duration(std::time::Duration::from_millis(2300));
+        let shown = PrettyFormatter::new().format_tool_result(&call, &result);
+        assert!(shown.lines().next().unwrap().contains("(2.3s)"), "{}", shown);
+
+        assert!(!PrettyFormatter::new().format_tool_result(&call, &ToolResult::Denied).contains("ms)"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+use chrono::{TimeDelta, Utc};
+use openai_dive::v1::resources::chat::ChatMessage;
+use crate::agent::{AgentEvent, AgentEventHandler};
+use crate::agent::trace::content_text;
+use crate::tools::{ToolCall, ToolResult};
+
+/// Events the agent would have emitted while building `trace`, to render a saved session again
+/// without the LLM. Tool durations are unknown and set to zero, results lose their metadata
+pub fn trace_events(trace: &[ChatMessage]) -> Vec<AgentEvent> {
+    let mut events = Vec::new();
+    // tool calls of the last assistant message still waiting for their result
+    let mut pending: Vec<ToolCall> = Vec::new();
+
+    for message in trace {
+        match message {
+            ChatMessage::User { content, .. } => {
+                events.push(AgentEvent::UserInput { input: content_text(content) });
+            }
+            ChatMessage::Assistant { tool_calls, reasoning_content, .. } => {
+                if let Some(text) = reasoning_content.as_ref().filter(|r| !r.trim().is_empty()) {
+                    events.push(AgentEvent::Reasoning { timestamp: Utc::now(), text: text.clone() });
+                }
+                events.push(AgentEvent::BrainResult { timestamp: Utc::now(), thought: Ok(message.clone()) });
+                pending = tool_calls.iter().flatten()
+                    .map(|tc| ToolCall {
+                        tool_call_id: tc.id.clone(),
+                        tool_name: tc.function.name.clone(),
+                        parameters: serde_json::from_str(&tc.function.arguments)
+                            .unwrap_or_else(|_| serde_json::Value::String(tc.function.arguments.clone())),
+                    })
+                    .collect();
+                events.extend(pending.iter().map(|call| AgentEvent::ToolCallStarted { timestamp: Utc::now(), call: call.clone() }));
+            }
+            ChatMessage::Tool { tool_call_id, content } => {
+                // a result without its call has nothing to be displayed with
+                if let Some(pos) = pending.iter().position(|call| &call.tool_call_id == tool_call_id) {
+                    events.push(AgentEvent::ToolCallCompleted {
+                        duration: TimeDelta::zero(),
+                        call: pending.remove(pos),
+                        result: ToolResult::from_trace_text(&content_text(content)),
+                    });
+                }
+            }
+            ChatMessage::System { .. } | ChatMessage::Developer { .. } => {}
+        }
+    }
+    events
+}
+
+/// Events of a log written one JSON event per line (`shai --json`), blank lines are skipped
+pub fn parse_event_log(log: &str) -> Result<Vec<AgentEvent>, serde_json::Error> {
+    log.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// Feed the events to `handler` as if they were live, waiting `pace` between two events
+pub async fn replay_events<H: AgentEventHandler>(events: Vec<AgentEvent>, handler: &H, pace: Option<Duration>) {
+    for (i, event) in events.into_iter().enumerate() {
+        if let Some(pace) = pace.filter(|_| i > 0) {
+            tokio::time::sleep(pace).await;
+        }
+        handler.handle_event(event).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::{ChatMessageContent, Function, ToolCall as LlmToolCall};
+
+    #[test]
+    fn test_trace_events_rebuild_tool_calls() {
+        let trace = vec![
+            ChatMessage::System { content: ChatMessageContent::Text("be nice".to_string()), name: None },
+            ChatMessage::User { content: ChatMessageContent::Text("list files".to_string()), name: None },
+            ChatMessage::Assistant {
+                content: None,
+                reasoning_content: None,
+                refusal: None,
+                name: None,
+                audio: None,
+                tool_calls: Some(vec![LlmToolCall {
+                    id: "call_1".to_string(),
+                    r#type: "function".to_string(),
+                    function: Function { name: "ls".to_string(), arguments: "{\"path\": \".\"}".to_string() },
+                }]),
+            },
+            ChatMessage::Tool { tool_call_id: "call_1".to_string(), content: ChatMessageContent::Text("The tool failed with the following error: no such dir".to_string()) },
+            ChatMessage::Tool { tool_call_id: "orphan".to_string(), content: ChatMessageContent::Text("lost".to_string()) },
+        ];
+
+        let events = trace_events(&trace);
+        assert_eq!(events.len(), 4, "{:?}", events);
+        assert!(matches!(&events[0], AgentEvent::UserInput { input } if input == "list files"));
+        assert!(matches!(&events[1], AgentEvent::BrainResult { thought: Ok(ChatMessage::Assistant { .. }), .. }));
+        assert!(matches!(&events[2], AgentEvent::ToolCallStarted { call, .. } if call.parameters["path"] == "."));
+        match &events[3] {
+            AgentEvent::ToolCallCompleted { call, result, .. } => {
+                assert_eq!(call.tool_name, "ls");
+                assert_eq!(result, &ToolResult::error("no such dir".to_string()));
+            }
+            other => panic!("expected a completed tool call, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_event_log() {
+        let log = "{\"type\":\"user_input\",\"input\":\"hi\"}\n\n{\"type\":\"completed\",\"success\":true,\"message\":\"done\"}\n";
+        let events = parse_event_log(log).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[1], AgentEvent::Completed { success: true, message } if message == "done"));
+        assert!(parse_event_log("{\"type\":\"nope\"}").is_err());
+    }
+}
@@ -1,17 +1,109 @@
 use std::io::{self, Write};
+use std::sync::RwLock;
 use async_trait::async_trait;
+use openai_dive::v1::resources::chat::ChatMessage;
 use crate::agent::{AgentEvent, AgentEventHandler};
 use super::pretty::PrettyFormatter;
+use crate::config::config::OutputConfig;
+
+/// How much of each event the stdout manager renders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// tool names and assistant answers only
+    Quiet,
+    #[default]
+    Normal,
+    /// full tool parameters and longer previews
+    Verbose,
+}
+
+impl Verbosity {
+    /// Normal keeps the configured preview length, verbose shows at least 50 lines
+    fn max_preview_lines(self, configured: usize) -> usize {
+        match self {
+            Verbosity::Quiet => 0,
+            Verbosity::Normal => configured,
+            Verbosity::Verbose => configured.max(50),
+        }
+    }
+}
 
 /// Stdout event manager that formats and prints agent activity in a user-friendly way
 pub struct StdoutEventManager {
-    formatter: PrettyFormatter,
+    formatter: RwLock<PrettyFormatter>,
+    verbosity: RwLock<Verbosity>,
+    output: OutputConfig,
 }
 
 impl StdoutEventManager {
     pub fn new() -> Self {
+        Self::with_verbosity(Verbosity::Normal)
+    }
+
+    pub fn with_verbosity(verbosity: Verbosity) -> Self {
+        Self::with_config(verbosity, OutputConfig::default())
+    }
+
+    /// Manager rendering with the `output` config section, the verbosity adjusts its preview length
+    pub fn with_config(verbosity: Verbosity, output: OutputConfig) -> Self {
         Self {
-            formatter: PrettyFormatter::new(),
+            formatter: RwLock::new(Self::formatter(verbosity, &output)),
+            verbosity: RwLock::new(verbosity),
+            output,
+        }
+    }
+
+    fn formatter(verbosity: Verbosity, output: &OutputConfig) -> PrettyFormatter {
+        PrettyFormatter::with_config(&OutputConfig {
+            max_preview_lines: verbosity.max_preview_lines(output.max_preview_lines),
+            ..output.clone()
+        })
+    }
+
+    /// Change the level of detail of the next events
+    pub fn set_verbosity(&self, verbosity: Verbosity) {
+        *self.formatter.write().unwrap() = Self::formatter(verbosity, &self.output);
+        *self.verbosity.write().unwrap() = verbosity;
+    }
+
+    pub fn verbosity(&self) -> Verbosity {
+        *self.verbosity.read().unwrap()
+    }
+
+    fn render(&self, event: &AgentEvent) -> Option<String> {
+        let formatter = self.formatter.read().unwrap();
+        match (self.verbosity(), event) {
+            (Verbosity::Quiet, AgentEvent::ToolCallCompleted { call, result, .. }) => {
+                Some(format!("\n{}", formatter.format_tool_header(call, result)))
+            }
+            (Verbosity::Quiet, AgentEvent::BrainResult { timestamp, thought: Ok(ChatMessage::Assistant { content, tool_calls, name, audio, refusal, .. }) }) => {
+                // drop the reasoning, keep the answer
+                formatter.format_event(&AgentEvent::BrainResult {
+                    timestamp: *timestamp,
+                    thought: Ok(ChatMessage::Assistant {
+                        content: content.clone(),
+                        reasoning_content: None,
+                        tool_calls: tool_calls.clone(),
+                        name: name.clone(),
+                        audio: audio.clone(),
+                        refusal: refusal.clone(),
+                    }),
+                })
+            }
+            (Verbosity::Quiet, AgentEvent::UserInput { .. }) => None,
+            (Verbosity::Verbose, AgentEvent::ToolCallCompleted { call, .. }) => {
+                let mut text = formatter.format_event(event)?;
+                let parameters = formatter.format_toolcall(call, None);
+                if !parameters.trim().is_empty() {
+                    text.push_str("\n  \x1b[2mparameters:");
+                    for line in parameters.lines() {
+                        text.push_str(&format!("\n      {}", line));
+                    }
+                    text.push_str("\x1b[0m");
+                }
+                Some(text)
+            }
+            _ => formatter.format_event(event),
         }
     }
 }
@@ -19,7 +111,7 @@ impl StdoutEventManager {
 #[async_trait]
 impl AgentEventHandler for StdoutEventManager {
     async fn handle_event(&self, event: AgentEvent) {
-        if let Some(formatted) = self.formatter.format_event(&event) {
+        if let Some(formatted) = self.render(&event) {
             eprintln!("{}", formatted);
             let _ = io::stdout().flush();
         }
@@ -30,4 +122,36 @@ impl Default for StdoutEventManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use crate::tools::{ToolCall, ToolResult};
+
+    fn completed() -> AgentEvent {
+        AgentEvent::ToolCallCompleted {
+            duration: TimeDelta::zero(),
+            call: ToolCall {
+                tool_call_id: "call_1".to_string(),
+                tool_name: "bash".to_string(),
+                parameters: serde_json::json!({"command": "ls", "timeout": 5}),
+            },
+            result: ToolResult::success("a.txt\nb.txt".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_verbosity_controls_tool_details() {
+        let manager = StdoutEventManager::with_verbosity(Verbosity::Quiet);
+        let quiet = manager.render(&completed()).unwrap();
+        assert!(quiet.contains("Bash"));
+        assert!(!quiet.contains("a.txt"), "{}", quiet);
+
+        manager.set_verbosity(Verbosity::Verbose);
+        let verbose = manager.render(&completed()).unwrap();
+        assert!(verbose.contains("a.txt"), "{}", verbose);
+        assert!(verbose.contains("timeout: 5"), "{}", verbose);
+    }
+}
@@ -1,10 +1,13 @@
+use chrono::{DateTime, Utc};
 use openai_dive::v1::resources::chat::ChatMessage;
 use shai_llm::ToolCallMethod;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::{timeout, Duration};
 use crate::agent::AgentError;
+use crate::config::pricing::CostEstimate;
+use crate::tools::{TodoItem, TodoStatus};
 
-use super::{PermissionResponse, PublicAgentState, UserResponse};
+use super::{PermissionResponse, PublicAgentState, SamplingOverrides, StepToolChoice, SystemPromptOverride, TokenTotals, ToolTimeTotals, UserResponse};
 
 /// Commands that can be sent to a running agent
 #[derive(Debug, Clone)]
@@ -13,10 +16,20 @@ pub enum AgentRequest {
     Terminate,
     /// Stop the currently executing task
     StopCurrentTask,    
+    /// Cancel one running tool call, the other calls of the batch go on
+    CancelTool {
+        tool_call_id: String
+    },
     /// Get current agent state
     GetState,
     /// Get the conversation trace
     GetTrace,
+    /// Get the tokens used since the agent started, and the budget
+    GetTokenUsage,
+    /// Get the time spent running tools since the agent started
+    GetToolTime,
+    /// Get the trace, state and token usage as of the same instant, to save the agent
+    Checkpoint,
     /// Send user input (cancels current task, adds to trace, resumes agent)
     SendUserInput{
         input: String
@@ -29,6 +42,22 @@ pub enum AgentRequest {
     SwitchToolCallMethod {
         method: Option<ToolCallMethod>
     },
+    /// Hold the next brain step to a tool call, or to some tools only
+    ForceToolChoice {
+        choice: StepToolChoice
+    },
+    /// Sampling parameters of the next brain steps, over the ones of the brain
+    SetSampling {
+        sampling: SamplingOverrides
+    },
+    /// Instructions of the next brain steps, added to or in place of the brain's system prompt
+    SetSystemPrompt {
+        system_prompt: Option<SystemPromptOverride>
+    },
+    /// Run the tool calls of the next batches at once, or one after the other in their order
+    SetParallelToolCalls {
+        parallel: bool
+    },
     /// Send user input (cancels current task, adds to trace, resumes agent)
     UserQueryResponse{
         request_id: String,
@@ -41,6 +70,21 @@ pub enum AgentRequest {
     },
     /// Wait until the agent reaches the Paused state
     WaitTurn,
+    /// Get the todo list
+    GetTodos,
+    /// Append a pending item to the todo list
+    AddTodo {
+        content: String
+    },
+    /// Change the status of a todo item
+    SetTodoStatus {
+        id: String,
+        status: TodoStatus
+    },
+    /// Remove a todo item
+    RemoveTodo {
+        id: String
+    },
     /// Manage sudo mode: Some(true) = enable, Some(false) = disable, None = get status
     /// Always returns current sudo status after operation
     Sudo(Option<bool>),
@@ -62,14 +106,37 @@ pub enum AgentResponse {
     Trace {
         trace: Vec<ChatMessage>
     },
+    TokenUsage {
+        usage: TokenTotals,
+        budget: Option<u64>,
+        cost: Option<CostEstimate>
+    },
+    ToolTime {
+        totals: ToolTimeTotals
+    },
+    Checkpoint {
+        checkpoint: AgentCheckpoint
+    },
     SudoStatus {
         enabled: bool
     },
+    Todos {
+        todos: Vec<TodoItem>
+    },
     Error {
         error: String
     }
 }
 
+/// Snapshot of a running agent. Tool calls still running have no result in the trace yet
+#[derive(Debug, Clone)]
+pub struct AgentCheckpoint {
+    pub trace: Vec<ChatMessage>,
+    pub state: PublicAgentState,
+    pub token_usage: TokenTotals,
+    pub taken_at: DateTime<Utc>,
+}
+
 /// Commands that can be sent to a running agent
 #[derive(Debug)]
 pub struct SentCommand {
@@ -114,6 +181,23 @@ impl AgentController {
         self.send(AgentRequest::StopCurrentTask).await.map(|_| Ok(()))?
     }
 
+    /// Queue a StopCurrentTask without waiting for the agent's answer, for callers that can't
+    /// await such as a Drop. It runs before any command sent after it
+    pub fn stop_current_task_now(&self) -> Result<(), AgentError> {
+        let (tx, _) = oneshot::channel();
+        self.txcmd.send(SentCommand { command: AgentRequest::StopCurrentTask, backchannel: tx })
+            .map_err(|_| AgentError::SessionClosed)
+    }
+
+    /// Cancel a single running tool call, it completes with a cancelled result
+    pub async fn cancel_tool(&self, tool_call_id: String) -> Result<(), AgentError> {
+        match self.send(AgentRequest::CancelTool { tool_call_id }).await? {
+            AgentResponse::Ack => Ok(()),
+            AgentResponse::Error { error } => Err(AgentError::ExecutionError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected Ack response for CancelTool".to_string()))
+        }
+    }
+
     pub async fn set_method(&self, method:Option<ToolCallMethod>) -> Result<ToolCallMethod, AgentError> {
         match self.send(AgentRequest::SwitchToolCallMethod { method }).await? {
             AgentResponse::Method{method} => Ok(method),
@@ -121,6 +205,26 @@ impl AgentController {
         }
     }
 
+    /// Constrain the tool calls of the next brain step, the steps after it are free again
+    pub async fn force_tool_choice(&self, choice: StepToolChoice) -> Result<(), AgentError> {
+        self.send(AgentRequest::ForceToolChoice { choice }).await.map(|_| Ok(()))?
+    }
+
+    /// Sampling parameters of the brain steps to come, an empty one goes back to the brain's own
+    pub async fn set_sampling(&self, sampling: SamplingOverrides) -> Result<(), AgentError> {
+        self.send(AgentRequest::SetSampling { sampling }).await.map(|_| Ok(()))?
+    }
+
+    /// Instructions of the brain steps to come, None goes back to the brain's own system prompt
+    pub async fn set_system_prompt(&self, system_prompt: Option<SystemPromptOverride>) -> Result<(), AgentError> {
+        self.send(AgentRequest::SetSystemPrompt { system_prompt }).await.map(|_| Ok(()))?
+    }
+
+    /// Whether the tool calls of a batch may run at once, false runs them one after the other in their order
+    pub async fn set_parallel_tool_calls(&self, parallel: bool) -> Result<(), AgentError> {
+        self.send(AgentRequest::SetParallelToolCalls { parallel }).await.map(|_| Ok(()))?
+    }
+
     pub async fn send_user_input(&self, input: String) -> Result<(), AgentError> {
         self.send(AgentRequest::SendUserInput { input: input }).await.map(|_| Ok(()))?
     }
@@ -151,6 +255,67 @@ impl AgentController {
         }
     }
 
+    /// Tokens used since the agent started, with the token budget if one is set
+    pub async fn get_token_usage(&self) -> Result<(TokenTotals, Option<u64>), AgentError> {
+        match self.send(AgentRequest::GetTokenUsage).await? {
+            AgentResponse::TokenUsage { usage, budget, .. } => Ok((usage, budget)),
+            _ => Err(AgentError::InvalidResponse("Expected TokenUsage response".to_string()))
+        }
+    }
+
+    /// Estimated cost of the tokens used since the agent started, None when it has no model to price
+    pub async fn get_cost_estimate(&self) -> Result<Option<CostEstimate>, AgentError> {
+        match self.send(AgentRequest::GetTokenUsage).await? {
+            AgentResponse::TokenUsage { cost, .. } => Ok(cost),
+            _ => Err(AgentError::InvalidResponse("Expected TokenUsage response".to_string()))
+        }
+    }
+
+    /// Time spent running tools since the agent started, by tool
+    pub async fn get_tool_time(&self) -> Result<ToolTimeTotals, AgentError> {
+        match self.send(AgentRequest::GetToolTime).await? {
+            AgentResponse::ToolTime { totals } => Ok(totals),
+            _ => Err(AgentError::InvalidResponse("Expected ToolTime response".to_string()))
+        }
+    }
+
+    /// Snapshot of the agent, for the caller to save
+    pub async fn checkpoint(&self) -> Result<AgentCheckpoint, AgentError> {
+        match self.send(AgentRequest::Checkpoint).await? {
+            AgentResponse::Checkpoint { checkpoint } => Ok(checkpoint),
+            _ => Err(AgentError::InvalidResponse("Expected Checkpoint response".to_string()))
+        }
+    }
+
+    pub async fn get_todos(&self) -> Result<Vec<TodoItem>, AgentError> {
+        self.todos(AgentRequest::GetTodos).await
+    }
+
+    /// Add a pending todo, the agent sees it on its next step
+    pub async fn add_todo(&self, content: String) -> Result<Vec<TodoItem>, AgentError> {
+        self.todos(AgentRequest::AddTodo { content }).await
+    }
+
+    pub async fn set_todo_status(&self, id: String, status: TodoStatus) -> Result<Vec<TodoItem>, AgentError> {
+        self.todos(AgentRequest::SetTodoStatus { id, status }).await
+    }
+
+    pub async fn complete_todo(&self, id: String) -> Result<Vec<TodoItem>, AgentError> {
+        self.set_todo_status(id, TodoStatus::Completed).await
+    }
+
+    pub async fn remove_todo(&self, id: String) -> Result<Vec<TodoItem>, AgentError> {
+        self.todos(AgentRequest::RemoveTodo { id }).await
+    }
+
+    async fn todos(&self, request: AgentRequest) -> Result<Vec<TodoItem>, AgentError> {
+        match self.send(request).await? {
+            AgentResponse::Todos { todos } => Ok(todos),
+            AgentResponse::Error { error } => Err(AgentError::ExecutionError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected Todos response".to_string()))
+        }
+    }
+
     /// Wait until the agent reaches the Paused state
     pub async fn wait_turn(&self, timeout_ms: Option<u64>) -> Result<(), AgentError> {
         let (tx, rx) = oneshot::channel();
@@ -20,6 +20,12 @@ impl AgentCore {
                 }
                 Ok(())
             },
+            InternalAgentEvent::VerificationCompleted { result } => {
+                self.process_verification(result).await
+            },
+            InternalAgentEvent::FormattingCompleted { results } => {
+                self.process_formatting(results).await
+            },
             _ => {
                 Ok(())
             }
@@ -13,6 +13,7 @@ impl AgentCore {
                 // Silently ignore
             }
             InternalAgentEvent::ThinkingStart => {
+                self.compact_if_needed().await;
                 self.spawn_next_step().await;
             }
             _ => {
@@ -1,5 +1,6 @@
 use tokio_util::sync::CancellationToken;
 use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
 
 /// Internal agent status (contains channels and sync primitives)
 #[derive(Debug)]
@@ -24,7 +25,8 @@ pub enum InternalAgentState {
 
 
 /// Public agent status (clean version without internal channels/sync primitives)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
 pub enum PublicAgentState {
     /// Agent is starting up
     Starting,
@@ -1,12 +1,18 @@
 use crate::agent::Agent;
-use crate::tools::{AnyTool, ToolResult, ReadTool, LsTool};
+use crate::tools::{AnyTool, BashTool, EditTool, FetchTool, ToolCapability, ToolResult, ReadTool, LsTool, FixtureToolBox, FsOperationLog, FsOperationType, TodoReadTool, TodoStatus, TodoStorage, ToolOutputLimit, OUTPUT_BYTES_METADATA_KEY};
+use crate::runners::coder::CoderBrain;
 use crate::tools::tool;
 use super::brain::{ThinkerContext, Brain};
 use super::error::AgentError;
 use super::builder::AgentBuilder;
 use crate::logging::LoggingConfig;
-use super::{AgentRequest, PublicAgentState, ThinkerDecision};
-use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ToolCall, Function};
+use crate::config::agent::VerifyConfig;
+use crate::config::pricing::ModelPricing;
+use super::{forbidden_pattern, AgentEvent, AgentRequest, CommandDenyList, SamplingOverrides, StepToolChoice, SystemPromptOverride, InterceptorDecision, PermissionResponse, PublicAgentState, ThinkerDecision, ToolCallInterceptor, TraceIssue, check_tool_call_ids, repair_tool_call_ids, compact_trace, estimate_tokens};
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ToolCall, Function, ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChoice, ChatCompletionToolChoice};
+use openai_dive::v1::resources::model::ListModelResponse;
+use shai_llm::{LlmClient, ToolDescription};
+use shai_llm::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo};
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
 use schemars::JsonSchema;
@@ -52,6 +58,89 @@ impl SleepingTool {
     }
 }
 
+// Same as the sleeping tool but writes, so it needs a permission to run
+struct GuardedSleepingTool;
+
+#[tool(name = "sleeping_tool", description = "A tool that needs a permission before sleeping", capabilities = [ToolCapability::Write])]
+impl GuardedSleepingTool {
+    async fn execute(&self, params: SleepParams) -> ToolResult {
+        ToolResult::success("Finished sleeping".to_string())
+    }
+}
+
+// Same as the sleeping tool but polls, like checking on a background job
+struct PollingSleepingTool;
+
+#[tool(name = "sleeping_tool", description = "A tool that checks on something that keeps changing")]
+impl PollingSleepingTool {
+    fn polls(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, params: SleepParams) -> ToolResult {
+        ToolResult::success("Finished sleeping".to_string())
+    }
+}
+
+// Sleeps for the duration given in its parameters, to run calls of different lengths in one batch
+struct NapTool;
+
+#[tool(name = "nap", description = "A tool that sleeps for the duration it is given")]
+impl NapTool {
+    async fn execute(&self, params: SleepParams) -> ToolResult {
+        tokio::time::sleep(Duration::from_millis(params.duration_ms)).await;
+        ToolResult::success("Finished sleeping".to_string())
+    }
+}
+
+// Stands in for the bash tool, the one whose calls count as checking the changes
+struct CheckTool;
+
+#[tool(name = "bash", description = "A tool that pretends to run the tests of the project")]
+impl CheckTool {
+    async fn execute(&self, _params: SleepParams) -> ToolResult {
+        ToolResult::success("all tests passed".to_string())
+    }
+}
+
+// Test thinker that naps twice in parallel, briefly then for long, and pauses
+struct TwoNapsThinker {
+    called_tool: bool,
+}
+
+#[async_trait]
+impl Brain for TwoNapsThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        if self.called_tool {
+            return Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+                content: Some(ChatMessageContent::Text("we are done".to_string())),
+                reasoning_content: None,
+                tool_calls: None,
+                name: None,
+                audio: None,
+                refusal: None,
+            }));
+        }
+        self.called_tool = true;
+        let nap = |id: &str, duration_ms: u64| ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: Function {
+                name: "nap".to_string(),
+                arguments: format!("{{\"duration_ms\": {}}}", duration_ms),
+            },
+        };
+        Ok(ThinkerDecision::agent_continue(ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(vec![nap("call_short", 300), nap("call_long", 5000)]),
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
 struct MockLlm {
 
 }
@@ -329,6 +418,413 @@ async fn test_event_handling() {
     assert!(has_running_status, "Should have seen transition to Running status in events: {:?}", *events);
 }
 
+#[tokio::test]
+async fn test_max_duration_cancels_running_tool() {
+    init_test_logging();
+
+    let sleeping_tool: Box<dyn AnyTool> = Box::new(SleepingTool::new(5000));
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-max-duration-agent")
+        .goal("Test goal to start running")
+        .tools(vec![sleeping_tool])
+        .max_duration(Duration::from_millis(300))
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    let start_time = std::time::Instant::now();
+    let result = agent.run().await.expect("agent should stop cleanly");
+
+    assert!(start_time.elapsed() < Duration::from_millis(3000), "tool was not cancelled: {:?}", start_time.elapsed());
+    assert!(!result.success);
+    assert!(result.message.contains("time limit reached"), "{}", result.message);
+
+    let mut tripped = false;
+    while let Ok(event) = events.try_recv() {
+        tripped |= matches!(event, AgentEvent::TimeLimitReached { .. });
+    }
+    assert!(tripped, "TimeLimitReached should have been emitted");
+}
+
+#[tokio::test]
+async fn test_cancel_tool_only_stops_that_call() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(TwoNapsThinker { called_tool: false }))
+        .id("test-cancel-tool-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(NapTool) as Box<dyn AnyTool>])
+        .sudo()
+        .build();
+
+    let mut controller = agent.controller();
+    let mut events = agent.watch();
+    let start_time = std::time::Instant::now();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    controller.cancel_tool("call_long".to_string()).await.expect("call_long should be running");
+    assert!(controller.cancel_tool("call_unknown".to_string()).await.is_err());
+
+    // the short nap still finishes, then the agent ends its turn without waiting for the long one
+    controller.wait_turn(Some(3000)).await.expect("turn should end once the short nap is done");
+    assert!(start_time.elapsed() < Duration::from_millis(3000), "long nap was not cancelled: {:?}", start_time.elapsed());
+
+    let mut results = std::collections::HashMap::new();
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::ToolCallCompleted { call, result, .. } = event {
+            results.insert(call.tool_call_id, result.to_string());
+        }
+    }
+    assert_eq!(results.get("call_short").map(String::as_str), Some("Finished sleeping"));
+    assert!(results["call_long"].contains("cancelled"), "{}", results["call_long"]);
+
+    // both results are in the trace, so the next request stays valid for the provider
+    let trace = controller.get_trace().await.unwrap();
+    assert_eq!(trace.iter().filter(|message| matches!(message, ChatMessage::Tool { .. })).count(), 2);
+
+    controller.drop().await.expect("failed to drop the controller");
+    handle.await.unwrap().expect("agent should complete");
+}
+
+#[tokio::test]
+async fn test_unanswered_permission_times_out_to_deny() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-permission-timeout-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>])
+        .permission_timeout(Some(Duration::from_millis(200)))
+        .build();
+
+    let mut controller = agent.controller();
+    let mut events = agent.watch();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+
+    // nobody answers the permission request
+    let mut timed_out = None;
+    let result = tokio::time::timeout(Duration::from_secs(3), async {
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::PermissionTimedOut { response, .. }) => timed_out = Some(response),
+                Ok(AgentEvent::ToolCallCompleted { result, .. }) => return result,
+                Ok(_) => continue,
+                Err(e) => panic!("event channel closed: {:?}", e),
+            }
+        }
+    }).await.expect("permission request should not hang");
+
+    assert_eq!(timed_out, Some(PermissionResponse::Deny));
+    assert!(result.is_denied(), "tool should be denied: {:?}", result);
+
+    controller.drop().await.expect("failed to drop the controller");
+    handle.abort();
+}
+
+#[tokio::test]
+async fn test_failed_verification_is_fed_back_and_reported() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-verify-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>])
+        .verify(VerifyConfig { command: "echo broken >&2 && exit 3".to_string(), auto_fix: true, max_attempts: 2 })
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop cleanly");
+
+    let verification = result.verification.expect("verify command should have run after the write");
+    assert!(!verification.success);
+    assert_eq!(verification.attempts, 1);
+    assert!(verification.output.contains("broken"), "{}", verification.output);
+
+    let fed_back = result.trace.iter().any(|m| matches!(m,
+        ChatMessage::User { content: ChatMessageContent::Text(text), .. } if text.contains("verification command")));
+    assert!(fed_back, "failure should be sent back to the agent: {:?}", result.trace);
+}
+
+#[tokio::test]
+async fn test_verification_skipped_without_writes() {
+    init_test_logging();
+
+    let sleeping_tool: Box<dyn AnyTool> = Box::new(SleepingTool::new(10));
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-verify-read-only-agent")
+        .goal("Test goal to start running")
+        .tools(vec![sleeping_tool])
+        .verify(VerifyConfig { command: "exit 1".to_string(), auto_fix: true, max_attempts: 2 })
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop cleanly");
+    assert!(result.verification.is_none());
+}
+
+// Write-capable tool answering to the sleeping thinker, logs writes to the given files
+struct FileWritingTool {
+    fs_log: Arc<FsOperationLog>,
+    files: Vec<String>,
+}
+
+#[tool(name = "sleeping_tool", description = "A tool that writes files", capabilities = [ToolCapability::Write])]
+impl FileWritingTool {
+    async fn execute(&self, params: SleepParams) -> ToolResult {
+        for file in &self.files {
+            std::fs::write(file, "unformatted").unwrap();
+            self.fs_log.log_operation(FsOperationType::Write, file.clone()).await;
+        }
+        ToolResult::success("files written".to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_written_files_are_formatted_when_agent_stops() {
+    init_test_logging();
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let code = dir.path().join("main.txt").to_string_lossy().to_string();
+    let notes = dir.path().join("notes.md").to_string_lossy().to_string();
+    let fs_log = Arc::new(FsOperationLog::new());
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-format-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(FileWritingTool { fs_log: fs_log.clone(), files: vec![code.clone(), notes.clone()] }) as Box<dyn AnyTool>])
+        .formatters(std::collections::HashMap::from([("txt".to_string(), "printf formatted >".to_string())]), fs_log)
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    agent.run().await.expect("agent should stop cleanly");
+
+    assert_eq!(std::fs::read_to_string(&code).unwrap(), "formatted");
+    assert_eq!(std::fs::read_to_string(&notes).unwrap(), "unformatted", "no formatter for .md files");
+
+    let mut formatted = vec![];
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::FilesFormatted { results } = event {
+            formatted.extend(results);
+        }
+    }
+    assert_eq!(formatted.len(), 1);
+    assert_eq!(formatted[0].file, code);
+    assert!(formatted[0].success, "{:?}", formatted[0]);
+}
+
+// Interceptor that blocks every write-capable sleeping tool call
+struct NoSleepPolicy;
+
+#[async_trait]
+impl ToolCallInterceptor for NoSleepPolicy {
+    async fn intercept(&self, call: &crate::tools::ToolCall) -> InterceptorDecision {
+        if call.tool_name == "sleeping_tool" {
+            InterceptorDecision::Deny { reason: "sleeping is not allowed".to_string() }
+        } else {
+            InterceptorDecision::Allow
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_interceptor_blocks_tool_call_before_permission() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-interceptor-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>])
+        .interceptor(NoSleepPolicy)
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    agent.run().await.expect("agent should stop cleanly");
+
+    let mut blocked = None;
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::ToolCallCompleted { result, .. } = event {
+            blocked = Some(result);
+        }
+    }
+    let blocked = blocked.expect("tool call should have completed");
+    assert!(blocked.is_error(), "{:?}", blocked);
+    assert!(blocked.to_string().contains("sleeping is not allowed"), "{}", blocked);
+}
+
+// Test thinker whose provider is down for the first calls
+struct FlakyThinker {
+    failures_left: u32,
+}
+
+#[async_trait]
+impl Brain for FlakyThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        if self.failures_left > 0 {
+            self.failures_left -= 1;
+            return Err(AgentError::LlmError("connection reset by peer".to_string()));
+        }
+        Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("we are done".to_string())),
+            reasoning_content: None,
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_task_restarts_after_provider_failure() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(FlakyThinker { failures_left: 2 }))
+        .id("test-task-retry-agent")
+        .goal("Test goal to start running")
+        .retry_task(3, Duration::from_millis(10))
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("task should succeed once the provider is back");
+    assert!(result.success);
+    assert_eq!(result.trace.len(), 2, "trace should restart from the goal: {:?}", result.trace);
+
+    let mut restarts = vec![];
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::TaskRestarting { attempt, delay, .. } = event {
+            restarts.push((attempt, delay));
+        }
+    }
+    assert_eq!(restarts, vec![(1, Duration::from_millis(10)), (2, Duration::from_millis(20))]);
+}
+
+#[tokio::test]
+async fn test_task_retry_is_off_by_default() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(FlakyThinker { failures_left: 1 }))
+        .id("test-no-task-retry-agent")
+        .goal("Test goal to start running")
+        .build();
+
+    let error = agent.run().await.expect_err("provider failure should fail the run");
+    assert!(matches!(error, AgentError::LlmError(_)), "{:?}", error);
+}
+
+// Test thinker streaming far more deltas than the event buses hold before it answers
+struct ChattyThinker;
+
+#[async_trait]
+impl Brain for ChattyThinker {
+    async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        let deltas = context.deltas.expect("deltas are streamed");
+        for _ in 0..5000 {
+            deltas.emit("word ".to_string(), false);
+        }
+        Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("we are done".to_string())),
+            reasoning_content: None,
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_streamed_deltas_do_not_push_out_the_brain_result() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(ChattyThinker))
+        .id("test-chatty-agent")
+        .goal("Test goal to start running")
+        .stream_deltas(true)
+        .build();
+
+    // never read, so it lags behind the deltas
+    let _events = agent.watch();
+    let result = tokio::time::timeout(Duration::from_secs(10), agent.run()).await
+        .expect("the agent should not wait for a lost brain result")
+        .expect("the run should succeed");
+    assert!(result.success);
+}
+
+#[tokio::test]
+async fn test_controller_todo_crud_emits_events() {
+    init_test_logging();
+
+    let storage = Arc::new(TodoStorage::new());
+    let mut agent = AgentBuilder::with_brain(Box::new(PausableThinker::new()))
+        .id("test-todo-agent")
+        .tools(vec![Box::new(TodoReadTool::new(storage.clone())) as Box<dyn AnyTool>])
+        .todos(storage.clone())
+        .build();
+
+    let mut controller = agent.controller();
+    let mut events = agent.watch();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+
+    let todos = controller.add_todo("write the tests".to_string()).await.unwrap();
+    assert_eq!(todos.len(), 1);
+    let id = todos[0].id.clone();
+
+    let todos = controller.complete_todo(id.clone()).await.unwrap();
+    assert!(matches!(todos[0].status, TodoStatus::Completed));
+
+    // the todo tools of the agent share the same list
+    assert!(matches!(storage.get_all().await[0].status, TodoStatus::Completed));
+
+    assert!(controller.remove_todo("unknown".to_string()).await.is_err());
+    assert!(controller.remove_todo(id).await.unwrap().is_empty());
+
+    let mut updates = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::TodoUpdated { todos } = event {
+            updates.push(todos.len());
+        }
+    }
+    assert_eq!(updates, vec![1, 1, 0]);
+
+    controller.drop().await.expect("failed to drop the controller");
+    handle.await.unwrap().expect("agent should complete");
+}
+
+#[tokio::test]
+async fn test_checkpoint_snapshots_trace_and_state() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(PausableThinker::new()))
+        .id("test-checkpoint-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(SleepingTool::new(50)) as Box<dyn AnyTool>])
+        .sudo()
+        .build();
+
+    let mut controller = agent.controller();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+
+    controller.wait_turn(Some(3000)).await.expect("turn should end");
+    let checkpoint = controller.checkpoint().await.unwrap();
+    assert!(matches!(checkpoint.state, PublicAgentState::Paused), "{:?}", checkpoint.state);
+    assert_eq!(checkpoint.trace.len(), controller.get_trace().await.unwrap().len());
+    assert!(checkpoint.trace.iter().any(|message| matches!(message, ChatMessage::Tool { .. })));
+
+    controller.drop().await.expect("failed to drop the controller");
+    handle.await.unwrap().expect("agent should complete");
+}
+
 // Test thinker that uses real tools from the toolkit
 struct RealToolsThinker {
     step: u32,
@@ -461,3 +957,1407 @@ async fn test_agent_with_real_tools() {
         }
     }
 }
+
+// Scripted LLM provider: replays canned assistant messages and keeps the requests it received
+pub(crate) struct ScriptedLlm {
+    replies: std::sync::Mutex<std::collections::VecDeque<ChatMessage>>,
+    requests: Arc<std::sync::Mutex<Vec<ChatCompletionParameters>>>,
+}
+
+impl ScriptedLlm {
+    /// Provider answering with these messages, one per request
+    pub(crate) fn replying(replies: Vec<ChatMessage>) -> Self {
+        Self {
+            replies: std::sync::Mutex::new(replies.into()),
+            requests: Arc::new(std::sync::Mutex::new(Vec::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ScriptedLlm {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        Err("scripted provider has no model list".into())
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        self.requests.lock().unwrap().push(request.clone());
+        let message = self.replies.lock().unwrap().pop_front()
+            .ok_or("scripted provider ran out of replies")?;
+
+        Ok(ChatCompletionResponse {
+            id: Some("chatcmpl-scripted".to_string()),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: request.model,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message,
+                finish_reason: None,
+                logprobs: None,
+            }],
+            usage: None,
+            system_fingerprint: None,
+            service_tier: None,
+        })
+    }
+
+    async fn chat_stream(&self, _request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        Err("scripted provider does not stream".into())
+    }
+
+    fn supports_functions(&self, _model: String) -> bool {
+        true
+    }
+
+    fn supports_structured_output(&self, _model: String) -> bool {
+        false
+    }
+
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+
+    fn info() -> ProviderInfo where Self: Sized {
+        ProviderInfo {
+            name: "scripted",
+            display_name: "Scripted",
+            env_vars: vec![],
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_full_turn_with_fixture_tools_and_scripted_llm() {
+    init_test_logging();
+
+    let fixtures = FixtureToolBox::new()
+        .tool("read", "Read a file")
+        .fixture("read", serde_json::json!({"path": "notes.txt"}), ToolResult::success("remember the milk".to_string()));
+
+    let replies = vec![
+        ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(vec![ToolCall {
+                id: "call_read".to_string(),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: "read".to_string(),
+                    arguments: r#"{"path": "notes.txt"}"#.to_string(),
+                },
+            }]),
+            name: None,
+            audio: None,
+            refusal: None,
+        },
+        ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("The note says: remember the milk".to_string())),
+            reasoning_content: None,
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        },
+    ];
+
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let llm = LlmClient::from_provider(Box::new(ScriptedLlm {
+        replies: std::sync::Mutex::new(replies.into()),
+        requests: requests.clone(),
+    }));
+    let brain = CoderBrain::new(Arc::new(llm), "scripted-model".to_string());
+
+    let mut agent = AgentBuilder::with_brain(Box::new(brain))
+        .id("test-fixture-agent")
+        .goal("What does my note say?")
+        .tools(fixtures.build())
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("agent should complete");
+    assert!(result.success);
+
+    // the tool was called exactly once with the parameters chosen by the llm
+    let calls = fixtures.calls_to("read");
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].parameters, serde_json::json!({"path": "notes.txt"}));
+
+    // the fixture result was fed back to the llm on the second step
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    let fed_back = requests[1].messages.iter().any(|m| matches!(m,
+        ChatMessage::Tool { content: ChatMessageContent::Text(text), tool_call_id }
+            if tool_call_id == "call_read" && text == "remember the milk"));
+    assert!(fed_back, "tool result should be part of the second request");
+
+    match result.trace.last() {
+        Some(ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. }) => {
+            assert_eq!(text, "The note says: remember the milk");
+        }
+        other => panic!("expected final assistant message, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_tool_choice_holds_only_the_next_step() {
+    init_test_logging();
+
+    let fixtures = FixtureToolBox::new()
+        .tool("read", "Read a file")
+        .tool("write", "Write a file")
+        .fixture("read", serde_json::json!({"path": "notes.txt"}), ToolResult::success("remember the milk".to_string()));
+
+    let replies = vec![
+        assistant_calling_read(),
+        ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("The note says: remember the milk".to_string())),
+            reasoning_content: None,
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        },
+    ];
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let llm = LlmClient::from_provider(Box::new(ScriptedLlm {
+        replies: std::sync::Mutex::new(replies.into()),
+        requests: requests.clone(),
+    }));
+
+    let mut agent = AgentBuilder::with_brain(Box::new(CoderBrain::new(Arc::new(llm), "scripted-model".to_string())))
+        .id("test-tool-choice-agent")
+        .goal("What does my note say?")
+        .tools(fixtures.build())
+        .tool_choice(StepToolChoice { required: true, allowed_tools: Some(vec!["read".to_string()]) })
+        .sudo()
+        .build();
+    assert!(agent.run().await.expect("agent should complete").success);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    let tool_names = |request: &ChatCompletionParameters| -> Vec<String> {
+        request.tools.iter().flatten().map(|tool| tool.function.name.clone()).collect()
+    };
+
+    // the first step may only call read, and must call it
+    assert_eq!(tool_names(&requests[0]), vec!["read".to_string()]);
+    assert_eq!(serde_json::to_value(&requests[0].tool_choice).unwrap(),
+        serde_json::json!({"type": "function", "function": {"name": "read"}}));
+
+    // the next one is free again
+    assert_eq!(tool_names(&requests[1]), vec!["read".to_string(), "write".to_string()]);
+    assert_eq!(requests[1].tool_choice, Some(ChatCompletionToolChoice::Auto));
+
+    assert_eq!(StepToolChoice::required().llm_tool_choice(), Some(ChatCompletionToolChoice::Required));
+    assert_eq!(StepToolChoice::only(["read", "grep"]).llm_tool_choice(), None);
+    assert!(StepToolChoice::default().is_auto());
+}
+
+#[tokio::test]
+async fn test_sampling_overrides_reach_the_llm_request() {
+    init_test_logging();
+
+    let fixtures = FixtureToolBox::new()
+        .tool("read", "Read a file")
+        .fixture("read", serde_json::json!({"path": "notes.txt"}), ToolResult::success("remember the milk".to_string()));
+
+    let replies = vec![
+        assistant_calling_read(),
+        ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("The note says: remember the milk".to_string())),
+            reasoning_content: None,
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        },
+    ];
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let llm = LlmClient::from_provider(Box::new(ScriptedLlm {
+        replies: std::sync::Mutex::new(replies.into()),
+        requests: requests.clone(),
+    }));
+
+    let mut agent = AgentBuilder::with_brain(Box::new(CoderBrain::new(Arc::new(llm), "scripted-model".to_string())))
+        .id("test-sampling-agent")
+        .goal("What does my note say?")
+        .tools(fixtures.build())
+        .sampling(SamplingOverrides { temperature: Some(0.9), max_tokens: Some(256), top_p: None })
+        .sudo()
+        .build();
+    assert!(agent.run().await.expect("agent should complete").success);
+
+    // every step of the request uses the caller's sampling, the brain's own fills the gaps
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in requests.iter() {
+        assert_eq!(request.temperature, Some(0.9));
+        assert_eq!(request.max_completion_tokens, Some(256));
+        assert_eq!(request.top_p, None);
+    }
+}
+
+#[tokio::test]
+async fn test_request_instructions_append_to_or_replace_the_system_prompt() {
+    init_test_logging();
+
+    async fn system_prompt_sent(system_prompt: SystemPromptOverride) -> String {
+        let replies = vec![ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("Bonjour".to_string())),
+            reasoning_content: None,
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        }];
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let llm = LlmClient::from_provider(Box::new(ScriptedLlm {
+            replies: std::sync::Mutex::new(replies.into()),
+            requests: requests.clone(),
+        }));
+
+        let mut agent = AgentBuilder::with_brain(Box::new(CoderBrain::new(Arc::new(llm), "scripted-model".to_string())))
+            .id("test-system-prompt-agent")
+            .goal("Say hello")
+            .system_prompt(system_prompt)
+            .sudo()
+            .build();
+        assert!(agent.run().await.expect("agent should complete").success);
+
+        let requests = requests.lock().unwrap();
+        match &requests[0].messages[0] {
+            ChatMessage::System { content: ChatMessageContent::Text(text), .. } => text.clone(),
+            other => panic!("expected the system prompt first, got {:?}", other),
+        }
+    }
+
+    let appended = system_prompt_sent(SystemPromptOverride::Append("Answer in French.".to_string())).await;
+    assert!(appended.contains("You are SHAI"), "the base prompt is kept");
+    assert!(appended.contains("Answer in French."));
+
+    let replaced = system_prompt_sent(SystemPromptOverride::Replace("Answer in French. Today is {{TODAY}}.".to_string())).await;
+    assert!(replaced.starts_with("Answer in French. Today is "));
+    assert!(!replaced.contains("{{TODAY}}"), "placeholders still render");
+    assert!(!replaced.contains("You are SHAI"));
+}
+
+fn assistant_calling_read() -> ChatMessage {
+    ChatMessage::Assistant {
+        content: None,
+        reasoning_content: None,
+        tool_calls: Some(vec![ToolCall {
+            id: "call_read".to_string(),
+            r#type: "function".to_string(),
+            function: Function {
+                name: "read".to_string(),
+                arguments: r#"{"path": "notes.txt"}"#.to_string(),
+            },
+        }]),
+        name: None,
+        audio: None,
+        refusal: None,
+    }
+}
+
+fn assistant_calling(ids: &[&str]) -> ChatMessage {
+    ChatMessage::Assistant {
+        content: None,
+        reasoning_content: None,
+        tool_calls: Some(ids.iter().map(|id| ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: Function {
+                name: "read".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }).collect()),
+        name: None,
+        audio: None,
+        refusal: None,
+    }
+}
+
+fn tool_result(id: &str) -> ChatMessage {
+    ChatMessage::Tool {
+        tool_call_id: id.to_string(),
+        content: ChatMessageContent::Text(format!("result of {}", id)),
+    }
+}
+
+#[test]
+fn test_tool_call_ids_survive_a_save_and_reload() {
+    let trace = vec![
+        ChatMessage::User { content: ChatMessageContent::Text("read both files".to_string()), name: None },
+        assistant_calling(&["call_a", "call_b"]),
+        tool_result("call_b"),
+        tool_result("call_a"),
+    ];
+
+    let reloaded: Vec<ChatMessage> = serde_json::from_str(&serde_json::to_string(&trace).unwrap()).unwrap();
+    assert!(check_tool_call_ids(&reloaded).is_empty());
+
+    let (repaired, issues) = repair_tool_call_ids(reloaded);
+    assert!(issues.is_empty());
+    assert_eq!(serde_json::to_value(&repaired).unwrap(), serde_json::to_value(&trace).unwrap());
+}
+
+#[test]
+fn test_repair_tool_call_ids() {
+    let trace = vec![
+        assistant_calling(&["call_a", "call_b"]),
+        tool_result("call_a"),
+        tool_result("call_zombie"),
+        assistant_calling(&["call_c"]),
+    ];
+
+    let (repaired, issues) = repair_tool_call_ids(trace);
+    assert_eq!(issues, vec![
+        TraceIssue::OrphanToolResult { index: 2, tool_call_id: "call_zombie".to_string() },
+        TraceIssue::MissingToolResult { index: 0, tool_call_id: "call_b".to_string() },
+        TraceIssue::MissingToolResult { index: 3, tool_call_id: "call_c".to_string() },
+    ]);
+
+    let ids: Vec<_> = repaired.iter().filter_map(|m| match m {
+        ChatMessage::Tool { tool_call_id, .. } => Some(tool_call_id.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(ids, vec!["call_a", "call_b", "call_c"]);
+    assert_eq!(repaired.len(), 5);
+    assert!(check_tool_call_ids(&repaired).is_empty());
+}
+
+#[test]
+fn test_repair_closes_tool_calls_before_the_next_user_message() {
+    let trace = vec![
+        assistant_calling(&["call_a", "call_b"]),
+        tool_result("call_a"),
+        ChatMessage::User { content: ChatMessageContent::Text("stop, do this instead".to_string()), name: None },
+        tool_result("call_b"),
+    ];
+
+    let (repaired, issues) = repair_tool_call_ids(trace);
+    assert_eq!(issues, vec![
+        TraceIssue::MissingToolResult { index: 0, tool_call_id: "call_b".to_string() },
+        TraceIssue::OrphanToolResult { index: 3, tool_call_id: "call_b".to_string() },
+    ]);
+    assert!(matches!(&repaired[2], ChatMessage::Tool { tool_call_id, .. } if tool_call_id == "call_b"));
+    assert!(matches!(repaired[3], ChatMessage::User { .. }));
+    assert_eq!(repaired.len(), 4);
+    assert!(check_tool_call_ids(&repaired).is_empty());
+}
+
+#[test]
+fn test_compact_trace_keeps_tool_calls_with_their_results() {
+    let mut trace = vec![ChatMessage::User { content: ChatMessageContent::Text("fix the build".to_string()), name: None }];
+    for i in 0..5 {
+        let id = format!("call_{}", i);
+        trace.push(assistant_calling(&[&id]));
+        trace.push(tool_result(&id));
+    }
+
+    // the 3 last messages would start on a tool result, its call is kept with it
+    let (compacted, folded) = compact_trace(&trace, 3).unwrap();
+    assert_eq!(folded, 6);
+    assert_eq!(compacted.len(), 6);
+    assert!(check_tool_call_ids(&compacted).is_empty());
+    assert!(matches!(&compacted[0], ChatMessage::User { .. }));
+    match &compacted[1] {
+        ChatMessage::Assistant { content: Some(ChatMessageContent::Text(summary)), tool_calls: None, .. } => {
+            assert!(summary.contains("- read({}) -> result of call_0"), "{}", summary);
+            assert!(!summary.contains("call_3"), "{}", summary);
+        }
+        other => panic!("expected the summary, got {:?}", other),
+    }
+
+    assert!(compact_trace(&compacted, 10).is_none());
+}
+
+#[tokio::test]
+async fn test_trace_is_compacted_before_the_next_step() {
+    init_test_logging();
+
+    let long_output = "line of build output\n".repeat(200);
+    let mut trace = vec![ChatMessage::User { content: ChatMessageContent::Text("fix the build".to_string()), name: None }];
+    for i in 0..4 {
+        let id = format!("call_{}", i);
+        trace.push(assistant_calling(&[&id]));
+        trace.push(ChatMessage::Tool { tool_call_id: id, content: ChatMessageContent::Text(long_output.clone()) });
+    }
+    let tokens_before = estimate_tokens(&trace);
+
+    let mut agent = AgentBuilder::with_brain(Box::new(FlakyThinker { failures_left: 0 }))
+        .id("test-compaction-agent")
+        .with_traces(trace)
+        .goal("now run the tests")
+        .with_compaction(2000, 3)
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should complete");
+    assert!(check_tool_call_ids(&result.trace).is_empty());
+    // task, summary, the last call and its result, the new goal, the final answer
+    assert_eq!(result.trace.len(), 6, "{:?}", result.trace);
+
+    let mut compactions = vec![];
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::TraceCompacted { tokens_before, tokens_after, messages } = event {
+            compactions.push((tokens_before, tokens_after, messages));
+        }
+    }
+    assert_eq!(compactions.len(), 1);
+    assert!(compactions[0].0 > tokens_before, "the goal counts too: {:?}", compactions);
+    assert!(compactions[0].1 < 2000, "{:?}", compactions);
+    assert_eq!(compactions[0].2, 6);
+}
+
+// Test thinker that never stops calling the sleeping tool
+struct LoopingThinker {
+    calls: u32,
+}
+
+#[async_trait]
+impl Brain for LoopingThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        self.calls += 1;
+        Ok(ThinkerDecision::agent_continue(ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(vec![ToolCall {
+                id: format!("call_{}", self.calls),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: "sleeping_tool".to_string(),
+                    arguments: "{}".to_string(),
+                },
+            }]),
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_max_steps_stops_a_tool_loop() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(LoopingThinker { calls: 0 }))
+        .id("test-max-steps-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(SleepingTool::new(1)) as Box<dyn AnyTool>])
+        .max_steps(3)
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should stop, not fail");
+    assert!(!result.success);
+    assert!(result.message.contains("maximum of 3 tool steps"), "{}", result.message);
+    assert!(check_tool_call_ids(&result.trace).is_empty());
+
+    let results: Vec<_> = result.trace.iter().filter_map(|m| match m {
+        ChatMessage::Tool { content: ChatMessageContent::Text(text), .. } => Some(text.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[..3].iter().all(|r| *r == "Finished sleeping"), "{:?}", results);
+    assert!(results[3].starts_with("not run"), "{:?}", results);
+
+    let mut errors = 0;
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::Error { error } = event {
+            assert!(error.contains("maximum of 3 tool steps"), "{}", error);
+            errors += 1;
+        }
+    }
+    assert_eq!(errors, 1);
+}
+
+fn tool_results(trace: &[ChatMessage]) -> Vec<&str> {
+    trace.iter().filter_map(|m| match m {
+        ChatMessage::Tool { content: ChatMessageContent::Text(text), .. } => Some(text.as_str()),
+        _ => None,
+    }).collect()
+}
+
+#[tokio::test]
+async fn test_repeated_tool_calls_are_not_run() {
+    init_test_logging();
+
+    // a write tool runs twice, the next identical calls get an error until the step limit
+    let mut agent = AgentBuilder::with_brain(Box::new(LoopingThinker { calls: 0 }))
+        .id("test-repeat-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>])
+        .max_steps(4)
+        .repeated_call_limit(Some(2))
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop, not fail");
+    let results = tool_results(&result.trace);
+    assert_eq!(results.len(), 5, "{:?}", results);
+    assert!(results[..2].iter().all(|r| *r == "Finished sleeping"), "{:?}", results);
+    assert!(results[2..4].iter().all(|r| r.contains("already called 2 times")), "{:?}", results);
+
+    // a read-only tool gets a higher limit
+    let mut agent = AgentBuilder::with_brain(Box::new(LoopingThinker { calls: 0 }))
+        .id("test-repeat-read-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(SleepingTool::new(1)) as Box<dyn AnyTool>])
+        .max_steps(4)
+        .repeated_call_limit(Some(1))
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop, not fail");
+    let results = tool_results(&result.trace);
+    assert!(results[..3].iter().all(|r| *r == "Finished sleeping"), "{:?}", results);
+    assert!(results[3].contains("already called 3 times"), "{:?}", results);
+}
+
+#[tokio::test]
+async fn test_repeated_polls_are_run() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(LoopingThinker { calls: 0 }))
+        .id("test-repeat-poll-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(PollingSleepingTool) as Box<dyn AnyTool>])
+        .max_steps(12)
+        .repeated_call_limit(Some(1))
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop, not fail");
+    let results = tool_results(&result.trace);
+    assert!(results[..12].iter().all(|r| *r == "Finished sleeping"), "{:?}", results);
+}
+
+// Alternates between two calls of the same tool, like an edit and the test command run after it
+struct AlternatingThinker {
+    calls: u32,
+}
+
+#[async_trait]
+impl Brain for AlternatingThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        self.calls += 1;
+        let arguments = if self.calls % 2 == 0 { r#"{"duration_ms": 1}"# } else { "{}" };
+        Ok(ThinkerDecision::agent_continue(ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(vec![ToolCall {
+                id: format!("call_{}", self.calls),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: "sleeping_tool".to_string(),
+                    arguments: arguments.to_string(),
+                },
+            }]),
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_repeated_calls_with_other_calls_between_them_are_counted() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(AlternatingThinker { calls: 0 }))
+        .id("test-alternating-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>])
+        .max_steps(6)
+        .repeated_call_limit(Some(1))
+        .sudo()
+        .build();
+
+    let mut controller = agent.controller();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+
+    // each of the two calls runs once, then both are refused however they alternate
+    controller.wait_turn(Some(3000)).await.expect("turn should end");
+    let trace = controller.get_trace().await.expect("trace should be returned");
+    let results = tool_results(&trace);
+    assert!(results[..2].iter().all(|r| *r == "Finished sleeping"), "{:?}", results);
+    assert!(results[2..6].iter().all(|r| r.contains("already called 1 times")), "{:?}", results);
+
+    // user input starts the count over
+    controller.send(AgentRequest::SendUserInput { input: "go on".to_string() }).await.expect("input should be accepted");
+    controller.wait_turn(Some(3000)).await.expect("turn should end");
+    let trace = controller.get_trace().await.expect("trace should be returned");
+    let results = tool_results(&trace);
+    assert!(results[7..9].iter().all(|r| *r == "Finished sleeping"), "{:?}", results);
+    assert!(results[9..13].iter().all(|r| r.contains("already called 1 times")), "{:?}", results);
+
+    controller.drop().await.expect("failed to drop the controller");
+    let _ = handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_allow_always_stops_asking() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(LoopingThinker { calls: 0 }))
+        .id("test-allow-always-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>])
+        .max_steps(3)
+        .repeated_call_limit(None)
+        .build();
+
+    let mut controller = agent.controller();
+    let mut events = agent.watch();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+
+    // only the first call asks, the answer covers the calls after it
+    let (requests, results) = tokio::time::timeout(Duration::from_secs(3), async {
+        let mut requests = 0;
+        let mut results = Vec::new();
+        loop {
+            match events.recv().await {
+                Ok(AgentEvent::PermissionRequired { request_id, .. }) => {
+                    requests += 1;
+                    controller.response_permission_request(request_id, PermissionResponse::AllowAlways).await
+                        .expect("failed to answer the permission request");
+                }
+                Ok(AgentEvent::ToolCallCompleted { result, .. }) => results.push(result),
+                Ok(AgentEvent::Error { .. }) => return (requests, results),
+                Ok(_) => continue,
+                Err(e) => panic!("event channel closed: {:?}", e),
+            }
+        }
+    }).await.expect("agent should reach its step limit");
+
+    assert_eq!(requests, 1);
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|r| matches!(r, ToolResult::Success { .. })), "{:?}", results);
+
+    controller.drop().await.expect("failed to drop the controller");
+    handle.abort();
+}
+
+// Test thinker that makes one tool call then completes
+struct OneCallThinker {
+    tool_name: String,
+    arguments: serde_json::Value,
+    called: bool,
+}
+
+impl OneCallThinker {
+    fn new(tool_name: &str, arguments: serde_json::Value) -> Self {
+        Self { tool_name: tool_name.to_string(), arguments, called: false }
+    }
+}
+
+#[async_trait]
+impl Brain for OneCallThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        if self.called {
+            return Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+                content: Some(ChatMessageContent::Text("we are done".to_string())),
+                reasoning_content: None,
+                tool_calls: None,
+                name: None,
+                audio: None,
+                refusal: None,
+            }));
+        }
+        self.called = true;
+        Ok(ThinkerDecision::agent_continue(ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(vec![ToolCall {
+                id: "call_1".to_string(),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: self.tool_name.clone(),
+                    arguments: self.arguments.to_string(),
+                },
+            }]),
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_deny_list_wins_over_sudo() {
+    init_test_logging();
+
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("marker");
+    let command = format!("true && touch '{}'", marker.display());
+
+    let mut agent = AgentBuilder::with_brain(Box::new(OneCallThinker::new("bash", serde_json::json!({"command": command}))))
+        .id("test-deny-list-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(BashTool::new()) as Box<dyn AnyTool>])
+        .command_deny_list(CommandDenyList::new(&[r"\btouch\b"]))
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should stop cleanly");
+
+    assert!(!marker.exists(), "forbidden command should not have run");
+    let results = tool_results(&result.trace);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].contains(r"deny-list pattern `\btouch\b`"), "{:?}", results);
+    assert!(!results[0].contains("rejected by the user"), "{:?}", results);
+
+    let mut forbidden = None;
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::CommandForbidden { pattern, response, .. } = event {
+            forbidden = Some((pattern, response));
+        }
+    }
+    assert_eq!(forbidden, Some((r"\btouch\b".to_string(), PermissionResponse::Forbidden)));
+}
+
+#[tokio::test]
+async fn test_images_read_by_a_tool_only_go_to_the_model() {
+    init_test_logging();
+
+    let dir = tempfile::tempdir().unwrap();
+    let image = dir.path().join("screenshot.png");
+    std::fs::write(&image, [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0xFF, 0x00]).unwrap();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(OneCallThinker::new("read", serde_json::json!({"path": image.to_string_lossy()}))))
+        .id("test-image-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(ReadTool::new(Arc::new(FsOperationLog::new()))) as Box<dyn AnyTool>])
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should stop cleanly");
+
+    // the model gets the image after the tool results
+    let attached = result.trace.iter().any(|message| matches!(message, ChatMessage::User { content: ChatMessageContent::ContentPart(_), .. }));
+    assert!(attached, "{:?}", result.trace);
+
+    // the event has its size and type, not its content
+    let mut completed = 0;
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::ToolCallCompleted { result, .. } = event {
+            let ToolResult::Success { metadata: Some(metadata), .. } = &result else {
+                panic!("the image should have been read: {:?}", result);
+            };
+            assert!(result.image().is_none());
+            assert_eq!(metadata["mime_type"], serde_json::json!("image/png"));
+            completed += 1;
+        }
+    }
+    assert_eq!(completed, 1);
+}
+
+#[tokio::test]
+async fn test_dry_run_previews_instead_of_executing() {
+    init_test_logging();
+
+    // a tool with a preview: the edit is shown, the file is left alone
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("main.rs");
+    std::fs::write(&file, "fn main() {}\n").unwrap();
+    let edit = serde_json::json!({"path": file.to_string_lossy(), "old_string": "main", "new_string": "start"});
+    let fs_log = Arc::new(FsOperationLog::new());
+    fs_log.log_operation(FsOperationType::Read, file.to_string_lossy().to_string()).await;
+
+    let mut agent = AgentBuilder::with_brain(Box::new(OneCallThinker::new("edit", edit)))
+        .id("test-dry-run-edit-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(EditTool::new(fs_log)) as Box<dyn AnyTool>])
+        .dry_run()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop cleanly");
+    let results = tool_results(&result.trace);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].starts_with("dry-run: not executed"), "{:?}", results);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "fn main() {}\n");
+
+    // a tool without one is not run either, and no permission is asked for it
+    let mut agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new()))
+        .id("test-dry-run-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>])
+        .dry_run()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop cleanly");
+    let results = tool_results(&result.trace);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].contains("sleeping_tool has no preview"), "{:?}", results);
+}
+
+// Same as the looping thinker, each step reports the tokens it used
+struct SpendingThinker {
+    looping: LoopingThinker,
+}
+
+#[async_trait]
+impl Brain for SpendingThinker {
+    async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        let decision = self.looping.next_step(context).await?;
+        Ok(ThinkerDecision::agent_continue_with_tokens(decision.message, 60, 40))
+    }
+}
+
+#[tokio::test]
+async fn test_token_budget_stops_the_agent() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SpendingThinker { looping: LoopingThinker { calls: 0 } }))
+        .id("test-token-budget-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(SleepingTool::new(1)) as Box<dyn AnyTool>])
+        .repeated_call_limit(None)
+        .token_budget(250)
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should stop, not fail");
+    assert!(!result.success);
+    assert!(result.message.contains("token budget of 250 exceeded (300 tokens used)"), "{}", result.message);
+
+    // the third step goes over, its tool call is answered without running
+    let results = tool_results(&result.trace);
+    assert_eq!(results.len(), 3, "{:?}", results);
+    assert!(results[..2].iter().all(|r| *r == "Finished sleeping"), "{:?}", results);
+    assert!(results[2].starts_with("not run: token budget"), "{:?}", results);
+    assert!(check_tool_call_ids(&result.trace).is_empty());
+
+    let mut budgets = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::TokenBudget { used, limit } = event {
+            budgets.push((used, limit));
+        }
+    }
+    assert_eq!(budgets, vec![(100, 250), (200, 250), (300, 250)]);
+}
+
+#[tokio::test]
+async fn test_user_input_rearms_a_spent_token_budget() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SpendingThinker { looping: LoopingThinker { calls: 0 } }))
+        .id("test-rearmed-budget-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(SleepingTool::new(1)) as Box<dyn AnyTool>])
+        .repeated_call_limit(None)
+        .token_budget(250)
+        .sudo()
+        .build();
+
+    let mut controller = agent.controller();
+    let mut events = agent.watch();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+
+    // the budget stops the first turn, the user going on gives the agent a new one
+    controller.wait_turn(Some(3000)).await.expect("turn should end");
+    controller.send(AgentRequest::SendUserInput { input: "go on".to_string() }).await.expect("input should be accepted");
+    controller.wait_turn(Some(3000)).await.expect("turn should end");
+
+    let mut budgets = Vec::new();
+    let mut errors = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        match event {
+            AgentEvent::TokenBudget { used, .. } => budgets.push(used),
+            AgentEvent::Error { error } => errors.push(error),
+            _ => {}
+        }
+    }
+    assert_eq!(budgets, vec![100, 200, 300, 100, 200, 300]);
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors.iter().all(|error| error.contains("token budget of 250 exceeded (300 tokens used)")), "{:?}", errors);
+
+    let (usage, _) = controller.get_token_usage().await.expect("usage should be reported");
+    assert_eq!(usage.total(), 600);
+
+    controller.drop().await.expect("failed to drop the controller");
+    let _ = handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_cost_estimate_follows_token_usage() {
+    init_test_logging();
+
+    let pricing = ModelPricing::lookup("gpt-4o", &std::collections::HashMap::new());
+    let mut agent = AgentBuilder::with_brain(Box::new(SpendingThinker { looping: LoopingThinker { calls: 0 } }))
+        .id("test-cost-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(SleepingTool::new(1)) as Box<dyn AnyTool>])
+        .repeated_call_limit(None)
+        .token_budget(250)
+        .pricing(pricing)
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should stop, not fail");
+
+    // 180 input tokens at $2.50 and 120 output tokens at $10 per million
+    let cost = result.cost.expect("a priced agent estimates its cost");
+    assert_eq!(result.token_usage.total(), 300);
+    assert!((cost.usd - 0.00165).abs() < 1e-9, "{}", cost.usd);
+
+    let mut estimates = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::CostEstimate { model, usd, priced } = event {
+            assert_eq!(model, "gpt-4o");
+            assert!(priced);
+            estimates.push(usd);
+        }
+    }
+    assert_eq!(estimates.len(), 3);
+    assert!(estimates.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", estimates);
+
+    // an agent without a model to price reports no cost
+    let mut agent = AgentBuilder::with_brain(Box::new(SpendingThinker { looping: LoopingThinker { calls: 0 } }))
+        .id("test-unpriced-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(SleepingTool::new(1)) as Box<dyn AnyTool>])
+        .repeated_call_limit(None)
+        .token_budget(50)
+        .sudo()
+        .build();
+    assert!(agent.run().await.unwrap().cost.is_none());
+}
+
+#[test]
+fn test_agent_events_roundtrip_through_json() {
+    let call = crate::tools::ToolCall {
+        tool_call_id: "call_1".to_string(),
+        tool_name: "bash".to_string(),
+        parameters: serde_json::json!({"command": "ls"}),
+    };
+    let events = vec![
+        AgentEvent::StatusChanged {
+            old_status: PublicAgentState::Processing { task_name: "think".to_string(), tools_exec_at: chrono::Utc::now() },
+            new_status: PublicAgentState::Failed { error: "boom".to_string() },
+        },
+        AgentEvent::BrainResult {
+            timestamp: chrono::Utc::now(),
+            thought: Ok(ChatMessage::Assistant {
+                content: Some(ChatMessageContent::Text("done".to_string())),
+                reasoning_content: None,
+                refusal: None,
+                name: None,
+                audio: None,
+                tool_calls: None,
+            }),
+        },
+        AgentEvent::BrainResult { timestamp: chrono::Utc::now(), thought: Err(AgentError::LlmError("rate limited".to_string())) },
+        AgentEvent::Reasoning { timestamp: chrono::Utc::now(), text: "the user wants a list".to_string() },
+        AgentEvent::ToolCallCompleted { duration: chrono::TimeDelta::milliseconds(42), call: call.clone(), result: ToolResult::success("a.txt".to_string()) },
+        AgentEvent::PermissionTimedOut { request_id: "req".to_string(), call, response: PermissionResponse::Deny },
+        AgentEvent::TaskRestarting { attempt: 1, max_retries: 3, delay: Duration::from_millis(1500), error: "outage".to_string() },
+        AgentEvent::Completed { success: true, message: "ok".to_string() },
+    ];
+
+    for event in events {
+        let json = serde_json::to_value(&event).unwrap();
+        let back: AgentEvent = serde_json::from_value(json.clone()).unwrap();
+        // timestamps are RFC 3339 strings, durations milliseconds: the same event gives the same json
+        assert_eq!(serde_json::to_value(&back).unwrap(), json);
+        assert_eq!(format!("{:?}", back), format!("{:?}", event));
+    }
+
+    let error = AgentEvent::BrainResult { timestamp: chrono::Utc::now(), thought: Err(AgentError::SessionClosed) };
+    let json = serde_json::to_value(&error).unwrap();
+    assert_eq!(json["thought"], serde_json::json!({"error": {"kind": "session_closed"}}));
+    let limit = serde_json::to_value(AgentEvent::TimeLimitReached { limit: Duration::from_secs(2) }).unwrap();
+    assert_eq!(limit, serde_json::json!({"type": "time_limit_reached", "limit_ms": 2000}));
+}
+
+#[test]
+fn test_read_only_builder_drops_write_capable_tools() {
+    let fs_log = Arc::new(FsOperationLog::new());
+    let tools = || vec![
+        Box::new(NapTool) as Box<dyn AnyTool>,
+        Box::new(FileWritingTool { fs_log: fs_log.clone(), files: vec![] }) as Box<dyn AnyTool>,
+    ];
+
+    let agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new())).tools(tools()).read_only().build();
+    let names: Vec<String> = agent.available_tools.iter().map(|tool| tool.name()).collect();
+    assert_eq!(names, vec!["nap".to_string()]);
+
+    let agent = AgentBuilder::with_brain(Box::new(SleepingThinker::new())).tools(tools()).build();
+    assert_eq!(agent.available_tools.len(), 2);
+}
+
+#[tokio::test]
+async fn test_read_only_refuses_calls_that_write() {
+    init_test_logging();
+
+    // fetch is kept in a read-only toolbox, but a POST would change the remote side
+    let post = serde_json::json!({"url": "http://127.0.0.1:9/", "method": "POST", "body": "{}"});
+    let mut agent = AgentBuilder::with_brain(Box::new(OneCallThinker::new("fetch", post)))
+        .id("test-read-only-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(FetchTool::new()) as Box<dyn AnyTool>])
+        .read_only()
+        .sudo()
+        .build();
+
+    let result = agent.run().await.expect("agent should stop cleanly");
+    let results = tool_results(&result.trace);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].contains("read-only run: this fetch call would change things"), "{:?}", results);
+}
+
+// Answers right away, thinking first
+struct ReasoningThinker;
+
+#[async_trait]
+impl Brain for ReasoningThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("42".to_string())),
+            reasoning_content: Some("six times seven".to_string()),
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_reasoning_is_emitted_before_the_brain_result() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(ReasoningThinker))
+        .id("test-reasoning-agent")
+        .goal("What is six times seven?")
+        .build();
+
+    let mut events = agent.watch();
+    agent.run().await.expect("agent should complete");
+
+    let mut seen = vec![];
+    while let Ok(event) = events.try_recv() {
+        match event {
+            AgentEvent::Reasoning { text, .. } => seen.push(format!("reasoning: {}", text)),
+            // the message still carries its reasoning for the clients that read it there
+            AgentEvent::BrainResult { thought: Ok(ChatMessage::Assistant { reasoning_content, .. }), .. } => {
+                seen.push(format!("result: {}", reasoning_content.unwrap_or_default()))
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(seen, vec!["reasoning: six times seven", "result: six times seven"]);
+}
+
+#[tokio::test]
+async fn test_tool_results_carry_their_duration() {
+    init_test_logging();
+
+    let mut agent = AgentBuilder::with_brain(Box::new(OneCallThinker::new("bash", serde_json::json!({"command": "sleep 0.05"}))))
+        .id("test-tool-time-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(BashTool::new()) as Box<dyn AnyTool>])
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    agent.run().await.expect("agent should stop cleanly");
+
+    let mut completed = None;
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::ToolCallCompleted { result, duration, .. } = event {
+            completed = Some((result, duration));
+        }
+    }
+    let (result, duration) = completed.expect("the call should complete");
+    let elapsed = result.duration().expect("a call that ran has a duration");
+    assert!(elapsed >= Duration::from_millis(50), "{:?}", elapsed);
+    assert_eq!(elapsed.as_millis() as i64, duration.num_milliseconds());
+
+    let totals = agent.tool_time.lock().unwrap().clone();
+    assert_eq!(totals.by_tool["bash"].calls, 1);
+    assert_eq!(totals.total().total_ms, elapsed.as_millis() as u64);
+
+    // a denied call never ran: no duration, nothing counted
+    let command = "true && touch /nonexistent";
+    let mut agent = AgentBuilder::with_brain(Box::new(OneCallThinker::new("bash", serde_json::json!({"command": command}))))
+        .id("test-tool-time-denied-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(BashTool::new()) as Box<dyn AnyTool>])
+        .command_deny_list(CommandDenyList::new(&[r"\btouch\b"]))
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    agent.run().await.expect("agent should stop cleanly");
+
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::ToolCallCompleted { result, duration, .. } = event {
+            assert_eq!(forbidden_pattern(&result), Some(r"\btouch\b"), "{:?}", result);
+            assert_eq!(result.duration(), None);
+            assert_eq!(duration, chrono::TimeDelta::zero());
+        }
+    }
+    assert_eq!(agent.tool_time.lock().unwrap().total(), Default::default());
+}
+
+// Counts how many of its calls run at the same time
+#[derive(Clone, Default)]
+struct Crowd {
+    running: Arc<std::sync::atomic::AtomicUsize>,
+    peak: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl Crowd {
+    async fn nap(&self) -> ToolResult {
+        use std::sync::atomic::Ordering::SeqCst;
+        let now = self.running.fetch_add(1, SeqCst) + 1;
+        self.peak.fetch_max(now, SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.running.fetch_sub(1, SeqCst);
+        ToolResult::success("Finished sleeping".to_string())
+    }
+}
+
+struct CrowdReadTool {
+    crowd: Crowd,
+}
+
+#[tool(name = "crowd_read", description = "A read-only tool that naps")]
+impl CrowdReadTool {
+    async fn execute(&self, params: SleepParams) -> ToolResult {
+        self.crowd.nap().await
+    }
+}
+
+struct CrowdWriteTool {
+    crowd: Crowd,
+}
+
+#[tool(name = "crowd_write", description = "A tool that naps and writes", capabilities = [ToolCapability::Write])]
+impl CrowdWriteTool {
+    async fn execute(&self, params: SleepParams) -> ToolResult {
+        self.crowd.nap().await
+    }
+}
+
+// Test thinker that calls a tool several times in one batch, then pauses
+struct BatchThinker {
+    tool: &'static str,
+    calls: usize,
+    called_tool: bool,
+}
+
+#[async_trait]
+impl Brain for BatchThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        if self.called_tool {
+            return Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+                content: Some(ChatMessageContent::Text("we are done".to_string())),
+                reasoning_content: None,
+                tool_calls: None,
+                name: None,
+                audio: None,
+                refusal: None,
+            }));
+        }
+        self.called_tool = true;
+        let calls = (0..self.calls).map(|i| ToolCall {
+            id: format!("call_{}", i),
+            r#type: "function".to_string(),
+            function: Function { name: self.tool.to_string(), arguments: "{}".to_string() },
+        }).collect();
+        Ok(ThinkerDecision::agent_continue(ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(calls),
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_parallel_tool_calls_are_capped() {
+    init_test_logging();
+
+    // calls that write share the limit
+    let crowd = Crowd::default();
+    let mut agent = AgentBuilder::with_brain(Box::new(BatchThinker { tool: "crowd_write", calls: 6, called_tool: false }))
+        .id("test-parallel-write-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(CrowdWriteTool { crowd: crowd.clone() }) as Box<dyn AnyTool>])
+        .max_parallel_tools(2)
+        .sudo()
+        .build();
+    let result = agent.run().await.expect("agent should stop cleanly");
+    assert_eq!(tool_results(&result.trace).len(), 6);
+    assert_eq!(crowd.peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+    // read-only calls get twice as many slots
+    let crowd = Crowd::default();
+    let mut agent = AgentBuilder::with_brain(Box::new(BatchThinker { tool: "crowd_read", calls: 6, called_tool: false }))
+        .id("test-parallel-read-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(CrowdReadTool { crowd: crowd.clone() }) as Box<dyn AnyTool>])
+        .max_parallel_tools(2)
+        .sudo()
+        .build();
+    let result = agent.run().await.expect("agent should stop cleanly");
+    assert!(tool_results(&result.trace).iter().all(|r| *r == "Finished sleeping"));
+    assert_eq!(crowd.peak.load(std::sync::atomic::Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_sequential_tool_calls_run_in_order() {
+    init_test_logging();
+
+    let crowd = Crowd::default();
+    let mut agent = AgentBuilder::with_brain(Box::new(BatchThinker { tool: "crowd_read", calls: 4, called_tool: false }))
+        .id("test-sequential-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(CrowdReadTool { crowd: crowd.clone() }) as Box<dyn AnyTool>])
+        .parallel_tool_calls(false)
+        .sudo()
+        .build();
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should stop cleanly");
+    assert_eq!(tool_results(&result.trace).len(), 4);
+    assert_eq!(crowd.peak.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    // each call starts once the previous one completed
+    let mut order = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        match event {
+            AgentEvent::ToolCallStarted { call, .. } => order.push(format!("start {}", call.tool_call_id)),
+            AgentEvent::ToolCallCompleted { call, .. } => order.push(format!("end {}", call.tool_call_id)),
+            _ => {}
+        }
+    }
+    let expected: Vec<String> = (0..4).flat_map(|i| [format!("start call_{}", i), format!("end call_{}", i)]).collect();
+    assert_eq!(order, expected);
+}
+
+#[tokio::test]
+async fn test_long_tool_outputs_lose_their_middle() {
+    init_test_logging();
+
+    let limit = ToolOutputLimit { max_bytes: 0, per_tool: std::collections::HashMap::from([("bash".to_string(), 1000)]) };
+    let mut agent = AgentBuilder::with_brain(Box::new(OneCallThinker::new("bash", serde_json::json!({"command": "seq 1 20000"}))))
+        .id("test-tool-output-limit-agent")
+        .goal("Test goal to start running")
+        .tools(vec![Box::new(BashTool::new()) as Box<dyn AnyTool>])
+        .tool_output_limit(limit)
+        .sudo()
+        .build();
+
+    let mut events = agent.watch();
+    let result = agent.run().await.expect("agent should stop cleanly");
+
+    let output = tool_results(&result.trace)[0];
+    assert!(output.len() < 1100, "{}", output.len());
+    assert!(output.starts_with("1\n2\n3\n"), "{}", output);
+    assert!(output.trim_end().ends_with("19999\n20000"), "{}", output);
+    assert!(output.contains(" bytes truncated ...]"), "{}", output);
+
+    let mut completed = None;
+    while let Ok(event) = events.try_recv() {
+        if let AgentEvent::ToolCallCompleted { result, .. } = event {
+            completed = Some(result);
+        }
+    }
+    let Some(ToolResult::Success { metadata: Some(metadata), .. }) = completed else { panic!("the call should succeed") };
+    assert!(metadata[OUTPUT_BYTES_METADATA_KEY].as_u64().unwrap() > 100_000);
+}
+
+// Writes with the sleeping tool then stops; when asked to check its changes, first calls `check` if set
+struct SelfCheckingThinker {
+    check: Option<&'static str>,
+    calls: usize,
+}
+
+#[async_trait]
+impl Brain for SelfCheckingThinker {
+    async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        let asked = matches!(context.trace.read().await.last(),
+            Some(ChatMessage::User { content: ChatMessageContent::Text(text), .. }) if text.starts_with("Before you stop"));
+        let tool = match (self.calls, asked) {
+            (0, _) => Some("sleeping_tool"),
+            (_, true) => self.check,
+            _ => None,
+        };
+        let Some(tool) = tool else {
+            return Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+                content: Some(ChatMessageContent::Text("we are done".to_string())),
+                reasoning_content: None,
+                tool_calls: None,
+                name: None,
+                audio: None,
+                refusal: None,
+            }));
+        };
+        self.calls += 1;
+        Ok(ThinkerDecision::agent_continue(ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(vec![ToolCall {
+                id: format!("call_{}", self.calls),
+                r#type: "function".to_string(),
+                function: Function { name: tool.to_string(), arguments: r#"{"duration_ms": 1}"#.to_string() },
+            }]),
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_verify_before_complete_asks_for_a_check() {
+    init_test_logging();
+
+    let run = |check: Option<&'static str>, gate: bool| async move {
+        let mut builder = AgentBuilder::with_brain(Box::new(SelfCheckingThinker { check, calls: 0 }))
+            .id("test-self-check-agent")
+            .goal("Test goal to start running")
+            .tools(vec![Box::new(GuardedSleepingTool) as Box<dyn AnyTool>, Box::new(NapTool) as Box<dyn AnyTool>, Box::new(CheckTool) as Box<dyn AnyTool>])
+            .sudo();
+        if gate {
+            builder = builder.verify_before_complete();
+        }
+        let mut agent = builder.build();
+        let mut events = agent.watch();
+        let result = agent.run().await.expect("agent should stop cleanly");
+
+        let mut attempts = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::SelfCheckRequested { attempt } = event {
+                attempts.push(attempt);
+            }
+        }
+        (tool_results(&result.trace).len(), attempts)
+    };
+
+    // the model runs the tests once asked, then may stop
+    assert_eq!(run(Some("bash"), true).await, (2, vec![1]));
+    // any other tool is not a check, the model is asked again until the last prompt
+    assert_eq!(run(Some("nap"), true).await, (3, vec![1, 2]));
+    // a model that never checks is let go after the last prompt
+    assert_eq!(run(None, true).await, (1, vec![1, 2]));
+    // off by default
+    assert_eq!(run(Some("bash"), false).await, (1, vec![]));
+}
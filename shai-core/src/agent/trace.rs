@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ChatMessageContentPart};
+
+/// Content of the tool result added for a tool call that never got one
+pub const INTERRUPTED_TOOL_RESULT: &str = "tool call was interrupted before returning a result";
+
+/// A broken link between the assistant tool calls and the tool results of a trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceIssue {
+    /// a tool result whose id matches no pending assistant tool call
+    OrphanToolResult { index: usize, tool_call_id: String },
+    /// an assistant tool call without tool result (e.g. trace saved while the tool was running)
+    MissingToolResult { index: usize, tool_call_id: String },
+}
+
+impl fmt::Display for TraceIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceIssue::OrphanToolResult { index, tool_call_id } => {
+                write!(f, "message #{}: tool result {} has no matching assistant tool call", index, tool_call_id)
+            }
+            TraceIssue::MissingToolResult { index, tool_call_id } => {
+                write!(f, "message #{}: assistant tool call {} has no tool result", index, tool_call_id)
+            }
+        }
+    }
+}
+
+/// List the tool results and tool calls of a trace that are not linked by their id.
+/// Strict providers reject such traces, which typically come from a session reloaded from disk.
+pub fn check_tool_call_ids(trace: &[ChatMessage]) -> Vec<TraceIssue> {
+    repair_tool_call_ids(trace.to_vec()).1
+}
+
+/// Restore the assistant ↔ tool linkage: orphan tool results are dropped and tool calls
+/// left without result get an INTERRUPTED_TOOL_RESULT right after the results they have, before
+/// the next message that is not a tool result or at the end of the trace.
+/// Ids are never rewritten, a trace without issue is returned unchanged.
+pub fn repair_tool_call_ids(trace: Vec<ChatMessage>) -> (Vec<ChatMessage>, Vec<TraceIssue>) {
+    let mut repaired = Vec::with_capacity(trace.len());
+    let mut issues = Vec::new();
+    // tool calls of the last assistant message still waiting for their result
+    let mut pending: Vec<(usize, String)> = Vec::new();
+
+    for (index, message) in trace.into_iter().enumerate() {
+        match &message {
+            ChatMessage::Assistant { tool_calls, .. } => {
+                close_pending(&mut pending, &mut repaired, &mut issues);
+                pending = tool_calls.iter().flatten()
+                    .map(|tc| (index, tc.id.clone()))
+                    .collect();
+            }
+            ChatMessage::Tool { tool_call_id, .. } => {
+                match pending.iter().position(|(_, id)| id == tool_call_id) {
+                    Some(pos) => { pending.remove(pos); }
+                    None => {
+                        issues.push(TraceIssue::OrphanToolResult { index, tool_call_id: tool_call_id.clone() });
+                        continue;
+                    }
+                }
+            }
+            // a user, system or developer message ends the results of the previous tool calls
+            _ => close_pending(&mut pending, &mut repaired, &mut issues),
+        }
+        repaired.push(message);
+    }
+    close_pending(&mut pending, &mut repaired, &mut issues);
+
+    (repaired, issues)
+}
+
+fn close_pending(pending: &mut Vec<(usize, String)>, repaired: &mut Vec<ChatMessage>, issues: &mut Vec<TraceIssue>) {
+    for (index, tool_call_id) in pending.drain(..) {
+        repaired.push(ChatMessage::Tool {
+            tool_call_id: tool_call_id.clone(),
+            content: ChatMessageContent::Text(INTERRUPTED_TOOL_RESULT.to_string()),
+        });
+        issues.push(TraceIssue::MissingToolResult { index, tool_call_id });
+    }
+}
+
+/// Rough token cost of an image part, providers bill them by size
+const IMAGE_TOKENS: usize = 1000;
+/// Longest excerpt of a message kept in the compaction summary
+const SUMMARY_EXCERPT_CHARS: usize = 200;
+
+/// Token count of the trace, estimated at 4 characters per token.
+/// Only meant to tell when the trace gets close to the context window, not for billing.
+pub fn estimate_tokens(trace: &[ChatMessage]) -> usize {
+    trace.iter().map(|message| {
+        let (content, calls) = match message {
+            ChatMessage::System { content, .. }
+            | ChatMessage::Developer { content, .. }
+            | ChatMessage::User { content, .. }
+            | ChatMessage::Tool { content, .. } => (Some(content), None),
+            ChatMessage::Assistant { content, tool_calls, .. } => (content.as_ref(), tool_calls.as_ref()),
+        };
+        let mut chars = content.map_or(0, |content| content_text(content).len());
+        let images = match content {
+            Some(ChatMessageContent::ContentPart(parts)) => parts.iter()
+                .filter(|part| matches!(part, ChatMessageContentPart::Image(_)))
+                .count(),
+            _ => 0,
+        };
+        chars += calls.into_iter().flatten()
+            .map(|call| call.function.name.len() + call.function.arguments.len())
+            .sum::<usize>();
+        // role and separators
+        4 + chars.div_ceil(4) + images * IMAGE_TOKENS
+    }).sum()
+}
+
+/// Fold the messages between the task (everything up to the first user message) and the
+/// `keep_recent` last messages into one assistant message listing what happened, with the
+/// tool results cut to their first line. An assistant tool call is always folded or kept
+/// together with its results, so the compacted trace passes `check_tool_call_ids` whenever
+/// the original did. Returns None when there is nothing to fold.
+pub fn compact_trace(trace: &[ChatMessage], keep_recent: usize) -> Option<(Vec<ChatMessage>, usize)> {
+    let head = trace.iter()
+        .position(|message| matches!(message, ChatMessage::User { .. }))
+        .map_or(0, |index| index + 1);
+
+    // the kept messages must not start with results whose tool call gets folded
+    let mut tail = trace.len().saturating_sub(keep_recent);
+    while tail > head && matches!(trace.get(tail), Some(ChatMessage::Tool { .. })) {
+        tail -= 1;
+    }
+    if tail <= head {
+        return None;
+    }
+
+    let folded = &trace[head..tail];
+    let results: HashMap<&str, &ChatMessageContent> = folded.iter()
+        .filter_map(|message| match message {
+            ChatMessage::Tool { tool_call_id, content } => Some((tool_call_id.as_str(), content)),
+            _ => None,
+        })
+        .collect();
+
+    let mut summary = String::from("Earlier steps of this session, condensed to fit the context window:");
+    for message in folded {
+        match message {
+            ChatMessage::User { content, .. } => {
+                summary.push_str(&format!("\n- user: {}", excerpt(&content_text(content))));
+            }
+            ChatMessage::Assistant { content, tool_calls, .. } => {
+                let text = content.as_ref().map(content_text).unwrap_or_default();
+                if !text.trim().is_empty() {
+                    summary.push_str(&format!("\n- assistant: {}", excerpt(&text)));
+                }
+                for call in tool_calls.iter().flatten() {
+                    let result = results.get(call.id.as_str())
+                        .map(|content| content_text(content))
+                        .unwrap_or_else(|| INTERRUPTED_TOOL_RESULT.to_string());
+                    let first_line = result.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+                    summary.push_str(&format!("\n- {}({}) -> {}", call.function.name, excerpt(&call.function.arguments), excerpt(first_line)));
+                }
+            }
+            // tool results are listed with their call
+            _ => {}
+        }
+    }
+
+    let mut compacted = Vec::with_capacity(head + 1 + trace.len() - tail);
+    compacted.extend_from_slice(&trace[..head]);
+    compacted.push(ChatMessage::Assistant {
+        content: Some(ChatMessageContent::Text(summary)),
+        reasoning_content: None,
+        tool_calls: None,
+        refusal: None,
+        name: None,
+        audio: None,
+    });
+    compacted.extend_from_slice(&trace[tail..]);
+    Some((compacted, folded.len()))
+}
+
+pub(crate) fn content_text(content: &ChatMessageContent) -> String {
+    match content {
+        ChatMessageContent::Text(text) => text.clone(),
+        ChatMessageContent::ContentPart(parts) => parts.iter()
+            .filter_map(|part| match part {
+                ChatMessageContentPart::Text(text_part) => Some(text_part.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ChatMessageContent::None => String::new(),
+    }
+}
+
+/// Text on a single line, cut to SUMMARY_EXCERPT_CHARS
+fn excerpt(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(SUMMARY_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
@@ -4,8 +4,15 @@ use json_comments::StripComments;
 use serde::{Serialize, Deserialize};
 use shai_llm::ToolCallMethod;
 use crate::tools::mcp::McpConfig;
+use crate::tools::BashConfig;
 use super::config::ShaiConfig;
 
+/// Builtin tools of the "*" set of an agent config
+pub const ALL_BUILTIN_TOOLS: [&str; 15] = ["bash", "bash_status", "edit", "env", "multiedit", "apply_patch", "fetch", "find", "grep", "ls", "read", "todo_read", "todo_write", "undo", "write"];
+
+/// Builtin tools an agent only gets when its config lists them
+pub const OPT_IN_BUILTIN_TOOLS: [&str; 2] = ["git", "semantic_search"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentProviderConfig {
     pub provider: String,
@@ -25,6 +32,11 @@ pub struct McpToolConfig {
     /// If false, connection errors will be logged as warnings and agent will continue
     #[serde(default)]
     pub required: bool,
+    /// Register the tools as `<mcp_name>__<tool>` so they can't collide with the builtins or
+    /// the tools of another server (default: false). enabled_tools and excluded_tools keep the
+    /// server-side names
+    #[serde(default)]
+    pub prefix_tools: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +49,19 @@ pub struct AgentTools {
     pub mcp: HashMap<String, McpToolConfig>,
 }
 
+/// Check run once the agent stops after modifying files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerifyConfig {
+    /// Shell command (e.g. "cargo check"), a non-zero exit code means the changes are broken
+    pub command: String,
+    /// Send failures back to the agent so it fixes them, otherwise they are only reported
+    #[serde(default)]
+    pub auto_fix: bool,
+    /// Verification runs allowed per turn when auto_fix is on
+    #[serde(default = "default_verify_max_attempts")]
+    pub max_attempts: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentConfig {
     pub name: String,
@@ -51,6 +76,24 @@ pub struct AgentConfig {
     pub max_tokens: u32,
     #[serde(default = "default_temperature")]
     pub temperature: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify: Option<VerifyConfig>,
+    /// Ask the model to check its changes with a test or a build before it stops after modifying files
+    #[serde(default)]
+    pub verify_before_complete: bool,
+    /// Formatter command per file extension (e.g. "rs": "rustfmt"), run on the files the agent
+    /// wrote once it stops; `{file}` is replaced by the path, otherwise the path is appended
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub formatters: HashMap<String, String>,
+    /// Coerce tool arguments of the wrong type ("5" for 5, "true" for true...) to their schema type
+    #[serde(default = "default_normalize_tool_args")]
+    pub normalize_tool_args: bool,
+    /// Tokens (input and output) the agent may use in a session before it is stopped, until the next user input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<u64>,
+    /// Shell and environment of the bash tool over the ones of the user's config, see `BashConfig::overridden_by`
+    #[serde(default, skip_serializing_if = "BashConfig::is_empty")]
+    pub bash: BashConfig,
 }
 
 fn default_llm_provider() -> AgentProviderConfig {
@@ -82,10 +125,56 @@ fn default_temperature() -> f32 {
     0.3
 }
 
+fn default_normalize_tool_args() -> bool {
+    true
+}
+
+fn default_verify_max_attempts() -> u32 {
+    3
+}
+
 fn default_enabled_tools() -> Vec<String> {
     vec!["*".to_string()]
 }
 
+impl AgentProviderConfig {
+    /// Provider currently selected in the auth config
+    pub fn selected() -> Result<Self, Box<dyn std::error::Error>> {
+        let shai_config = ShaiConfig::load()?;
+        let provider_config = shai_config
+            .get_selected_provider()
+            .ok_or("No provider configured, run `shai auth` first")?;
+
+        Ok(Self {
+            provider: provider_config.provider.clone(),
+            env_vars: provider_config.env_vars.clone(),
+            model: provider_config.model.clone(),
+            tool_method: provider_config.tool_method.clone(),
+        })
+    }
+}
+
+impl AgentTools {
+    /// Builtin tools from a comma-separated list, empty or "*" for the whole "*" set
+    pub fn parse_builtin(list: &str) -> Result<Vec<String>, String> {
+        let tools: Vec<String> = list.split(',')
+            .map(|tool| tool.trim().to_lowercase())
+            .filter(|tool| !tool.is_empty())
+            .collect();
+        if tools.is_empty() || tools.iter().any(|tool| tool == "*") {
+            return Ok(vec!["*".to_string()]);
+        }
+
+        for tool in &tools {
+            if !ALL_BUILTIN_TOOLS.contains(&tool.as_str()) && !OPT_IN_BUILTIN_TOOLS.contains(&tool.as_str()) {
+                return Err(format!("Unknown builtin tool: {} (available: {}, {})",
+                    tool, ALL_BUILTIN_TOOLS.join(", "), OPT_IN_BUILTIN_TOOLS.join(", ")));
+            }
+        }
+        Ok(tools)
+    }
+}
+
 impl Default for AgentTools {
     fn default() -> Self {
         Self {
@@ -97,6 +186,36 @@ impl Default for AgentTools {
 }
 
 impl AgentConfig {
+    /// New agent with the default prompt and settings
+    pub fn new(name: &str, description: &str, llm_provider: AgentProviderConfig, builtin: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            llm_provider,
+            tools: AgentTools { builtin, ..AgentTools::default() },
+            system_prompt: default_system_prompt(),
+            max_tokens: default_max_tokens(),
+            temperature: default_temperature(),
+            verify: None,
+            verify_before_complete: false,
+            formatters: HashMap::new(),
+            normalize_tool_args: default_normalize_tool_args(),
+            token_budget: None,
+            bash: BashConfig::default(),
+        }
+    }
+
+    /// Agent names become file names: letters, digits, '-' and '_' only
+    pub fn validate_name(agent_name: &str) -> Result<(), String> {
+        if agent_name.is_empty() {
+            return Err("Agent name cannot be empty".to_string());
+        }
+        if !agent_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Invalid agent name '{}': use letters, digits, '-' and '_' only", agent_name));
+        }
+        Ok(())
+    }
+
     /// Get the agents directory path
     pub fn agents_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
         let config_dir = std::env::var("XDG_CONFIG_HOME")
@@ -206,4 +325,39 @@ impl AgentConfig {
             .cloned()
             .collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_builtin_tools() {
+        assert_eq!(AgentTools::parse_builtin(""), Ok(vec!["*".to_string()]));
+        assert_eq!(AgentTools::parse_builtin(" * "), Ok(vec!["*".to_string()]));
+        assert_eq!(AgentTools::parse_builtin("Read, ls,git,"), Ok(vec!["read".to_string(), "ls".to_string(), "git".to_string()]));
+        assert!(AgentTools::parse_builtin("read, rm").unwrap_err().contains("rm"));
+    }
+
+    #[test]
+    fn test_new_agent_config_round_trips() {
+        let provider = AgentProviderConfig {
+            provider: "ollama".to_string(),
+            env_vars: HashMap::new(),
+            model: "qwen3".to_string(),
+            tool_method: ToolCallMethod::FunctionCall,
+        };
+        let config = AgentConfig::new("reviewer", "reviews code", provider, vec!["read".to_string()]);
+        let parsed: AgentConfig = serde_json::from_str(&serde_json::to_string_pretty(&config).unwrap()).unwrap();
+
+        assert_eq!(parsed.name, "reviewer");
+        assert_eq!(parsed.llm_provider.model, "qwen3");
+        assert_eq!(parsed.tools.builtin, vec!["read"]);
+        assert_eq!(parsed.system_prompt, "{{CODER_BASE_PROMPT}}");
+        assert!(parsed.tools.mcp.is_empty());
+
+        assert!(AgentConfig::validate_name("code-reviewer_2").is_ok());
+        assert!(AgentConfig::validate_name("../reviewer").is_err());
+        assert!(AgentConfig::validate_name("").is_err());
+    }
+}
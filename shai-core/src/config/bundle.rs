@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::tools::mcp::McpConfig;
+use super::agent::AgentConfig;
+use super::config::ShaiConfig;
+use super::diff::AgentConfigDiff;
+
+/// Format version written in every bundle, bumped on incompatible changes
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Parts of an env var name that mark its value as a secret
+const SECRET_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD"];
+
+/// The whole shai setup in one document: auth config (providers and MCP servers) and every agent config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub config: ShaiConfig,
+    #[serde(default)]
+    pub agents: BTreeMap<String, AgentConfig>,
+}
+
+/// Something an import would overwrite
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleConflict {
+    /// the local auth config differs from the bundled one
+    Config,
+    /// a local agent with the same name has a different config, with the fields that differ
+    Agent { name: String, fields: Vec<String> },
+}
+
+impl fmt::Display for BundleConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleConflict::Config => write!(f, "auth.config: providers or MCP servers differ"),
+            BundleConflict::Agent { name, fields } => write!(f, "agent '{}': {}", name, fields.join(", ")),
+        }
+    }
+}
+
+pub fn is_secret_env_var(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+impl ConfigBundle {
+    pub fn new(config: ShaiConfig, agents: Vec<AgentConfig>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            config,
+            agents: agents.into_iter().map(|agent| (agent.name.clone(), agent)).collect(),
+        }
+    }
+
+    /// Gather the config files of this machine, the default config is used when there is no auth.config
+    pub fn collect() -> Result<Self, Box<dyn std::error::Error>> {
+        let config = if ShaiConfig::exists() { ShaiConfig::load_global()? } else { ShaiConfig::default() };
+        let agents = AgentConfig::list_agents()?
+            .iter()
+            .map(|name| AgentConfig::load(name).map_err(|e| format!("agent '{}': {}", name, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(config, agents))
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let bundle: ConfigBundle = serde_json::from_str(content)?;
+        Ok(bundle)
+    }
+
+    /// Same bundle with the secret env vars emptied and the MCP OAuth tokens removed,
+    /// fit to share as a template. The emptied keys are kept so the receiver knows what to fill.
+    pub fn without_secrets(mut self) -> Self {
+        for provider in &mut self.config.providers {
+            strip_env_vars(&mut provider.env_vars);
+        }
+        for mcp in self.config.mcp_configs.values_mut() {
+            strip_mcp_auth(mcp);
+        }
+        for agent in self.agents.values_mut() {
+            strip_env_vars(&mut agent.llm_provider.env_vars);
+            for mcp in agent.tools.mcp.values_mut() {
+                strip_mcp_auth(&mut mcp.config);
+            }
+        }
+        self
+    }
+
+    /// Fill the secrets left empty in the bundle with the values this machine already has
+    /// for the same provider
+    pub fn fill_secrets_from(&mut self, local: &ShaiConfig) {
+        let local_value = |provider: &str, name: &str| {
+            local.providers.iter()
+                .filter(|p| p.provider == provider)
+                .find_map(|p| p.env_vars.get(name).filter(|v| !v.is_empty()).cloned())
+        };
+
+        for provider in &mut self.config.providers {
+            for (name, value) in provider.env_vars.iter_mut().filter(|(_, v)| v.is_empty()) {
+                if let Some(local) = local_value(&provider.provider, name) {
+                    *value = local;
+                }
+            }
+        }
+        for agent in self.agents.values_mut() {
+            let provider = &mut agent.llm_provider;
+            for (name, value) in provider.env_vars.iter_mut().filter(|(_, v)| v.is_empty()) {
+                if let Some(local) = local_value(&provider.provider, name) {
+                    *value = local;
+                }
+            }
+        }
+    }
+
+    /// Secret env vars without value, as "<where>: <name>"
+    pub fn missing_secrets(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        for provider in &self.config.providers {
+            missing.extend(empty_secrets(&provider.env_vars).map(|name| format!("provider {}: {}", provider.provider, name)));
+        }
+        for agent in self.agents.values() {
+            missing.extend(empty_secrets(&agent.llm_provider.env_vars).map(|name| format!("agent {}: {}", agent.name, name)));
+        }
+        missing
+    }
+
+    /// Everything that would make the bundle unusable once imported
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.version > BUNDLE_VERSION {
+            errors.push(format!("bundle version {} is newer than the supported version {}", self.version, BUNDLE_VERSION));
+        }
+        if self.config.providers.is_empty() {
+            errors.push("no provider configured".to_string());
+        } else if self.config.selected_provider >= self.config.providers.len() {
+            errors.push(format!("selected provider {} out of bounds (have {} providers)", self.config.selected_provider, self.config.providers.len()));
+        }
+        for (key, agent) in &self.agents {
+            if *key != agent.name {
+                errors.push(format!("agent '{}' is stored under the name '{}'", agent.name, key));
+            }
+            if agent.name.is_empty() || agent.name.contains(['/', '\\']) || agent.name.starts_with('.') {
+                errors.push(format!("invalid agent name '{}'", agent.name));
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// What importing this bundle would overwrite in `local`, identical configs are not conflicts
+    pub fn conflicts_with(&self, local: &ConfigBundle) -> Vec<BundleConflict> {
+        let mut conflicts = Vec::new();
+        if serde_json::to_value(&self.config).ok() != serde_json::to_value(&local.config).ok() {
+            conflicts.push(BundleConflict::Config);
+        }
+        for (name, agent) in &self.agents {
+            let Some(existing) = local.agents.get(name) else {
+                continue;
+            };
+            let diff = AgentConfigDiff::between(existing, agent);
+            let mut fields: Vec<String> = diff.scalars.iter().map(|c| c.field.clone())
+                .chain(diff.lists.iter().map(|c| c.field.clone()))
+                .collect();
+            if !diff.system_prompt.is_empty() {
+                fields.push("system_prompt".to_string());
+            }
+            if fields.is_empty() && serde_json::to_value(existing).ok() != serde_json::to_value(agent).ok() {
+                fields.push("other settings".to_string());
+            }
+            if !fields.is_empty() {
+                conflicts.push(BundleConflict::Agent { name: name.clone(), fields });
+            }
+        }
+        conflicts
+    }
+
+    /// Write the auth config and every agent config, agents not in the bundle are left untouched
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.config.save()?;
+        for agent in self.agents.values() {
+            agent.save()?;
+        }
+        Ok(())
+    }
+}
+
+fn strip_env_vars(env_vars: &mut std::collections::HashMap<String, String>) {
+    for (name, value) in env_vars.iter_mut() {
+        // a ${NAME} reference holds no secret, the bundle keeps it
+        if is_secret_env_var(name) && !value.contains("${") {
+            value.clear();
+        }
+    }
+}
+
+fn strip_mcp_auth(mcp: &mut McpConfig) {
+    if let McpConfig::Http { auth, .. } = mcp {
+        *auth = None;
+    }
+}
+
+fn empty_secrets(env_vars: &std::collections::HashMap<String, String>) -> impl Iterator<Item = &String> {
+    let mut names: Vec<&String> = env_vars.iter()
+        .filter(|(name, value)| value.is_empty() && is_secret_env_var(name))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn agent(name: &str, temperature: f32) -> AgentConfig {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "description": "test agent",
+            "llm_provider": {
+                "provider": "openai",
+                "env_vars": {"OPENAI_API_KEY": "sk-agent", "OPENAI_BASE_URL": "https://api.openai.com/v1"},
+                "model": "gpt-4o",
+                "tool_method": "FunctionCall"
+            },
+            "temperature": temperature
+        })).unwrap()
+    }
+
+    fn config() -> ShaiConfig {
+        let mut config = ShaiConfig::default();
+        config.providers[0].env_vars.insert("OVH_API_KEY".to_string(), "ovh-secret".to_string());
+        config.add_provider(
+            "openai".to_string(),
+            HashMap::from([("OPENAI_API_KEY".to_string(), "sk-local".to_string())]),
+            "gpt-4o".to_string(),
+        );
+        config
+    }
+
+    #[test]
+    fn test_bundle_roundtrip_without_secrets() {
+        let bundle = ConfigBundle::new(config(), vec![agent("reviewer", 0.3)]).without_secrets();
+        let json = serde_json::to_string_pretty(&bundle).unwrap();
+        assert!(!json.contains("ovh-secret") && !json.contains("sk-agent"), "{}", json);
+        assert!(json.contains("https://api.openai.com/v1"), "{}", json);
+
+        let mut imported = ConfigBundle::from_json(&json).unwrap();
+        assert!(imported.validate().is_ok());
+        assert_eq!(imported.missing_secrets(), vec![
+            "provider ovhcloud: OVH_API_KEY".to_string(),
+            "provider openai: OPENAI_API_KEY".to_string(),
+            "agent reviewer: OPENAI_API_KEY".to_string(),
+        ]);
+
+        imported.fill_secrets_from(&config());
+        assert!(imported.missing_secrets().is_empty());
+        assert_eq!(imported.agents["reviewer"].llm_provider.env_vars["OPENAI_API_KEY"], "sk-local");
+    }
+
+    #[test]
+    fn test_bundle_conflicts_and_validation() {
+        let local = ConfigBundle::new(config(), vec![agent("reviewer", 0.3), agent("writer", 0.3)]);
+        let bundle = ConfigBundle::new(config(), vec![agent("reviewer", 0.7), agent("writer", 0.3), agent("new", 0.3)]);
+        assert_eq!(bundle.conflicts_with(&local), vec![
+            BundleConflict::Agent { name: "reviewer".to_string(), fields: vec!["temperature".to_string()] },
+        ]);
+
+        let mut broken = bundle.clone();
+        broken.config.selected_provider = 5;
+        broken.agents.insert("../evil".to_string(), agent("../evil", 0.3));
+        let errors = broken.validate().unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+}
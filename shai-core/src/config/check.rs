@@ -0,0 +1,444 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use json_comments::StripComments;
+use reqwest::Url;
+use shai_llm::LlmClient;
+use crate::tools::mcp::McpConfig;
+use super::agent::{AgentConfig, ALL_BUILTIN_TOOLS, OPT_IN_BUILTIN_TOOLS};
+use super::config::{resolve_env_vars, ProjectConfig, ShaiConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// shai fails or misbehaves with this config
+    Error,
+    /// the config loads but something looks wrong
+    Warning,
+}
+
+/// Position of a parse error with the source line it points at
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceContext {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+/// One problem found in a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub file: PathBuf,
+    pub severity: Severity,
+    pub message: String,
+    pub context: Option<SourceContext>,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match &self.context {
+            Some(context) => {
+                writeln!(f, "{}: {}:{}:{}: {}", label, self.file.display(), context.line, context.column, self.message)?;
+                writeln!(f, "  {:>4} | {}", context.line, context.text)?;
+                write!(f, "       | {}^", " ".repeat(context.column.saturating_sub(1)))
+            }
+            None => write!(f, "{}: {}: {}", label, self.file.display(), self.message),
+        }
+    }
+}
+
+/// Result of `shai config check`: the files read and what is wrong with them
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReport {
+    pub files: Vec<PathBuf>,
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigReport {
+    /// Check auth.config, the `.shai.config` of the current project and every agent config of
+    /// this machine. A file that can't be read is reported and the others are still checked
+    pub fn run() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut report = Self::default();
+
+        let config_path = ShaiConfig::config_path()?;
+        let config = if config_path.exists() {
+            report.read(&config_path).and_then(|content| report.check_config(&config_path, &content))
+        } else {
+            report.warn(&config_path, "no auth config, run `shai auth` to add a provider");
+            None
+        };
+
+        if let Some(path) = std::env::current_dir().ok().and_then(|dir| ProjectConfig::find(&dir)) {
+            if let Some(content) = report.read(&path) {
+                report.check_project(&path, &content, config.as_ref());
+            }
+        }
+
+        let agents_dir = AgentConfig::agents_dir()?;
+        match AgentConfig::list_agents() {
+            Ok(names) => {
+                for name in names {
+                    let path = agents_dir.join(format!("{}.config", name));
+                    if let Some(content) = report.read(&path) {
+                        report.check_agent(&path, &content, config.as_ref());
+                    }
+                }
+            }
+            Err(e) => report.error(&agents_dir, format!("cannot list the agents: {}", e)),
+        }
+        Ok(report)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues.iter().filter(|issue| issue.severity == severity).count()
+    }
+
+    /// Check the content of an auth.config, the parsed config is returned when it is readable
+    pub fn check_config(&mut self, path: &Path, content: &[u8]) -> Option<ShaiConfig> {
+        self.files.push(path.to_path_buf());
+        let config: ShaiConfig = match serde_json::from_reader(StripComments::new(content)) {
+            Ok(config) => config,
+            Err(e) => {
+                self.parse_error(path, content, e);
+                return None;
+            }
+        };
+
+        if config.providers.is_empty() {
+            self.error(path, "no provider configured, run `shai auth` to add one");
+        } else if config.selected_provider >= config.providers.len() {
+            self.warn(path, format!("selected_provider {} is out of bounds ({} providers), the first provider is used",
+                config.selected_provider, config.providers.len()));
+        }
+
+        for (index, provider) in config.providers.iter().enumerate() {
+            let place = format!("provider {} ({})", index, provider.provider);
+            self.check_provider(path, &place, &provider.provider, &provider.env_vars);
+        }
+
+        let mut profiles: Vec<_> = config.profiles.iter().collect();
+        profiles.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, profile) in profiles {
+            if profile.provider >= config.providers.len() {
+                self.error(path, format!("profile '{}' uses provider {} which does not exist ({} providers)",
+                    name, profile.provider, config.providers.len()));
+            }
+        }
+
+        let mut servers: Vec<_> = config.mcp_configs.iter().collect();
+        servers.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, mcp) in servers {
+            self.check_mcp(path, name, mcp, false);
+        }
+
+        Some(config)
+    }
+
+    /// Check the content of a project `.shai.config`, `config` is the auth config it is merged over
+    pub fn check_project(&mut self, path: &Path, content: &[u8], config: Option<&ShaiConfig>) {
+        self.files.push(path.to_path_buf());
+        let project: ProjectConfig = match serde_json::from_reader(StripComments::new(content)) {
+            Ok(project) => project,
+            Err(e) => return self.parse_error(path, content, e),
+        };
+
+        if let Some(pinned) = &project.provider {
+            let providers = LlmClient::list_providers();
+            if !providers.iter().any(|info| info.name == pinned.provider) {
+                let names: Vec<_> = providers.iter().map(|info| info.name).collect();
+                self.error(path, format!("provider: unknown provider '{}' (known: {})", pinned.provider, names.join(", ")));
+            } else if !config.is_some_and(|config| config.providers.iter().any(|p| p.provider == pinned.provider)) {
+                self.warn(path, format!("asks for the {} provider which is not configured, run `shai auth` to add it", pinned.provider));
+            }
+        }
+
+        let mut servers: Vec<_> = project.mcp_configs.iter().collect();
+        servers.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, mcp) in servers {
+            match mcp {
+                // a project never starts a command, see ProjectConfig
+                McpConfig::Stdio { command, .. } => self.warn(path, format!(
+                    "MCP '{}' runs a command ({}) and is ignored, add it to your own config to use it", name, command)),
+                _ => self.check_mcp(path, name, mcp, false),
+            }
+        }
+
+        for pattern in &project.bash_deny_list {
+            if let Err(e) = regex::Regex::new(pattern) {
+                self.error(path, format!("bash_deny_list: invalid pattern {:?}: {}", pattern, e));
+            }
+        }
+    }
+
+    /// Check the content of an agent config, `config` is the auth config it falls back on
+    pub fn check_agent(&mut self, path: &Path, content: &[u8], config: Option<&ShaiConfig>) {
+        self.files.push(path.to_path_buf());
+
+        // syntax first: an agent without llm_provider takes the selected provider of the auth config
+        let value: serde_json::Value = match serde_json::from_reader(StripComments::new(content)) {
+            Ok(value) => value,
+            Err(e) => return self.parse_error(path, content, e),
+        };
+        if value.get("llm_provider").is_none() && !config.is_some_and(|config| !config.providers.is_empty()) {
+            return self.error(path, "no llm_provider and no provider in the auth config to fall back on");
+        }
+        let agent: AgentConfig = match serde_json::from_reader(StripComments::new(content)) {
+            Ok(agent) => agent,
+            Err(e) => return self.parse_error(path, content, e),
+        };
+
+        let file_name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        if agent.name != file_name {
+            self.warn(path, format!("agent is named '{}' but is loaded as '{}'", agent.name, file_name));
+        }
+
+        let provider = &agent.llm_provider;
+        self.check_provider(path, "llm_provider", &provider.provider, &provider.env_vars);
+
+        let known = |tool: &str| tool == "*" || ALL_BUILTIN_TOOLS.contains(&tool) || OPT_IN_BUILTIN_TOOLS.contains(&tool);
+        for tool in agent.tools.builtin.iter().filter(|tool| !known(tool)) {
+            self.error(path, format!("unknown builtin tool '{}', the agent will not start (available: {}, {})",
+                tool, ALL_BUILTIN_TOOLS.join(", "), OPT_IN_BUILTIN_TOOLS.join(", ")));
+        }
+        for tool in agent.tools.builtin_excluded.iter().filter(|tool| !known(tool)) {
+            self.warn(path, format!("builtin_excluded names unknown tool '{}'", tool));
+        }
+
+        let mut servers: Vec<_> = agent.tools.mcp.iter().collect();
+        servers.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, mcp) in servers {
+            self.check_mcp(path, name, &mcp.config, mcp.required);
+            if mcp.enabled_tools.is_empty() {
+                self.warn(path, format!("MCP '{}' enables no tool", name));
+            }
+        }
+    }
+
+    fn check_provider(&mut self, path: &Path, place: &str, provider: &str, env_vars: &std::collections::HashMap<String, String>) {
+        let providers = LlmClient::list_providers();
+        let Some(info) = providers.iter().find(|info| info.name == provider) else {
+            let names: Vec<_> = providers.iter().map(|info| info.name).collect();
+            return self.error(path, format!("{}: unknown provider '{}' (known: {})", place, provider, names.join(", ")));
+        };
+
+        // the environment of the machine running the check may not be the one running shai
+        if let Err(e) = resolve_env_vars(env_vars) {
+            self.warn(path, format!("{}: {}", place, e));
+        }
+        for env_var in info.env_vars.iter().filter(|env_var| env_var.required) {
+            let configured = env_vars.get(&env_var.name).is_some_and(|value| !value.is_empty());
+            if !configured && std::env::var(&env_var.name).is_err() {
+                self.warn(path, format!("{}: {} is neither configured nor set in the environment", place, env_var.name));
+            }
+        }
+    }
+
+    /// An MCP server the agent needs (`required`) is an error when unreachable, otherwise it is skipped
+    fn check_mcp(&mut self, path: &Path, name: &str, mcp: &McpConfig, required: bool) {
+        let problem = match mcp {
+            McpConfig::Stdio { command, .. } if find_command(command).is_none() => {
+                format!("MCP '{}': command '{}' not found", name, command)
+            }
+            McpConfig::Http { url, .. } | McpConfig::Sse { url } if !is_http_url(url) => {
+                format!("MCP '{}': '{}' is not an http(s) URL", name, url)
+            }
+            _ => return,
+        };
+        if required {
+            self.error(path, problem);
+        } else {
+            self.warn(path, format!("{}, it will be skipped", problem));
+        }
+    }
+
+    /// Content of a file to check, None with an error reported when it can't be read
+    fn read(&mut self, path: &Path) -> Option<Vec<u8>> {
+        match std::fs::read(path) {
+            Ok(content) => Some(content),
+            Err(e) => {
+                self.files.push(path.to_path_buf());
+                self.error(path, format!("cannot read the file: {}", e));
+                None
+            }
+        }
+    }
+
+    fn parse_error(&mut self, path: &Path, content: &[u8], error: serde_json::Error) {
+        // comments are blanked out, not removed, so the positions are those of the file
+        let text = String::from_utf8_lossy(content)
+            .lines()
+            .nth(error.line().saturating_sub(1))
+            .unwrap_or_default()
+            .to_string();
+        let message = error.to_string();
+        // serde_json appends the position, the context already shows it
+        let message = message.split(" at line ").next().unwrap_or(&message).to_string();
+        self.issues.push(ConfigIssue {
+            file: path.to_path_buf(),
+            severity: Severity::Error,
+            message,
+            context: Some(SourceContext { line: error.line(), column: error.column(), text }),
+        });
+    }
+
+    fn error(&mut self, path: &Path, message: impl Into<String>) {
+        self.push(path, Severity::Error, message.into());
+    }
+
+    fn warn(&mut self, path: &Path, message: impl Into<String>) {
+        self.push(path, Severity::Warning, message.into());
+    }
+
+    fn push(&mut self, path: &Path, severity: Severity, message: String) {
+        self.issues.push(ConfigIssue { file: path.to_path_buf(), severity, message, context: None });
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Path of a command as the MCP client would start it: as given when it has a directory, else from PATH
+fn find_command(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| {
+            let candidate = dir.join(command);
+            [candidate.with_extension("exe"), candidate]
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "providers": [{"provider": "ollama", "env_vars": {}, "model": "qwen3", "tool_method": "FunctionCall"}],
+        "selected_provider": 0
+    }"#;
+
+    fn config() -> ShaiConfig {
+        let mut report = ConfigReport::default();
+        report.check_config(Path::new("auth.config"), CONFIG.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_the_line() {
+        let content = "{\n  // providers\n  \"providers\": [,\n}";
+        let mut report = ConfigReport::default();
+        assert!(report.check_config(Path::new("auth.config"), content.as_bytes()).is_none());
+
+        assert!(report.has_errors());
+        let context = report.issues[0].context.clone().unwrap();
+        assert_eq!(context.line, 3);
+        assert_eq!(context.text, "  \"providers\": [,");
+        assert!(!report.issues[0].message.contains(" at line "));
+        assert!(report.issues[0].to_string().contains("auth.config:3:"));
+    }
+
+    #[test]
+    fn test_valid_config_has_no_issue() {
+        let mut report = ConfigReport::default();
+        report.check_config(Path::new("auth.config"), CONFIG.as_bytes());
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_agent_checks_provider_tools_and_mcp() {
+        let content = r#"{
+            "name": "reviewer",
+            "description": "reviews code",
+            "llm_provider": {"provider": "olama", "env_vars": {}, "model": "qwen3", "tool_method": "FunctionCall"},
+            "tools": {
+                "builtin": ["read", "rm"],
+                "builtin_excluded": ["wirte"],
+                "mcp": {
+                    "docs": {"config": {"type": "stdio", "command": "shai-no-such-command", "args": []}, "required": true},
+                    "web": {"config": {"type": "sse", "url": "localhost:3000"}}
+                }
+            }
+        }"#;
+        let mut report = ConfigReport::default();
+        report.check_agent(Path::new("agents/reviewer.config"), content.as_bytes(), Some(&config()));
+
+        let errors: Vec<_> = report.issues.iter().filter(|i| i.severity == Severity::Error).map(|i| i.message.as_str()).collect();
+        let warnings: Vec<_> = report.issues.iter().filter(|i| i.severity == Severity::Warning).map(|i| i.message.as_str()).collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("unknown provider 'olama'"));
+        assert!(errors[1].contains("unknown builtin tool 'rm'"));
+        assert!(errors[2].contains("shai-no-such-command"));
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[0].contains("wirte"));
+        assert!(warnings[1].contains("MCP 'web'") && warnings[1].contains("skipped"));
+    }
+
+    #[test]
+    fn test_agent_without_provider_needs_one_in_the_auth_config() {
+        let content = r#"{"name": "reviewer", "description": "reviews code"}"#;
+        let mut report = ConfigReport::default();
+        report.check_agent(Path::new("agents/reviewer.config"), content.as_bytes(), None);
+        assert!(report.has_errors());
+        assert!(report.issues[0].message.contains("llm_provider"));
+    }
+
+    #[test]
+    fn test_project_checks_provider_mcp_and_deny_list() {
+        let content = r#"{
+            // pinned by the repository
+            "provider": {"provider": "openai", "model": "gpt-4o"},
+            "mcp_configs": {
+                "local": {"type": "stdio", "command": "docs-server", "args": []},
+                "web": {"type": "sse", "url": "localhost:3000"}
+            },
+            "bash_deny_list": ["\\bnpm publish\\b", "rm -rf ("]
+        }"#;
+        let mut report = ConfigReport::default();
+        report.check_project(Path::new(".shai.config"), content.as_bytes(), Some(&config()));
+
+        let errors: Vec<_> = report.issues.iter().filter(|i| i.severity == Severity::Error).map(|i| i.message.as_str()).collect();
+        let warnings: Vec<_> = report.issues.iter().filter(|i| i.severity == Severity::Warning).map(|i| i.message.as_str()).collect();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].contains("rm -rf ("));
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[0].contains("openai provider which is not configured"));
+        assert!(warnings[1].contains("MCP 'local'") && warnings[1].contains("ignored"));
+        assert!(warnings[2].contains("MCP 'web'"));
+
+        let mut report = ConfigReport::default();
+        report.check_project(Path::new(".shai.config"), b"{\"provider\": {\"provider\": \"olama\"}}", Some(&config()));
+        assert!(report.issues[0].message.contains("unknown provider 'olama'"), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_unreadable_file_is_reported() {
+        let mut report = ConfigReport::default();
+        assert!(report.read(Path::new("/no/such/dir/auth.config")).is_none());
+        assert_eq!(report.files.len(), 1);
+        assert!(report.has_errors());
+        assert!(report.issues[0].message.contains("cannot read"));
+    }
+
+    #[test]
+    fn test_profiles_must_name_an_existing_provider() {
+        let content = r#"{
+            "providers": [{"provider": "ollama", "env_vars": {}, "model": "qwen3", "tool_method": "FunctionCall"}],
+            "selected_provider": 3,
+            "profiles": {"fast": {"provider": 1}}
+        }"#;
+        let mut report = ConfigReport::default();
+        report.check_config(Path::new("auth.config"), content.as_bytes());
+        assert_eq!(report.count(Severity::Error), 1);
+        assert_eq!(report.count(Severity::Warning), 1);
+        assert!(report.issues.iter().any(|i| i.message.contains("profile 'fast'")));
+    }
+}
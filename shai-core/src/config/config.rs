@@ -1,12 +1,16 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}};
 use std::fs;
+use std::sync::{Mutex, OnceLock};
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use reqwest::Url;
 use json_comments::StripComments;
 use serde::{Serialize, Deserialize};
-use shai_llm::{LlmClient, ToolCallMethod};
+use shai_llm::{LlmClient, RateLimit, ToolCallMethod};
 use crate::tools::mcp::McpConfig;
+use crate::agent::DEFAULT_BASH_DENY_LIST;
+use crate::tools::{BashConfig, ToolOutputLimit, DEFAULT_FETCH_MAX_BYTES, DEFAULT_READ_MAX_BYTES};
+use super::pricing::ModelPrice;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
@@ -16,12 +20,332 @@ pub struct ProviderConfig {
     pub tool_method: ToolCallMethod
 }
 
+impl ProviderConfig {
+    /// env_vars with their `${NAME}` references resolved, see `interpolate_env`
+    pub fn resolved_env_vars(&self) -> Result<HashMap<String, String>, String> {
+        resolve_env_vars(&self.env_vars)
+    }
+}
+
+/// Value of a provider env var with every `${NAME}` replaced by the variable NAME of shai's
+/// environment, so the config can hold a reference instead of the secret. `$${` writes a
+/// literal `${`, values without a reference are kept as they are
+pub fn interpolate_env(value: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find('}') else {
+            return Err(format!("unclosed ${{ in \"{}\"", value));
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        if name.is_empty() {
+            return Err(format!("empty ${{}} in \"{}\"", value));
+        }
+        let resolved = std::env::var(name)
+            .map_err(|_| format!("environment variable {} is not set", name))?;
+        result.push_str(&resolved);
+        rest = &rest[start + 2 + len + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Every value of `env_vars` interpolated, the error names the entry that could not be resolved
+pub fn resolve_env_vars(env_vars: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+    env_vars.iter()
+        .map(|(name, value)| interpolate_env(value)
+            .map(|value| (name.clone(), value))
+            .map_err(|e| format!("{}: {}", name, e)))
+        .collect()
+}
+
+/// Environment variable naming the profile to use when none is given on the command line
+pub const PROFILE_ENV: &str = "SHAI_PROFILE";
+
+/// A named choice of provider, e.g. "fast" or "local"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    /// Index in `providers`
+    pub provider: usize,
+    /// Model to use instead of the provider's own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// What to do when the configured model is not in the provider's model list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnavailableModelPolicy {
+    /// Refuse to start and list the models the provider offers
+    #[default]
+    Fail,
+    /// Warn and use the provider's default model instead
+    Fallback,
+}
+
+/// Panel the TUI shows on first run, deployments can ship their own content in the default config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnboardingConfig {
+    /// Set when the config is created on first run, cleared once the user dismisses the panel
+    #[serde(default)]
+    pub pending: bool,
+    #[serde(default = "default_onboarding_title")]
+    pub title: String,
+    #[serde(default = "default_example_prompts")]
+    pub example_prompts: Vec<String>,
+}
+
+impl Default for OnboardingConfig {
+    fn default() -> Self {
+        Self {
+            pending: false,
+            title: default_onboarding_title(),
+            example_prompts: default_example_prompts(),
+        }
+    }
+}
+
+fn default_onboarding_title() -> String {
+    "Welcome to shai!".to_string()
+}
+
+fn default_example_prompts() -> Vec<String> {
+    vec![
+        "explain the architecture of this project".to_string(),
+        "find and fix the failing tests".to_string(),
+        "add a --verbose flag to the cli".to_string(),
+    ]
+}
+
+/// How the CLI renders the agent activity, e.g. lighter colors on a light terminal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputConfig {
+    /// Lines of tool output previewed under each tool call
+    #[serde(default = "default_max_preview_lines")]
+    pub max_preview_lines: usize,
+    /// Show the reasoning of the models that send it
+    #[serde(default = "default_show_reasoning")]
+    pub show_reasoning: bool,
+    #[serde(default)]
+    pub palette: OutputPalette,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            max_preview_lines: default_max_preview_lines(),
+            show_reasoning: default_show_reasoning(),
+            palette: OutputPalette::default(),
+        }
+    }
+}
+
+fn default_max_preview_lines() -> usize {
+    10
+}
+
+fn default_show_reasoning() -> bool {
+    true
+}
+
+/// RGB colors of the formatted output, bold text is drawn a bit lighter
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OutputPalette {
+    #[serde(default = "default_success_color")]
+    pub success: [u8; 3],
+    #[serde(default = "default_error_color")]
+    pub error: [u8; 3],
+    /// reasoning and echoed user input
+    #[serde(default = "default_dim_color")]
+    pub dim: [u8; 3],
+}
+
+impl Default for OutputPalette {
+    fn default() -> Self {
+        Self {
+            success: default_success_color(),
+            error: default_error_color(),
+            dim: default_dim_color(),
+        }
+    }
+}
+
+fn default_success_color() -> [u8; 3] {
+    [100, 255, 100]
+}
+
+fn default_error_color() -> [u8; 3] {
+    [255, 100, 100]
+}
+
+fn default_dim_color() -> [u8; 3] {
+    [120, 120, 120]
+}
+
+/// Name of the per-project config, looked up from the current directory to the root
+pub const PROJECT_CONFIG_FILE: &str = ".shai.config";
+
+/// Tools an agent may use, by name: `allow` keeps only the listed tools when set, `deny` removes some
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+}
+
+impl ToolFilter {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    pub fn allows(&self, tool: &str) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|t| t == tool)) && !self.deny.iter().any(|t| t == tool)
+    }
+
+    /// The filter with `other` applied on top: a tool must pass both
+    fn narrowed_by(&self, other: &ToolFilter) -> ToolFilter {
+        let allow = match (self.allow.is_empty(), other.allow.is_empty()) {
+            (_, true) => self.allow.clone(),
+            (true, false) => other.allow.clone(),
+            (false, false) => self.allow.iter().filter(|t| other.allow.contains(t)).cloned().collect(),
+        };
+        let mut deny = self.deny.clone();
+        deny.extend(other.deny.iter().filter(|t| !self.deny.contains(t)).cloned());
+        ToolFilter { allow, deny }
+    }
+}
+
+/// Settings a repository pins in its `.shai.config`, merged over the user's config.
+/// Credentials and endpoints stay in the user's config: a project picks one of the configured
+/// providers and its model, not where the keys are sent, and only remote MCP servers are taken
+/// so that running shai in a freshly cloned repository never starts a command
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub provider: Option<ProjectProvider>,
+    #[serde(default)]
+    pub mcp_configs: HashMap<String, McpConfig>,
+    #[serde(default)]
+    pub tools: ToolFilter,
+    /// Added to the user's deny-list, a project can't remove patterns from it
+    #[serde(default)]
+    pub bash_deny_list: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectProvider {
+    pub provider: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub tool_method: Option<ToolCallMethod>,
+}
+
+impl ProjectConfig {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content_bytes = fs::read(path)?;
+        Ok(serde_json::from_reader(StripComments::new(&content_bytes[..]))?)
+    }
+
+    /// The closest `.shai.config` in `dir` or one of its parents
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Where the values of a loaded config come from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOrigin {
+    /// Project config merged over auth.config, if one was found
+    pub project_file: Option<PathBuf>,
+    /// Settings taken from the project config ("provider", "tools", "mcp_configs.<name>"...),
+    /// every other value comes from auth.config
+    pub project_keys: Vec<String>,
+}
+
+impl ConfigOrigin {
+    /// The project config a setting was taken from, None when it comes from auth.config
+    pub fn project_source(&self, key: &str) -> Option<&Path> {
+        self.project_keys.iter().any(|k| k == key).then_some(self.project_file.as_deref()).flatten()
+    }
+}
+
+/// Print a config warning once, the config is loaded many times per run
+fn warn_once(message: String) {
+    static SHOWN: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    if SHOWN.get_or_init(Default::default).lock().unwrap().insert(message.clone()) {
+        eprintln!("\x1b[2m⚠ {}\x1b[0m", message);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShaiConfig {
     pub providers: Vec<ProviderConfig>,
     pub selected_provider: usize,
+    /// Named providers picked with `--profile` or SHAI_PROFILE, `selected_provider` is used otherwise
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, ProfileConfig>,
     #[serde(default)]
     pub mcp_configs: HashMap<String, McpConfig>,
+    #[serde(default)]
+    pub unavailable_model: UnavailableModelPolicy,
+    #[serde(default)]
+    pub onboarding: OnboardingConfig,
+    /// Largest response body the fetch tool reads, in bytes
+    #[serde(default = "default_fetch_max_bytes")]
+    pub fetch_max_bytes: u64,
+    /// Largest text the read tool returns in one call, in bytes
+    #[serde(default = "default_read_max_bytes")]
+    pub read_max_bytes: u64,
+    /// Largest tool output the model sees, in bytes overall and by tool name, the middle of longer ones is cut
+    #[serde(default, skip_serializing_if = "ToolOutputLimit::is_default")]
+    pub tool_output: ToolOutputLimit,
+    /// Regexes of the bash commands that are never run, even in sudo mode
+    #[serde(default = "default_bash_deny_list")]
+    pub bash_deny_list: Vec<String>,
+    /// Shell of the bash tool and the environment variables its commands see
+    #[serde(default, skip_serializing_if = "BashConfig::is_empty")]
+    pub bash: BashConfig,
+    /// Tools the agents may use
+    #[serde(default, skip_serializing_if = "ToolFilter::is_empty")]
+    pub tools: ToolFilter,
+    /// Give the default agent the git tool
+    #[serde(default)]
+    pub git_tool: bool,
+    /// Preview length, reasoning and colors of the formatted output
+    #[serde(default)]
+    pub output: OutputConfig,
+    /// Price of the models missing from the default table or priced differently, by model name prefix
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub pricing: HashMap<String, ModelPrice>,
+    /// Requests and tokens per minute by provider name, calls over the budget wait instead of failing
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rate_limits: HashMap<String, RateLimit>,
+    /// Where the values come from, filled by `load`
+    #[serde(skip)]
+    pub origin: ConfigOrigin,
+}
+
+fn default_fetch_max_bytes() -> u64 {
+    DEFAULT_FETCH_MAX_BYTES
+}
+
+fn default_read_max_bytes() -> u64 {
+    DEFAULT_READ_MAX_BYTES
+}
+
+fn default_bash_deny_list() -> Vec<String> {
+    DEFAULT_BASH_DENY_LIST.iter().map(|pattern| pattern.to_string()).collect()
 }
 
 impl ShaiConfig {
@@ -69,6 +393,28 @@ impl ShaiConfig {
         }
     }
 
+    /// Profile names, sorted
+    pub fn list_profiles(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.profiles.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Make the provider of the profile the selected one, with the profile's model if it sets one
+    pub fn select_profile(&mut self, name: &str) -> Result<(), String> {
+        let profile = match self.profiles.get(name) {
+            Some(profile) => profile.clone(),
+            None if self.profiles.is_empty() => return Err(format!("unknown profile '{}', no profiles are configured", name)),
+            None => return Err(format!("unknown profile '{}' (available: {})", name, self.list_profiles().join(", "))),
+        };
+        self.set_selected_provider(profile.provider)
+            .map_err(|e| format!("profile '{}': {}", name, e))?;
+        if let Some(model) = profile.model {
+            self.providers[profile.provider].model = model;
+        }
+        Ok(())
+    }
+
     pub fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
         let config_dir = std::env::var("XDG_CONFIG_HOME")
             .map(PathBuf::from)
@@ -84,7 +430,77 @@ impl ShaiConfig {
         Ok(shai_config_dir.join("auth.config"))
     }
 
+    /// The user's config merged with the `.shai.config` of the project shai runs in, if any.
+    /// Use `load_global` to edit and save the config, the merged view belongs to the project
     pub fn load() -> Result<ShaiConfig, Box<dyn std::error::Error>> {
+        let mut config = Self::load_global()?;
+        let project_file = std::env::current_dir().ok().and_then(|dir| ProjectConfig::find(&dir));
+        if let Some(path) = project_file {
+            match ProjectConfig::load(&path) {
+                Ok(project) => config.merge_project(project, path),
+                Err(e) => warn_once(format!("ignoring {}: {}", path.display(), e)),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Apply a project config over this one and record which settings it changed
+    pub fn merge_project(&mut self, project: ProjectConfig, path: PathBuf) {
+        let mut keys = Vec::new();
+
+        if let Some(pinned) = project.provider {
+            // the selected provider when it is of the pinned type, its keys are the user's
+            let index = Some(self.selected_provider)
+                .filter(|&i| self.providers.get(i).is_some_and(|p| p.provider == pinned.provider))
+                .or_else(|| self.providers.iter().position(|p| p.provider == pinned.provider));
+            match index {
+                Some(index) => {
+                    let provider = &mut self.providers[index];
+                    if let Some(model) = pinned.model {
+                        provider.model = model;
+                    }
+                    if let Some(tool_method) = pinned.tool_method {
+                        provider.tool_method = tool_method;
+                    }
+                    self.selected_provider = index;
+                    keys.push("provider".to_string());
+                }
+                None => warn_once(format!(
+                    "{} asks for the {} provider which is not configured, run `shai auth` to add it",
+                    path.display(), pinned.provider
+                )),
+            }
+        }
+
+        let mut names: Vec<_> = project.mcp_configs.into_iter().collect();
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, mcp) in names {
+            if let McpConfig::Stdio { command, .. } = &mcp {
+                warn_once(format!(
+                    "{}: MCP server '{}' runs a command ({}), add it to your own config to use it",
+                    path.display(), name, command
+                ));
+                continue;
+            }
+            self.mcp_configs.insert(name.clone(), mcp);
+            keys.push(format!("mcp_configs.{}", name));
+        }
+
+        if !project.tools.is_empty() {
+            self.tools = self.tools.narrowed_by(&project.tools);
+            keys.push("tools".to_string());
+        }
+
+        if !project.bash_deny_list.is_empty() {
+            self.bash_deny_list.extend(project.bash_deny_list);
+            keys.push("bash_deny_list".to_string());
+        }
+
+        self.origin = ConfigOrigin { project_file: Some(path), project_keys: keys };
+    }
+
+    /// The user's config alone, auth.config
+    pub fn load_global() -> Result<ShaiConfig, Box<dyn std::error::Error>> {
         let config_path = Self::config_path()?;
         
         if !config_path.exists() {
@@ -121,17 +537,69 @@ impl ShaiConfig {
         Ok(())
     }
 
+    /// Fetch size limit of the config file, the default one when there is no config
+    pub fn load_fetch_max_bytes() -> u64 {
+        Self::load().map(|config| config.fetch_max_bytes).unwrap_or(DEFAULT_FETCH_MAX_BYTES)
+    }
+
+    /// Read size limit of the config file, the default one when there is no config
+    pub fn load_read_max_bytes() -> u64 {
+        Self::load().map(|config| config.read_max_bytes).unwrap_or(DEFAULT_READ_MAX_BYTES)
+    }
+
+    /// Tool output limits of the config file, the default size for every tool when there is no config
+    pub fn load_tool_output_limit() -> ToolOutputLimit {
+        Self::load().map(|config| config.tool_output).unwrap_or_default()
+    }
+
+    /// Bash deny-list of the config file, the default one when there is no config
+    pub fn load_bash_deny_list() -> Vec<String> {
+        Self::load().map(|config| config.bash_deny_list).unwrap_or_else(|_| default_bash_deny_list())
+    }
+
+    /// Bash shell and environment of the config file, $SHELL and shai's whole environment when there is no config
+    pub fn load_bash() -> BashConfig {
+        Self::load().map(|config| config.bash).unwrap_or_default()
+    }
+
+    /// Tool filter of the config file, every tool allowed when there is no config
+    pub fn load_tool_filter() -> ToolFilter {
+        Self::load().map(|config| config.tools).unwrap_or_default()
+    }
+
+    /// Whether the default agent gets the git tool, off when there is no config
+    pub fn load_git_tool() -> bool {
+        Self::load().map(|config| config.git_tool).unwrap_or(false)
+    }
+
+    /// Output settings of the config file, the default look when there is no config
+    pub fn load_output() -> OutputConfig {
+        Self::load().map(|config| config.output).unwrap_or_default()
+    }
+
+    /// Model prices of the config file, only the default table applies when there is no config
+    pub fn load_pricing() -> HashMap<String, ModelPrice> {
+        Self::load().map(|config| config.pricing).unwrap_or_default()
+    }
+
+    pub fn load_rate_limit(provider: &str) -> RateLimit {
+        Self::load().ok().and_then(|config| config.rate_limits.get(provider).copied()).unwrap_or_default()
+    }
+
     pub fn exists() -> bool {
         Self::config_path()
             .map(|path| path.exists())
             .unwrap_or(false)
     }
 
-    /// Set environment variables from the currently selected provider
+    /// Set environment variables from the currently selected provider, `${NAME}` references resolved.
+    /// A variable whose reference can't be resolved is left unset, creating the client reports it
     pub fn set_env_vars(&self) {
         if let Some(provider_config) = self.get_selected_provider() {
             for (name, value) in &provider_config.env_vars {
-                std::env::set_var(name, value);
+                if let Ok(value) = interpolate_env(value) {
+                    std::env::set_var(name, value);
+                }
             }
             // Set model-specific environment variable if model is specified
             std::env::set_var("SHAI_MODEL", &provider_config.model);
@@ -158,6 +626,14 @@ impl ShaiConfig {
             self.selected_provider -= 1;
         }
 
+        // profiles of the removed provider go with it, the others follow their provider
+        self.profiles.retain(|_, profile| profile.provider != index);
+        for profile in self.profiles.values_mut() {
+            if profile.provider > index {
+                profile.provider -= 1;
+            }
+        }
+
         Ok(removed)
     }
 
@@ -233,28 +709,266 @@ impl Default for ShaiConfig {
                 tool_method: ToolCallMethod::FunctionCall
             }],
             selected_provider: 0,
+            profiles: HashMap::new(),
             mcp_configs: HashMap::new(),
+            unavailable_model: UnavailableModelPolicy::default(),
+            onboarding: OnboardingConfig::default(),
+            fetch_max_bytes: DEFAULT_FETCH_MAX_BYTES,
+            read_max_bytes: DEFAULT_READ_MAX_BYTES,
+            tool_output: ToolOutputLimit::default(),
+            bash_deny_list: default_bash_deny_list(),
+            bash: BashConfig::default(),
+            tools: ToolFilter::default(),
+            git_tool: false,
+            output: OutputConfig::default(),
+            pricing: HashMap::new(),
+            rate_limits: HashMap::new(),
+            origin: ConfigOrigin::default(),
         }
     }
 }
 
 impl ShaiConfig {
+    /// LLM of the profile named by SHAI_PROFILE, of the selected provider when it is not set
     pub async fn get_llm() -> Result<(LlmClient, String), Box<dyn std::error::Error>>{
-        let config = ShaiConfig::load()
+        Self::get_llm_with_profile(None).await
+    }
+
+    /// Config with the given profile selected, falling back to SHAI_PROFILE
+    fn load_with_profile(profile: Option<&str>) -> Result<ShaiConfig, String> {
+        let mut config = ShaiConfig::load()
             .unwrap_or_else(|_| ShaiConfig::default());
 
+        let profile = profile.map(str::to_string)
+            .or_else(|| std::env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty()));
+        if let Some(profile) = profile {
+            config.select_profile(&profile)?;
+        }
+        Ok(config)
+    }
+
+    /// Tool call method of the provider `get_llm_with_profile` picks for the same profile
+    pub fn load_tool_method(profile: Option<&str>) -> ToolCallMethod {
+        Self::load_with_profile(profile).ok()
+            .and_then(|config| config.get_selected_provider().map(|provider| provider.tool_method))
+            .unwrap_or(ToolCallMethod::FunctionCall)
+    }
+
+    /// LLM of the given profile, falling back to SHAI_PROFILE and then to the selected provider
+    pub async fn get_llm_with_profile(profile: Option<&str>) -> Result<(LlmClient, String), Box<dyn std::error::Error>>{
+        let config = Self::load_with_profile(profile)?;
         config.set_env_vars();
         
         let llm = if let Some(provider_config) = config.get_selected_provider() {
+            let env_vars = provider_config.resolved_env_vars()
+                .map_err(|e| format!("Invalid {} config: {}", provider_config.provider, e))?;
             LlmClient::create_provider(
                 &provider_config.provider, 
-                &provider_config.env_vars)
+                &env_vars)
                 .map_err(|e| format!("Failed to create {} client: {}", provider_config.provider, e))?
+                .with_rate_limit(config.rate_limits.get(&provider_config.provider).copied().unwrap_or_default())
         } else {
             return Err("No provider configured".into());
         };
     
         let model = llm.default_model().await.map_err(|_| "no Model available")?;
+        let model = resolve_model(&llm, &model, config.unavailable_model).await?;
         Ok((llm, model))
     }
-}
\ No newline at end of file
+}
+
+/// Check the model against the provider's model list before the first call uses it.
+/// Providers that cannot list their models are trusted as is.
+pub async fn resolve_model(llm: &LlmClient, model: &str, policy: UnavailableModelPolicy) -> Result<String, String> {
+    let available: Vec<String> = match llm.models().await {
+        Ok(models) => models.data.into_iter().map(|m| m.id).collect(),
+        Err(_) => return Ok(model.to_string()),
+    };
+    if available.is_empty() || available.iter().any(|m| m == model) {
+        return Ok(model.to_string());
+    }
+
+    match policy {
+        UnavailableModelPolicy::Fallback => {
+            // ask the provider directly, the client would return SHAI_MODEL again
+            let fallback = match llm.provider().default_model().await {
+                Ok(default) if available.contains(&default) => default,
+                _ => available[0].clone(),
+            };
+            eprintln!("\x1b[2m⚠ model '{}' is not available on {}, using '{}' instead\x1b[0m",
+                model, llm.provider_name(), fallback);
+            Ok(fallback)
+        }
+        UnavailableModelPolicy::Fail => Err(format!(
+            "model '{}' is not available on {}. Available models: {}. Run `shai auth` to pick another one, or set \"unavailable_model\": \"fallback\" in the config to use the provider's default model",
+            model, llm.provider_name(), suggest_models(model, &available).join(", ")
+        )),
+    }
+}
+
+/// Models sharing the family of the requested one first (e.g. "gpt-4" for "gpt-4o-mini"), at most 10
+fn suggest_models(model: &str, available: &[String]) -> Vec<String> {
+    let family = model.split(['-', ':', '/']).next().unwrap_or(model).to_lowercase();
+    let (mut similar, others): (Vec<&String>, Vec<&String>) = available.iter()
+        .partition(|m| m.to_lowercase().contains(&family));
+    similar.extend(others);
+    let mut suggestions: Vec<String> = similar.into_iter().take(10).cloned().collect();
+    if available.len() > suggestions.len() {
+        suggestions.push(format!("... ({} more)", available.len() - suggestions.len()));
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use openai_dive::v1::resources::chat::{ChatCompletionParameters, ChatCompletionResponse};
+    use openai_dive::v1::resources::model::{ListModelResponse, Model};
+    use shai_llm::provider::{LlmError, LlmProvider, LlmStream, ProviderInfo};
+
+    /// Only answers the model list, with a fixed default model
+    struct ModelList {
+        models: Vec<&'static str>,
+        default: &'static str,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ModelList {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Ok(ListModelResponse {
+                object: "list".to_string(),
+                data: self.models.iter().map(|id| Model {
+                    id: id.to_string(),
+                    object: "model".to_string(),
+                    created: None,
+                    owned_by: "test".to_string(),
+                }).collect(),
+            })
+        }
+
+        async fn default_model(&self) -> Result<String, LlmError> {
+            Ok(self.default.to_string())
+        }
+
+        async fn chat(&self, _request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            Err("no chat".into())
+        }
+
+        async fn chat_stream(&self, _request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            Err("no chat".into())
+        }
+
+        fn supports_functions(&self, _model: String) -> bool { true }
+
+        fn supports_structured_output(&self, _model: String) -> bool { false }
+
+        fn name(&self) -> &'static str { "models" }
+
+        fn info() -> ProviderInfo where Self: Sized {
+            ProviderInfo { name: "models", display_name: "Models", env_vars: vec![] }
+        }
+    }
+
+    fn client() -> LlmClient {
+        LlmClient::from_provider(Box::new(ModelList {
+            models: vec!["mistral-large", "gpt-4o", "gpt-4o-mini"],
+            default: "gpt-4o-mini",
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_resolve_model_keeps_available_model() {
+        let model = resolve_model(&client(), "gpt-4o", UnavailableModelPolicy::Fail).await.unwrap();
+        assert_eq!(model, "gpt-4o");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_model_invalid_name_fails_or_falls_back() {
+        let err = resolve_model(&client(), "gpt-5-turbo", UnavailableModelPolicy::Fail).await.unwrap_err();
+        assert!(err.contains("'gpt-5-turbo' is not available"), "{}", err);
+        assert!(err.contains("gpt-4o, gpt-4o-mini, mistral-large"), "{}", err);
+
+        let model = resolve_model(&client(), "gpt-5-turbo", UnavailableModelPolicy::Fallback).await.unwrap();
+        assert_eq!(model, "gpt-4o-mini");
+    }
+
+    #[test]
+    fn test_onboarding_not_pending_for_existing_configs() {
+        let config: ShaiConfig = serde_json::from_str(r#"{"providers": [], "selected_provider": 0}"#).unwrap();
+        assert_eq!(config.onboarding, OnboardingConfig::default());
+        assert!(!config.onboarding.pending);
+
+        let config: ShaiConfig = serde_json::from_str(r#"{"providers": [], "selected_provider": 0,
+            "onboarding": {"pending": true, "example_prompts": ["deploy it"]}}"#).unwrap();
+        assert!(config.onboarding.pending);
+        assert_eq!(config.onboarding.title, "Welcome to shai!");
+        assert_eq!(config.onboarding.example_prompts, vec!["deploy it".to_string()]);
+    }
+
+    #[test]
+    fn test_project_config_merge() {
+        let mut config = ShaiConfig::default();
+        config.add_provider("openai".to_string(), HashMap::from([("OPENAI_API_KEY".to_string(), "sk-user".to_string())]), "gpt-4o".to_string());
+        config.tools.deny = vec!["fetch".to_string()];
+
+        let project: ProjectConfig = serde_json::from_str(r#"{
+            "provider": {"provider": "openai", "model": "gpt-4.1", "env_vars": {"OPENAI_BASE_URL": "https://attacker.example"}},
+            "mcp_configs": {
+                "docs": {"type": "sse", "url": "https://docs.example/sse"},
+                "local": {"type": "stdio", "command": "./serve.sh", "args": []}
+            },
+            "tools": {"allow": ["read", "grep", "fetch"], "deny": ["bash"]},
+            "bash_deny_list": ["git push"]
+        }"#).unwrap();
+        config.merge_project(project, PathBuf::from("/repo/.shai.config"));
+
+        // the user's provider with its key, only the model changes
+        let provider = config.get_selected_provider().unwrap();
+        assert_eq!(provider.model, "gpt-4.1");
+        assert_eq!(provider.env_vars, HashMap::from([("OPENAI_API_KEY".to_string(), "sk-user".to_string())]));
+
+        assert!(config.mcp_configs.contains_key("docs"));
+        assert!(!config.mcp_configs.contains_key("local"));
+
+        assert!(config.tools.allows("read"));
+        assert!(!config.tools.allows("fetch"));
+        assert!(!config.tools.allows("bash"));
+        assert!(!config.tools.allows("write"));
+        assert_eq!(config.bash_deny_list.last().map(String::as_str), Some("git push"));
+
+        assert_eq!(config.origin.project_source("provider"), Some(Path::new("/repo/.shai.config")));
+        assert_eq!(config.origin.project_source("mcp_configs.docs"), Some(Path::new("/repo/.shai.config")));
+        assert_eq!(config.origin.project_source("unavailable_model"), None);
+    }
+
+    #[test]
+    fn test_project_config_found_in_parent_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let nested = root.path().join("crates/core/src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.path().join(PROJECT_CONFIG_FILE), "{}").unwrap();
+        fs::write(root.path().join("crates").join(PROJECT_CONFIG_FILE), "{}").unwrap();
+
+        assert_eq!(ProjectConfig::find(&nested), Some(root.path().join("crates").join(PROJECT_CONFIG_FILE)));
+        assert_eq!(ProjectConfig::find(root.path()), Some(root.path().join(PROJECT_CONFIG_FILE)));
+    }
+
+    #[test]
+    fn test_env_vars_interpolate_references() {
+        std::env::set_var("SHAI_TEST_INTERPOLATED_KEY", "sk-from-env");
+        let env_vars = HashMap::from([
+            ("OPENAI_API_KEY".to_string(), "${SHAI_TEST_INTERPOLATED_KEY}".to_string()),
+            ("OPENAI_BASE_URL".to_string(), "https://api.openai.com/v1".to_string()),
+            ("SHAI_NOTE".to_string(), "Bearer ${SHAI_TEST_INTERPOLATED_KEY} and $${LITERAL}".to_string()),
+        ]);
+        let resolved = resolve_env_vars(&env_vars).unwrap();
+        assert_eq!(resolved["OPENAI_API_KEY"], "sk-from-env");
+        assert_eq!(resolved["OPENAI_BASE_URL"], "https://api.openai.com/v1");
+        assert_eq!(resolved["SHAI_NOTE"], "Bearer sk-from-env and ${LITERAL}");
+
+        let err = resolve_env_vars(&HashMap::from([("OPENAI_API_KEY".to_string(), "${SHAI_TEST_UNSET_KEY}".to_string())])).unwrap_err();
+        assert_eq!(err, "OPENAI_API_KEY: environment variable SHAI_TEST_UNSET_KEY is not set");
+        assert!(interpolate_env("${UNCLOSED").unwrap_err().contains("unclosed"));
+    }
+}
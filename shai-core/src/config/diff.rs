@@ -0,0 +1,208 @@
+use std::collections::BTreeSet;
+use similar::{ChangeTag, TextDiff};
+
+use super::agent::AgentConfig;
+
+/// A scalar field whose value differs between the two configs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarChange {
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// A list-like field, only the entries present on one side are reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListChange {
+    pub field: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineChange {
+    Removed(String),
+    Added(String),
+}
+
+/// Field-by-field difference between two agent configs, going from `left` to `right`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentConfigDiff {
+    pub scalars: Vec<ScalarChange>,
+    pub lists: Vec<ListChange>,
+    /// Line diff of the system prompt, empty when both prompts are identical
+    pub system_prompt: Vec<LineChange>,
+}
+
+impl AgentConfigDiff {
+    pub fn between(left: &AgentConfig, right: &AgentConfig) -> Self {
+        let mut diff = Self::default();
+
+        diff.scalar("description", &left.description, &right.description);
+        diff.scalar("provider", &left.llm_provider.provider, &right.llm_provider.provider);
+        diff.scalar("model", &left.llm_provider.model, &right.llm_provider.model);
+        diff.scalar("tool_method", &format!("{:?}", left.llm_provider.tool_method), &format!("{:?}", right.llm_provider.tool_method));
+        diff.scalar("max_tokens", &left.max_tokens.to_string(), &right.max_tokens.to_string());
+        diff.scalar("temperature", &left.temperature.to_string(), &right.temperature.to_string());
+        diff.scalar("verify.command",
+            left.verify.as_ref().map_or("", |v| v.command.as_str()),
+            right.verify.as_ref().map_or("", |v| v.command.as_str()));
+        diff.scalar("verify.auto_fix",
+            &left.verify.as_ref().is_some_and(|v| v.auto_fix).to_string(),
+            &right.verify.as_ref().is_some_and(|v| v.auto_fix).to_string());
+
+        diff.scalar("verify_before_complete", &left.verify_before_complete.to_string(), &right.verify_before_complete.to_string());
+        diff.scalar("normalize_tool_args", &left.normalize_tool_args.to_string(), &right.normalize_tool_args.to_string());
+        diff.scalar("token_budget",
+            &left.token_budget.map_or(String::new(), |tokens| tokens.to_string()),
+            &right.token_budget.map_or(String::new(), |tokens| tokens.to_string()));
+
+        diff.scalar("bash.shell", left.bash.shell.as_deref().unwrap_or(""), right.bash.shell.as_deref().unwrap_or(""));
+        diff.list("bash.env_allow", &left.bash.env_allow, &right.bash.env_allow);
+        diff.list("bash.env_deny", &left.bash.env_deny, &right.bash.env_deny);
+
+        diff.list("formatters", left.formatters.keys(), right.formatters.keys());
+        let mut shared_formatters: Vec<&String> = left.formatters.keys()
+            .filter(|ext| right.formatters.contains_key(*ext))
+            .collect();
+        shared_formatters.sort();
+        for ext in shared_formatters {
+            diff.scalar(&format!("formatters.{}", ext), &left.formatters[ext], &right.formatters[ext]);
+        }
+
+        // env vars usually hold secrets, so only the keys are compared
+        diff.list("env_vars", left.llm_provider.env_vars.keys(), right.llm_provider.env_vars.keys());
+        for (key, value) in &left.llm_provider.env_vars {
+            if right.llm_provider.env_vars.get(key).is_some_and(|v| v != value) {
+                diff.scalar(&format!("env_vars.{}", key), "***", "*** (changed)");
+            }
+        }
+
+        diff.list("tools.builtin", &left.tools.builtin, &right.tools.builtin);
+        diff.list("tools.builtin_excluded", &left.tools.builtin_excluded, &right.tools.builtin_excluded);
+        diff.list("tools.mcp", left.tools.mcp.keys(), right.tools.mcp.keys());
+
+        let mut shared_mcp: Vec<&String> = left.tools.mcp.keys()
+            .filter(|name| right.tools.mcp.contains_key(*name))
+            .collect();
+        shared_mcp.sort();
+        for name in shared_mcp {
+            let (l, r) = (&left.tools.mcp[name], &right.tools.mcp[name]);
+            diff.list(&format!("tools.mcp.{}.enabled_tools", name), &l.enabled_tools, &r.enabled_tools);
+            diff.list(&format!("tools.mcp.{}.excluded_tools", name), &l.excluded_tools, &r.excluded_tools);
+            diff.scalar(&format!("tools.mcp.{}.required", name), &l.required.to_string(), &r.required.to_string());
+            diff.scalar(&format!("tools.mcp.{}.prefix_tools", name), &l.prefix_tools.to_string(), &r.prefix_tools.to_string());
+        }
+
+        if left.system_prompt != right.system_prompt {
+            diff.system_prompt = TextDiff::from_lines(&left.system_prompt, &right.system_prompt)
+                .iter_all_changes()
+                .filter_map(|change| {
+                    let line = change.value().trim_end_matches('\n').to_string();
+                    match change.tag() {
+                        ChangeTag::Delete => Some(LineChange::Removed(line)),
+                        ChangeTag::Insert => Some(LineChange::Added(line)),
+                        ChangeTag::Equal => None,
+                    }
+                })
+                .collect();
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scalars.is_empty() && self.lists.is_empty() && self.system_prompt.is_empty()
+    }
+
+    fn scalar(&mut self, field: &str, left: &str, right: &str) {
+        if left != right {
+            self.scalars.push(ScalarChange {
+                field: field.to_string(),
+                left: left.to_string(),
+                right: right.to_string(),
+            });
+        }
+    }
+
+    fn list<'a>(&mut self, field: &str, left: impl IntoIterator<Item = &'a String>, right: impl IntoIterator<Item = &'a String>) {
+        let left: BTreeSet<&String> = left.into_iter().collect();
+        let right: BTreeSet<&String> = right.into_iter().collect();
+
+        let added: Vec<String> = right.difference(&left).map(|s| s.to_string()).collect();
+        let removed: Vec<String> = left.difference(&right).map(|s| s.to_string()).collect();
+
+        if !added.is_empty() || !removed.is_empty() {
+            self.lists.push(ListChange { field: field.to_string(), added, removed });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use shai_llm::ToolCallMethod;
+    use crate::config::agent::{AgentProviderConfig, AgentTools};
+
+    fn config(name: &str) -> AgentConfig {
+        AgentConfig {
+            name: name.to_string(),
+            description: "an agent".to_string(),
+            llm_provider: AgentProviderConfig {
+                provider: "openai".to_string(),
+                env_vars: HashMap::from([("OPENAI_API_KEY".to_string(), "sk-1".to_string())]),
+                model: "gpt-4o".to_string(),
+                tool_method: ToolCallMethod::FunctionCall,
+            },
+            tools: AgentTools {
+                builtin: vec!["read".to_string(), "ls".to_string()],
+                builtin_excluded: Vec::new(),
+                mcp: HashMap::new(),
+            },
+            system_prompt: "line one\nline two\n".to_string(),
+            max_tokens: 4096,
+            temperature: 0.3,
+            verify: None,
+            verify_before_complete: false,
+            formatters: HashMap::new(),
+            normalize_tool_args: true,
+            token_budget: None,
+            bash: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_identical_configs_have_empty_diff() {
+        // the name is the file identity, not a setting worth reporting
+        assert!(AgentConfigDiff::between(&config("a"), &config("b")).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_scalars_tools_and_prompt() {
+        let left = config("a");
+        let mut right = config("b");
+        right.llm_provider.model = "gpt-4.1".to_string();
+        right.llm_provider.env_vars.insert("OPENAI_API_KEY".to_string(), "sk-2".to_string());
+        right.temperature = 0.7;
+        right.tools.builtin = vec!["read".to_string(), "bash".to_string()];
+        right.system_prompt = "line one\nline 2\n".to_string();
+
+        let diff = AgentConfigDiff::between(&left, &right);
+
+        let fields: Vec<&str> = diff.scalars.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["model", "temperature", "env_vars.OPENAI_API_KEY"]);
+        assert!(diff.scalars.iter().all(|c| !c.left.contains("sk-") && !c.right.contains("sk-")));
+
+        assert_eq!(diff.lists, vec![ListChange {
+            field: "tools.builtin".to_string(),
+            added: vec!["bash".to_string()],
+            removed: vec!["ls".to_string()],
+        }]);
+
+        assert_eq!(diff.system_prompt, vec![
+            LineChange::Removed("line two".to_string()),
+            LineChange::Added("line 2".to_string()),
+        ]);
+    }
+}
@@ -1,2 +1,9 @@
 pub mod config;
-pub mod agent;
\ No newline at end of file
+pub mod agent;
+pub mod diff;
+pub mod bundle;
+pub mod check;
+pub mod pricing;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::TokenTotals;
+use super::config::ShaiConfig;
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input:  f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    pub const fn new(input: f64, output: f64) -> Self {
+        Self { input, output }
+    }
+
+    pub fn cost(&self, usage: &TokenTotals) -> f64 {
+        (usage.input_tokens as f64 * self.input + usage.output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// Public list prices of common models, a name matches its longest prefix in the table.
+/// The `pricing` section of the config adds models or overrides these
+pub const DEFAULT_PRICES: &[(&str, ModelPrice)] = &[
+    ("gpt-4o-mini",       ModelPrice::new(0.15, 0.60)),
+    ("gpt-4o",            ModelPrice::new(2.50, 10.00)),
+    ("gpt-4.1-nano",      ModelPrice::new(0.10, 0.40)),
+    ("gpt-4.1-mini",      ModelPrice::new(0.40, 1.60)),
+    ("gpt-4.1",           ModelPrice::new(2.00, 8.00)),
+    ("o3-mini",           ModelPrice::new(1.10, 4.40)),
+    ("o4-mini",           ModelPrice::new(1.10, 4.40)),
+    ("claude-3-5-haiku",  ModelPrice::new(0.80, 4.00)),
+    ("claude-3-5-sonnet", ModelPrice::new(3.00, 15.00)),
+    ("claude-3-7-sonnet", ModelPrice::new(3.00, 15.00)),
+    ("claude-sonnet-4",   ModelPrice::new(3.00, 15.00)),
+    ("claude-opus-4",     ModelPrice::new(15.00, 75.00)),
+    ("mistral-large",     ModelPrice::new(2.00, 6.00)),
+    ("mistral-small",     ModelPrice::new(0.10, 0.30)),
+    ("codestral",         ModelPrice::new(0.30, 0.90)),
+];
+
+/// The model an agent runs on and its price, None when neither the config nor the table knows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelPricing {
+    pub model: String,
+    pub price: Option<ModelPrice>,
+}
+
+impl ModelPricing {
+    /// Price of the longest name of `prices` and the default table that `model` starts with, `prices`
+    /// wins over the table for the same name. Names are compared without case and without the
+    /// `provider/` part some gateways put in front of them
+    pub fn lookup(model: &str, prices: &HashMap<String, ModelPrice>) -> Self {
+        let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        let configured = prices.iter().map(|(key, price)| (key.as_str(), *price, true));
+        let defaults = DEFAULT_PRICES.iter().map(|(key, price)| (*key, *price, false));
+        let price = best_match(&name, configured.chain(defaults));
+        Self { model: model.to_string(), price }
+    }
+
+    /// Price of `model` with the `pricing` section of ShaiConfig, the default table when there is no config
+    pub fn from_config(model: &str) -> Self {
+        Self::lookup(model, &ShaiConfig::load_pricing())
+    }
+
+    pub fn estimate(&self, usage: &TokenTotals) -> CostEstimate {
+        CostEstimate {
+            model: self.model.clone(),
+            usd: self.price.map(|price| price.cost(usage)).unwrap_or(0.0),
+            priced: self.price.is_some(),
+        }
+    }
+}
+
+/// Exact name first, then the longest prefix, a configured price before a default one of the same length
+fn best_match<'a>(name: &str, prices: impl Iterator<Item = (&'a str, ModelPrice, bool)>) -> Option<ModelPrice> {
+    prices
+        .map(|(key, price, configured)| (key.to_lowercase(), price, configured))
+        .filter(|(key, _, _)| name.starts_with(key.as_str()))
+        .max_by_key(|(key, _, configured)| (key.len(), *configured))
+        .map(|(_, price, _)| price)
+}
+
+/// Estimated cost of the tokens a session used. A model without a known price costs zero
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub model: String,
+    pub usd: f64,
+    pub priced: bool,
+}
+
+impl CostEstimate {
+    /// Why the estimate is zero, for models without a price
+    pub fn note(&self) -> Option<String> {
+        (!self.priced).then(|| format!("no price known for {}, add it to `pricing` in the config", self.model))
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "~${:.3}", self.usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input_tokens: u64, output_tokens: u64) -> TokenTotals {
+        TokenTotals { input_tokens, output_tokens }
+    }
+
+    #[test]
+    fn test_lookup_takes_the_longest_prefix() {
+        let pricing = ModelPricing::lookup("gpt-4o-mini-2024-07-18", &HashMap::new());
+        assert_eq!(pricing.price, Some(ModelPrice::new(0.15, 0.60)));
+
+        let pricing = ModelPricing::lookup("openrouter/GPT-4o", &HashMap::new());
+        assert_eq!(pricing.price, Some(ModelPrice::new(2.50, 10.00)));
+        assert_eq!(pricing.model, "openrouter/GPT-4o");
+    }
+
+    #[test]
+    fn test_config_prices_win_for_the_same_name() {
+        let prices = HashMap::from([
+            ("gpt-4o".to_string(), ModelPrice::new(1.0, 2.0)),
+            ("qwen3".to_string(), ModelPrice::new(0.5, 0.5)),
+        ]);
+        assert_eq!(ModelPricing::lookup("gpt-4o", &prices).price, Some(ModelPrice::new(1.0, 2.0)));
+        assert_eq!(ModelPricing::lookup("Qwen3-32B", &prices).price, Some(ModelPrice::new(0.5, 0.5)));
+        // a longer name of the table is a closer match than a shorter configured one
+        assert_eq!(ModelPricing::lookup("gpt-4o-mini", &prices).price, Some(ModelPrice::new(0.15, 0.60)));
+        let prices = HashMap::from([("gpt".to_string(), ModelPrice::new(1.0, 1.0))]);
+        assert_eq!(ModelPricing::lookup("gpt-4o", &prices).price, Some(ModelPrice::new(2.50, 10.00)));
+        assert_eq!(ModelPricing::lookup("gpt-unknown", &prices).price, Some(ModelPrice::new(1.0, 1.0)));
+    }
+
+    #[test]
+    fn test_estimate() {
+        let estimate = ModelPricing::lookup("gpt-4o", &HashMap::new()).estimate(&usage(12_000, 1_200));
+        assert!((estimate.usd - 0.042).abs() < 1e-9, "{}", estimate.usd);
+        assert_eq!(estimate.to_string(), "~$0.042");
+        assert!(estimate.note().is_none());
+
+        let estimate = ModelPricing::lookup("Qwen3-32B", &HashMap::new()).estimate(&usage(12_000, 1_200));
+        assert_eq!(estimate.to_string(), "~$0.000");
+        assert!(estimate.note().unwrap().contains("Qwen3-32B"));
+    }
+}
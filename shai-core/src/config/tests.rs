@@ -0,0 +1,36 @@
+use super::config::ShaiConfig;
+
+#[test]
+fn test_profiles_select_provider_and_model() {
+    let mut config: ShaiConfig = serde_json::from_str(r#"{
+        "providers": [
+            {"provider": "ovhcloud", "env_vars": {}, "model": "Qwen3-32B", "tool_method": "FunctionCall"},
+            {"provider": "openai", "env_vars": {}, "model": "gpt-4o", "tool_method": "FunctionCall"},
+            {"provider": "ollama", "env_vars": {}, "model": "llama3", "tool_method": "FunctionCall"}
+        ],
+        "selected_provider": 0,
+        "profiles": {
+            "smart": {"provider": 1},
+            "fast": {"provider": 1, "model": "gpt-4o-mini"},
+            "local": {"provider": 2}
+        }
+    }"#).unwrap();
+    assert_eq!(config.list_profiles(), vec!["fast", "local", "smart"]);
+
+    config.select_profile("fast").unwrap();
+    assert_eq!(config.selected_provider, 1);
+    assert_eq!(config.get_selected_provider().unwrap().model, "gpt-4o-mini");
+
+    let err = config.select_profile("cheap").unwrap_err();
+    assert!(err.contains("available: fast, local, smart"), "{}", err);
+
+    // profiles follow their provider when an earlier one is removed
+    config.remove_provider(1).unwrap();
+    assert_eq!(config.list_profiles(), vec!["local"]);
+    assert_eq!(config.profiles["local"].provider, 1);
+
+    // configs without profiles keep using selected_provider
+    let mut config = ShaiConfig::default();
+    assert!(config.select_profile("fast").unwrap_err().contains("no profiles are configured"));
+    assert_eq!(config.get_selected_provider().unwrap().provider, "ovhcloud");
+}
@@ -1,17 +1,57 @@
 use std::sync::Arc;
 
 use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatCompletionResponseFormat, JsonSchemaBuilder, ChatMessage, ChatMessageContent};
-use shai_llm::{client::LlmClient, provider::LlmError};
+use shai_llm::{client::LlmClient, provider::LlmError, tool::strip_json_fences};
 use serde::{Deserialize, Serialize};
 
 use super::prompt::clifix_prompt;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CliFixResponse {
+/// Most candidates a clifix response keeps, the prompt asks for at most this many
+pub const MAX_CLIFIX_CANDIDATES: usize = 3;
+
+/// One candidate fix of the failed command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliFix {
     pub short_rational: Option<String>,
     pub fixed_cli: String,
 }
 
+/// Candidate fixes of the failed command, the most likely first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliFixResponse {
+    pub fixes: Vec<CliFix>,
+}
+
+impl CliFixResponse {
+    /// Response of the model, the single-fix shape of older prompts is accepted too.
+    /// Candidates with an empty or repeated command are dropped
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let content = strip_json_fences(content);
+        let fixes = match serde_json::from_str::<CliFixResponse>(content) {
+            Ok(response) => response.fixes,
+            Err(e) => match serde_json::from_str::<CliFix>(content) {
+                Ok(fix) => vec![fix],
+                Err(_) => return Err(format!("Failed to parse CLI fix response: {}", e)),
+            },
+        };
+
+        let mut kept: Vec<CliFix> = Vec::new();
+        for fix in fixes {
+            let fixed_cli = fix.fixed_cli.trim();
+            if fixed_cli.is_empty() || kept.iter().any(|kept| kept.fixed_cli == fixed_cli) {
+                continue;
+            }
+            kept.push(CliFix { fixed_cli: fixed_cli.to_string(), ..fix });
+        }
+        kept.truncate(MAX_CLIFIX_CANDIDATES);
+
+        if kept.is_empty() {
+            return Err("CLI fix response has no fix".to_string());
+        }
+        Ok(Self { fixes: kept })
+    }
+}
+
 pub async fn clifix(llm: Arc<LlmClient>, model: String, messages: Vec<ChatMessage>) -> Result<CliFixResponse, LlmError> {
     let mut messages = messages.clone();
     messages.push(ChatMessage::System { 
@@ -28,22 +68,32 @@ pub async fn clifix(llm: Arc<LlmClient>, model: String, messages: Vec<ChatMessag
         .response_format(ChatCompletionResponseFormat::JsonSchema {
             json_schema: JsonSchemaBuilder::default()
                 .name("cli_fix_response")
-                .description("Response format for CLI fix with rationale and fixed command")
+                .description("Response format for CLI fix with ranked candidate commands and their rationale")
                 .schema(serde_json::json!({
                     "type": "object",
                     "properties": {
-                        "short_rational": { "type": "string" },
-                        "fixed_cli": { "type": "string" }
+                        "fixes": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "short_rational": { "type": "string" },
+                                    "fixed_cli": { "type": "string" }
+                                },
+                                "required": ["fixed_cli"],
+                                "additionalProperties": false
+                            }
+                        }
                     },
-                    "required": ["fixed_cli"],
+                    "required": ["fixes"],
                     "additionalProperties": false
                 }))
                 .strict(true)
                 .build()
-                .map_err(|e| -> LlmError { e.into() })?
+                .map_err(|e| LlmError::from(e.to_string()))?
         })
         .build()
-        .map_err(|e| -> LlmError { e.into() })?;
+        .map_err(|e| LlmError::from(e.to_string()))?;
 
         /*
         if let Ok(json) = serde_json::to_string_pretty(&request) {
@@ -57,10 +107,43 @@ pub async fn clifix(llm: Arc<LlmClient>, model: String, messages: Vec<ChatMessag
         .await?;
 
     if let ChatMessage::Assistant { content: Some(ChatMessageContent::Text(content)), .. } = response.choices[0].message.clone() {
-        let parsed: CliFixResponse = serde_json::from_str(&content)
-            .map_err(|e| -> LlmError { format!("Failed to parse CLI fix response: {}", e).into() })?;
-        Ok(parsed)
+        CliFixResponse::parse(&content).map_err(LlmError::Parse)
     } else {
-        Err("No content in response".into())
+        Err(LlmError::Parse("No content in response".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ranked_fixes() {
+        let response = CliFixResponse::parse(r#"{"fixes": [
+            {"short_rational": "no upstream", "fixed_cli": "git push --set-upstream origin main"},
+            {"fixed_cli": " git push "},
+            {"fixed_cli": "git push"},
+            {"fixed_cli": ""}
+        ]}"#).unwrap();
+        assert_eq!(response.fixes, vec![
+            CliFix { short_rational: Some("no upstream".to_string()), fixed_cli: "git push --set-upstream origin main".to_string() },
+            CliFix { short_rational: None, fixed_cli: "git push".to_string() },
+        ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_single_fix_shape() {
+        let response = CliFixResponse::parse("```json\n{\"short_rational\": \"typo\", \"fixed_cli\": \"ls -la\"}\n```").unwrap();
+        assert_eq!(response.fixes.len(), 1);
+        assert_eq!(response.fixes[0].fixed_cli, "ls -la");
+    }
+
+    #[test]
+    fn test_parse_keeps_at_most_the_max_candidates() {
+        let fixes: Vec<_> = (0..5).map(|i| serde_json::json!({"fixed_cli": format!("cmd{}", i)})).collect();
+        let response = CliFixResponse::parse(&serde_json::json!({"fixes": fixes}).to_string()).unwrap();
+        assert_eq!(response.fixes.len(), MAX_CLIFIX_CANDIDATES);
+        assert_eq!(response.fixes[0].fixed_cli, "cmd0");
+        assert!(CliFixResponse::parse(r#"{"fixes": []}"#).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use super::fix::MAX_CLIFIX_CANDIDATES;
 use crate::runners::coder::env::{get_os_version, get_platform, get_today, get_working_dir, is_git_repo, env_all_key};
 
 
@@ -19,20 +20,25 @@ The user executed a command that failed. Your mission:
 - **Path problems**: Correct file/directory references
 
 ## Response Requirements
-Return valid JSON with exactly these fields:
+Return valid JSON with a ranked list of candidate fixes, the most likely first:
 ```json
 {
-  "short_rational": "Brief explanation of what went wrong (optional)",
-  "fixed_cli": "corrected command ready to copy-paste"
+  "fixes": [
+    {
+      "short_rational": "Brief explanation of what went wrong and what this fix does (optional)",
+      "fixed_cli": "corrected command ready to copy-paste"
+    }
+  ]
 }
 ```
 
 **Guidelines:**
 - Keep explanations concise and constructive
-- Ensure `fixed_cli` works in the current environment
+- Ensure every `fixed_cli` works in the current environment
 - No quotes or backticks around the command
-- Focus on the most likely fix, not all possibilities
-- If unsure, provide the safest/most common solution
+- Return a single fix when the intent is clear
+- Add alternatives (at most {max_candidates} fixes in total) only when the intent is ambiguous, e.g. `git push` vs `git push --set-upstream origin <branch>`
+- If unsure, rank the safest/most common solution first
 
 ## Environment Context
 <env>
@@ -63,5 +69,6 @@ pub fn clifix_prompt() -> String {
     .replace("{os_version}", &os)
     .replace("{today}", &today)
     .replace("{env}", &env)
+    .replace("{max_candidates}", &MAX_CLIFIX_CANDIDATES.to_string())
     .to_string()
 }
\ No newline at end of file
@@ -6,12 +6,13 @@ use async_trait::async_trait;
 use tracing::debug;
 
 use crate::agent::brain::ThinkerDecision;
-use crate::agent::{Agent, AgentBuilder, AgentError, Brain, ThinkerContext};
+use crate::agent::{Agent, AgentBuilder, AgentError, Brain, SystemPromptOverride, ThinkerContext};
 use crate::tools::types::{ContainsAnyTool, IntoToolBox};
-use shai_llm::tool::LlmToolCall;
-use crate::tools::{AnyTool, BashTool, EditTool, FetchTool, FindTool, LsTool, MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, WriteTool, TodoStorage, FsOperationLog};
+use shai_llm::tool::{LlmToolCall, LlmToolCallStreaming};
+use shai_llm::{ChatDelta, ToolDescription};
+use crate::tools::{AnyTool, BashTool, EditTool, FetchTool, FindTool, GrepTool, LsTool, MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, WriteTool, TodoStorage, FsOperationLog};
 
-use super::prompt::{render_system_prompt_template, get_todo_read};
+use super::prompt::{render_system_prompt_template, get_todo_read, reasoning_channel_prompt};
 
 #[derive(Clone)]
 pub struct CoderBrain {
@@ -49,8 +50,16 @@ impl Brain for CoderBrain {
     async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
         let mut trace = context.trace.read().await.clone();
 
-        // Render the user's system prompt template
-        let mut system_prompt = render_system_prompt_template(&self.system_prompt_template);
+        // Render the user's system prompt template, or the one of the request
+        let template = match &context.system_prompt {
+            Some(SystemPromptOverride::Replace(template)) => template,
+            _ => &self.system_prompt_template,
+        };
+        let mut system_prompt = render_system_prompt_template(template);
+        if let Some(SystemPromptOverride::Append(instructions)) = &context.system_prompt {
+            system_prompt += "\n\n";
+            system_prompt += instructions;
+        }
         
         // Add todo status if available
         if let Some(tool) = context.available_tools.get_tool("todo_read") {
@@ -58,25 +67,49 @@ impl Brain for CoderBrain {
             system_prompt += &todo_status;
         }
 
+        // reasoning and final answer must reach the clients apart
+        if !self.llm.supports_reasoning_content(&self.model) {
+            system_prompt += &reasoning_channel_prompt();
+        }
+
         trace.insert(0, ChatMessage::System {
             content: ChatMessageContent::Text(system_prompt),
             name: None,
         });
 
-        // get next step with custom temperature
-        let request = ChatCompletionParametersBuilder::default()
-            .model(&self.model)
+        // get next step with custom temperature, unless the caller set its own sampling
+        let sampling = context.sampling;
+        let mut request = ChatCompletionParametersBuilder::default();
+        request.model(&self.model)
             .messages(trace)
-            .temperature(self.temperature)
-            .build()
+            .temperature(sampling.temperature.unwrap_or(self.temperature));
+        if let Some(max_tokens) = sampling.max_tokens {
+            request.max_completion_tokens(max_tokens);
+        }
+        if let Some(top_p) = sampling.top_p {
+            request.top_p(top_p);
+        }
+        if let Some(tool_choice) = context.tool_choice.llm_tool_choice() {
+            request.tool_choice(tool_choice);
+        }
+        let request = request.build()
             .map_err(|e| AgentError::LlmError(e.to_string()))?;
         
-        let brain_decision = self.llm.chat_with_tools(
-                request,
-                &context.available_tools.into_toolbox(),
-                context.method)
-                .await
-                .map_err(|e| AgentError::LlmError(e.to_string()))?;
+        // the step may be held to some of the tools only
+        let tool_choice = context.tool_choice;
+        let mut tools = context.available_tools;
+        tools.retain(|tool| tool_choice.allows(&tool.name()));
+        let toolbox = tools.into_toolbox();
+        let brain_decision = match &context.deltas {
+            Some(deltas) => {
+                self.llm.chat_with_tools_streaming(request, &toolbox, context.method, &mut |delta| match delta {
+                    ChatDelta::Reasoning(text) => deltas.emit(text, true),
+                    ChatDelta::Content(text) => deltas.emit(text, false),
+                }).await
+            }
+            None => self.llm.chat_with_tools(request, &toolbox, context.method).await,
+        }
+        .map_err(|e| AgentError::LlmError(e.to_string()))?;
 
         // Extract token usage information
         let token_usage = brain_decision.usage.as_ref().map(|usage| {
@@ -115,14 +148,18 @@ pub fn coder(llm: Arc<LlmClient>, model: String) -> impl Agent {
     let multiedit = Box::new(MultiEditTool::new(fs_log.clone()));
     let fetch = Box::new(FetchTool::new());
     let find = Box::new(FindTool::new());
+    let grep = Box::new(GrepTool::new());
     let ls = Box::new(LsTool::new());
     let read = Box::new(ReadTool::new(fs_log.clone()));
     let todoread = Box::new(TodoReadTool::new(todo_storage.clone()));
     let todowrite = Box::new(TodoWriteTool::new(todo_storage.clone()));
     let write = Box::new(WriteTool::new(fs_log.clone()));
-    let toolbox: Vec<Box<dyn AnyTool>> = vec![bash, edit, multiedit, fetch, find, ls, read, todoread, todowrite, write];
+    let toolbox: Vec<Box<dyn AnyTool>> = vec![bash, edit, multiedit, fetch, find, grep, ls, read, todoread, todowrite, write];
 
     AgentBuilder::with_brain(Box::new(CoderBrain::new(llm.clone(), model)))
+    .user_settings()
     .tools(toolbox)
+    .todos(todo_storage)
+    .persistent_permissions()
     .build()
 }
\ No newline at end of file
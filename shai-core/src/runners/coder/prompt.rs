@@ -187,6 +187,18 @@ pub fn coder_next_step() -> String {
 }
 
 
+static REASONING_CHANNEL: &str = r#"
+<reasoning>
+Keep your reasoning apart from your answer: write your thinking between <think> and </think>, then write only the final answer to the user after the closing tag. Never repeat the reasoning in the answer.
+</reasoning>
+"#;
+
+/// Asks the model to wrap its reasoning in think tags, for providers without a reasoning channel
+pub fn reasoning_channel_prompt() -> String {
+    REASONING_CHANNEL.to_string()
+}
+
+
 static TODO_STATUS: &str = r#"
 <todo>
 todoStatus: This is the current status of the todo list
@@ -72,7 +72,11 @@ async fn test_coder_brain_think_simple() {
             name: None,
         }])),
         available_tools: vec![],
-        method: ToolCallMethod::FunctionCall
+        method: ToolCallMethod::FunctionCall,
+        tool_choice: Default::default(),
+        sampling: Default::default(),
+        system_prompt: None,
+        deltas: None
     };
     
     let result = brain.next_step(context).await;
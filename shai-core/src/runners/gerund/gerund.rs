@@ -1,15 +1,29 @@
+use std::sync::Arc;
+
 use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessage, ChatMessageContent};
 use shai_llm::{client::LlmClient, provider::LlmError};
 
 use super::prompt::gerund_prompt;
 
+/// Label used when the model did not answer with a usable gerund
+pub const GERUND_FALLBACK: &str = "Thinking";
+
+/// Longest word accepted as a status label
+pub const GERUND_MAX_LEN: usize = 24;
 
+/// Words ending in -ing that are not present participles
+const NOT_GERUNDS: &[&str] = &[
+    "thing", "something", "nothing", "anything", "everything",
+    "string", "during", "bring", "spring", "morning", "evening",
+];
 
-pub async fn gerund(llm: LlmClient, model: String, message: String) -> Result<ChatMessage, LlmError> {
-    let message = if message.is_empty() { "the user has sent an empty message".to_string()} else {message}; 
+/// Ask the model for a cheerful -ing word describing the message, ready to be shown
+/// as a spinner or status label. Falls back to GERUND_FALLBACK when the answer holds no gerund.
+pub async fn gerund(llm: Arc<LlmClient>, model: String, message: String) -> Result<String, LlmError> {
+    let message = if message.is_empty() { "the user has sent an empty message".to_string()} else {message};
     let mut messages = vec![ChatMessage::User { content: ChatMessageContent::Text(message.clone()), name: None }];
-    messages.push(ChatMessage::System { 
-        content: ChatMessageContent::Text(gerund_prompt()), 
+    messages.push(ChatMessage::System {
+        content: ChatMessageContent::Text(gerund_prompt()),
         name: None
     });
 
@@ -18,11 +32,38 @@ pub async fn gerund(llm: LlmClient, model: String, message: String) -> Result<Ch
         .messages(messages)
         .temperature(0.1)
         .build()
-        .map_err(|e| e)?;
-        
+        .map_err(|e| LlmError::from(e.to_string()))?;
+
         // submit it to our big brain coder
         let response = llm.chat(request)
         .await?;
 
-        Ok(response.choices[0].message.clone())
-}
\ No newline at end of file
+        let text = match response.choices.first().map(|c| &c.message) {
+            Some(ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. }) => text.as_str(),
+            _ => "",
+        };
+        Ok(clean_gerund(text))
+}
+
+/// Extract a single capitalized gerund from a model answer: the first -ing word,
+/// stripped of punctuation and markdown, no longer than GERUND_MAX_LEN.
+/// Chatty answers ("Sure! Here is your word: **Polishing**.") are reduced to the word itself.
+pub fn clean_gerund(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| word.chars().all(|c| c.is_alphabetic() || c == '-'))
+        .map(|word| word.to_lowercase())
+        .find(|word| {
+            word.len() > 4
+                && word.len() <= GERUND_MAX_LEN
+                && word.ends_with("ing")
+                && !NOT_GERUNDS.contains(&word.as_str())
+        })
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .unwrap_or_else(|| GERUND_FALLBACK.to_string())
+}
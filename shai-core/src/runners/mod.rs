@@ -2,4 +2,5 @@ pub mod coder;
 pub mod compacter;
 pub mod searcher;
 pub mod gerund;
-pub mod clifixer;
\ No newline at end of file
+pub mod clifixer;
+pub mod planner;
\ No newline at end of file
@@ -0,0 +1,7 @@
+pub mod planner;
+pub mod prompt;
+
+pub use planner::PlannerBrain;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
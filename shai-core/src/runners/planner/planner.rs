@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+use async_trait::async_trait;
+use tracing::debug;
+
+use crate::agent::brain::{ThinkerDecision, ThinkerFlowControl};
+use crate::agent::{AgentError, Brain, StepToolChoice, SystemPromptOverride, ThinkerContext};
+use crate::tools::types::ContainsAnyTool;
+use crate::tools::{TodoItem, TodoStatus, TodoStorage};
+
+use super::prompt::{planner_open_todos, planner_plan_first, planner_track};
+
+/// How many times in a turn the planner sends the model back to its open todos before it lets it stop
+pub const DEFAULT_MAX_REMINDERS: u32 = 3;
+
+/// Wraps a brain so that it plans before acting: the first step of a turn must write the todo
+/// list, and the model is sent back to work while items of the list are open
+pub struct PlannerBrain {
+    inner: Box<dyn Brain>,
+    todos: Arc<TodoStorage>,
+    max_reminders: u32,
+    /// reminders sent in the current turn
+    reminders: u32,
+    /// the last step stopped with open todos, the next one is told which
+    reminding: bool,
+}
+
+impl PlannerBrain {
+    /// `todos` must be the storage of the agent's todo tools
+    pub fn new(inner: Box<dyn Brain>, todos: Arc<TodoStorage>) -> Self {
+        Self { inner, todos, max_reminders: DEFAULT_MAX_REMINDERS, reminders: 0, reminding: false }
+    }
+
+    pub fn max_reminders(mut self, max_reminders: u32) -> Self {
+        self.max_reminders = max_reminders;
+        self
+    }
+
+    async fn open_todos(&self) -> Vec<TodoItem> {
+        self.todos.get_all().await
+            .into_iter()
+            .filter(|todo| !matches!(todo.status, TodoStatus::Completed))
+            .collect()
+    }
+}
+
+/// The planner's instructions go after the caller's, whichever way the caller set them
+fn with_instructions(system_prompt: Option<SystemPromptOverride>, instructions: String) -> Option<SystemPromptOverride> {
+    Some(match system_prompt {
+        Some(SystemPromptOverride::Append(own)) => SystemPromptOverride::Append(format!("{}\n{}", own, instructions)),
+        Some(SystemPromptOverride::Replace(template)) => SystemPromptOverride::Replace(format!("{}\n{}", template, instructions)),
+        None => SystemPromptOverride::Append(instructions),
+    })
+}
+
+#[async_trait]
+impl Brain for PlannerBrain {
+    async fn next_step(&mut self, mut context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        // without the todo tools there is nothing to plan with
+        if !context.available_tools.contains_tool("todo_write") {
+            return self.inner.next_step(context).await;
+        }
+
+        let turn_start = matches!(context.trace.read().await.last(), Some(ChatMessage::User { .. }));
+        if turn_start {
+            self.reminders = 0;
+            self.reminding = false;
+        }
+
+        let open = self.open_todos().await;
+        let instructions = if turn_start && open.is_empty() {
+            // a new task, or the previous plan is done: the step may only write the plan
+            context.tool_choice = StepToolChoice { required: true, allowed_tools: Some(vec!["todo_write".to_string()]) };
+            planner_plan_first()
+        } else if self.reminding && !open.is_empty() {
+            // the model answered as if it was done, the trace tells it otherwise before its next step
+            let reminder = planner_open_todos(&open);
+            context.trace.write().await.push(ChatMessage::User {
+                content: ChatMessageContent::Text(reminder.trim().to_string()),
+                name: None,
+            });
+            reminder
+        } else {
+            planner_track()
+        };
+        self.reminding = false;
+        context.system_prompt = with_instructions(context.system_prompt, instructions);
+
+        let mut decision = self.inner.next_step(context).await?;
+
+        // the model wants to stop: only once its list is done, or it was reminded enough
+        let stops = match &decision.message {
+            ChatMessage::Assistant { tool_calls, .. } => tool_calls.as_ref().map_or(true, |calls| calls.is_empty()),
+            _ => true,
+        };
+        if stops && matches!(decision.flow, ThinkerFlowControl::AgentPause) {
+            let open = self.open_todos().await;
+            if !open.is_empty() && self.reminders < self.max_reminders {
+                self.reminders += 1;
+                self.reminding = true;
+                debug!(target: "brain::planner", open = open.len(), reminder = self.reminders, "stopped with open todos");
+                decision.flow = ThinkerFlowControl::AgentContinue;
+            }
+        }
+        Ok(decision)
+    }
+}
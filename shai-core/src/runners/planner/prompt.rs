@@ -0,0 +1,48 @@
+use crate::tools::{TodoItem, TodoStatus};
+
+static PLANNER_PLAN_FIRST: &str = r#"
+# Planning
+
+Before doing anything else, write the plan of this task with the `todo_write` tool: one item per step you need to take to fully address the request, in the order you will take them. A simple request may need a single item. Do not answer or call any other tool yet, you will carry out the plan in the next steps.
+"#;
+
+static PLANNER_TRACK: &str = r#"
+# Planning
+
+Work through your todo list in order. Mark an item in_progress when you start it and completed as soon as it is done, using `todo_write`. If the plan turns out to be wrong, update the list rather than ignoring it. Do not give your final answer while items are still pending or in progress.
+"#;
+
+static PLANNER_OPEN_TODOS: &str = r#"
+# Unfinished plan
+
+You stopped while these items of your todo list are still open:
+{{OPEN_TODOS}}
+
+Carry on with them. If some are already done, mark them completed with `todo_write`; if some no longer apply, remove them from the list. Only then give your final answer.
+"#;
+
+/// Instructions of the step that writes the plan
+pub fn planner_plan_first() -> String {
+    PLANNER_PLAN_FIRST.to_string()
+}
+
+/// Instructions of the steps that carry out the plan
+pub fn planner_track() -> String {
+    PLANNER_TRACK.to_string()
+}
+
+/// Reminder sent when the model stopped with open todos
+pub fn planner_open_todos(open: &[TodoItem]) -> String {
+    let list = open.iter()
+        .map(|todo| {
+            let status = match todo.status {
+                TodoStatus::Pending => "pending",
+                TodoStatus::InProgress => "in_progress",
+                TodoStatus::Completed => "completed",
+            };
+            format!("- [{}] {}", status, todo.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    PLANNER_OPEN_TODOS.replace("{{OPEN_TODOS}}", &list)
+}
@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+use shai_llm::ToolCallMethod;
+use tokio::sync::RwLock;
+
+use super::planner::PlannerBrain;
+use crate::agent::brain::{ThinkerDecision, ThinkerFlowControl};
+use crate::agent::{AgentError, Brain, StepToolChoice, SystemPromptOverride, ThinkerContext};
+use crate::tools::{AnyTool, TodoStatus, TodoStorage, TodoWriteTool};
+
+/// Answers without tool calls and records what each step was given
+#[derive(Clone, Default)]
+struct RecordingBrain {
+    steps: Arc<Mutex<Vec<(StepToolChoice, Option<SystemPromptOverride>)>>>,
+}
+
+#[async_trait]
+impl Brain for RecordingBrain {
+    async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        self.steps.lock().unwrap().push((context.tool_choice.clone(), context.system_prompt.clone()));
+        Ok(ThinkerDecision::agent_pause(assistant()))
+    }
+}
+
+fn context(last: ChatMessage, todos: &Arc<TodoStorage>, system_prompt: Option<SystemPromptOverride>) -> ThinkerContext {
+    let todo_write: Arc<dyn AnyTool> = Arc::new(TodoWriteTool::new(todos.clone()));
+    ThinkerContext {
+        trace: Arc::new(RwLock::new(vec![last])),
+        available_tools: vec![todo_write],
+        method: ToolCallMethod::FunctionCall,
+        tool_choice: Default::default(),
+        sampling: Default::default(),
+        system_prompt,
+        deltas: None,
+    }
+}
+
+fn user() -> ChatMessage {
+    ChatMessage::User { content: ChatMessageContent::Text("Add a --json flag".to_string()), name: None }
+}
+
+fn assistant() -> ChatMessage {
+    ChatMessage::Assistant {
+        content: Some(ChatMessageContent::Text("done".to_string())),
+        reasoning_content: None,
+        refusal: None,
+        name: None,
+        audio: None,
+        tool_calls: None,
+    }
+}
+
+#[tokio::test]
+async fn test_first_step_of_a_turn_must_write_the_plan() {
+    let todos = Arc::new(TodoStorage::new());
+    let inner = RecordingBrain::default();
+    let mut planner = PlannerBrain::new(Box::new(inner.clone()), todos.clone());
+
+    let user_instructions = Some(SystemPromptOverride::Append("Answer in French.".to_string()));
+    planner.next_step(context(user(), &todos, user_instructions)).await.unwrap();
+
+    let (tool_choice, system_prompt) = inner.steps.lock().unwrap()[0].clone();
+    assert_eq!(tool_choice, StepToolChoice { required: true, allowed_tools: Some(vec!["todo_write".to_string()]) });
+    let Some(SystemPromptOverride::Append(instructions)) = system_prompt else { panic!("expected appended instructions") };
+    assert!(instructions.starts_with("Answer in French."));
+    assert!(instructions.contains("todo_write"));
+}
+
+#[tokio::test]
+async fn test_open_todos_keep_the_agent_going_until_the_reminders_run_out() {
+    let todos = Arc::new(TodoStorage::new());
+    let item = todos.add("write the tests".to_string()).await;
+    let inner = RecordingBrain::default();
+    let mut planner = PlannerBrain::new(Box::new(inner.clone()), todos.clone()).max_reminders(2);
+
+    // a plan is open, so the first step is free and its answer is sent back
+    let decision = planner.next_step(context(user(), &todos, None)).await.unwrap();
+    assert!(matches!(decision.flow, ThinkerFlowControl::AgentContinue));
+    assert!(inner.steps.lock().unwrap()[0].0.is_auto());
+
+    // the next step is told what is left, in its instructions and in the trace
+    let step = context(assistant(), &todos, None);
+    let trace = step.trace.clone();
+    let decision = planner.next_step(step).await.unwrap();
+    assert!(matches!(decision.flow, ThinkerFlowControl::AgentContinue));
+    let Some(SystemPromptOverride::Append(reminder)) = inner.steps.lock().unwrap()[1].1.clone() else { panic!("expected a reminder") };
+    assert!(reminder.contains("- [pending] write the tests"));
+    assert!(matches!(trace.read().await.last(),
+        Some(ChatMessage::User { content: ChatMessageContent::Text(text), .. }) if text.contains("- [pending] write the tests")));
+
+    // out of reminders, the agent may stop
+    let decision = planner.next_step(context(assistant(), &todos, None)).await.unwrap();
+    assert!(matches!(decision.flow, ThinkerFlowControl::AgentPause));
+
+    // a done list lets it stop right away, and a new turn plans again
+    todos.set_status(&item.id, TodoStatus::Completed).await;
+    let mut planner = PlannerBrain::new(Box::new(inner.clone()), todos.clone());
+    let decision = planner.next_step(context(user(), &todos, None)).await.unwrap();
+    assert!(matches!(decision.flow, ThinkerFlowControl::AgentPause));
+    assert!(inner.steps.lock().unwrap()[3].0.required);
+}
+
+#[tokio::test]
+async fn test_brain_without_todo_tools_is_left_alone() {
+    let todos = Arc::new(TodoStorage::new());
+    let inner = RecordingBrain::default();
+    let mut planner = PlannerBrain::new(Box::new(inner.clone()), todos.clone());
+
+    let mut context = context(user(), &todos, None);
+    context.available_tools.clear();
+    planner.next_step(context).await.unwrap();
+
+    let (tool_choice, system_prompt) = inner.steps.lock().unwrap()[0].clone();
+    assert!(tool_choice.is_auto());
+    assert!(system_prompt.is_none());
+}
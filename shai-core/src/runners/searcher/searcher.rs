@@ -6,7 +6,7 @@ use async_trait::async_trait;
 
 use crate::agent::brain::ThinkerDecision;
 use crate::agent::{Agent, AgentBuilder, AgentError, Brain, ThinkerContext};
-use crate::tools::{AnyTool, FetchTool, FindTool, LsTool, ReadTool, TodoReadTool, TodoWriteTool, TodoStorage};
+use crate::tools::{AnyTool, FetchTool, FindTool, GrepTool, LsTool, ReadTool, SemanticSearchTool, TodoReadTool, TodoWriteTool, TodoStorage};
 
 use super::prompt::searcher_next_step;
 
@@ -84,13 +84,21 @@ pub fn searcher(llm: Arc<LlmClient>, model: String) -> impl Agent {
     // Only read-only tools for the searcher
     let fetch = Box::new(FetchTool::new());
     let find = Box::new(FindTool::new());
+    let grep = Box::new(GrepTool::new());
     let ls = Box::new(LsTool::new());
     let read = Box::new(ReadTool::new(Arc::new(crate::tools::FsOperationLog::new())));
     let todoread = Box::new(TodoReadTool::new(todo_storage.clone()));
     let todowrite = Box::new(TodoWriteTool::new(todo_storage.clone()));
-    let toolbox: Vec<Box<dyn AnyTool>> = vec![fetch, find, ls, read, todoread, todowrite];
+    let mut toolbox: Vec<Box<dyn AnyTool>> = vec![fetch, find, grep, ls, read, todoread, todowrite];
+
+    // semantic search is only offered when an embedding model is configured
+    if let Some(semantic) = SemanticSearchTool::from_env(llm.clone()) {
+        toolbox.push(Box::new(semantic));
+    }
     
     AgentBuilder::with_brain(Box::new(SearcherBrain{llm: llm.clone(), model}))
+    .user_settings()
     .tools(toolbox)
+    .todos(todo_storage)
     .build()
 }
\ No newline at end of file
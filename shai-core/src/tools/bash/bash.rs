@@ -1,18 +1,61 @@
-use super::structs::BashToolParams;
+use super::jobs::{BashJob, BashJobs, JobState, ProcessGroupGuard};
+use super::structs::{BashConfig, BashToolParams};
 use crate::tools::{tool, ToolResult};
 use serde_json::json;
 use tokio_util::sync::CancellationToken;
 use std::collections::HashMap;
+use std::path::Path;
 use std::process::Stdio;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::process::Command;
 use tokio::io::{AsyncReadExt, BufReader};
+use tokio::task::JoinHandle;
 
-pub struct BashTool;
+/// Commands are killed after this long unless the call asks for another timeout
+pub const DEFAULT_BASH_TIMEOUT_SECS: u32 = 600;
+
+/// Once the process group is killed, how long to wait for the rest of its output
+const OUTPUT_DRAIN_DELAY: Duration = Duration::from_secs(1);
+
+pub struct BashTool {
+    default_timeout_secs: u32,
+    config: BashConfig,
+    jobs: Arc<BashJobs>,
+}
+
+/// Everything a finished command printed, stdout and stderr kept apart
+struct CommandOutput {
+    stdout: String,
+    stderr: String,
+    exit: CommandExit,
+}
+
+enum CommandExit {
+    Code(i32),
+    /// killed after this many seconds
+    TimedOut(u32),
+}
 
 impl BashTool {
     pub fn new() -> Self {
-        Self
+        Self::with_default_timeout(DEFAULT_BASH_TIMEOUT_SECS)
+    }
+
+    /// Timeout of the calls that don't set timeout_secs
+    pub fn with_default_timeout(default_timeout_secs: u32) -> Self {
+        Self { default_timeout_secs, config: BashConfig::default(), jobs: Arc::new(BashJobs::new()) }
+    }
+
+    /// Shell and environment of the commands, see `ShaiConfig::load_bash`
+    pub fn with_config(config: BashConfig) -> Self {
+        Self { default_timeout_secs: DEFAULT_BASH_TIMEOUT_SECS, config, jobs: Arc::new(BashJobs::new()) }
+    }
+
+    /// Registry of the background jobs, to share with the bash_status tool
+    pub fn with_jobs(mut self, jobs: Arc<BashJobs>) -> Self {
+        self.jobs = jobs;
+        self
     }
 
     async fn kill_process_group(child: &mut tokio::process::Child) {
@@ -38,22 +81,38 @@ impl BashTool {
         let _ = child.wait().await;
     }
 
-    async fn execute_command(&self, params: &BashToolParams, cancel_token: Option<CancellationToken>) -> Result<(String, String, i32), Box<dyn std::error::Error + Send + Sync>> {       
+    /// Output read so far by a reader task, a process that escaped the group may hold the pipe open
+    async fn drain(task: JoinHandle<std::io::Result<String>>) -> String {
+        match tokio::time::timeout(OUTPUT_DRAIN_DELAY, task).await {
+            Ok(Ok(Ok(output))) => output,
+            _ => String::new(),
+        }
+    }
+
+    /// The command of the call, run by the configured shell with piped output, in its own process group
+    fn command(&self, params: &BashToolParams) -> Result<Command, Box<dyn std::error::Error + Send + Sync>> {
         // Validate command is not empty
         if params.command.trim().is_empty() {
             return Err("Command cannot be empty".into());
         }
 
         // Create the command with process group
-        let mut cmd = Command::new("bash");
+        let mut cmd = Command::new(self.config.shell_program());
         cmd.args(["-c", &params.command]);
 
         // Set working directory if specified
-        if let Some(working_dir) = &params.working_dir {
-            cmd.current_dir(working_dir);
+        if let Some(cwd) = &params.cwd {
+            if !Path::new(cwd).is_dir() {
+                return Err(format!("Working directory does not exist: {}", cwd).into());
+            }
+            cmd.current_dir(cwd);
         }
 
-        // Set environment variables
+        // Keep the variables the config lets through, then set the ones of the call
+        if self.config.filters_env() {
+            cmd.env_clear();
+            cmd.envs(std::env::vars_os().filter(|(key, _)| key.to_str().is_some_and(|key| self.config.passes_env(key))));
+        }
         for (key, value) in &params.env {
             cmd.env(key, value);
         }
@@ -63,11 +122,14 @@ impl BashTool {
            .stderr(Stdio::piped())
            .stdin(Stdio::null());
 
-        // Spawn the process
         #[cfg(unix)]
         cmd.process_group(0);
-        
-        let mut child = cmd.spawn()?;
+        Ok(cmd)
+    }
+
+    async fn execute_command(&self, params: &BashToolParams, cancel_token: Option<CancellationToken>) -> Result<CommandOutput, Box<dyn std::error::Error + Send + Sync>> {       
+        // Spawn the process
+        let mut child = self.command(params)?.spawn()?;
         
         // Read output asynchronously (needed to prevent blocking on full buffers)
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
@@ -94,23 +156,17 @@ impl BashTool {
                 std::future::pending::<()>().await;
             }
         };
-        let timeout_future = async {
-            if let Some(timeout_secs) = params.timeout {
-                tokio::time::sleep(Duration::from_secs(timeout_secs as u64)).await;
-            } else {
-                std::future::pending::<()>().await;
-            }
-        };
+        let timeout_secs = params.timeout_secs.unwrap_or(self.default_timeout_secs);
 
         // Wait for process completion, cancellation, or timeout
         tokio::select! {
             // Process completed normally
             wait_result = child.wait() => {
                 let exit_status = wait_result?;
-                let stdout_output = stdout_task.await??;
-                let stderr_output = stderr_task.await??;
+                let stdout = stdout_task.await??;
+                let stderr = stderr_task.await??;
                 let exit_code = exit_status.code().unwrap_or(-1);
-                Ok((stdout_output, stderr_output, exit_code))
+                Ok(CommandOutput { stdout, stderr, exit: CommandExit::Code(exit_code) })
             }
             // Cancellation requested
             _ = cancel_future => {
@@ -119,15 +175,69 @@ impl BashTool {
                 Self::kill_process_group(&mut child).await;
                 Err("Command was cancelled by user".into())
             }
-            // Timeout occurred
-            _ = timeout_future => {
-                stdout_task.abort();
-                stderr_task.abort();
+            // Timeout occurred, what the command printed until then is kept
+            _ = tokio::time::sleep(Duration::from_secs(timeout_secs as u64)) => {
                 Self::kill_process_group(&mut child).await;
-                Err(format!("Command timed out after {} seconds", params.timeout.unwrap()).into())
+                let stdout = Self::drain(stdout_task).await;
+                let stderr = Self::drain(stderr_task).await;
+                Ok(CommandOutput { stdout, stderr, exit: CommandExit::TimedOut(timeout_secs) })
             }
         }
     }
+
+    /// Start the command detached, a task collects its output until it exits, is cancelled or times out
+    fn start_job(&self, params: &BashToolParams) -> Result<Arc<BashJob>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut cmd = self.command(params)?;
+        // a job left running when shai exits goes with it, with the whole group of its shell
+        cmd.kill_on_drop(true);
+        let mut child = cmd.spawn()?;
+        let mut group = ProcessGroupGuard::new(child.id());
+
+        let job = self.jobs.register(&params.command);
+        let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+        let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+        let readers = tokio::spawn({
+            let job = job.clone();
+            async move {
+                tokio::join!(job.clone().read_from(stdout), job.read_from(stderr));
+            }
+        });
+
+        let timeout_secs = params.timeout_secs.unwrap_or(self.default_timeout_secs);
+        let cancel = job.cancel_token();
+        tokio::spawn({
+            let job = job.clone();
+            async move {
+                let state = tokio::select! {
+                    status = child.wait() => JobState::Exited(status.ok().and_then(|status| status.code()).unwrap_or(-1)),
+                    _ = cancel.cancelled() => {
+                        Self::kill_process_group(&mut child).await;
+                        JobState::Cancelled
+                    }
+                    _ = tokio::time::sleep(Duration::from_secs(timeout_secs as u64)) => {
+                        Self::kill_process_group(&mut child).await;
+                        JobState::TimedOut(timeout_secs)
+                    }
+                };
+                // what is left in the pipes belongs to the job, unless a process that escaped the group holds them open
+                group.disarm();
+                let _ = tokio::time::timeout(OUTPUT_DRAIN_DELAY, readers).await;
+                job.finish(state);
+            }
+        });
+        Ok(job)
+    }
+}
+
+/// stdout followed by stderr, as the model reads them
+fn combine_output(stdout: &str, stderr: &str) -> String {
+    if stderr.is_empty() {
+        stdout.to_string()
+    } else if stdout.is_empty() {
+        stderr.to_string()
+    } else {
+        format!("{}\n--- STDERR ---\n{}", stdout, stderr)
+    }
 }
 
 #[tool(name = "bash", description = r#"
@@ -143,8 +253,10 @@ Development Workflow:
 - Running Tests: After making changes, always run the project's test suite (e.g., npm test, pytest, cargo test) to verify that your changes haven't introduced any regressions.
 
 Usage Guidelines:
-- this tool always runs from the same path. If you need to execute command in another directory, chain the commands with && for instance "cd subcrate && cargo test"
-- For file system navigation and inspection, prefer the built-in ls, read, and find tools. Use bash for executing other programs or scripts.
+- Commands run from the current directory, set `cwd` to run one in another directory (e.g. cwd="subcrate" with "cargo test") instead of chaining "cd subcrate && ...".
+- Commands are killed after `timeout_secs` (10 minutes by default). Set a longer timeout for slow builds or installs, never start servers or watchers that don't exit on their own.
+- For commands that take minutes (full builds, long test suites), set `background` to true: the call returns a job id right away, keep working and use `bash_status` to check on the job or wait for its output.
+- For file system navigation and inspection, prefer the built-in ls, read, find and grep tools. Use bash for executing other programs or scripts.
 - Always provide a clear, concise description of the command's purpose for the user.
 - Chain commands using && to ensure that subsequent commands only run if the previous ones succeed.
 - Enclose file paths and arguments in double quotes (") to handle spaces and special characters correctly.
@@ -160,85 +272,89 @@ Examples:
 impl BashTool {
     async fn execute(&self, params: BashToolParams, cancel_token: Option<CancellationToken>) -> ToolResult {
         let start_time = Instant::now();
-        
-        match self.execute_command(&params, cancel_token).await {
-            Ok((stdout, stderr, exit_code)) => {
-                let execution_time = start_time.elapsed();
-                let mut metadata = HashMap::new();
-                
-                metadata.insert("command".to_string(), json!(params.command));
-                metadata.insert("exit_code".to_string(), json!(exit_code));
-                metadata.insert("execution_time_ms".to_string(), json!(execution_time.as_millis()));
-                if let Some(timeout_val) = params.timeout {
-                    metadata.insert("timeout".to_string(), json!(timeout_val));
-                } else {
-                    metadata.insert("timeout".to_string(), json!("none"));
-                }
-                metadata.insert("success".to_string(), json!(exit_code == 0));
-                
-                if let Some(working_dir) = &params.working_dir {
-                    metadata.insert("working_dir".to_string(), json!(working_dir));
-                }
-                
-                if !params.env.is_empty() {
-                    metadata.insert("env_vars".to_string(), json!(params.env));
-                }
-                
-                // Include stderr info if present
-                let has_stderr = !stderr.is_empty();
-                if has_stderr {
-                    metadata.insert("has_stderr".to_string(), json!(true));
-                    metadata.insert("stderr_length".to_string(), json!(stderr.len()));
-                }
-                
-                // Prepare error message if needed
-                let error_message = if exit_code != 0 && has_stderr {
-                    Some(format!("Command failed with exit code {}: {}", exit_code, stderr))
-                } else if exit_code != 0 {
-                    Some(format!("Command failed with exit code {}", exit_code))
-                } else {
-                    None
-                };
-                
-                // Combine stdout and stderr for output
-                let output = if stderr.is_empty() {
-                    stdout
-                } else if stdout.is_empty() {
-                    stderr
-                } else {
-                    format!("{}\n--- STDERR ---\n{}", stdout, stderr)
-                };
-                
-                if exit_code == 0 {
+        let timeout_secs = params.timeout_secs.unwrap_or(self.default_timeout_secs);
+
+        let mut metadata = HashMap::new();
+        metadata.insert("command".to_string(), json!(params.command));
+        metadata.insert("shell".to_string(), json!(self.config.shell_program()));
+        metadata.insert("timeout_secs".to_string(), json!(timeout_secs));
+        if let Some(cwd) = &params.cwd {
+            metadata.insert("cwd".to_string(), json!(cwd));
+        }
+        if !params.env.is_empty() {
+            metadata.insert("env_vars".to_string(), json!(params.env));
+        }
+
+        if params.background {
+            return match self.start_job(&params) {
+                Ok(job) => {
+                    metadata.insert("job_id".to_string(), json!(job.id));
+                    metadata.insert("background".to_string(), json!(true));
                     ToolResult::Success {
-                        output,
-                        metadata: Some(metadata),
-                    }
-                } else {
-                    ToolResult::Error {
-                        error: error_message.unwrap_or_else(|| format!("Command failed with exit code {}", exit_code)),
+                        output: format!("Started {} in the background, check on it with bash_status (job_id \"{}\")", job.id, job.id),
                         metadata: Some(metadata),
                     }
                 }
-            },
+                Err(e) => {
+                    metadata.insert("success".to_string(), json!(false));
+                    ToolResult::Error { error: e.to_string(), metadata: Some(metadata) }
+                }
+            };
+        }
+
+        let result = self.execute_command(&params, cancel_token).await;
+        metadata.insert("execution_time_ms".to_string(), json!(start_time.elapsed().as_millis()));
+
+        let CommandOutput { stdout, stderr, exit } = match result {
+            Ok(output) => output,
             Err(e) => {
-                let execution_time = start_time.elapsed();
-                let mut metadata = HashMap::new();
-                
-                metadata.insert("command".to_string(), json!(params.command));
-                metadata.insert("execution_time_ms".to_string(), json!(execution_time.as_millis()));
-                if let Some(timeout_val) = params.timeout {
-                    metadata.insert("timeout".to_string(), json!(timeout_val));
+                metadata.insert("success".to_string(), json!(false));
+                return ToolResult::Error {
+                    error: e.to_string(),
+                    metadata: Some(metadata),
+                };
+            }
+        };
+
+        // both streams stay available apart for callers, the model gets them combined
+        let output = combine_output(&stdout, &stderr);
+        metadata.insert("stdout".to_string(), json!(stdout));
+        metadata.insert("stderr".to_string(), json!(stderr));
+
+        match exit {
+            CommandExit::Code(0) => {
+                metadata.insert("exit_code".to_string(), json!(0));
+                metadata.insert("success".to_string(), json!(true));
+                ToolResult::Success {
+                    output,
+                    metadata: Some(metadata),
+                }
+            }
+            CommandExit::Code(exit_code) => {
+                metadata.insert("exit_code".to_string(), json!(exit_code));
+                metadata.insert("success".to_string(), json!(false));
+                let error = if stderr.is_empty() {
+                    format!("Command failed with exit code {}", exit_code)
                 } else {
-                    metadata.insert("timeout".to_string(), json!("none"));
+                    format!("Command failed with exit code {}: {}", exit_code, stderr)
+                };
+                ToolResult::Error {
+                    error,
+                    metadata: Some(metadata),
                 }
+            }
+            CommandExit::TimedOut(secs) => {
+                metadata.insert("timed_out".to_string(), json!(true));
                 metadata.insert("success".to_string(), json!(false));
-                
+                let mut error = format!("Command timed out after {} seconds and was killed", secs);
+                if !output.is_empty() {
+                    error.push_str(&format!(", output before the timeout:\n{}", output));
+                }
                 ToolResult::Error {
-                    error: e.to_string(),
+                    error,
                     metadata: Some(metadata),
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
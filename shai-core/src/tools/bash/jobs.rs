@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+/// Output kept per job, the oldest lines are dropped past it so that a chatty build can't fill the memory
+pub const MAX_JOB_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Where a background job is at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobState {
+    Running,
+    Exited(i32),
+    /// killed after this many seconds
+    TimedOut(u32),
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_running(&self) -> bool {
+        matches!(self, JobState::Running)
+    }
+}
+
+/// What the job printed, stdout and stderr in the order they came
+#[derive(Debug, Default)]
+struct JobOutput {
+    text: String,
+    /// end of the part already reported by `take_new_output`
+    reported: usize,
+    /// bytes dropped from the start to stay under MAX_JOB_OUTPUT_BYTES
+    dropped: usize,
+}
+
+/// A command started by the bash tool with `background: true`
+#[derive(Debug)]
+pub struct BashJob {
+    pub id: String,
+    pub command: String,
+    started: Instant,
+    output: Mutex<JobOutput>,
+    state: watch::Sender<JobState>,
+    cancel: CancellationToken,
+}
+
+impl BashJob {
+    pub fn state(&self) -> JobState {
+        *self.state.borrow()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Kill the job and its children, the job task reports it as cancelled
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Wait until the job is over or `timeout` ran out, whichever comes first
+    pub async fn wait(&self, timeout: Duration) -> JobState {
+        let mut state = self.state.subscribe();
+        let _ = tokio::time::timeout(timeout, state.wait_for(|state| !state.is_running())).await;
+        self.state()
+    }
+
+    /// Output printed since the previous call, with a note when lines were dropped in between
+    pub fn take_new_output(&self) -> String {
+        let mut output = self.output.lock().unwrap();
+        let start = output.reported.saturating_sub(output.dropped);
+        let mut new = output.text[start..].to_string();
+        if output.reported < output.dropped {
+            new.insert_str(0, &format!("[... {} bytes of older output dropped]\n", output.dropped - output.reported));
+        }
+        output.reported = output.dropped + output.text.len();
+        new
+    }
+
+    fn push(&self, line: &str) {
+        let mut output = self.output.lock().unwrap();
+        output.text.push_str(line);
+        if output.text.len() > MAX_JOB_OUTPUT_BYTES {
+            let mut cut = output.text.len() - MAX_JOB_OUTPUT_BYTES;
+            while !output.text.is_char_boundary(cut) {
+                cut += 1;
+            }
+            output.text.drain(..cut);
+            output.dropped += cut;
+        }
+    }
+
+    /// Append what `pipe` prints to the output, line by line until it is closed
+    pub(super) async fn read_from(self: Arc<Self>, pipe: impl AsyncRead + Unpin) {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut line).await, Ok(read) if read > 0) {
+            self.push(&String::from_utf8_lossy(&line));
+            line.clear();
+        }
+    }
+
+    pub(super) fn finish(&self, state: JobState) {
+        self.state.send_replace(state);
+    }
+
+    pub(super) fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+}
+
+/// Kills the process group of a job when dropped, so that a job whose task goes away
+/// (the runtime shuts down with shai) doesn't leave the children of its shell running
+pub(super) struct ProcessGroupGuard {
+    pid: Option<u32>,
+}
+
+impl ProcessGroupGuard {
+    pub(super) fn new(pid: Option<u32>) -> Self {
+        Self { pid }
+    }
+
+    /// The job is over and its group was reaped or killed, nothing to do on drop
+    pub(super) fn disarm(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.pid {
+            unsafe {
+                libc::kill(-(pid as i32), libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// Background jobs of a session, shared by the bash tool that starts them and the
+/// bash_status tool that checks on them
+#[derive(Debug, Default)]
+pub struct BashJobs {
+    jobs: Mutex<HashMap<String, Arc<BashJob>>>,
+    next_id: AtomicU32,
+}
+
+impl BashJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a job for `command`, its task reports its end with `finish`
+    pub(super) fn register(&self, command: &str) -> Arc<BashJob> {
+        let id = format!("job-{}", self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        let (state, _) = watch::channel(JobState::Running);
+        let job = Arc::new(BashJob {
+            id: id.clone(),
+            command: command.to_string(),
+            started: Instant::now(),
+            output: Mutex::new(JobOutput::default()),
+            state,
+            cancel: CancellationToken::new(),
+        });
+        self.jobs.lock().unwrap().insert(id, job.clone());
+        job
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<BashJob>> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// All the jobs, oldest first
+    pub fn all(&self) -> Vec<Arc<BashJob>> {
+        let mut jobs: Vec<Arc<BashJob>> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|job| job.started);
+        jobs
+    }
+}
+
+impl Drop for BashJobs {
+    /// the session is over, nobody can check on its jobs anymore
+    fn drop(&mut self) {
+        for job in self.jobs.get_mut().unwrap().values() {
+            if job.state().is_running() {
+                job.cancel();
+            }
+        }
+    }
+}
@@ -1,8 +1,12 @@
 pub mod structs;
 pub mod bash;
+pub mod jobs;
+pub mod status;
 
 #[cfg(test)]
 mod tests;
 
-pub use structs::BashToolParams;
-pub use bash::BashTool;
\ No newline at end of file
+pub use structs::{BashConfig, BashToolParams, BashStatusParams, SUPPORTED_SHELLS};
+pub use bash::BashTool;
+pub use jobs::{BashJob, BashJobs, JobState};
+pub use status::BashStatusTool;
\ No newline at end of file
@@ -0,0 +1,123 @@
+use super::jobs::{BashJob, BashJobs, JobState};
+use super::structs::BashStatusParams;
+use crate::tools::{tool, ToolCapability, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Longest wait a single call may ask for, the model checks again after it
+pub const MAX_JOB_WAIT_SECS: u32 = 600;
+
+/// After a cancel, how long to wait for the job to be killed and report
+const CANCEL_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct BashStatusTool {
+    jobs: Arc<BashJobs>,
+}
+
+impl BashStatusTool {
+    /// `jobs` must be the registry of the agent's bash tool
+    pub fn new(jobs: Arc<BashJobs>) -> Self {
+        Self { jobs }
+    }
+
+    fn describe(job: &BashJob) -> String {
+        let elapsed = job.elapsed().as_secs();
+        match job.state() {
+            JobState::Running => format!("{} is running for {}s: {}", job.id, elapsed, job.command),
+            JobState::Exited(code) => format!("{} exited with code {}: {}", job.id, code, job.command),
+            JobState::TimedOut(secs) => format!("{} timed out after {} seconds and was killed: {}", job.id, secs, job.command),
+            JobState::Cancelled => format!("{} was cancelled: {}", job.id, job.command),
+        }
+    }
+
+    fn list(&self) -> ToolResult {
+        let jobs = self.jobs.all();
+        let output = if jobs.is_empty() {
+            "No background job, start one with bash and background set to true".to_string()
+        } else {
+            jobs.iter().map(|job| Self::describe(job)).collect::<Vec<_>>().join("\n")
+        };
+        let mut meta = HashMap::new();
+        meta.insert("jobs".to_string(), json!(jobs.len()));
+        meta.insert("running".to_string(), json!(jobs.iter().filter(|job| job.state().is_running()).count()));
+        ToolResult::Success { output, metadata: Some(meta) }
+    }
+}
+
+#[tool(name = "bash_status", description = r#"Checks on a command started in the background with the `bash` tool (`background: true`): tells whether it is still running or how it ended, followed by what it printed since the last check.
+
+**Usage Guidelines:**
+- Without `job_id`, lists the background jobs of the session and their state.
+- Set `wait_secs` to wait for the job to finish (up to 10 minutes per call) when there is nothing else to do meanwhile; the call returns as soon as the job is over.
+- Set `cancel` to kill a job that is stuck or no longer needed."#, capabilities = [ToolCapability::Read])]
+impl BashStatusTool {
+    // the job keeps running between two checks, asking again is how it is followed
+    fn polls(&self) -> bool {
+        true
+    }
+
+    fn call_capabilities(&self, params: &BashStatusParams) -> &'static [ToolCapability] {
+        // killing a job changes things, checking on it does not
+        if params.cancel {
+            &[ToolCapability::Read, ToolCapability::Write]
+        } else {
+            &[ToolCapability::Read]
+        }
+    }
+
+    async fn execute(&self, params: BashStatusParams, cancel_token: Option<CancellationToken>) -> ToolResult {
+        let Some(job_id) = params.job_id else {
+            return self.list();
+        };
+        let Some(job) = self.jobs.get(&job_id) else {
+            let known: Vec<String> = self.jobs.all().iter().map(|job| job.id.clone()).collect();
+            return ToolResult::error(if known.is_empty() {
+                format!("There is no background job {}, none was started", job_id)
+            } else {
+                format!("There is no background job {}, the jobs are: {}", job_id, known.join(", "))
+            });
+        };
+
+        if params.cancel {
+            job.cancel();
+            job.wait(CANCEL_WAIT).await;
+        } else if params.wait_secs > 0 {
+            // a cancelled tool call stops waiting, the job keeps running
+            let cancelled = async {
+                match cancel_token {
+                    Some(token) => token.cancelled().await,
+                    None => std::future::pending::<()>().await,
+                }
+            };
+            tokio::select! {
+                _ = job.wait(Duration::from_secs(params.wait_secs.min(MAX_JOB_WAIT_SECS) as u64)) => {}
+                _ = cancelled => {}
+            }
+        }
+
+        let state = job.state();
+        let new_output = job.take_new_output();
+        let output = if new_output.is_empty() {
+            format!("{}\n(no new output)", Self::describe(&job))
+        } else {
+            format!("{}\n{}", Self::describe(&job), new_output)
+        };
+
+        let mut meta = HashMap::new();
+        meta.insert("job_id".to_string(), json!(job.id));
+        meta.insert("running".to_string(), json!(state.is_running()));
+        meta.insert("elapsed_secs".to_string(), json!(job.elapsed().as_secs()));
+        if let JobState::Exited(code) = state {
+            meta.insert("exit_code".to_string(), json!(code));
+        }
+        match state {
+            JobState::Exited(code) if code != 0 => ToolResult::Error { error: output, metadata: Some(meta) },
+            JobState::TimedOut(_) => ToolResult::Error { error: output, metadata: Some(meta) },
+            _ => ToolResult::Success { output, metadata: Some(meta) },
+        }
+    }
+}
@@ -1,16 +1,106 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 use std::collections::HashMap;
+use std::path::Path;
+
+/// Shells picked up from $SHELL when none is configured, the commands the model writes are POSIX-ish
+pub const SUPPORTED_SHELLS: [&str; 3] = ["bash", "zsh", "sh"];
 
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct BashToolParams {
     /// The bash command to execute
     pub command: String,
-    /// Timeout in seconds (optional, None = no timeout)
-    pub timeout: Option<u32>,
-    /// Working directory for command execution (optional)
-    pub working_dir: Option<String>,
+    /// Timeout in seconds, the command and its children are killed past it (optional, defaults to 10 minutes)
+    #[serde(default, alias = "timeout")]
+    pub timeout_secs: Option<u32>,
+    /// Working directory for command execution (optional, defaults to the current directory)
+    #[serde(default, alias = "working_dir")]
+    pub cwd: Option<String>,
     /// Environment variables to set (optional)
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Start the command in the background and return its job id right away, check on it with bash_status (optional, defaults to false)
+    #[serde(default)]
+    pub background: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct BashStatusParams {
+    /// Id of the background job to check, all the jobs are listed when unset
+    #[serde(default)]
+    pub job_id: Option<String>,
+    /// Seconds to wait for the job to finish before reporting (optional, defaults to 0: report right away)
+    #[serde(default)]
+    pub wait_secs: u32,
+    /// Kill the job and its children instead of waiting for it (optional, defaults to false)
+    #[serde(default)]
+    pub cancel: bool,
+}
+
+/// Shell the bash tool runs the commands with and the environment variables they see
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BashConfig {
+    /// Shell binary ("bash", "zsh", "sh" or a path), the user's $SHELL when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    /// Variables of shai's environment passed to the commands, all of them when empty.
+    /// A `*` matches any part of the name ("LC_*"), PATH is always passed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_allow: Vec<String>,
+    /// Variables never passed to the commands (e.g. "*_API_KEY"), whatever env_allow says
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_deny: Vec<String>,
+}
+
+impl BashConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The config with the settings of an agent on top: its shell and allow-list replace these
+    /// ones when set, its deny-list is added, an agent can't pass a variable the user denied
+    pub fn overridden_by(&self, agent: &BashConfig) -> BashConfig {
+        let mut env_deny = self.env_deny.clone();
+        env_deny.extend(agent.env_deny.iter().filter(|name| !self.env_deny.contains(name)).cloned());
+        BashConfig {
+            shell: agent.shell.clone().or_else(|| self.shell.clone()),
+            env_allow: if agent.env_allow.is_empty() { self.env_allow.clone() } else { agent.env_allow.clone() },
+            env_deny,
+        }
+    }
+
+    /// Shell to run the commands with: the configured one, else $SHELL when it is a supported shell, else bash
+    pub fn shell_program(&self) -> String {
+        self.shell.clone()
+            .or_else(|| std::env::var("SHELL").ok().filter(|shell| {
+                Path::new(shell).file_name().and_then(|name| name.to_str()).is_some_and(|name| SUPPORTED_SHELLS.contains(&name))
+            }))
+            .unwrap_or_else(|| "bash".to_string())
+    }
+
+    /// Whether the commands see the variable `name` of shai's environment
+    pub fn passes_env(&self, name: &str) -> bool {
+        let allowed = self.env_allow.is_empty() || name == "PATH" || self.env_allow.iter().any(|pattern| env_matches(pattern, name));
+        allowed && !self.env_deny.iter().any(|pattern| env_matches(pattern, name))
+    }
+
+    /// Whether the environment of the commands is filtered at all
+    pub fn filters_env(&self) -> bool {
+        !self.env_allow.is_empty() || !self.env_deny.is_empty()
+    }
+}
+
+/// Variable name against a pattern where `*` matches any run of characters
+fn env_matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else { return false };
+            if rest.is_empty() {
+                return true;
+            }
+            // the rest of the pattern may itself hold stars: try every place the next part can start
+            (0..=name.len()).filter(|&i| name.is_char_boundary(i)).any(|i| env_matches(rest, &name[i..]))
+        }
+    }
 }
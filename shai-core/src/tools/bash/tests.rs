@@ -1,8 +1,11 @@
-use super::structs::BashToolParams;
+use super::structs::{BashConfig, BashStatusParams, BashToolParams};
 use super::bash::BashTool;
-use crate::tools::{Tool, ToolCapability};
+use super::jobs::{BashJobs, JobState};
+use super::status::BashStatusTool;
+use crate::tools::{AnyTool, Tool, ToolCapability, ToolResult};
 use shai_llm::ToolDescription;
 use std::collections::HashMap;
+use std::sync::Arc;
 use serde_json::json;
 
 #[test]
@@ -27,9 +30,10 @@ async fn test_bash_tool_execution() {
     let tool = BashTool::new();
     let params = BashToolParams {
         command: "echo hello".to_string(),
-        timeout: None,
-        working_dir: None,
+        timeout_secs: None,
+        cwd: None,
         env: HashMap::new(),
+        background: false,
     };
     
     let result = Tool::execute(&tool, params, None).await;
@@ -42,4 +46,231 @@ async fn test_bash_tool_execution() {
     } else {
         panic!("Expected success result");
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_bash_tool_separates_stdout_and_stderr() {
+    let tool = BashTool::new();
+    let params = BashToolParams {
+        command: "echo out; echo err >&2".to_string(),
+        timeout_secs: None,
+        cwd: None,
+        env: HashMap::new(),
+        background: false,
+    };
+
+    let result = Tool::execute(&tool, params, None).await;
+    let crate::tools::types::ToolResult::Success { output, metadata } = result else {
+        panic!("Expected success result");
+    };
+    assert_eq!(output, "out\n\n--- STDERR ---\nerr\n");
+    let metadata = metadata.unwrap();
+    assert_eq!(metadata["stdout"], json!("out\n"));
+    assert_eq!(metadata["stderr"], json!("err\n"));
+}
+
+#[tokio::test]
+async fn test_bash_tool_cwd() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool = BashTool::new();
+    let params = BashToolParams {
+        command: "pwd".to_string(),
+        timeout_secs: None,
+        cwd: Some(dir.path().to_string_lossy().to_string()),
+        env: HashMap::new(),
+        background: false,
+    };
+
+    let result = Tool::execute(&tool, params, None).await;
+    let expected = dir.path().canonicalize().unwrap();
+    let crate::tools::types::ToolResult::Success { output, .. } = result else {
+        panic!("Expected success result");
+    };
+    assert_eq!(std::path::Path::new(output.trim()).canonicalize().unwrap(), expected);
+
+    let params = BashToolParams {
+        command: "pwd".to_string(),
+        timeout_secs: None,
+        cwd: Some(dir.path().join("missing").to_string_lossy().to_string()),
+        env: HashMap::new(),
+        background: false,
+    };
+    let result = Tool::execute(&tool, params, None).await;
+    assert!(result.to_string().contains("Working directory does not exist"), "{}", result);
+}
+
+#[tokio::test]
+async fn test_bash_tool_timeout_kills_the_command() {
+    let tool = BashTool::new();
+    let params = BashToolParams {
+        command: "echo started; sleep 30".to_string(),
+        timeout_secs: Some(1),
+        cwd: None,
+        env: HashMap::new(),
+        background: false,
+    };
+
+    let start = std::time::Instant::now();
+    let result = Tool::execute(&tool, params, None).await;
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+
+    let crate::tools::types::ToolResult::Error { error, metadata } = result else {
+        panic!("Expected an error result");
+    };
+    assert!(error.contains("timed out after 1 seconds"), "{}", error);
+    assert!(error.contains("started"), "output before the timeout is kept: {}", error);
+    assert_eq!(metadata.unwrap()["timed_out"], json!(true));
+}
+
+#[test]
+fn test_bash_tool_accepts_the_old_parameter_names() {
+    let params: BashToolParams = serde_json::from_value(json!({"command": "ls", "timeout": 5, "working_dir": "/tmp"})).unwrap();
+    assert_eq!(params.timeout_secs, Some(5));
+    assert_eq!(params.cwd.as_deref(), Some("/tmp"));
+}
+
+#[test]
+fn test_bash_config_env_patterns() {
+    let config = BashConfig {
+        shell: None,
+        env_allow: vec!["LC_*".to_string(), "HOME".to_string()],
+        env_deny: vec!["*_API_KEY".to_string()],
+    };
+    assert!(config.passes_env("LC_ALL"));
+    assert!(config.passes_env("HOME"));
+    assert!(config.passes_env("PATH"), "PATH is always passed");
+    assert!(!config.passes_env("USER"));
+    assert!(!config.passes_env("LC_OPENAI_API_KEY"), "the deny-list wins");
+    assert!(BashConfig::default().passes_env("USER"));
+}
+
+#[test]
+fn test_bash_config_agent_override_keeps_the_user_deny_list() {
+    let user = BashConfig { shell: Some("zsh".to_string()), env_allow: vec![], env_deny: vec!["AWS_*".to_string()] };
+    let agent = BashConfig { shell: Some("sh".to_string()), env_allow: vec!["HOME".to_string()], env_deny: vec!["GITHUB_TOKEN".to_string()] };
+    let merged = user.overridden_by(&agent);
+    assert_eq!(merged.shell.as_deref(), Some("sh"));
+    assert_eq!(merged.env_allow, vec!["HOME".to_string()]);
+    assert_eq!(merged.env_deny, vec!["AWS_*".to_string(), "GITHUB_TOKEN".to_string()]);
+    assert_eq!(user.overridden_by(&BashConfig::default()), user);
+}
+
+#[tokio::test]
+async fn test_bash_tool_hides_denied_variables() {
+    let tool = BashTool::with_config(BashConfig { shell: Some("sh".to_string()), env_allow: vec![], env_deny: vec!["HOME".to_string()] });
+    let params = BashToolParams {
+        command: "echo ${HOME:-unset}".to_string(),
+        timeout_secs: None,
+        cwd: None,
+        env: HashMap::new(),
+        background: false,
+    };
+
+    let result = Tool::execute(&tool, params, None).await;
+    let crate::tools::types::ToolResult::Success { output, metadata } = result else {
+        panic!("Expected success result");
+    };
+    assert_eq!(output, "unset\n");
+    assert_eq!(metadata.unwrap()["shell"], json!("sh"));
+}
+
+fn background(command: &str) -> BashToolParams {
+    BashToolParams { command: command.to_string(), timeout_secs: None, cwd: None, env: HashMap::new(), background: true }
+}
+
+fn status(job_id: &str, wait_secs: u32, cancel: bool) -> BashStatusParams {
+    BashStatusParams { job_id: Some(job_id.to_string()), wait_secs, cancel }
+}
+
+#[tokio::test]
+async fn test_background_job_reports_its_output_and_exit_code() {
+    let jobs = Arc::new(BashJobs::new());
+    let bash = BashTool::new().with_jobs(jobs.clone());
+    let bash_status = BashStatusTool::new(jobs);
+
+    let result = Tool::execute(&bash, background("echo one; sleep 0.3; echo two; exit 3"), None).await;
+    let ToolResult::Success { metadata, .. } = result else {
+        panic!("Expected the job to start");
+    };
+    assert_eq!(metadata.unwrap()["job_id"], json!("job-1"));
+
+    let result = Tool::execute(&bash_status, status("job-1", 10, false), None).await;
+    let ToolResult::Error { error, metadata } = result else {
+        panic!("Expected the exit code to fail the status");
+    };
+    assert!(error.contains("job-1 exited with code 3"), "{}", error);
+    assert!(error.contains("one\ntwo"), "{}", error);
+    assert_eq!(metadata.unwrap()["exit_code"], json!(3));
+
+    // the output was reported once
+    let result = Tool::execute(&bash_status, status("job-1", 0, false), None).await;
+    assert!(result.to_string().contains("(no new output)"), "{}", result);
+
+    let result = Tool::execute(&bash_status, status("job-7", 0, false), None).await;
+    assert!(result.to_string().contains("the jobs are: job-1"), "{}", result);
+}
+
+#[test]
+fn test_bash_status_cancel_needs_write() {
+    let bash_status = BashStatusTool::new(Arc::new(BashJobs::new()));
+    let call = |params: serde_json::Value| AnyTool::call_capabilities(&bash_status, &params).to_vec();
+    assert_eq!(call(json!({"job_id": "job-1"})), vec![ToolCapability::Read]);
+    assert!(call(json!({"job_id": "job-1", "cancel": true})).contains(&ToolCapability::Write));
+}
+
+#[tokio::test]
+async fn test_background_job_can_be_cancelled() {
+    let jobs = Arc::new(BashJobs::new());
+    let bash = BashTool::new().with_jobs(jobs.clone());
+    let bash_status = BashStatusTool::new(jobs);
+
+    Tool::execute(&bash, background("echo started; sleep 30"), None).await;
+    let result = Tool::execute(&bash_status, BashStatusParams { job_id: None, wait_secs: 0, cancel: false }, None).await;
+    assert!(result.to_string().contains("job-1 is running"), "{}", result);
+
+    let start = std::time::Instant::now();
+    let result = Tool::execute(&bash_status, status("job-1", 0, true), None).await;
+    assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    assert!(result.is_success(), "{}", result);
+    assert!(result.to_string().contains("job-1 was cancelled"), "{}", result);
+}
+
+/// whether the process runs, a killed one left as a zombie until it is reaped does not
+#[cfg(target_os = "linux")]
+fn is_running(pid: &str) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .is_ok_and(|stat| !stat.rsplit(')').next().unwrap_or("").trim_start().starts_with('Z'))
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_background_jobs_are_killed_with_their_children_when_dropped() {
+    let jobs = Arc::new(BashJobs::new());
+    let bash = BashTool::new().with_jobs(jobs.clone());
+
+    Tool::execute(&bash, background("sleep 30 & echo $!; wait"), None).await;
+    let job = jobs.get("job-1").unwrap();
+    let mut pid = String::new();
+    for _ in 0..50 {
+        pid.push_str(&job.take_new_output());
+        if pid.ends_with('\n') {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let pid = pid.trim().to_string();
+    assert!(is_running(&pid), "the child of the job should have started: {:?}", pid);
+
+    // the session goes away, the sleep started by the shell of the job goes with it
+    drop(bash);
+    drop(jobs);
+    job.wait(std::time::Duration::from_secs(5)).await;
+    for _ in 0..50 {
+        if !is_running(&pid) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(job.state(), JobState::Cancelled);
+    assert!(!is_running(&pid));
+}
@@ -0,0 +1,206 @@
+use crate::tools::{tool, BashConfig, ToolEmptyParams, ToolResult};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Files that tell the kind of a project, with the command printing the version of its toolchain
+const PROJECT_MARKERS: &[(&str, &str, &[&str])] = &[
+    ("Cargo.toml", "Rust", &["cargo", "--version"]),
+    ("package.json", "Node.js", &["node", "--version"]),
+    ("deno.json", "Deno", &["deno", "--version"]),
+    ("pyproject.toml", "Python", &["python3", "--version"]),
+    ("requirements.txt", "Python", &["python3", "--version"]),
+    ("setup.py", "Python", &["python3", "--version"]),
+    ("go.mod", "Go", &["go", "version"]),
+    ("pom.xml", "Java (Maven)", &["mvn", "--version"]),
+    ("build.gradle", "Java (Gradle)", &["gradle", "--version"]),
+    ("build.gradle.kts", "Kotlin (Gradle)", &["gradle", "--version"]),
+    ("Gemfile", "Ruby", &["ruby", "--version"]),
+    ("composer.json", "PHP", &["php", "--version"]),
+    ("mix.exs", "Elixir", &["elixir", "--version"]),
+    ("CMakeLists.txt", "C/C++ (CMake)", &["cmake", "--version"]),
+];
+
+/// A probe (git or toolchain) that doesn't answer by then is reported as missing
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Keep the toolchain managers from downloading the toolchain a project pins (rust-toolchain.toml,
+/// the go directive of go.mod) when the version command runs through their shim
+const NO_TOOLCHAIN_DOWNLOAD: &[(&str, &str)] = &[
+    ("RUSTUP_AUTO_INSTALL", "0"),
+    ("GOTOOLCHAIN", "local"),
+];
+
+/// Where the agent runs, as reported by the env tool
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EnvSummary {
+    pub os: String,
+    pub arch: String,
+    /// shell the bash tool runs the commands with
+    pub shell: String,
+    pub cwd: String,
+    pub git: Option<GitSummary>,
+    pub projects: Vec<ProjectSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GitSummary {
+    pub root: String,
+    /// None on a detached HEAD
+    pub branch: Option<String>,
+    /// uncommitted changes or untracked files
+    pub dirty: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectSummary {
+    pub kind: String,
+    pub marker: String,
+    /// first line of the toolchain's version, None when it is not installed
+    pub toolchain: Option<String>,
+}
+
+impl EnvSummary {
+    /// Look around `cwd`: its git repository, and the projects of `cwd` and of the repository root.
+    /// `shell` is the one the bash tool uses
+    pub async fn collect(cwd: &Path, shell: &str) -> Self {
+        let git = git_summary(cwd).await;
+        let mut dirs = vec![cwd.to_path_buf()];
+        if let Some(root) = git.as_ref().map(|git| PathBuf::from(&git.root)).filter(|root| root != cwd) {
+            dirs.push(root);
+        }
+
+        let mut projects: Vec<ProjectSummary> = Vec::new();
+        for dir in dirs {
+            for (marker, kind, version) in PROJECT_MARKERS {
+                if !dir.join(marker).is_file() || projects.iter().any(|project| project.kind == *kind) {
+                    continue;
+                }
+                let marker = if dir == cwd { marker.to_string() } else { dir.join(marker).display().to_string() };
+                projects.push(ProjectSummary { kind: kind.to_string(), marker, toolchain: first_line_of(version, cwd).await });
+            }
+        }
+
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            shell: shell.to_string(),
+            cwd: cwd.display().to_string(),
+            git,
+            projects,
+        }
+    }
+
+    /// One line per topic, what the model reads
+    pub fn describe(&self) -> String {
+        let mut lines = vec![
+            format!("OS: {} ({})", self.os, self.arch),
+            format!("Shell: {}", self.shell),
+            format!("Working directory: {}", self.cwd),
+        ];
+        lines.push(match &self.git {
+            Some(git) => format!(
+                "Git: repository at {}, {}, {}",
+                git.root,
+                git.branch.as_ref().map_or("detached HEAD".to_string(), |branch| format!("branch {}", branch)),
+                if git.dirty { "uncommitted changes" } else { "clean" }
+            ),
+            None => "Git: not a repository".to_string(),
+        });
+        if self.projects.is_empty() {
+            lines.push("Project: no known project file".to_string());
+        }
+        for project in &self.projects {
+            lines.push(format!(
+                "Project: {} ({}), {}",
+                project.kind,
+                project.marker,
+                project.toolchain.as_deref().unwrap_or("toolchain not found")
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Trimmed stdout of a git command run in `cwd`, None when it fails or hangs
+async fn git(args: &[&str], cwd: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(PROBE_TIMEOUT, output).await.ok()?.ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn git_summary(cwd: &Path) -> Option<GitSummary> {
+    let root = git(&["rev-parse", "--show-toplevel"], cwd).await?;
+    let branch = git(&["branch", "--show-current"], cwd).await.filter(|branch| !branch.is_empty());
+    let dirty = git(&["status", "--porcelain"], cwd).await.is_some_and(|status| !status.is_empty());
+    Some(GitSummary { root, branch, dirty })
+}
+
+/// First line a version command prints, some print it on stderr
+async fn first_line_of(command: &[&str], cwd: &Path) -> Option<String> {
+    let output = Command::new(command[0])
+        .args(&command[1..])
+        .current_dir(cwd)
+        .envs(NO_TOOLCHAIN_DOWNLOAD.iter().copied())
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(PROBE_TIMEOUT, output).await.ok()?.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    String::from_utf8_lossy(&text).lines().map(str::trim).find(|line| !line.is_empty()).map(str::to_string)
+}
+
+#[derive(Clone)]
+pub struct EnvTool {
+    shell: String,
+}
+
+impl EnvTool {
+    pub fn new() -> Self {
+        Self::with_shell(BashConfig::default().shell_program())
+    }
+
+    /// Report `shell`, the program the bash tool of the agent runs the commands with
+    pub fn with_shell(shell: String) -> Self {
+        Self { shell }
+    }
+}
+
+impl Default for EnvTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[tool(name = "env", description = r#"Describes the environment the agent works in: OS and architecture, shell, working directory, git repository (root, branch, uncommitted changes) and the kind of project found there (Rust, Node.js, Python...) with the version of its toolchain.
+
+**Usage Guidelines:**
+- Call it at the start of a task instead of running `uname`, `pwd`, `git status` or version commands through bash.
+- It doesn't list the changed files, use `git` or `bash` for that."#, capabilities = [ToolCapability::Read])]
+impl EnvTool {
+    async fn execute(&self, params: ToolEmptyParams) -> ToolResult {
+        let cwd = match std::env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(e) => return ToolResult::error(format!("Cannot read the working directory: {}", e)),
+        };
+        let summary = EnvSummary::collect(&cwd, &self.shell).await;
+        let metadata: HashMap<String, Value> = match serde_json::to_value(&summary) {
+            Ok(Value::Object(fields)) => fields.into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        ToolResult::Success { output: summary.describe(), metadata: Some(metadata) }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod env;
+
+#[cfg(test)]
+mod tests;
+
+pub use env::{EnvSummary, EnvTool, GitSummary, ProjectSummary};
\ No newline at end of file
@@ -0,0 +1,52 @@
+use super::env::{EnvSummary, EnvTool};
+use crate::tools::{Tool, ToolCapability};
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(dir: &TempDir, args: &[&str]) {
+    let status = Command::new("git").args(args).current_dir(dir.path()).status().unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_env_tool_is_read_only() {
+    let tool = EnvTool::new();
+    assert_eq!(&tool.name(), "env");
+    assert_eq!(Tool::capabilities(&tool), &[ToolCapability::Read]);
+}
+
+#[tokio::test]
+async fn test_env_summary_outside_a_repository() {
+    let dir = TempDir::new().unwrap();
+    let summary = EnvSummary::collect(dir.path(), "bash").await;
+
+    assert_eq!(summary.os, std::env::consts::OS);
+    assert_eq!(summary.git, None);
+    assert!(summary.projects.is_empty());
+    assert!(summary.describe().contains("Git: not a repository"));
+}
+
+#[tokio::test]
+async fn test_env_summary_of_a_repository() {
+    let dir = TempDir::new().unwrap();
+    git(&dir, &["init", "--quiet", "--initial-branch=main"]);
+    std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    std::fs::create_dir(dir.path().join("web")).unwrap();
+    std::fs::write(dir.path().join("web/package.json"), "{}").unwrap();
+
+    let summary = EnvSummary::collect(&dir.path().join("web"), "zsh").await;
+    let git = summary.git.as_ref().expect("inside a repository");
+    assert_eq!(git.branch.as_deref(), Some("main"));
+    assert!(git.dirty);
+
+    // the projects of the working directory come first, then the ones of the repository root
+    let kinds: Vec<&str> = summary.projects.iter().map(|project| project.kind.as_str()).collect();
+    assert_eq!(kinds, vec!["Node.js", "Rust"]);
+    assert_eq!(summary.projects[0].marker, "package.json");
+    assert!(summary.projects[1].marker.ends_with("Cargo.toml"));
+
+    let described = summary.describe();
+    assert!(described.contains("Shell: zsh"), "{}", described);
+    assert!(described.contains("branch main, uncommitted changes"), "{}", described);
+    assert!(described.contains("Project: Rust"), "{}", described);
+}
@@ -1,15 +1,44 @@
+use super::html::{html_to_markdown, is_html};
 use super::structs::{FetchToolParams, HttpMethod};
-use crate::tools::{ToolResult, tool};
+use crate::tools::{ToolCapability, ToolResult, tool};
 use serde_json::json;
 use std::collections::HashMap;
 use reqwest;
 use std::time::Duration;
 
-pub struct FetchTool;
+/// Response bodies are cut past this size unless the config says otherwise
+pub const DEFAULT_FETCH_MAX_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Redirects followed before giving up on a URL
+const MAX_REDIRECTS: usize = 5;
+
+pub struct FetchTool {
+    max_bytes: u64,
+}
 
 impl FetchTool {
     pub fn new() -> Self {
-        Self
+        Self::with_max_bytes(DEFAULT_FETCH_MAX_BYTES)
+    }
+
+    /// Ceiling on the response size, calls can only ask for less
+    pub fn with_max_bytes(max_bytes: u64) -> Self {
+        Self { max_bytes }
+    }
+
+    /// Read the body chunk by chunk, stopping once the limit is exceeded
+    /// so a huge download never gets buffered. Returns the body and whether it was truncated.
+    async fn read_body(mut response: reqwest::Response, max_bytes: u64) -> Result<(Vec<u8>, bool), reqwest::Error> {
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            let room = max_bytes.saturating_sub(body.len() as u64) as usize;
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                return Ok((body, true));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((body, false))
     }
 }
 
@@ -23,7 +52,8 @@ impl FetchTool {
 **Usage Notes:**
 - Provide a fully-qualified URL.
 - For API interactions, you can set the `Content-Type` header to `application/json` and provide a JSON string as the `body`.
-- The tool will return the raw response body, which you can then parse or analyze.
+- HTML pages are converted to markdown (scripts, styles and markup removed), set `raw` to true when you need the HTML itself. Other responses are returned as they are.
+- Large responses are cut after `max_bytes` bytes (a note tells when this happened), set a lower `max_bytes` when only the beginning matters.
 
 **Examples:**
 - **Get a web page:** `fetch(url='https://example.com')`
@@ -31,9 +61,18 @@ impl FetchTool {
 - **Post JSON data to an API:** `fetch(url='https://api.example.com/users', method='POST', headers={'Content-Type': 'application/json'}, body='{"name": "John Doe"}')`
 "#, capabilities = [ToolCapability::Network])]
 impl FetchTool {
+    fn call_capabilities(&self, params: &FetchToolParams) -> &'static [ToolCapability] {
+        // anything but a GET may change the remote side
+        match params.method {
+            HttpMethod::Get => &[ToolCapability::Network],
+            HttpMethod::Post | HttpMethod::Put | HttpMethod::Delete => &[ToolCapability::Network, ToolCapability::Write],
+        }
+    }
+
     async fn execute(&self, params: FetchToolParams) -> ToolResult {
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(params.timeout))
+            .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
             .build();
 
         let client = match client {
@@ -62,6 +101,7 @@ impl FetchTool {
         }
 
         // Execute the request
+        let max_bytes = params.max_bytes.map_or(self.max_bytes, |requested| requested.min(self.max_bytes));
         match request_builder.send().await {
             Ok(response) => {
                 let status = response.status();
@@ -71,8 +111,15 @@ impl FetchTool {
                     .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
                     .collect();
 
-                match response.text().await {
-                    Ok(body) => {
+                match Self::read_body(response, max_bytes).await {
+                    Ok((body, truncated)) => {
+                        let raw_length = body.len();
+                        let mut body = String::from_utf8_lossy(&body).into_owned();
+                        let convert = !params.raw && is_html(headers.get("content-type").map(String::as_str), &body);
+                        if convert {
+                            body = html_to_markdown(&body);
+                        }
+
                         let mut meta = HashMap::new();
                         meta.insert("url".to_string(), json!(params.url));
                         meta.insert("method".to_string(), json!(match params.method {
@@ -83,7 +130,12 @@ impl FetchTool {
                         }));
                         meta.insert("status_code".to_string(), json!(status.as_u16()));
                         meta.insert("response_headers".to_string(), json!(headers));
-                        meta.insert("content_length".to_string(), json!(body.len()));
+                        meta.insert("content_length".to_string(), json!(raw_length));
+                        meta.insert("converted_to_markdown".to_string(), json!(convert));
+                        meta.insert("truncated".to_string(), json!(truncated));
+                        if truncated {
+                            body.push_str(&format!("\n\n[response truncated after {} bytes]", max_bytes));
+                        }
 
                         if status.is_success() {
                             ToolResult::Success {
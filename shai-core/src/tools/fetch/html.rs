@@ -0,0 +1,331 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Elements dropped with everything inside them, nothing there is worth reading
+const SKIPPED_ELEMENTS: &[&str] = &["head", "script", "style", "noscript", "template", "svg", "iframe", "canvas"];
+
+/// Elements that start a new paragraph
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p", "div", "section", "article", "main", "header", "footer", "aside", "nav", "blockquote",
+    "table", "ul", "ol", "dl", "dt", "dd", "form", "figure", "figcaption", "details", "summary",
+];
+
+/// Whether a response should be converted, from its content type or its first bytes without one
+pub fn is_html(content_type: Option<&str>, body: &str) -> bool {
+    match content_type {
+        Some(content_type) => content_type.to_ascii_lowercase().contains("html"),
+        None => {
+            let start: String = body.trim_start().chars().take(15).collect::<String>().to_ascii_lowercase();
+            start.starts_with("<!doctype html") || start.starts_with("<html")
+        }
+    }
+}
+
+/// Turn an HTML page into markdown an LLM can read: scripts, styles and the head are dropped,
+/// headings, lists, links, emphasis and code blocks are kept, whitespace is collapsed.
+/// This is a single pass over the tags, not a parser, malformed or truncated pages still convert.
+pub fn html_to_markdown(html: &str) -> String {
+    let mut md = Markdown::default();
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        md.text(&rest[..lt]);
+        rest = &rest[lt..];
+
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        // a tag cut by the size cap ends the page
+        let Some(gt) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        // doctype, processing instructions, CDATA
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name = tag
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if !closing && SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            if !tag.ends_with('/') {
+                rest = skip_element(rest, &name);
+            }
+            continue;
+        }
+        md.tag(&name, tag, closing);
+    }
+    md.text(rest);
+    md.finish()
+}
+
+/// The input after the closing tag of `name`, nothing when it is never closed
+fn skip_element<'a>(html: &'a str, name: &str) -> &'a str {
+    // ascii lowercasing keeps byte offsets
+    let close = format!("</{}", name);
+    html.to_ascii_lowercase()
+        .find(&close)
+        .and_then(|start| html[start..].find('>').map(|gt| &html[start + gt + 1..]))
+        .unwrap_or("")
+}
+
+/// Value of an attribute in the inside of a tag, entities decoded
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let attribute = ATTRIBUTE.get_or_init(|| {
+        Regex::new(r#"([A-Za-z_:][-A-Za-z0-9_:.]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+))"#).unwrap()
+    });
+    attribute.captures_iter(tag)
+        .find(|captures| captures[1].eq_ignore_ascii_case(name))
+        .and_then(|captures| captures.get(2).or(captures.get(3)).or(captures.get(4)))
+        .map(|value| decode_entities(value.as_str()))
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..].find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix('x').or_else(|| number.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        _ => return None,
+    })
+}
+
+/// Markdown being written, with the state the tags around the current text leave
+#[derive(Default)]
+struct Markdown {
+    out: String,
+    /// whitespace seen since the last word, written before the next one
+    pending_space: bool,
+    /// inside <pre>, text is kept as is
+    pre: usize,
+    /// href of each open link, None for links without a usable target
+    links: Vec<Option<String>>,
+    /// next number of each open list, None for bullet lists
+    lists: Vec<Option<u32>>,
+}
+
+impl Markdown {
+    fn text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let text = decode_entities(text);
+        if self.pre > 0 {
+            self.out.push_str(&text);
+            return;
+        }
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.pending_space = true;
+            } else {
+                self.flush_space();
+                self.out.push(c);
+            }
+        }
+    }
+
+    /// The whitespace before a word or an opening marker, never at the start of a line
+    fn flush_space(&mut self) {
+        if self.pending_space && !self.out.is_empty() && !self.out.ends_with(['\n', ' ']) {
+            self.out.push(' ');
+        }
+        self.pending_space = false;
+    }
+
+    fn inline(&mut self, marker: &str, opening: bool) {
+        if opening {
+            self.flush_space();
+        }
+        self.out.push_str(marker);
+    }
+
+    fn line_break(&mut self) {
+        self.trim_trailing_spaces();
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.pending_space = false;
+    }
+
+    fn paragraph(&mut self) {
+        self.line_break();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn trim_trailing_spaces(&mut self) {
+        let trimmed = self.out.trim_end_matches([' ', '\t']).len();
+        self.out.truncate(trimmed);
+    }
+
+    fn tag(&mut self, name: &str, tag: &str, closing: bool) {
+        match (name, closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.paragraph();
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => self.paragraph(),
+            ("br", _) => {
+                self.trim_trailing_spaces();
+                self.out.push('\n');
+                self.pending_space = false;
+            }
+            ("hr", _) => {
+                self.paragraph();
+                self.out.push_str("---");
+                self.paragraph();
+            }
+            ("ul", false) => {
+                self.paragraph();
+                self.lists.push(None);
+            }
+            ("ol", false) => {
+                self.paragraph();
+                self.lists.push(Some(1));
+            }
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                self.paragraph();
+            }
+            ("li", false) => {
+                self.line_break();
+                let depth = self.lists.len().max(1);
+                self.out.push_str(&"  ".repeat(depth - 1));
+                match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        self.out.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            ("li", true) => self.line_break(),
+            ("tr", _) => self.line_break(),
+            ("td" | "th", false) => {
+                if !self.out.ends_with('\n') && !self.out.is_empty() {
+                    self.trim_trailing_spaces();
+                    self.out.push_str(" | ");
+                }
+                self.pending_space = false;
+            }
+            ("pre", false) => {
+                self.paragraph();
+                self.out.push_str("```\n");
+                self.pre += 1;
+            }
+            ("pre", true) => {
+                self.pre = self.pre.saturating_sub(1);
+                if !self.out.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.out.push_str("```");
+                self.paragraph();
+            }
+            ("code", _) if self.pre > 0 => {}
+            ("code", closing) => self.inline("`", !closing),
+            ("strong" | "b", closing) => self.inline("**", !closing),
+            ("em" | "i", closing) => self.inline("*", !closing),
+            ("a", false) => {
+                let href = attribute(tag, "href")
+                    .filter(|href| !href.is_empty() && !href.starts_with('#') && !href.starts_with("javascript:"));
+                if href.is_some() {
+                    self.inline("[", true);
+                }
+                self.links.push(href);
+            }
+            ("a", true) => {
+                if let Some(Some(href)) = self.links.pop() {
+                    self.out.push_str(&format!("]({})", href));
+                }
+            }
+            ("img", false) => {
+                if let Some(alt) = attribute(tag, "alt").filter(|alt| !alt.trim().is_empty()) {
+                    let src = attribute(tag, "src").unwrap_or_default();
+                    self.inline(&format!("![{}]({})", alt.trim(), src), true);
+                }
+            }
+            (name, _) if BLOCK_ELEMENTS.contains(&name) => self.paragraph(),
+            _ => {}
+        }
+    }
+
+    /// Trailing spaces removed and runs of blank lines collapsed to one
+    fn finish(self) -> String {
+        let mut markdown = String::with_capacity(self.out.len());
+        let mut blank_lines = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+        markdown.trim().to_string()
+    }
+}
@@ -1,8 +1,9 @@
 pub mod structs;
 pub mod fetch;
+pub mod html;
 
 #[cfg(test)]
 mod tests;
 
 pub use structs::{FetchToolParams, HttpMethod};
-pub use fetch::FetchTool;
\ No newline at end of file
+pub use fetch::{FetchTool, DEFAULT_FETCH_MAX_BYTES};
\ No newline at end of file
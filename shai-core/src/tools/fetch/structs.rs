@@ -18,6 +18,12 @@ pub struct FetchToolParams {
     /// Request timeout in seconds (optional, defaults to 30)
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+    /// Stop reading the response after this many bytes (optional, cannot exceed the configured limit)
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Return HTML pages as they are instead of converted to markdown (optional, defaults to false)
+    #[serde(default)]
+    pub raw: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
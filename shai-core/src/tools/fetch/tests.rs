@@ -1,13 +1,23 @@
 use super::fetch::FetchTool;
-use crate::tools::{Tool, ToolCapability};
+use super::html::{html_to_markdown, is_html};
+use super::structs::{FetchToolParams, HttpMethod};
+use crate::tools::{AnyTool, Tool, ToolCapability, ToolResult};
 use shai_llm::ToolDescription;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
 
 #[test]
 fn test_fetch_tool_permissions() {
     let tool = FetchTool::new();
-    let perms = tool.capabilities();
+    let perms = Tool::capabilities(&tool);
     assert!(perms.contains(&ToolCapability::Network));
     assert_eq!(perms.len(), 1);
+
+    let call = |method: &str| AnyTool::call_capabilities(&tool, &json!({"url": "https://example.com", "method": method})).to_vec();
+    assert_eq!(call("GET"), vec![ToolCapability::Network]);
+    assert!(call("POST").contains(&ToolCapability::Write));
+    assert!(call("DELETE").contains(&ToolCapability::Write));
 }
 
 #[tokio::test]
@@ -18,4 +28,152 @@ async fn test_fetch_tool_creation() {
 }
 
 // Note: Actual network tests would require internet connectivity
-// In a real environment, you'd test with mock servers or local endpoints
\ No newline at end of file
+// In a real environment, you'd test with mock servers or local endpoints
+
+/// Serve a single response of `size` bytes on a local port, written in small chunks
+async fn serve_large_body(size: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 1024];
+        let _ = socket.read(&mut request).await;
+        let header = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", size);
+        if socket.write_all(header.as_bytes()).await.is_err() {
+            return;
+        }
+        let chunk = vec![b'a'; 16 * 1024];
+        let mut sent = 0;
+        while sent < size {
+            let n = chunk.len().min(size - sent);
+            // the client hangs up once it has read enough
+            if socket.write_all(&chunk[..n]).await.is_err() {
+                return;
+            }
+            sent += n;
+        }
+    });
+    format!("http://{}/large", addr)
+}
+
+fn params(url: String, max_bytes: Option<u64>) -> FetchToolParams {
+    FetchToolParams { url, method: HttpMethod::Get, headers: None, body: None, timeout: 10, max_bytes, raw: false }
+}
+
+#[tokio::test]
+async fn test_fetch_stops_reading_past_max_bytes() {
+    let url = serve_large_body(64 * 1024 * 1024).await;
+    let tool = FetchTool::with_max_bytes(1024 * 1024);
+
+    let result = tool.execute(params(url, Some(1000)), None).await;
+    let ToolResult::Success { output, metadata } = result else {
+        panic!("expected success, got {:?}", result);
+    };
+    assert!(output.starts_with(&"a".repeat(1000)));
+    assert!(output.ends_with("[response truncated after 1000 bytes]"), "{}", &output[1000..]);
+    let metadata = metadata.unwrap();
+    assert_eq!(metadata["truncated"], true);
+    assert_eq!(metadata["content_length"], 1000);
+}
+
+#[tokio::test]
+async fn test_fetch_max_bytes_cannot_exceed_tool_limit() {
+    let url = serve_large_body(64 * 1024).await;
+    let tool = FetchTool::with_max_bytes(4096);
+
+    let result = tool.execute(params(url, Some(1024 * 1024)), None).await;
+    let ToolResult::Success { output, .. } = result else {
+        panic!("expected success, got {:?}", result);
+    };
+    assert!(output.ends_with("[response truncated after 4096 bytes]"));
+}
+
+#[tokio::test]
+async fn test_fetch_small_body_is_not_truncated() {
+    let url = serve_large_body(100).await;
+    let tool = FetchTool::new();
+
+    let result = tool.execute(params(url, None), None).await;
+    let ToolResult::Success { output, metadata } = result else {
+        panic!("expected success, got {:?}", result);
+    };
+    assert_eq!(output, "a".repeat(100));
+    assert_eq!(metadata.unwrap()["truncated"], false);
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Docs</title><style>body { color: red }</style></head>
+<body>
+  <script>var tracking = "<p>not text</p>";</script>
+  <h1>Getting&nbsp;started</h1>
+  <p>Install the <b>cli</b> with <code>cargo install shai</code>, see the
+     <a href="/docs/config">configuration</a> page.</p>
+  <ul><li>fast</li><li>small &amp; safe</li></ul>
+  <pre><code>fn main() {
+    println!("hi");
+}</code></pre>
+  <!-- <p>hidden</p> -->
+</body></html>"#;
+
+#[test]
+fn test_html_to_markdown() {
+    assert_eq!(html_to_markdown(PAGE), r#"# Getting started
+
+Install the **cli** with `cargo install shai`, see the [configuration](/docs/config) page.
+
+- fast
+- small & safe
+
+```
+fn main() {
+    println!("hi");
+}
+```"#);
+}
+
+#[test]
+fn test_html_detection_and_truncated_pages() {
+    assert!(is_html(Some("text/html; charset=utf-8"), ""));
+    assert!(!is_html(Some("application/json"), "<html>"));
+    assert!(is_html(None, "  <!DOCTYPE html><html>"));
+    assert!(!is_html(None, "{\"a\": 1}"));
+
+    // cut by the size cap in the middle of a tag
+    assert_eq!(html_to_markdown("<ol><li>one</li><li>two <a href=\"x"), "1. one\n2. two");
+}
+
+/// Serve a single HTML page on a local port
+async fn serve_html(page: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 1024];
+        let _ = socket.read(&mut request).await;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            page.len(), page);
+        let _ = socket.write_all(response.as_bytes()).await;
+    });
+    format!("http://{}/docs", addr)
+}
+
+#[tokio::test]
+async fn test_fetch_converts_html_unless_raw() {
+    let tool = FetchTool::new();
+
+    let result = tool.execute(params(serve_html(PAGE).await, None), None).await;
+    let ToolResult::Success { output, metadata } = result else {
+        panic!("expected success, got {:?}", result);
+    };
+    assert!(output.starts_with("# Getting started"), "{}", output);
+    assert!(!output.contains("tracking"), "{}", output);
+    assert_eq!(metadata.unwrap()["converted_to_markdown"], true);
+
+    let mut raw = params(serve_html(PAGE).await, None);
+    raw.raw = true;
+    let ToolResult::Success { output, .. } = tool.execute(raw, None).await else {
+        panic!("expected success");
+    };
+    assert_eq!(output, PAGE);
+}
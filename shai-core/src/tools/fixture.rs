@@ -0,0 +1,212 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use shai_llm::ToolDescription;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use super::types::{AnyTool, ToolCapability, ToolResult};
+
+/// A call received by a fixture tool, recorded for later assertions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub tool_name: String,
+    pub parameters: serde_json::Value,
+}
+
+/// A pre-seeded result. When `parameters` is None the fixture matches any call to the tool
+#[derive(Debug, Clone)]
+struct Fixture {
+    tool_name: String,
+    parameters: Option<serde_json::Value>,
+    result: ToolResult,
+}
+
+/// Shared state between a FixtureToolBox and the tools it hands out
+#[derive(Debug, Default)]
+struct FixtureStore {
+    fixtures: Mutex<Vec<Fixture>>,
+    calls: Mutex<Vec<RecordedCall>>,
+}
+
+impl FixtureStore {
+    /// Exact parameter matches win over catch-all fixtures, regardless of declaration order
+    fn resolve(&self, tool_name: &str, parameters: &serde_json::Value) -> ToolResult {
+        self.calls.lock().unwrap().push(RecordedCall {
+            tool_name: tool_name.to_string(),
+            parameters: parameters.clone(),
+        });
+
+        let fixtures = self.fixtures.lock().unwrap();
+        let candidates = fixtures.iter().filter(|f| f.tool_name == tool_name);
+
+        candidates.clone()
+            .find(|f| f.parameters.as_ref() == Some(parameters))
+            .or_else(|| candidates.clone().find(|f| f.parameters.is_none()))
+            .map(|f| f.result.clone())
+            .unwrap_or_else(|| ToolResult::error(format!(
+                "no fixture for tool '{}' with parameters {}", tool_name, parameters
+            )))
+    }
+}
+
+/// A tool whose results come from a FixtureToolBox instead of being executed
+pub struct MockTool {
+    name: String,
+    description: String,
+    parameters_schema: serde_json::Value,
+    capabilities: Vec<ToolCapability>,
+    store: Arc<FixtureStore>,
+}
+
+impl ToolDescription for MockTool {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.parameters_schema.clone()
+    }
+
+    fn group(&self) -> Option<&str> {
+        Some("fixture")
+    }
+}
+
+#[async_trait]
+impl AnyTool for MockTool {
+    fn capabilities(&self) -> &[ToolCapability] {
+        &self.capabilities
+    }
+
+    async fn execute_json(&self, params: serde_json::Value, _cancel_token: Option<CancellationToken>) -> ToolResult {
+        self.store.resolve(&self.name, &params)
+    }
+
+    async fn execute_preview_json(&self, _params: serde_json::Value) -> Option<ToolResult> {
+        None
+    }
+}
+
+/// Test helper that builds deterministic tools backed by a map of
+/// `(tool_name, params) -> ToolResult`, and records every call they receive.
+///
+/// ```ignore
+/// let fixtures = FixtureToolBox::new()
+///     .tool("read", "Read a file")
+///     .fixture("read", json!({"path": "a.txt"}), ToolResult::success("hello".into()));
+///
+/// let agent = AgentBuilder::with_brain(brain).tools(fixtures.build()).build();
+/// // ... run the agent ...
+/// assert_eq!(fixtures.calls_to("read").len(), 1);
+/// ```
+#[derive(Clone, Default)]
+pub struct FixtureToolBox {
+    tools: Vec<(String, String, Vec<ToolCapability>)>,
+    store: Arc<FixtureStore>,
+}
+
+impl FixtureToolBox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a tool with no capabilities (never triggers a permission request)
+    pub fn tool(self, name: &str, description: &str) -> Self {
+        self.tool_with_capabilities(name, description, &[])
+    }
+
+    /// Declare a tool with the given capabilities, useful to exercise the permission flow
+    pub fn tool_with_capabilities(mut self, name: &str, description: &str, capabilities: &[ToolCapability]) -> Self {
+        self.tools.push((name.to_string(), description.to_string(), capabilities.to_vec()));
+        self
+    }
+
+    /// Seed the result returned when `tool_name` is called with exactly `parameters`
+    pub fn fixture(self, tool_name: &str, parameters: serde_json::Value, result: ToolResult) -> Self {
+        self.push_fixture(tool_name, Some(parameters), result)
+    }
+
+    /// Seed the result returned when `tool_name` is called with parameters that have no exact fixture
+    pub fn fallback(self, tool_name: &str, result: ToolResult) -> Self {
+        self.push_fixture(tool_name, None, result)
+    }
+
+    fn push_fixture(self, tool_name: &str, parameters: Option<serde_json::Value>, result: ToolResult) -> Self {
+        self.store.fixtures.lock().unwrap().push(Fixture {
+            tool_name: tool_name.to_string(),
+            parameters,
+            result,
+        });
+        self
+    }
+
+    /// Build the tools, ready to be handed to `AgentBuilder::tools`.
+    /// The box keeps recording calls made to the tools it built.
+    pub fn build(&self) -> Vec<Box<dyn AnyTool>> {
+        self.tools.iter()
+            .map(|(name, description, capabilities)| Box::new(MockTool {
+                name: name.clone(),
+                description: description.clone(),
+                parameters_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {},
+                    "additionalProperties": true
+                }),
+                capabilities: capabilities.clone(),
+                store: self.store.clone(),
+            }) as Box<dyn AnyTool>)
+            .collect()
+    }
+
+    /// All calls received so far, in order
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.store.calls.lock().unwrap().clone()
+    }
+
+    /// Calls received by a specific tool, in order
+    pub fn calls_to(&self, tool_name: &str) -> Vec<RecordedCall> {
+        self.calls()
+            .into_iter()
+            .filter(|c| c.tool_name == tool_name)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_exact_fixture_wins_over_fallback() {
+        let fixtures = FixtureToolBox::new()
+            .tool("read", "Read a file")
+            .fallback("read", ToolResult::error("missing".to_string()))
+            .fixture("read", json!({"path": "a.txt"}), ToolResult::success("hello".to_string()));
+
+        let tools = fixtures.build();
+        let read = &tools[0];
+
+        assert_eq!(read.execute_json(json!({"path": "a.txt"}), None).await, ToolResult::success("hello".to_string()));
+        assert_eq!(read.execute_json(json!({"path": "b.txt"}), None).await, ToolResult::error("missing".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_call_is_an_error_and_is_recorded() {
+        let fixtures = FixtureToolBox::new().tool("ls", "List files");
+        let tools = fixtures.build();
+
+        let result = tools[0].execute_json(json!({"directory": "."}), None).await;
+        assert!(result.is_error());
+
+        assert_eq!(fixtures.calls(), vec![RecordedCall {
+            tool_name: "ls".to_string(),
+            parameters: json!({"directory": "."}),
+        }]);
+        assert!(fixtures.calls_to("read").is_empty());
+    }
+}
@@ -1,5 +1,5 @@
 use super::structs::EditToolParams;
-use super::super::{FsOperationLog, FsOperationType};
+use super::super::{FileBackup, FsOperationLog, FsOperationType};
 use crate::tools::{tool, ToolResult};
 use similar::{ChangeTag, TextDiff};
 use serde_json::json;
@@ -238,11 +238,15 @@ impl EditTool {
             return ToolResult::error(err);
         }
 
+        let backup = FileBackup::capture(&params.path).ok();
         match self.perform_edit(&params, preview) {
             Ok((message, replacement_count)) => {
                 // Log the edit operation only if not preview
                 if !preview {
-                    self.operation_log.log_operation(FsOperationType::Edit, params.path.clone()).await;
+                    match backup {
+                        Some(backup) => self.operation_log.log_change(FsOperationType::Edit, params.path.clone(), backup).await,
+                        None => self.operation_log.log_operation(FsOperationType::Edit, params.path.clone()).await,
+                    }
                 }
                 
                 let mut meta = HashMap::new();
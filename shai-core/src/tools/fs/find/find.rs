@@ -4,10 +4,13 @@ use serde_json::json;
 use std::collections::HashMap;
 use std::path::Path;
 use regex::Regex;
-use walkdir::WalkDir;
+use ignore::WalkBuilder;
 use std::fs;
 use std::io::{BufRead, BufReader};
 
+/// Ceiling on `max_results`, whatever the call asks for
+pub const MAX_FIND_RESULTS: u32 = 1000;
+
 pub struct FindTool;
 
 impl FindTool {
@@ -83,7 +86,7 @@ impl FindTool {
                     match_type: "content".to_string(),
                 });
                 
-                if results.len() >= params.max_results as usize {
+                if results.len() >= params.max_results.min(MAX_FIND_RESULTS) as usize {
                     break;
                 }
             }
@@ -119,10 +122,11 @@ impl FindTool {
 
 **Filtering and Scope:**
 - Narrow your search to specific file types by providing a comma-separated list of extensions to `include_extensions` (e.g., 'rs,js,py').
-- Exclude irrelevant directories and files (like `target` or `.git`) using the `exclude_patterns` parameter to speed up the search.
+- Paths ignored by `.gitignore` (build output, `node_modules`...) and hidden files are skipped. Set `respect_gitignore` to false or `include_hidden` to true to search them too.
+- Exclude other directories and files using the `exclude_patterns` parameter to speed up the search.
 
 **Output:**
-- Returns a list of matching file paths, sorted with the most recently modified files appearing first. This helps prioritize recently changed files."#, capabilities = [ToolCapability::Read])]
+- Returns a list of matches in path order, at most `max_results` of them (1000 at most)."#, capabilities = [ToolCapability::Read])]
 
 impl FindTool {
     async fn execute(&self, params: FindToolParams) -> ToolResult {
@@ -132,7 +136,8 @@ impl FindTool {
         let search_path = params.path.as_ref().unwrap_or(&default_path);
         meta.insert("path".to_string(), json!(search_path));
         meta.insert("case_sensitive".to_string(), json!(params.case_sensitive));
-        meta.insert("max_results".to_string(), json!(params.max_results));
+        let max_results = params.max_results.min(MAX_FIND_RESULTS) as usize;
+        meta.insert("max_results".to_string(), json!(max_results));
         meta.insert("find_type".to_string(), json!(format!("{:?}", params.find_type)));
 
         // Create regex pattern
@@ -160,12 +165,21 @@ impl FindTool {
 
         let mut all_results = Vec::new();
 
-        // Walk through directory
-        for entry in WalkDir::new(search_path)
+        // Walk through directory, in name order so the same search gives the same results
+        let mut walker = WalkBuilder::new(search_path);
+        walker
+            .standard_filters(params.respect_gitignore)
+            .hidden(!params.include_hidden)
+            .require_git(false)
             .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+            .sort_by_file_name(|a, b| a.cmp(b));
+        if params.respect_gitignore {
+            // git never tracks its own directory, even when hidden files are searched
+            walker.filter_entry(|entry| entry.file_name() != ".git");
+        }
+
+        let mut limit_reached = false;
+        for entry in walker.build().filter_map(|e| e.ok()) {
             let path = entry.path();
             
             // Skip directories for content search
@@ -200,15 +214,17 @@ impl FindTool {
                 }
             }
 
-            if all_results.len() >= params.max_results as usize {
+            if all_results.len() >= max_results {
+                limit_reached = true;
                 break;
             }
         }
 
         // Truncate results to max_results
-        all_results.truncate(params.max_results as usize);
+        all_results.truncate(max_results);
 
         meta.insert("results_count".to_string(), json!(all_results.len()));
+        meta.insert("limit_reached".to_string(), json!(limit_reached));
 
         ToolResult::Success {
             output: serde_json::to_string_pretty(&all_results).unwrap_or_default(),
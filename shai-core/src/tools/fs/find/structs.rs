@@ -14,9 +14,15 @@ pub struct FindToolParams {
     /// File patterns to exclude (e.g., "target,node_modules,.git")
     #[serde(default)]
     pub exclude_patterns: Option<String>,
-    /// Maximum number of results to return
+    /// Maximum number of results to return (at most 1000)
     #[serde(default = "default_max_results")]
     pub max_results: u32,
+    /// Skip the paths ignored by .gitignore, .ignore and .git/info/exclude (defaults to true)
+    #[serde(default = "default_respect_gitignore")]
+    pub respect_gitignore: bool,
+    /// Also search hidden files and directories, those starting with a dot
+    #[serde(default)]
+    pub include_hidden: bool,
     /// Whether to use case-sensitive search
     #[serde(default)]
     pub case_sensitive: bool,
@@ -44,6 +50,7 @@ pub enum FindType {
 }
 
 fn default_max_results() -> u32 { 100 }
+fn default_respect_gitignore() -> bool { true }
 fn default_find_type() -> FindType { FindType::Content }
 fn default_show_line_numbers() -> bool { true }
 
@@ -1,5 +1,5 @@
 use super::find::FindTool;
-use super::structs::{FindToolParams, FindType};
+use super::structs::{FindToolParams, FindType, SearchResult};
 use crate::tools::Tool;
 use shai_llm::ToolDescription;
 use tempfile::TempDir;
@@ -56,6 +56,8 @@ fn main() {
         include_extensions: Some("rs".to_string()),
         exclude_patterns: None,
         max_results: 10,
+        respect_gitignore: true,
+        include_hidden: false,
         case_sensitive: false,
         find_type: FindType::Content,
         show_line_numbers: true,
@@ -85,6 +87,8 @@ fn main() {
         include_extensions: Some("rs".to_string()),
         exclude_patterns: None,
         max_results: 10,
+        respect_gitignore: true,
+        include_hidden: false,
         case_sensitive: true,
         find_type: FindType::Content,
         show_line_numbers: true,
@@ -127,6 +131,8 @@ async fn test_find_tool_filename_search() {
         include_extensions: None,
         exclude_patterns: None,
         max_results: 10,
+        respect_gitignore: true,
+        include_hidden: false,
         case_sensitive: false,
         find_type: FindType::Filename,
         show_line_numbers: false,
@@ -183,6 +189,8 @@ async fn test_find_tool_with_filters() {
         include_extensions: Some("rs".to_string()),
         exclude_patterns: Some("target".to_string()),
         max_results: 10,
+        respect_gitignore: true,
+        include_hidden: false,
         case_sensitive: false,
         find_type: FindType::Content,
         show_line_numbers: true,
@@ -235,6 +243,8 @@ fn calculate_guest_score() -> u32 {
         include_extensions: Some("rs".to_string()),
         exclude_patterns: None,
         max_results: 10,
+        respect_gitignore: true,
+        include_hidden: false,
         case_sensitive: false,
         find_type: FindType::Content,
         show_line_numbers: true,
@@ -278,6 +288,8 @@ async fn test_find_tool_invalid_regex() {
         include_extensions: None,
         exclude_patterns: None,
         max_results: 10,
+        respect_gitignore: true,
+        include_hidden: false,
         case_sensitive: false,
         find_type: FindType::Content,
         show_line_numbers: true,
@@ -297,4 +309,59 @@ async fn test_find_tool_invalid_regex() {
             panic!("Find tool was denied");
         }
     }
-}
\ No newline at end of file
+}
+
+fn filename_params(root: &std::path::Path, respect_gitignore: bool, include_hidden: bool) -> FindToolParams {
+    FindToolParams {
+        pattern: r"\.rs$".to_string(),
+        path: Some(root.to_string_lossy().to_string()),
+        include_extensions: None,
+        exclude_patterns: None,
+        max_results: 10,
+        respect_gitignore,
+        include_hidden,
+        case_sensitive: false,
+        find_type: FindType::Filename,
+        show_line_numbers: true,
+        context_lines: None,
+        whole_word: false,
+    }
+}
+
+async fn found_files(root: &std::path::Path, params: FindToolParams) -> Vec<String> {
+    let crate::tools::ToolResult::Success { output, .. } = FindTool::new().execute(params, None).await else {
+        panic!("find should succeed");
+    };
+    let results: Vec<SearchResult> = serde_json::from_str(&output).unwrap();
+    results.iter()
+        .map(|r| std::path::Path::new(&r.file_path).strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_find_tool_skips_ignored_and_hidden_paths() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    for dir in ["src", "target/debug", ".cache", ".git"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    fs::write(root.join(".gitignore"), "target/\n").unwrap();
+    for file in ["src/main.rs", "src/lib.rs", "build.rs", "target/debug/out.rs", ".cache/cached.rs", ".git/hook.rs"] {
+        fs::write(root.join(file), "fn main() {}").unwrap();
+    }
+
+    // ignored and hidden paths are left out, the rest comes in path order
+    let found = found_files(root, filename_params(root, true, false)).await;
+    assert_eq!(found, vec!["build.rs", "src/lib.rs", "src/main.rs"]);
+
+    // hidden files on demand, .git stays out while .gitignore is respected
+    let found = found_files(root, filename_params(root, true, true)).await;
+    assert_eq!(found, vec![".cache/cached.rs", "build.rs", "src/lib.rs", "src/main.rs"]);
+
+    let found = found_files(root, filename_params(root, false, false)).await;
+    assert_eq!(found, vec!["build.rs", "src/lib.rs", "src/main.rs", "target/debug/out.rs"]);
+
+    let mut capped = filename_params(root, false, true);
+    capped.max_results = 2;
+    assert_eq!(found_files(root, capped).await.len(), 2);
+}
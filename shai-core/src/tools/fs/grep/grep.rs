@@ -0,0 +1,254 @@
+use super::structs::GrepToolParams;
+use crate::tools::{tool, ToolResult};
+use regex::{Regex, RegexBuilder};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use walkdir::{DirEntry, WalkDir};
+
+/// Matched lines are cut past this many characters, minified files would flood the context otherwise
+const MAX_LINE_LENGTH: usize = 300;
+
+/// Directories the builtin search skips, ripgrep skips them through .gitignore
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+pub struct GrepTool;
+
+impl GrepTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Search with ripgrep, None when it is not installed
+    async fn search_with_ripgrep(&self, params: &GrepToolParams, path: &str, max_results: usize) -> Option<Result<Vec<String>, String>> {
+        let mut cmd = Command::new("rg");
+        cmd.args(["--line-number", "--with-filename", "--no-heading", "--null", "--color=never"]);
+        if params.case_insensitive {
+            cmd.arg("--ignore-case");
+        }
+        if let Some(glob) = &params.glob {
+            cmd.arg("--glob").arg(glob);
+        }
+        cmd.arg("--regexp").arg(&params.pattern).arg("--").arg(path);
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => return None,
+            Err(e) => return Some(Err(format!("Failed to run ripgrep: {}", e))),
+        };
+
+        // lines are read as bytes, a match in a file that is not UTF-8 must not end the search
+        let mut stdout = BufReader::new(child.stdout.take()?);
+        let mut matches = Vec::new();
+        let mut line = Vec::new();
+        while stdout.read_until(b'\n', &mut line).await.map_or(false, |read| read > 0) {
+            // path, NUL, line number, colon, text
+            let text = String::from_utf8_lossy(&line);
+            if let Some((file, rest)) = text.split_once('\0') {
+                if let Some((line_number, content)) = rest.split_once(':') {
+                    matches.push(format_match(file, line_number, content));
+                }
+            }
+            line.clear();
+            if matches.len() >= max_results {
+                let _ = child.kill().await;
+                return Some(Ok(matches));
+            }
+        }
+
+        // exit code 1 only means nothing matched, 2 is an error (bad pattern, unreadable path)
+        let output = match child.wait_with_output().await {
+            Ok(output) => output,
+            Err(e) => return Some(Err(format!("Failed to run ripgrep: {}", e))),
+        };
+        match output.status.code() {
+            Some(0) | Some(1) => Some(Ok(matches)),
+            _ if !matches.is_empty() => Some(Ok(matches)),
+            _ => Some(Err(format!("ripgrep failed: {}", String::from_utf8_lossy(&output.stderr).trim()))),
+        }
+    }
+
+    /// Search without ripgrep: walk the tree, skipping hidden entries, build directories and binary files
+    fn search_with_walker(&self, params: &GrepToolParams, path: &str, max_results: usize) -> Result<Vec<String>, String> {
+        let pattern = RegexBuilder::new(&params.pattern)
+            .case_insensitive(params.case_insensitive)
+            .build()
+            .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+        let glob = params.glob.as_deref().map(GlobFilter::new).transpose()?;
+
+        let root = Path::new(path);
+        let mut matches = Vec::new();
+        let walker = WalkDir::new(root)
+            .follow_links(false)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_skipped(entry));
+
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            if glob.as_ref().is_some_and(|glob| !glob.includes(root, entry.path())) {
+                continue;
+            }
+            let Ok(bytes) = fs::read(entry.path()) else {
+                continue;
+            };
+            if bytes[..bytes.len().min(8192)].contains(&0) {
+                continue;
+            }
+
+            let file = entry.path().to_string_lossy();
+            for (index, line) in String::from_utf8_lossy(&bytes).lines().enumerate() {
+                if pattern.is_match(line) {
+                    matches.push(format_match(&file, &(index + 1).to_string(), line));
+                    if matches.len() >= max_results {
+                        return Ok(matches);
+                    }
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
+
+/// `file:line: text`, the text trimmed and cut to MAX_LINE_LENGTH
+fn format_match(file: &str, line_number: &str, text: &str) -> String {
+    let text = text.trim_end();
+    match text.char_indices().nth(MAX_LINE_LENGTH) {
+        Some((cut, _)) => format!("{}:{}: {}...", file, line_number, &text[..cut]),
+        None => format!("{}:{}: {}", file, line_number, text),
+    }
+}
+
+fn is_skipped(entry: &DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    name.starts_with('.') || (entry.file_type().is_dir() && SKIPPED_DIRS.contains(&&*name))
+}
+
+/// The `glob` parameter as ripgrep reads it: a glob without `/` matches file names, one with `/`
+/// matches the path relative to the search root, a leading `!` excludes the matches
+struct GlobFilter {
+    regex: Regex,
+    negated: bool,
+    whole_path: bool,
+}
+
+impl GlobFilter {
+    fn new(glob: &str) -> Result<Self, String> {
+        let (negated, glob) = match glob.strip_prefix('!') {
+            Some(glob) => (true, glob),
+            None => (false, glob),
+        };
+        Ok(Self { regex: glob_regex(glob)?, negated, whole_path: glob.contains('/') })
+    }
+
+    fn includes(&self, root: &Path, path: &Path) -> bool {
+        let candidate = if self.whole_path {
+            path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+        } else {
+            path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+        };
+        self.regex.is_match(&candidate) != self.negated
+    }
+}
+
+/// `*` and `?` stay within a path component, `**` crosses them, `{a,b}` is either
+fn glob_regex(glob: &str) -> Result<Regex, String> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    let mut in_braces = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.next_if_eq(&'/').is_some() {
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            '{' => {
+                in_braces = true;
+                pattern.push_str("(?:");
+            }
+            '}' if in_braces => {
+                in_braces = false;
+                pattern.push(')');
+            }
+            ',' if in_braces => pattern.push('|'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|e| format!("Invalid glob '{}': {}", glob, e))
+}
+
+#[tool(name = "grep", description = r#"Searches the contents of files for a regular expression and returns every matching line as `file:line: text`. This is the tool to locate where a function, type, string or error message appears in the codebase.
+
+**Usage:**
+- `pattern` is a regular expression (Rust regex syntax), e.g. `fn\s+parse_config` or `TODO|FIXME`.
+- `path` narrows the search to a directory or a single file, it defaults to the current directory.
+- `glob` keeps only the files matching it, e.g. `*.rs` or `src/**/*.ts`; prefix it with `!` to exclude files instead.
+- Set `case_insensitive` to ignore case.
+
+**Notes:**
+- Uses ripgrep when it is installed (respecting .gitignore), otherwise a builtin search that skips hidden files, `target` and `node_modules`.
+- Binary files are skipped. Results stop at `max_results` (100 by default); narrow `path` or `glob` if the search is cut short.
+- Prefer this tool over running `grep -r` or `rg` through bash. Use `find` to search for files by name."#, capabilities = [ToolCapability::Read])]
+impl GrepTool {
+    async fn execute(&self, params: GrepToolParams) -> ToolResult {
+        let path = params.path.clone().unwrap_or_else(|| ".".to_string());
+        let max_results = params.max_results.max(1) as usize;
+
+        let mut meta = HashMap::new();
+        meta.insert("pattern".to_string(), json!(params.pattern));
+        meta.insert("path".to_string(), json!(path));
+        meta.insert("glob".to_string(), json!(params.glob));
+
+        if !Path::new(&path).exists() {
+            return ToolResult::Error {
+                error: format!("Path not found: {}", path),
+                metadata: Some(meta),
+            };
+        }
+
+        let (engine, result) = match self.search_with_ripgrep(&params, &path, max_results).await {
+            Some(result) => ("ripgrep", result),
+            None => ("builtin", self.search_with_walker(&params, &path, max_results)),
+        };
+        meta.insert("engine".to_string(), json!(engine));
+
+        let matches = match result {
+            Ok(matches) => matches,
+            Err(error) => {
+                return ToolResult::Error {
+                    error,
+                    metadata: Some(meta),
+                };
+            }
+        };
+        meta.insert("results_count".to_string(), json!(matches.len()));
+
+        let output = if matches.is_empty() {
+            format!("No matches found for pattern '{}'", params.pattern)
+        } else if matches.len() >= max_results {
+            format!("{}\n(stopped at {} results, narrow the search with path or glob to see the rest)", matches.join("\n"), max_results)
+        } else {
+            matches.join("\n")
+        };
+
+        ToolResult::Success {
+            output,
+            metadata: Some(meta),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod grep;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::GrepToolParams;
+pub use grep::GrepTool;
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GrepToolParams {
+    /// The regular expression to search for in file contents
+    pub pattern: String,
+    /// File or directory to search in (defaults to current directory)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Only search files matching this glob, e.g. "*.rs" or "src/**/*.ts" (prefix with ! to exclude)
+    #[serde(default)]
+    pub glob: Option<String>,
+    /// Whether to ignore case when matching
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Maximum number of matching lines to return
+    #[serde(default = "default_max_results")]
+    pub max_results: u32,
+}
+
+fn default_max_results() -> u32 { 100 }
@@ -0,0 +1,93 @@
+use super::grep::GrepTool;
+use super::structs::GrepToolParams;
+use crate::tools::{Tool, ToolCapability, ToolResult};
+use shai_llm::ToolDescription;
+use tempfile::TempDir;
+use std::fs;
+
+fn params(pattern: &str, dir: &TempDir) -> GrepToolParams {
+    GrepToolParams {
+        pattern: pattern.to_string(),
+        path: Some(dir.path().to_string_lossy().to_string()),
+        glob: None,
+        case_insensitive: false,
+        max_results: 100,
+    }
+}
+
+fn output(result: ToolResult) -> String {
+    match result {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("grep should succeed, got: {}", other),
+    }
+}
+
+fn project() -> TempDir {
+    let dir = TempDir::new().expect("Failed to create temp directory");
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {\n    parse_config();\n}\n").unwrap();
+    fs::write(dir.path().join("src/config.rs"), "// Config loading\npub fn parse_config() {}\n").unwrap();
+    fs::write(dir.path().join("README.md"), "Call parse_config first\n").unwrap();
+    fs::create_dir(dir.path().join(".hidden")).unwrap();
+    fs::write(dir.path().join(".hidden/notes.rs"), "parse_config\n").unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn test_grep_tool_creation() {
+    let tool = GrepTool::new();
+    assert_eq!(&tool.name(), "grep");
+    assert!(!tool.description().is_empty());
+    assert_eq!(tool.capabilities(), &[ToolCapability::Read]);
+}
+
+#[tokio::test]
+async fn test_grep_matches_as_file_line_text() {
+    let dir = project();
+    let output = output(GrepTool::new().execute(params("parse_config", &dir), None).await);
+
+    let config = dir.path().join("src").join("config.rs");
+    assert!(output.contains(&format!("{}:2: pub fn parse_config() {{}}", config.display())), "{}", output);
+    assert!(output.contains("main.rs:2:     parse_config();"), "{}", output);
+    assert!(output.contains("README.md:1: Call parse_config first"), "{}", output);
+    assert!(!output.contains("notes.rs"), "hidden files are skipped: {}", output);
+}
+
+#[tokio::test]
+async fn test_grep_glob_and_case() {
+    let dir = project();
+
+    let mut rust_only = params("PARSE_CONFIG", &dir);
+    rust_only.glob = Some("*.rs".to_string());
+    rust_only.case_insensitive = true;
+    let output = output(GrepTool::new().execute(rust_only, None).await);
+    assert_eq!(output.lines().count(), 2, "{}", output);
+    assert!(!output.contains("README.md"), "{}", output);
+
+    let mut no_markdown = params("parse_config", &dir);
+    no_markdown.glob = Some("!*.md".to_string());
+    let output = output(GrepTool::new().execute(no_markdown, None).await);
+    assert_eq!(output.lines().count(), 2, "{}", output);
+
+    let output = output(GrepTool::new().execute(params("PARSE_CONFIG", &dir), None).await);
+    assert!(output.starts_with("No matches found"), "{}", output);
+}
+
+#[tokio::test]
+async fn test_grep_max_results_and_errors() {
+    let dir = project();
+
+    let mut capped = params("parse_config", &dir);
+    capped.max_results = 1;
+    let output = output(GrepTool::new().execute(capped, None).await);
+    assert_eq!(output.lines().count(), 2, "{}", output);
+    assert!(output.contains("stopped at 1 results"), "{}", output);
+
+    let result = GrepTool::new().execute(params("fn (", &dir), None).await;
+    assert!(matches!(result, ToolResult::Error { .. }), "an invalid pattern is an error");
+
+    let mut missing = params("main", &dir);
+    missing.path = Some(dir.path().join("nope").to_string_lossy().to_string());
+    let result = GrepTool::new().execute(missing, None).await;
+    assert!(matches!(result, ToolResult::Error { .. }), "a missing path is an error");
+}
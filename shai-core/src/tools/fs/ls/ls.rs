@@ -4,6 +4,10 @@ use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use ignore::WalkBuilder;
+
+/// Depth of a tree when the call gives no max_depth, like `tree -L 3`
+pub const DEFAULT_TREE_DEPTH: u32 = 3;
 
 pub struct LsTool;
 
@@ -133,6 +137,7 @@ impl LsTool {
                             show_hidden: params.show_hidden,
                             long_format: params.long_format,
                             max_depth: params.max_depth,
+                            tree: false,
                             max_files: params.max_files, // Keep original params
                         };
                         
@@ -149,6 +154,66 @@ impl LsTool {
         Ok(files)
     }
 
+    /// The directory as a tree, like `tree -L <max_depth>`: hidden files only with show_hidden,
+    /// the files ignored by git are left out. Returns the tree, the entries shown and whether
+    /// max_files cut it short
+    fn tree(&self, params: &LsToolParams) -> Result<(String, usize, bool), Box<dyn std::error::Error>> {
+        let root = Path::new(&params.directory);
+        if !root.exists() {
+            return Err(format!("Directory '{}' does not exist", params.directory).into());
+        }
+        if !root.is_dir() {
+            return Err(format!("'{}' is not a directory", params.directory).into());
+        }
+
+        let depth = params.max_depth.unwrap_or(DEFAULT_TREE_DEPTH).max(1);
+        let max_files = params.max_files.unwrap_or(200) as usize;
+        let mut walker = WalkBuilder::new(root);
+        walker
+            .hidden(!params.show_hidden)
+            .require_git(false)
+            .follow_links(false)
+            .max_depth(Some(depth as usize))
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(|entry| entry.file_name() != ".git");
+
+        // (depth, name, is_dir) in walk order, a directory comes right before its content
+        let mut entries = Vec::new();
+        let mut truncated = false;
+        for entry in walker.build().filter_map(|e| e.ok()).filter(|e| e.depth() > 0) {
+            if entries.len() >= max_files {
+                truncated = true;
+                break;
+            }
+            let is_dir = entry.file_type().is_some_and(|file_type| file_type.is_dir());
+            entries.push((entry.depth(), entry.file_name().to_string_lossy().to_string(), is_dir));
+        }
+
+        let mut lines = vec![format!("{}/", params.directory.trim_end_matches('/'))];
+        // for each level above the entry, whether that ancestor has siblings left to draw
+        let mut open: Vec<bool> = Vec::new();
+        for (i, (depth, name, is_dir)) in entries.iter().enumerate() {
+            let last = !entries[i + 1..].iter()
+                .take_while(|(d, ..)| d >= depth)
+                .any(|(d, ..)| d == depth);
+            open.truncate(depth - 1);
+            let mut line: String = open.iter().map(|more| if *more { "│   " } else { "    " }).collect();
+            line += if last { "└── " } else { "├── " };
+            line += name;
+            if *is_dir {
+                line += "/";
+            }
+            lines.push(line);
+            open.push(!last);
+        }
+
+        let mut output = lines.join("\n");
+        if truncated {
+            output += &format!("\n\n... (output truncated, showing first {} entries)", entries.len());
+        }
+        Ok((output, entries.len(), truncated))
+    }
+
     fn format_output(&self, files: &[FileInfo], params: &LsToolParams) -> String {
         if files.is_empty() {
             return "No files found".to_string();
@@ -207,6 +272,7 @@ impl LsTool {
 - The `directory` parameter must be an absolute path to the location you wish to inspect.
 - By default, lists files non-recursively to avoid overwhelming output.
 - Set `recursive: true` to include subdirectories (use with caution in large directories).
+- Set `tree: true` to see the structure of a project in one call: a tree down to `max_depth` levels (3 by default), without the files ignored by git.
 - Default limit of 200 files prevents excessive output. Increase `max_files` if you need more, or set to `null` for unlimited.
 
 **Recommendations:**
@@ -214,6 +280,21 @@ impl LsTool {
 - Use `recursive: true` carefully, especially in directories like `node_modules/` which contain thousands of files."#, capabilities = [ToolCapability::Read])]
 impl LsTool {
     async fn execute(&self, params: LsToolParams) -> ToolResult {
+        if params.tree {
+            return match self.tree(&params) {
+                Ok((output, count, truncated)) => {
+                    let mut meta = HashMap::new();
+                    meta.insert("directory".to_string(), json!(params.directory));
+                    meta.insert("file_count".to_string(), json!(count));
+                    meta.insert("tree".to_string(), json!(true));
+                    meta.insert("max_depth".to_string(), json!(params.max_depth.unwrap_or(DEFAULT_TREE_DEPTH)));
+                    meta.insert("truncated".to_string(), json!(truncated));
+                    ToolResult::Success { output, metadata: Some(meta) }
+                }
+                Err(e) => ToolResult::error(format!("Failed to list directory: {}", e)),
+            };
+        }
+
         let mut files_collected = 0;
         match self.list_directory(&params, 0, &mut files_collected) {
             Ok(files) => {
@@ -15,9 +15,12 @@ pub struct LsToolParams {
     /// Show detailed information (size, permissions, etc.)
     #[serde(default)]
     pub long_format: bool,
-    /// Maximum depth for recursive listing (None = unlimited)
+    /// Maximum depth for recursive listing (None = unlimited), or of the tree (defaults to 3)
     #[serde(default)]
     pub max_depth: Option<u32>,
+    /// Render the directory as a tree down to max_depth, skipping the files ignored by git (defaults to false)
+    #[serde(default)]
+    pub tree: bool,
     /// Maximum number of files to return (defaults to 200, set to None for unlimited)
     #[serde(default = "default_max_files")]
     pub max_files: Option<u32>,
@@ -1,9 +1,12 @@
 pub mod edit;
 pub mod find;
+pub mod grep;
 pub mod ls;
 pub mod multiedit;
 pub mod operation_log;
+pub mod patch;
 pub mod read;
+pub mod undo;
 pub mod write;
 
 #[cfg(test)]
@@ -11,8 +14,11 @@ mod tests;
 
 pub use edit::EditTool;
 pub use find::FindTool;
+pub use grep::GrepTool;
 pub use ls::LsTool;
 pub use multiedit::MultiEditTool;
-pub use operation_log::{FsOperationLog, FsOperationType, FsOperation, FsOperationSummary};
-pub use read::ReadTool;
+pub use patch::ApplyPatchTool;
+pub use operation_log::{FsOperationLog, FsOperationType, FsOperation, FsOperationSummary, FileBackup, UndoStep};
+pub use read::{ReadTool, DEFAULT_READ_MAX_BYTES};
+pub use undo::UndoTool;
 pub use write::WriteTool;
\ No newline at end of file
@@ -1,5 +1,5 @@
 use super::structs::MultiEditToolParams;
-use super::super::{FsOperationLog, FsOperationType, EditTool};
+use super::super::{FileBackup, FsOperationLog, FsOperationType, EditTool};
 use crate::tools::{tool, ToolResult};
 use serde_json::json;
 use std::collections::HashMap;
@@ -23,7 +23,8 @@ impl MultiEditTool {
         Self { operation_log, edit_tool }
     }
     
-    async fn perform_multi_edit(&self, params: &MultiEditToolParams, preview: bool) -> Result<(String, Vec<usize>), String> {
+    /// Returns the diff and, per edit, its replacement count or why it was skipped
+    async fn perform_multi_edit(&self, params: &MultiEditToolParams, preview: bool) -> Result<(String, Vec<Result<usize, String>>), String> {
         let path = Path::new(&params.file_path);
 
         // Check if file exists
@@ -34,14 +35,17 @@ impl MultiEditTool {
         // Read initial content
         let mut current_content = fs::read_to_string(path).map_err(|e| e.to_string())?;
         let original_content = current_content.clone();
-        let mut replacements_per_edit = Vec::new();
+        let mut outcomes = Vec::new();
 
         // Apply each edit operation sequentially on content
         for (index, edit) in params.edits.iter().enumerate() {
             match self.edit_tool.perform_edit_on_content(&current_content, &edit.old_string, &edit.new_string, edit.replace_all) {
                 Ok((new_content, replacements)) => {
                     current_content = new_content;
-                    replacements_per_edit.push(replacements);
+                    outcomes.push(Ok(replacements));
+                },
+                Err(error) if params.continue_on_error => {
+                    outcomes.push(Err(error));
                 },
                 Err(error) => {
                     return Err(format!("Edit #{}: {}", index + 1, error));
@@ -49,15 +53,23 @@ impl MultiEditTool {
             }
         }
 
+        // best effort still needs something to apply
+        if outcomes.iter().all(|outcome| outcome.is_err()) {
+            let reasons: Vec<String> = outcomes.iter().enumerate()
+                .filter_map(|(i, outcome)| outcome.as_ref().err().map(|e| format!("Edit #{}: {}", i + 1, e)))
+                .collect();
+            return Err(format!("no edit could be applied\n{}", reasons.join("\n")));
+        }
+
         // Generate comprehensive diff
         let diff = self.edit_tool.myers_diff(&original_content, &current_content);
         
-        // Only write to file if not preview mode
+        // Only write to file if not preview mode, the applied edits land in a single write
         if !preview {
             self.edit_tool.commit_edit(&params.file_path, &current_content)?;
         }
 
-        Ok((diff, replacements_per_edit))
+        Ok((diff, outcomes))
     }
 }
 
@@ -66,6 +78,7 @@ impl MultiEditTool {
 **Execution Logic:**
 - Edits are applied in the exact order they are provided. The second edit operates on the result of the first, the third on the result of the second, and so on.
 - The entire sequence is atomic. If any single edit fails (e.g., its `old_string` is not found), the whole operation is rolled back, and the file remains unmodified.
+- Set `continue_on_error` to true for best-effort bulk changes: edits that fail are skipped and reported, the others are applied in a single write.
 
 **Critical Considerations:**
 - You must first use the `read` tool to understand the file's contents.
@@ -90,18 +103,29 @@ impl MultiEditTool {
             return ToolResult::error(err);
         }
 
+        let backup = FileBackup::capture(&params.file_path).ok();
         match self.perform_multi_edit(&params, preview).await {
-            Ok((message, replacements_per_edit)) => {
+            Ok((diff, outcomes)) => {
                 // Log the multiedit operation only if not preview
                 if !preview {
-                    self.operation_log.log_operation(FsOperationType::MultiEdit, params.file_path.clone()).await;
+                    match backup {
+                        Some(backup) => self.operation_log.log_change(FsOperationType::MultiEdit, params.file_path.clone(), backup).await,
+                        None => self.operation_log.log_operation(FsOperationType::MultiEdit, params.file_path.clone()).await,
+                    }
                 }
                 
+                let replacements_per_edit: Vec<usize> = outcomes.iter().map(|outcome| *outcome.as_ref().unwrap_or(&0)).collect();
+                let applied: Vec<usize> = outcomes.iter().enumerate().filter(|(_, o)| o.is_ok()).map(|(i, _)| i + 1).collect();
+                let skipped: Vec<(usize, &String)> = outcomes.iter().enumerate()
+                    .filter_map(|(i, o)| o.as_ref().err().map(|e| (i + 1, e)))
+                    .collect();
                 let mut meta = HashMap::new();
                 meta.insert("path".to_string(), json!(params.file_path));
                 meta.insert("edit_count".to_string(), json!(params.edits.len()));
                 meta.insert("total_replacements".to_string(), json!(replacements_per_edit.iter().sum::<usize>()));
                 meta.insert("replacements_per_edit".to_string(), json!(replacements_per_edit));
+                meta.insert("applied_edits".to_string(), json!(applied));
+                meta.insert("skipped_edits".to_string(), json!(skipped.iter().map(|(i, reason)| json!({"edit": i, "reason": reason})).collect::<Vec<_>>()));
                 meta.insert("preview_mode".to_string(), json!(preview));
 
                 // Add detailed information about each edit
@@ -111,11 +135,24 @@ impl MultiEditTool {
                         "old_string": edit.old_string,
                         "new_string": edit.new_string,
                         "replace_all": edit.replace_all,
-                        "replacements_made": replacements_per_edit[i]
+                        "replacements_made": replacements_per_edit[i],
+                        "applied": outcomes[i].is_ok()
                     })
                 }).collect();
                 meta.insert("edit_details".to_string(), json!(edit_details));
 
+                // spell out the partial application, the diff alone does not show what was skipped
+                let message = if skipped.is_empty() {
+                    diff
+                } else {
+                    let applied: Vec<String> = applied.iter().map(|i| format!("#{}", i)).collect();
+                    let mut report = format!("{}\n\napplied edits: {}\nskipped edits:", diff, applied.join(", "));
+                    for (i, reason) in &skipped {
+                        report.push_str(&format!("\n  #{}: {}", i, reason));
+                    }
+                    report
+                };
+
                 // Add file size information
                 if let Ok(metadata) = std::fs::metadata(&params.file_path) {
                     meta.insert("file_size_bytes".to_string(), json!(metadata.len()));
@@ -19,4 +19,7 @@ pub struct MultiEditToolParams {
     pub file_path: String,
     /// Array of edit operations to perform sequentially
     pub edits: Vec<EditOperation>,
+    /// Apply the edits that match and skip the others instead of failing the whole batch (default: false)
+    #[serde(default)]
+    pub continue_on_error: bool,
 }
\ No newline at end of file
@@ -49,6 +49,7 @@ async fn test_multiedit_multiple_replacements() {
                 replace_all: true,
             },
         ],
+        continue_on_error: false,
     };
 
     let result = tool.execute(params, None).await;
@@ -82,6 +83,7 @@ async fn test_multiedit_preview_diff_output() {
                 replace_all: false,
             },
         ],
+        continue_on_error: false,
     };
 
     // Test preview - should return Some(ToolResult) with diff
@@ -114,4 +116,81 @@ async fn test_multiedit_preview_diff_output() {
     // Original file should be unchanged after preview
     let original_content = fs::read_to_string(&file_path).unwrap();
     assert_eq!(original_content, "line1\nHello World\nline3\nGoodbye World");
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_multiedit_continue_on_error_applies_matching_edits() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "Hello World").unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
+
+    let tool = MultiEditTool::new(log);
+    let edits = vec![
+        EditOperation {
+            old_string: "Hello".to_string(),
+            new_string: "Hi".to_string(),
+            replace_all: false,
+        },
+        EditOperation {
+            old_string: "Missing".to_string(),
+            new_string: "Found".to_string(),
+            replace_all: false,
+        },
+        EditOperation {
+            old_string: "World".to_string(),
+            new_string: "Earth".to_string(),
+            replace_all: false,
+        },
+    ];
+
+    // transactional by default: nothing is written
+    let result = tool.execute(MultiEditToolParams {
+        file_path: file_path.to_string_lossy().to_string(),
+        edits: edits.clone(),
+        continue_on_error: false,
+    }, None).await;
+    assert!(result.is_error());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "Hello World");
+
+    let result = tool.execute(MultiEditToolParams {
+        file_path: file_path.to_string_lossy().to_string(),
+        edits,
+        continue_on_error: true,
+    }, None).await;
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "Hi Earth");
+
+    let crate::tools::ToolResult::Success { output, metadata } = result else {
+        panic!("expected success, got {:?}", result);
+    };
+    assert!(output.contains("applied edits: #1, #3"), "{}", output);
+    assert!(output.contains("#2:"), "{}", output);
+    let metadata = metadata.unwrap();
+    assert_eq!(metadata["applied_edits"], serde_json::json!([1, 3]));
+    assert_eq!(metadata["skipped_edits"][0]["edit"], 2);
+}
+
+#[tokio::test]
+async fn test_multiedit_continue_on_error_fails_when_nothing_applies() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "Hello World").unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
+
+    let tool = MultiEditTool::new(log);
+    let result = tool.execute(MultiEditToolParams {
+        file_path: file_path.to_string_lossy().to_string(),
+        edits: vec![EditOperation {
+            old_string: "Missing".to_string(),
+            new_string: "Found".to_string(),
+            replace_all: false,
+        }],
+        continue_on_error: true,
+    }, None).await;
+    assert!(result.is_error());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "Hello World");
+}
@@ -1,23 +1,133 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use tracing::warn;
+
+/// Changes kept in the undo journal, older ones can no longer be undone. The journal is appended
+/// to and only trimmed back to this every MAX_UNDO_JOURNAL changes
+pub const MAX_UNDO_JOURNAL: usize = 100;
+
+/// How long a process waits for another one to release the undo journal
+const JOURNAL_LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A lock older than this was left by a process that died while holding it
+const STALE_JOURNAL_LOCK: Duration = Duration::from_secs(30);
 
 /// Represents a file system operation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FsOperation {
     pub operation_type: FsOperationType,
     pub file_path: String,
     pub timestamp: DateTime<Utc>,
+    /// What the file held before the operation, for the operations that can be undone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<FileBackup>,
+    /// Hash of the file right after the operation, to notice that it was changed since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+/// A file as it was before an operation changed it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "content", rename_all = "snake_case")]
+pub enum FileBackup {
+    /// The operation created the file, undoing it deletes the file
+    Absent,
+    Content(String),
+}
+
+impl FileBackup {
+    /// The file as it is now, to be called right before changing it
+    pub fn capture(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(FileBackup::Content(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(FileBackup::Absent),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    fn content(&self) -> Option<&str> {
+        match self {
+            FileBackup::Absent => None,
+            FileBackup::Content(content) => Some(content),
+        }
+    }
+}
+
+/// Stable hash of a file content, the same in every process and build
+fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+
+/// One operation to revert: the file as it is, and as it will be once reverted (None when absent).
+/// `changed_since` is set when the file no longer holds what the operation left in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndoStep {
+    pub operation: FsOperation,
+    pub current: Option<String>,
+    pub restored: Option<String>,
+    pub changed_since: bool,
+}
+
+impl UndoStep {
+    /// Unified diff of the revert
+    pub fn diff(&self) -> String {
+        if self.current == self.restored {
+            return format!("{}: no changes", self.operation.file_path);
+        }
+        TextDiff::from_lines(self.current.as_deref().unwrap_or(""), self.restored.as_deref().unwrap_or(""))
+            .unified_diff()
+            .context_radius(3)
+            .header(&self.operation.file_path, &self.operation.file_path)
+            .to_string()
+    }
+
+    /// One line of what the revert does
+    pub fn summary(&self) -> String {
+        let action = match (&self.current, &self.restored) {
+            (_, None) => "deleted",
+            (None, Some(_)) => "recreated",
+            (Some(_), Some(_)) => "restored",
+        };
+        let changed = if self.changed_since { ", the file was changed since" } else { "" };
+        format!("{} {} (undoing {:?} of {}{})", action, self.operation.file_path, self.operation.operation_type, self.operation.timestamp.format("%H:%M:%S"), changed)
+    }
+
+    fn apply(&self) -> Result<(), String> {
+        let path = Path::new(&self.operation.file_path);
+        match &self.restored {
+            Some(content) => fs::write(path, content),
+            None => match fs::remove_file(path) {
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+                other => other,
+            },
+        }.map_err(|e| format!("{}: {}", self.operation.file_path, e))
+    }
 }
 
 /// Types of file system operations we track
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FsOperationType {
     Read,
+    /// Whole file overwritten (or created) by the write tool
     Write,
+    /// Content added at the end of the file, the previous content is untouched
+    Append,
+    /// New file, the write tool refused to touch an existing one
+    Create,
     Edit,
     MultiEdit,
+    /// Hunks of a unified diff applied by the apply_patch tool
+    Patch,
 }
 
 /// Shared log for tracking file system operations
@@ -25,6 +135,10 @@ pub enum FsOperationType {
 pub struct FsOperationLog {
     operations: RwLock<Vec<FsOperation>>,
     read_files: RwLock<HashSet<String>>, // Tracks which files have been read
+    /// File the changes are also written to, so that they can be undone after the session
+    journal: Option<PathBuf>,
+    /// changes appended to the journal by this log since it was last trimmed
+    appended: AtomicUsize,
 }
 
 impl FsOperationLog {
@@ -33,17 +147,81 @@ impl FsOperationLog {
         Self {
             operations: RwLock::new(Vec::new()),
             read_files: RwLock::new(HashSet::new()),
+            journal: None,
+            appended: AtomicUsize::new(0),
         }
     }
 
+    /// A log that also writes its changes to `journal`, where `shai undo` finds them
+    pub fn with_journal(journal: impl Into<PathBuf>) -> Self {
+        Self { journal: Some(journal.into()), ..Self::new() }
+    }
+
+    /// A log journaled in the undo journal of the current directory, where `shai undo` finds it.
+    /// Without a cache directory the changes are not journaled
+    pub fn persistent() -> Self {
+        match Self::default_journal() {
+            Some(journal) => Self::with_journal(journal),
+            None => {
+                warn!(target: "tools::fs", "no cache directory, the file changes can't be undone after the session");
+                Self::new()
+            }
+        }
+    }
+
+    /// The undo journal of the current directory, in the user's cache directory
+    /// (~/.cache/shai/undo on Linux) so that the backups stay out of the project
+    pub fn default_journal() -> Option<PathBuf> {
+        let project = std::env::current_dir().ok()?;
+        let project = project.canonicalize().unwrap_or(project);
+        let name = format!("{}.jsonl", content_hash(&project.to_string_lossy()));
+        Some(dirs::cache_dir()?.join("shai").join("undo").join(name))
+    }
+
+    /// The changes of an undo journal, the log keeps writing to it. A missing journal is empty
+    pub fn load_journal(journal: impl Into<PathBuf>) -> Result<Self, String> {
+        let journal = journal.into();
+        let operations = read_journal(&journal)?;
+        Ok(Self { operations: RwLock::new(operations), journal: Some(journal), ..Self::new() })
+    }
+
     /// Log a file operation
     pub async fn log_operation(&self, operation_type: FsOperationType, file_path: String) {
+        self.push(operation_type, file_path, None).await;
+    }
+
+    /// Log an operation that changed a file, with what the file held before so that it can be undone
+    pub async fn log_change(&self, operation_type: FsOperationType, file_path: String, backup: FileBackup) {
+        self.push(operation_type, file_path, Some(backup)).await;
+    }
+
+    async fn push(&self, operation_type: FsOperationType, file_path: String, backup: Option<FileBackup>) {
+        // changes are logged once made, the file now holds what undoing them would overwrite
+        let after = backup.as_ref()
+            .and_then(|_| fs::read_to_string(&file_path).ok())
+            .map(|content| content_hash(&content));
         let operation = FsOperation {
             operation_type: operation_type.clone(),
             file_path: file_path.clone(),
             timestamp: Utc::now(),
+            backup,
+            after,
         };
 
+        if let (Some(journal), Some(_)) = (&self.journal, &operation.backup) {
+            let mut journaled = append_journal(journal, &operation).await;
+            if journaled.is_ok() && self.appended.fetch_add(1, Ordering::SeqCst) + 1 >= MAX_UNDO_JOURNAL {
+                self.appended.store(0, Ordering::SeqCst);
+                journaled = update_journal(journal, |journaled| {
+                    let excess = journaled.len().saturating_sub(MAX_UNDO_JOURNAL);
+                    journaled.drain(..excess);
+                }).await;
+            }
+            if let Err(e) = journaled {
+                warn!(target: "tools::fs", "could not journal the change of {}: {}", file_path, e);
+            }
+        }
+
         // Add to operations log
         {
             let mut ops = self.operations.write().await;
@@ -90,6 +268,74 @@ impl FsOperationLog {
         operations.clone()
     }
 
+    /// Files written, appended to or edited after `since`, in the order they were first touched
+    pub async fn modified_since(&self, since: DateTime<Utc>) -> Vec<String> {
+        let operations = self.operations.read().await;
+        let mut files: Vec<String> = Vec::new();
+        for op in operations.iter().filter(|op| op.timestamp > since && op.operation_type != FsOperationType::Read) {
+            if !files.contains(&op.file_path) {
+                files.push(op.file_path.clone());
+            }
+        }
+        files
+    }
+
+    /// The last `count` changes that can be undone, newest first, with the content each revert
+    /// restores and whether the file was changed since. Nothing is changed on disk
+    pub async fn undo_plan(&self, count: usize) -> Vec<UndoStep> {
+        let operations = self.operations.read().await;
+        let mut files: HashMap<String, Option<String>> = HashMap::new();
+        let mut steps = Vec::new();
+        for operation in operations.iter().rev().filter(|op| op.backup.is_some()).take(count) {
+            let current = files.entry(operation.file_path.clone())
+                .or_insert_with(|| fs::read_to_string(&operation.file_path).ok())
+                .clone();
+            let restored = operation.backup.as_ref().and_then(|backup| backup.content()).map(str::to_string);
+            let changed_since = operation.after.as_ref()
+                .is_some_and(|after| current.as_deref().map(content_hash).as_ref() != Some(after));
+            files.insert(operation.file_path.clone(), restored.clone());
+            steps.push(UndoStep { operation: operation.clone(), current, restored, changed_since });
+        }
+        steps
+    }
+
+    /// Revert the last `count` changes, newest first, and forget them. Nothing is reverted when a
+    /// file was changed since, unless `force` is set. Stops at the first file that cannot be
+    /// written, the changes reverted until then are forgotten
+    pub async fn undo(&self, count: usize, force: bool) -> Result<Vec<UndoStep>, String> {
+        let steps = self.undo_plan(count).await;
+        if let Some(step) = steps.iter().find(|step| step.changed_since && !force) {
+            return Err(format!(
+                "{} was changed since the {:?} of {}, undoing it would lose those changes. Nothing was reverted, force the undo to overwrite them",
+                step.operation.file_path, step.operation.operation_type, step.operation.timestamp.format("%H:%M:%S")));
+        }
+        let mut done = Vec::new();
+        let mut failure = None;
+        for step in steps {
+            if let Err(e) = step.apply() {
+                failure = Some(e);
+                break;
+            }
+            done.push(step);
+        }
+
+        let undone: Vec<&FsOperation> = done.iter().map(|step| &step.operation).collect();
+        self.operations.write().await.retain(|op| !undone.contains(&op));
+        if let Some(journal) = &self.journal {
+            let forgotten = update_journal(journal, |journaled| {
+                journaled.retain(|op| !undone.contains(&op));
+            }).await;
+            if let Err(e) = forgotten {
+                warn!(target: "tools::fs", "could not update the undo journal: {}", e);
+            }
+        }
+
+        match failure {
+            Some(e) => Err(format!("{} change(s) reverted, then: {}", done.len(), e)),
+            None => Ok(done),
+        }
+    }
+
     /// Get list of all files that have been read
     pub async fn get_read_files(&self) -> HashSet<String> {
         let read_files = self.read_files.read().await;
@@ -115,15 +361,19 @@ impl FsOperationLog {
 
         let mut read_count = 0;
         let mut write_count = 0;
+        let mut append_count = 0;
         let mut edit_count = 0;
         let mut multiedit_count = 0;
+        let mut patch_count = 0;
 
         for op in operations.iter() {
             match op.operation_type {
                 FsOperationType::Read => read_count += 1,
-                FsOperationType::Write => write_count += 1,
+                FsOperationType::Write | FsOperationType::Create => write_count += 1,
+                FsOperationType::Append => append_count += 1,
                 FsOperationType::Edit => edit_count += 1,
                 FsOperationType::MultiEdit => multiedit_count += 1,
+                FsOperationType::Patch => patch_count += 1,
             }
         }
 
@@ -131,8 +381,10 @@ impl FsOperationLog {
             total_operations: operations.len(),
             read_count,
             write_count,
+            append_count,
             edit_count,
             multiedit_count,
+            patch_count,
             unique_files_read: read_files.len(),
         }
     }
@@ -144,8 +396,10 @@ pub struct FsOperationSummary {
     pub total_operations: usize,
     pub read_count: usize,
     pub write_count: usize,
+    pub append_count: usize,
     pub edit_count: usize,
     pub multiedit_count: usize,
+    pub patch_count: usize,
     pub unique_files_read: usize,
 }
 
@@ -155,6 +409,100 @@ impl Default for FsOperationLog {
     }
 }
 
+/// One change per line, oldest first. A line that cannot be read (cut by a crash, written by
+/// another version) is skipped, the other changes can still be undone
+fn read_journal(journal: &Path) -> Result<Vec<FsOperation>, String> {
+    let content = match fs::read_to_string(journal) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{}: {}", journal.display(), e)),
+    };
+    Ok(content.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| match serde_json::from_str(line) {
+            Ok(operation) => Some(operation),
+            Err(e) => {
+                warn!(target: "tools::fs", "skipping line {} of {}: {}", index + 1, journal.display(), e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Add one change at the end of the journal while holding its lock, a crash can only cut that line
+async fn append_journal(journal: &Path, operation: &FsOperation) -> Result<(), String> {
+    create_journal_dir(journal)?;
+    let line = serde_json::to_string(operation).map_err(|e| e.to_string())? + "\n";
+    let _lock = JournalLock::acquire(journal).await?;
+    fs::OpenOptions::new().create(true).append(true).open(journal)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("{}: {}", journal.display(), e))
+}
+
+fn create_journal_dir(journal: &Path) -> Result<(), String> {
+    match journal.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        Some(parent) => fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e)),
+        None => Ok(()),
+    }
+}
+
+/// Change the journal while holding its lock, so that the sessions of other processes do not
+/// lose each other's changes. The new journal replaces the old one at once, a crash leaves either
+async fn update_journal(journal: &Path, update: impl FnOnce(&mut Vec<FsOperation>)) -> Result<(), String> {
+    create_journal_dir(journal)?;
+    let _lock = JournalLock::acquire(journal).await?;
+    let mut operations = read_journal(journal)?;
+    update(&mut operations);
+
+    let mut content = String::new();
+    for operation in &operations {
+        content.push_str(&serde_json::to_string(operation).map_err(|e| e.to_string())?);
+        content.push('\n');
+    }
+    let temporary = journal.with_extension("jsonl.tmp");
+    fs::write(&temporary, content)
+        .and_then(|_| fs::rename(&temporary, journal))
+        .map_err(|e| format!("{}: {}", journal.display(), e))
+}
+
+/// Lock file next to the journal, removed when dropped
+struct JournalLock {
+    path: PathBuf,
+}
+
+impl JournalLock {
+    async fn acquire(journal: &Path) -> Result<Self, String> {
+        let path = journal.with_extension("jsonl.lock");
+        let start = Instant::now();
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path).and_then(|meta| meta.modified()).ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_JOURNAL_LOCK);
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed() > JOURNAL_LOCK_TIMEOUT {
+                        return Err(format!("{} is locked by another process", journal.display()));
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                Err(e) => return Err(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+}
+
+impl Drop for JournalLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -223,6 +571,118 @@ mod tests {
         assert_eq!(summary.unique_files_read, 1);
     }
 
+    #[tokio::test]
+    async fn test_modified_since() {
+        let log = FsOperationLog::new();
+        log.log_operation(FsOperationType::Write, "old.rs".to_string()).await;
+        let since = Utc::now();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        log.log_operation(FsOperationType::Read, "read.rs".to_string()).await;
+        log.log_operation(FsOperationType::Edit, "b.rs".to_string()).await;
+        log.log_operation(FsOperationType::Create, "a.rs".to_string()).await;
+        log.log_operation(FsOperationType::MultiEdit, "b.rs".to_string()).await;
+
+        assert_eq!(log.modified_since(since).await, vec!["b.rs".to_string(), "a.rs".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_journal_is_undone_by_another_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join(".shai").join("undo.jsonl");
+        let file = dir.path().join("a.txt");
+        let path = file.to_string_lossy().to_string();
+
+        // a session writes the file twice, each change is logged once made like the tools do
+        let log = FsOperationLog::with_journal(&journal);
+        let backup = FileBackup::capture(&path).unwrap();
+        fs::write(&file, "one\n").unwrap();
+        log.log_change(FsOperationType::Create, path.clone(), backup).await;
+        let backup = FileBackup::capture(&path).unwrap();
+        fs::write(&file, "two\n").unwrap();
+        log.log_change(FsOperationType::Write, path.clone(), backup).await;
+        log.log_operation(FsOperationType::Read, path.clone()).await;
+
+        // a later process finds the changes in the journal
+        let later = FsOperationLog::load_journal(&journal).unwrap();
+        let plan = later.undo_plan(10).await;
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan[0].current.as_deref(), Some("two\n"));
+        assert_eq!(plan[0].restored.as_deref(), Some("one\n"));
+        assert_eq!(plan[1].restored, None);
+        assert!(plan[0].diff().contains("-two\n+one"), "{}", plan[0].diff());
+
+        let undone = later.undo(1, false).await.unwrap();
+        assert_eq!(undone.len(), 1);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one\n");
+        assert_eq!(FsOperationLog::load_journal(&journal).unwrap().undo_plan(10).await.len(), 1);
+
+        later.undo(1, false).await.unwrap();
+        assert!(!file.exists());
+        assert!(FsOperationLog::load_journal(&journal).unwrap().get_all_operations().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_undo_refuses_to_overwrite_a_file_changed_since() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        let path = file.to_string_lossy().to_string();
+        fs::write(&file, "one\n").unwrap();
+
+        let log = FsOperationLog::new();
+        let backup = FileBackup::capture(&path).unwrap();
+        fs::write(&file, "two\n").unwrap();
+        log.log_change(FsOperationType::Write, path.clone(), backup).await;
+
+        // the user edits the file after the agent
+        fs::write(&file, "three\n").unwrap();
+        assert!(log.undo_plan(1).await[0].changed_since);
+        let error = log.undo(1, false).await.unwrap_err();
+        assert!(error.contains("was changed since"), "{}", error);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "three\n");
+
+        let undone = log.undo(1, true).await.unwrap();
+        assert_eq!(undone.len(), 1);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "one\n");
+    }
+
+    #[tokio::test]
+    async fn test_journal_skips_the_lines_it_cannot_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("undo.jsonl");
+        let path = dir.path().join("a.txt").to_string_lossy().to_string();
+        fs::write(&journal, "{\"operation_type\": \"Wri\n").unwrap();
+
+        let log = FsOperationLog::with_journal(&journal);
+        log.log_change(FsOperationType::Create, path.clone(), FileBackup::Absent).await;
+        log.log_change(FsOperationType::Create, path, FileBackup::Absent).await;
+
+        let later = FsOperationLog::load_journal(&journal).unwrap();
+        assert_eq!(later.get_all_operations().await.len(), 2);
+        assert!(!journal.with_extension("jsonl.lock").exists());
+    }
+
+    #[tokio::test]
+    async fn test_journal_is_appended_to_and_trimmed() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("undo.jsonl");
+        let path = dir.path().join("a.txt").to_string_lossy().to_string();
+
+        let log = FsOperationLog::with_journal(&journal);
+        for _ in 0..MAX_UNDO_JOURNAL - 1 {
+            log.log_change(FsOperationType::Create, path.clone(), FileBackup::Absent).await;
+        }
+        // a change journaled by another process stays
+        let other = FsOperationLog::with_journal(&journal);
+        other.log_change(FsOperationType::Write, path.clone(), FileBackup::Content("other\n".to_string())).await;
+        assert_eq!(read_journal(&journal).unwrap().len(), MAX_UNDO_JOURNAL);
+
+        log.log_change(FsOperationType::Create, path.clone(), FileBackup::Absent).await;
+        let journaled = read_journal(&journal).unwrap();
+        assert_eq!(journaled.len(), MAX_UNDO_JOURNAL);
+        assert_eq!(journaled[MAX_UNDO_JOURNAL - 2].operation_type, FsOperationType::Write);
+    }
+
     #[tokio::test]
     async fn test_clear_log() {
         let log = FsOperationLog::new();
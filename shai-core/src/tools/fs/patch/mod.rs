@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod patch;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::ApplyPatchToolParams;
+pub use patch::ApplyPatchTool;
@@ -0,0 +1,364 @@
+use super::structs::ApplyPatchToolParams;
+use super::super::{EditTool, FileBackup, FsOperationLog, FsOperationType};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Lines of the file shown around the place a failing hunk was expected
+const FAILURE_CONTEXT_LINES: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Hunk {
+    /// The `@@ ... @@` line, to name the hunk in errors
+    header: String,
+    /// Index of the original lines where the hunk applies (0-based), when the header gives it
+    old_start: Option<usize>,
+    lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+            HunkLine::Add(_) => None,
+        }).collect()
+    }
+
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Add(text) => Some(text.as_str()),
+            HunkLine::Remove(_) => None,
+        }).collect()
+    }
+}
+
+/// The hunks of one file, `None` paths stand for /dev/null
+#[derive(Debug, Clone, PartialEq, Default)]
+struct FilePatch {
+    has_headers: bool,
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// A file of the patch with its content before (None when the patch creates it) and after
+struct PatchedFile {
+    path: String,
+    original: Option<String>,
+    patched: String,
+    hunks: usize,
+}
+
+#[derive(Clone)]
+pub struct ApplyPatchTool {
+    operation_log: Arc<FsOperationLog>,
+    edit_tool: EditTool,
+}
+
+impl ApplyPatchTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        let edit_tool = EditTool::new(operation_log.clone());
+        Self { operation_log, edit_tool }
+    }
+
+    /// Content of every file once patched, or why the patch does not apply. Nothing is written here
+    async fn prepare(&self, params: &ApplyPatchToolParams) -> Result<Vec<PatchedFile>, String> {
+        let files = parse_patch(&params.patch)?;
+        let single = files.len() == 1;
+
+        let mut patched = Vec::new();
+        let mut failures = Vec::new();
+        for file in &files {
+            let (path, creates) = target(file, params.path.as_deref(), single)?;
+            let original = if creates {
+                if Path::new(&path).exists() {
+                    return Err(format!("{} already exists, the patch creates it", path));
+                }
+                None
+            } else {
+                if !Path::new(&path).exists() {
+                    return Err(format!("File does not exist: {}", path));
+                }
+                self.operation_log.validate_edit_permission(&path).await?;
+                Some(fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?)
+            };
+
+            match apply_hunks(original.as_deref().unwrap_or(""), &file.hunks) {
+                Ok(content) => patched.push(PatchedFile { path, original, patched: content, hunks: file.hunks.len() }),
+                Err(errors) => failures.extend(errors.into_iter().map(|error| format!("{}: {}", path, error))),
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(format!("nothing was changed\n{}", failures.join("\n")));
+        }
+        Ok(patched)
+    }
+
+    /// Write every file, putting back the ones already written when a write fails
+    fn commit(&self, files: &[PatchedFile]) -> Result<(), String> {
+        for (index, file) in files.iter().enumerate() {
+            let written = match Path::new(&file.path).parent() {
+                Some(parent) if file.original.is_none() && !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+                _ => Ok(()),
+            }.and_then(|_| fs::write(&file.path, &file.patched));
+
+            if let Err(e) = written {
+                for done in &files[..index] {
+                    let _ = match &done.original {
+                        Some(original) => fs::write(&done.path, original),
+                        None => fs::remove_file(&done.path),
+                    };
+                }
+                return Err(format!("{}: {}", file.path, e));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a unified diff into files and hunks. Hunk line counts are not trusted, models get them
+/// wrong: a hunk runs until the next hunk, the next file or a line that is not part of a diff
+fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = patch.lines().collect();
+    let is_file_header = |i: usize| {
+        lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|next| next.starts_with("+++ "))
+    };
+
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if is_file_header(i) {
+            files.push(FilePatch {
+                has_headers: true,
+                old_path: header_path(&lines[i][4..]),
+                new_path: header_path(&lines[i + 1][4..]),
+                hunks: Vec::new(),
+            });
+            i += 2;
+            continue;
+        }
+        if !lines[i].starts_with("@@") {
+            // diff --git, index, mode lines and any text around the patch
+            i += 1;
+            continue;
+        }
+
+        let header = lines[i].trim().to_string();
+        i += 1;
+        let mut body = Vec::new();
+        // blank lines closing the patch are not context, a blank context line is " "
+        let mut trailing_blank = 0;
+        while i < lines.len() && !lines[i].starts_with("@@") && !lines[i].starts_with("diff ") && !is_file_header(i) {
+            let line = lines[i];
+            match line.chars().next() {
+                Some(' ') => body.push(HunkLine::Context(line[1..].to_string())),
+                Some('-') => body.push(HunkLine::Remove(line[1..].to_string())),
+                Some('+') => body.push(HunkLine::Add(line[1..].to_string())),
+                // "\ No newline at end of file"
+                Some('\\') => {}
+                None => body.push(HunkLine::Context(String::new())),
+                Some(_) => break,
+            }
+            trailing_blank = if line.is_empty() { trailing_blank + 1 } else { 0 };
+            i += 1;
+        }
+        body.truncate(body.len() - trailing_blank);
+
+        if files.is_empty() {
+            files.push(FilePatch::default());
+        }
+        files.last_mut().unwrap().hunks.push(Hunk { old_start: old_start(&header), header, lines: body });
+    }
+
+    files.retain(|file| !file.hunks.is_empty());
+    if files.is_empty() {
+        return Err("the patch has no hunk, write the changes as @@ -line,count +line,count @@ hunks".to_string());
+    }
+    Ok(files)
+}
+
+/// Path of a `---` or `+++` header, without the timestamp some tools add
+fn header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or_default().trim();
+    (path != "/dev/null" && !path.is_empty()).then(|| path.to_string())
+}
+
+/// Where the original lines of `@@ -12,5 +12,7 @@` start, index 11. A hunk that only inserts
+/// (`@@ -12,0 +13,2 @@`) names the line it goes after, so it goes at index 12
+fn old_start(header: &str) -> Option<usize> {
+    let range = header.trim_start_matches('@').trim_start().strip_prefix('-')?;
+    let range = range.split_whitespace().next()?;
+    let (start, count) = match range.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (range.parse().ok()?, 1),
+    };
+    Some(if count == 0 { start } else { start.saturating_sub(1) })
+}
+
+/// File a patch applies to and whether the patch creates it
+fn target(file: &FilePatch, path: Option<&str>, single: bool) -> Result<(String, bool), String> {
+    if !file.has_headers {
+        return path.map(|path| (path.to_string(), false))
+            .ok_or_else(|| "the patch has no --- / +++ headers, set path to the file to patch".to_string());
+    }
+    let Some(new_path) = &file.new_path else {
+        return Err(format!("the patch deletes {}, delete files with bash instead", file.old_path.as_deref().unwrap_or("a file")));
+    };
+    let creates = file.old_path.is_none();
+    match path {
+        Some(path) if single => Ok((path.to_string(), creates)),
+        _ => Ok((strip_diff_prefix(new_path), creates)),
+    }
+}
+
+/// `a/src/main.rs` is `src/main.rs`, unless a directory named `a` really holds it
+fn strip_diff_prefix(path: &str) -> String {
+    match path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")) {
+        Some(stripped) if !Path::new(path).exists() => stripped.to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Apply the hunks in order. A hunk is looked for at its line first, then at the closest place
+/// after the previous hunk, ignoring trailing whitespace as a last resort.
+/// Returns the description of every hunk that does not apply
+fn apply_hunks(content: &str, hunks: &[Hunk]) -> Result<String, Vec<String>> {
+    let eol = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut patched: Vec<&str> = Vec::new();
+    let mut failures = Vec::new();
+    let mut cursor = 0;
+    for (index, hunk) in hunks.iter().enumerate() {
+        let old = hunk.old_lines();
+        let expected = hunk.old_start.unwrap_or(0);
+        match find_hunk(&lines, &old, cursor, expected) {
+            Some(at) => {
+                patched.extend(&lines[cursor..at]);
+                patched.extend(hunk.new_lines());
+                cursor = at + old.len();
+            }
+            None => failures.push(hunk_failure(index, hunk, &lines, expected)),
+        }
+    }
+    if !failures.is_empty() {
+        return Err(failures);
+    }
+    patched.extend(&lines[cursor..]);
+
+    let mut result = patched.join(eol);
+    if !result.is_empty() && (content.is_empty() || content.ends_with('\n')) {
+        result.push_str(eol);
+    }
+    Ok(result)
+}
+
+fn find_hunk(lines: &[&str], old: &[&str], from: usize, expected: usize) -> Option<usize> {
+    if old.is_empty() {
+        return Some(expected.clamp(from, lines.len()));
+    }
+    let last = lines.len().checked_sub(old.len())?;
+    if from > last {
+        return None;
+    }
+    for exact in [true, false] {
+        let matches = |at: usize| lines[at..at + old.len()].iter().zip(old)
+            .all(|(line, wanted)| if exact { line == wanted } else { line.trim_end() == wanted.trim_end() });
+        if let Some(at) = (from..=last).filter(|&at| matches(at)).min_by_key(|&at| at.abs_diff(expected)) {
+            return Some(at);
+        }
+    }
+    None
+}
+
+/// What the hunk expected and what the file has around that place
+fn hunk_failure(index: usize, hunk: &Hunk, lines: &[&str], expected: usize) -> String {
+    let old = hunk.old_lines();
+    let mut message = format!("hunk #{} ({}) does not match the file, it expects:", index + 1, hunk.header);
+    for line in &old {
+        message.push_str(&format!("\n  {}", line));
+    }
+
+    let start = expected.saturating_sub(FAILURE_CONTEXT_LINES).min(lines.len());
+    let end = (expected + old.len() + FAILURE_CONTEXT_LINES).min(lines.len());
+    if start < end {
+        message.push_str(&format!("\nthe file has, lines {}-{}:", start + 1, end));
+        for (offset, line) in lines[start..end].iter().enumerate() {
+            message.push_str(&format!("\n{:>6}\t{}", start + offset + 1, line));
+        }
+    }
+    message
+}
+
+#[tool(name = "apply_patch", description = r#"Applies a patch in unified diff format to one or more files. Use it for changes spread over many places of a file or over several files, where edit and multiedit would need many exact matches.
+
+**Prerequisites:**
+- Read every file the patch changes with the `read` tool first, the patch fails otherwise. Files the patch creates (`--- /dev/null`) need no reading.
+
+**Usage Guidelines:**
+- Write a regular unified diff: `--- a/path` and `+++ b/path` headers, then `@@ -line,count +line,count @@` hunks whose lines start with a space (context), `-` (removed) or `+` (added).
+- Give each hunk about 3 lines of unchanged context, copied exactly from the file. A hunk that moved is still found near its line, but its context and removed lines must match the file.
+- The patch is all-or-nothing: when a hunk does not match, nothing is written and the error shows what the hunk expected next to what the file has.
+- A patch without headers applies to `path`. Deleting files is not supported, use bash for that.
+"#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
+impl ApplyPatchTool {
+    async fn execute_preview(&self, params: ApplyPatchToolParams) -> Option<ToolResult> {
+        Some(self.execute_internal(params, true).await)
+    }
+
+    async fn execute(&self, params: ApplyPatchToolParams) -> ToolResult {
+        self.execute_internal(params, false).await
+    }
+
+    async fn execute_internal(&self, params: ApplyPatchToolParams, preview: bool) -> ToolResult {
+        let files = match self.prepare(&params).await {
+            Ok(files) => files,
+            Err(e) => return ToolResult::error(format!("Patch {}failed: {}", if preview { "preview " } else { "" }, e)),
+        };
+
+        if !preview {
+            if let Err(e) = self.commit(&files) {
+                return ToolResult::error(format!("Patch failed, the files were restored: {}", e));
+            }
+            for file in &files {
+                let (operation, backup) = match &file.original {
+                    Some(original) => (FsOperationType::Patch, FileBackup::Content(original.clone())),
+                    None => (FsOperationType::Create, FileBackup::Absent),
+                };
+                self.operation_log.log_change(operation, file.path.clone(), backup).await;
+            }
+        }
+
+        // the preview shows the files as they would be, the model and the user see the diff otherwise
+        let mut sections = Vec::new();
+        for file in &files {
+            let diff = self.edit_tool.myers_diff(file.original.as_deref().unwrap_or(""), &file.patched);
+            sections.push(format!("{}\n{}", file.path, diff));
+            if preview {
+                sections.push(format!("{} once patched:\n{}", file.path, file.patched));
+            }
+        }
+
+        let mut meta = HashMap::new();
+        meta.insert("paths".to_string(), json!(files.iter().map(|file| &file.path).collect::<Vec<_>>()));
+        meta.insert("hunks_per_file".to_string(), json!(files.iter().map(|file| file.hunks).collect::<Vec<_>>()));
+        meta.insert("created".to_string(), json!(files.iter().filter(|file| file.original.is_none()).map(|file| &file.path).collect::<Vec<_>>()));
+        meta.insert("preview_mode".to_string(), json!(preview));
+
+        ToolResult::Success {
+            output: sections.join("\n\n"),
+            metadata: Some(meta),
+        }
+    }
+}
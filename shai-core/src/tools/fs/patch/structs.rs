@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ApplyPatchToolParams {
+    /// The patch, in unified diff format (`--- a/path`, `+++ b/path` and `@@ -l,s +l,s @@` hunks)
+    pub patch: String,
+    /// File to patch, needed when the patch has no `---`/`+++` headers (optional, wins over the headers of a single-file patch)
+    #[serde(default)]
+    pub path: Option<String>,
+}
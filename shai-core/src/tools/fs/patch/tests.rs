@@ -0,0 +1,118 @@
+use super::structs::ApplyPatchToolParams;
+use super::patch::ApplyPatchTool;
+use crate::tools::{Tool, ToolCapability, ToolResult, FsOperationLog, FsOperationType};
+use shai_llm::ToolDescription;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+const ORIGINAL: &str = "fn main() {\n    let a = 1;\n    let b = 2;\n    println!(\"{}\", a + b);\n}\n\nfn helper() {\n    todo!()\n}\n";
+
+async fn read_tool(paths: &[&Path]) -> (ApplyPatchTool, Arc<FsOperationLog>) {
+    let log = Arc::new(FsOperationLog::new());
+    for path in paths {
+        log.log_operation(FsOperationType::Read, path.to_string_lossy().to_string()).await;
+    }
+    (ApplyPatchTool::new(log.clone()), log)
+}
+
+fn params(patch: String, path: Option<&Path>) -> ApplyPatchToolParams {
+    ApplyPatchToolParams { patch, path: path.map(|p| p.to_string_lossy().to_string()) }
+}
+
+#[test]
+fn test_apply_patch_tool_permissions() {
+    let tool = ApplyPatchTool::new(Arc::new(FsOperationLog::new()));
+    assert_eq!(&tool.name(), "apply_patch");
+    assert_eq!(tool.capabilities(), &[ToolCapability::Read, ToolCapability::Write]);
+}
+
+#[tokio::test]
+async fn test_apply_patch_applies_every_hunk() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("main.rs");
+    fs::write(&file, ORIGINAL).unwrap();
+    let (tool, log) = read_tool(&[&file]).await;
+
+    // the second hunk says line 9, the function starts at line 7: it is found anyway
+    let patch = format!("--- a/{path}\n+++ b/{path}\n\
+        @@ -2,3 +2,3 @@\n     let a = 1;\n-    let b = 2;\n+    let b = 3;\n     println!(\"{{}}\", a + b);\n\
+        @@ -9,3 +9,3 @@\n fn helper() {{\n-    todo!()\n+    println!(\"help\");\n }}\n",
+        path = file.display());
+    let result = tool.execute(params(patch, None), None).await;
+    assert!(result.is_success(), "{:?}", result);
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), ORIGINAL.replace("let b = 2", "let b = 3").replace("todo!()", "println!(\"help\");"));
+    assert_eq!(log.get_summary().await.patch_count, 1);
+}
+
+#[tokio::test]
+async fn test_apply_patch_inserts_after_the_line_of_a_zero_count_hunk() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("main.rs");
+    fs::write(&file, ORIGINAL).unwrap();
+    let (tool, _) = read_tool(&[&file]).await;
+
+    // no context: the lines go after line 3, not before it
+    let patch = "@@ -3,0 +4,1 @@\n+    let c = 3;\n".to_string();
+    let result = tool.execute(params(patch, Some(&file)), None).await;
+    assert!(result.is_success(), "{:?}", result);
+    assert_eq!(fs::read_to_string(&file).unwrap(), ORIGINAL.replace("let b = 2;\n", "let b = 2;\n    let c = 3;\n"));
+}
+
+#[tokio::test]
+async fn test_apply_patch_is_all_or_nothing() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("main.rs");
+    fs::write(&file, ORIGINAL).unwrap();
+    let (tool, _) = read_tool(&[&file]).await;
+
+    let patch = "@@ -2,2 +2,2 @@\n     let a = 1;\n-    let b = 2;\n+    let b = 3;\n\
+        @@ -8,1 +8,1 @@\n-    unimplemented!()\n+    println!(\"help\");\n".to_string();
+    let result = tool.execute(params(patch, Some(&file)), None).await;
+
+    let ToolResult::Error { error, .. } = result else { panic!("expected an error, got {:?}", result) };
+    assert!(error.contains("hunk #2"), "{}", error);
+    assert!(!error.contains("hunk #1"), "{}", error);
+    assert!(error.contains("unimplemented!()"), "{}", error);
+    assert!(error.contains("     8\t    todo!()"), "{}", error);
+    assert_eq!(fs::read_to_string(&file).unwrap(), ORIGINAL);
+}
+
+#[tokio::test]
+async fn test_apply_patch_requires_read_and_creates_files() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("main.rs");
+    let created = dir.path().join("src").join("new.rs");
+    fs::write(&file, ORIGINAL).unwrap();
+    let (tool, _) = read_tool(&[]).await;
+
+    let patch = format!("--- a/{file}\n+++ b/{file}\n@@ -2,1 +2,1 @@\n-    let a = 1;\n+    let a = 0;\n\
+        --- /dev/null\n+++ b/{created}\n@@ -0,0 +1,2 @@\n+pub fn new() {{}}\n+\n",
+        file = file.display(), created = created.display());
+    let result = tool.execute(params(patch.clone(), None), None).await;
+    assert!(matches!(result, ToolResult::Error { ref error, .. } if error.contains("must be read first")), "{:?}", result);
+    assert!(!created.exists());
+
+    let (tool, _) = read_tool(&[&file]).await;
+    let result = tool.execute(params(patch, None), None).await;
+    assert!(result.is_success(), "{:?}", result);
+    assert_eq!(fs::read_to_string(&created).unwrap(), "pub fn new() {}\n\n");
+    assert!(fs::read_to_string(&file).unwrap().contains("let a = 0;"));
+}
+
+#[tokio::test]
+async fn test_apply_patch_preview_shows_the_resulting_file() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("notes.txt");
+    fs::write(&file, "one\ntwo\nthree\n").unwrap();
+    let (tool, _) = read_tool(&[&file]).await;
+
+    let patch = "```diff\n@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\n```\n".to_string();
+    let result = tool.execute_preview(params(patch, Some(&file))).await.unwrap();
+
+    let ToolResult::Success { output, .. } = result else { panic!("expected a preview, got {:?}", result) };
+    assert!(output.contains("once patched:\none\n2\nthree"), "{}", output);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "one\ntwo\nthree\n");
+}
@@ -5,4 +5,4 @@ pub mod read;
 mod tests;
 
 pub use structs::ReadToolParams;
-pub use read::ReadTool;
\ No newline at end of file
+pub use read::{ReadTool, DEFAULT_READ_MAX_BYTES};
\ No newline at end of file
@@ -1,4 +1,7 @@
 use crate::tools::{ToolResult, tool};
+use crate::tools::types::IMAGE_METADATA_KEY;
+use base64::Engine;
+use shai_llm::image::{data_url, image_mime_type};
 use super::structs::ReadToolParams;
 use super::super::{FsOperationLog, FsOperationType};
 use serde_json::json;
@@ -8,102 +11,118 @@ use std::io::{self, BufRead, BufReader};
 use std::path::Path;
 use std::sync::Arc;
 
+/// Largest image the read tool attaches, providers reject bigger ones anyway
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Text returned by a single read is cut past this size unless the config says otherwise
+pub const DEFAULT_READ_MAX_BYTES: u64 = 100 * 1024;
+
 #[derive(Clone)]
 pub struct ReadTool {
     operation_log: Arc<FsOperationLog>,
+    max_bytes: u64,
 }
 
 impl ReadTool {
     pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
-        Self { operation_log }
+        Self::with_max_bytes(operation_log, DEFAULT_READ_MAX_BYTES)
+    }
+
+    /// Ceiling on the text a read returns, larger files have to be read in ranges
+    pub fn with_max_bytes(operation_log: Arc<FsOperationLog>, max_bytes: u64) -> Self {
+        Self { operation_log, max_bytes }
+    }
+
+    /// First and last line asked for, offset/limit win over line_start/line_end
+    fn line_range(params: &ReadToolParams) -> (Option<u32>, Option<u32>) {
+        if params.offset.is_none() && params.limit.is_none() {
+            return (params.line_start, params.line_end);
+        }
+        let start = params.offset.unwrap_or(1).max(1);
+        (Some(start), params.limit.map(|limit| start.saturating_add(limit.max(1) - 1)))
+    }
+
+    /// A range read with offset/limit always shows where it is in the file
+    fn shows_line_numbers(params: &ReadToolParams) -> bool {
+        params.show_line_numbers || params.offset.is_some() || params.limit.is_some()
     }
 
     fn read_file_content(&self, params: &ReadToolParams) -> io::Result<String> {
-        let file = fs::File::open(&params.path)?;
-        let reader = BufReader::new(file);
-        
-        match (params.line_start, params.line_end) {
-            // Read specific line range
-            (Some(start), Some(end)) => {
-                let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                    .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line_num = i as u32 + 1; // 1-based line numbers
-                        if line_num >= start && line_num <= end {
-                            Some(line.map(|l| (line_num, l)))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                
-                match lines {
-                    Ok(filtered_lines) => Ok(self.format_lines(filtered_lines, params.show_line_numbers)),
-                    Err(e) => Err(e)
-                }
-            },
-            // Read from start line to end of file
-            (Some(start), None) => {
-                let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                    .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line_num = i as u32 + 1; // 1-based line numbers
-                        if line_num >= start {
-                            Some(line.map(|l| (line_num, l)))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                
-                match lines {
-                    Ok(filtered_lines) => Ok(self.format_lines(filtered_lines, params.show_line_numbers)),
-                    Err(e) => Err(e)
-                }
-            },
-            // Read from beginning to end line
-            (None, Some(end)) => {
-                let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                    .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line_num = i as u32 + 1; // 1-based line numbers
-                        if line_num <= end {
-                            Some(line.map(|l| (line_num, l)))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                
-                match lines {
-                    Ok(filtered_lines) => Ok(self.format_lines(filtered_lines, params.show_line_numbers)),
-                    Err(e) => Err(e)
-                }
-            },
-            // Read entire file
-            (None, None) => {
-                if params.show_line_numbers {
-                    let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                        .lines()
-                        .enumerate()
-                        .map(|(i, line)| {
-                            let line_num = i as u32 + 1;
-                            line.map(|l| (line_num, l))
-                        })
-                        .collect();
-                    
-                    match lines {
-                        Ok(numbered_lines) => Ok(self.format_lines(numbered_lines, true)),
-                        Err(e) => Err(e)
-                    }
-                } else {
-                    fs::read_to_string(&params.path)
-                }
-            }
+        let (start, end) = Self::line_range(params);
+        let show_line_numbers = Self::shows_line_numbers(params);
+        let byte_offset = params.byte_offset.unwrap_or(0) as usize;
+
+        if start.is_none() && end.is_none() && !show_line_numbers && byte_offset == 0 {
+            return fs::read_to_string(&params.path);
         }
+
+        let (start, end) = (start.unwrap_or(1), end.unwrap_or(u32::MAX));
+        let reader = BufReader::new(fs::File::open(&params.path)?);
+        let lines = reader
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i as u32 + 1, line)) // 1-based line numbers
+            .skip_while(|(line_num, _)| *line_num < start)
+            .take_while(|(line_num, _)| *line_num <= end)
+            .map(|(line_num, line)| line.map(|l| (line_num, if line_num == start { from_byte(l, byte_offset) } else { l })))
+            .collect::<io::Result<Vec<(u32, String)>>>()?;
+
+        Ok(self.format_lines(lines, show_line_numbers))
+    }
+
+    /// Cut the content after the last whole line that fits in max_bytes, with a note telling
+    /// how many lines were left out and where to continue. A first line too long to fit is cut
+    /// inside, the note then gives the byte of the line to continue from.
+    /// Returns the number of lines left out, the cut line included
+    fn truncate(&self, content: String, params: &ReadToolParams) -> (String, usize) {
+        let max_bytes = self.max_bytes as usize;
+        if content.len() <= max_bytes {
+            return (content, 0);
+        }
+
+        let mut limit = max_bytes;
+        while !content.is_char_boundary(limit) {
+            limit -= 1;
+        }
+        let first_line = Self::line_range(params).0.unwrap_or(1);
+        if let Some(newline) = content[..limit].rfind('\n') {
+            let whole_lines = content[..newline].matches('\n').count() + 1;
+            let left_out = content.lines().count() - whole_lines;
+            let output = format!(
+                "{}\n\n[output truncated at {} bytes: {} more lines not shown, continue with offset={} and a limit]",
+                &content[..newline], max_bytes, left_out, first_line as usize + whole_lines);
+            return (output, left_out);
+        }
+
+        // the line number prefix is not part of the line
+        let prefix = if Self::shows_line_numbers(params) { format!("{:4}: ", first_line).len() } else { 0 };
+        let next_byte = params.byte_offset.unwrap_or(0) as usize + limit.saturating_sub(prefix);
+        let left_out = content.lines().count();
+        let output = format!(
+            "{}\n\n[output truncated at {} bytes: line {} is too long, continue with offset={} and byte_offset={}; {} more lines not shown]",
+            &content[..limit], max_bytes, first_line, first_line, next_byte, left_out - 1);
+        (output, left_out)
+    }
+
+    /// Attach an image file to the result so a vision model can look at it
+    fn read_image(&self, path: &str, mime_type: &str) -> Result<ToolResult, String> {
+        let size = fs::metadata(path).map_err(|e| format!("Failed to read file: {}", e))?.len();
+        if size > MAX_IMAGE_BYTES {
+            return Err(format!("Image is too large to attach: {} bytes (max {})", size, MAX_IMAGE_BYTES));
+        }
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+
+        let mut meta = HashMap::new();
+        meta.insert("path".to_string(), json!(path));
+        meta.insert("mime_type".to_string(), json!(mime_type));
+        meta.insert("size".to_string(), json!(size));
+        meta.insert(IMAGE_METADATA_KEY.to_string(), json!(data_url(mime_type, &encoded)));
+
+        Ok(ToolResult::success_with_metadata(
+            format!("Image file {} ({}, {} bytes), attached for you to look at.", path, mime_type, size),
+            meta,
+        ))
     }
 
     fn format_lines(&self, lines: Vec<(u32, String)>, show_line_numbers: bool) -> String {
@@ -127,8 +146,10 @@ impl ReadTool {
 
 **Usage:**
 - An absolute `path` to the file is required.
-- For large files, you can read a specific portion by specifying `line_start` and `line_end`. If omitted, the entire file is read (within system limits).
+- For large files, read a specific portion with `offset` (first line, from 1) and `limit` (number of lines), e.g. `offset=200, limit=60` for lines 200 to 259; such reads are always prefixed with line numbers. `line_start` and `line_end` also select a range.
+- If no range is given, the entire file is read, but the output is cut once it gets too large: a note at the end says how many lines were left out and which `offset` to continue from. A single line too long to fit is cut inside, the note then also gives the `byte_offset` of the line to continue from.
 - The output is formatted with line numbers for easy reference, which is crucial context for subsequent `edit` operations.
+- Image files (png, jpg, gif, webp) are attached as images instead, for you to look at.
 
 **Best Practices:**
 - When investigating a task, it is often effective to read multiple potentially relevant files in a single turn to build a complete understanding of the context."#, capabilities = [Read])]
@@ -146,6 +167,14 @@ impl ReadTool {
             return ToolResult::error(format!("Path is not a file: {}", params.path));
         }
 
+        if let Some(mime_type) = image_mime_type(&params.path) {
+            let result = self.read_image(&params.path, mime_type).unwrap_or_else(ToolResult::error);
+            if result.is_success() {
+                self.operation_log.log_operation(FsOperationType::Read, params.path.clone()).await;
+            }
+            return result;
+        }
+
         // Read the file
         match self.read_file_content(&params) {
             Ok(content) => {
@@ -162,6 +191,20 @@ impl ReadTool {
                 if let Some(end) = params.line_end {
                     meta.insert("line_end".to_string(), json!(end));
                 }
+                if let Some(offset) = params.offset {
+                    meta.insert("offset".to_string(), json!(offset));
+                }
+                if let Some(limit) = params.limit {
+                    meta.insert("limit".to_string(), json!(limit));
+                }
+                if let Some(byte_offset) = params.byte_offset {
+                    meta.insert("byte_offset".to_string(), json!(byte_offset));
+                }
+
+                let (content, truncated_lines) = self.truncate(content, &params);
+                if truncated_lines > 0 {
+                    meta.insert("truncated_lines".to_string(), json!(truncated_lines));
+                }
 
                 ToolResult::Success {
                     output: content,
@@ -172,3 +215,15 @@ impl ReadTool {
         }
     }
 }
+
+/// `line` from its byte `offset`, moved back to the start of the character it falls in
+fn from_byte(line: String, offset: usize) -> String {
+    if offset >= line.len() {
+        return if offset == 0 { line } else { String::new() };
+    }
+    let mut offset = offset;
+    while !line.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    line[offset..].to_string()
+}
@@ -11,7 +11,16 @@ pub struct ReadToolParams {
     /// Ending line number (optional)
     #[serde(default)]
     pub line_end: Option<u32>,
+    /// First line to read, from 1 (optional, used with limit instead of line_start/line_end)
+    #[serde(default)]
+    pub offset: Option<u32>,
+    /// Number of lines to read from offset (optional)
+    #[serde(default)]
+    pub limit: Option<u32>,
     /// Whether to include line numbers in the output
     #[serde(default)]
     pub show_line_numbers: bool,
-}
\ No newline at end of file
+    /// Byte of the first line to start from (optional), to continue a line too long to be read at once
+    #[serde(default)]
+    pub byte_offset: Option<u32>,
+}
@@ -1,6 +1,6 @@
 use super::read::ReadTool;
 use super::structs::ReadToolParams;
-use crate::tools::{Tool, ToolCapability, FsOperationLog};
+use crate::tools::{Tool, ToolCapability, ToolResult, FsOperationLog};
 use shai_llm::ToolDescription;
 use tempfile::TempDir;
 use std::fs;
@@ -45,7 +45,10 @@ End of file"#;
         path: test_file_path.to_string_lossy().to_string(),
         line_start: None,
         line_end: None,
+        offset: None,
+        limit: None,
         show_line_numbers: false,
+        byte_offset: None,
     };
 
     let result = read_tool.execute(params, None).await;
@@ -68,7 +71,10 @@ End of file"#;
         path: test_file_path.to_string_lossy().to_string(),
         line_start: None,
         line_end: None,
+        offset: None,
+        limit: None,
         show_line_numbers: true,
+        byte_offset: None,
     };
 
     let result_with_lines = read_tool.execute(params_with_lines, None).await;
@@ -111,7 +117,10 @@ async fn test_read_tool_line_range_reading() {
         path: test_file_path.to_string_lossy().to_string(),
         line_start: Some(5),
         line_end: Some(10),
+        offset: None,
+        limit: None,
         show_line_numbers: true,
+        byte_offset: None,
     };
 
     let result_range = read_tool.execute(params_range, None).await;
@@ -139,7 +148,10 @@ async fn test_read_tool_line_range_reading() {
         path: test_file_path.to_string_lossy().to_string(),
         line_start: Some(15),
         line_end: None,
+        offset: None,
+        limit: None,
         show_line_numbers: true,
+        byte_offset: None,
     };
 
     let result_from_line = read_tool.execute(params_from_line, None).await;
@@ -166,7 +178,10 @@ async fn test_read_tool_line_range_reading() {
         path: "/nonexistent/path/file.txt".to_string(),
         line_start: None,
         line_end: None,
+        offset: None,
+        limit: None,
         show_line_numbers: false,
+        byte_offset: None,
     };
 
     let result_nonexistent = read_tool.execute(params_nonexistent, None).await;
@@ -182,4 +197,125 @@ async fn test_read_tool_line_range_reading() {
             panic!("Read tool was denied");
         }
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_read_tool_attaches_images() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let image_path = temp_dir.path().join("screenshot.png");
+    // PNG signature followed by a few bytes that are not valid UTF-8
+    fs::write(&image_path, [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0xFF, 0x00]).expect("Failed to write image");
+
+    let read_tool = ReadTool::new(Arc::new(FsOperationLog::new()));
+    let result = read_tool.execute(ReadToolParams {
+        path: image_path.to_string_lossy().to_string(),
+        line_start: None,
+        line_end: None,
+        offset: None,
+        limit: None,
+        show_line_numbers: true,
+        byte_offset: None,
+    }, None).await;
+
+    assert!(result.is_success(), "Reading an image should succeed: {}", result);
+    assert!(result.to_string().contains("image/png"));
+    let image = result.image().expect("the image should be attached");
+    assert!(image.starts_with("data:image/png;base64,iVBORw0KGgo"), "{}", image);
+
+    // once taken for the model, only the description of the image is left
+    let mut result = result;
+    assert!(result.take_image().is_some());
+    assert!(result.image().is_none());
+    let ToolResult::Success { metadata: Some(metadata), .. } = result else {
+        panic!("the result should keep its metadata");
+    };
+    assert_eq!(metadata["mime_type"], serde_json::json!("image/png"));
+    assert_eq!(metadata["size"], serde_json::json!(10));
+}
+
+#[tokio::test]
+async fn test_read_tool_offset_and_limit() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let test_file_path = temp_dir.path().join("long.txt");
+    let test_content = (1..=500).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n");
+    fs::write(&test_file_path, test_content).expect("Failed to write test file");
+
+    let read_tool = ReadTool::new(Arc::new(FsOperationLog::new()));
+    let result = read_tool.execute(ReadToolParams {
+        path: test_file_path.to_string_lossy().to_string(),
+        line_start: None,
+        line_end: None,
+        offset: Some(200),
+        limit: Some(60),
+        show_line_numbers: false,
+        byte_offset: None,
+    }, None).await;
+
+    let crate::tools::ToolResult::Success { output, .. } = result else {
+        panic!("Read tool should succeed, got: {}", result);
+    };
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 60);
+    assert_eq!(lines[0], " 200: line 200");
+    assert_eq!(lines[59], " 259: line 259");
+}
+
+#[tokio::test]
+async fn test_read_tool_caps_whole_file_reads() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let test_file_path = temp_dir.path().join("long.txt");
+    // 100 lines of 10 bytes each, newline included
+    let test_content = (1..=100).map(|i| format!("line {:04}", i)).collect::<Vec<_>>().join("\n");
+    fs::write(&test_file_path, test_content).expect("Failed to write test file");
+
+    let read_tool = ReadTool::with_max_bytes(Arc::new(FsOperationLog::new()), 105);
+    let result = read_tool.execute(ReadToolParams {
+        path: test_file_path.to_string_lossy().to_string(),
+        line_start: None,
+        line_end: None,
+        offset: None,
+        limit: None,
+        show_line_numbers: false,
+        byte_offset: None,
+    }, None).await;
+
+    let crate::tools::ToolResult::Success { output, metadata } = result else {
+        panic!("Read tool should succeed, got: {}", result);
+    };
+    assert!(output.starts_with("line 0001\n"), "{}", output);
+    assert!(output.contains("line 0010\n\n[output truncated at 105 bytes: 90 more lines not shown, continue with offset=11"), "{}", output);
+    assert!(!output.contains("line 0011"), "{}", output);
+    assert_eq!(metadata.unwrap()["truncated_lines"], 90);
+}
+
+#[tokio::test]
+async fn test_read_tool_cuts_a_line_too_long_inside() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let test_file_path = temp_dir.path().join("minified.js");
+    let long_line: String = (0..300).map(|i| char::from(b'a' + (i % 26) as u8)).collect();
+    fs::write(&test_file_path, format!("{}\nsecond line", long_line)).expect("Failed to write test file");
+
+    let read_tool = ReadTool::with_max_bytes(Arc::new(FsOperationLog::new()), 100);
+    let read = |offset: Option<u32>, byte_offset: Option<u32>| read_tool.execute(ReadToolParams {
+        path: test_file_path.to_string_lossy().to_string(),
+        line_start: None,
+        line_end: None,
+        offset,
+        limit: None,
+        show_line_numbers: false,
+        byte_offset,
+    }, None);
+
+    let output = read(None, None).await.to_string();
+    assert!(output.starts_with(&long_line[..100]), "{}", output);
+    assert!(output.contains("line 1 is too long, continue with offset=1 and byte_offset=100; 1 more lines not shown"), "{}", output);
+
+    // the continuation is prefixed with the line number, which takes room from the line
+    let output = read(Some(1), Some(100)).await.to_string();
+    assert!(output.starts_with(&format!("   1: {}", &long_line[100..194])), "{}", output);
+    assert!(output.contains("continue with offset=1 and byte_offset=194"), "{}", output);
+
+    let output = read(Some(1), Some(250)).await.to_string();
+    assert!(output.starts_with(&format!("   1: {}\n   2: second line", &long_line[250..])), "{}", output);
+    assert!(!output.contains("truncated"), "{}", output);
+}
@@ -9,7 +9,7 @@ mod integration_tests {
     use crate::tools::fs::{
         ls::structs::LsToolParams,
         find::structs::FindToolParams,
-        write::structs::WriteToolParams,
+        write::structs::{WriteMode, WriteToolParams},
         read::structs::ReadToolParams,
         edit::structs::EditToolParams,
         multiedit::structs::{MultiEditToolParams, EditOperation}
@@ -39,6 +39,7 @@ mod integration_tests {
             show_hidden: false,
             long_format: false,
             max_depth: None,
+            tree: false,
             max_files: None,
         }, None).await;
         assert!(ls_result.is_success());
@@ -48,6 +49,7 @@ mod integration_tests {
         let write_result = write_tool.execute(WriteToolParams {
             path: file_path.to_string_lossy().to_string(),
             content: "Hello, World!\nThis is a test file.".to_string(),
+            mode: WriteMode::Overwrite,
         }, None).await;
         assert!(write_result.is_success());
         
@@ -58,6 +60,7 @@ mod integration_tests {
             show_hidden: false,
             long_format: false,
             max_depth: None,
+            tree: false,
             max_files: None,
         }, None).await;
         assert!(ls_result.is_success());
@@ -70,7 +73,10 @@ mod integration_tests {
             path: file_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         assert!(read_result.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = read_result {
@@ -102,6 +108,7 @@ mod integration_tests {
                     replace_all: false,
                 },
             ],
+            continue_on_error: false,
         }, None).await;
         assert!(multiedit_result.is_success());
         
@@ -110,7 +117,10 @@ mod integration_tests {
             path: file_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         assert!(final_read.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = final_read {
@@ -143,11 +153,13 @@ mod integration_tests {
         let _ = write_tool.execute(WriteToolParams {
             path: file1_path.to_string_lossy().to_string(),
             content: "Content of file 1".to_string(),
+            mode: WriteMode::Overwrite,
         }, None).await;
         
         let _ = write_tool.execute(WriteToolParams {
             path: file2_path.to_string_lossy().to_string(),
             content: "Content of file 2".to_string(),
+            mode: WriteMode::Overwrite,
         }, None).await;
         
         // Try to edit file1 without reading it first - should fail
@@ -172,6 +184,7 @@ mod integration_tests {
                     replace_all: false,
                 },
             ],
+            continue_on_error: false,
         }, None).await;
         assert!(multiedit_result.is_error());
         if let crate::tools::types::ToolResult::Error { error, .. } = multiedit_result {
@@ -183,7 +196,10 @@ mod integration_tests {
             path: file1_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         
         let edit_result = edit_tool.execute(EditToolParams {
@@ -199,7 +215,10 @@ mod integration_tests {
             path: file2_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         
         let multiedit_result = multiedit_tool.execute(MultiEditToolParams {
@@ -211,6 +230,7 @@ mod integration_tests {
                     replace_all: false,
                 },
             ],
+            continue_on_error: false,
         }, None).await;
         assert!(multiedit_result.is_success());
     }
@@ -245,6 +265,7 @@ mod integration_tests {
             let write_result = write_tool.execute(WriteToolParams {
                 path: file_path.to_string_lossy().to_string(),
                 content: content.to_string(),
+                mode: WriteMode::Overwrite,
             }, None).await;
             assert!(write_result.is_success());
         }
@@ -256,6 +277,8 @@ mod integration_tests {
             include_extensions: Some("json".to_string()),
             exclude_patterns: None,
             max_results: 100,
+            respect_gitignore: true,
+            include_hidden: false,
             case_sensitive: false,
             find_type: crate::tools::fs::find::structs::FindType::Content,
             show_line_numbers: false,
@@ -270,7 +293,10 @@ mod integration_tests {
             path: config_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         assert!(read_result.is_success());
         
@@ -289,7 +315,10 @@ mod integration_tests {
             path: script_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         assert!(read_result.is_success());
         
@@ -306,7 +335,10 @@ mod integration_tests {
             path: config_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         assert!(final_config_read.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = final_config_read {
@@ -317,7 +349,10 @@ mod integration_tests {
             path: script_path.to_string_lossy().to_string(),
             line_start: None,
             line_end: None,
+            offset: None,
+            limit: None,
             show_line_numbers: false,
+            byte_offset: None,
         }, None).await;
         assert!(final_script_read.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = final_script_read {
@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod undo;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::UndoToolParams;
+pub use undo::UndoTool;
\ No newline at end of file
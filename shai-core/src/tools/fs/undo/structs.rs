@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct UndoToolParams {
+    /// Number of file changes to revert, the most recent first (defaults to 1)
+    #[serde(default = "default_count")]
+    pub count: usize,
+    /// Revert even the files changed since, losing those later changes (defaults to false)
+    #[serde(default)]
+    pub force: bool,
+}
+
+fn default_count() -> usize {
+    1
+}
@@ -0,0 +1,74 @@
+use super::structs::UndoToolParams;
+use super::undo::UndoTool;
+use crate::tools::fs::edit::EditToolParams;
+use crate::tools::fs::write::{WriteMode, WriteToolParams};
+use crate::tools::{EditTool, FsOperationLog, FsOperationType, Tool, ToolResult, WriteTool};
+use std::fs;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+fn output(result: ToolResult) -> String {
+    match result {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("expected success, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_undo_reverts_the_last_changes_newest_first() {
+    let dir = tempdir().unwrap();
+    let edited = dir.path().join("main.rs");
+    let created = dir.path().join("new.rs");
+    fs::write(&edited, "fn main() {}\n").unwrap();
+    let edited_path = edited.to_string_lossy().to_string();
+
+    let log = Arc::new(FsOperationLog::new());
+    log.log_operation(FsOperationType::Read, edited_path.clone()).await;
+    let edit = EditTool::new(log.clone());
+    let write = WriteTool::new(log.clone());
+    let undo = UndoTool::new(log.clone());
+
+    let params = EditToolParams { path: edited_path.clone(), old_string: "{}".to_string(), new_string: "{ run() }".to_string(), replace_all: false };
+    assert!(edit.execute(params, None).await.is_success());
+    let params = WriteToolParams { path: created.to_string_lossy().to_string(), content: "fn run() {}\n".to_string(), mode: WriteMode::Overwrite };
+    assert!(write.execute(params, None).await.is_success());
+
+    // the preview shows the diff and changes nothing
+    let preview = output(undo.execute_preview(UndoToolParams { count: 2, force: false }).await.unwrap());
+    assert!(preview.contains("-fn main() { run() }"), "{}", preview);
+    assert!(preview.contains("+fn main() {}"), "{}", preview);
+    assert!(created.exists());
+
+    let result = output(undo.execute(UndoToolParams { count: 1, force: false }, None).await);
+    assert!(result.starts_with("deleted"), "{}", result);
+    assert!(!created.exists());
+    assert_eq!(fs::read_to_string(&edited).unwrap(), "fn main() { run() }\n");
+
+    let result = output(undo.execute(UndoToolParams { count: 5, force: false }, None).await);
+    assert!(result.starts_with("restored"), "{}", result);
+    assert_eq!(fs::read_to_string(&edited).unwrap(), "fn main() {}\n");
+
+    assert!(undo.execute(UndoToolParams { count: 1, force: false }, None).await.is_error());
+}
+
+#[tokio::test]
+async fn test_undo_leaves_a_file_changed_since_unless_forced() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("notes.txt");
+    let path = file.to_string_lossy().to_string();
+
+    let log = Arc::new(FsOperationLog::new());
+    let write = WriteTool::new(log.clone());
+    let undo = UndoTool::new(log.clone());
+    let params = WriteToolParams { path: path.clone(), content: "agent\n".to_string(), mode: WriteMode::Overwrite };
+    assert!(write.execute(params, None).await.is_success());
+    fs::write(&file, "user\n").unwrap();
+
+    let result = undo.execute(UndoToolParams { count: 1, force: false }, None).await;
+    assert!(result.is_error(), "{:?}", result);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "user\n");
+
+    let result = output(undo.execute(UndoToolParams { count: 1, force: true }, None).await);
+    assert!(result.contains("the file was changed since"), "{}", result);
+    assert!(!file.exists());
+}
@@ -0,0 +1,57 @@
+use super::structs::UndoToolParams;
+use super::super::{FsOperationLog, UndoStep};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct UndoTool {
+    operation_log: Arc<FsOperationLog>,
+}
+
+impl UndoTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        Self { operation_log }
+    }
+
+    fn report(steps: &[UndoStep], preview: bool) -> ToolResult {
+        let mut sections: Vec<String> = steps.iter().map(|step| step.summary()).collect();
+        sections.extend(steps.iter().map(|step| step.diff()));
+
+        let mut meta = HashMap::new();
+        meta.insert("files".to_string(), json!(steps.iter().map(|step| &step.operation.file_path).collect::<Vec<_>>()));
+        meta.insert("reverted".to_string(), json!(steps.len()));
+        meta.insert("preview_mode".to_string(), json!(preview));
+
+        ToolResult::Success {
+            output: sections.join("\n"),
+            metadata: Some(meta),
+        }
+    }
+}
+
+#[tool(name = "undo", description = r#"Reverts the most recent file changes made with the `write`, `edit`, `multiedit` and `apply_patch` tools, newest first: each file gets back the content it had before the change, and files the change created are deleted.
+
+**Usage Guidelines:**
+- Use it when a change broke a file and restoring it is simpler than fixing it, then read the file again before editing it.
+- `count` is the number of changes to revert, one by default. Each tool call that changed a file is one change, an `apply_patch` on several files is one change per file.
+- A file changed since (by `bash`, the user or another tool) is not reverted, nothing is. Read it to see what changed, and only set `force` when losing those later changes is what you want.
+- Changes made with `bash` are not recorded and cannot be undone."#, capabilities = [ToolCapability::Write])]
+impl UndoTool {
+    async fn execute_preview(&self, params: UndoToolParams) -> Option<ToolResult> {
+        let steps = self.operation_log.undo_plan(params.count).await;
+        if steps.is_empty() {
+            return Some(ToolResult::error("there is no file change to undo".to_string()));
+        }
+        Some(Self::report(&steps, true))
+    }
+
+    async fn execute(&self, params: UndoToolParams) -> ToolResult {
+        match self.operation_log.undo(params.count, params.force).await {
+            Ok(steps) if steps.is_empty() => ToolResult::error("there is no file change to undo".to_string()),
+            Ok(steps) => Self::report(&steps, false),
+            Err(e) => ToolResult::error(format!("Undo failed: {}", e)),
+        }
+    }
+}
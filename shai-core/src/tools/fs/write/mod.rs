@@ -4,5 +4,5 @@ pub mod write;
 #[cfg(test)]
 mod tests;
 
-pub use structs::WriteToolParams;
+pub use structs::{WriteMode, WriteToolParams};
 pub use write::WriteTool;
\ No newline at end of file
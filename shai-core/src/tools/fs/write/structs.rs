@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
@@ -7,4 +7,18 @@ pub struct WriteToolParams {
     pub path: String,
     /// Content to write to the file
     pub content: String,
-}
\ No newline at end of file
+    /// How to write: "overwrite" (default) replaces the file, "append" adds the content at the end,
+    /// "create_new" fails if the file already exists
+    #[serde(default)]
+    pub mode: WriteMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[schemars(inline)]
+pub enum WriteMode {
+    #[default]
+    Overwrite,
+    Append,
+    CreateNew,
+}
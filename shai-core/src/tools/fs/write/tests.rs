@@ -1,6 +1,6 @@
-use super::structs::WriteToolParams;
+use super::structs::{WriteMode, WriteToolParams};
 use super::write::WriteTool;
-use crate::tools::{Tool, ToolCapability, FsOperationLog};
+use crate::tools::{Tool, ToolCapability, FsOperationLog, FsOperationType, ToolResult};
 use shai_llm::ToolDescription;
 use std::fs;
 use std::sync::Arc;
@@ -34,6 +34,7 @@ async fn test_write_new_file() {
     let params = WriteToolParams {
         path: file_path.to_string_lossy().to_string(),
         content: "Hello, World!".to_string(),
+        mode: WriteMode::Overwrite,
     };
 
     let result = tool.execute(params, None).await;
@@ -46,4 +47,99 @@ async fn test_write_new_file() {
     
     let content = fs::read_to_string(&file_path).unwrap();
     assert_eq!(content, "Hello, World!");
-}
\ No newline at end of file
+}
+
+fn params(path: &std::path::Path, content: &str, mode: WriteMode) -> WriteToolParams {
+    WriteToolParams {
+        path: path.to_string_lossy().to_string(),
+        content: content.to_string(),
+        mode,
+    }
+}
+
+#[test]
+fn test_write_mode_defaults_to_overwrite() {
+    let params: WriteToolParams = serde_json::from_str(r#"{"path": "a.txt", "content": "x"}"#).unwrap();
+    assert_eq!(params.mode, WriteMode::Overwrite);
+
+    let params: WriteToolParams = serde_json::from_str(r#"{"path": "a.txt", "content": "x", "mode": "create_new"}"#).unwrap();
+    assert_eq!(params.mode, WriteMode::CreateNew);
+}
+
+#[tokio::test]
+async fn test_write_overwrite_replaces_content() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    fs::write(&file_path, "old content").unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    let tool = WriteTool::new(log.clone());
+    let result = tool.execute(params(&file_path, "new", WriteMode::Overwrite), None).await;
+
+    assert!(result.is_success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "new");
+    assert_eq!(log.get_all_operations().await[0].operation_type, FsOperationType::Write);
+}
+
+#[tokio::test]
+async fn test_write_append_adds_to_the_end() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("app.log");
+    fs::write(&file_path, "line 1\n").unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    let tool = WriteTool::new(log.clone());
+
+    let preview = tool.execute_preview(params(&file_path, "line 2\n", WriteMode::Append)).await.unwrap();
+    if let ToolResult::Success { output, .. } = preview {
+        assert!(output.starts_with("... appending after the 7 existing bytes"), "{}", output);
+    } else {
+        panic!("Expected success preview");
+    }
+
+    let result = tool.execute(params(&file_path, "line 2\n", WriteMode::Append), None).await;
+    assert!(result.is_success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "line 1\nline 2\n");
+    assert_eq!(log.get_all_operations().await[0].operation_type, FsOperationType::Append);
+
+    // appending to a missing file creates it
+    let new_path = dir.path().join("new.log");
+    assert!(tool.execute(params(&new_path, "first", WriteMode::Append), None).await.is_success());
+    assert_eq!(fs::read_to_string(&new_path).unwrap(), "first");
+}
+
+#[tokio::test]
+async fn test_write_create_new() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("fresh.txt");
+
+    let log = Arc::new(FsOperationLog::new());
+    let tool = WriteTool::new(log.clone());
+    let result = tool.execute(params(&file_path, "fresh", WriteMode::CreateNew), None).await;
+
+    assert!(result.is_success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "fresh");
+    assert_eq!(log.get_all_operations().await[0].operation_type, FsOperationType::Create);
+}
+
+#[tokio::test]
+async fn test_write_create_new_refuses_existing_file() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("taken.txt");
+    fs::write(&file_path, "keep me").unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    let tool = WriteTool::new(log.clone());
+
+    let preview = tool.execute_preview(params(&file_path, "other", WriteMode::CreateNew)).await.unwrap();
+    assert!(!preview.is_success());
+
+    let result = tool.execute(params(&file_path, "other", WriteMode::CreateNew), None).await;
+    if let ToolResult::Error { error, .. } = result {
+        assert!(error.contains("already exists"), "{}", error);
+    } else {
+        panic!("Expected error result");
+    }
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "keep me");
+    assert!(log.get_all_operations().await.is_empty());
+}
@@ -1,10 +1,11 @@
-use super::structs::WriteToolParams;
-use super::super::{FsOperationLog, FsOperationType};
+use super::structs::{WriteMode, WriteToolParams};
+use super::super::{FileBackup, FsOperationLog, FsOperationType};
 use crate::tools::{ToolResult, tool};
 //use crate::tools::highlight::highlight_content;
 use serde_json::json;
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -31,18 +32,44 @@ impl WriteTool {
             }
         }
 
-        // Write content to file (overwrites if exists)
-        fs::write(path, &params.content).map_err(|e| e.to_string())?;
-
-        let action = if file_existed { "updated" } else { "created" };
+        // create_new relies on the OS to refuse an existing file, so a concurrent writer cannot slip in
+        match params.mode {
+            WriteMode::Overwrite => fs::write(path, &params.content),
+            WriteMode::Append => OpenOptions::new().create(true).append(true).open(path)
+                .and_then(|mut file| file.write_all(params.content.as_bytes())),
+            WriteMode::CreateNew => OpenOptions::new().write(true).create_new(true).open(path)
+                .and_then(|mut file| file.write_all(params.content.as_bytes())),
+        }.map_err(|e| match e.kind() {
+            ErrorKind::AlreadyExists => format!("file '{}' already exists, use mode \"overwrite\" to replace it", params.path),
+            _ => e.to_string(),
+        })?;
+
+        let action = match (params.mode, file_existed) {
+            (WriteMode::Append, true) => "appended to",
+            (_, true) => "updated",
+            (_, false) => "created",
+        };
         
         Ok(format!("Successfully {} file '{}' with {} bytes", 
                   action, params.path, params.content.len()))
     }
+
+    fn operation_type(mode: WriteMode) -> FsOperationType {
+        match mode {
+            WriteMode::Overwrite => FsOperationType::Write,
+            WriteMode::Append => FsOperationType::Append,
+            WriteMode::CreateNew => FsOperationType::Create,
+        }
+    }
 }
 
 #[tool(name = "write", description = r#"Creates a new file with specified content or completely overwrites an existing file. This tool should be used with caution.
 
+**Modes**
+- `overwrite` (default): replace the whole file, creating it if needed.
+- `append`: add the content at the end of the file, creating it if needed. Include the leading newline yourself if the file does not end with one.
+- `create_new`: create the file, failing if it already exists.
+
 **Guidelines**
 - To overwrite an existing file, you must first have read it with the `read` tool. This is a safety measure to ensure you are aware of the content being replaced.
 - This tool is primarily for creating new files when explicitly instructed. For modifying existing files, the `edit` or `multiedit` tools are the correct choice.
@@ -52,29 +79,48 @@ impl WriteTool {
     async fn execute_preview(&self, params: WriteToolParams) -> Option<ToolResult> {
         //let highlighted_content = highlight_content(&params.content, &params.path);
 
+        let existing_size = fs::metadata(&params.path).ok().map(|m| m.len());
+        if params.mode == WriteMode::CreateNew && existing_size.is_some() {
+            return Some(ToolResult::error(format!("file '{}' already exists, use mode \"overwrite\" to replace it", params.path)));
+        }
+
         let mut metadata = HashMap::new();
         metadata.insert("path".to_string(), json!(params.path));
+        metadata.insert("mode".to_string(), json!(params.mode));
         metadata.insert("content_length".to_string(), json!(params.content.len()));
         metadata.insert("line_count".to_string(), json!(params.content.lines().count()));
         metadata.insert("operation".to_string(), json!("write_preview"));
 
+        // appended content is shown after a marker of what it is appended to
+        let output = match (params.mode, existing_size) {
+            (WriteMode::Append, Some(size)) => format!("... appending after the {} existing bytes\n{}", size, params.content),
+            _ => params.content,
+        };
+
         Some(ToolResult::Success {
-            output: params.content,
+            output,
             metadata: Some(metadata),
         })
     }
 
     async fn execute(&self, params: WriteToolParams) -> ToolResult {
+        // a file that is not text is written without a backup, the write cannot be undone
+        let backup = FileBackup::capture(&params.path).ok();
         match self.perform_write(&params) {
             Ok(message) => {
                 // Log the write operation
-                self.operation_log.log_operation(FsOperationType::Write, params.path.clone()).await;
+                let operation = Self::operation_type(params.mode);
+                match backup {
+                    Some(backup) => self.operation_log.log_change(operation, params.path.clone(), backup).await,
+                    None => self.operation_log.log_operation(operation, params.path.clone()).await,
+                }
 
                 let output = format!("{}\n{}", message, params.content);
                 let mut meta = HashMap::new();
                 meta.insert("path".to_string(), json!(params.path));
                 meta.insert("content_length".to_string(), json!(params.content.len()));
                 meta.insert("operation".to_string(), json!("write"));
+                meta.insert("mode".to_string(), json!(params.mode));
 
                 // Add file size information
                 if let Ok(metadata) = std::fs::metadata(&params.path) {
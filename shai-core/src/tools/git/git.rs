@@ -0,0 +1,210 @@
+use super::structs::{GitCommand, GitToolParams};
+use crate::tools::{tool, ToolCapability, ToolResult};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Commits shown by log when the call doesn't set max_count
+pub const DEFAULT_GIT_LOG_COUNT: u32 = 20;
+
+pub struct GitTool;
+
+impl GitTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// git arguments of a call, or why the call is incomplete
+    fn arguments(params: &GitToolParams) -> Result<Vec<String>, String> {
+        let paths = || -> Vec<String> {
+            if params.paths.is_empty() {
+                vec![]
+            } else {
+                std::iter::once("--".to_string()).chain(params.paths.iter().cloned()).collect()
+            }
+        };
+
+        let mut args: Vec<String> = match params.command {
+            GitCommand::Status => vec!["status".into(), "--porcelain=v1".into(), "--branch".into()],
+            GitCommand::Diff if params.staged => vec!["diff".into(), "--staged".into()],
+            GitCommand::Diff => vec!["diff".into()],
+            GitCommand::Add if params.paths.is_empty() => return Err("add needs the paths to stage".to_string()),
+            GitCommand::Add => vec!["add".into()],
+            GitCommand::Commit => match params.message.as_deref().map(str::trim) {
+                Some(message) if !message.is_empty() => vec!["commit".into(), "-m".into(), message.to_string()],
+                _ => return Err("commit needs a message".to_string()),
+            },
+            GitCommand::Log => vec![
+                "log".into(),
+                "--format=%h %ad %an: %s".into(),
+                "--date=short".into(),
+                format!("--max-count={}", params.max_count.unwrap_or(DEFAULT_GIT_LOG_COUNT)),
+            ],
+            GitCommand::Branch => match &params.name {
+                // git would read it as an option
+                Some(name) if name.starts_with('-') => return Err(format!("branch name {} can't start with '-'", name)),
+                Some(name) if params.checkout => vec!["switch".into(), "-c".into(), name.clone()],
+                Some(name) => vec!["branch".into(), name.clone()],
+                None => vec!["branch".into(), "--list".into()],
+            },
+        };
+        if matches!(params.command, GitCommand::Diff | GitCommand::Add | GitCommand::Log) {
+            args.extend(paths());
+        }
+        Ok(args)
+    }
+
+    /// Run git, returning its stdout, or its stderr when it fails
+    async fn run(args: &[String], cwd: Option<&str>, cancel_token: Option<CancellationToken>) -> Result<String, String> {
+        let mut cmd = Command::new("git");
+        cmd.args(["--no-pager", "-c", "color.ui=false"]).args(args);
+        if let Some(cwd) = cwd {
+            if !Path::new(cwd).is_dir() {
+                return Err(format!("Working directory does not exist: {}", cwd));
+            }
+            cmd.current_dir(cwd);
+        }
+        // never wait for credentials or an editor nobody will see
+        cmd.env("GIT_TERMINAL_PROMPT", "0").env("GIT_EDITOR", "true");
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+
+        let cancelled = async {
+            match cancel_token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+        let output = tokio::select! {
+            output = cmd.output() => output.map_err(|e| format!("Failed to run git: {}", e))?,
+            _ = cancelled => return Err("git was cancelled".to_string()),
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        if output.status.success() {
+            Ok(stdout)
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = if stderr.trim().is_empty() { stdout.trim() } else { stderr.trim() };
+            Err(format!("git {} failed: {}", args[0], reason))
+        }
+    }
+
+    /// What the model reads for the output of a successful call
+    fn describe(params: &GitToolParams, output: &str) -> String {
+        match params.command {
+            GitCommand::Status => format_status(output),
+            GitCommand::Diff if output.trim().is_empty() => {
+                format!("No {} changes", if params.staged { "staged" } else { "unstaged" })
+            }
+            GitCommand::Add => format!("Staged {}", params.paths.join(", ")),
+            GitCommand::Log if output.trim().is_empty() => "No commits".to_string(),
+            GitCommand::Branch if output.trim().is_empty() => match &params.name {
+                Some(name) if params.checkout => format!("Created branch {} and switched to it", name),
+                Some(name) => format!("Created branch {}", name),
+                None => "No branches yet".to_string(),
+            },
+            _ => output.trim_end().to_string(),
+        }
+    }
+}
+
+/// Group `git status --porcelain=v1 --branch` lines by state
+pub fn format_status(porcelain: &str) -> String {
+    let mut branch = None;
+    let mut conflicts = Vec::new();
+    let mut staged = Vec::new();
+    let mut unstaged = Vec::new();
+    let mut untracked = Vec::new();
+
+    for line in porcelain.lines() {
+        if let Some(head) = line.strip_prefix("## ") {
+            branch = Some(head.to_string());
+            continue;
+        }
+        let (Some(code), Some(path)) = (line.get(..2), line.get(3..)) else {
+            continue;
+        };
+        let (index, worktree) = (code.as_bytes()[0] as char, code.as_bytes()[1] as char);
+        match (index, worktree) {
+            ('?', '?') => untracked.push(path.to_string()),
+            ('!', '!') => {}
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => conflicts.push(path.to_string()),
+            _ => {
+                if index != ' ' {
+                    staged.push(format!("{}: {}", change_name(index), path));
+                }
+                if worktree != ' ' {
+                    unstaged.push(format!("{}: {}", change_name(worktree), path));
+                }
+            }
+        }
+    }
+
+    let mut status = match branch {
+        Some(branch) => format!("On branch {}", branch),
+        None => "Not on a branch".to_string(),
+    };
+    for (title, entries) in [("Conflicts", conflicts), ("Staged", staged), ("Unstaged", unstaged), ("Untracked", untracked)] {
+        if !entries.is_empty() {
+            status.push_str(&format!("\n{}:\n  {}", title, entries.join("\n  ")));
+        }
+    }
+    if status.lines().count() == 1 {
+        status.push_str("\nWorking tree clean");
+    }
+    status
+}
+
+fn change_name(code: char) -> &'static str {
+    match code {
+        'M' => "modified",
+        'A' => "added",
+        'D' => "deleted",
+        'R' => "renamed",
+        'C' => "copied",
+        'T' => "type changed",
+        _ => "changed",
+    }
+}
+
+#[tool(name = "git", description = r#"
+Runs common git operations on the repository: status, diff, add, commit, log and branch.
+Prefer it to running git through bash: status, diff, log and listing branches never need the user's permission, only add, commit and creating a branch do.
+
+Usage Guidelines:
+- Check `status` and `diff` before committing, and commit only what the user asked for.
+- `add` stages the given `paths`, `commit` commits the staged changes with `message`.
+- `diff` shows the unstaged changes, set `staged` to see what the next commit contains. `paths` limits diff and log to some files.
+- `branch` lists the branches, with `name` it creates one, and switches to it with `checkout`.
+- Never rewrite history (rebase, reset, force push), ask the user to do it instead.
+"#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
+impl GitTool {
+    fn call_capabilities(&self, params: &GitToolParams) -> &'static [ToolCapability] {
+        match params.command {
+            GitCommand::Status | GitCommand::Diff | GitCommand::Log => &[ToolCapability::Read],
+            GitCommand::Branch if params.name.is_none() => &[ToolCapability::Read],
+            GitCommand::Add | GitCommand::Commit | GitCommand::Branch => &[ToolCapability::Read, ToolCapability::Write],
+        }
+    }
+
+    async fn execute(&self, params: GitToolParams, cancel_token: Option<CancellationToken>) -> ToolResult {
+        let args = match Self::arguments(&params) {
+            Ok(args) => args,
+            Err(e) => return ToolResult::error(e),
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("command".to_string(), json!(format!("git {}", args.join(" "))));
+        if let Some(cwd) = &params.cwd {
+            metadata.insert("cwd".to_string(), json!(cwd));
+        }
+
+        match Self::run(&args, params.cwd.as_deref(), cancel_token).await {
+            Ok(output) => ToolResult::success_with_metadata(Self::describe(&params, &output), metadata),
+            Err(error) => ToolResult::error_with_metadata(error, metadata),
+        }
+    }
+}
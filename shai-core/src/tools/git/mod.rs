@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod git;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{GitCommand, GitToolParams};
+pub use git::GitTool;
@@ -0,0 +1,47 @@
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+/// The git operations the tool runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GitCommand {
+    /// Current branch, staged, unstaged and untracked files
+    Status,
+    /// Unstaged changes, or the staged ones with `staged`
+    Diff,
+    /// Stage `paths`
+    Add,
+    /// Commit the staged changes with `message`
+    Commit,
+    /// Latest commits
+    Log,
+    /// List the branches, or create `name`
+    Branch,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct GitToolParams {
+    /// The git operation to run
+    pub command: GitCommand,
+    /// Files to stage (add), or to limit diff and log to (optional)
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Show the staged changes instead of the unstaged ones (diff only)
+    #[serde(default)]
+    pub staged: bool,
+    /// Commit message (commit only)
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Branch to create, the branches are listed without it (branch only)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Switch to the new branch once created (branch only)
+    #[serde(default)]
+    pub checkout: bool,
+    /// How many commits to show (log only, defaults to 20)
+    #[serde(default)]
+    pub max_count: Option<u32>,
+    /// Repository directory (optional, defaults to the current directory)
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
@@ -0,0 +1,130 @@
+use super::git::{format_status, GitTool};
+use super::structs::{GitCommand, GitToolParams};
+use crate::tools::{AnyTool, Tool, ToolCapability, ToolResult};
+use shai_llm::ToolDescription;
+use serde_json::json;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn params(command: GitCommand, cwd: &TempDir) -> GitToolParams {
+    GitToolParams {
+        command,
+        paths: vec![],
+        staged: false,
+        message: None,
+        name: None,
+        checkout: false,
+        max_count: None,
+        cwd: Some(cwd.path().to_string_lossy().to_string()),
+    }
+}
+
+fn repo() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for args in [
+        vec!["init", "--quiet", "--initial-branch=main"],
+        vec!["config", "user.name", "Test"],
+        vec!["config", "user.email", "test@example.com"],
+    ] {
+        let status = Command::new("git").args(&args).current_dir(dir.path()).status().unwrap();
+        assert!(status.success());
+    }
+    dir
+}
+
+fn output(result: ToolResult) -> String {
+    match result {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("expected success, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_git_tool_permissions() {
+    let tool = GitTool::new();
+    assert_eq!(&tool.name(), "git");
+    assert_eq!(Tool::capabilities(&tool), &[ToolCapability::Read, ToolCapability::Write]);
+
+    let read = |command: &str| AnyTool::call_capabilities(&tool, &json!({"command": command})).to_vec();
+    assert_eq!(read("status"), vec![ToolCapability::Read]);
+    assert_eq!(read("diff"), vec![ToolCapability::Read]);
+    assert_eq!(read("log"), vec![ToolCapability::Read]);
+    assert_eq!(read("branch"), vec![ToolCapability::Read]);
+    assert!(read("commit").contains(&ToolCapability::Write));
+    assert!(read("add").contains(&ToolCapability::Write));
+    assert!(AnyTool::call_capabilities(&tool, &json!({"command": "branch", "name": "feature"})).contains(&ToolCapability::Write));
+
+    // parameters that don't parse keep every capability of the tool
+    assert!(AnyTool::call_capabilities(&tool, &json!({"command": "push"})).contains(&ToolCapability::Write));
+}
+
+#[tokio::test]
+async fn test_git_tool_add_commit_log() {
+    let dir = repo();
+    let tool = GitTool::new();
+    std::fs::write(dir.path().join("notes.txt"), "hello\n").unwrap();
+
+    let status = output(Tool::execute(&tool, params(GitCommand::Status, &dir), None).await);
+    assert!(status.contains("On branch"), "{}", status);
+    assert!(status.contains("Untracked:\n  notes.txt"), "{}", status);
+
+    let mut add = params(GitCommand::Add, &dir);
+    add.paths = vec!["notes.txt".to_string()];
+    assert_eq!(output(Tool::execute(&tool, add, None).await), "Staged notes.txt");
+
+    let mut diff = params(GitCommand::Diff, &dir);
+    diff.staged = true;
+    assert!(output(Tool::execute(&tool, diff, None).await).contains("+hello"));
+
+    let mut commit = params(GitCommand::Commit, &dir);
+    commit.message = Some("Add notes".to_string());
+    assert!(Tool::execute(&tool, commit, None).await.is_success());
+
+    let log = output(Tool::execute(&tool, params(GitCommand::Log, &dir), None).await);
+    assert!(log.contains("Test: Add notes"), "{}", log);
+
+    let status = output(Tool::execute(&tool, params(GitCommand::Status, &dir), None).await);
+    assert!(status.ends_with("Working tree clean"), "{}", status);
+}
+
+#[tokio::test]
+async fn test_git_tool_rejects_incomplete_calls() {
+    let dir = repo();
+    let tool = GitTool::new();
+
+    let result = Tool::execute(&tool, params(GitCommand::Commit, &dir), None).await;
+    assert!(matches!(result, ToolResult::Error { ref error, .. } if error.contains("message")), "{:?}", result);
+
+    let result = Tool::execute(&tool, params(GitCommand::Add, &dir), None).await;
+    assert!(matches!(result, ToolResult::Error { ref error, .. } if error.contains("paths")), "{:?}", result);
+
+    // git's own error reaches the model
+    let mut add = params(GitCommand::Add, &dir);
+    add.paths = vec!["missing.txt".to_string()];
+    let result = Tool::execute(&tool, add, None).await;
+    assert!(matches!(result, ToolResult::Error { ref error, .. } if error.contains("missing.txt")), "{:?}", result);
+}
+
+#[tokio::test]
+async fn test_git_tool_rejects_branch_names_read_as_options() {
+    let dir = repo();
+    let tool = GitTool::new();
+
+    for checkout in [false, true] {
+        let mut branch = params(GitCommand::Branch, &dir);
+        branch.name = Some("--delete".to_string());
+        branch.checkout = checkout;
+        let result = Tool::execute(&tool, branch, None).await;
+        assert!(matches!(result, ToolResult::Error { ref error, .. } if error.contains("can't start with '-'")), "{:?}", result);
+    }
+}
+
+#[test]
+fn test_format_status_groups_changes() {
+    let status = format_status("## main...origin/main [ahead 1]\nM  src/lib.rs\n M README.md\nMM src/main.rs\nUU Cargo.lock\n?? notes.txt\n");
+    assert_eq!(status, "On branch main...origin/main [ahead 1]\n\
+        Conflicts:\n  Cargo.lock\n\
+        Staged:\n  modified: src/lib.rs\n  modified: src/main.rs\n\
+        Unstaged:\n  modified: README.md\n  modified: src/main.rs\n\
+        Untracked:\n  notes.txt");
+}
@@ -1,15 +1,34 @@
 use async_trait::async_trait;
+use serde_json::json;
 use shai_llm::ToolDescription;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tracing::warn;
 
 use crate::tools::{ToolResult, ToolCall, AnyTool, ToolCapability};
 
+/// Times a tool call reconnects its server after a transport error before giving up
+pub const MCP_RECONNECT_ATTEMPTS: u32 = 3;
+
+/// Metadata of a result whose call had to reconnect its server: `{"server": ..., "attempts": ...}`
+pub const MCP_RECONNECT_METADATA_KEY: &str = "mcp_reconnect";
+
+/// Separator between the server and the tool in the name of a prefixed MCP tool
+pub const MCP_TOOL_SEPARATOR: &str = "__";
+
+/// Name an MCP tool is registered under when its server prefixes its tools: `<mcp_name>__<tool>`
+pub fn prefixed_tool_name(mcp_name: &str, tool_name: &str) -> String {
+    format!("{}{}{}", mcp_name, MCP_TOOL_SEPARATOR, tool_name)
+}
+
 #[derive(Debug, Clone)]
 pub struct McpToolDescription {
     pub name: String,
     pub description: String,
     pub parameters_schema: serde_json::Value,
+    /// the server annotates the tool with `readOnlyHint`, it does not modify its environment
+    pub read_only: bool,
 }
 
 #[async_trait]
@@ -18,17 +37,51 @@ pub trait McpClient: Send + Sync {
     async fn disconnect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
     async fn list_tools(&self) -> Result<Vec<McpToolDescription>, Box<dyn std::error::Error + Send + Sync>>;
     async fn execute_tool(&self, tool_call: ToolCall) -> Result<ToolResult, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Drop the current connection, whatever state it is in, and connect again
+    async fn reconnect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = self.disconnect().await;
+        self.connect().await
+    }
+}
+
+/// Whether an error means the connection to the server is gone, rather than the server
+/// answering the call with an error. A call that timed out may still be running on the
+/// server, so it is never taken for a lost connection and run again
+pub fn is_connection_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    if let Some(error) = error.downcast_ref::<rmcp::ServiceError>() {
+        return matches!(error, rmcp::ServiceError::TransportSend(_) | rmcp::ServiceError::TransportClosed);
+    }
+    if let Some(error) = error.downcast_ref::<std::io::Error>() {
+        return error.kind() != std::io::ErrorKind::TimedOut;
+    }
+    error.to_string() == "Not connected"
+}
+
+/// Server a call reconnected to and the attempts it took, from the metadata of its result
+pub fn mcp_reconnect(result: &ToolResult) -> Option<(String, u32)> {
+    let (ToolResult::Success { metadata: Some(metadata), .. } | ToolResult::Error { metadata: Some(metadata), .. }) = result else {
+        return None;
+    };
+    let reconnect = metadata.get(MCP_RECONNECT_METADATA_KEY)?;
+    Some((reconnect.get("server")?.as_str()?.to_string(), reconnect.get("attempts")?.as_u64()? as u32))
 }
 
 pub struct WrappedMcpTool {
     pub desc: McpToolDescription,
     pub client: Arc<Mutex<Box<dyn McpClient>>>,
     pub mcp_name: String,
+    /// Registered as `<mcp_name>__<tool>`, the server is still called with its own name
+    pub prefixed: bool,
 }
 
 impl ToolDescription for WrappedMcpTool {
     fn name(&self) -> String {
-        self.desc.name.clone()
+        if self.prefixed {
+            prefixed_tool_name(&self.mcp_name, &self.desc.name)
+        } else {
+            self.desc.name.clone()
+        }
     }
 
     fn description(&self) -> String {
@@ -44,12 +97,30 @@ impl ToolDescription for WrappedMcpTool {
     }
 }
 
+impl WrappedMcpTool {
+    /// Record in the result that the call reconnected its server, so the agent can tell the user
+    fn with_reconnect(&self, mut result: ToolResult, attempts: u32, reconnected: bool) -> ToolResult {
+        if let (true, ToolResult::Success { metadata, .. } | ToolResult::Error { metadata, .. }) = (reconnected, &mut result) {
+            metadata.get_or_insert_with(HashMap::new).insert(
+                MCP_RECONNECT_METADATA_KEY.to_string(),
+                json!({ "server": self.mcp_name, "attempts": attempts }),
+            );
+        }
+        result
+    }
+}
+
 #[async_trait]
 impl AnyTool for WrappedMcpTool {
     fn capabilities(&self) -> &[ToolCapability] {
         &[ToolCapability::Network]
     }
 
+    /// what an MCP tool does is only known from its server, unannotated tools may write
+    fn is_read_only(&self) -> bool {
+        self.desc.read_only
+    }
+
     async fn execute_json(&self, params: serde_json::Value, cancel_token: Option<tokio_util::sync::CancellationToken>) -> ToolResult {
         let tool_call = ToolCall {
             tool_call_id: format!("mcp-{}", uuid::Uuid::new_v4()),
@@ -59,11 +130,35 @@ impl AnyTool for WrappedMcpTool {
 
         // Lock the client for execution
         // right now we only do one call at a time per mcp server to avoid race condition
-        let client = self.client.lock().await;
+        let mut client = self.client.lock().await;
+
+        let mut attempts = 0;
+        let mut reconnected = false;
+        loop {
+            let error = match client.execute_tool(tool_call.clone()).await {
+                Ok(result) => return self.with_reconnect(result, attempts, reconnected),
+                Err(e) if attempts < MCP_RECONNECT_ATTEMPTS && is_connection_error(e.as_ref()) => e,
+                Err(e) => {
+                    let result = ToolResult::error(format!("MCP tool execution failed: {}", e));
+                    return self.with_reconnect(result, attempts, reconnected);
+                }
+            };
 
-        match client.execute_tool(tool_call).await {
-            Ok(result) => result,
-            Err(e) => ToolResult::error(format!("MCP tool execution failed: {}", e)),
+            // the server crashed or restarted, the same call is tried again on a new connection
+            attempts += 1;
+            warn!(target: "mcp", "connection to {} lost, reconnecting (attempt {}): {}", self.mcp_name, attempts, error);
+            tokio::time::sleep(std::time::Duration::from_millis(500 * attempts as u64)).await;
+            match client.reconnect().await {
+                Ok(()) => reconnected = true,
+                Err(e) if attempts < MCP_RECONNECT_ATTEMPTS => {
+                    warn!(target: "mcp", "reconnection to {} failed (attempt {}): {}", self.mcp_name, attempts, e);
+                }
+                Err(e) => {
+                    return ToolResult::error(format!(
+                        "MCP tool execution failed: connection to {} lost ({}) and {} reconnection attempts failed: {}",
+                        self.mcp_name, error, attempts, e));
+                }
+            }
         }
     }
 
@@ -72,8 +167,8 @@ impl AnyTool for WrappedMcpTool {
     }
 }
 
-/// Create AnyTool instances from an MCP client
-pub async fn get_mcp_tools(mut client: Box<dyn McpClient>, mcp_name: &str) -> Result<Vec<Box<dyn AnyTool>>, Box<dyn std::error::Error + Send + Sync>> {
+/// Create AnyTool instances from an MCP client, named `<mcp_name>__<tool>` when `prefixed`
+pub async fn get_mcp_tools(mut client: Box<dyn McpClient>, mcp_name: &str, prefixed: bool) -> Result<Vec<Box<dyn AnyTool>>, Box<dyn std::error::Error + Send + Sync>> {
     // Auto-connect if not already connected
     client.connect().await?;
     
@@ -87,6 +182,7 @@ pub async fn get_mcp_tools(mut client: Box<dyn McpClient>, mcp_name: &str) -> Re
                 desc,
                 client: client_ref.clone(),
                 mcp_name: mcp_name.to_string(),
+                prefixed,
             }) as Box<dyn AnyTool>
         })
         .collect();
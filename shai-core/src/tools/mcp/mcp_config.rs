@@ -50,8 +50,7 @@ pub fn create_mcp_client(config: McpConfig) -> Box<dyn McpClient> {
             Box::new(StdioClient::new(command, args))
         }
         McpConfig::Http { url, auth } => {
-            let bearer_token = auth.map(|t| t.access_token);
-            Box::new(HttpClient::new_with_auth(url, bearer_token))
+            Box::new(HttpClient::new_with_oauth(url, auth))
         }
         McpConfig::Sse { url } => {
             Box::new(SseClient::new(url))
@@ -9,10 +9,12 @@ use std::borrow::Cow;
 
 use crate::tools::{ToolResult, ToolCall};
 use super::mcp::{McpClient, McpToolDescription};
+use super::mcp_config::OAuthToken;
+use super::mcp_oauth::signin_oauth;
 
 pub struct HttpClient {
     url: String,
-    bearer_token: Option<String>,
+    auth: Option<OAuthToken>,
     service: Option<RunningService<RoleClient, InitializeRequestParam>>,
 }
 
@@ -22,9 +24,14 @@ impl HttpClient {
     }
 
     pub fn new_with_auth(url: String, bearer_token: Option<String>) -> Self {
+        Self::new_with_oauth(url, bearer_token.map(|access_token| OAuthToken { access_token, expires_at: None }))
+    }
+
+    /// Client of a server signed in with OAuth, which signs in again when reconnecting with an expired or rejected token
+    pub fn new_with_oauth(url: String, auth: Option<OAuthToken>) -> Self {
         Self {
             url,
-            bearer_token,
+            auth,
             service: None,
         }
     }
@@ -38,12 +45,12 @@ impl McpClient for HttpClient {
             return Ok(());
         }
         
-        let transport = if let Some(token) = &self.bearer_token {
+        let transport = if let Some(token) = &self.auth {
             // Create a custom reqwest client with default bearer token
             let mut default_headers = reqwest::header::HeaderMap::new();
             default_headers.insert(
                 reqwest::header::AUTHORIZATION,
-                reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))?
+                reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token.access_token))?
             );
             let client = reqwest::Client::builder()
                 .default_headers(default_headers)
@@ -84,6 +91,25 @@ impl McpClient for HttpClient {
         Ok(())
     }
 
+    async fn reconnect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _ = self.disconnect().await;
+        match &self.auth {
+            None => return self.connect().await,
+            Some(token) if !token.is_expired() => {
+                if self.connect().await.is_ok() {
+                    return Ok(());
+                }
+            }
+            Some(_) => {}
+        }
+
+        // the token expired, or the restarted server doesn't accept it anymore
+        let token = signin_oauth(&self.url).await
+            .map_err(|e| format!("signing in to {} again failed: {}", self.url, e))?;
+        self.auth = Some(token);
+        self.connect().await
+    }
+
     async fn list_tools(&self) -> Result<Vec<McpToolDescription>, Box<dyn std::error::Error + Send + Sync>> {
         let service = self.service.as_ref().ok_or("Not connected")?;
         let tools_result = service.list_tools(None).await?;
@@ -95,6 +121,7 @@ impl McpClient for HttpClient {
                 name: tool.name.to_string(),
                 description: tool.description.unwrap_or_default().to_string(),
                 parameters_schema: serde_json::Value::Object((*tool.input_schema).clone()),
+                read_only: tool.annotations.as_ref().and_then(|a| a.read_only_hint).unwrap_or(false),
             })
             .collect();
         
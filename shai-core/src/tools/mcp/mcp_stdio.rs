@@ -63,6 +63,7 @@ impl McpClient for StdioClient {
                 name: tool.name.to_string(),
                 description: tool.description.unwrap_or_default().to_string(),
                 parameters_schema: serde_json::Value::Object((*tool.input_schema).clone()),
+                read_only: tool.annotations.as_ref().and_then(|a| a.read_only_hint).unwrap_or(false),
             })
             .collect();
         
@@ -8,7 +8,7 @@ pub mod mcp_oauth;
 #[cfg(test)]
 mod tests;
 
-pub use mcp::{McpClient, McpToolDescription, get_mcp_tools};
+pub use mcp::{McpClient, McpToolDescription, get_mcp_tools, mcp_reconnect, prefixed_tool_name, MCP_RECONNECT_ATTEMPTS, MCP_TOOL_SEPARATOR};
 pub use mcp_config::{McpConfig, OAuthToken, create_mcp_client};
 pub use mcp_stdio::StdioClient;
 pub use mcp_http::HttpClient;
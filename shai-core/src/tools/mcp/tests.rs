@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
     use crate::tools::{StdioClient, HttpClient, SseClient, McpClient, McpConfig, create_mcp_client};
-    use crate::tools::ToolCall;
+    use crate::tools::{get_mcp_tools, AnyTool, mcp_reconnect, McpToolDescription, ToolCall, ToolResult};
+    use crate::tools::mcp::mcp::{is_connection_error, MCP_RECONNECT_ATTEMPTS, MCP_RECONNECT_METADATA_KEY};
     use serde_json::json;
     use std::process::Command;
     use tokio;
@@ -310,4 +311,123 @@ mod tests {
             Err(e) => println!("❌ Failed to disconnect: {}", e),
         }
     }
-}
\ No newline at end of file
+
+    /// Client whose connection drops before each of the first `drops` calls
+    struct FlakyClient {
+        drops: u32,
+        connected: bool,
+        connects: std::sync::Arc<std::sync::atomic::AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl McpClient for FlakyClient {
+        async fn connect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.connects.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.connected = true;
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.connected = false;
+            Ok(())
+        }
+
+        async fn list_tools(&self) -> Result<Vec<McpToolDescription>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(vec![McpToolDescription {
+                name: "echo".to_string(),
+                description: "Echo the text".to_string(),
+                parameters_schema: json!({"type": "object"}),
+                read_only: true,
+            }])
+        }
+
+        async fn execute_tool(&self, tool_call: ToolCall) -> Result<ToolResult, Box<dyn std::error::Error + Send + Sync>> {
+            assert_eq!(tool_call.tool_name, "echo");
+            let dropped = self.connects.load(std::sync::atomic::Ordering::SeqCst) <= self.drops;
+            if !self.connected || dropped {
+                return Err("Not connected".into());
+            }
+            Ok(ToolResult::success(tool_call.parameters["text"].as_str().unwrap_or_default().to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mcp_tool_reconnects_after_a_dropped_connection() {
+        let connects = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let client = FlakyClient { drops: 2, connected: false, connects: connects.clone() };
+        let tools = get_mcp_tools(Box::new(client), "flaky", false).await.unwrap();
+
+        let result = tools[0].execute_json(json!({"text": "hello"}), None).await;
+        assert_eq!(result, ToolResult::Success {
+            output: "hello".to_string(),
+            metadata: Some([(MCP_RECONNECT_METADATA_KEY.to_string(), json!({"server": "flaky", "attempts": 2}))].into()),
+        });
+        assert_eq!(mcp_reconnect(&result), Some(("flaky".to_string(), 2)));
+        assert_eq!(connects.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // a server that never comes back fails the call once the attempts are spent
+        let client = FlakyClient { drops: u32::MAX, connected: false, connects: std::sync::Arc::default() };
+        let tools = get_mcp_tools(Box::new(client), "down", false).await.unwrap();
+        let result = tools[0].execute_json(json!({"text": "hello"}), None).await;
+        assert!(matches!(result, ToolResult::Error { ref error, .. } if error.contains("Not connected")), "{:?}", result);
+        assert_eq!(mcp_reconnect(&result), Some(("down".to_string(), MCP_RECONNECT_ATTEMPTS)));
+    }
+
+    /// Client whose calls all time out, counting them
+    struct SlowClient {
+        calls: std::sync::Arc<std::sync::atomic::AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl McpClient for SlowClient {
+        async fn connect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        async fn list_tools(&self) -> Result<Vec<McpToolDescription>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(vec![McpToolDescription {
+                name: "deploy".to_string(),
+                description: "Deploy the app".to_string(),
+                parameters_schema: json!({"type": "object"}),
+                read_only: false,
+            }])
+        }
+
+        async fn execute_tool(&self, _tool_call: ToolCall) -> Result<ToolResult, Box<dyn std::error::Error + Send + Sync>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(Box::new(rmcp::ServiceError::Timeout { timeout: std::time::Duration::from_secs(30) }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mcp_tool_call_that_timed_out_is_not_run_again() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let tools = get_mcp_tools(Box::new(SlowClient { calls: calls.clone() }), "slow", false).await.unwrap();
+
+        let result = tools[0].execute_json(json!({}), None).await;
+        assert!(result.is_error(), "{:?}", result);
+        assert_eq!(mcp_reconnect(&result), None);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        assert!(is_connection_error(&rmcp::ServiceError::TransportClosed));
+        assert!(!is_connection_error(&std::io::Error::from(std::io::ErrorKind::TimedOut)));
+        assert!(is_connection_error(&std::io::Error::from(std::io::ErrorKind::BrokenPipe)));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_tools_can_be_prefixed() {
+        let client = FlakyClient { drops: 0, connected: false, connects: std::sync::Arc::default() };
+        let tools = get_mcp_tools(Box::new(client), "notes", true).await.unwrap();
+        assert_eq!(tools[0].name(), "notes__echo");
+        assert_eq!(tools[0].group(), Some("notes"));
+        assert!(tools[0].is_read_only(), "the server annotates echo with readOnlyHint");
+
+        // the server is still called with its own name
+        let result = tools[0].execute_json(json!({"text": "hello"}), None).await;
+        assert_eq!(result, ToolResult::success("hello".to_string()));
+    }
+}
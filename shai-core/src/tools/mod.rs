@@ -4,17 +4,29 @@ pub mod todo;
 pub mod fs;
 pub mod fetch;
 pub mod bash;
+pub mod git;
+pub mod env;
 pub mod mcp;
+pub mod fixture;
+pub mod semantic;
+pub mod normalize;
+pub mod output_limit;
 
 #[cfg(test)]
 mod tests_llm;
 
 pub use shai_macros::tool;
-pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolEmptyParams};
+pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolEmptyParams, DURATION_METADATA_KEY};
+pub use normalize::{normalize_arguments, Coercion};
+pub use output_limit::{ToolOutputLimit, DEFAULT_TOOL_OUTPUT_MAX_BYTES, OUTPUT_BYTES_METADATA_KEY};
 
 // Re-export all tools
-pub use bash::BashTool;
-pub use fetch::FetchTool;
-pub use fs::{EditTool, FindTool, LsTool, MultiEditTool, ReadTool, WriteTool, FsOperationLog, FsOperationType, FsOperation, FsOperationSummary};
+pub use bash::{BashTool, BashConfig, BashJobs, BashStatusTool};
+pub use git::GitTool;
+pub use env::EnvTool;
+pub use fetch::{FetchTool, DEFAULT_FETCH_MAX_BYTES};
+pub use fs::{ApplyPatchTool, EditTool, FindTool, GrepTool, LsTool, MultiEditTool, ReadTool, UndoTool, WriteTool, DEFAULT_READ_MAX_BYTES, FsOperationLog, FsOperationType, FsOperation, FsOperationSummary, FileBackup, UndoStep};
 pub use todo::{TodoReadTool, TodoWriteTool, TodoStorage, TodoItem, TodoStatus, TodoWriteParams, TodoItemInput};
-pub use mcp::{McpClient, McpToolDescription, McpConfig, create_mcp_client, get_mcp_tools, StdioClient, HttpClient, SseClient};
+pub use semantic::SemanticSearchTool;
+pub use fixture::{FixtureToolBox, MockTool, RecordedCall};
+pub use mcp::{McpClient, McpToolDescription, McpConfig, create_mcp_client, get_mcp_tools, mcp_reconnect, prefixed_tool_name, StdioClient, HttpClient, SseClient};
@@ -0,0 +1,234 @@
+use std::fmt;
+
+use serde_json::{Map, Number, Value};
+
+/// A tool argument rewritten to the type its schema expects
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coercion {
+    /// location of the argument, e.g. `edits[0].replace_all`
+    pub path: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+impl fmt::Display for Coercion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.path, self.from, self.to)
+    }
+}
+
+/// Fix the type mismatches LLMs commonly make in tool arguments, against the tool's JSON schema:
+/// numbers and booleans sent as strings ("5", "true"), scalars sent where a string is expected,
+/// arrays or objects sent as a JSON string, and a single item sent without its array.
+/// Only values matching none of the schema types are touched, and only when the schema
+/// names a single target type, so valid arguments are always returned unchanged.
+pub fn normalize_arguments(schema: &Value, mut params: Value) -> (Value, Vec<Coercion>) {
+    let mut coercions = Vec::new();
+    normalize(schema, schema, &mut params, String::new(), &mut coercions);
+    (params, coercions)
+}
+
+fn normalize(root: &Value, schema: &Value, value: &mut Value, path: String, coercions: &mut Vec<Coercion>) {
+    let schema = resolve(root, schema);
+
+    // Option<T> of a struct shows up as anyOf [T, null]
+    if let Some(branches) = schema.get("anyOf").or_else(|| schema.get("oneOf")).and_then(Value::as_array) {
+        let non_null: Vec<&Value> = branches.iter()
+            .map(|branch| resolve(root, branch))
+            .filter(|branch| branch.get("type").and_then(Value::as_str) != Some("null"))
+            .collect();
+        if let [branch] = non_null.as_slice() {
+            if !value.is_null() {
+                normalize(root, branch, value, path, coercions);
+            }
+        }
+        return;
+    }
+
+    let expected: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let accepts_null = expected.contains(&"null");
+    let expected: Vec<&str> = expected.into_iter().filter(|t| *t != "null").collect();
+
+    if let [target] = expected.as_slice() {
+        let already_valid = matches_type(value, target) || (value.is_null() && accepts_null);
+        if !already_valid {
+            if let Some(coerced) = coerce(root, schema, value, target) {
+                coercions.push(Coercion {
+                    path: if path.is_empty() { "(arguments)".to_string() } else { path.clone() },
+                    from: value.clone(),
+                    to: coerced.clone(),
+                });
+                *value = coerced;
+            }
+        }
+    }
+
+    match value {
+        Value::Object(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional = schema.get("additionalProperties").filter(|s| s.is_object());
+            for (name, field) in fields.iter_mut() {
+                let field_schema = properties.and_then(|p| p.get(name)).or(additional);
+                if let Some(field_schema) = field_schema {
+                    let field_path = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+                    normalize(root, field_schema, field, field_path, coercions);
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items").filter(|s| s.is_object()) {
+                for (index, item) in items.iter_mut().enumerate() {
+                    normalize(root, item_schema, item, format!("{}[{}]", path, index), coercions);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Follow a local `$ref` (`#/$defs/...` or `#/definitions/...`)
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    schema.get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+        .unwrap_or(schema)
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        // unknown type keyword, nothing to compare against
+        _ => true,
+    }
+}
+
+/// The value converted to `expected`, None when the conversion would be a guess
+fn coerce(root: &Value, schema: &Value, value: &Value, expected: &str) -> Option<Value> {
+    match (expected, value) {
+        ("integer", Value::String(s)) => {
+            let s = s.trim();
+            s.parse::<i64>().map(Value::from).ok()
+                .or_else(|| s.parse::<u64>().map(Value::from).ok())
+        }
+        ("integer", Value::Number(n)) => {
+            // 5.0 -> 5, 5.5 stays a float and fails where it would have failed anyway
+            n.as_f64()
+                .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+                .map(|f| Value::from(f as i64))
+        }
+        ("number", Value::String(s)) => {
+            let s = s.trim();
+            s.parse::<i64>().map(Value::from).ok()
+                .or_else(|| s.parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number))
+        }
+        ("boolean", Value::String(s)) => match s.trim().to_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        ("array", Value::String(s)) if s.trim_start().starts_with('[') => {
+            serde_json::from_str::<Vec<Value>>(s).ok().map(Value::Array)
+        }
+        ("object", Value::String(s)) if s.trim_start().starts_with('{') => {
+            serde_json::from_str::<Map<String, Value>>(s).ok().map(Value::Object)
+        }
+        ("array", Value::Null) => None,
+        ("array", single) => {
+            // a lone item where a list is expected, only when the item has the item type
+            let item_schema = resolve(root, schema.get("items")?);
+            let item_type = item_schema.get("type").and_then(Value::as_str)?;
+            matches_type(single, item_type).then(|| Value::Array(vec![single.clone()]))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use serde_json::json;
+    use shai_llm::ToolDescription;
+    use crate::tools::{BashTool, FsOperationLog, MultiEditTool, ReadTool, TodoWriteTool, TodoStorage};
+
+    #[test]
+    fn test_valid_arguments_are_untouched() {
+        let schema = BashTool::new().parameters_schema();
+        let params = json!({"command": "ls", "timeout": 5, "env": {"A": "1"}});
+        let (normalized, coercions) = normalize_arguments(&schema, params.clone());
+        assert_eq!(normalized, params);
+        assert!(coercions.is_empty());
+    }
+
+    #[test]
+    fn test_numbers_and_booleans_sent_as_strings() {
+        let schema = ReadTool::new(Arc::new(FsOperationLog::new())).parameters_schema();
+        let (normalized, coercions) = normalize_arguments(&schema, json!({
+            "path": "src/main.rs",
+            "line_start": "10",
+            "line_end": 20.0,
+            "show_line_numbers": "True"
+        }));
+        assert_eq!(normalized, json!({"path": "src/main.rs", "line_start": 10, "line_end": 20, "show_line_numbers": true}));
+        assert_eq!(coercions.len(), 3, "{:?}", coercions);
+        assert_eq!(coercions.iter().find(|c| c.path == "line_start").unwrap().to_string(), r#"line_start: "10" -> 10"#);
+    }
+
+    #[test]
+    fn test_ambiguous_values_are_left_alone() {
+        let schema = ReadTool::new(Arc::new(FsOperationLog::new())).parameters_schema();
+        let params = json!({"path": "a.txt", "line_start": "ten", "show_line_numbers": "yes"});
+        let (normalized, coercions) = normalize_arguments(&schema, params.clone());
+        assert_eq!(normalized, params);
+        assert!(coercions.is_empty());
+    }
+
+    #[test]
+    fn test_scalars_sent_for_strings() {
+        let schema = BashTool::new().parameters_schema();
+        let (normalized, _) = normalize_arguments(&schema, json!({"command": 42, "env": {"DEBUG": true}}));
+        assert_eq!(normalized, json!({"command": "42", "env": {"DEBUG": "true"}}));
+    }
+
+    #[test]
+    fn test_nested_items_and_missing_wrappers() {
+        let schema = MultiEditTool::new(Arc::new(FsOperationLog::new())).parameters_schema();
+
+        // a single edit without its array, with a stringified boolean inside
+        let (normalized, coercions) = normalize_arguments(&schema, json!({
+            "file_path": "a.rs",
+            "edits": {"old_string": "a", "new_string": "b", "replace_all": "false"}
+        }));
+        assert_eq!(normalized["edits"], json!([{"old_string": "a", "new_string": "b", "replace_all": false}]));
+        assert_eq!(coercions.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(), vec!["edits", "edits[0].replace_all"]);
+
+        // the whole array sent as a JSON string
+        let (normalized, _) = normalize_arguments(&schema, json!({
+            "file_path": "a.rs",
+            "edits": r#"[{"old_string": "a", "new_string": "b"}]"#
+        }));
+        assert_eq!(normalized["edits"], json!([{"old_string": "a", "new_string": "b"}]));
+    }
+
+    #[test]
+    fn test_normalized_arguments_deserialize() {
+        let tool = TodoWriteTool::new(Arc::new(TodoStorage::new()));
+        let (normalized, _) = normalize_arguments(&tool.parameters_schema(), json!({
+            "todos": r#"[{"content": "write tests", "status": "pending"}]"#
+        }));
+        assert!(serde_json::from_value::<crate::tools::TodoWriteParams>(normalized).is_ok());
+    }
+}
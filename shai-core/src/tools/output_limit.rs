@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::ToolResult;
+
+/// Largest tool output kept in the trace by default, in bytes
+pub const DEFAULT_TOOL_OUTPUT_MAX_BYTES: usize = 128 * 1024;
+
+/// Metadata key of the size of an output before it was cut, only set on the outputs that were
+pub const OUTPUT_BYTES_METADATA_KEY: &str = "output_bytes";
+
+/// Size of the tool outputs that go into the trace: longer ones lose their middle, the start
+/// and the end of an output (the command, the error at the bottom) being what the model needs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolOutputLimit {
+    /// Limit of every tool, 0 keeps whole outputs
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+    /// Limit of some tools by name, over `max_bytes`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub per_tool: HashMap<String, usize>,
+}
+
+fn default_max_bytes() -> usize {
+    DEFAULT_TOOL_OUTPUT_MAX_BYTES
+}
+
+impl Default for ToolOutputLimit {
+    fn default() -> Self {
+        Self { max_bytes: DEFAULT_TOOL_OUTPUT_MAX_BYTES, per_tool: HashMap::new() }
+    }
+}
+
+impl ToolOutputLimit {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Limit of `tool_name`, 0 is none
+    pub fn for_tool(&self, tool_name: &str) -> usize {
+        self.per_tool.get(tool_name).copied().unwrap_or(self.max_bytes)
+    }
+
+    /// Cut the output (or error) of a result of `tool_name` over its limit, recording its size in the metadata
+    pub fn apply(&self, tool_name: &str, result: &mut ToolResult) {
+        let max_bytes = self.for_tool(tool_name);
+        if let ToolResult::Success { output: text, metadata } | ToolResult::Error { error: text, metadata } = result {
+            let original = text.len();
+            if let Some(truncated) = truncate_middle(text, max_bytes) {
+                *text = truncated;
+                metadata.get_or_insert_with(HashMap::new)
+                    .insert(OUTPUT_BYTES_METADATA_KEY.to_string(), serde_json::json!(original));
+            }
+        }
+    }
+}
+
+/// `text` without its middle so that it fits in about `max_bytes`, None when it already fits.
+/// The cut goes to the nearest line break when there is one close by
+pub fn truncate_middle(text: &str, max_bytes: usize) -> Option<String> {
+    if max_bytes == 0 || text.len() <= max_bytes {
+        return None;
+    }
+    let half = max_bytes / 2;
+
+    let mut head_end = half;
+    while !text.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    if let Some(newline) = text[..head_end].rfind('\n').filter(|&newline| newline >= half / 2) {
+        head_end = newline + 1;
+    }
+
+    let mut tail_start = text.len() - half;
+    while !text.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    if let Some(newline) = text[tail_start..].find('\n').filter(|&newline| newline < half / 2) {
+        tail_start += newline + 1;
+    }
+
+    Some(format!(
+        "{}\n[... {} bytes truncated ...]\n{}",
+        text[..head_end].trim_end_matches('\n'),
+        tail_start - head_end,
+        &text[tail_start..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_outputs_are_kept() {
+        assert_eq!(truncate_middle("hello", 10), None);
+        assert_eq!(truncate_middle(&"x".repeat(1000), 0), None);
+    }
+
+    #[test]
+    fn test_the_middle_is_cut_at_line_breaks() {
+        let text: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+        let truncated = truncate_middle(&text, 200).unwrap();
+
+        assert!(truncated.starts_with("line 0\nline 1\n"));
+        assert!(truncated.ends_with("line 999\n"));
+        assert!(truncated.contains(" bytes truncated ...]\nline 9"), "{}", truncated);
+        assert!(truncated.len() < 260, "{}", truncated.len());
+        assert!(truncated.lines().all(|line| line.starts_with("line ") || line.starts_with("[... ")));
+    }
+
+    #[test]
+    fn test_a_single_long_line_is_cut_on_char_boundaries() {
+        let text = "é".repeat(1000);
+        let truncated = truncate_middle(&text, 101).unwrap();
+        assert!(truncated.contains("[... 1900 bytes truncated ...]"), "{}", truncated);
+    }
+
+    #[test]
+    fn test_limits_by_tool() {
+        let limit = ToolOutputLimit { max_bytes: 100, per_tool: HashMap::from([("read".to_string(), 0)]) };
+        let output = "x".repeat(500);
+
+        let mut result = ToolResult::success(output.clone());
+        limit.apply("bash", &mut result);
+        let ToolResult::Success { output: cut, metadata } = &result else { panic!() };
+        assert!(cut.len() < 150);
+        assert_eq!(metadata.as_ref().unwrap()[OUTPUT_BYTES_METADATA_KEY], serde_json::json!(500));
+
+        let mut result = ToolResult::error(output.clone());
+        limit.apply("bash", &mut result);
+        assert!(result.to_string().contains("bytes truncated"));
+
+        // the read tool keeps its whole output
+        let mut result = ToolResult::success(output.clone());
+        limit.apply("read", &mut result);
+        assert_eq!(result, ToolResult::success(output));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+use shai_llm::{LlmClient, provider::LlmError};
+
+use super::structs::CodeChunkMatch;
+
+/// Number of lines per indexed chunk
+pub const CHUNK_LINES: usize = 40;
+/// Upper bounds on what gets indexed, to keep memory and embedding cost in check
+pub const MAX_INDEXED_FILES: usize = 2_000;
+pub const MAX_INDEXED_CHUNKS: usize = 10_000;
+pub const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+const IGNORED_DIRS: &[&str] = &[
+    ".git", "target", "node_modules", "dist", "build", "vendor",
+    "__pycache__", ".venv", "venv", ".idea", ".vscode", ".shai",
+];
+
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "jsx", "ts", "tsx", "go", "java", "kt", "c", "h", "cc", "cpp", "hpp",
+    "cs", "rb", "php", "swift", "scala", "sh", "bash", "zsh", "lua", "sql",
+    "toml", "yaml", "yml", "json", "md", "html", "css",
+];
+
+#[derive(Debug, Clone)]
+struct IndexedChunk {
+    line_start: u32,
+    line_end: u32,
+    content: String,
+    vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedFile {
+    modified: SystemTime,
+    chunks: Vec<IndexedChunk>,
+}
+
+/// What changed during a refresh
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RefreshStats {
+    pub files_indexed: usize,
+    pub files_removed: usize,
+    pub chunks_embedded: usize,
+}
+
+/// In-memory vector store over the source files of a project.
+/// Files are re-embedded only when their modification time changes.
+#[derive(Debug)]
+pub struct SemanticIndex {
+    root: PathBuf,
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+impl SemanticIndex {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root, files: HashMap::new() }
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.files.values().map(|f| f.chunks.len()).sum()
+    }
+
+    /// List the indexable files under root with their modification time
+    pub fn scan(&self) -> Vec<(PathBuf, SystemTime)> {
+        WalkDir::new(&self.root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !is_ignored(e.path(), e.file_type().is_dir()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && has_source_extension(e.path()))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                if meta.len() > MAX_FILE_BYTES {
+                    return None;
+                }
+                Some((e.path().to_path_buf(), meta.modified().ok()?))
+            })
+            .take(MAX_INDEXED_FILES)
+            .collect()
+    }
+
+    /// Bring the index in sync with the filesystem, embedding new and modified files only
+    pub async fn refresh(&mut self, llm: &LlmClient, model: &str) -> Result<RefreshStats, LlmError> {
+        let mut stats = RefreshStats::default();
+        let current: HashMap<PathBuf, SystemTime> = self.scan().into_iter().collect();
+
+        // forget files that disappeared or were pushed out of the bounded scan
+        let before = self.files.len();
+        self.files.retain(|path, _| current.contains_key(path));
+        stats.files_removed = before - self.files.len();
+
+        let mut stale: Vec<(PathBuf, SystemTime)> = current.into_iter()
+            .filter(|(path, modified)| self.files.get(path).map_or(true, |f| f.modified != *modified))
+            .collect();
+        stale.sort();
+
+        let mut budget = MAX_INDEXED_CHUNKS.saturating_sub(
+            self.files.iter()
+                .filter(|(path, _)| !stale.iter().any(|(p, _)| p == *path))
+                .map(|(_, f)| f.chunks.len())
+                .sum()
+        );
+
+        for (path, modified) in stale {
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                self.files.remove(&path);
+                continue;
+            };
+
+            let chunks = chunk_content(&content);
+            if chunks.len() > budget {
+                self.files.remove(&path);
+                continue;
+            }
+            budget -= chunks.len();
+
+            let display = self.display_path(&path);
+            let inputs: Vec<String> = chunks.iter()
+                .map(|(start, end, text)| format!("{}:{}-{}\n{}", display, start, end, text))
+                .collect();
+            let vectors = if inputs.is_empty() {
+                Vec::new()
+            } else {
+                llm.embed(model, inputs).await?.embeddings
+            };
+
+            stats.chunks_embedded += vectors.len();
+            stats.files_indexed += 1;
+            self.files.insert(path, IndexedFile {
+                modified,
+                chunks: chunks.into_iter().zip(vectors)
+                    .map(|((line_start, line_end, content), vector)| IndexedChunk { line_start, line_end, content, vector })
+                    .collect(),
+            });
+        }
+
+        Ok(stats)
+    }
+
+    /// Rank every chunk against the query vector by cosine similarity
+    pub fn search(&self, query: &[f32], top_k: usize) -> Vec<CodeChunkMatch> {
+        let mut matches: Vec<CodeChunkMatch> = self.files.iter()
+            .flat_map(|(path, file)| file.chunks.iter().map(move |chunk| (path, chunk)))
+            .map(|(path, chunk)| CodeChunkMatch {
+                path: self.display_path(path),
+                line_start: chunk.line_start,
+                line_end: chunk.line_end,
+                score: cosine_similarity(query, &chunk.vector),
+                content: chunk.content.clone(),
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        matches.truncate(top_k);
+        matches
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+fn is_ignored(path: &Path, is_dir: bool) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    is_dir && (name.starts_with('.') || IGNORED_DIRS.contains(&&*name))
+}
+
+fn has_source_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| SOURCE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Split content in fixed-size line windows, skipping blank chunks.
+/// Returns 1-based inclusive line ranges.
+pub fn chunk_content(content: &str) -> Vec<(u32, u32, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    lines.chunks(CHUNK_LINES)
+        .enumerate()
+        .filter(|(_, chunk)| chunk.iter().any(|l| !l.trim().is_empty()))
+        .map(|(i, chunk)| {
+            let start = i * CHUNK_LINES + 1;
+            (start as u32, (start + chunk.len() - 1) as u32, chunk.join("\n"))
+        })
+        .collect()
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
@@ -0,0 +1,10 @@
+pub mod structs;
+pub mod index;
+pub mod semantic;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{SemanticSearchToolParams, CodeChunkMatch};
+pub use index::SemanticIndex;
+pub use semantic::SemanticSearchTool;
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use serde_json::json;
+use shai_llm::LlmClient;
+use tokio::sync::Mutex;
+
+use crate::tools::{tool, ToolResult};
+use super::index::SemanticIndex;
+use super::structs::SemanticSearchToolParams;
+
+/// Semantic code search over an embedding index of the project.
+/// The index lives as long as the tool, so it is built once per session and
+/// refreshed incrementally on every query.
+pub struct SemanticSearchTool {
+    llm: Arc<LlmClient>,
+    embedding_model: String,
+    index: Mutex<SemanticIndex>,
+}
+
+impl SemanticSearchTool {
+    pub fn new(llm: Arc<LlmClient>, embedding_model: String) -> Self {
+        let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self::with_root(llm, embedding_model, root)
+    }
+
+    pub fn with_root(llm: Arc<LlmClient>, embedding_model: String, root: PathBuf) -> Self {
+        Self {
+            llm,
+            embedding_model,
+            index: Mutex::new(SemanticIndex::new(root)),
+        }
+    }
+
+    /// Build the tool from SHAI_EMBEDDING_MODEL, if the provider can embed at all
+    pub fn from_env(llm: Arc<LlmClient>) -> Option<Self> {
+        if !llm.supports_embeddings() {
+            return None;
+        }
+        std::env::var("SHAI_EMBEDDING_MODEL").ok()
+            .filter(|m| !m.is_empty())
+            .map(|model| Self::new(llm, model))
+    }
+}
+
+#[tool(name = "semantic_search", description = r#"Search the codebase by meaning rather than by exact text.
+
+**Usage:**
+- Describe what you are looking for in natural language, e.g. 'where are http sessions persisted to disk'.
+- Returns the most relevant code chunks with their file path and line range, best match first.
+- Prefer `find` when you know an exact identifier or string; use this tool when you only know the intent.
+
+**Notes:**
+- The project is indexed on first use, later calls only re-index files that changed.
+- Build output, dependencies and hidden directories are not indexed."#, capabilities = [ToolCapability::Read])]
+impl SemanticSearchTool {
+    async fn execute(&self, params: SemanticSearchToolParams) -> ToolResult {
+        let mut meta = HashMap::new();
+        meta.insert("query".to_string(), json!(params.query));
+        meta.insert("top_k".to_string(), json!(params.top_k));
+
+        if params.query.trim().is_empty() {
+            return ToolResult::error_with_metadata("query must not be empty".to_string(), meta);
+        }
+
+        let mut index = self.index.lock().await;
+        match index.refresh(&self.llm, &self.embedding_model).await {
+            Ok(stats) => {
+                meta.insert("files_reindexed".to_string(), json!(stats.files_indexed));
+                meta.insert("indexed_files".to_string(), json!(index.file_count()));
+                meta.insert("indexed_chunks".to_string(), json!(index.chunk_count()));
+            }
+            Err(e) => {
+                return ToolResult::error_with_metadata(format!("Failed to index the project: {}", e), meta);
+            }
+        }
+
+        let query_vector = match self.llm.embed(&self.embedding_model, vec![params.query.clone()]).await {
+            Ok(response) => response.embeddings.into_iter().next().unwrap_or_default(),
+            Err(e) => {
+                return ToolResult::error_with_metadata(format!("Failed to embed the query: {}", e), meta);
+            }
+        };
+
+        let matches = index.search(&query_vector, params.top_k.max(1) as usize);
+        meta.insert("results_count".to_string(), json!(matches.len()));
+
+        ToolResult::success_with_metadata(
+            serde_json::to_string_pretty(&matches).unwrap_or_default(),
+            meta,
+        )
+    }
+}
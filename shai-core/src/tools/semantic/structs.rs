@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SemanticSearchToolParams {
+    /// Natural-language description of the code you are looking for
+    pub query: String,
+    /// Number of code chunks to return (defaults to 5)
+    #[serde(default = "default_top_k")]
+    pub top_k: u32,
+}
+
+fn default_top_k() -> u32 { 5 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeChunkMatch {
+    pub path: String,
+    pub line_start: u32,
+    pub line_end: u32,
+    pub score: f32,
+    pub content: String,
+}
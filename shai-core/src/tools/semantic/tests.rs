@@ -0,0 +1,165 @@
+use super::index::{chunk_content, cosine_similarity, SemanticIndex, CHUNK_LINES};
+use super::semantic::SemanticSearchTool;
+use super::structs::{CodeChunkMatch, SemanticSearchToolParams};
+use crate::tools::{Tool, ToolCapability};
+use async_trait::async_trait;
+use openai_dive::v1::resources::chat::{ChatCompletionParameters, ChatCompletionResponse};
+use openai_dive::v1::resources::model::ListModelResponse;
+use shai_llm::provider::{LlmError, LlmProvider, LlmStream, ProviderInfo};
+use shai_llm::{EmbeddingResponse, LlmClient, ToolDescription};
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const DIMENSIONS: usize = 64;
+
+/// Deterministic bag-of-words embedder, counts how many texts it embedded
+struct BagOfWords {
+    embedded: Arc<AtomicUsize>,
+}
+
+fn bag_of_words(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0; DIMENSIONS];
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| w.len() > 2) {
+        let bucket = word.to_lowercase().bytes().fold(7usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+        vector[bucket % DIMENSIONS] += 1.0;
+    }
+    vector
+}
+
+#[async_trait]
+impl LlmProvider for BagOfWords {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        Err("no models".into())
+    }
+
+    async fn chat(&self, _request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        Err("no chat".into())
+    }
+
+    async fn chat_stream(&self, _request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        Err("no chat".into())
+    }
+
+    fn supports_functions(&self, _model: String) -> bool { false }
+
+    fn supports_structured_output(&self, _model: String) -> bool { false }
+
+    fn supports_embeddings(&self) -> bool { true }
+
+    async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        self.embedded.fetch_add(inputs.len(), Ordering::SeqCst);
+        Ok(EmbeddingResponse {
+            model,
+            embeddings: inputs.iter().map(|i| bag_of_words(i)).collect(),
+            usage: None,
+        })
+    }
+
+    fn name(&self) -> &'static str { "bag_of_words" }
+
+    fn info() -> ProviderInfo where Self: Sized {
+        ProviderInfo { name: "bag_of_words", display_name: "Bag of words", env_vars: vec![] }
+    }
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("target/debug")).unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+
+    fs::write(root.join("src/session.rs"), "fn persist_session_to_disk(session: &Session) {\n    write_session_file(session);\n}\n").unwrap();
+    fs::write(root.join("src/color.rs"), "fn gradient_color(theme: Theme) -> Color {\n    blend_theme_color(theme)\n}\n").unwrap();
+    fs::write(root.join("target/debug/session.rs"), "fn persist_session_to_disk() {}\n").unwrap();
+    fs::write(root.join(".git/config"), "persist session disk").unwrap();
+    fs::write(root.join("notes.bin"), "persist session disk").unwrap();
+    temp_dir
+}
+
+fn tool_for(root: &std::path::Path, embedded: Arc<AtomicUsize>) -> SemanticSearchTool {
+    let llm = Arc::new(LlmClient::from_provider(Box::new(BagOfWords { embedded })));
+    SemanticSearchTool::with_root(llm, "bow".to_string(), root.to_path_buf())
+}
+
+#[test]
+fn test_semantic_search_tool_creation() {
+    let temp_dir = TempDir::new().unwrap();
+    let tool = tool_for(temp_dir.path(), Arc::new(AtomicUsize::new(0)));
+    assert_eq!(&tool.name(), "semantic_search");
+    assert!(!tool.description().is_empty());
+    assert_eq!(tool.capabilities(), &[ToolCapability::Read]);
+}
+
+#[test]
+fn test_chunk_content_ranges() {
+    let content: String = (1..=CHUNK_LINES + 5).map(|i| format!("line {}\n", i)).collect();
+    let chunks = chunk_content(&content);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!((chunks[0].0, chunks[0].1), (1, CHUNK_LINES as u32));
+    assert_eq!((chunks[1].0, chunks[1].1), (CHUNK_LINES as u32 + 1, CHUNK_LINES as u32 + 5));
+    assert!(chunk_content("\n\n   \n").is_empty());
+}
+
+#[test]
+fn test_cosine_similarity() {
+    assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+    assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+    assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+}
+
+#[test]
+fn test_scan_skips_ignored_directories() {
+    let project = create_project();
+    let index = SemanticIndex::new(project.path().to_path_buf());
+    let mut files: Vec<String> = index.scan().into_iter()
+        .map(|(p, _)| p.strip_prefix(project.path()).unwrap().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(files, vec!["src/color.rs".to_string(), "src/session.rs".to_string()]);
+}
+
+#[tokio::test]
+async fn test_semantic_search_ranks_relevant_chunk_first() {
+    let project = create_project();
+    let tool = tool_for(project.path(), Arc::new(AtomicUsize::new(0)));
+
+    let result = tool.execute(SemanticSearchToolParams {
+        query: "persist the session to disk".to_string(),
+        top_k: 1,
+    }, None).await;
+    assert!(result.is_success(), "{:?}", result);
+
+    let matches: Vec<CodeChunkMatch> = serde_json::from_str(&result.to_string()).unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].path, "src/session.rs");
+    assert_eq!((matches[0].line_start, matches[0].line_end), (1, 3));
+}
+
+#[tokio::test]
+async fn test_index_is_cached_and_updated_incrementally() {
+    let project = create_project();
+    let embedded = Arc::new(AtomicUsize::new(0));
+    let tool = tool_for(project.path(), embedded.clone());
+    let params = SemanticSearchToolParams { query: "theme color".to_string(), top_k: 3 };
+
+    tool.execute(params.clone(), None).await;
+    // two chunks plus the query
+    assert_eq!(embedded.load(Ordering::SeqCst), 3);
+
+    tool.execute(params.clone(), None).await;
+    // nothing changed, only the query is embedded again
+    assert_eq!(embedded.load(Ordering::SeqCst), 4);
+
+    // a new file gets indexed on the next query, a deleted one disappears
+    fs::write(project.path().join("src/theme.rs"), "fn theme_color() {}\n").unwrap();
+    fs::remove_file(project.path().join("src/color.rs")).unwrap();
+    let result = tool.execute(params, None).await;
+    assert_eq!(embedded.load(Ordering::SeqCst), 6);
+
+    let matches: Vec<CodeChunkMatch> = serde_json::from_str(&result.to_string()).unwrap();
+    assert!(matches.iter().any(|m| m.path == "src/theme.rs"));
+    assert!(matches.iter().all(|m| m.path != "src/color.rs"));
+}
@@ -20,6 +20,36 @@ impl TodoStorage {
     pub async fn replace_all(&self, items: Vec<TodoItem>) {
         *self.store.write().await = items;
     }
+
+    /// Append a pending item and return it
+    pub async fn add(&self, content: String) -> TodoItem {
+        let now = chrono::Utc::now().to_rfc3339();
+        let item = TodoItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            content,
+            status: TodoStatus::Pending,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        self.store.write().await.push(item.clone());
+        item
+    }
+
+    /// Change the status of an item, None if no item has this id
+    pub async fn set_status(&self, id: &str, status: TodoStatus) -> Option<TodoItem> {
+        let mut store = self.store.write().await;
+        let item = store.iter_mut().find(|item| item.id == id)?;
+        item.status = status;
+        item.updated_at = chrono::Utc::now().to_rfc3339();
+        Some(item.clone())
+    }
+
+    /// Remove an item, None if no item has this id
+    pub async fn remove(&self, id: &str) -> Option<TodoItem> {
+        let mut store = self.store.write().await;
+        let index = store.iter().position(|item| item.id == id)?;
+        Some(store.remove(index))
+    }
 }
 
 
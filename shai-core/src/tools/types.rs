@@ -7,6 +7,13 @@ use tokio_util::sync::CancellationToken;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Metadata key of the image a tool result carries for the model, see `ToolResult::image`
+pub const IMAGE_METADATA_KEY: &str = "image";
+
+/// Metadata key of the time the call took to run in milliseconds, see `ToolResult::duration`
+pub const DURATION_METADATA_KEY: &str = "duration_ms";
 
 /// Empty parameters struct for tools that don't need any parameters
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -50,17 +57,32 @@ pub enum ToolResult {
     Denied,
 }
 
+/// How the trace shows a failed and a denied tool call to the model
+const TOOL_ERROR_PREFIX: &str = "The tool failed with the following error: ";
+const TOOL_DENIED_TEXT: &str = "The tool call was rejected by the user";
+
 impl fmt::Display for ToolResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ToolResult::Success { output, .. } => write!(f, "{}", output),
-            ToolResult::Error { error, .. } => write!(f, "The tool failed with the following error: {}", error),
-            ToolResult::Denied  => write!(f, "The tool call was rejected by the user"),
+            ToolResult::Error { error, .. } => write!(f, "{}{}", TOOL_ERROR_PREFIX, error),
+            ToolResult::Denied  => write!(f, "{}", TOOL_DENIED_TEXT),
         }
     }
 }
 
 impl ToolResult {
+    /// Read back a result from the text of a tool message of the trace, the metadata is lost
+    pub fn from_trace_text(text: &str) -> Self {
+        if text == TOOL_DENIED_TEXT {
+            Self::Denied
+        } else if let Some(error) = text.strip_prefix(TOOL_ERROR_PREFIX) {
+            Self::error(error.to_string())
+        } else {
+            Self::success(text.to_string())
+        }
+    }
+
     /// Create a successful result with output
     pub fn success(output: String) -> Self {
         Self::Success {
@@ -112,6 +134,44 @@ impl ToolResult {
     pub fn is_denied(&self) -> bool {
         matches!(self, Self::Denied)
     }
+
+    /// Image to show the model along with the output, as a `data:` URL
+    pub fn image(&self) -> Option<&str> {
+        match self {
+            Self::Success { metadata: Some(metadata), .. } => metadata.get(IMAGE_METADATA_KEY)?.as_str(),
+            _ => None,
+        }
+    }
+
+    /// Remove the image from the metadata and return it. The agent takes it before the result is
+    /// logged, broadcast or saved, only the message to the model carries it
+    pub fn take_image(&mut self) -> Option<String> {
+        match self {
+            Self::Success { metadata: Some(metadata), .. } => match metadata.remove(IMAGE_METADATA_KEY)? {
+                serde_json::Value::String(image) => Some(image),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Record how long the call took to run. A denied call carries no duration
+    pub fn set_duration(&mut self, duration: Duration) {
+        if let Self::Success { metadata, .. } | Self::Error { metadata, .. } = self {
+            metadata.get_or_insert_with(HashMap::new)
+                .insert(DURATION_METADATA_KEY.to_string(), serde_json::json!(duration.as_millis() as u64));
+        }
+    }
+
+    /// How long the call took to run, None when it did not run (denied, cancelled, repeated)
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            Self::Success { metadata: Some(metadata), .. } | Self::Error { metadata: Some(metadata), .. } => {
+                metadata.get(DURATION_METADATA_KEY)?.as_u64().map(Duration::from_millis)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[async_trait]
@@ -120,6 +180,18 @@ pub trait Tool: ToolDescription + Send + Sync {
 
     fn capabilities(&self) -> &'static [ToolCapability];
 
+    /// capabilities one call needs, for tools whose calls don't all read and write.
+    /// Default implementation returns the capabilities of the tool
+    fn call_capabilities(&self, _params: &Self::Params) -> &'static [ToolCapability] {
+        self.capabilities()
+    }
+
+    /// whether the tool reports on something that changes between calls, the same call repeated
+    /// is then not a loop. Default implementation returns false
+    fn polls(&self) -> bool {
+        false
+    }
+
     /// execute the tool.
     /// parameters are specific for each tool
     async fn execute(&self, params: Self::Params, cancel_token: Option<CancellationToken>) -> ToolResult;
@@ -148,6 +220,21 @@ pub trait Tool: ToolDescription + Send + Sync {
 #[async_trait]
 pub trait AnyTool: ToolDescription + Send + Sync {
     fn capabilities(&self) -> &[ToolCapability];
+
+    /// capabilities of one call, permissions and dry-run go by these
+    fn call_capabilities(&self, _params: &serde_json::Value) -> &[ToolCapability] {
+        self.capabilities()
+    }
+
+    /// whether the tool can be kept in a read-only toolbox, by default when it never writes
+    fn is_read_only(&self) -> bool {
+        !self.capabilities().contains(&ToolCapability::Write)
+    }
+
+    /// whether repeating the same call can give another result, such calls are never refused as repeats
+    fn polls(&self) -> bool {
+        false
+    }
     
     async fn execute_json(&self, params: serde_json::Value, cancel_token: Option<CancellationToken>) -> ToolResult;
     async fn execute_preview_json(&self, params: serde_json::Value) -> Option<ToolResult>;
@@ -162,6 +249,18 @@ where
     fn capabilities(&self) -> &[ToolCapability] {
         <T as Tool>::capabilities(self)
     }
+
+    fn call_capabilities(&self, params: &serde_json::Value) -> &[ToolCapability] {
+        // unparsable parameters fail at execution, until then the tool may do anything it declares
+        match serde_json::from_value::<<T as Tool>::Params>(params.clone()) {
+            Ok(params) => <T as Tool>::call_capabilities(self, &params),
+            Err(_) => <T as Tool>::capabilities(self),
+        }
+    }
+
+    fn polls(&self) -> bool {
+        <T as Tool>::polls(self)
+    }
     
     async fn execute_json(&self, params: serde_json::Value, cancel_token: Option<CancellationToken>) -> ToolResult {
         self.execute_json(params, cancel_token).await
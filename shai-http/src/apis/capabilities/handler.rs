@@ -0,0 +1,115 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Json,
+};
+use shai_core::agent::AgentBuilder;
+use shai_core::config::agent::{AgentConfig, ALL_BUILTIN_TOOLS};
+use shai_core::config::config::ShaiConfig;
+use shai_core::tools::{prefixed_tool_name, AnyTool, GitTool, TodoStorage};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::request_id::RequestId;
+use crate::session::SessionManagerConfig;
+use crate::ServerState;
+use shai_api::capabilities::{AgentCapabilities, CapabilitiesResponse, ServerCapabilities, ToolCapability, ToolInfo};
+
+/// GET /v1/capabilities - Describe the server and the agents it can run
+pub async fn handle_capabilities(
+    State(state): State<ServerState>,
+    request_id: RequestId,
+) -> Response {
+    info!("[{}] GET /v1/capabilities", request_id);
+    Json(state.capabilities.as_ref().clone()).into_response()
+}
+
+/// Capabilities of a tool as the API lists them
+fn wire_capabilities(tool: &dyn AnyTool) -> Vec<ToolCapability> {
+    tool.capabilities().iter()
+        .map(|capability| match capability {
+            shai_core::tools::ToolCapability::Read => ToolCapability::Read,
+            shai_core::tools::ToolCapability::Write => ToolCapability::Write,
+            shai_core::tools::ToolCapability::Network => ToolCapability::Network,
+        })
+        .collect()
+}
+
+/// Computed once at startup from the configs only: MCP servers are not contacted,
+/// so their tools are listed as configured and without capabilities
+pub fn collect_capabilities(config: &SessionManagerConfig) -> CapabilitiesResponse {
+    let mut builtin_capabilities: HashMap<String, Vec<ToolCapability>> = AgentBuilder::create_default_tools(Arc::new(TodoStorage::new()))
+        .iter()
+        .map(|tool| (tool.name(), wire_capabilities(tool.as_ref())))
+        .collect();
+    // opt-in, so not always part of the default tools
+    let git: Box<dyn AnyTool> = Box::new(GitTool::new());
+    builtin_capabilities.insert(git.name(), wire_capabilities(git.as_ref()));
+    let builtin = |name: &str| ToolInfo {
+        name: name.to_string(),
+        capabilities: builtin_capabilities.get(name).cloned().unwrap_or_default(),
+        source: "builtin".to_string(),
+    };
+
+    let shai_config = ShaiConfig::load().unwrap_or_default();
+    let provider = shai_config.get_selected_provider();
+    let mut agents = vec![AgentCapabilities {
+        name: "default".to_string(),
+        description: None,
+        provider: provider.map(|p| p.provider.clone()),
+        model: provider.map(|p| p.model.clone()),
+        tools: ALL_BUILTIN_TOOLS.into_iter()
+            .chain(shai_config.git_tool.then_some("git"))
+            .map(builtin)
+            .collect(),
+    }];
+
+    for name in AgentConfig::list_agents().unwrap_or_default() {
+        let Ok(agent) = AgentConfig::load(&name) else { continue };
+
+        let builtins: Vec<&str> = if agent.tools.builtin.iter().any(|t| t == "*") {
+            ALL_BUILTIN_TOOLS.to_vec()
+        } else {
+            agent.tools.builtin.iter().map(String::as_str).collect()
+        };
+        let mut tools: Vec<ToolInfo> = builtins.into_iter()
+            .filter(|tool| !agent.tools.builtin_excluded.iter().any(|t| t == tool))
+            .map(builtin)
+            .collect();
+
+        let mut mcp_names: Vec<&String> = agent.tools.mcp.keys().collect();
+        mcp_names.sort();
+        for mcp_name in mcp_names {
+            let mcp = &agent.tools.mcp[mcp_name];
+            tools.extend(mcp.enabled_tools.iter()
+                .filter(|tool| !mcp.excluded_tools.contains(tool))
+                .map(|tool| ToolInfo {
+                    name: if mcp.prefix_tools { prefixed_tool_name(mcp_name, tool) } else { tool.clone() },
+                    capabilities: Vec::new(),
+                    source: format!("mcp:{}", mcp_name),
+                }));
+        }
+
+        agents.push(AgentCapabilities {
+            name: agent.name,
+            description: Some(agent.description),
+            provider: Some(agent.llm_provider.provider),
+            model: Some(agent.llm_provider.model),
+            tools,
+        });
+    }
+
+    CapabilitiesResponse {
+        server: ServerCapabilities {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            apis: ["chat_completions", "responses", "multimodal", "todos"].iter().map(|s| s.to_string()).collect(),
+            ephemeral: config.ephemeral,
+            max_sessions: config.max_sessions,
+            // sessions are created in sudo mode, see SessionManager::create_session
+            sudo: true,
+            permission_timeout_secs: config.permission_timeout.map(|t| t.as_secs()),
+        },
+        agents,
+    }
+}
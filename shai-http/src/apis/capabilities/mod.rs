@@ -0,0 +1,4 @@
+pub mod handler;
+
+pub use shai_api::capabilities::{CapabilitiesResponse, ServerCapabilities, AgentCapabilities, ToolInfo};
+pub use handler::handle_capabilities;
@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::time::Duration;
+use tracing::info;
+
+use crate::request_id::RequestId;
+use crate::{ErrorResponse, ServerState};
+use super::types::{EventsQuery, EventsResponse};
+
+/// Wait applied when the client does not give a timeout
+const DEFAULT_POLL_TIMEOUT_SECS: u64 = 30;
+/// Longest wait a client can ask for, proxies tend to cut idle requests after a minute
+const MAX_POLL_TIMEOUT_SECS: u64 = 60;
+
+/// GET /v1/sessions/{session_id}/events?since=<seq>&timeout=<secs> - Long-poll the events of a running session
+/// Answers as soon as events newer than `since` exist, or with an empty list once the timeout expires.
+pub async fn handle_poll_events(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    Query(query): Query<EventsQuery>,
+    request_id: RequestId,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] GET /v1/sessions/{}/events since={:?}", request_id, session_id, query.since);
+
+    let session = state.session_manager
+        .find_session(&session_id)
+        .await
+        .ok_or_else(|| ErrorResponse::new(
+            format!("Session not found: {}", session_id),
+            "not_found".to_string(),
+            Some("session_not_found".to_string()),
+        ))?;
+
+    let since = query.since.unwrap_or(0);
+    let timeout = Duration::from_secs(query.timeout.unwrap_or(DEFAULT_POLL_TIMEOUT_SECS).min(MAX_POLL_TIMEOUT_SECS));
+    let page = session.events().wait_since(since, timeout).await;
+
+    Ok(Json(EventsResponse {
+        session_id,
+        events: page.events,
+        next_since: page.next_since,
+        missed: page.missed,
+    }).into_response())
+}
@@ -0,0 +1,5 @@
+pub mod types;
+pub mod handler;
+
+pub use types::{EventsQuery, EventsResponse};
+pub use handler::handle_poll_events;
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+use crate::session::BufferedEvent;
+
+/// Query string of GET /v1/sessions/{session_id}/events
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventsQuery {
+    /// last sequence number already seen by the client (0 or absent = from the start of the buffer)
+    pub since: Option<u64>,
+    /// seconds to wait for a new event when there is none yet (0 = answer immediately)
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventsResponse {
+    pub session_id: String,
+    pub events: Vec<BufferedEvent>,
+    /// `since` to send on the next poll
+    pub next_since: u64,
+    /// events the client will never see because they were dropped from the buffer
+    pub missed: u64,
+}
@@ -1,2 +1,7 @@
 pub mod simple;
-pub mod openai;
\ No newline at end of file
+pub mod openai;
+pub mod todos;
+pub mod events;
+pub mod sessions;
+pub mod capabilities;
+pub mod ws;
@@ -3,18 +3,31 @@ use openai_dive::v1::resources::chat::{
     ChatCompletionChunkResponse, ChatCompletionChunkChoice, DeltaChatMessage,
     ChatMessageContent, ChatMessage,
 };
-use openai_dive::v1::resources::shared::FinishReason;
-use shai_core::agent::AgentEvent;
+use openai_dive::v1::resources::shared::{FinishReason, Usage};
+use shai_core::agent::{AgentEvent, PublicAgentState, TokenTotals};
 use uuid::Uuid;
 
 use crate::streaming::EventFormatter;
 
 /// Formatter for OpenAI Chat Completion API (streaming)
-/// Tool calls are converted to "thinking" reasoning_content deltas
+/// Model reasoning and tool calls are streamed as reasoning_content deltas, only the
+/// final answer goes to content. When the agent emits BrainDelta events the text is
+/// forwarded as it is generated and not sent again once the step completes.
+/// The tokens of every step are summed up and sent with the final chunk.
 pub struct ChatCompletionFormatter {
     pub model: String,
     pub created: u32,
     accumulated_text: String,
+    /// content streamed for the step in progress
+    step_content: String,
+    /// reasoning streamed for the step in progress
+    step_reasoning: bool,
+    /// content streamed for the last completed step
+    streamed_content: String,
+    /// any content streamed so far, later steps are separated from it
+    any_content: bool,
+    /// tokens the provider reported over the steps of the request
+    token_usage: Option<TokenTotals>,
 }
 
 impl ChatCompletionFormatter {
@@ -28,6 +41,31 @@ impl ChatCompletionFormatter {
             model,
             created,
             accumulated_text: String::new(),
+            step_content: String::new(),
+            step_reasoning: false,
+            streamed_content: String::new(),
+            any_content: false,
+            token_usage: None,
+        }
+    }
+
+    /// Tokens of the steps so far in OpenAI's terms, None when the provider reported none
+    pub fn usage(&self) -> Option<Usage> {
+        self.token_usage.map(|usage| completion_usage(usage.input_tokens, usage.output_tokens))
+    }
+
+    fn text_delta(&self, text: String, reasoning: bool) -> DeltaChatMessage {
+        let (content, reasoning_content) = if reasoning {
+            (None, Some(text))
+        } else {
+            (Some(ChatMessageContent::Text(text)), None)
+        };
+        DeltaChatMessage::Assistant {
+            content,
+            reasoning_content,
+            refusal: None,
+            name: None,
+            tool_calls: None,
         }
     }
 
@@ -47,6 +85,99 @@ impl ChatCompletionFormatter {
             system_fingerprint: None,
         }
     }
+
+    /// Last chunk of the turn: the answer minus what was already streamed, the finish reason
+    /// and the usage of the whole request
+    fn final_chunk(&mut self, message: String) -> ChatCompletionChunkResponse {
+        if !message.is_empty() {
+            self.accumulated_text = message;
+        }
+
+        // Send the final content delta, minus what the last step already streamed
+        // (the answer was trimmed when its think tags were removed, the stream was not)
+        let streamed = self.streamed_content.trim();
+        let remaining = match self.accumulated_text.trim().strip_prefix(streamed) {
+            Some(rest) if !streamed.is_empty() => rest.to_string(),
+            _ if self.any_content && !self.accumulated_text.is_empty() => format!("\n\n{}", self.accumulated_text),
+            _ => self.accumulated_text.clone(),
+        };
+        let content_delta = DeltaChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text(remaining)),
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            tool_calls: None,
+        };
+
+        // Always use StopSequenceReached for completion
+        // Success/failure is indicated in the content
+        let mut chunk = self.create_chunk(content_delta, Some(FinishReason::StopSequenceReached));
+        chunk.usage = self.usage();
+        chunk
+    }
+}
+
+/// Usage block of a chat completion, prompt and completion tokens
+pub fn completion_usage(prompt_tokens: u64, completion_tokens: u64) -> Usage {
+    Usage {
+        input_tokens: None,
+        input_tokens_details: None,
+        output_tokens: None,
+        output_tokens_details: None,
+        prompt_tokens: Some(prompt_tokens as u32),
+        completion_tokens: Some(completion_tokens as u32),
+        total_tokens: (prompt_tokens + completion_tokens) as u32,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    }
+}
+
+/// The chunks of ChatCompletionFormatter merged back into one message, for clients asking
+/// for a single `chat.completion` object instead of a stream
+#[derive(Default)]
+pub struct ChatCompletionAccumulator {
+    content: String,
+    reasoning: String,
+    /// the last reasoning piece was a whole line (tool call, step reasoning) rather than a token
+    reasoning_line: bool,
+    finish_reason: Option<FinishReason>,
+}
+
+impl ChatCompletionAccumulator {
+    /// Add a chunk, `whole_line` when it comes from any event but a BrainDelta so it
+    /// doesn't run into the reasoning around it
+    pub fn push(&mut self, chunk: ChatCompletionChunkResponse, whole_line: bool) {
+        for choice in chunk.choices {
+            if let DeltaChatMessage::Assistant { content, reasoning_content, .. } = choice.delta {
+                if let Some(ChatMessageContent::Text(text)) = content {
+                    self.content.push_str(&text);
+                }
+                if let Some(reasoning) = reasoning_content.filter(|r| !r.is_empty()) {
+                    if (whole_line || self.reasoning_line) && !self.reasoning.is_empty() && !self.reasoning.ends_with('\n') {
+                        self.reasoning.push('\n');
+                    }
+                    self.reasoning.push_str(&reasoning);
+                    self.reasoning_line = whole_line;
+                }
+            }
+            if choice.finish_reason.is_some() {
+                self.finish_reason = choice.finish_reason;
+            }
+        }
+    }
+
+    pub fn into_message(self) -> (ChatMessage, FinishReason) {
+        let reasoning = self.reasoning.trim();
+        let message = ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text(self.content.trim().to_string())),
+            reasoning_content: (!reasoning.is_empty()).then(|| reasoning.to_string()),
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: None,
+        };
+        (message, self.finish_reason.unwrap_or(FinishReason::StopSequenceReached))
+    }
 }
 
 #[async_trait]
@@ -59,19 +190,47 @@ impl EventFormatter for ChatCompletionFormatter {
         _session_id: &str,
     ) -> Option<Self::Output> {
         match event {
+            // Forward the text as the model generates it
+            AgentEvent::BrainDelta { text, reasoning } => {
+                let text = if reasoning {
+                    self.step_reasoning = true;
+                    text
+                } else {
+                    let separator = if self.step_content.is_empty() && self.any_content { "\n\n" } else { "" };
+                    self.step_content.push_str(&text);
+                    self.any_content = true;
+                    format!("{}{}", separator, text)
+                };
+                Some(self.create_chunk(self.text_delta(text, reasoning), None))
+            }
+
             // Capture assistant messages from brain results
             AgentEvent::BrainResult { thought, .. } => {
+                self.streamed_content = std::mem::take(&mut self.step_content);
+                let reasoning_streamed = std::mem::replace(&mut self.step_reasoning, false);
                 match thought {
                     Ok(msg) => {
-                        if let ChatMessage::Assistant {
-                            content: Some(ChatMessageContent::Text(text)),
-                            ..
-                        } = msg
-                        {
+                        let ChatMessage::Assistant { content, reasoning_content, .. } = msg else {
+                            return None;
+                        };
+                        if let Some(ChatMessageContent::Text(text)) = content {
                             // Accumulate the text for final response
                             self.accumulated_text = text;
                         }
-                        None
+
+                        // stream the thinking right away, clients can hide it
+                        if reasoning_streamed {
+                            return None;
+                        }
+                        let reasoning = reasoning_content.filter(|r| !r.trim().is_empty())?;
+                        let delta = DeltaChatMessage::Assistant {
+                            content: None,
+                            reasoning_content: Some(reasoning),
+                            refusal: None,
+                            name: None,
+                            tool_calls: None,
+                        };
+                        Some(self.create_chunk(delta, None))
                     }
                     Err(err) => {
                         // Stream error as assistant message
@@ -130,25 +289,16 @@ impl EventFormatter for ChatCompletionFormatter {
             }
 
             // Agent completed - stream final content as delta
-            AgentEvent::Completed { message, .. } => {
-                if !message.is_empty() {
-                    self.accumulated_text = message;
-                }
-
-                // Send the final content delta
-                let content_delta = DeltaChatMessage::Assistant {
-                    content: Some(ChatMessageContent::Text(self.accumulated_text.clone())),
-                    reasoning_content: None,
-                    refusal: None,
-                    name: None,
-                    tool_calls: None,
-                };
+            AgentEvent::Completed { message, .. } => Some(self.final_chunk(message)),
 
-                // Always use StopSequenceReached for completion
-                // Success/failure is indicated in the content
-                let finish_reason = Some(FinishReason::StopSequenceReached);
+            // the agent waits for the next message: the turn is over, the stream ends on it
+            AgentEvent::StatusChanged { new_status: PublicAgentState::Paused, .. } => Some(self.final_chunk(String::new())),
 
-                Some(self.create_chunk(content_delta, finish_reason))
+            AgentEvent::TokenUsage { input_tokens, output_tokens } => {
+                let usage = self.token_usage.get_or_insert_with(TokenTotals::default);
+                usage.input_tokens += input_tokens as u64;
+                usage.output_tokens += output_tokens as u64;
+                None
             }
 
             AgentEvent::Error { error } => {
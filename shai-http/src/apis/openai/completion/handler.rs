@@ -7,21 +7,25 @@ use openai_dive::v1::resources::chat::{
     ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChoice,
     ChatMessage, ChatMessageContent,
 };
-use openai_dive::v1::resources::shared::{Usage, FinishReason};
-use shai_core::agent::AgentEvent;
+use openai_dive::v1::resources::shared::Usage;
+use shai_core::agent::{estimate_tokens, AgentEvent, SamplingOverrides};
 use tokio_stream::wrappers::BroadcastStream;
-use tracing::info;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tracing::{info, warn};
 use uuid::Uuid;
 
-use super::formatter::ChatCompletionFormatter;
+use super::formatter::{completion_usage, ChatCompletionAccumulator, ChatCompletionFormatter};
+use crate::request_id::RequestId;
+use crate::session::RequestOptions;
+use crate::streaming::EventFormatter;
 use crate::{ApiJson, ServerState, ErrorResponse, session_to_sse_stream};
 
 /// Handle OpenAI chat completion - supports both streaming and non-streaming
 pub async fn handle_chat_completion(
     State(state): State<ServerState>,
+    request_id: RequestId,
     ApiJson(payload): ApiJson<ChatCompletionParameters>,
 ) -> Result<Response, ErrorResponse> {
-    let request_id = Uuid::new_v4();
     let session_id = Uuid::new_v4().to_string();
 
     let is_streaming = payload.stream.unwrap_or(false);
@@ -36,11 +40,24 @@ pub async fn handle_chat_completion(
     }
 }
 
+/// Sampling and tool call parameters of the request, max_tokens is read when max_completion_tokens is not set
+fn request_options(payload: &ChatCompletionParameters) -> RequestOptions {
+    RequestOptions {
+        sampling: SamplingOverrides {
+            temperature: payload.temperature,
+            max_tokens: payload.max_completion_tokens.or(payload.max_tokens),
+            top_p: payload.top_p,
+        },
+        system_prompt: None,
+        parallel_tool_calls: payload.parallel_tool_calls,
+    }
+}
+
 /// Handle streaming chat completion
 async fn handle_chat_completion_stream(
     state: ServerState,
     payload: ChatCompletionParameters,
-    request_id: Uuid,
+    request_id: RequestId,
     session_id: String,
 ) -> Result<Response, ErrorResponse> {
     let trace = build_message_trace(&payload);
@@ -54,7 +71,7 @@ async fn handle_chat_completion_stream(
 
     // Create request session
     let request_session = agent_session
-        .handle_request(&request_id.to_string(), trace)
+        .handle_request(&request_id.to_string(), trace, request_options(&payload))
         .await
         .map_err(|e| ErrorResponse::internal_error(format!("Failed to handle request: {}", e)))?;
 
@@ -68,14 +85,15 @@ async fn handle_chat_completion_stream(
 }
 
 /// Handle non-streaming chat completion
-/// Directly processes events and returns a single complete response
+/// The events go through the same formatter as the stream, merged into a single response
 async fn handle_chat_completion_non_stream(
     state: ServerState,
     payload: ChatCompletionParameters,
-    request_id: Uuid,
+    request_id: RequestId,
     session_id: String,
 ) -> Result<Response, ErrorResponse> {
     let trace = build_message_trace(&payload);
+    let prompt_estimate = estimate_tokens(&trace);
 
     // Create ephemeral session
     let agent_session = state.session_manager
@@ -83,16 +101,17 @@ async fn handle_chat_completion_non_stream(
         .await
         .map_err(|e| ErrorResponse::internal_error(format!("Failed to create session: {}", e)))?;
 
-    // Send messages and get event stream
+    // Send messages and get event stream, the session lives until the lifecycle is dropped
     let request_session = agent_session
-        .handle_request(&request_id.to_string(), trace)
+        .handle_request(&request_id.to_string(), trace, request_options(&payload))
         .await
         .map_err(|e| ErrorResponse::internal_error(format!("Failed to handle request: {}", e)))?;
+    let mut lifecycle = request_session.lifecycle;
 
-    // Collect events - accumulate both content and reasoning (tool calls)
+    let mut formatter = ChatCompletionFormatter::new(payload.model.clone());
+    let mut accumulator = ChatCompletionAccumulator::default();
+    let mut finished = false;
     let mut event_stream = BroadcastStream::new(request_session.event_rx);
-    let mut final_message = String::new();
-    let mut reasoning_steps = Vec::new();
 
     while let Some(result) = event_stream.next().await {
         match result {
@@ -106,87 +125,53 @@ async fn handle_chat_completion_non_stream(
                             ..
                         }
                 );
+                finished |= is_terminal;
 
-                match event {
-                    AgentEvent::Completed { message, .. } => {
-                        final_message = message;
-                    }
-                    AgentEvent::BrainResult { thought, .. } => {
-                        if let Ok(msg) = thought {
-                            if let ChatMessage::Assistant {
-                                content: Some(ChatMessageContent::Text(text)),
-                                ..
-                            } = msg
-                            {
-                                final_message = text;
-                            }
-                        }
-                    }
-                    AgentEvent::ToolCallStarted { call, .. } => {
-                        reasoning_steps.push(format!("[toolcall: {}]", call.tool_name));
-                    }
-                    AgentEvent::ToolCallCompleted { call, result: tool_result, .. } => {
-                        use shai_core::tools::ToolResult;
-                        let step = match &tool_result {
-                            ToolResult::Success { .. } => format!("[tool succeeded: {}]", call.tool_name),
-                            ToolResult::Error { error, .. } => {
-                                let error_oneline = error.lines().next().unwrap_or(error);
-                                format!("[tool failed: {} - {}]", call.tool_name, error_oneline)
-                            }
-                            ToolResult::Denied => format!("[tool denied: {}]", call.tool_name),
-                        };
-                        reasoning_steps.push(step);
-                    }
-                    _ => {}
+                let whole_line = !matches!(event, AgentEvent::BrainDelta { .. });
+                if let Some(chunk) = formatter.format_event(event, &session_id).await {
+                    accumulator.push(chunk, whole_line);
                 }
 
                 if is_terminal {
                     break;
                 }
             }
-            Err(e) => {
-                return Err(ErrorResponse::internal_error(format!("Event stream error: {}", e)));
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                // the missed events may hold part of the answer, better an error than a truncated one
+                warn!("[{}] {} events skipped, the answer is incomplete", request_id, skipped);
+                return Err(ErrorResponse::internal_error(format!(
+                    "{} agent events were missed while collecting the answer, it would be incomplete", skipped)));
             }
         }
     }
+    // the answer is complete, releasing the session from now on is not a client disconnect
+    lifecycle.finish();
+
+    // the formatter sends what the last step answered on the terminal event, flushed here
+    // when the events ended before it
+    if !finished {
+        let flush = AgentEvent::Completed { success: true, message: String::new() };
+        if let Some(chunk) = formatter.format_event(flush, &session_id).await {
+            accumulator.push(chunk, true);
+        }
+    }
+    let (message, finish_reason) = accumulator.into_message();
+
+    let usage = response_usage(&formatter, prompt_estimate, &message);
 
     // Build OpenAI-compatible response
     let response = ChatCompletionResponse {
         id: Some(format!("chatcmpl-{}", Uuid::new_v4())),
         object: "chat.completion".to_string(),
-        created: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as u32,
+        created: formatter.created,
         model: payload.model.clone(),
         choices: vec![ChatCompletionChoice {
             index: 0,
-            message: ChatMessage::Assistant {
-                content: Some(ChatMessageContent::Text(final_message)),
-                name: None,
-                tool_calls: None,
-                audio: None,
-                reasoning_content: if reasoning_steps.is_empty() {
-                    None
-                } else {
-                    Some(reasoning_steps.join("\n"))
-                },
-                refusal: None,
-            },
-            finish_reason: Some(FinishReason::StopSequenceReached),
+            message,
+            finish_reason: Some(finish_reason),
             logprobs: None,
         }],
-        usage: Some(Usage {
-            input_tokens: None,
-            input_tokens_details: None,
-            output_tokens: None,
-            output_tokens_details: None,
-            prompt_tokens: Some(0),
-            completion_tokens: Some(0),
-            total_tokens: 0,
-            completion_tokens_details: None,
-            prompt_tokens_details: None,
-        }),
+        usage: Some(usage),
         system_fingerprint: None,
         service_tier: None,
     };
@@ -194,6 +179,14 @@ async fn handle_chat_completion_non_stream(
     Ok(Json(response).into_response())
 }
 
+/// The tokens the provider reported over the steps of the request, estimated from the prompt
+/// and the answer when it reported none
+pub(super) fn response_usage(formatter: &ChatCompletionFormatter, prompt_estimate: usize, message: &ChatMessage) -> Usage {
+    formatter.usage().unwrap_or_else(|| {
+        completion_usage(prompt_estimate as u64, estimate_tokens(std::slice::from_ref(message)) as u64)
+    })
+}
+
 /// Build message trace from OpenAI chat completion parameters
 fn build_message_trace(params: &ChatCompletionParameters) -> Vec<ChatMessage> {
     let mut trace = Vec::new();
@@ -209,6 +202,16 @@ fn build_message_trace(params: &ChatCompletionParameters) -> Vec<ChatMessage> {
                 }
             }
             ChatMessage::User { content, name, .. } => {
+                // images are kept as parts, LlmClient replaces them for models without vision
+                if let ChatMessageContent::ContentPart(parts) = content {
+                    if parts.iter().any(|p| matches!(p, openai_dive::v1::resources::chat::ChatMessageContentPart::Image(_))) {
+                        trace.push(ChatMessage::User {
+                            content: content.clone(),
+                            name: name.clone(),
+                        });
+                        continue;
+                    }
+                }
                 let text = match content {
                     ChatMessageContent::Text(t) => t.clone(),
                     ChatMessageContent::ContentPart(parts) => {
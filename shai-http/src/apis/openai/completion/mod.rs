@@ -2,3 +2,6 @@ pub mod handler;
 pub mod formatter;
 
 pub use handler::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,135 @@
+use chrono::Utc;
+use openai_dive::v1::resources::chat::{ChatCompletionChunkResponse, ChatMessage, ChatMessageContent, DeltaChatMessage};
+use shai_core::agent::{AgentEvent, PublicAgentState};
+
+use crate::streaming::EventFormatter;
+use super::formatter::{ChatCompletionAccumulator, ChatCompletionFormatter};
+use super::handler::response_usage;
+
+fn delta(text: &str, reasoning: bool) -> AgentEvent {
+    AgentEvent::BrainDelta { text: text.to_string(), reasoning }
+}
+
+fn brain_result(content: &str, reasoning: Option<&str>) -> AgentEvent {
+    AgentEvent::BrainResult {
+        timestamp: Utc::now(),
+        thought: Ok(ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text(content.to_string())),
+            reasoning_content: reasoning.map(str::to_string),
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        }),
+    }
+}
+
+fn token_usage(input_tokens: u32, output_tokens: u32) -> AgentEvent {
+    AgentEvent::TokenUsage { input_tokens, output_tokens }
+}
+
+fn paused() -> AgentEvent {
+    AgentEvent::StatusChanged { old_status: PublicAgentState::Running, new_status: PublicAgentState::Paused }
+}
+
+/// Chunks the formatter sends for these events, in order
+async fn chunks_for(events: Vec<AgentEvent>) -> Vec<ChatCompletionChunkResponse> {
+    let mut formatter = ChatCompletionFormatter::new("test-model".to_string());
+    let mut chunks = Vec::new();
+    for event in events {
+        chunks.extend(formatter.format_event(event, "session").await);
+    }
+    chunks
+}
+
+/// Content and reasoning of the chunks, as a client appending the deltas sees them
+fn streamed_text(chunks: &[ChatCompletionChunkResponse]) -> (String, String) {
+    let (mut content, mut reasoning) = (String::new(), String::new());
+    for choice in chunks.iter().flat_map(|chunk| &chunk.choices) {
+        if let DeltaChatMessage::Assistant { content: text, reasoning_content, .. } = &choice.delta {
+            if let Some(ChatMessageContent::Text(text)) = text {
+                content.push_str(text);
+            }
+            if let Some(text) = reasoning_content {
+                reasoning.push_str(text);
+            }
+        }
+    }
+    (content, reasoning)
+}
+
+#[tokio::test]
+async fn test_streamed_deltas_are_not_repeated_by_the_brain_result() {
+    let chunks = chunks_for(vec![
+        delta("Let me ", true),
+        delta("think", true),
+        delta("Hello", false),
+        delta(" world", false),
+        brain_result("Hello world", Some("Let me think")),
+        paused(),
+    ]).await;
+
+    // one chunk per delta, nothing for the BrainResult, then the final chunk
+    assert_eq!(chunks.len(), 5);
+    assert_eq!(streamed_text(&chunks[..4]), ("Hello world".to_string(), "Let me think".to_string()));
+    assert_eq!(streamed_text(&chunks[4..]), (String::new(), String::new()));
+    assert!(chunks[4].choices[0].finish_reason.is_some());
+}
+
+#[tokio::test]
+async fn test_steps_without_deltas_are_sent_whole() {
+    let chunks = chunks_for(vec![
+        brain_result("Hello world", Some("Let me think")),
+        paused(),
+    ]).await;
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(streamed_text(&chunks), ("Hello world".to_string(), "Let me think".to_string()));
+}
+
+#[tokio::test]
+async fn test_each_step_streams_its_own_text_once() {
+    let chunks = chunks_for(vec![
+        delta("Looking at the files.", false),
+        brain_result("Looking at the files.", None),
+        delta("Done.", false),
+        brain_result("Done.", None),
+        paused(),
+    ]).await;
+
+    assert_eq!(streamed_text(&chunks).0, "Looking at the files.\n\nDone.");
+}
+
+#[tokio::test]
+async fn test_usage_sums_the_steps_of_the_request() {
+    let events = vec![
+        brain_result("Looking at the files.", None),
+        token_usage(120, 30),
+        brain_result("Done.", None),
+        token_usage(200, 15),
+        paused(),
+    ];
+
+    // the stream reports it on the final chunk
+    let chunks = chunks_for(events.clone()).await;
+    let usage = chunks.last().unwrap().usage.clone().expect("the final chunk carries the usage");
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (Some(320), Some(45), 365));
+    assert!(chunks[..chunks.len() - 1].iter().all(|chunk| chunk.usage.is_none()));
+
+    // the single response uses it rather than the estimate
+    let mut formatter = ChatCompletionFormatter::new("test-model".to_string());
+    let mut accumulator = ChatCompletionAccumulator::default();
+    for event in events {
+        if let Some(chunk) = formatter.format_event(event, "session").await {
+            accumulator.push(chunk, true);
+        }
+    }
+    let (message, _) = accumulator.into_message();
+    let usage = response_usage(&formatter, 9999, &message);
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (Some(320), Some(45), 365));
+
+    // without reported tokens it is estimated
+    let formatter = ChatCompletionFormatter::new("test-model".to_string());
+    let usage = response_usage(&formatter, 9999, &message);
+    assert_eq!(usage.prompt_tokens, Some(9999));
+}
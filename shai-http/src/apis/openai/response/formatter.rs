@@ -87,6 +87,44 @@ impl ResponseFormatter {
             error: None,
         }
     }
+
+    /// Final response of a turn that already ended, rebuilt from the session trace:
+    /// the tool calls and the answer that followed its last user message
+    pub fn stored_response(&self, session_id: &str, trace: &[ChatMessage]) -> ResponseObject {
+        let turn_start = trace.iter()
+            .rposition(|message| matches!(message, ChatMessage::User { .. }))
+            .map_or(0, |index| index + 1);
+
+        let mut output = Vec::new();
+        let mut answer = None;
+        for message in &trace[turn_start..] {
+            if let ChatMessage::Assistant { content, tool_calls, .. } = message {
+                for call in tool_calls.iter().flatten() {
+                    output.push(ResponseOutput::FunctionToolCall(FunctionToolCall {
+                        id: call.id.clone(),
+                        call_id: call.id.clone(),
+                        name: call.function.name.clone(),
+                        arguments: call.function.arguments.clone(),
+                        status: InputItemStatus::Completed,
+                    }));
+                }
+                if let Some(ChatMessageContent::Text(text)) = content {
+                    answer = Some(text.clone());
+                }
+            }
+        }
+        output.push(ResponseOutput::Message(OutputMessage {
+            id: Uuid::new_v4().to_string(),
+            role: Role::Assistant,
+            status: MessageStatus::Completed,
+            content: vec![OutputContent::Text {
+                text: answer.unwrap_or_default(),
+                annotations: vec![],
+            }],
+        }));
+
+        self.build_response_object(session_id, ReasoningStatus::Completed, output)
+    }
 }
 
 #[async_trait]
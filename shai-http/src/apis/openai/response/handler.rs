@@ -4,9 +4,12 @@ use axum::{
     Json,
 };
 use openai_dive::v1::resources::response::request::ResponseParameters;
+use shai_core::agent::{SamplingOverrides, SystemPromptOverride};
 use tracing::info;
 use uuid::Uuid;
 
+use crate::request_id::RequestId;
+use crate::session::{RequestOptions, SessionPersist};
 use crate::{event_to_sse_stream, session_to_sse_stream, ApiJson, ErrorResponse, ServerState};
 use super::types::build_message_trace;
 use super::formatter::ResponseFormatter;
@@ -15,9 +18,9 @@ use super::formatter::ResponseFormatter;
 /// Supports both stateful (store=true, previous_response_id) and stateless (store=false) modes
 pub async fn handle_response(
     State(state): State<ServerState>,
+    request_id: RequestId,
     ApiJson(payload): ApiJson<ResponseParameters>,
 ) -> Result<Response, ErrorResponse> {
-    let request_id = Uuid::new_v4();
     let store = payload.store.unwrap_or(true);
     let session_id = payload.previous_response_id.clone()
         .unwrap_or_else(|| format!("resp_{}", Uuid::new_v4()));
@@ -37,7 +40,7 @@ pub async fn handle_response(
 async fn handle_response_stream(
     state: ServerState,
     payload: ResponseParameters,
-    request_id: Uuid,
+    request_id: RequestId,
     session_id: String,
     is_ephemeral: bool,
 ) -> Result<Response, ErrorResponse> {
@@ -61,7 +64,20 @@ async fn handle_response_stream(
 
     // Create request session
     let request_session = agent_session
-        .handle_request(&request_id.to_string(), trace)
+        .handle_request(&request_id.to_string(), trace, RequestOptions {
+            sampling: SamplingOverrides {
+                temperature: payload.temperature,
+                max_tokens: payload.max_output_tokens,
+                top_p: payload.top_p,
+            },
+            // the agent's prompt keeps its tool and environment sections, the instructions go with it or in its place
+            system_prompt: payload.instructions.clone().map(|instructions| if state.replace_system_prompt {
+                SystemPromptOverride::Replace(instructions)
+            } else {
+                SystemPromptOverride::Append(instructions)
+            }),
+            parallel_tool_calls: payload.parallel_tool_calls,
+        })
         .await
         .map_err(|e| ErrorResponse::internal_error(format!("Failed to handle request: {}", e)))?;
 
@@ -78,7 +94,7 @@ async fn handle_response_stream(
 async fn handle_response_non_stream(
     _state: ServerState,
     _payload: ResponseParameters,
-    _request_id: Uuid,
+    _request_id: RequestId,
     _session_id: String,
     _is_ephemeral: bool,
 ) -> Result<Response, ErrorResponse> {
@@ -87,24 +103,21 @@ async fn handle_response_non_stream(
 
 
 /// GET /v1/responses/{response_id} - Retrieve a model response
-/// Read-only access to an ongoing or completed session
+/// Streams the events of the turn in progress, or returns the final response once the turn is over
 pub async fn handle_get_response(
     State(state): State<ServerState>,
     Path(response_id): Path<String>,
+    request_id: RequestId,
 ) -> Result<Response, ErrorResponse> {
-    let request_id = Uuid::new_v4();
     info!("[{}] GET /v1/responses/{}", request_id, response_id);
 
-    // Get the existing session (note: without agent_name, will only check memory, not disk)
-    // For GET we don't have the model from request, so we use the session's agent_name
-    // This means GET can only access in-memory sessions
-    let agent_session = state.session_manager
-        .get_session(&request_id.to_string(), &response_id, "default".to_string())
-        .await
-        .map_err(|e| ErrorResponse::invalid_request(format!("Response not found: {}", e)))?;
-
-    // Subscribe to events (non-blocking, read-only)
-    let event_rx = agent_session.watch();
+    // Only look the session up: a stopped session is read from disk rather than restarted
+    let Some(agent_session) = state.session_manager.find_session(&response_id).await else {
+        let session_data = SessionPersist::load_session(&response_id)
+            .map_err(|_| response_not_found(&response_id))?;
+        let formatter = ResponseFormatter::new("default".to_string(), ResponseParameters::default());
+        return Ok(Json(formatter.stored_response(&response_id, &session_data.trace)).into_response());
+    };
 
     // Create a minimal payload for the formatter
     let placeholder_payload = ResponseParameters {
@@ -112,10 +125,18 @@ pub async fn handle_get_response(
         stream: Some(true),
         ..Default::default()
     };
-
-    // Create the formatter
     let formatter = ResponseFormatter::new(agent_session.agent_name.clone(), placeholder_payload);
 
+    // No request in progress: the turn is over, its answer is in the trace
+    if !agent_session.is_busy() {
+        let trace = agent_session.trace().await
+            .map_err(|e| ErrorResponse::internal_error(format!("Failed to read the session trace: {}", e)))?;
+        return Ok(Json(formatter.stored_response(&response_id, &trace)).into_response());
+    }
+
+    // Subscribe to events (non-blocking, read-only)
+    let event_rx = agent_session.watch();
+
     // Create SSE stream using the simple sse_stream (no lifecycle needed for read-only)
     // stop_on_pause = false means stream stops on Completed OR Paused
     let stream = event_to_sse_stream(event_rx, formatter, response_id, false);
@@ -123,13 +144,21 @@ pub async fn handle_get_response(
     Ok(Sse::new(stream).into_response())
 }
 
+fn response_not_found(response_id: &str) -> ErrorResponse {
+    ErrorResponse::new(
+        format!("Response not found: {}", response_id),
+        "not_found".to_string(),
+        Some("response_not_found".to_string()),
+    )
+}
+
 
 /// POST /v1/responses/{response_id}/cancel - Cancel a model response
 pub async fn handle_cancel_response(
     State(state): State<ServerState>,
     Path(response_id): Path<String>,
+    request_id: RequestId,
 ) -> Result<Response, ErrorResponse> {
-    let request_id = Uuid::new_v4();
     info!("[{}] POST /v1/responses/{}/cancel", request_id, response_id);
 
     // Cancel the session
@@ -153,18 +153,11 @@ impl ResponseStreamEvent {
     }
 }
 
-/// Convert OpenAI Response API input to ChatMessage trace
+/// Convert OpenAI Response API input to ChatMessage trace.
+/// The instructions are not part of it, they reach the agent's system prompt instead
 pub fn build_message_trace(params: &ResponseParameters) -> Vec<ChatMessage> {
     let mut trace = Vec::new();
 
-    // Add instructions as system message if present
-    if let Some(instructions) = &params.instructions {
-        trace.push(ChatMessage::System {
-            content: ChatMessageContent::Text(instructions.clone()),
-            name: None,
-        });
-    }
-
     // Convert input messages
     match &params.input {
         ResponseInput::Text(text) => {
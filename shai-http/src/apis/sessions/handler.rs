@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use openai_dive::v1::resources::chat::ChatMessage;
+use tracing::info;
+
+use crate::request_id::RequestId;
+use crate::session::{status_label, AgentSession, SessionPersist};
+use crate::{ErrorResponse, ServerState};
+use super::types::{SessionDetails, SessionInfo, SessionListResponse, SessionTerminatedResponse, SessionTraceResponse, SessionCheckpointResponse, TraceQuery};
+
+const ROLES: [&str; 5] = ["system", "developer", "user", "assistant", "tool"];
+
+fn session_not_found(session_id: &str) -> ErrorResponse {
+    ErrorResponse::new(
+        format!("Session not found: {}", session_id),
+        "not_found".to_string(),
+        Some("session_not_found".to_string()),
+    )
+}
+
+/// The state comes from the agent, a session whose agent already stopped shows as "stopped"
+async fn session_info(session: &AgentSession) -> SessionInfo {
+    let status = session.state().await
+        .map(|state| status_label(&state))
+        .unwrap_or_else(|_| "stopped".to_string());
+    SessionInfo {
+        session_id: session.session_id.clone(),
+        agent_name: session.agent_name.clone(),
+        ephemeral: session.is_ephemeral(),
+        status,
+        busy: session.is_busy(),
+        created_at: session.created_at,
+    }
+}
+
+/// GET /v1/sessions - List the sessions running in memory
+pub async fn handle_list_sessions(
+    State(state): State<ServerState>,
+    request_id: RequestId,
+) -> Response {
+    info!("[{}] GET /v1/sessions", request_id);
+
+    let mut sessions = Vec::new();
+    for session in state.session_manager.list_sessions().await {
+        sessions.push(session_info(&session).await);
+    }
+    Json(SessionListResponse { sessions }).into_response()
+}
+
+/// GET /v1/sessions/{session_id} - Details of a running session
+pub async fn handle_get_session(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    request_id: RequestId,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] GET /v1/sessions/{}", request_id, session_id);
+
+    let session = state.session_manager
+        .find_session(&session_id)
+        .await
+        .ok_or_else(|| session_not_found(&session_id))?;
+
+    let info = session_info(&session).await;
+    let (token_usage, token_budget) = session.token_usage().await.unwrap_or_default();
+    let todos = session.get_todos().await.unwrap_or_default();
+
+    Ok(Json(SessionDetails {
+        session: info,
+        token_usage,
+        token_budget,
+        todos,
+        last_event_seq: session.events().last_seq(),
+    }).into_response())
+}
+
+fn message_role(message: &ChatMessage) -> &'static str {
+    match message {
+        ChatMessage::System { .. } => "system",
+        ChatMessage::Developer { .. } => "developer",
+        ChatMessage::User { .. } => "user",
+        ChatMessage::Assistant { .. } => "assistant",
+        ChatMessage::Tool { .. } => "tool",
+    }
+}
+
+/// GET /v1/sessions/{session_id}/trace?role=<roles> - Conversation trace of a running session
+/// Read from the agent directly, so it answers while a request is being handled and includes its latest messages.
+pub async fn handle_get_trace(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    Query(query): Query<TraceQuery>,
+    request_id: RequestId,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] GET /v1/sessions/{}/trace role={:?}", request_id, session_id, query.role);
+
+    let roles: Option<Vec<String>> = query.role.map(|roles| {
+        roles.split(',').map(|role| role.trim().to_lowercase()).filter(|role| !role.is_empty()).collect()
+    });
+    if let Some(unknown) = roles.iter().flatten().find(|role| !ROLES.contains(&role.as_str())) {
+        return Err(ErrorResponse::invalid_request(format!(
+            "Unknown role '{}', expected one of: {}", unknown, ROLES.join(", ")
+        )));
+    }
+
+    let session = state.session_manager
+        .find_session(&session_id)
+        .await
+        .ok_or_else(|| session_not_found(&session_id))?;
+
+    let trace = session.trace().await
+        .map_err(|e| ErrorResponse::internal_error(format!("Could not read the trace of session {}: {}", session_id, e)))?;
+    let total = trace.len();
+    let messages = match roles {
+        Some(roles) => trace.into_iter().filter(|message| roles.iter().any(|role| role == message_role(message))).collect(),
+        None => trace,
+    };
+
+    Ok(Json(SessionTraceResponse { session_id, total, messages }).into_response())
+}
+
+/// POST /v1/sessions/{session_id}/checkpoint - Save a running session to disk now
+/// A background session is otherwise saved when its request ends, this keeps long runs safe from a server crash.
+pub async fn handle_checkpoint_session(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    request_id: RequestId,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] POST /v1/sessions/{}/checkpoint", request_id, session_id);
+
+    if !SessionPersist::is_enabled() {
+        return Err(ErrorResponse::invalid_request(
+            "Session persistence is disabled (SHAI_SESSION_PERSIST_ENABLE=false)".to_string()
+        ));
+    }
+
+    let session = state.session_manager
+        .find_session(&session_id)
+        .await
+        .ok_or_else(|| session_not_found(&session_id))?;
+
+    // an ephemeral session ends with its request, there is nothing to resume it from
+    if session.is_ephemeral() {
+        return Err(ErrorResponse::invalid_request(
+            format!("Session {} is ephemeral and cannot be checkpointed", session_id)
+        ));
+    }
+
+    let checkpoint = session.checkpoint(&request_id.to_string()).await
+        .map_err(|e| ErrorResponse::internal_error(format!("Could not checkpoint session {}: {}", session_id, e)))?;
+
+    Ok(Json(SessionCheckpointResponse {
+        session_id,
+        status: status_label(&checkpoint.state),
+        messages: checkpoint.trace.len(),
+        saved_at: checkpoint.taken_at,
+    }).into_response())
+}
+
+/// DELETE /v1/sessions/{session_id} - Stop a running session, the request it is handling ends with it
+pub async fn handle_terminate_session(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    request_id: RequestId,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] DELETE /v1/sessions/{}", request_id, session_id);
+
+    if !state.session_manager.terminate_session(&request_id.to_string(), &session_id).await {
+        return Err(session_not_found(&session_id));
+    }
+
+    Ok(Json(SessionTerminatedResponse { session_id, terminated: true }).into_response())
+}
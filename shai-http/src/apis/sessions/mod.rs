@@ -0,0 +1,5 @@
+pub mod types;
+pub mod handler;
+
+pub use types::{SessionInfo, SessionDetails, SessionListResponse, SessionTerminatedResponse, SessionTraceResponse, SessionCheckpointResponse, TraceQuery};
+pub use handler::{handle_list_sessions, handle_get_session, handle_get_trace, handle_checkpoint_session, handle_terminate_session};
@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use openai_dive::v1::resources::chat::ChatMessage;
+use serde::{Deserialize, Serialize};
+use shai_core::agent::TokenTotals;
+use shai_core::tools::TodoItem;
+
+/// A session running in memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub agent_name: String,
+    pub ephemeral: bool,
+    /// live agent state, same values as the status events ("paused", "processing:bash"...)
+    pub status: String,
+    /// a request is being handled, the next one waits for it
+    pub busy: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionInfo>,
+}
+
+/// GET /v1/sessions/{session_id}, the summary plus what the agent reports about its work
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDetails {
+    #[serde(flatten)]
+    pub session: SessionInfo,
+    pub token_usage: TokenTotals,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<u64>,
+    pub todos: Vec<TodoItem>,
+    /// sequence number of the last event, the `since` to poll the events from now on
+    pub last_event_seq: u64,
+}
+
+/// Query string of GET /v1/sessions/{session_id}/trace
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TraceQuery {
+    /// comma separated roles to keep (system, developer, user, assistant, tool), all of them when absent
+    pub role: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTraceResponse {
+    pub session_id: String,
+    /// messages of the whole trace, before the role filter
+    pub total: usize,
+    pub messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCheckpointResponse {
+    pub session_id: String,
+    /// agent state when the snapshot was taken, a running tool call is saved without its result
+    pub status: String,
+    pub messages: usize,
+    pub saved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTerminatedResponse {
+    pub session_id: String,
+    pub terminated: bool,
+}
@@ -3,7 +3,7 @@ use shai_core::agent::AgentEvent;
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
 use std::collections::HashMap;
 
-use super::types::{MultiModalStreamingResponse, ToolCall, ToolCallResult};
+use shai_api::simple::{MultiModalStreamingResponse, ToolCall, ToolCallResult};
 use crate::streaming::EventFormatter;
 
 /// Formatter for Simple API multimodal responses
@@ -30,20 +30,24 @@ impl EventFormatter for SimpleFormatter {
             AgentEvent::BrainResult { thought, .. } => {
                 match thought {
                     Ok(msg) => {
-                        // Extract text content from the ChatMessage
-                        let text_content = match &msg {
-                            ChatMessage::Assistant {
-                                content: Some(ChatMessageContent::Text(text)),
-                                ..
-                            } => Some(text.clone()),
-                            _ => None,
+                        // Extract the answer and the reasoning from the ChatMessage
+                        let (text_content, reasoning) = match msg {
+                            ChatMessage::Assistant { content, reasoning_content, .. } => (
+                                match content {
+                                    Some(ChatMessageContent::Text(text)) => Some(text),
+                                    _ => None,
+                                },
+                                reasoning_content.filter(|r| !r.trim().is_empty()),
+                            ),
+                            _ => (None, None),
                         };
 
-                        if let Some(text) = text_content {
+                        if text_content.is_some() || reasoning.is_some() {
                             return Some(MultiModalStreamingResponse {
                                 id: session_id.to_string(),
                                 model: self.model.clone(),
-                                assistant: Some(text),
+                                reasoning,
+                                assistant: text_content,
                                 call: None,
                                 result: None,
                             });
@@ -55,6 +59,7 @@ impl EventFormatter for SimpleFormatter {
                         Some(MultiModalStreamingResponse {
                             id: session_id.to_string(),
                             model: self.model.clone(),
+                            reasoning: None,
                             assistant: Some(format!("Error: {}", err)),
                             call: None,
                             result: None,
@@ -65,6 +70,7 @@ impl EventFormatter for SimpleFormatter {
             AgentEvent::ToolCallStarted { call, .. } => Some(MultiModalStreamingResponse {
                 id: session_id.to_string(),
                 model: self.model.clone(),
+                reasoning: None,
                 assistant: None,
                 call: Some(ToolCall {
                     tool: call.tool_name.clone(),
@@ -74,8 +80,11 @@ impl EventFormatter for SimpleFormatter {
                 result: None,
             }),
             AgentEvent::ToolCallCompleted { call, result, .. } => {
-                use shai_core::tools::ToolResult;
+                use shai_core::tools::{ToolResult, DURATION_METADATA_KEY};
 
+                let extra = result.duration().map(|duration| {
+                    HashMap::from([(DURATION_METADATA_KEY.to_string(), duration.as_millis().to_string())])
+                });
                 let (tool_result, output_str) = match &result {
                     ToolResult::Success { output, .. } => (
                         ToolCallResult {
@@ -85,7 +94,7 @@ impl EventFormatter for SimpleFormatter {
                             speech: None,
                             other: None,
                             error: None,
-                            extra: None,
+                            extra: extra.clone(),
                         },
                         output.clone(),
                     ),
@@ -97,7 +106,7 @@ impl EventFormatter for SimpleFormatter {
                             speech: None,
                             other: None,
                             error: Some(error.clone()),
-                            extra: None,
+                            extra: extra.clone(),
                         },
                         String::new(),
                     ),
@@ -118,6 +127,7 @@ impl EventFormatter for SimpleFormatter {
                 Some(MultiModalStreamingResponse {
                     id: session_id.to_string(),
                     model: self.model.clone(),
+                    reasoning: None,
                     assistant: None,
                     call: Some(ToolCall {
                         tool: call.tool_name.clone(),
@@ -130,6 +140,7 @@ impl EventFormatter for SimpleFormatter {
             AgentEvent::Completed { message, .. } => Some(MultiModalStreamingResponse {
                 id: session_id.to_string(),
                 model: self.model.clone(),
+                reasoning: None,
                 assistant: Some(message),
                 call: None,
                 result: None,
@@ -137,6 +148,7 @@ impl EventFormatter for SimpleFormatter {
             AgentEvent::Error { error } => Some(MultiModalStreamingResponse {
                 id: session_id.to_string(),
                 model: self.model.clone(),
+                reasoning: None,
                 assistant: None,
                 call: None,
                 result: Some(ToolCallResult {
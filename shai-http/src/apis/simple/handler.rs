@@ -3,38 +3,42 @@ use axum::{
     response::{IntoResponse, Response, Sse},
 };
 use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ToolCall as LlmToolCall, Function};
+use shai_llm::image::{data_url, image_mime_type, image_part, sniff_image_mime_type, text_part};
 use tracing::info;
 use uuid::Uuid;
 
-use super::types::{MultiModalQuery, Message};
+use shai_api::simple::{MultiModalQuery, Message, UserMessage};
 use super::formatter::SimpleFormatter;
+use crate::request_id::RequestId;
+use crate::session::RequestOptions;
 use crate::{session_to_sse_stream, ApiJson, ErrorResponse, ServerState};
 
 /// Handle multimodal query without explicit session id (ephemeral session)
 pub async fn handle_multimodal_query_stream(
     State(state): State<ServerState>,
+    request_id: RequestId,
     ApiJson(payload): ApiJson<MultiModalQuery>,
 ) -> Result<Response, ErrorResponse> {
-    handle_multimodal_query_stream_internal(state, None, payload).await
+    handle_multimodal_query_stream_internal(state, request_id, None, payload).await
 }
 
 /// Handle multimodal query with provided session id (persistent session)
 pub async fn handle_multimodal_query_stream_with_session(
     State(state): State<ServerState>,
     Path(session_id): Path<String>,
+    request_id: RequestId,
     ApiJson(payload): ApiJson<MultiModalQuery>,
 ) -> Result<Response, ErrorResponse> {
-    handle_multimodal_query_stream_internal(state, Some(session_id), payload).await
+    handle_multimodal_query_stream_internal(state, request_id, Some(session_id), payload).await
 }
 
 /// Shared implementation for multimodal query handlers
 async fn handle_multimodal_query_stream_internal(
     state: ServerState,
+    request_id: RequestId,
     session_id_param: Option<String>,
     payload: MultiModalQuery,
 ) -> Result<Response, ErrorResponse> {
-    let request_id = Uuid::new_v4();
-
     // Determine session_id: use provided, or generate ephemeral
     let is_ephemeral = session_id_param.is_none();
     let session_id = session_id_param
@@ -71,7 +75,7 @@ async fn handle_multimodal_query_stream_internal(
 
     // Create request session
     let request_session = agent_session
-        .handle_request(&request_id.to_string(), trace)
+        .handle_request(&request_id.to_string(), trace, RequestOptions::default())
         .await
         .map_err(|e| ErrorResponse::internal_error(format!("Failed to handle request: {}", e)))?;
 
@@ -94,7 +98,7 @@ fn build_message_trace(query: &MultiModalQuery) -> Vec<ChatMessage> {
             match msg {
                 Message::User(user_msg) => {
                     trace.push(ChatMessage::User {
-                        content: ChatMessageContent::Text(user_msg.message.clone()),
+                        content: user_content(user_msg),
                         name: None,
                     });
                 }
@@ -150,3 +154,29 @@ fn build_message_trace(query: &MultiModalQuery) -> Vec<ChatMessage> {
 
     trace
 }
+
+/// Message text, followed by the images and the attached image files when there are any
+fn user_content(user_msg: &UserMessage) -> ChatMessageContent {
+    let mut images: Vec<String> = user_msg.images.iter().flatten()
+        .map(|image| {
+            if image.starts_with("http://") || image.starts_with("https://") || image.starts_with("data:") {
+                image.clone()
+            } else {
+                data_url(sniff_image_mime_type(image).unwrap_or("image/png"), image)
+            }
+        })
+        .collect();
+    if let Some(files) = &user_msg.attached_files {
+        let mut files: Vec<_> = files.iter().collect();
+        files.sort();
+        images.extend(files.into_iter()
+            .filter_map(|(name, data)| image_mime_type(name).map(|mime_type| data_url(mime_type, data))));
+    }
+
+    if images.is_empty() {
+        return ChatMessageContent::Text(user_msg.message.clone());
+    }
+    let mut parts = vec![text_part(user_msg.message.clone())];
+    parts.extend(images.into_iter().map(image_part));
+    ChatMessageContent::ContentPart(parts)
+}
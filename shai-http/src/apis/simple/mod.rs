@@ -1,7 +1,6 @@
-pub mod types;
 pub mod handler;
 pub mod formatter;
 
-pub use types::{MultiModalQuery, Message};
+pub use shai_api::simple::{MultiModalQuery, Message};
 pub use handler::{handle_multimodal_query_stream, handle_multimodal_query_stream_with_session};
 pub use formatter::SimpleFormatter;
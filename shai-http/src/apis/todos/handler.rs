@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use shai_core::tools::TodoItem;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::request_id::RequestId;
+use crate::session::AgentSession;
+use crate::{ApiJson, ErrorResponse, ServerState};
+use super::types::{AddTodoRequest, TodoListResponse, UpdateTodoRequest};
+
+/// Todos are only reachable on sessions running in memory
+async fn running_session(state: &ServerState, session_id: &str) -> Result<Arc<AgentSession>, ErrorResponse> {
+    state.session_manager
+        .find_session(session_id)
+        .await
+        .ok_or_else(|| ErrorResponse::new(
+            format!("Session not found: {}", session_id),
+            "not_found".to_string(),
+            Some("session_not_found".to_string()),
+        ))
+}
+
+fn todo_list(session_id: String, todos: Vec<TodoItem>) -> Response {
+    Json(TodoListResponse { session_id, todos }).into_response()
+}
+
+/// GET /v1/sessions/{session_id}/todos - List the todos of a running session
+pub async fn handle_list_todos(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    request_id: RequestId,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] GET /v1/sessions/{}/todos", request_id, session_id);
+
+    let todos = running_session(&state, &session_id).await?
+        .get_todos()
+        .await
+        .map_err(|e| ErrorResponse::internal_error(format!("Failed to get todos: {}", e)))?;
+
+    Ok(todo_list(session_id, todos))
+}
+
+/// POST /v1/sessions/{session_id}/todos - Add a pending todo, the agent sees it on its next step
+pub async fn handle_add_todo(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    request_id: RequestId,
+    ApiJson(payload): ApiJson<AddTodoRequest>,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] POST /v1/sessions/{}/todos", request_id, session_id);
+
+    if payload.content.trim().is_empty() {
+        return Err(ErrorResponse::invalid_request("Todo content cannot be empty".to_string()));
+    }
+
+    let todos = running_session(&state, &session_id).await?
+        .add_todo(&request_id.to_string(), payload.content)
+        .await
+        .map_err(|e| ErrorResponse::internal_error(format!("Failed to add todo: {}", e)))?;
+
+    Ok(todo_list(session_id, todos))
+}
+
+/// PATCH /v1/sessions/{session_id}/todos/{todo_id} - Change the status of a todo
+pub async fn handle_update_todo(
+    State(state): State<ServerState>,
+    Path((session_id, todo_id)): Path<(String, String)>,
+    request_id: RequestId,
+    ApiJson(payload): ApiJson<UpdateTodoRequest>,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] PATCH /v1/sessions/{}/todos/{}", request_id, session_id, todo_id);
+
+    let todos = running_session(&state, &session_id).await?
+        .set_todo_status(&request_id.to_string(), todo_id, payload.status)
+        .await
+        .map_err(|e| ErrorResponse::invalid_request(format!("Failed to update todo: {}", e)))?;
+
+    Ok(todo_list(session_id, todos))
+}
+
+/// DELETE /v1/sessions/{session_id}/todos/{todo_id} - Remove a todo
+pub async fn handle_remove_todo(
+    State(state): State<ServerState>,
+    Path((session_id, todo_id)): Path<(String, String)>,
+    request_id: RequestId,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] DELETE /v1/sessions/{}/todos/{}", request_id, session_id, todo_id);
+
+    let todos = running_session(&state, &session_id).await?
+        .remove_todo(&request_id.to_string(), todo_id)
+        .await
+        .map_err(|e| ErrorResponse::invalid_request(format!("Failed to remove todo: {}", e)))?;
+
+    Ok(todo_list(session_id, todos))
+}
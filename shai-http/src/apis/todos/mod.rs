@@ -0,0 +1,5 @@
+pub mod types;
+pub mod handler;
+
+pub use types::{TodoListResponse, AddTodoRequest, UpdateTodoRequest};
+pub use handler::{handle_list_todos, handle_add_todo, handle_update_todo, handle_remove_todo};
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use shai_core::tools::{TodoItem, TodoStatus};
+
+/// Current todo list of a session, returned by every todo endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoListResponse {
+    pub session_id: String,
+    pub todos: Vec<TodoItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddTodoRequest {
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTodoRequest {
+    pub status: TodoStatus,
+}
@@ -0,0 +1,82 @@
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Path, State},
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use crate::request_id::RequestId;
+use crate::session::AgentSession;
+use crate::{ErrorResponse, ServerState};
+use super::types::{WsClientMessage, WsServerMessage};
+
+/// GET /v1/ws/{session_id} - Interactive connection to a running session
+/// Every agent event is sent as a JSON text message, the client answers with `WsClientMessage`s
+/// (user input, permission responses, stop) on the same socket.
+pub async fn handle_session_ws(
+    State(state): State<ServerState>,
+    Path(session_id): Path<String>,
+    request_id: RequestId,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ErrorResponse> {
+    info!("[{}] GET /v1/ws/{}", request_id, session_id);
+
+    let session = state.session_manager
+        .find_session(&session_id)
+        .await
+        .ok_or_else(|| ErrorResponse::new(
+            format!("Session not found: {}", session_id),
+            "not_found".to_string(),
+            Some("session_not_found".to_string()),
+        ))?;
+
+    Ok(ws.on_upgrade(move |socket| run_socket(socket, session, request_id.to_string())))
+}
+
+/// Relay the session events to the socket and the client messages to the agent,
+/// until either side closes. Closing the socket leaves the session running
+async fn run_socket(socket: WebSocket, session: Arc<AgentSession>, http_request_id: String) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut events = session.watch();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let text = match event {
+                    Ok(event) => serde_json::to_string(&event),
+                    Err(RecvError::Lagged(missed)) => serde_json::to_string(&WsServerMessage::Lagged { missed }),
+                    // the agent stopped
+                    Err(RecvError::Closed) => break,
+                };
+                let Ok(text) = text else { continue };
+                if sender.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = receiver.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // pings are answered by axum, binary messages are not part of the protocol
+                    Some(Ok(_)) => continue,
+                };
+                let result = match serde_json::from_str::<WsClientMessage>(text.as_str()) {
+                    Ok(message) => session.send(&http_request_id, message.into_request()).await.map_err(|e| e.to_string()),
+                    Err(e) => Err(format!("Invalid message: {}", e)),
+                };
+                if let Err(error) = result {
+                    warn!("[{}] - websocket message refused: {}", http_request_id, error);
+                    let Ok(text) = serde_json::to_string(&WsServerMessage::RequestFailed { error }) else { continue };
+                    if sender.send(Message::Text(text.into())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    let _ = sender.close().await;
+    info!("[{}] - websocket closed", http_request_id);
+}
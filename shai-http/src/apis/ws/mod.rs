@@ -0,0 +1,5 @@
+pub mod types;
+pub mod handler;
+
+pub use types::{WsClientMessage, WsServerMessage};
+pub use handler::handle_session_ws;
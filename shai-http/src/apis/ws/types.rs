@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use shai_core::agent::{AgentRequest, PermissionResponse, UserResponse};
+
+/// Messages a WebSocket client sends to the agent of the session, e.g.
+/// `{"type": "permission_response", "request_id": "...", "response": "Allow"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsClientMessage {
+    /// New user message, the current task is cancelled and the agent resumes with it
+    UserInput { input: String },
+    /// Answer to a `permission_required` event
+    PermissionResponse { request_id: String, response: PermissionResponse },
+    /// Answer to a `user_input_required` event
+    UserResponse { request_id: String, response: UserResponse },
+    /// Stop the current task, the session stays open
+    Stop,
+    /// Cancel one running tool call
+    CancelTool { tool_call_id: String },
+    /// Turn sudo off to get `permission_required` events instead of running every tool call
+    Sudo { enabled: bool },
+}
+
+impl WsClientMessage {
+    pub fn into_request(self) -> AgentRequest {
+        match self {
+            WsClientMessage::UserInput { input } => AgentRequest::SendUserInput { input },
+            WsClientMessage::PermissionResponse { request_id, response } => AgentRequest::UserPermissionResponse { request_id, response },
+            WsClientMessage::UserResponse { request_id, response } => AgentRequest::UserQueryResponse { request_id, response },
+            WsClientMessage::Stop => AgentRequest::StopCurrentTask,
+            WsClientMessage::CancelTool { tool_call_id } => AgentRequest::CancelTool { tool_call_id },
+            WsClientMessage::Sudo { enabled } => AgentRequest::Sudo(Some(enabled)),
+        }
+    }
+}
+
+/// Messages of the socket itself, sent along the agent events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsServerMessage {
+    /// The client read too slowly, `missed` events were dropped
+    Lagged { missed: u64 },
+    /// A client message could not be parsed or the agent refused it
+    RequestFailed { error: String },
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::error::ErrorResponse;
+use crate::request_id::RequestId;
+
+/// Key clients must send as `Authorization: Bearer <key>`
+#[derive(Clone)]
+pub struct ApiKey(Arc<str>);
+
+impl ApiKey {
+    pub fn new(key: &str) -> Self {
+        Self(Arc::from(key))
+    }
+
+    /// Compare the bearer token of the request, in constant time so the key can't be guessed byte by byte
+    pub fn accepts(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = bearer_token(headers) else {
+            return false;
+        };
+        let (expected, given) = (self.0.as_bytes(), token.as_bytes());
+        expected.len() == given.len()
+            && expected.iter().zip(given).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?.trim();
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// Refuse the request with a 401 unless it carries the API key
+pub async fn api_key_middleware(State(api_key): State<ApiKey>, request: Request, next: Next) -> Response {
+    if api_key.accepts(request.headers()) {
+        return next.run(request).await;
+    }
+
+    let request_id = request.extensions().get::<RequestId>().cloned().unwrap_or_else(RequestId::generate);
+    let message = if request.headers().contains_key(header::AUTHORIZATION) {
+        "Incorrect API key provided"
+    } else {
+        "Missing API key, send it as 'Authorization: Bearer <key>'"
+    };
+    warn!("[{}] {} {} refused: {}", request_id, request.method(), request.uri().path(), message);
+
+    let mut response = ErrorResponse::unauthorized(message.to_string()).into_response();
+    response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    /// A protected route, and an open one added after the layer like the health checks
+    fn app() -> Router {
+        Router::new()
+            .route("/v1/sessions", get(|| async { "sessions" }))
+            .route_layer(middleware::from_fn_with_state(ApiKey::new("secret-key"), api_key_middleware))
+            .route("/health", get(|| async { "ok" }))
+    }
+
+    async fn get_with(path: &str, authorization: Option<&str>) -> (StatusCode, HeaderMap, String) {
+        let mut request = axum::http::Request::builder().uri(path);
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        let response = app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let (status, headers) = (response.status(), response.headers().clone());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, headers, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_missing_key_is_refused() {
+        let (status, headers, body) = get_with("/v1/sessions", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(headers[header::WWW_AUTHENTICATE], "Bearer");
+        assert!(body.contains("Missing API key"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_key_is_refused() {
+        for authorization in ["Bearer secret-kez", "Bearer secret", "Basic secret-key", "secret-key"] {
+            let (status, _, body) = get_with("/v1/sessions", Some(authorization)).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", authorization);
+            assert!(body.contains("Incorrect API key"), "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_valid_key_is_accepted() {
+        for authorization in ["Bearer secret-key", "bearer  secret-key "] {
+            let (status, _, body) = get_with("/v1/sessions", Some(authorization)).await;
+            assert_eq!(status, StatusCode::OK, "{}", authorization);
+            assert_eq!(body, "sessions");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_added_after_the_layer_stay_open() {
+        let (status, _, body) = get_with("/health", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "ok");
+    }
+}
@@ -0,0 +1,99 @@
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::request_id::REQUEST_ID_HEADER;
+
+/// Cross-origin policy for browser clients.
+///
+/// The default allows no origin at all, so browsers refuse cross-origin calls
+/// and only same-origin pages or non-browser clients can use the server.
+/// Allowing `*` lets any web page the user visits drive the agent (and its
+/// tools) through the user's browser: only do it on a loopback bind address.
+#[derive(Clone, Debug, Default)]
+pub struct CorsConfig {
+    /// Allowed origins (e.g. "http://localhost:5173"), "*" allows any origin
+    pub allowed_origins: Vec<String>,
+    /// Allowed methods, defaults to the methods used by the API when empty
+    pub allowed_methods: Vec<String>,
+    /// Allowed request headers, defaults to content-type, authorization and x-request-id when empty
+    pub allowed_headers: Vec<String>,
+}
+
+impl CorsConfig {
+    /// Allow any origin, method and header. Meant for local development only
+    pub fn permissive() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: vec!["*".to_string()],
+            allowed_headers: vec!["*".to_string()],
+        }
+    }
+
+    pub fn with_origins(mut self, origins: Vec<String>) -> Self {
+        self.allowed_origins = origins;
+        self
+    }
+
+    pub fn with_methods(mut self, methods: Vec<String>) -> Self {
+        self.allowed_methods = methods;
+        self
+    }
+
+    pub fn with_headers(mut self, headers: Vec<String>) -> Self {
+        self.allowed_headers = headers;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.allowed_origins.is_empty()
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|o| o == "*")
+    }
+
+    /// Build the axum layer, None when CORS is disabled.
+    /// The layer answers preflight OPTIONS requests itself for every route.
+    pub fn layer(&self) -> Result<Option<CorsLayer>, String> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+
+        let origins = if self.allows_any_origin() {
+            AllowOrigin::any()
+        } else {
+            let origins = self.allowed_origins.iter()
+                .map(|o| HeaderValue::from_str(o).map_err(|_| format!("invalid CORS origin: {}", o)))
+                .collect::<Result<Vec<_>, _>>()?;
+            AllowOrigin::list(origins)
+        };
+
+        let methods = if self.allowed_methods.iter().any(|m| m == "*") {
+            AllowMethods::any()
+        } else if self.allowed_methods.is_empty() {
+            AllowMethods::list([Method::GET, Method::POST, Method::PATCH, Method::DELETE, Method::OPTIONS])
+        } else {
+            let methods = self.allowed_methods.iter()
+                .map(|m| Method::from_bytes(m.to_uppercase().as_bytes()).map_err(|_| format!("invalid CORS method: {}", m)))
+                .collect::<Result<Vec<_>, _>>()?;
+            AllowMethods::list(methods)
+        };
+
+        let headers = if self.allowed_headers.iter().any(|h| h == "*") {
+            AllowHeaders::any()
+        } else if self.allowed_headers.is_empty() {
+            AllowHeaders::list([axum::http::header::CONTENT_TYPE, axum::http::header::AUTHORIZATION, REQUEST_ID_HEADER.clone()])
+        } else {
+            let headers = self.allowed_headers.iter()
+                .map(|h| HeaderName::from_bytes(h.as_bytes()).map_err(|_| format!("invalid CORS header: {}", h)))
+                .collect::<Result<Vec<_>, _>>()?;
+            AllowHeaders::list(headers)
+        };
+
+        Ok(Some(CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .expose_headers([REQUEST_ID_HEADER.clone()])))
+    }
+}
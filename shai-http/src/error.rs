@@ -6,20 +6,14 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
+pub use shai_api::ErrorDetail;
+
 /// Error response structure for API errors
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: ErrorDetail,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ErrorDetail {
-    pub message: String,
-    pub r#type: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub code: Option<String>,
-}
-
 impl ErrorResponse {
     pub fn new(message: String, error_type: String, code: Option<String>) -> Self {
         Self {
@@ -39,6 +33,10 @@ impl ErrorResponse {
         Self::new(message, "invalid_request".to_string(), None)
     }
 
+    pub fn unauthorized(message: String) -> Self {
+        Self::new(message, "authentication_error".to_string(), Some("invalid_api_key".to_string()))
+    }
+
     pub fn internal_error(message: String) -> Self {
         Self::new(message, "internal_error".to_string(), None)
     }
@@ -49,6 +47,7 @@ impl IntoResponse for ErrorResponse {
         let status = match self.error.r#type.as_str() {
             "not_found" => StatusCode::NOT_FOUND,
             "invalid_request" => StatusCode::BAD_REQUEST,
+            "authentication_error" => StatusCode::UNAUTHORIZED,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
         (status, Json(self)).into_response()
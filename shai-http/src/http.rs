@@ -1,14 +1,20 @@
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{get, patch, post},
     Router,
 };
 use std::sync::Arc;
-use tower_http::cors::CorsLayer;
-use tracing::info;
+use std::time::Duration;
+use tracing::{info, warn};
 
+use crate::auth::ApiKey;
+use crate::cors::CorsConfig;
 use crate::session::{SessionManager, SessionManagerConfig};
 use crate::apis;
 
+/// Below the 10s docker and most process managers wait between SIGTERM and SIGKILL
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(8);
+
 /// Configuration for the HTTP server
 #[derive(Clone, Debug)]
 pub struct ServerConfig {
@@ -16,6 +22,18 @@ pub struct ServerConfig {
     pub address: String,
     /// Session manager configuration
     pub session_manager: SessionManagerConfig,
+    /// CORS policy, disabled by default
+    pub cors: CorsConfig,
+    /// Print the startup summary (settings, endpoints, agents) on stdout
+    pub banner: bool,
+    /// Key required as `Authorization: Bearer <key>` on the /v1 routes, None leaves them open
+    pub api_key: Option<String>,
+    /// On SIGINT/SIGTERM, how long running tool calls get to finish before they are cancelled
+    pub shutdown_grace: Duration,
+    /// Serve the Prometheus counters on GET /metrics
+    pub metrics: bool,
+    /// The `instructions` of a Response API request replace the agent's system prompt instead of being added to it
+    pub replace_system_prompt: bool,
 }
 
 impl ServerConfig {
@@ -24,6 +42,12 @@ impl ServerConfig {
         Self {
             address,
             session_manager: SessionManagerConfig::default(),
+            cors: CorsConfig::default(),
+            banner: true,
+            api_key: None,
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            metrics: false,
+            replace_system_prompt: false,
         }
     }
 
@@ -38,12 +62,83 @@ impl ServerConfig {
         self.session_manager.max_sessions = max_sessions;
         self
     }
+
+    /// Set how long agents wait for a permission answer before denying (None = forever)
+    pub fn with_permission_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+        self.session_manager.permission_timeout = timeout;
+        self
+    }
+
+    /// Set how many events each session keeps for GET /v1/sessions/{session_id}/events
+    pub fn with_event_buffer_size(mut self, size: usize) -> Self {
+        self.session_manager.event_buffer_size = size;
+        self
+    }
+
+    /// Save background sessions after every N completed tool calls, None saves them only when a request ends
+    pub fn with_checkpoint_every(mut self, every: Option<u32>) -> Self {
+        self.session_manager.checkpoint_every = every;
+        self
+    }
+
+    /// Whether a background session stops its current task when the client of the request disconnects
+    /// before the end (on by default), off lets the agent run to the end for a client to fetch later
+    pub fn with_stop_on_disconnect(mut self, stop: bool) -> Self {
+        self.session_manager.stop_on_disconnect = stop;
+        self
+    }
+
+    /// Set the CORS policy for browser clients
+    pub fn with_cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Set whether the startup summary is printed, logs are unaffected
+    pub fn with_banner(mut self, banner: bool) -> Self {
+        self.banner = banner;
+        self
+    }
+
+    /// Require this key on every /v1 route, None (or an empty key) disables authentication
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key.filter(|key| !key.is_empty());
+        self
+    }
+
+    /// Set how long running tool calls get to finish when the server is stopped
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Serve the session, tool call, token and LLM latency counters on GET /metrics (off by default)
+    pub fn with_metrics(mut self, metrics: bool) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Let the `instructions` of a request replace the agent's system prompt (off: they are added after it)
+    pub fn with_replace_system_prompt(mut self, replace: bool) -> Self {
+        self.replace_system_prompt = replace;
+        self
+    }
+
+    /// Whether the bind address only accepts local connections
+    pub fn is_loopback(&self) -> bool {
+        self.address.starts_with("localhost:")
+            || self.address.parse::<std::net::SocketAddr>().map(|addr| addr.ip().is_loopback()).unwrap_or(false)
+    }
 }
 
 /// Server state holding the session manager
 #[derive(Clone)]
 pub struct ServerState {
     pub session_manager: Arc<SessionManager>,
+    /// Computed at startup, served as is by GET /v1/capabilities
+    pub capabilities: Arc<apis::capabilities::CapabilitiesResponse>,
+    /// Request instructions replace the system prompt of the agent instead of being added to it
+    pub replace_system_prompt: bool,
 }
 
 
@@ -54,20 +149,23 @@ pub async fn start_server(
     // Create session manager
     let session_manager = SessionManager::new(config.session_manager.clone());
 
-    println!("✓ Session manager initialized");
-    if let Some(max) = config.session_manager.max_sessions {
-        println!("  Max sessions: \x1b[1m{}\x1b[0m", max);
-    } else {
-        println!("  Max sessions: \x1b[1munlimited\x1b[0m");
+    if config.api_key.is_none() && !config.is_loopback() {
+        warn!("no API key while bound to non-loopback address {}", config.address);
+    }
+    if config.cors.allows_any_origin() && !config.is_loopback() {
+        warn!("CORS allows any origin while bound to non-loopback address {}", config.address);
     }
-    println!("  Default mode: \x1b[1m{}\x1b[0m", if config.session_manager.ephemeral { "ephemeral" } else { "persistent" });
-    println!();
 
+    let session_manager = Arc::new(session_manager);
     let state = ServerState {
-        session_manager: Arc::new(session_manager),
+        session_manager: session_manager.clone(),
+        capabilities: Arc::new(apis::capabilities::handler::collect_capabilities(&config.session_manager)),
+        replace_system_prompt: config.replace_system_prompt,
     };
 
-    let app = Router::new()
+    let mut app = Router::new()
+        // Server and agents description
+        .route("/v1/capabilities", get(apis::capabilities::handle_capabilities))
         // Simple API
         .route("/v1/multimodal", post(apis::simple::handle_multimodal_query_stream))
         .route("/v1/multimodal/{session_id}", post(apis::simple::handle_multimodal_query_stream_with_session))
@@ -77,20 +175,121 @@ pub async fn start_server(
         .route("/v1/responses/{response_id}/cancel", post(apis::openai::handle_cancel_response))
         // OpenAI-compatible Chat Completion API
         .route("/v1/chat/completions", post(apis::openai::handle_chat_completion))
-        .layer(CorsLayer::permissive())
-        .with_state(state);
+        // Sessions running in memory
+        .route("/v1/sessions", get(apis::sessions::handle_list_sessions))
+        .route("/v1/sessions/{session_id}", get(apis::sessions::handle_get_session).delete(apis::sessions::handle_terminate_session))
+        .route("/v1/sessions/{session_id}/trace", get(apis::sessions::handle_get_trace))
+        .route("/v1/sessions/{session_id}/checkpoint", post(apis::sessions::handle_checkpoint_session))
+        // Shared todo list of a running session
+        .route("/v1/sessions/{session_id}/todos", get(apis::todos::handle_list_todos).post(apis::todos::handle_add_todo))
+        .route("/v1/sessions/{session_id}/todos/{todo_id}", patch(apis::todos::handle_update_todo).delete(apis::todos::handle_remove_todo))
+        .route("/v1/sessions/{session_id}/events", get(apis::events::handle_poll_events))
+        // Interactive session over WebSocket
+        .route("/v1/ws/{session_id}", get(apis::ws::handle_session_ws));
+    if config.metrics {
+        app = app.route("/metrics", get(crate::metrics::handle_metrics));
+    }
+
+    // Only the routes above need the key, routes added after this point (health checks) stay open.
+    // CORS is layered outside, so preflight requests are answered without it
+    if let Some(api_key) = &config.api_key {
+        app = app.route_layer(middleware::from_fn_with_state(ApiKey::new(api_key), crate::auth::api_key_middleware));
+    }
+
+    // Every request gets an id (the client's X-Request-Id or a new one) echoed in the response
+    app = app.layer(middleware::from_fn(crate::request_id::request_id_middleware));
+    if let Some(cors) = config.cors.layer()? {
+        app = app.layer(cors);
+    }
+    let app = app.with_state(state);
 
     let listener = tokio::net::TcpListener::bind(&config.address).await?;
 
-    // Print server info
+    if config.banner {
+        print_banner(&config);
+    }
+
+    info!("HTTP server listening on {}", config.address);
+
+    // On SIGINT/SIGTERM the listener closes first, then the sessions are stopped, which ends their streams
+    let (stop_accepting, stopped) = tokio::sync::oneshot::channel::<()>();
+    let mut server = tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async move { let _ = stopped.await; })
+            .await
+    });
+    tokio::select! {
+        result = &mut server => {
+            result??;
+            return Ok(());
+        }
+        _ = shutdown_signal() => {}
+    }
+
+    info!("Shutdown requested, no longer accepting connections");
+    let _ = stop_accepting.send(());
+    session_manager.shutdown(config.shutdown_grace).await;
+
+    // connections still open once the sessions are gone are dropped
+    if tokio::time::timeout(CONNECTION_DRAIN_TIMEOUT, &mut server).await.is_err() {
+        warn!("Connections still open after shutdown, closing them");
+        server.abort();
+    }
+    info!("HTTP server stopped");
+    Ok(())
+}
+
+/// Settings, warnings, endpoints and agents of the server, printed once it is bound
+fn print_banner(config: &ServerConfig) {
+    println!("✓ Session manager initialized");
+    if let Some(max) = config.session_manager.max_sessions {
+        println!("  Max sessions: \x1b[1m{}\x1b[0m", max);
+    } else {
+        println!("  Max sessions: \x1b[1munlimited\x1b[0m");
+    }
+    println!("  Default mode: \x1b[1m{}\x1b[0m", if config.session_manager.ephemeral { "ephemeral" } else { "persistent" });
+    if let Some(every) = config.session_manager.checkpoint_every.filter(|&every| every > 0) {
+        println!("  Checkpoint: \x1b[1mevery {} tool calls\x1b[0m", every);
+    }
+    println!("  Client disconnect: \x1b[1m{}\x1b[0m", if config.session_manager.stop_on_disconnect { "stops the current task" } else { "the agent keeps running" });
+    if config.cors.is_enabled() {
+        println!("  CORS origins: \x1b[1m{}\x1b[0m", config.cors.allowed_origins.join(", "));
+    } else {
+        println!("  CORS: \x1b[1mdisabled\x1b[0m");
+    }
+    println!("  Auth: \x1b[1m{}\x1b[0m", if config.api_key.is_some() { "bearer API key" } else { "none" });
+    println!("  Request instructions: \x1b[1m{}\x1b[0m", if config.replace_system_prompt { "replace the system prompt" } else { "added to the system prompt" });
+    if config.api_key.is_none() && !config.is_loopback() {
+        println!("  \x1b[33m⚠ anyone who can reach {} can run the agent and its tools, set an API key\x1b[0m", config.address);
+    }
+    if config.cors.allows_any_origin() && !config.is_loopback() {
+        println!("  \x1b[33m⚠ any web page can call this server and its tools from a visitor's browser (CORS * on {})\x1b[0m", config.address);
+    }
+    println!();
+
     println!("Server starting on \x1b[1mhttp://{}\x1b[0m", config.address);
     println!("\nAvailable endpoints:");
+    println!("  \x1b[1mGET  /v1/capabilities\x1b[0m                 - Server and agents capabilities");
     println!("  \x1b[1mPOST /v1/chat/completions\x1b[0m            - OpenAI Chat Completions API (ephemeral)");
     println!("  \x1b[1mPOST /v1/responses\x1b[0m                    - OpenAI Responses API (stateful/stateless)");
     println!("  \x1b[1mGET  /v1/responses/:id\x1b[0m                - Get response by ID");
     println!("  \x1b[1mPOST /v1/responses/:id/cancel\x1b[0m        - Cancel a response");
     println!("  \x1b[1mPOST /v1/multimodal\x1b[0m                   - Simple multimodal API (streaming)");
     println!("  \x1b[1mPOST /v1/multimodal/:session_id\x1b[0m      - Simple multimodal API (with session)");
+    println!("  \x1b[1mGET  /v1/sessions\x1b[0m                     - List the running sessions");
+    println!("  \x1b[1mGET  /v1/sessions/:id\x1b[0m                 - Session state, token usage and todos");
+    println!("  \x1b[1mDELETE /v1/sessions/:id\x1b[0m              - Stop a session");
+    println!("  \x1b[1mGET  /v1/sessions/:id/trace\x1b[0m           - Conversation trace, filtered with ?role=");
+    println!("  \x1b[1mPOST /v1/sessions/:id/checkpoint\x1b[0m      - Save the session to disk now");
+    println!("  \x1b[1mGET  /v1/sessions/:id/todos\x1b[0m            - List the session todos");
+    println!("  \x1b[1mPOST /v1/sessions/:id/todos\x1b[0m            - Add a todo");
+    println!("  \x1b[1mPATCH /v1/sessions/:id/todos/:todo_id\x1b[0m  - Change a todo status");
+    println!("  \x1b[1mDELETE /v1/sessions/:id/todos/:todo_id\x1b[0m - Remove a todo");
+    println!("  \x1b[1mGET  /v1/sessions/:id/events\x1b[0m           - Long-poll the session events");
+    println!("  \x1b[1mGET  /v1/ws/:id\x1b[0m                        - Interactive session over WebSocket");
+    if config.metrics {
+        println!("  \x1b[1mGET  /metrics\x1b[0m                          - Prometheus counters");
+    }
 
     // List available agents
     use shai_core::config::agent::AgentConfig;
@@ -102,9 +301,35 @@ pub async fn start_server(
     }
 
     println!("\nPress Ctrl+C to stop\n");
+}
 
-    info!("HTTP server listening on {}", config.address);
+/// How long open connections get to end after the sessions are stopped
+const CONNECTION_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
 
-    axum::serve(listener, app).await?;
-    Ok(())
+/// Resolves on Ctrl+C, or SIGTERM on unix
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => { signal.recv().await; }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
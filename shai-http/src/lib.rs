@@ -1,10 +1,18 @@
 pub mod http;
+pub mod auth;
+pub mod cors;
 pub mod apis;
 pub mod error;
+pub mod request_id;
 pub mod session;
 pub mod streaming;
+pub mod metrics;
 
 pub use error::{ApiJson, ErrorResponse};
 pub use session::{SessionManager, SessionManagerConfig, AgentSession};
 pub use streaming::{EventFormatter, event_to_sse_stream, session_to_sse_stream};
-pub use http::{ServerConfig, ServerState, start_server};
\ No newline at end of file
+pub use http::{ServerConfig, ServerState, start_server};
+pub use cors::CorsConfig;
+pub use request_id::RequestId;
+pub use auth::ApiKey;
+pub use metrics::Metrics;
\ No newline at end of file
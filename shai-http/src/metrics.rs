@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use shai_core::agent::AgentEvent;
+use shai_core::tools::ToolResult;
+
+use crate::ServerState;
+
+/// Upper bounds in seconds of the LLM latency histogram buckets
+const LLM_LATENCY_BUCKETS: [f64; 9] = [0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+#[derive(Default)]
+struct Histogram {
+    /// count of observations under each bound of LLM_LATENCY_BUCKETS
+    buckets: [u64; LLM_LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LLM_LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Counters of the server, fed by the session manager and rendered by GET /metrics
+#[derive(Default)]
+pub struct Metrics {
+    sessions_created: AtomicU64,
+    requests: AtomicU64,
+    /// (tool, result) -> completed calls
+    tool_calls: Mutex<BTreeMap<(String, &'static str), u64>>,
+    /// tool -> (timed calls, seconds spent running them)
+    tool_time: Mutex<BTreeMap<String, (u64, f64)>>,
+    /// (provider, input|output) -> tokens
+    tokens: Mutex<BTreeMap<(String, &'static str), u64>>,
+    /// provider -> duration of the brain steps
+    llm_latency: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn session_created(&self) {
+        self.sessions_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn request_handled(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count the tool calls and tokens of an event of a session running on `provider`
+    pub fn record_event(&self, provider: &str, event: &AgentEvent) {
+        match event {
+            AgentEvent::ToolCallCompleted { call, result, .. } => {
+                let outcome = match result {
+                    ToolResult::Success { .. } => "success",
+                    ToolResult::Error { .. } => "error",
+                    ToolResult::Denied => "denied",
+                };
+                *self.tool_calls.lock().unwrap().entry((call.tool_name.clone(), outcome)).or_default() += 1;
+                // denied and cancelled calls carry no duration: they never ran
+                if let Some(duration) = result.duration() {
+                    let mut tool_time = self.tool_time.lock().unwrap();
+                    let (count, sum) = tool_time.entry(call.tool_name.clone()).or_default();
+                    *count += 1;
+                    *sum += duration.as_secs_f64();
+                }
+            }
+            AgentEvent::TokenUsage { input_tokens, output_tokens } => {
+                let mut tokens = self.tokens.lock().unwrap();
+                *tokens.entry((provider.to_string(), "input")).or_default() += *input_tokens as u64;
+                *tokens.entry((provider.to_string(), "output")).or_default() += *output_tokens as u64;
+            }
+            _ => {}
+        }
+    }
+
+    /// Time between the start of a brain step and its result
+    pub fn observe_llm_latency(&self, provider: &str, latency: Duration) {
+        self.llm_latency.lock().unwrap().entry(provider.to_string()).or_default().observe(latency.as_secs_f64());
+    }
+
+    /// Prometheus text exposition format
+    pub fn render(&self, active_sessions: usize) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "shai_sessions_active", "gauge", "Sessions running in memory");
+        let _ = writeln!(out, "shai_sessions_active {}", active_sessions);
+
+        header(&mut out, "shai_sessions_created_total", "counter", "Sessions created, including the ones reloaded from disk");
+        let _ = writeln!(out, "shai_sessions_created_total {}", self.sessions_created.load(Ordering::Relaxed));
+
+        header(&mut out, "shai_requests_total", "counter", "Requests handled by the agents");
+        let _ = writeln!(out, "shai_requests_total {}", self.requests.load(Ordering::Relaxed));
+
+        header(&mut out, "shai_tool_calls_total", "counter", "Completed tool calls by tool and result");
+        for ((tool, result), count) in self.tool_calls.lock().unwrap().iter() {
+            let _ = writeln!(out, "shai_tool_calls_total{{tool=\"{}\",result=\"{}\"}} {}", escape(tool), result, count);
+        }
+
+        header(&mut out, "shai_tool_call_duration_seconds", "summary", "Time spent running the tool calls by tool");
+        for (tool, (count, sum)) in self.tool_time.lock().unwrap().iter() {
+            let tool = escape(tool);
+            let _ = writeln!(out, "shai_tool_call_duration_seconds_sum{{tool=\"{}\"}} {}", tool, sum);
+            let _ = writeln!(out, "shai_tool_call_duration_seconds_count{{tool=\"{}\"}} {}", tool, count);
+        }
+
+        header(&mut out, "shai_tokens_total", "counter", "Tokens used by the LLM calls by provider");
+        for ((provider, kind), count) in self.tokens.lock().unwrap().iter() {
+            let _ = writeln!(out, "shai_tokens_total{{provider=\"{}\",kind=\"{}\"}} {}", escape(provider), kind, count);
+        }
+
+        header(&mut out, "shai_llm_request_duration_seconds", "histogram", "Duration of the LLM calls (brain steps) by provider");
+        for (provider, histogram) in self.llm_latency.lock().unwrap().iter() {
+            let provider = escape(provider);
+            for (bound, count) in LLM_LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(out, "shai_llm_request_duration_seconds_bucket{{provider=\"{}\",le=\"{}\"}} {}", provider, bound, count);
+            }
+            let _ = writeln!(out, "shai_llm_request_duration_seconds_bucket{{provider=\"{}\",le=\"+Inf\"}} {}", provider, histogram.count);
+            let _ = writeln!(out, "shai_llm_request_duration_seconds_sum{{provider=\"{}\"}} {}", provider, histogram.sum);
+            let _ = writeln!(out, "shai_llm_request_duration_seconds_count{{provider=\"{}\"}} {}", provider, histogram.count);
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Label values escape backslashes, quotes and newlines
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// GET /metrics - Server counters in Prometheus text format, only routed with `ServerConfig::with_metrics(true)`
+pub async fn handle_metrics(State(state): State<ServerState>) -> Response {
+    let active_sessions = state.session_manager.session_count().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.session_manager.metrics().render(active_sessions),
+    ).into_response()
+}
@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+use serde::Serialize;
+use shai_core::agent::{AgentEvent, PublicAgentState};
+use shai_core::tools::{TodoItem, ToolResult};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Events kept per session for polling clients when nothing else is configured
+pub const DEFAULT_EVENT_BUFFER_SIZE: usize = 1000;
+
+/// Agent state as shown to HTTP clients, e.g. "paused" or "processing:bash"
+pub fn status_label(state: &PublicAgentState) -> String {
+    match state {
+        PublicAgentState::Starting => "starting".to_string(),
+        PublicAgentState::Running => "running".to_string(),
+        PublicAgentState::Processing { task_name, .. } => format!("processing:{}", task_name),
+        PublicAgentState::Paused => "paused".to_string(),
+        PublicAgentState::Completed { success: true } => "completed".to_string(),
+        PublicAgentState::Completed { success: false } => "failed".to_string(),
+        PublicAgentState::Cancelled => "cancelled".to_string(),
+        PublicAgentState::Failed { error } => format!("failed: {}", error),
+    }
+}
+
+/// Agent event as returned to polling clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PolledEvent {
+    Status { status: String },
+    Assistant {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        content: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reasoning: Option<String>,
+    },
+    ToolCall { call_id: String, tool: String, parameters: serde_json::Value },
+    ToolResult { call_id: String, tool: String, success: bool, output: String },
+    TodoUpdated { todos: Vec<TodoItem> },
+    Completed { success: bool, message: String },
+    Error { error: String },
+}
+
+impl PolledEvent {
+    /// Only the events a client can act on are kept
+    fn from_event(event: &AgentEvent) -> Option<Self> {
+        match event {
+            AgentEvent::StatusChanged { new_status, .. } => Some(PolledEvent::Status { status: status_label(new_status) }),
+            AgentEvent::BrainResult { thought: Ok(ChatMessage::Assistant { content, reasoning_content, .. }), .. } => {
+                let content = match content {
+                    Some(ChatMessageContent::Text(text)) if !text.trim().is_empty() => Some(text.clone()),
+                    _ => None,
+                };
+                let reasoning = reasoning_content.clone().filter(|r| !r.trim().is_empty());
+                (content.is_some() || reasoning.is_some()).then_some(PolledEvent::Assistant { content, reasoning })
+            }
+            AgentEvent::BrainResult { thought: Err(error), .. } => Some(PolledEvent::Error { error: error.to_string() }),
+            AgentEvent::ToolCallStarted { call, .. } => Some(PolledEvent::ToolCall {
+                call_id: call.tool_call_id.clone(),
+                tool: call.tool_name.clone(),
+                parameters: call.parameters.clone(),
+            }),
+            AgentEvent::ToolCallCompleted { call, result, .. } => Some(PolledEvent::ToolResult {
+                call_id: call.tool_call_id.clone(),
+                tool: call.tool_name.clone(),
+                success: result.is_success(),
+                output: match result {
+                    ToolResult::Success { output, .. } => output.clone(),
+                    ToolResult::Error { error, .. } => error.clone(),
+                    ToolResult::Denied => "Tool call denied".to_string(),
+                },
+            }),
+            AgentEvent::TodoUpdated { todos } => Some(PolledEvent::TodoUpdated { todos: todos.clone() }),
+            AgentEvent::Completed { success, message } => Some(PolledEvent::Completed { success: *success, message: message.clone() }),
+            AgentEvent::Error { error } => Some(PolledEvent::Error { error: error.clone() }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferedEvent {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: PolledEvent,
+}
+
+/// Events after a given sequence number
+#[derive(Debug, Clone)]
+pub struct EventPage {
+    pub events: Vec<BufferedEvent>,
+    /// sequence number to poll from next time
+    pub next_since: u64,
+    /// events after `since` that were already dropped from the buffer
+    pub missed: u64,
+}
+
+struct BufferInner {
+    events: VecDeque<BufferedEvent>,
+    last_seq: u64,
+}
+
+/// Ring buffer of the latest events of a session, numbered from 1 in emission order.
+/// Fed from the session broadcast channel, read by clients that can't hold a stream open.
+pub struct EventBuffer {
+    inner: Mutex<BufferInner>,
+    notify: Notify,
+    capacity: usize,
+}
+
+impl EventBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(BufferInner { events: VecDeque::new(), last_seq: 0 }),
+            notify: Notify::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Number the event and keep it, the oldest one is dropped once the buffer is full
+    pub fn push(&self, event: &AgentEvent) {
+        let Some(event) = PolledEvent::from_event(event) else {
+            return;
+        };
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.last_seq += 1;
+            let seq = inner.last_seq;
+            inner.events.push_back(BufferedEvent { seq, timestamp: Utc::now(), event });
+            while inner.events.len() > self.capacity {
+                inner.events.pop_front();
+            }
+        }
+        self.notify.notify_waiters();
+    }
+
+    /// Sequence number of the latest event, 0 before the first one
+    pub fn last_seq(&self) -> u64 {
+        self.inner.lock().unwrap().last_seq
+    }
+
+    /// Buffered events with a sequence number greater than `since`
+    pub fn since(&self, since: u64) -> EventPage {
+        let inner = self.inner.lock().unwrap();
+        let first_kept = inner.events.front().map_or(inner.last_seq + 1, |e| e.seq);
+        let events: Vec<BufferedEvent> = inner.events.iter()
+            .filter(|e| e.seq > since)
+            .cloned()
+            .collect();
+        EventPage {
+            next_since: events.last().map_or(since.min(inner.last_seq), |e| e.seq),
+            missed: first_kept.saturating_sub(since + 1),
+            events,
+        }
+    }
+
+    /// Same as `since`, but waits up to `timeout` for a new event when there is none yet
+    pub async fn wait_since(&self, since: u64, timeout: Duration) -> EventPage {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let page = self.since(since);
+            if !page.events.is_empty() {
+                return page;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.since(since);
+            }
+        }
+    }
+}
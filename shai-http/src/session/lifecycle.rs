@@ -6,11 +6,15 @@ use crate::session::logger::colored_session_id;
 use crate::session::persist::SessionPersist;
 
 
+/// Holds the controller of a session for one request, released when the request ends or its client goes away
 pub enum RequestLifecycle {
     Background {
         controller_guard: OwnedMutexGuard<AgentController>,
         request_id: String,
         session_id: String,
+        /// stop the current task when dropped before `finish`, the client went away
+        stop_on_disconnect: bool,
+        finished: bool,
     },
     Ephemeral {
         controller_guard: OwnedMutexGuard<AgentController>,
@@ -20,10 +24,17 @@ pub enum RequestLifecycle {
 }
 
 impl RequestLifecycle {
-    pub fn new(ephemeral: bool, controller_guard: OwnedMutexGuard<AgentController>, request_id: String, session_id: String) -> Self {
+    pub fn new(ephemeral: bool, stop_on_disconnect: bool, controller_guard: OwnedMutexGuard<AgentController>, request_id: String, session_id: String) -> Self {
         match ephemeral {
             true => Self::Ephemeral { controller_guard, request_id, session_id },
-            false => Self::Background { controller_guard, request_id, session_id },
+            false => Self::Background { controller_guard, request_id, session_id, stop_on_disconnect, finished: false },
+        }
+    }
+
+    /// The response reached its end, dropping the lifecycle from now on is not a client disconnect
+    pub fn finish(&mut self) {
+        if let Self::Background { finished, .. } = self {
+            *finished = true;
         }
     }
 }
@@ -31,7 +42,18 @@ impl RequestLifecycle {
 impl Drop for RequestLifecycle {
     fn drop(&mut self) {
         match self {
-            Self::Background { controller_guard, request_id, session_id } => {
+            Self::Background { controller_guard, request_id, session_id, stop_on_disconnect, finished } => {
+                // queued while the guard is held, so the stop can't reach the next request
+                if *stop_on_disconnect && !*finished {
+                    info!(
+                        "[{}] - {} Client disconnected, stopping the current task",
+                        request_id,
+                        colored_session_id(session_id)
+                    );
+                    if let Err(e) = controller_guard.stop_current_task_now() {
+                        warn!("Failed to stop session {}: {}", session_id, e);
+                    }
+                }
                 info!(
                     "[{}] - {} Stream completed, releasing controller lock (background session)",
                     request_id,
@@ -54,6 +54,9 @@ pub fn log_event(event: &AgentEvent, session_id: &str) {
             info!("{} - Completed: success={} msg={}", 
                 session_id, success, message);
         }
+        AgentEvent::TodoUpdated { todos } => {
+            debug!("{} - TodoUpdated: {} items", session_id, todos.len());
+        }
         _ => {}
     }
 }
\ No newline at end of file
@@ -1,13 +1,20 @@
-use shai_core::agent::{Agent, AgentError};
+use shai_core::agent::{Agent, AgentError, AgentEvent};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{error, info};
+use std::time::Duration;
+use futures::future::join_all;
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
 use openai_dive::v1::resources::chat::ChatMessage;
 
 use shai_core::agent::AgentBuilder;
+use shai_core::agent::builder::DEFAULT_PERMISSION_TIMEOUT;
+use shai_core::config::agent::{AgentConfig, AgentProviderConfig};
+use crate::metrics::Metrics;
 use crate::session::{log_event, logger::colored_session_id};
 use crate::session::persist::SessionPersist;
+use crate::session::buffer::{EventBuffer, DEFAULT_EVENT_BUFFER_SIZE};
 
 use super::AgentSession;
 
@@ -18,6 +25,14 @@ pub struct SessionManagerConfig {
     pub max_sessions: Option<usize>,
     /// Whether sessions are ephemeral or background (ephemeral session is destroyed after a single query)
     pub ephemeral: bool,
+    /// Unanswered permission requests are denied after this delay (None = wait forever)
+    pub permission_timeout: Option<Duration>,
+    /// Number of events each session keeps for polling clients
+    pub event_buffer_size: usize,
+    /// Save background sessions to disk after every N completed tool calls (None = only when a request ends)
+    pub checkpoint_every: Option<u32>,
+    /// Stop the current task of a background session when its client disconnects before the end of the request
+    pub stop_on_disconnect: bool,
 }
 
 impl Default for SessionManagerConfig {
@@ -25,6 +40,10 @@ impl Default for SessionManagerConfig {
         Self {
             max_sessions: Some(100),
             ephemeral: false,
+            permission_timeout: Some(DEFAULT_PERMISSION_TIMEOUT),
+            event_buffer_size: DEFAULT_EVENT_BUFFER_SIZE,
+            checkpoint_every: None,
+            stop_on_disconnect: true,
         }
     }
 }
@@ -34,7 +53,12 @@ impl Default for SessionManagerConfig {
 pub struct SessionManager {
     sessions: Arc<Mutex<HashMap<String, Arc<AgentSession>>>>,
     max_sessions: Option<usize>,
-    ephemeral: bool
+    ephemeral: bool,
+    permission_timeout: Option<Duration>,
+    event_buffer_size: usize,
+    checkpoint_every: Option<u32>,
+    stop_on_disconnect: bool,
+    metrics: Arc<Metrics>,
 }
 
 impl SessionManager {
@@ -42,10 +66,20 @@ impl SessionManager {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
             max_sessions: config.max_sessions,
-            ephemeral: config.ephemeral
+            ephemeral: config.ephemeral,
+            permission_timeout: config.permission_timeout,
+            event_buffer_size: config.event_buffer_size,
+            checkpoint_every: config.checkpoint_every.filter(|&every| every > 0),
+            stop_on_disconnect: config.stop_on_disconnect,
+            metrics: Arc::new(Metrics::new()),
         }
     }
 
+    /// Counters of the sessions, served by GET /metrics
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     async fn create_session(
         &self,
         http_request_id: &String,
@@ -60,6 +94,8 @@ impl SessionManager {
         let mut builder = AgentBuilder::create(agent_name.clone().filter(|name| name != "default"))
             .await
             .map_err(|e| AgentError::ExecutionError(format!("Failed to create agent: {}", e)))?
+            .permission_timeout(self.permission_timeout)
+            .stream_deltas(true)
             .sudo();
 
         if let Some(trace) = trace {
@@ -70,13 +106,55 @@ impl SessionManager {
 
         let controller = agent.controller();
         let event_rx = agent.watch();
+        self.metrics.session_created();
+        let metrics = self.metrics.clone();
+        let provider = provider_name(agent_name.as_deref());
 
-        // Spawn logging task alongside agent
+        // Spawn logging task alongside agent, it also feeds the buffer of polling clients
+        // and checkpoints background sessions as their tool calls complete
+        let events = Arc::new(EventBuffer::new(self.event_buffer_size));
+        let events_for_logger = events.clone();
         let mut event_for_logger = event_rx.resubscribe();
         let sid_for_logger = session_id.to_string();
+        let checkpoint_every = self.checkpoint_every.filter(|_| !ephemeral);
+        let controller_for_checkpoint = controller.clone();
         let logging_task = tokio::spawn(async move {
-            while let Ok(event) = event_for_logger.recv().await {
-                log_event(&event, &sid_for_logger);
+            let mut tools_completed: u32 = 0;
+            let mut thinking_since: Option<Instant> = None;
+            loop {
+                match event_for_logger.recv().await {
+                    Ok(event) => {
+                        log_event(&event, &sid_for_logger);
+                        events_for_logger.push(&event);
+                        metrics.record_event(&provider, &event);
+                        match &event {
+                            AgentEvent::ThinkingStart => thinking_since = Some(Instant::now()),
+                            AgentEvent::BrainResult { .. } => {
+                                if let Some(since) = thinking_since.take() {
+                                    metrics.observe_llm_latency(&provider, since.elapsed());
+                                }
+                            }
+                            _ => {}
+                        }
+
+                        if let (Some(every), AgentEvent::ToolCallCompleted { .. }) = (checkpoint_every, &event) {
+                            tools_completed += 1;
+                            if tools_completed % every == 0 {
+                                // the agent answers from its own loop, the next events are not held up meanwhile
+                                let controller = controller_for_checkpoint.clone();
+                                let sid = sid_for_logger.clone();
+                                tokio::spawn(async move {
+                                    match SessionPersist::checkpoint(&sid, &controller).await {
+                                        Ok(checkpoint) => debug!("{} - checkpoint saved ({} messages)", colored_session_id(&sid), checkpoint.trace.len()),
+                                        Err(e) => warn!("{} - checkpoint failed: {}", colored_session_id(&sid), e),
+                                    }
+                                });
+                            }
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
             }
         });
 
@@ -100,10 +178,13 @@ impl SessionManager {
             session_id.to_string(),
             controller,
             event_rx,
+            events,
             logging_task,
             agent_task,
             agent_name,
             ephemeral,
+            self.stop_on_disconnect,
+            self.metrics.clone(),
         ));
 
         Ok(session)
@@ -201,6 +282,42 @@ impl SessionManager {
         Ok(session)
     }
 
+    /// Get a session only if it is currently running in memory
+    pub async fn find_session(&self, session_id: &str) -> Option<Arc<AgentSession>> {
+        self.sessions.lock().await.get(session_id).cloned()
+    }
+
+    /// Sessions running in memory, oldest first
+    pub async fn list_sessions(&self) -> Vec<Arc<AgentSession>> {
+        let mut sessions: Vec<_> = self.sessions.lock().await.values().cloned().collect();
+        sessions.sort_by_key(|session| session.created_at);
+        sessions
+    }
+
+    /// Stop a running session right away and drop it from the manager, false if it isn't running.
+    /// Its saved trace stays on disk, the next request to the id reloads it
+    pub async fn terminate_session(&self, http_request_id: &String, session_id: &str) -> bool {
+        let Some(session) = self.sessions.lock().await.remove(session_id) else {
+            return false;
+        };
+        if let Err(e) = session.terminate(http_request_id).await {
+            // the agent had stopped on its own, removing the session is all there was to do
+            warn!("[{}] - {} agent already stopped: {}", http_request_id, colored_session_id(session_id), e);
+        }
+        true
+    }
+
+    /// Stop every session for a server shutdown, giving their tool calls up to `grace` to finish
+    pub async fn shutdown(&self, grace: Duration) {
+        let sessions: Vec<_> = self.sessions.lock().await.drain().map(|(_, session)| session).collect();
+        if sessions.is_empty() {
+            return;
+        }
+        info!("Stopping {} session(s), waiting up to {}s for running tools", sessions.len(), grace.as_secs());
+        let deadline = Instant::now() + grace;
+        join_all(sessions.iter().map(|session| session.shutdown(deadline))).await;
+    }
+
     /// Cancel a session (stop the agent)
     pub async fn cancel_session(&self, http_request_id: &String, session_id: &str) -> Result<(), AgentError> {
         if let Some(session) = self.sessions.lock().await.get(session_id) {
@@ -214,3 +331,11 @@ impl SessionManager {
         self.sessions.lock().await.len()
     }
 }
+
+/// Provider the agent of a session runs on, the metrics label of its LLM calls
+fn provider_name(agent_name: Option<&str>) -> String {
+    match agent_name.filter(|name| *name != "default") {
+        Some(name) => AgentConfig::load(name).ok().map(|config| config.llm_provider.provider),
+        None => AgentProviderConfig::selected().ok().map(|provider| provider.provider),
+    }.unwrap_or_else(|| "unknown".to_string())
+}
@@ -3,10 +3,15 @@ mod session;
 mod manager;
 mod logger;
 mod persist;
+mod buffer;
+
+#[cfg(test)]
+mod tests;
 
 pub use logger::log_event;
 pub use lifecycle::{RequestLifecycle};
-pub use session::{AgentSession, RequestSession};
+pub use session::{AgentSession, RequestOptions, RequestSession};
 pub use manager::{SessionManager, SessionManagerConfig};
-pub use persist::{SessionPersist, SessionData};
+pub use persist::{SessionPersist, SessionData, ToolIdCheck};
+pub use buffer::{EventBuffer, EventPage, BufferedEvent, PolledEvent, DEFAULT_EVENT_BUFFER_SIZE, status_label};
 
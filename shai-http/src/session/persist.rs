@@ -4,7 +4,9 @@ use std::path::PathBuf;
 use chrono::{DateTime, Utc};
 use openai_dive::v1::resources::chat::ChatMessage;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error};
+use shai_core::agent::{repair_tool_call_ids, AgentCheckpoint, AgentController, TokenTotals};
+use crate::session::status_label;
+use tracing::{debug, error, warn};
 use uuid::Uuid;
 
 /// Session data stored on disk
@@ -14,6 +16,22 @@ pub struct SessionData {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub trace: Vec<ChatMessage>,
+    /// agent state when the session was checkpointed, absent when it was saved at the end of a request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenTotals>,
+}
+
+/// What to do when the tool results of a reloaded trace don't match its assistant tool calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolIdCheck {
+    /// load the trace as saved
+    Off,
+    /// log the mismatches but keep the trace as saved
+    Warn,
+    /// log the mismatches, drop orphan tool results and close unanswered tool calls
+    Repair,
 }
 
 /// Handle session persistence to disk
@@ -36,6 +54,15 @@ impl SessionPersist {
             .unwrap_or_else(|_| PathBuf::from(".shai/sessions"))
     }
 
+    /// How reloaded traces are checked, via SHAI_SESSION_PERSIST_TOOL_IDS (off, warn or repair, the default)
+    pub fn tool_id_check() -> ToolIdCheck {
+        match std::env::var("SHAI_SESSION_PERSIST_TOOL_IDS").map(|v| v.to_lowercase()).as_deref() {
+            Ok("off") => ToolIdCheck::Off,
+            Ok("warn") => ToolIdCheck::Warn,
+            _ => ToolIdCheck::Repair,
+        }
+    }
+
     /// Get the file path for a specific session
     fn session_file_path(session_id: &str) -> PathBuf {
         Self::folder().join(format!("{}.json", session_id))
@@ -45,6 +72,28 @@ impl SessionPersist {
     pub fn save_session(
         session_id: &str,
         trace: Vec<ChatMessage>,
+    ) -> Result<(), PersistError> {
+        Self::write_session(session_id, trace, None, None)
+    }
+
+    /// Snapshot a running agent and save it right away, without waiting for its request to end.
+    /// A tool call still running is saved unanswered, the reload closes it as interrupted
+    pub async fn checkpoint(session_id: &str, controller: &AgentController) -> Result<AgentCheckpoint, PersistError> {
+        let checkpoint = controller.checkpoint().await?;
+        Self::write_session(
+            session_id,
+            checkpoint.trace.clone(),
+            Some(status_label(&checkpoint.state)),
+            Some(checkpoint.token_usage),
+        )?;
+        Ok(checkpoint)
+    }
+
+    fn write_session(
+        session_id: &str,
+        trace: Vec<ChatMessage>,
+        status: Option<String>,
+        token_usage: Option<TokenTotals>,
     ) -> Result<(), PersistError> {
         if !Self::is_enabled() {
             return Ok(());
@@ -80,6 +129,8 @@ impl SessionPersist {
             created_at,
             updated_at,
             trace,
+            status,
+            token_usage,
         };
 
         // Serialize to JSON
@@ -119,7 +170,18 @@ impl SessionPersist {
 
         // Read and parse the session file
         let content = fs::read_to_string(&file_path)?;
-        let session_data: SessionData = serde_json::from_str(&content)?;
+        let mut session_data: SessionData = serde_json::from_str(&content)?;
+
+        let check = Self::tool_id_check();
+        if check != ToolIdCheck::Off {
+            let (repaired, issues) = repair_tool_call_ids(session_data.trace.clone());
+            for issue in &issues {
+                warn!("Session {} - {}", session_id, issue);
+            }
+            if check == ToolIdCheck::Repair && !issues.is_empty() {
+                session_data.trace = repaired;
+            }
+        }
 
         debug!("Loaded session from disk: {}", session_id);
         Ok(session_data)
@@ -1,13 +1,21 @@
-use shai_core::agent::{AgentController, AgentError, AgentEvent};
+use chrono::{DateTime, Utc};
+use shai_core::agent::{AgentCheckpoint, AgentController, AgentError, AgentEvent, AgentRequest, AgentResponse, PublicAgentState, SamplingOverrides, SystemPromptOverride, TokenTotals};
+use shai_core::tools::{TodoItem, TodoStatus};
 use openai_dive::v1::resources::chat::ChatMessage;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{broadcast::Receiver, Mutex};
 use tokio::task::JoinHandle;
-use tracing::info;
+use tokio::time::{sleep, Instant};
+use tracing::{info, warn};
 use crate::session::logger::colored_session_id;
+use crate::session::persist::SessionPersist;
+use crate::metrics::Metrics;
 
-use super::RequestLifecycle;
+use super::{EventBuffer, RequestLifecycle};
 
+/// How often a session shutting down checks whether its tool calls are done
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Represents a single HTTP request session with automatic lifecycle management
 pub struct RequestSession {
@@ -16,20 +24,39 @@ pub struct RequestSession {
     pub lifecycle: RequestLifecycle
 }
 
+/// Generation settings of one request, applied to the agent before the request's messages
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    pub sampling: SamplingOverrides,
+    /// instructions of the request, added to or in place of the agent's system prompt
+    pub system_prompt: Option<SystemPromptOverride>,
+    /// false runs the tool calls of a batch one after the other, None lets them run at once
+    pub parallel_tool_calls: Option<bool>,
+}
+
 /// A single agent session - represents one running agent instance
 /// Can be ephemeral (destroyed after request) or persistent (kept alive)
 /// Each request holds a guard against the controller so that only one query is processed per session
 /// - In background mode (ephemeral=false), the session survives the request and the guard is simply drop
 /// - In ephemeral mode (ephemeral=true), the entire session stops and is deleted once the query ends or the client disconnect
+/// - A background session whose client disconnects mid-request stops its current task, unless `stop_on_disconnect` is off
 pub struct AgentSession {
     controller: Arc<Mutex<AgentController>>,
+    /// unguarded handle for side commands (todos) that may run while a request holds the guard
+    side_controller: AgentController,
     event_rx: Receiver<AgentEvent>,
+    /// latest events, for clients polling instead of streaming
+    events: Arc<EventBuffer>,
     logging_task: JoinHandle<()>,
     agent_task: JoinHandle<()>,
+    metrics: Arc<Metrics>,
 
     pub session_id: String,
     pub agent_name: String,
     pub ephemeral: bool,
+    /// pause the agent when the client of a request goes away before its end (background sessions)
+    pub stop_on_disconnect: bool,
+    pub created_at: DateTime<Utc>,
 }
 
 impl AgentSession {
@@ -37,21 +64,29 @@ impl AgentSession {
         session_id: String,
         controller: AgentController,
         event_rx: Receiver<AgentEvent>,
+        events: Arc<EventBuffer>,
         agent_task: JoinHandle<()>,
         logging_task: JoinHandle<()>,
         agent_name: Option<String>,
         ephemeral: bool,
+        stop_on_disconnect: bool,
+        metrics: Arc<Metrics>,
     ) -> Self {
         let agent_name_display = agent_name.unwrap_or_else(|| "default".to_string());
 
         Self {
+            side_controller: controller.clone(),
             controller: Arc::new(Mutex::new(controller)),
             event_rx,
+            events,
             logging_task,
             agent_task,
+            metrics,
             session_id,
             agent_name: agent_name_display,
             ephemeral: ephemeral,
+            stop_on_disconnect,
+            created_at: Utc::now(),
         }
     }
 
@@ -62,24 +97,108 @@ impl AgentSession {
         ctrl.terminate().await
     }
 
+    /// Stop the agent now, without waiting for the request in progress to end
+    pub async fn terminate(&self, http_request_id: &String) -> Result<(), AgentError> {
+        info!("[{}] - {} terminating session", http_request_id, colored_session_id(&self.session_id));
+        self.side_controller.terminate().await
+    }
+
+    /// Stop the agent for a server shutdown: tool calls and formatting in progress are let finish
+    /// until `deadline` so no file is left half-written, then the trace of a background session
+    /// is saved and the agent terminated. A brain step is cancelled right away, it changes nothing
+    pub async fn shutdown(&self, deadline: Instant) {
+        loop {
+            match self.side_controller.get_state().await {
+                Ok(PublicAgentState::Processing { task_name, .. }) if task_name == "tools" || task_name == "format" => {
+                    if Instant::now() >= deadline {
+                        warn!("{} - grace period over, cancelling {}", colored_session_id(&self.session_id), task_name);
+                        break;
+                    }
+                    sleep(SHUTDOWN_POLL_INTERVAL).await;
+                }
+                Ok(_) => break,
+                // the agent already stopped
+                Err(_) => return,
+            }
+        }
+
+        if !self.ephemeral {
+            match self.side_controller.get_trace().await {
+                Ok(trace) => {
+                    if let Err(e) = SessionPersist::save_session(&self.session_id, trace) {
+                        warn!("Failed to save session {}: {}", self.session_id, e);
+                    }
+                }
+                Err(e) => warn!("Failed to get trace for session {}: {}", self.session_id, e),
+            }
+        }
+        let _ = self.side_controller.terminate().await;
+        info!("{} - session stopped for shutdown", colored_session_id(&self.session_id));
+    }
+
+    /// Save the session to disk now, even while a request is being handled
+    pub async fn checkpoint(&self, http_request_id: &String) -> Result<AgentCheckpoint, Box<dyn std::error::Error + Send + Sync>> {
+        info!("[{}] - {} checkpointing session", http_request_id, colored_session_id(&self.session_id));
+        SessionPersist::checkpoint(&self.session_id, &self.side_controller).await
+    }
+
+    /// Live state of the agent, asked to the agent itself
+    pub async fn state(&self) -> Result<PublicAgentState, AgentError> {
+        self.side_controller.get_state().await
+    }
+
+    /// Conversation trace of the agent, read without waiting for the request in progress
+    pub async fn trace(&self) -> Result<Vec<ChatMessage>, AgentError> {
+        self.side_controller.get_trace().await
+    }
+
+    pub async fn token_usage(&self) -> Result<(TokenTotals, Option<u64>), AgentError> {
+        self.side_controller.get_token_usage().await
+    }
+
+    /// Whether a request is being handled, the next one waits for it
+    pub fn is_busy(&self) -> bool {
+        self.controller.try_lock().is_err()
+    }
+
+    /// Forward a command from an interactive client (user input, permission answer, stop...),
+    /// without waiting for the request in progress
+    pub async fn send(&self, http_request_id: &String, request: AgentRequest) -> Result<(), AgentError> {
+        info!("[{}] - {} forwarding {:?}", http_request_id, colored_session_id(&self.session_id), request);
+        match self.side_controller.send(request).await? {
+            AgentResponse::Error { error } => Err(AgentError::ExecutionError(error)),
+            _ => Ok(()),
+        }
+    }
+
     /// Subscribe to events from this session (read-only, non-blocking)
     /// Used for GET /v1/responses/{response_id} to observe an ongoing session
     pub fn watch(&self) -> Receiver<AgentEvent> {
         self.event_rx.resubscribe()
     }
 
-    /// Handle a request for this agent session
+    /// Buffered events of this session, used by GET /v1/sessions/{session_id}/events
+    pub fn events(&self) -> &EventBuffer {
+        &self.events
+    }
+
+    /// Handle a request for this agent session, generating with the options of the request
     /// Returns a RequestSession that manages the lifecycle
-    pub async fn handle_request(&self, http_request_id: &String, trace: Vec<ChatMessage>) -> Result<RequestSession, AgentError> {
+    pub async fn handle_request(&self, http_request_id: &String, trace: Vec<ChatMessage>, options: RequestOptions) -> Result<RequestSession, AgentError> {
         let controller_guard = self.controller.clone().lock_owned().await;
         controller_guard.wait_turn(None).await?;
         info!("[{}] - {} handling request", http_request_id, colored_session_id(&self.session_id));
+        self.metrics.request_handled();
 
+        // set for every request, one without options goes back to the brain's own
+        controller_guard.set_sampling(options.sampling).await?;
+        controller_guard.set_system_prompt(options.system_prompt).await?;
+        controller_guard.set_parallel_tool_calls(options.parallel_tool_calls.unwrap_or(true)).await?;
         controller_guard.send_trace(trace).await?;
 
         let event_rx = self.event_rx.resubscribe();
         let controller = controller_guard.clone();
-        let lifecycle = RequestLifecycle::new(self.ephemeral, controller_guard, http_request_id.clone(), self.session_id.clone());
+        let lifecycle = RequestLifecycle::new(self.ephemeral, self.stop_on_disconnect, controller_guard, http_request_id.clone(), self.session_id.clone());
 
         Ok(RequestSession{controller, event_rx, lifecycle})
     }
@@ -87,6 +206,25 @@ impl AgentSession {
     pub fn is_ephemeral(&self) -> bool {
         self.ephemeral
     }
+
+    pub async fn get_todos(&self) -> Result<Vec<TodoItem>, AgentError> {
+        self.side_controller.get_todos().await
+    }
+
+    pub async fn add_todo(&self, http_request_id: &String, content: String) -> Result<Vec<TodoItem>, AgentError> {
+        info!("[{}] - {} adding todo", http_request_id, colored_session_id(&self.session_id));
+        self.side_controller.add_todo(content).await
+    }
+
+    pub async fn set_todo_status(&self, http_request_id: &String, id: String, status: TodoStatus) -> Result<Vec<TodoItem>, AgentError> {
+        info!("[{}] - {} updating todo {}", http_request_id, colored_session_id(&self.session_id), id);
+        self.side_controller.set_todo_status(id, status).await
+    }
+
+    pub async fn remove_todo(&self, http_request_id: &String, id: String) -> Result<Vec<TodoItem>, AgentError> {
+        info!("[{}] - {} removing todo {}", http_request_id, colored_session_id(&self.session_id), id);
+        self.side_controller.remove_todo(id).await
+    }
 }
 
 impl Drop for AgentSession {
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent};
+use shai_core::agent::{AgentBuilder, AgentController, AgentError, AgentEvent, Brain, PublicAgentState, ThinkerContext, ThinkerDecision};
+use tokio::sync::Mutex;
+
+use super::{EventBuffer, PolledEvent, RequestLifecycle};
+
+/// Thinks for a long time, so that the agent is still busy when its request ends
+struct SlowBrain;
+
+#[async_trait]
+impl Brain for SlowBrain {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("done".to_string())),
+            reasoning_content: None,
+            tool_calls: None,
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+/// A running agent busy with its first step, and the lifecycle of a request of its background session
+async fn busy_session() -> (AgentController, RequestLifecycle) {
+    // the lifecycles save the trace when dropped, not in the working directory of the tests
+    std::env::set_var("SHAI_SESSION_PERSIST_ENABLE", "false");
+
+    let mut agent = AgentBuilder::with_brain(Box::new(SlowBrain))
+        .id("test-lifecycle-agent")
+        .goal("Think for a while")
+        .build();
+    let controller = agent.controller();
+    tokio::spawn(async move { agent.run().await });
+    wait_for_state(&controller, |state| matches!(state, PublicAgentState::Processing { .. })).await;
+
+    let guard = Arc::new(Mutex::new(controller.clone())).lock_owned().await;
+    let lifecycle = RequestLifecycle::new(false, true, guard, "request".to_string(), "session".to_string());
+    (controller, lifecycle)
+}
+
+async fn wait_for_state(controller: &AgentController, expected: impl Fn(&PublicAgentState) -> bool) -> bool {
+    for _ in 0..100 {
+        if controller.get_state().await.is_ok_and(|state| expected(&state)) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_client_disconnect_stops_the_current_task() {
+    let (controller, lifecycle) = busy_session().await;
+
+    // dropped before the end of the response, like an SSE stream whose client went away
+    drop(lifecycle);
+    assert!(wait_for_state(&controller, |state| matches!(state, PublicAgentState::Paused)).await);
+}
+
+#[tokio::test]
+async fn test_finished_request_leaves_the_task_running() {
+    let (controller, mut lifecycle) = busy_session().await;
+
+    lifecycle.finish();
+    drop(lifecycle);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let state = controller.get_state().await.unwrap();
+    assert!(matches!(state, PublicAgentState::Processing { .. }), "{:?}", state);
+}
+
+fn error_event(n: u64) -> AgentEvent {
+    AgentEvent::Error { error: format!("error {}", n) }
+}
+
+/// A buffer of `capacity` events that received `count` of them, numbered from 1
+fn buffer_with(capacity: usize, count: u64) -> EventBuffer {
+    let buffer = EventBuffer::new(capacity);
+    for n in 1..=count {
+        buffer.push(&error_event(n));
+    }
+    buffer
+}
+
+fn seqs(events: &[super::BufferedEvent]) -> Vec<u64> {
+    events.iter().map(|event| event.seq).collect()
+}
+
+#[test]
+fn test_events_since_a_sequence_number() {
+    let buffer = buffer_with(10, 3);
+    // events the client can't act on are not buffered, nor numbered
+    buffer.push(&AgentEvent::ThinkingStart);
+    assert_eq!(buffer.last_seq(), 3);
+
+    let page = buffer.since(0);
+    assert_eq!(seqs(&page.events), vec![1, 2, 3]);
+    assert!(matches!(&page.events[0].event, PolledEvent::Error { error } if error == "error 1"));
+    assert_eq!((page.next_since, page.missed), (3, 0));
+
+    let page = buffer.since(2);
+    assert_eq!(seqs(&page.events), vec![3]);
+    assert_eq!(page.next_since, 3);
+
+    // nothing new: the client polls from the same place, even one ahead of the buffer
+    let page = buffer.since(3);
+    assert!(page.events.is_empty());
+    assert_eq!(page.next_since, 3);
+    assert_eq!(buffer.since(42).next_since, 3);
+}
+
+#[test]
+fn test_events_dropped_from_the_buffer_are_missed() {
+    let buffer = buffer_with(2, 5);
+
+    let page = buffer.since(0);
+    assert_eq!(seqs(&page.events), vec![4, 5]);
+    assert_eq!((page.next_since, page.missed), (5, 3));
+    assert_eq!(buffer.since(1).missed, 2);
+    assert_eq!(buffer.since(3).missed, 0);
+    assert_eq!(buffer.since(5).missed, 0);
+}
+
+#[tokio::test]
+async fn test_poll_answers_empty_once_the_timeout_expires() {
+    let buffer = buffer_with(10, 1);
+
+    let started = std::time::Instant::now();
+    let page = buffer.wait_since(1, Duration::from_millis(100)).await;
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert!(page.events.is_empty());
+    assert_eq!((page.next_since, page.missed), (1, 0));
+
+    // events already there are returned without waiting
+    let started = std::time::Instant::now();
+    let page = buffer.wait_since(0, Duration::from_secs(30)).await;
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(seqs(&page.events), vec![1]);
+}
+
+#[tokio::test]
+async fn test_poll_wakes_up_on_a_new_event() {
+    let buffer = Arc::new(buffer_with(10, 1));
+    let pusher = buffer.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        pusher.push(&error_event(2));
+    });
+
+    let started = std::time::Instant::now();
+    let page = buffer.wait_since(1, Duration::from_secs(30)).await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(seqs(&page.events), vec![2]);
+    assert_eq!(page.next_since, 2);
+}
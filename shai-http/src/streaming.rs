@@ -6,9 +6,11 @@ use shai_core::agent::{AgentEvent, PublicAgentState};
 use std::convert::Infallible;
 use tokio::sync::broadcast::Receiver;
 use tokio_stream::wrappers::BroadcastStream;
-use tracing::error;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tracing::{error, warn};
 
-use crate::session::RequestSession;
+use crate::error::ErrorResponse;
+use crate::session::{RequestLifecycle, RequestSession};
 
 /// Trait for formatting AgentEvents into API-specific response formats
 #[async_trait]
@@ -31,23 +33,24 @@ pub trait EventFormatter: Send {
 }
 
 /// Internal helper to create SSE stream with optional lifecycle
-fn sse_stream_internal<F, L>(
+/// The lifecycle is marked finished once the terminal event is reached, a stream dropped
+/// before it is a client that disconnected
+fn sse_stream_internal<F>(
     event_rx: Receiver<AgentEvent>,
     formatter: F,
     session_id: String,
-    lifecycle: Option<L>,
+    lifecycle: Option<RequestLifecycle>,
     stop_on_pause: bool,
 ) -> impl Stream<Item = Result<Event, Infallible>>
 where
     F: EventFormatter + 'static,
-    L: Send + 'static,
 {
     futures::stream::unfold(
         (BroadcastStream::new(event_rx), formatter, false, lifecycle),
         move |state| {
             let session_id = session_id.clone();
             async move {
-                let (mut rx, mut fmt, done, lifecycle) = state;
+                let (mut rx, mut fmt, done, mut lifecycle) = state;
 
                 if done {
                     return None;
@@ -59,6 +62,9 @@ where
                             let is_terminal = is_terminal_event(&event, stop_on_pause);
                             let formatted = fmt.format_event(event, &session_id).await;
                             let new_done = if is_terminal { true } else { done };
+                            if is_terminal {
+                                lifecycle.iter_mut().for_each(RequestLifecycle::finish);
+                            }
 
                             if let Some(output) = formatted {
                                 match serde_json::to_string(&output) {
@@ -78,11 +84,16 @@ where
                                 continue;
                             }
                         }
-                        Some(Err(e)) => {
-                            error!("[{}] Error receiving event: {}", session_id, e);
-                            return None;
+                        Some(Err(BroadcastStreamRecvError::Lagged(skipped))) => {
+                            // a slow client under a burst of deltas, skip ahead rather than end the stream
+                            // but tell the client that what it reads from now on misses a part
+                            warn!("[{}] Client lagging behind, {} events skipped", session_id, skipped);
+                            let sse_event = lagged_event(skipped);
+                            return Some((Ok(sse_event), (rx, fmt, done, lifecycle)));
                         }
                         None => {
+                            // the agent is gone, nothing left to stop
+                            lifecycle.iter_mut().for_each(RequestLifecycle::finish);
                             return None;
                         }
                     }
@@ -92,6 +103,17 @@ where
     )
 }
 
+/// Error chunk sent in place of the events a lagging client missed, in the shape of the
+/// OpenAI in-stream errors so that their SDKs raise it
+fn lagged_event(skipped: u64) -> Event {
+    let error = ErrorResponse::new(
+        format!("{} agent events were missed by a slow client, the streamed answer is incomplete", skipped),
+        "internal_error".to_string(),
+        Some("events_lagged".to_string()),
+    );
+    Event::default().data(serde_json::to_string(&error).unwrap_or_default())
+}
+
 /// Core SSE stream creation from event receiver
 /// Watches events, formats them, and stops on completion or client disconnect
 ///
@@ -106,7 +128,7 @@ pub fn event_to_sse_stream<F>(
 where
     F: EventFormatter + 'static,
 {
-    sse_stream_internal(event_rx, formatter, session_id, None::<()>, stop_on_pause)
+    sse_stream_internal(event_rx, formatter, session_id, None, stop_on_pause)
 }
 
 /// Create an SSE stream from a RequestSession
@@ -18,10 +18,17 @@ use openai_dive::v1::resources::{
     model::ListModelResponse,
 };
 use regex::Regex;
+use crate::embedding::{EmbeddingResponse, EmbeddingUsage, EMBEDDING_BATCH_SIZE};
+use crate::retry::RetryConfig;
+use crate::image::StripImages;
+use crate::ratelimit::{RateLimit, RateLimiter};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct LlmClient {
     provider: Box<dyn LlmProvider>,
+    retry: RetryConfig,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 /// Provider Factory related method
@@ -29,99 +36,89 @@ impl LlmClient {
     /// Create an OpenAI provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_openai() -> Option<Self> {
-        OpenAIProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OpenAIProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an Anthropic provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_anthropic() -> Option<Self> {
-        AnthropicProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        AnthropicProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an Ollama provider from environment variables
-    /// Always returns Some since Ollama has a default base URL
+    /// Returns None if OLLAMA_BASE_URL is not set
     pub fn from_env_ollama() -> Option<Self> {
-        OllamaProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OllamaProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an OpenRouter provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_openrouter() -> Option<Self> {
-        OpenRouterProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OpenRouterProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an OpenAI Compatible provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_openai_compatible() -> Option<Self> {
-        OpenAICompatibleProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OpenAICompatibleProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create an OVH Cloud provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_ovhcloud() -> Option<Self> {
-        OvhCloudProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        OvhCloudProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     /// Create a Mistral provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env_mistral() -> Option<Self> {
-        MistralProvider::from_env().map(|provider| Self {
-            provider: Box::new(provider),
-        })
+        MistralProvider::from_env().map(|provider| Self::from_provider(Box::new(provider)))
     }
 
     pub fn openai(api_key: String) -> Self {
-        Self {
-            provider: Box::new(OpenAIProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(OpenAIProvider::new(api_key)))
     }
 
     pub fn compatible(api_key: String, base_url: String) -> Self {
-        Self {
-            provider: Box::new(OpenAICompatibleProvider::new(api_key, base_url)),
-        }
+        Self::from_provider(Box::new(OpenAICompatibleProvider::new(api_key, base_url)))
     }
 
     pub fn openrouter(api_key: String) -> Self {
-        Self {
-            provider: Box::new(OpenRouterProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(OpenRouterProvider::new(api_key)))
     }
 
     pub fn ovhcloud(api_key: String, base_url: Option<String>) -> Self {
-        Self {
-            provider: Box::new(OvhCloudProvider::new(api_key, base_url)),
-        }
+        Self::from_provider(Box::new(OvhCloudProvider::new(api_key, base_url)))
     }
 
     pub fn anthropic(api_key: String) -> Self {
-        Self {
-            provider: Box::new(AnthropicProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(AnthropicProvider::new(api_key)))
     }
 
     pub fn ollama(base_url: String) -> Self {
-        Self {
-            provider: Box::new(OllamaProvider::new(Some(base_url))),
-        }
+        Self::from_provider(Box::new(OllamaProvider::new(Some(base_url))))
     }
 
     pub fn mistral(api_key: String) -> Self {
-        Self {
-            provider: Box::new(MistralProvider::new(api_key)),
-        }
+        Self::from_provider(Box::new(MistralProvider::new(api_key)))
+    }
+
+    /// Wrap any provider, e.g. a scripted provider used to drive agents deterministically in tests
+    pub fn from_provider(provider: Box<dyn LlmProvider>) -> Self {
+        Self { provider, retry: RetryConfig::default(), rate_limiter: None }
+    }
+
+    /// Retry policy for transient provider errors, 3 attempts by default
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Hold calls back to the requests and tokens per minute of `limit` instead of running into
+    /// the provider's quota. Clients of the same provider share their limiter
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limiter = (!limit.is_unlimited()).then(|| RateLimiter::shared(self.provider_name(), limit));
+        self
     }
 
 
@@ -204,7 +201,7 @@ impl LlmClient {
             },
             "ollama" => {
                 let base_url = Self::get_or_env(env_values, "OLLAMA_BASE_URL")
-                    .unwrap_or_else(|| "http://localhost:11434/v1".to_string());
+                    .unwrap_or_else(|| "http://localhost:11434".to_string());
                 Ok(Self::ollama(base_url))
             },
             "mistral" => {
@@ -254,6 +251,24 @@ impl LlmClient {
         self.provider.name()
     }
 
+    /// Whether the provider returns the reasoning of this model apart from its answer
+    pub fn supports_reasoning_content(&self, model: &str) -> bool {
+        self.provider.supports_reasoning_content(model.to_string())
+    }
+
+    /// Whether the model can be sent tools, providers may only find out from a rejected request
+    pub fn supports_functions(&self, model: &str) -> bool {
+        self.provider.supports_functions(model.to_string())
+    }
+
+    pub fn supports_images(&self, model: &str) -> bool {
+        self.provider.supports_images(model.to_string())
+    }
+
+    pub fn supports_tool_call_streaming(&self) -> bool {
+        self.provider.supports_tool_call_streaming()
+    }
+
     /// Get a reference to the underlying provider (for testing)
     pub fn provider(&self) -> &dyn LlmProvider {
         &*self.provider
@@ -263,28 +278,95 @@ impl LlmClient {
 /// Higher level chat client
 impl LlmClient {
     pub async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
-        let request = request
+        let request = self.without_unsupported_images(request)
             .fix_mistral_alternating();
 
-        let response = self.provider
-            .chat(request.clone())
+        let response = self.retry
+            .run(|| async {
+                self.wait_for_rate_limit().await;
+                self.provider.chat(request.clone()).await
+            })
             .await
             .inspect_err(|error| {
                 crate::logging::log_llm_error(&request, error, self.provider_name());
             })?
             .extract_think_content();
 
+        if let (Some(limiter), Some(usage)) = (&self.rate_limiter, &response.usage) {
+            limiter.charge(usage.prompt_tokens.unwrap_or(0) as u64 + usage.completion_tokens.unwrap_or(0) as u64);
+        }
         Ok(response)
     }
 
     pub async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
-        let request = request
+        let request = self.without_unsupported_images(request)
             .fix_mistral_alternating();
 
-        self.provider.chat_stream(request).await
+        // only opening the stream is retried, a stream failing midway is the caller's to handle
+        let stream = self.retry
+            .run(|| async {
+                self.wait_for_rate_limit().await;
+                self.provider.chat_stream(request.clone()).await
+            })
+            .await?;
+
+        // a stream reports its usage at the end if at all, the prompt is charged once it is open
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.charge(estimate_tokens(&request));
+        }
+        Ok(stream)
     }
 
+    async fn wait_for_rate_limit(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+    }
 
+    fn without_unsupported_images(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        if self.supports_images(&request.model) {
+            request
+        } else {
+            request.strip_images()
+        }
+    }
+}
+
+/// Embeddings
+impl LlmClient {
+    pub fn supports_embeddings(&self) -> bool {
+        self.provider.supports_embeddings()
+    }
+
+    /// Embed the inputs, splitting them in batches of EMBEDDING_BATCH_SIZE.
+    /// Returns one vector per input in order, with the usage summed across batches.
+    pub async fn embed(&self, model: &str, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        if !self.provider.supports_embeddings() {
+            return Err(format!("provider {} does not support embeddings", self.provider_name()).into());
+        }
+
+        let mut result = EmbeddingResponse {
+            model: model.to_string(),
+            embeddings: Vec::with_capacity(inputs.len()),
+            usage: None,
+        };
+
+        for batch in inputs.chunks(EMBEDDING_BATCH_SIZE) {
+            let response = self.provider.embed(model.to_string(), batch.to_vec()).await?;
+            result.model = response.model;
+            result.embeddings.extend(response.embeddings);
+            if let Some(usage) = response.usage {
+                result.usage.get_or_insert_with(EmbeddingUsage::default).add(&usage);
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Rough token count of a request, about four characters per token
+fn estimate_tokens(request: &ChatCompletionParameters) -> u64 {
+    serde_json::to_string(&request.messages).map_or(0, |json| json.len() as u64 / 4)
 }
 
 pub trait ExtractThinkContent {
@@ -298,9 +380,18 @@ impl ExtractThinkContent for ChatCompletionResponse {
             if let ChatMessage::Assistant { reasoning_content, content, .. } = &mut choice.message {
                 if let Some(ChatMessageContent::Text(content_text)) = content {
                     let think_regex = Regex::new(r"(?s)<think>(.*?)</think>").unwrap();
-                    if let Some(reasoning) = think_regex.captures(content_text).map(|c| c.get(1).unwrap().as_str().trim()) {
-                        *reasoning_content = Some(reasoning.to_string());
-                        let cleaned = think_regex.replace_all(content_text, "").trim().to_string();
+                    let split = if let Some(reasoning) = think_regex.captures(content_text).map(|c| c.get(1).unwrap().as_str().trim()) {
+                        Some((reasoning.to_string(), think_regex.replace_all(content_text, "").trim().to_string()))
+                    } else {
+                        // some templates put the opening tag in the prompt, only the closing one is generated
+                        content_text.split_once("</think>")
+                            .map(|(reasoning, answer)| (reasoning.trim().to_string(), answer.trim().to_string()))
+                    };
+                    if let Some((reasoning, cleaned)) = split {
+                        // a native reasoning channel wins over tags found in the answer
+                        if reasoning_content.as_deref().map_or(true, |r| r.trim().is_empty()) {
+                            *reasoning_content = Some(reasoning).filter(|r| !r.is_empty());
+                        }
                         *content = if cleaned.is_empty() { None } else { Some(ChatMessageContent::Text(cleaned)) };
                     }
                 }
@@ -352,4 +443,61 @@ impl FixMistralAlternating for ChatCompletionParameters {
         }
         res
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::ChatCompletionChoice;
+
+    fn response(content: &str) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: None,
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "test".to_string(),
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(content.to_string())),
+                    reasoning_content: None,
+                    tool_calls: None,
+                    name: None,
+                    audio: None,
+                    refusal: None,
+                },
+                finish_reason: None,
+                logprobs: None,
+            }],
+            usage: None,
+            system_fingerprint: None,
+            service_tier: None,
+        }
+    }
+
+    fn split(response: ChatCompletionResponse) -> (Option<String>, Option<String>) {
+        match response.extract_think_content().choices.remove(0).message {
+            ChatMessage::Assistant { reasoning_content, content, .. } => {
+                let content = content.map(|c| match c {
+                    ChatMessageContent::Text(text) => text,
+                    other => panic!("unexpected content {:?}", other),
+                });
+                (reasoning_content, content)
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_think_tags_are_moved_to_reasoning() {
+        assert_eq!(
+            split(response("<think>the bug is in main.rs</think>\nFixed the off-by-one in main.rs.")),
+            (Some("the bug is in main.rs".to_string()), Some("Fixed the off-by-one in main.rs.".to_string())));
+
+        // opening tag left in the prompt by the chat template
+        assert_eq!(
+            split(response("let me check the tests</think>All tests pass.")),
+            (Some("let me check the tests".to_string()), Some("All tests pass.".to_string())));
+
+        assert_eq!(split(response("No reasoning here.")), (None, Some("No reasoning here.".to_string())));
+    }
+}
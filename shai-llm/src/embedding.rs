@@ -0,0 +1,183 @@
+// Embeddings over the OpenAI-compatible /embeddings endpoint
+use serde::{Deserialize, Serialize};
+
+use crate::provider::LlmError;
+
+/// Maximum number of inputs sent in a single /embeddings request
+pub const EMBEDDING_BATCH_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl EmbeddingUsage {
+    pub fn add(&mut self, other: &EmbeddingUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// One vector per input, in the same order as the inputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingResponse {
+    pub model: String,
+    pub embeddings: Vec<Vec<f32>>,
+    pub usage: Option<EmbeddingUsage>,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    encoding_format: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingApiResponse {
+    #[serde(default)]
+    model: Option<String>,
+    data: Vec<EmbeddingData>,
+    #[serde(default)]
+    usage: Option<EmbeddingUsage>,
+}
+
+/// Minimal client for OpenAI-compatible embedding endpoints
+#[derive(Clone, Debug)]
+pub struct EmbeddingClient {
+    http_client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl EmbeddingClient {
+    pub fn new(base_url: String, api_key: Option<String>) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.filter(|k| !k.is_empty()),
+        }
+    }
+
+    /// Embed a single batch of inputs, the caller is responsible for batching
+    pub async fn embed(&self, model: &str, inputs: &[String]) -> Result<EmbeddingResponse, LlmError> {
+        let url = format!("{}/embeddings", self.base_url);
+        let mut request = self.http_client
+            .post(&url)
+            .json(&EmbeddingRequest { model, input: inputs, encoding_format: "float" });
+
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(LlmError::from_status(status.as_u16(), None, format!("embeddings request failed ({}): {}", status, body)));
+        }
+
+        let mut parsed: EmbeddingApiResponse = response.json().await?;
+        if parsed.data.len() != inputs.len() {
+            return Err(LlmError::Parse(format!(
+                "embeddings response has {} vectors for {} inputs", parsed.data.len(), inputs.len()
+            )));
+        }
+
+        // the API is allowed to return vectors out of order
+        parsed.data.sort_by_key(|d| d.index);
+
+        Ok(EmbeddingResponse {
+            model: parsed.model.unwrap_or_else(|| model.to_string()),
+            embeddings: parsed.data.into_iter().map(|d| d.embedding).collect(),
+            usage: parsed.usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{LlmProvider, LlmStream, ProviderInfo};
+    use crate::LlmClient;
+    use async_trait::async_trait;
+    use openai_dive::v1::resources::chat::{ChatCompletionParameters, ChatCompletionResponse};
+    use openai_dive::v1::resources::model::ListModelResponse;
+    use std::sync::{Arc, Mutex};
+
+    /// Returns `[index, len]` for each input and records the batch sizes it was called with
+    struct CountingEmbedder {
+        batches: Arc<Mutex<Vec<usize>>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for CountingEmbedder {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Err("no models".into())
+        }
+
+        async fn chat(&self, _request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            Err("no chat".into())
+        }
+
+        async fn chat_stream(&self, _request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            Err("no chat".into())
+        }
+
+        fn supports_functions(&self, _model: String) -> bool { false }
+
+        fn supports_structured_output(&self, _model: String) -> bool { false }
+
+        fn supports_embeddings(&self) -> bool { true }
+
+        async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+            let offset: usize = self.batches.lock().unwrap().iter().sum();
+            self.batches.lock().unwrap().push(inputs.len());
+            Ok(EmbeddingResponse {
+                model,
+                embeddings: inputs.iter().enumerate()
+                    .map(|(i, s)| vec![(offset + i) as f32, s.len() as f32])
+                    .collect(),
+                usage: Some(EmbeddingUsage { prompt_tokens: inputs.len() as u32, total_tokens: inputs.len() as u32 }),
+            })
+        }
+
+        fn name(&self) -> &'static str { "counting" }
+
+        fn info() -> ProviderInfo where Self: Sized {
+            ProviderInfo { name: "counting", display_name: "Counting", env_vars: vec![] }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_batches_and_sums_usage() {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let client = LlmClient::from_provider(Box::new(CountingEmbedder { batches: batches.clone() }));
+
+        let inputs: Vec<String> = (0..EMBEDDING_BATCH_SIZE + 3).map(|i| "x".repeat(i % 5)).collect();
+        let response = client.embed("embed-model", inputs.clone()).await.unwrap();
+
+        assert_eq!(*batches.lock().unwrap(), vec![EMBEDDING_BATCH_SIZE, 3]);
+        assert_eq!(response.embeddings.len(), inputs.len());
+        for (i, vector) in response.embeddings.iter().enumerate() {
+            assert_eq!(vector, &vec![i as f32, inputs[i].len() as f32]);
+        }
+        assert_eq!(response.usage.unwrap().prompt_tokens, inputs.len() as u32);
+    }
+
+    #[tokio::test]
+    async fn test_embed_on_chat_only_provider_errors() {
+        let client = LlmClient::anthropic("not-a-key".to_string());
+        assert!(!client.supports_embeddings());
+
+        let err = client.embed("any", vec!["hello".to_string()]).await.unwrap_err();
+        assert!(err.to_string().contains("does not support embeddings"));
+    }
+}
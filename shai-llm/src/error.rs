@@ -0,0 +1,195 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use openai_dive::v1::error::APIError;
+
+use crate::retry::{parse_retry_hint, HttpError};
+
+/// Messages of errors that only come as text but are timeouts
+const TIMEOUT_MESSAGES: &[&str] = &["timed out", "timeout"];
+
+/// Error of an LLM call, by kind so that callers can tell a timeout from a bad key
+/// when deciding to retry or to fall back to another model
+#[derive(Debug)]
+pub enum LlmError {
+    /// The request or the connection timed out
+    Timeout(String),
+    /// The provider refused the call over its quota, `retry_after` is the delay it asked for
+    RateLimited { retry_after: Option<Duration>, message: String },
+    /// The API key is missing, wrong, or not allowed to use the model
+    Auth(String),
+    /// Any other non-success HTTP status
+    Http { status: u16, retry_after: Option<Duration>, message: String },
+    /// The answer could not be read: malformed JSON, unexpected shape, invalid structured output
+    Parse(String),
+    /// Everything not classified yet, e.g. connection failures and messages built from strings
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl LlmError {
+    /// HTTP status the provider answered with, when the error comes from one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            LlmError::RateLimited { .. } => Some(429),
+            LlmError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Delay the provider asked for before the next attempt, from the Retry-After header or the error message
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LlmError::RateLimited { retry_after, .. } | LlmError::Http { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Error for a non-success status, by kind when the status tells it
+    pub fn from_status(status: u16, retry_after: Option<Duration>, message: String) -> Self {
+        match status {
+            401 | 403 => LlmError::Auth(message),
+            408 => LlmError::Timeout(message),
+            429 => LlmError::RateLimited { retry_after, message },
+            _ => LlmError::Http { status, retry_after, message },
+        }
+    }
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::Timeout(message)
+            | LlmError::Auth(message)
+            | LlmError::Parse(message)
+            | LlmError::RateLimited { message, .. }
+            | LlmError::Http { message, .. } => write!(f, "{}", message),
+            LlmError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for LlmError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LlmError::Other(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+fn is_timeout_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    TIMEOUT_MESSAGES.iter().any(|pattern| message.contains(pattern))
+}
+
+/// Errors still built from a message are `Other`, until their call site says what they are
+impl From<String> for LlmError {
+    fn from(message: String) -> Self {
+        LlmError::Other(message.into())
+    }
+}
+
+impl From<&str> for LlmError {
+    fn from(message: &str) -> Self {
+        LlmError::Other(message.into())
+    }
+}
+
+impl From<Box<dyn Error + Send + Sync>> for LlmError {
+    fn from(error: Box<dyn Error + Send + Sync>) -> Self {
+        LlmError::Other(error)
+    }
+}
+
+impl From<HttpError> for LlmError {
+    fn from(error: HttpError) -> Self {
+        LlmError::from_status(error.status, error.retry_after, error.message)
+    }
+}
+
+/// openai_dive errors keep the body of the response but not its headers, so the delay of a
+/// rate-limited call is the one its message asks for
+impl From<APIError> for LlmError {
+    fn from(error: APIError) -> Self {
+        let message = error.to_string();
+        let retry_after = parse_retry_hint(&message);
+        match error {
+            APIError::RateLimitError(_) => LlmError::RateLimited { retry_after, message },
+            APIError::AuthenticationError(_) | APIError::PermissionError(_) => LlmError::Auth(message),
+            APIError::InvalidRequestError(_) => LlmError::Http { status: 400, retry_after: None, message },
+            APIError::NotFoundError(_) => LlmError::Http { status: 404, retry_after: None, message },
+            APIError::UnknownError(status, _) => LlmError::from_status(status, retry_after, message),
+            APIError::ParseError(_) => LlmError::Parse(message),
+            // connection failures end up as text in the other variants
+            _ if is_timeout_message(&message) => LlmError::Timeout(message),
+            other => LlmError::Other(Box::new(other)),
+        }
+    }
+}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            LlmError::Timeout(error.to_string())
+        } else if let Some(status) = error.status() {
+            LlmError::from_status(status.as_u16(), None, error.to_string())
+        } else if error.is_decode() {
+            LlmError::Parse(error.to_string())
+        } else {
+            LlmError::Other(Box::new(error))
+        }
+    }
+}
+
+impl From<serde_json::Error> for LlmError {
+    fn from(error: serde_json::Error) -> Self {
+        LlmError::Parse(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for LlmError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::TimedOut => LlmError::Timeout(error.to_string()),
+            std::io::ErrorKind::InvalidData => LlmError::Parse(error.to_string()),
+            _ => LlmError::Other(Box::new(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_are_classified() {
+        let error = LlmError::from(HttpError { status: 429, retry_after: Some(Duration::from_secs(3)), message: "slow down".to_string() });
+        assert!(matches!(error, LlmError::RateLimited { retry_after: Some(_), .. }));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(3)));
+        assert_eq!(error.to_string(), "slow down");
+
+        let error = LlmError::from(HttpError { status: 401, retry_after: None, message: "bad key".to_string() });
+        assert!(matches!(error, LlmError::Auth(_)));
+        let error = LlmError::from(HttpError { status: 502, retry_after: None, message: "bad gateway".to_string() });
+        assert_eq!(error.status(), Some(502));
+
+        assert!(matches!(LlmError::from(APIError::AuthenticationError("bad key".to_string())), LlmError::Auth(_)));
+        assert!(matches!(LlmError::from(APIError::RateLimitError("slow down".to_string())), LlmError::RateLimited { retry_after: None, .. }));
+        let error = LlmError::from(APIError::RateLimitError("Rate limit reached. Please try again in 20s.".to_string()));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(20)));
+        let error = LlmError::from(APIError::UnknownError(503, "overloaded, retry after 2 seconds".to_string()));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(2)));
+        assert!(matches!(LlmError::from(APIError::UnknownError(503, "".to_string())), LlmError::Http { status: 503, .. }));
+        assert!(matches!(LlmError::from(APIError::ParseError("eof".to_string())), LlmError::Parse(_)));
+
+        let timeout = std::io::Error::new(std::io::ErrorKind::TimedOut, "operation timed out");
+        assert!(matches!(LlmError::from(timeout), LlmError::Timeout(_)));
+        assert!(matches!(LlmError::from(serde_json::from_str::<u32>("{").unwrap_err()), LlmError::Parse(_)));
+
+        let error = LlmError::from("model not found");
+        assert!(matches!(error, LlmError::Other(_)));
+        assert_eq!(error.to_string(), "model not found");
+        assert_eq!(error.source().map(|source| source.to_string()).as_deref(), Some("model not found"));
+    }
+}
@@ -0,0 +1,130 @@
+use openai_dive::v1::resources::chat::{
+    ChatCompletionParameters, ChatMessage, ChatMessageContent, ChatMessageContentPart,
+    ChatMessageImageContentPart, ChatMessageTextContentPart, ImageUrlType,
+};
+
+/// Model name fragments of the vision models, used by providers that can't ask the API
+const VISION_MODELS: &[&str] = &[
+    "gpt-4o", "gpt-4.1", "gpt-5",
+    "claude",
+    "pixtral", "mistral-small-3", "mistral-medium",
+    "llava", "vision", "-vl", "gemma-3", "gemma3", "llama-4", "llama4",
+];
+
+/// Text sent instead of an image to a model that can't read it
+const IMAGE_OMITTED: &str = "[image omitted: this model does not accept images]";
+
+pub fn text_part(text: impl Into<String>) -> ChatMessageContentPart {
+    ChatMessageContentPart::Text(ChatMessageTextContentPart {
+        r#type: "text".to_string(),
+        text: text.into(),
+    })
+}
+
+/// Image part from an http(s) URL or a `data:` URL
+pub fn image_part(url: impl Into<String>) -> ChatMessageContentPart {
+    ChatMessageContentPart::Image(ChatMessageImageContentPart {
+        r#type: "image_url".to_string(),
+        image_url: ImageUrlType { url: url.into(), detail: None },
+    })
+}
+
+pub fn data_url(mime_type: &str, base64: &str) -> String {
+    format!("data:{};base64,{}", mime_type, base64)
+}
+
+/// Split a `data:<mime>;base64,<data>` URL, None for any other URL
+pub fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime_type = header.strip_suffix(";base64")?;
+    Some((mime_type, data))
+}
+
+/// Image type of a file from its extension
+pub fn image_mime_type(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit_once('.')?.1.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Image type of base64 data from its first bytes, as they appear once encoded
+pub fn sniff_image_mime_type(base64: &str) -> Option<&'static str> {
+    [("iVBORw0KGgo", "image/png"), ("/9j/", "image/jpeg"), ("R0lGOD", "image/gif"), ("UklGR", "image/webp")]
+        .into_iter()
+        .find(|(prefix, _)| base64.starts_with(prefix))
+        .map(|(_, mime_type)| mime_type)
+}
+
+pub fn model_has_vision(model: &str) -> bool {
+    let model = model.to_lowercase();
+    VISION_MODELS.iter().any(|fragment| model.contains(fragment))
+        || ["o1", "o3", "o4"].iter().any(|series| model.starts_with(series))
+}
+
+pub trait StripImages {
+    /// Replace the image parts of the messages with a short note, for models without vision
+    /// that would reject the whole request otherwise
+    fn strip_images(self) -> ChatCompletionParameters;
+}
+
+impl StripImages for ChatCompletionParameters {
+    fn strip_images(mut self) -> ChatCompletionParameters {
+        for message in &mut self.messages {
+            let content = match message {
+                ChatMessage::User { content, .. } | ChatMessage::System { content, .. } | ChatMessage::Developer { content, .. } => content,
+                _ => continue,
+            };
+            if let ChatMessageContent::ContentPart(parts) = content {
+                for part in parts.iter_mut() {
+                    if matches!(part, ChatMessageContentPart::Image(_)) {
+                        *part = text_part(IMAGE_OMITTED);
+                    }
+                }
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::ChatCompletionParametersBuilder;
+
+    #[test]
+    fn test_data_urls_and_mime_types() {
+        let url = data_url("image/png", "iVBORw0KGgoAAAA");
+        assert_eq!(parse_data_url(&url), Some(("image/png", "iVBORw0KGgoAAAA")));
+        assert_eq!(parse_data_url("https://example.com/a.png"), None);
+
+        assert_eq!(image_mime_type("shots/Screen.PNG"), Some("image/png"));
+        assert_eq!(image_mime_type("main.rs"), None);
+        assert_eq!(sniff_image_mime_type("/9j/4AAQSkZJRg"), Some("image/jpeg"));
+        assert_eq!(sniff_image_mime_type("aGVsbG8="), None);
+    }
+
+    #[test]
+    fn test_strip_images() {
+        let request = ChatCompletionParametersBuilder::default()
+            .model("mistral-large")
+            .messages(vec![ChatMessage::User {
+                content: ChatMessageContent::ContentPart(vec![text_part("what is this?"), image_part("https://example.com/a.png")]),
+                name: None,
+            }])
+            .build()
+            .unwrap();
+
+        assert!(!model_has_vision(&request.model));
+        assert!(model_has_vision("pixtral-large-latest"));
+
+        let ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. } = &request.strip_images().messages[0] else {
+            panic!("content parts expected");
+        };
+        assert_eq!(parts, &vec![text_part("what is this?"), text_part(IMAGE_OMITTED)]);
+    }
+}
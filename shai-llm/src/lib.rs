@@ -1,16 +1,28 @@
 pub mod client;
+pub mod error;
 pub mod providers;
 pub mod provider;
 pub mod chat;
+pub mod embedding;
 pub mod tool;
 pub mod logging;
+pub mod stream;
+pub mod retry;
+pub mod ratelimit;
+pub mod image;
 
 // Re-export our client
 pub use client::LlmClient;
+pub use error::LlmError;
+pub use embedding::{EmbeddingResponse, EmbeddingUsage};
+pub use stream::{ChatDelta, ChatStreamAccumulator};
+pub use retry::RetryConfig;
+pub use ratelimit::{RateLimit, RateLimiter, Throttle, ThrottleHook, with_throttle_hook};
 
 pub use tool::{
     ToolDescription, 
     ToolCallMethod,
+    LlmToolCallStreaming,
     ToolBox,
     ContainsTool,
     StructuredOutputBuilder, 
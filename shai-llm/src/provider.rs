@@ -1,14 +1,14 @@
 use std::fmt::Debug;
 use async_trait::async_trait;
 use futures::Stream;
-use std::error::Error;
 use openai_dive::v1::endpoints::chat::Chat;
 use openai_dive::v1::resources::{
     chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
     model::ListModelResponse,
 };
+use crate::embedding::EmbeddingResponse;
 
-pub type LlmError = Box<dyn Error + Send + Sync>;
+pub use crate::error::LlmError;
 pub type LlmStream = Box<dyn Stream<Item = Result<ChatCompletionChunkResponse, LlmError>> + Send + Unpin>;
 
 #[derive(Debug, Clone)]
@@ -62,6 +62,32 @@ pub trait LlmProvider: Send + Sync {
     fn supports_functions(&self, model: String) -> bool;
     
     fn supports_structured_output(&self, model: String) -> bool;
+
+    /// Whether the API returns the model reasoning apart from the answer, in reasoning_content.
+    /// Without it the model is asked to wrap its reasoning in <think> tags, split by the client.
+    fn supports_reasoning_content(&self, model: String) -> bool {
+        false
+    }
+
+    /// Whether this model reads the image parts of user messages, they are replaced by a note otherwise
+    fn supports_images(&self, model: String) -> bool {
+        crate::image::model_has_vision(&model)
+    }
+
+    /// Whether tool calls come through `chat_stream`, when they don't tool calling is never streamed
+    fn supports_tool_call_streaming(&self) -> bool {
+        true
+    }
+
+    /// Whether this provider exposes an embeddings endpoint
+    fn supports_embeddings(&self) -> bool {
+        false
+    }
+
+    /// Embed a single batch of inputs, chat-only providers keep the default error
+    async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        Err(format!("provider {} does not support embeddings", self.name()).into())
+    }
     
     fn name(&self) -> &'static str;
     
@@ -1,11 +1,13 @@
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::retry::HttpError;
+use crate::image::parse_data_url;
 use super::api::*;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::json;
 use futures::{StreamExt, stream};
 use openai_dive::v1::resources::{
-    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatCompletionChoice, ChatCompletionChunkChoice, ToolCall, Function},
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatMessageContentPart, ChatCompletionChoice, ChatCompletionChunkChoice, ToolCall, Function},
     model::ListModelResponse,
     shared::{FinishReason, Usage},
 };
@@ -43,7 +45,7 @@ impl AnthropicProvider {
                         let chunk_str = String::from_utf8_lossy(&chunk);
                         Self::parse_sse_chunk(&chunk_str)
                     }
-                    Err(e) => vec![Err(Box::new(e) as LlmError)],
+                    Err(e) => vec![Err(LlmError::from(e))],
                 }
             })
             .flat_map(|results| stream::iter(results));
@@ -87,10 +89,9 @@ impl AnthropicProvider {
                 if event_type == "ping" {
                     Ok(None)
                 } else {
-                    Err(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
+                    Err(LlmError::Parse(
                         format!("Failed to parse Anthropic event {}: {}. Error: {}", event_type, data, e)
-                    )) as LlmError)
+                    ))
                 }
             }
         }
@@ -217,7 +218,7 @@ impl AnthropicProvider {
                 ChatMessage::User { content, .. } => {
                     converted_messages.push(json!({
                         "role": "user",
-                        "content": self.extract_content_text(content)
+                        "content": self.convert_user_content(content)
                     }));
                 }
                 ChatMessage::Assistant { content, tool_calls, .. } => {
@@ -300,6 +301,35 @@ impl AnthropicProvider {
         }).collect()
     }
 
+    /// Plain text, or content blocks when the message carries images
+    fn convert_user_content(&self, content: &ChatMessageContent) -> serde_json::Value {
+        let ChatMessageContent::ContentPart(parts) = content else {
+            return json!(self.extract_content_text(content));
+        };
+        if !parts.iter().any(|part| matches!(part, ChatMessageContentPart::Image(_))) {
+            return json!(self.extract_content_text(content));
+        }
+
+        let blocks: Vec<serde_json::Value> = parts.iter().filter_map(|part| match part {
+            ChatMessageContentPart::Text(text_part) => Some(json!({"type": "text", "text": text_part.text})),
+            ChatMessageContentPart::Image(image_part) => {
+                let url = &image_part.image_url.url;
+                Some(match parse_data_url(url) {
+                    Some((media_type, data)) => json!({
+                        "type": "image",
+                        "source": {"type": "base64", "media_type": media_type, "data": data}
+                    }),
+                    None => json!({
+                        "type": "image",
+                        "source": {"type": "url", "url": url}
+                    }),
+                })
+            }
+            _ => None,
+        }).collect();
+        json!(blocks)
+    }
+
     fn extract_content_text(&self, content: &ChatMessageContent) -> String {
         match content {
             ChatMessageContent::Text(text) => text.clone(),
@@ -455,8 +485,7 @@ impl LlmProvider for AnthropicProvider {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API error: {}", error_text).into());
+            return Err(HttpError::from_response(response, "Anthropic API error").await.into());
         }
 
         let anthropic_response: serde_json::Value = response.json().await?;
@@ -478,8 +507,7 @@ impl LlmProvider for AnthropicProvider {
             .await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API streaming error: {}", error_text).into());
+            return Err(HttpError::from_response(response, "Anthropic API streaming error").await.into());
         }
 
         Self::parse_anthropic_stream(response).await
@@ -493,6 +521,15 @@ impl LlmProvider for AnthropicProvider {
         false
     }
 
+    fn supports_images(&self, model: String) -> bool {
+        true
+    }
+
+    fn supports_tool_call_streaming(&self) -> bool {
+        // the streaming parser only forwards text deltas, not tool_use blocks
+        false
+    }
+
     fn name(&self) -> &'static str {
         "anthropic"
     }
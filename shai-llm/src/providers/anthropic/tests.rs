@@ -174,4 +174,30 @@ mod tests {
         assert_eq!(tool_result_content[0]["tool_use_id"].as_str().unwrap(), "toolu_018qHepKa8d4rbZ9qskd2vqw");
         assert_eq!(tool_result_content[0]["content"].as_str().unwrap(), "Successfully updated file '/Users/lloiseau/Work/test/main.py' with 22 bytes");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_image_content_conversion() {
+        use crate::image::{data_url, image_part, text_part};
+
+        let provider = AnthropicProvider::new("test-key".to_string());
+        let request = ChatCompletionParametersBuilder::default()
+            .model("claude-sonnet-4")
+            .messages(vec![ChatMessage::User {
+                content: ChatMessageContent::ContentPart(vec![
+                    text_part("what does this screenshot show?"),
+                    image_part(data_url("image/png", "iVBORw0KGgo")),
+                    image_part("https://example.com/diagram.jpg"),
+                ]),
+                name: None,
+            }])
+            .build()
+            .unwrap();
+
+        let anthropic_format = provider.convert_to_anthropic_format(&request);
+        assert_eq!(anthropic_format["messages"][0]["content"], json!([
+            {"type": "text", "text": "what does this screenshot show?"},
+            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo"}},
+            {"type": "image", "source": {"type": "url", "url": "https://example.com/diagram.jpg"}}
+        ]));
+    }
+}
@@ -1,5 +1,6 @@
 // Mistral provider using flexible chat client with JSON hooks
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::embedding::{EmbeddingClient, EmbeddingResponse};
 use crate::chat::{ChatClient, JsonHooks};
 use serde_json::Value;
 use async_trait::async_trait;
@@ -59,14 +60,17 @@ impl From<MistralModel> for Model {
 pub struct MistralProvider {
     client: ChatClient,
     hooks: MistralHooks,
+    embeddings: EmbeddingClient,
 }
 
 impl MistralProvider {
     pub fn new(api_key: String) -> Self {
+        let embeddings = EmbeddingClient::new("https://api.mistral.ai/v1".to_string(), Some(api_key.clone()));
         let client = ChatClient::new(api_key, "https://api.mistral.ai/v1".to_string());
         Self { 
             client,
             hooks: MistralHooks,
+            embeddings,
         }
     }
 
@@ -151,12 +155,12 @@ impl LlmProvider for MistralProvider {
             .header("Authorization", format!("Bearer {}", self.client.api_key))
             .send()
             .await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
             
         let mistral_response: MistralListModelResponse = response
             .json()
             .await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
         
         // Filter models that support function calling and convert to OpenAI format
         let filtered_models: Vec<Model> = mistral_response.data
@@ -183,7 +187,7 @@ impl LlmProvider for MistralProvider {
         }
         
         let response = self.client.chat_completion(&request, &self.hooks).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
         Ok(response)
     }
 
@@ -198,10 +202,10 @@ impl LlmProvider for MistralProvider {
         }
         
         let stream = self.client.chat_completion_stream(&request, self.hooks).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         let converted_stream = stream.map(|result| {
-            result.map_err(|e| Box::new(e) as LlmError)
+            result.map_err(|e| LlmError::from(e))
         });
 
         Ok(Box::new(Box::pin(converted_stream)))
@@ -215,6 +219,14 @@ impl LlmProvider for MistralProvider {
         true
     }
 
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        self.embeddings.embed(&model, &inputs).await
+    }
+
     fn name(&self) -> &'static str {
         "mistral"
     }
@@ -0,0 +1,424 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::embedding::{EmbeddingClient, EmbeddingResponse};
+use crate::retry::HttpError;
+use crate::image::parse_data_url;
+use super::api::*;
+use async_trait::async_trait;
+use reqwest::Client;
+use futures::{StreamExt, stream};
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatCompletionResponseFormat, ChatCompletionTool, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatMessageContentPart, ChatCompletionChoice, ChatCompletionChunkChoice, DeltaToolCall, DeltaFunction, ToolCall, Function},
+    model::{ListModelResponse, Model},
+    shared::{FinishReason, Usage},
+};
+
+/// Ollama error message for a request with tools sent to a model without tool support
+const NO_TOOL_SUPPORT: &str = "does not support tools";
+
+pub struct OllamaProvider {
+    base_url: String,
+    client: Client,
+    embeddings: EmbeddingClient,
+    /// models that rejected a request with tools, they get structured output instead
+    no_tools: RwLock<HashSet<String>>,
+}
+
+impl OllamaProvider {
+    /// `base_url` is the Ollama server, e.g. http://localhost:11434.
+    /// The OpenAI compatible URL (ending in /v1) is accepted as well.
+    pub fn new(base_url: Option<String>) -> Self {
+        let base_url = base_url
+            .map(|url| url.trim_end_matches('/').trim_end_matches("/v1").to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| OLLAMA_BASE_URL.to_string());
+        let embeddings = EmbeddingClient::new(format!("{}/v1", base_url), None);
+        Self {
+            base_url,
+            client: Client::new(),
+            embeddings,
+            no_tools: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Create Ollama provider from environment variables
+    /// Returns None if OLLAMA_BASE_URL is not set
+    pub fn from_env() -> Option<Self> {
+        std::env::var("OLLAMA_BASE_URL").ok().map(|base_url| Self::new(Some(base_url)))
+    }
+
+    async fn tags(&self) -> Result<OllamaTagsResponse, LlmError> {
+        let response = self.client
+            .get(format!("{}/api/tags", self.base_url))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(HttpError::from_response(response, "Ollama API error").await.into());
+        }
+
+        Ok(response.json().await?)
+    }
+
+    async fn send_chat(&self, request: &OllamaChatRequest) -> Result<reqwest::Response, LlmError> {
+        let response = self.client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error = HttpError::from_response(response, "Ollama API error").await;
+            if request.tools.is_some() && error.message.contains(NO_TOOL_SUPPORT) {
+                self.no_tools.write().unwrap().insert(request.model.clone());
+            }
+            return Err(error.into());
+        }
+
+        Ok(response)
+    }
+
+    pub(crate) fn convert_to_ollama_format(&self, request: &ChatCompletionParameters, stream: bool) -> OllamaChatRequest {
+        let format = match &request.response_format {
+            Some(ChatCompletionResponseFormat::JsonSchema { json_schema }) => json_schema.schema.clone(),
+            Some(ChatCompletionResponseFormat::JsonObject) => Some(serde_json::json!("json")),
+            _ => None,
+        };
+
+        let options = OllamaOptions {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            num_predict: request.max_completion_tokens.or(request.max_tokens),
+        };
+
+        OllamaChatRequest {
+            model: request.model.clone(),
+            messages: self.convert_messages(&request.messages),
+            tools: request.tools.as_ref().map(|tools| self.convert_tools(tools)),
+            format,
+            options: Some(options),
+            stream,
+        }
+    }
+
+    fn convert_messages(&self, messages: &[ChatMessage]) -> Vec<OllamaMessage> {
+        // tool messages only carry the call id, Ollama wants the tool name
+        let mut tool_names = HashMap::new();
+
+        messages.iter().map(|msg| match msg {
+            ChatMessage::System { content, .. } | ChatMessage::Developer { content, .. } => OllamaMessage {
+                role: "system".to_string(),
+                content: self.extract_content_text(content),
+                ..Default::default()
+            },
+            ChatMessage::User { content, .. } => {
+                let images: Vec<String> = match content {
+                    ChatMessageContent::ContentPart(parts) => parts.iter().filter_map(|part| match part {
+                        // Ollama only takes inline images, not URLs
+                        ChatMessageContentPart::Image(image_part) => parse_data_url(&image_part.image_url.url)
+                            .map(|(_, data)| data.to_string()),
+                        _ => None,
+                    }).collect(),
+                    _ => Vec::new(),
+                };
+                OllamaMessage {
+                    role: "user".to_string(),
+                    content: self.extract_content_text(content),
+                    images: Some(images).filter(|images| !images.is_empty()),
+                    ..Default::default()
+                }
+            }
+            ChatMessage::Assistant { content, tool_calls, reasoning_content, .. } => {
+                let tool_calls = tool_calls.as_ref().map(|calls| calls.iter().map(|call| {
+                    tool_names.insert(call.id.clone(), call.function.name.clone());
+                    OllamaToolCall {
+                        function: OllamaFunctionCall {
+                            name: call.function.name.clone(),
+                            arguments: serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| serde_json::json!({})),
+                        },
+                    }
+                }).collect::<Vec<_>>());
+                OllamaMessage {
+                    role: "assistant".to_string(),
+                    content: content.as_ref().map(|c| self.extract_content_text(c)).unwrap_or_default(),
+                    tool_calls: tool_calls.filter(|calls| !calls.is_empty()),
+                    thinking: reasoning_content.clone(),
+                    ..Default::default()
+                }
+            }
+            ChatMessage::Tool { content, tool_call_id, .. } => OllamaMessage {
+                role: "tool".to_string(),
+                content: self.extract_content_text(content),
+                tool_name: tool_names.get(tool_call_id).cloned(),
+                ..Default::default()
+            },
+        }).collect()
+    }
+
+    fn convert_tools(&self, tools: &[ChatCompletionTool]) -> Vec<OllamaTool> {
+        tools.iter().map(|tool| OllamaTool {
+            tool_type: "function".to_string(),
+            function: OllamaFunction {
+                name: tool.function.name.clone(),
+                description: tool.function.description.clone().unwrap_or_else(|| tool.function.name.clone()),
+                parameters: tool.function.parameters.clone(),
+            },
+        }).collect()
+    }
+
+    fn extract_content_text(&self, content: &ChatMessageContent) -> String {
+        match content {
+            ChatMessageContent::Text(text) => text.clone(),
+            ChatMessageContent::ContentPart(parts) => {
+                parts.iter().filter_map(|part| match part {
+                    ChatMessageContentPart::Text(text_part) => Some(text_part.text.clone()),
+                    _ => None, // images go in the images field
+                }).collect::<Vec<_>>().join(" ")
+            }
+            ChatMessageContent::None => String::new(),
+        }
+    }
+
+    /// Ollama doesn't return call ids, each call gets a fresh one
+    fn convert_tool_calls(calls: Vec<OllamaToolCall>) -> Vec<ToolCall> {
+        calls.into_iter().map(|call| ToolCall {
+            id: format!("call_{}", uuid::Uuid::new_v4()),
+            r#type: "function".to_string(),
+            function: Function {
+                name: call.function.name,
+                arguments: call.function.arguments.to_string(),
+            },
+        }).collect()
+    }
+
+    fn finish_reason(response: &OllamaChatResponse, has_tool_calls: bool) -> Option<FinishReason> {
+        if has_tool_calls {
+            return Some(FinishReason::ToolCalls);
+        }
+        match response.done_reason.as_deref() {
+            Some("length") => Some(FinishReason::TokenLimitReached),
+            _ if response.done => Some(FinishReason::StopSequenceReached),
+            _ => None,
+        }
+    }
+
+    fn usage(response: &OllamaChatResponse) -> Option<Usage> {
+        if !response.done {
+            return None;
+        }
+        let prompt_tokens = response.prompt_eval_count.unwrap_or(0);
+        let completion_tokens = response.eval_count.unwrap_or(0);
+        Some(Usage {
+            input_tokens: None,
+            input_tokens_details: None,
+            output_tokens: None,
+            output_tokens_details: None,
+            prompt_tokens: Some(prompt_tokens),
+            completion_tokens: Some(completion_tokens),
+            total_tokens: prompt_tokens + completion_tokens,
+            prompt_tokens_details: None,
+            completion_tokens_details: None,
+        })
+    }
+
+    pub(crate) fn convert_from_ollama_format(response: OllamaChatResponse) -> ChatCompletionResponse {
+        let usage = Self::usage(&response);
+        let tool_calls = response.message.tool_calls.clone()
+            .map(Self::convert_tool_calls)
+            .filter(|calls| !calls.is_empty());
+        let finish_reason = Self::finish_reason(&response, tool_calls.is_some());
+        let message = response.message;
+
+        ChatCompletionResponse {
+            id: Some(format!("ollama-{}", uuid::Uuid::new_v4())),
+            object: "chat.completion".to_string(),
+            created: Self::now(),
+            model: response.model,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage::Assistant {
+                    content: Some(message.content).filter(|c| !c.is_empty()).map(ChatMessageContent::Text),
+                    reasoning_content: message.thinking.filter(|t| !t.is_empty()),
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls,
+                },
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            service_tier: None,
+            system_fingerprint: None,
+        }
+    }
+
+    /// One line of the streamed response as a chunk, tool calls come whole in a single line
+    pub(crate) fn convert_stream_line(response: OllamaChatResponse) -> ChatCompletionChunkResponse {
+        let usage = Self::usage(&response);
+        let tool_calls = response.message.tool_calls.clone()
+            .map(|calls| Self::convert_tool_calls(calls).into_iter().enumerate().map(|(index, call)| DeltaToolCall {
+                index: Some(index as u32),
+                id: Some(call.id),
+                r#type: Some(call.r#type),
+                function: DeltaFunction {
+                    name: Some(call.function.name),
+                    arguments: Some(call.function.arguments),
+                },
+            }).collect::<Vec<_>>())
+            .filter(|calls| !calls.is_empty());
+        let finish_reason = Self::finish_reason(&response, tool_calls.is_some());
+        let message = response.message;
+
+        ChatCompletionChunkResponse {
+            id: Some(format!("ollama-{}", uuid::Uuid::new_v4())),
+            object: "chat.completion.chunk".to_string(),
+            created: Self::now(),
+            model: response.model,
+            choices: vec![ChatCompletionChunkChoice {
+                index: Some(0),
+                delta: DeltaChatMessage::Assistant {
+                    content: Some(message.content).filter(|c| !c.is_empty()).map(ChatMessageContent::Text),
+                    reasoning_content: message.thinking.filter(|t| !t.is_empty()),
+                    refusal: None,
+                    name: None,
+                    tool_calls,
+                },
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            system_fingerprint: None,
+        }
+    }
+
+    /// The stream is newline delimited JSON, a line may be split across network chunks
+    fn parse_ollama_stream(response: reqwest::Response) -> LlmStream {
+        let parsed_stream = response.bytes_stream()
+            .scan(String::new(), |buffer, chunk_result| {
+                let results = match chunk_result {
+                    Ok(chunk) => {
+                        buffer.push_str(&String::from_utf8_lossy(&chunk));
+                        let mut results = Vec::new();
+                        while let Some(end) = buffer.find('\n') {
+                            let line: String = buffer.drain(..=end).collect();
+                            if let Some(result) = Self::parse_stream_line(&line) {
+                                results.push(result);
+                            }
+                        }
+                        results
+                    }
+                    Err(e) => vec![Err(LlmError::from(e))],
+                };
+                futures::future::ready(Some(results))
+            })
+            .flat_map(stream::iter);
+
+        Box::new(Box::pin(parsed_stream))
+    }
+
+    fn parse_stream_line(line: &str) -> Option<Result<ChatCompletionChunkResponse, LlmError>> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        // errors after the stream started come as a line of their own
+        if let Ok(serde_json::Value::Object(error)) = serde_json::from_str::<serde_json::Value>(line) {
+            if let Some(message) = error.get("error").and_then(|e| e.as_str()) {
+                return Some(Err(format!("Ollama API streaming error: {}", message).into()));
+            }
+        }
+        Some(serde_json::from_str::<OllamaChatResponse>(line)
+            .map(Self::convert_stream_line)
+            .map_err(|e| LlmError::Parse(format!("Failed to parse Ollama stream line {}: {}", line, e))))
+    }
+
+    fn now() -> u32 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        let tags = self.tags().await?;
+        Ok(ListModelResponse {
+            object: "list".to_string(),
+            data: tags.models.into_iter().map(|tag| Model {
+                id: tag.name,
+                object: "model".to_string(),
+                created: tag.modified_at
+                    .and_then(|date| chrono::DateTime::parse_from_rfc3339(&date).ok())
+                    .map(|date| date.timestamp() as u32),
+                owned_by: "ollama".to_string(),
+            }).collect(),
+        })
+    }
+
+    /// The most recently pulled chat model, embedding models can't answer
+    async fn default_model(&self) -> Result<String, LlmError> {
+        let mut models = self.models().await?.data;
+        models.sort_by_key(|m| std::cmp::Reverse(m.created));
+
+        models.iter()
+            .find(|m| !m.id.to_lowercase().contains("embed"))
+            .map(|m| m.id.clone())
+            .ok_or_else(|| "no model available, pull one with `ollama pull <model>`".into())
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        let ollama_request = self.convert_to_ollama_format(&request, false);
+        let response = self.send_chat(&ollama_request).await?;
+
+        let ollama_response: OllamaChatResponse = response.json().await?;
+        Ok(Self::convert_from_ollama_format(ollama_response))
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let ollama_request = self.convert_to_ollama_format(&request, true);
+        let response = self.send_chat(&ollama_request).await?;
+
+        Ok(Self::parse_ollama_stream(response))
+    }
+
+    fn supports_functions(&self, model: String) -> bool {
+        // unknown until the model rejects a request with tools
+        !self.no_tools.read().unwrap().contains(&model)
+    }
+
+    fn supports_structured_output(&self, model: String) -> bool {
+        true
+    }
+
+    fn supports_reasoning_content(&self, model: String) -> bool {
+        // thinking models answer with their reasoning in the thinking field, read as reasoning_content
+        true
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        self.embeddings.embed(&model, &inputs).await
+    }
+
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "ollama",
+            display_name: "Ollama",
+            env_vars: vec![
+                EnvVar::optional("OLLAMA_BASE_URL", "Ollama server URL (default http://localhost:11434)"),
+            ],
+        }
+    }
+}
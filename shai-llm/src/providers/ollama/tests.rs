@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use crate::providers::ollama::api::OllamaChatResponse;
+    use crate::providers::ollama::OllamaProvider;
+    use crate::image::{image_part, text_part};
+    use crate::stream::ChatStreamAccumulator;
+    use openai_dive::v1::resources::chat::{ChatCompletionFunction, ChatCompletionParametersBuilder, ChatCompletionTool, ChatCompletionToolType, ChatMessage, ChatMessageContent, Function, ToolCall};
+    use serde_json::json;
+
+    #[test]
+    fn test_request_conversion() {
+        let provider = OllamaProvider::new(None);
+        let request = ChatCompletionParametersBuilder::default()
+            .model("qwen3")
+            .messages(vec![
+                ChatMessage::System { content: ChatMessageContent::Text("You are a coder.".to_string()), name: None },
+                ChatMessage::User {
+                    content: ChatMessageContent::ContentPart(vec![text_part("what is this?"), image_part("data:image/png;base64,iVBORw0KGgo")]),
+                    name: None,
+                },
+                ChatMessage::Assistant {
+                    content: None,
+                    reasoning_content: None,
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: Some(vec![ToolCall {
+                        id: "call_1".to_string(),
+                        r#type: "function".to_string(),
+                        function: Function { name: "read".to_string(), arguments: "{\"path\":\"a.png\"}".to_string() },
+                    }]),
+                },
+                ChatMessage::Tool { content: ChatMessageContent::Text("done".to_string()), tool_call_id: "call_1".to_string() },
+            ])
+            .tools(vec![ChatCompletionTool {
+                r#type: ChatCompletionToolType::Function,
+                function: ChatCompletionFunction {
+                    name: "read".to_string(),
+                    description: Some("Read a file".to_string()),
+                    parameters: json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+                },
+            }])
+            .temperature(0.3)
+            .build()
+            .unwrap();
+
+        let converted = serde_json::to_value(provider.convert_to_ollama_format(&request, true)).unwrap();
+        assert_eq!(converted["stream"], json!(true));
+        assert_eq!(converted["options"]["temperature"], json!(0.3f32));
+        assert_eq!(converted["tools"][0]["function"]["name"], json!("read"));
+        assert_eq!(converted["tools"][0]["type"], json!("function"));
+
+        let messages = converted["messages"].as_array().unwrap();
+        assert_eq!(messages[0], json!({"role": "system", "content": "You are a coder."}));
+        assert_eq!(messages[1], json!({"role": "user", "content": "what is this?", "images": ["iVBORw0KGgo"]}));
+        assert_eq!(messages[2]["tool_calls"][0]["function"]["arguments"], json!({"path": "a.png"}));
+        assert_eq!(messages[3], json!({"role": "tool", "content": "done", "tool_name": "read"}));
+    }
+
+    #[test]
+    fn test_response_conversion() {
+        let response: OllamaChatResponse = serde_json::from_value(json!({
+            "model": "qwen3",
+            "created_at": "2025-01-01T00:00:00Z",
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "thinking": "the user wants the file",
+                "tool_calls": [{"function": {"name": "read", "arguments": {"path": "a.rs"}}}]
+            },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 5
+        })).unwrap();
+
+        let converted = OllamaProvider::convert_from_ollama_format(response.clone());
+        let ChatMessage::Assistant { content, reasoning_content, tool_calls: Some(calls), .. } = &converted.choices[0].message else {
+            panic!("tool calls expected");
+        };
+        assert_eq!(content, &None);
+        assert_eq!(reasoning_content.as_deref(), Some("the user wants the file"));
+        assert_eq!(calls[0].function.name, "read");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&calls[0].function.arguments).unwrap(), json!({"path": "a.rs"}));
+        assert_eq!(converted.usage.unwrap().total_tokens, 17);
+
+        // the same message streamed rebuilds the same tool call
+        let mut accumulator = ChatStreamAccumulator::new("qwen3".to_string());
+        accumulator.push(OllamaProvider::convert_stream_line(response));
+        let ChatMessage::Assistant { tool_calls: Some(calls), .. } = &accumulator.finish().choices[0].message else {
+            panic!("tool calls expected");
+        };
+        assert_eq!(calls[0].function.name, "read");
+    }
+}
@@ -1,5 +1,6 @@
 // llm/providers/openai.rs
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::embedding::{EmbeddingClient, EmbeddingResponse};
 use async_trait::async_trait;
 use futures::StreamExt;
 use openai_dive::v1::{
@@ -12,13 +13,15 @@ use openai_dive::v1::{
 
 pub struct OpenAIProvider {
     client: Client,
+    embeddings: EmbeddingClient,
 }
 
 impl OpenAIProvider {
     pub fn new(api_key: String) -> Self {
+        let embeddings = EmbeddingClient::new("https://api.openai.com/v1".to_string(), Some(api_key.clone()));
         let mut client = Client::new(api_key);
         client.set_base_url("https://api.openai.com/v1");
-        Self { client }
+        Self { client, embeddings }
     }
 
     /// Create OpenAI provider from environment variables
@@ -34,7 +37,7 @@ impl OpenAIProvider {
 impl LlmProvider for OpenAIProvider {
     async fn models(&self) -> Result<ListModelResponse, LlmError> {
         let response = self.client.models().list().await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
         Ok(response)
     }
 
@@ -50,7 +53,7 @@ impl LlmProvider for OpenAIProvider {
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
         let response = self.client.chat().create(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
         Ok(response)
     }
 
@@ -59,10 +62,10 @@ impl LlmProvider for OpenAIProvider {
         request.stream = Some(true);
         
         let stream = self.client.chat().create_stream(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         let converted_stream = stream.map(|result| {
-            result.map_err(|e| Box::new(e) as LlmError)
+            result.map_err(|e| LlmError::from(e))
         });
 
         Ok(Box::new(Box::pin(converted_stream)))
@@ -76,6 +79,14 @@ impl LlmProvider for OpenAIProvider {
         true
     }
 
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        self.embeddings.embed(&model, &inputs).await
+    }
+
     fn name(&self) -> &'static str {
         "openai"
     }
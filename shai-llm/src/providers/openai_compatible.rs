@@ -1,5 +1,6 @@
 // llm/providers/openai_compatible.rs
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::embedding::{EmbeddingClient, EmbeddingResponse};
 use async_trait::async_trait;
 use futures::StreamExt;
 use openai_dive::v1::{
@@ -14,13 +15,15 @@ use serde_json::Value;
 
 pub struct OpenAICompatibleProvider {
     client: Client,
+    embeddings: EmbeddingClient,
 }
 
 impl OpenAICompatibleProvider {
     pub fn new(api_key: String, base_url: String) -> Self {
+        let embeddings = EmbeddingClient::new(base_url.clone(), Some(api_key.clone()));
         let mut client = Client::new(api_key);
         client.set_base_url(&base_url);
-        Self { client }
+        Self { client, embeddings }
     }
 
     /// Create OpenAI Compatible provider from environment variables
@@ -39,13 +42,13 @@ impl OpenAICompatibleProvider {
 impl LlmProvider for OpenAICompatibleProvider {
     async fn models(&self) -> Result<ListModelResponse, LlmError> {
         let response = self.client.models().list().await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
         Ok(response)
     }
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
         let mut response = self.client.chat().create(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         Ok(response)
     }
@@ -55,10 +58,10 @@ impl LlmProvider for OpenAICompatibleProvider {
         request.stream = Some(true);
         
         let stream = self.client.chat().create_stream(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         let converted_stream = stream.map(|result| {
-            result.map_err(|e| Box::new(e) as LlmError)
+            result.map_err(|e| LlmError::from(e))
         });
 
         Ok(Box::new(Box::pin(converted_stream)))
@@ -72,6 +75,19 @@ impl LlmProvider for OpenAICompatibleProvider {
         true
     }
 
+    fn supports_reasoning_content(&self, model: String) -> bool {
+        // reasoning models served by vLLM-like servers fill reasoning_content
+        true
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        self.embeddings.embed(&model, &inputs).await
+    }
+
     fn name(&self) -> &'static str {
         "openai_compatible"
     }
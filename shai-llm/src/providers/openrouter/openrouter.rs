@@ -50,21 +50,22 @@ impl OpenRouterProvider {
             .header("Content-Type", "application/json")
             .send()
             .await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            return Err(LlmError::from_status(
+                status.as_u16(),
+                None,
                 format!("OpenRouter API error {}: {}", status, text)
-            )) as LlmError);
+            ));
         }
 
         let openrouter_response: OpenRouterModelsResponse = response
             .json()
             .await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         Ok(openrouter_response)
     }
@@ -91,7 +92,7 @@ impl LlmProvider for OpenRouterProvider {
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
         let response = self.client.chat().create(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
         Ok(response)
     }
 
@@ -100,10 +101,10 @@ impl LlmProvider for OpenRouterProvider {
         request.stream = Some(true);
         
         let stream = self.client.chat().create_stream(request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         let converted_stream = stream.map(|result| {
-            result.map_err(|e| Box::new(e) as LlmError)
+            result.map_err(|e| LlmError::from(e))
         });
 
         Ok(Box::new(Box::pin(converted_stream)))
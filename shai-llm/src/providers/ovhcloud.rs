@@ -1,5 +1,6 @@
 // llm/providers/ovhcloud.rs
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::embedding::{EmbeddingClient, EmbeddingResponse};
 use async_trait::async_trait;
 use futures::StreamExt;
 use openai_dive::v1::{
@@ -17,14 +18,16 @@ const OVH_API_BASE: &str = "https://oai.endpoints.kepler.ai.cloud.ovh.net/v1";
 
 pub struct OvhCloudProvider {
     client: Client,
+    embeddings: EmbeddingClient,
 }
 
 impl OvhCloudProvider {
     pub fn new(api_key: String, base_url: Option<String>) -> Self {
-        let mut client = Client::new(api_key);
         let url = base_url.unwrap_or_else(|| OVH_API_BASE.to_string());
+        let embeddings = EmbeddingClient::new(url.clone(), Some(api_key.clone()));
+        let mut client = Client::new(api_key);
         client.set_base_url(&url);
-        Self { client }
+        Self { client, embeddings }
     }
 
     /// Create OVH Cloud provider from environment variables
@@ -51,7 +54,7 @@ impl OvhCloudProvider {
 impl LlmProvider for OvhCloudProvider {
     async fn models(&self) -> Result<ListModelResponse, LlmError> {
         let response = self.client.models().list().await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
         Ok(response)
     }
 
@@ -68,7 +71,7 @@ impl LlmProvider for OvhCloudProvider {
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
         let sanitized_request = self.sanitize_request(request);
         let mut response = self.client.chat().create(sanitized_request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         Ok(response)
     }
@@ -78,10 +81,10 @@ impl LlmProvider for OvhCloudProvider {
         let sanitized_request = self.sanitize_request(request);
         
         let stream = self.client.chat().create_stream(sanitized_request).await
-            .map_err(|e| Box::new(e) as LlmError)?;
+            .map_err(|e| LlmError::from(e))?;
 
         let converted_stream = stream.map(|result| {
-            result.map_err(|e| Box::new(e) as LlmError)
+            result.map_err(|e| LlmError::from(e))
         });
 
         Ok(Box::new(Box::pin(converted_stream)))
@@ -95,6 +98,19 @@ impl LlmProvider for OvhCloudProvider {
         true
     }
 
+    fn supports_reasoning_content(&self, model: String) -> bool {
+        // reasoning models served by vLLM-like servers fill reasoning_content
+        true
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, model: String, inputs: Vec<String>) -> Result<EmbeddingResponse, LlmError> {
+        self.embeddings.embed(&model, &inputs).await
+    }
+
     fn name(&self) -> &'static str {
         "ovhcloud"
     }
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// Requests and tokens a provider accepts per minute, None is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_minute: Option<u64>,
+}
+
+impl RateLimit {
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_minute.is_none()
+    }
+}
+
+/// An LLM call held back by the rate limiter of its provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct Throttle {
+    pub provider: String,
+    pub wait: Duration,
+}
+
+/// Called when an LLM call of the task has to wait for its rate limiter
+pub type ThrottleHook = Arc<dyn Fn(Throttle) + Send + Sync>;
+
+tokio::task_local! {
+    static THROTTLE_HOOK: ThrottleHook;
+}
+
+/// Run `future` with `hook` told about every wait of its LLM calls, so the caller can show it
+pub async fn with_throttle_hook<F: Future>(hook: ThrottleHook, future: F) -> F::Output {
+    THROTTLE_HOOK.scope(hook, future).await
+}
+
+/// Refills `capacity` per minute, continuously. The token bucket goes below zero when a call
+/// used more than what was left, the next call waits until it is paid back
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    level: f64,
+}
+
+impl Bucket {
+    fn full(capacity: f64) -> Self {
+        Self { capacity, level: capacity }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.level = (self.level + elapsed.as_secs_f64() * self.capacity / 60.0).min(self.capacity);
+    }
+
+    /// Time until the bucket holds `needed`
+    fn wait_for(&self, needed: f64) -> Duration {
+        if self.level >= needed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((needed - self.level) * 60.0 / self.capacity)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+    updated: Instant,
+}
+
+impl Buckets {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.updated;
+        self.updated = now;
+        self.requests.iter_mut().chain(self.tokens.iter_mut()).for_each(|bucket| bucket.refill(elapsed));
+    }
+}
+
+/// Token buckets of a provider: calls wait for a free request and a positive token balance
+/// instead of failing on the provider's quota
+#[derive(Debug)]
+pub struct RateLimiter {
+    provider: String,
+    limit: RateLimit,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(provider: &str, limit: RateLimit) -> Self {
+        let buckets = Buckets {
+            requests: limit.requests_per_minute.map(|rpm| Bucket::full(rpm.max(1) as f64)),
+            tokens: limit.tokens_per_minute.map(|tpm| Bucket::full(tpm.max(1) as f64)),
+            updated: Instant::now(),
+        };
+        Self { provider: provider.to_string(), limit, buckets: Mutex::new(buckets) }
+    }
+
+    /// The limiter of `provider`, shared by every client of the process so that concurrent
+    /// sessions on the same provider draw from the same quota
+    pub fn shared(provider: &str, limit: RateLimit) -> Arc<Self> {
+        static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<RateLimiter>>>> = OnceLock::new();
+        let mut limiters = LIMITERS.get_or_init(Default::default).lock().unwrap();
+        match limiters.get(provider) {
+            Some(limiter) if limiter.limit == limit => limiter.clone(),
+            _ => {
+                let limiter = Arc::new(Self::new(provider, limit));
+                limiters.insert(provider.to_string(), limiter.clone());
+                limiter
+            }
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Zero and a request taken when the call may go now, the time to wait otherwise
+    fn try_acquire(&self) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.refill();
+        let wait = buckets.requests.as_ref().map_or(Duration::ZERO, |bucket| bucket.wait_for(1.0))
+            .max(buckets.tokens.as_ref().map_or(Duration::ZERO, |bucket| bucket.wait_for(0.0)));
+        if wait.is_zero() {
+            if let Some(requests) = &mut buckets.requests {
+                requests.level -= 1.0;
+            }
+        }
+        wait
+    }
+
+    /// Wait until a call may be sent, telling the throttle hook of the task each time it waits
+    pub async fn acquire(&self) {
+        loop {
+            let wait = self.try_acquire();
+            if wait.is_zero() {
+                return;
+            }
+            let throttle = Throttle { provider: self.provider.clone(), wait };
+            let _ = THROTTLE_HOOK.try_with(|hook| hook(throttle));
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take the tokens a call used from the token bucket
+    pub fn charge(&self, tokens: u64) {
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.refill();
+        if let Some(bucket) = &mut buckets.tokens {
+            bucket.level -= tokens as f64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(requests_per_minute: Option<u32>, tokens_per_minute: Option<u64>) -> RateLimit {
+        RateLimit { requests_per_minute, tokens_per_minute }
+    }
+
+    #[test]
+    fn test_requests_wait_for_the_bucket_to_refill() {
+        let limiter = RateLimiter::new("openai", limit(Some(2), None));
+        assert!(limiter.try_acquire().is_zero());
+        assert!(limiter.try_acquire().is_zero());
+
+        // 2 per minute: the next request comes back 30s later
+        let wait = limiter.try_acquire();
+        assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30), "{:?}", wait);
+    }
+
+    #[tokio::test]
+    async fn test_tokens_over_the_budget_are_paid_back_first() {
+        let limiter = RateLimiter::new("mistral", limit(None, Some(60_000)));
+        assert!(limiter.try_acquire().is_zero());
+
+        // a call used 100 tokens more than the bucket held, paid back at 1000 per second
+        limiter.charge(60_100);
+        let wait = limiter.try_acquire();
+        assert!(wait > Duration::from_millis(50) && wait <= Duration::from_millis(100), "{:?}", wait);
+
+        let throttles = Arc::new(Mutex::new(Vec::new()));
+        let seen = throttles.clone();
+        let hook: ThrottleHook = Arc::new(move |throttle| seen.lock().unwrap().push(throttle));
+        let started = Instant::now();
+        with_throttle_hook(hook, limiter.acquire()).await;
+
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        let throttles = throttles.lock().unwrap();
+        assert!(!throttles.is_empty());
+        assert_eq!(throttles[0].provider, "mistral");
+    }
+
+    #[test]
+    fn test_shared_limiters_by_provider() {
+        let a = RateLimiter::shared("shared-test", limit(Some(10), None));
+        let b = RateLimiter::shared("shared-test", limit(Some(10), None));
+        assert!(Arc::ptr_eq(&a, &b));
+
+        // a new limit replaces the limiter
+        let c = RateLimiter::shared("shared-test", limit(Some(20), None));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert!(limit(None, None).is_unlimited());
+    }
+}
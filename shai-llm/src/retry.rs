@@ -0,0 +1,272 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::provider::LlmError;
+
+/// Status codes worth another attempt: rate limited or the server failing for a moment
+const RETRYABLE_STATUS: &[u16] = &[429, 500, 502, 503];
+
+/// Error messages of transient failures, for errors that only come as text
+const TRANSIENT_MESSAGES: &[&str] = &[
+    "timed out",
+    "timeout",
+    "connection reset",
+    "connection closed",
+    "error sending request",
+    "too many requests",
+    "rate limit",
+    "internal server error",
+    "bad gateway",
+    "service unavailable",
+    "overloaded",
+];
+
+/// Phrases that introduce the delay in the text of a rate-limit error, e.g. "Please try again in 20s"
+const RETRY_HINTS: &[&str] = &["try again in", "retry after", "retry in"];
+
+/// How LlmClient retries a request that failed on a transient error
+/// (timeout, connection reset, HTTP 429/500/502/503). Other errors are returned right away.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// attempts in total, 1 never retries
+    pub max_attempts: u32,
+    /// delay before the first retry, doubled on each retry after
+    pub base_delay: Duration,
+    /// upper bound of a single delay, Retry-After included
+    pub max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn new(max_attempts: u32, base_delay: Duration) -> Self {
+        Self { max_attempts: max_attempts.max(1), base_delay, ..Self::default() }
+    }
+
+    /// Send each request once
+    pub fn none() -> Self {
+        Self::new(1, Duration::ZERO)
+    }
+
+    /// Delay before retry number `retry` (from 1), the server's Retry-After wins when it gave one
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after.unwrap_or_else(|| {
+            let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+            // a little jitter so clients rate limited together don't come back together
+            let jitter = fastrand::u64(0..=backoff.as_millis() as u64 / 10);
+            backoff + Duration::from_millis(jitter)
+        });
+        delay.min(self.max_delay)
+    }
+
+    /// Run `call` until it succeeds, fails on an error that is not transient, or runs out of attempts
+    pub async fn run<T, F, Fut>(&self, mut call: F) -> Result<T, LlmError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, LlmError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(error) if attempt < self.max_attempts && is_retryable(&error) => {
+                    tokio::time::sleep(self.delay(attempt, retry_after(&error))).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Non-success HTTP response of a provider that makes its own requests,
+/// keeps what the retry policy needs from the response
+#[derive(Debug)]
+pub struct HttpError {
+    pub status: u16,
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl HttpError {
+    /// Read the error response, `context` prefixes its body in the message
+    pub async fn from_response(response: reqwest::Response, context: &str) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response.text().await.unwrap_or_default();
+        Self { status, retry_after, message: format!("{}: {}", context, body) }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// Whether the request may succeed if sent again
+pub fn is_retryable(error: &LlmError) -> bool {
+    match error {
+        LlmError::Timeout(_) | LlmError::RateLimited { .. } => true,
+        LlmError::Http { status, .. } => RETRYABLE_STATUS.contains(status),
+        LlmError::Auth(_) | LlmError::Parse(_) => false,
+        LlmError::Other(error) => {
+            if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+                return error.is_connect();
+            }
+            if let Some(error) = error.downcast_ref::<std::io::Error>() {
+                return matches!(error.kind(),
+                    std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionAborted);
+            }
+            is_transient_message(&error.to_string())
+        }
+    }
+}
+
+/// Delay the server asked for, from the Retry-After header or the error message
+pub fn retry_after(error: &LlmError) -> Option<Duration> {
+    error.retry_after()
+}
+
+/// Retry-After is either a number of seconds or an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
+
+/// Delay the text of an error asks for, for errors that carry the body but not the headers
+/// (the ones of openai_dive). Compound delays like "1m30s" are summed
+pub(crate) fn parse_retry_hint(message: &str) -> Option<Duration> {
+    let message = message.to_lowercase();
+    let start = RETRY_HINTS.iter().find_map(|hint| message.find(hint).map(|i| i + hint.len()))?;
+    let mut rest = message[start..].trim_start();
+    let mut delay = None;
+    loop {
+        let number_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let Ok(value) = rest[..number_len].parse::<f64>() else {
+            break;
+        };
+        let after = rest[number_len..].trim_start();
+        let unit_len = after.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(after.len());
+        let seconds = match &after[..unit_len] {
+            "ms" | "millisecond" | "milliseconds" => value / 1000.0,
+            "" | "s" | "sec" | "secs" | "second" | "seconds" => value,
+            "m" | "min" | "mins" | "minute" | "minutes" => value * 60.0,
+            "h" | "hour" | "hours" => value * 3600.0,
+            _ => break,
+        };
+        let Ok(part) = Duration::try_from_secs_f64(seconds) else {
+            break;
+        };
+        delay = Some(delay.unwrap_or(Duration::ZERO) + part);
+        rest = &after[unit_len..];
+    }
+    delay
+}
+
+fn is_transient_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    TRANSIENT_MESSAGES.iter().any(|pattern| message.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::error::APIError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn http_error(status: u16, retry_after: Option<Duration>) -> LlmError {
+        LlmError::from(HttpError { status, retry_after, message: format!("error {}", status) })
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(is_retryable(&http_error(429, None)));
+        assert!(is_retryable(&http_error(503, None)));
+        assert!(!is_retryable(&http_error(400, None)));
+        assert!(!is_retryable(&http_error(401, None)));
+
+        assert!(is_retryable(&LlmError::from(APIError::RateLimitError("slow down".to_string()))));
+        assert!(is_retryable(&LlmError::from(APIError::UnknownError(502, "".to_string()))));
+        assert!(!is_retryable(&LlmError::from(APIError::AuthenticationError("bad key".to_string()))));
+        assert!(!is_retryable(&LlmError::from(APIError::InvalidRequestError("connection reset".to_string()))));
+
+        let reset = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset by peer");
+        assert!(is_retryable(&LlmError::from(reset)));
+        assert!(!is_retryable(&LlmError::Parse("expected value at line 1".to_string())));
+        assert!(is_retryable(&LlmError::from("operation timed out")));
+        assert!(!is_retryable(&LlmError::from("model not found")));
+    }
+
+    #[test]
+    fn test_delay() {
+        let config = RetryConfig { max_attempts: 5, base_delay: Duration::from_millis(100), max_delay: Duration::from_secs(1) };
+        assert!((100..=110).contains(&config.delay(1, None).as_millis()));
+        assert!((400..=440).contains(&config.delay(3, None).as_millis()));
+        assert_eq!(config.delay(10, None), Duration::from_secs(1));
+        assert_eq!(config.delay(1, Some(Duration::from_millis(700))), Duration::from_millis(700));
+        assert_eq!(config.delay(1, Some(Duration::from_secs(120))), Duration::from_secs(1));
+
+        assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn test_retry_hint_of_the_message() {
+        assert_eq!(parse_retry_hint("Rate limit reached. Please try again in 1.5s. Visit ..."), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_retry_hint("Please try again in 250ms."), Some(Duration::from_millis(250)));
+        assert_eq!(parse_retry_hint("Too many requests, retry after 20 seconds"), Some(Duration::from_secs(20)));
+        assert_eq!(parse_retry_hint("Please try again in 1m30s."), Some(Duration::from_secs(90)));
+        assert_eq!(parse_retry_hint("Rate limit reached, try again later"), None);
+        assert_eq!(parse_retry_hint("slow down"), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_transient_errors_only() {
+        let config = RetryConfig::new(3, Duration::from_millis(1));
+
+        let calls = &AtomicU32::new(0);
+        let result = config.run(|| async move {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(http_error(503, None)),
+                _ => Ok("done"),
+            }
+        }).await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = &AtomicU32::new(0);
+        let result: Result<(), _> = config.run(|| async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(http_error(429, Some(Duration::from_millis(1))))
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = &AtomicU32::new(0);
+        let result: Result<(), _> = config.run(|| async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(http_error(401, None))
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
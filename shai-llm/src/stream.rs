@@ -0,0 +1,259 @@
+use openai_dive::v1::resources::chat::{
+    ChatCompletionChoice, ChatCompletionChunkResponse, ChatCompletionResponse, ChatMessage, ChatMessageContent,
+    DeltaChatMessage, DeltaToolCall, Function, ToolCall,
+};
+use openai_dive::v1::resources::shared::{FinishReason, Usage};
+
+use crate::client::ExtractThinkContent;
+
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// Piece of an assistant message received while the completion is still streaming
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatDelta {
+    /// model reasoning, from reasoning_content or from <think> tags in the content
+    Reasoning(String),
+    /// answer text
+    Content(String),
+}
+
+/// Fold the chunks of a streamed completion into the response `LlmClient::chat` would have returned.
+/// Reasoning and content are accumulated apart, whatever order the chunks interleave them in,
+/// and tool calls are rebuilt from their deltas by index.
+pub struct ChatStreamAccumulator {
+    id: Option<String>,
+    created: u32,
+    model: String,
+    content: String,
+    reasoning: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<FinishReason>,
+    usage: Option<Usage>,
+    /// inside a <think> block of the content
+    in_think: bool,
+    /// content not streamed yet because it may be the start of a think tag
+    pending: String,
+}
+
+impl ChatStreamAccumulator {
+    pub fn new(model: String) -> Self {
+        Self {
+            id: None,
+            created: 0,
+            model,
+            content: String::new(),
+            reasoning: String::new(),
+            tool_calls: Vec::new(),
+            finish_reason: None,
+            usage: None,
+            in_think: false,
+            pending: String::new(),
+        }
+    }
+
+    /// Add a chunk, returns the text it carries split between reasoning and answer
+    pub fn push(&mut self, chunk: ChatCompletionChunkResponse) -> Vec<ChatDelta> {
+        let mut deltas = Vec::new();
+        self.id = self.id.take().or(chunk.id);
+        self.created = chunk.created;
+        self.model = chunk.model;
+        self.usage = chunk.usage.or(self.usage.take());
+
+        for choice in chunk.choices {
+            self.finish_reason = choice.finish_reason.or(self.finish_reason.take());
+            let (content, reasoning, tool_calls) = match choice.delta {
+                DeltaChatMessage::Assistant { content, reasoning_content, tool_calls, .. } => (content, reasoning_content, tool_calls),
+                DeltaChatMessage::Untagged { content, tool_calls, .. } => (content, None, tool_calls),
+                _ => continue,
+            };
+
+            if let Some(reasoning) = reasoning.filter(|r| !r.is_empty()) {
+                self.reasoning.push_str(&reasoning);
+                deltas.push(ChatDelta::Reasoning(reasoning));
+            }
+            if let Some(ChatMessageContent::Text(text)) = content {
+                self.content.push_str(&text);
+                deltas.extend(self.split_think(&text));
+            }
+            for call in tool_calls.into_iter().flatten() {
+                self.push_tool_call(call);
+            }
+        }
+        deltas
+    }
+
+    /// Text held back at the end of the stream, in case it was the start of a think tag
+    pub fn flush(&mut self) -> Vec<ChatDelta> {
+        let text = std::mem::take(&mut self.pending);
+        self.delta(text).into_iter().collect()
+    }
+
+    /// The complete response, with <think> tags moved to reasoning_content like `chat` does
+    pub fn finish(self) -> ChatCompletionResponse {
+        let message = ChatMessage::Assistant {
+            content: Some(self.content).filter(|c| !c.is_empty()).map(ChatMessageContent::Text),
+            reasoning_content: Some(self.reasoning).filter(|r| !r.is_empty()),
+            tool_calls: Some(self.tool_calls).filter(|calls| !calls.is_empty()),
+            refusal: None,
+            name: None,
+            audio: None,
+        };
+
+        ChatCompletionResponse {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: self.created,
+            model: self.model,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message,
+                finish_reason: self.finish_reason,
+                logprobs: None,
+            }],
+            usage: self.usage,
+            service_tier: None,
+            system_fingerprint: None,
+        }
+        .extract_think_content()
+    }
+
+    /// Route content text to reasoning while inside <think>...</think>, tags may be split across chunks
+    fn split_think(&mut self, text: &str) -> Vec<ChatDelta> {
+        let mut deltas = Vec::new();
+        self.pending.push_str(text);
+        loop {
+            let tag = if self.in_think { THINK_CLOSE } else { THINK_OPEN };
+            match self.pending.find(tag) {
+                Some(pos) => {
+                    let before: String = self.pending.drain(..pos + tag.len()).take(pos).collect();
+                    deltas.extend(self.delta(before));
+                    self.in_think = !self.in_think;
+                }
+                None => {
+                    let keep = (1..tag.len()).rev()
+                        .find(|len| self.pending.ends_with(&tag[..*len]))
+                        .unwrap_or(0);
+                    let ready: String = self.pending.drain(..self.pending.len() - keep).collect();
+                    deltas.extend(self.delta(ready));
+                    return deltas;
+                }
+            }
+        }
+    }
+
+    fn delta(&self, text: String) -> Option<ChatDelta> {
+        match (text.is_empty(), self.in_think) {
+            (true, _) => None,
+            (false, true) => Some(ChatDelta::Reasoning(text)),
+            (false, false) => Some(ChatDelta::Content(text)),
+        }
+    }
+
+    fn push_tool_call(&mut self, delta: DeltaToolCall) {
+        let index = match delta.index {
+            Some(index) => index as usize,
+            // providers without index send the id on the first delta of each call
+            None if delta.id.is_some() || self.tool_calls.is_empty() => self.tool_calls.len(),
+            None => self.tool_calls.len() - 1,
+        };
+        while self.tool_calls.len() <= index {
+            self.tool_calls.push(ToolCall {
+                id: String::new(),
+                r#type: "function".to_string(),
+                function: Function { name: String::new(), arguments: String::new() },
+            });
+        }
+
+        let call = &mut self.tool_calls[index];
+        if let Some(id) = delta.id.filter(|id| !id.is_empty()) {
+            call.id = id;
+        }
+        if let Some(name) = delta.function.name.filter(|_| call.function.name.is_empty()) {
+            call.function.name = name;
+        }
+        if let Some(arguments) = delta.function.arguments {
+            call.function.arguments.push_str(&arguments);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chunk(delta: serde_json::Value) -> ChatCompletionChunkResponse {
+        serde_json::from_value(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1,
+            "model": "test",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": null}]
+        })).unwrap()
+    }
+
+    fn message(response: ChatCompletionResponse) -> ChatMessage {
+        response.choices.into_iter().next().unwrap().message
+    }
+
+    #[test]
+    fn test_interleaved_reasoning_and_content() {
+        let mut acc = ChatStreamAccumulator::new("test".to_string());
+        let mut deltas = Vec::new();
+        deltas.extend(acc.push(chunk(json!({"role": "assistant", "reasoning_content": "let me "}))));
+        deltas.extend(acc.push(chunk(json!({"role": "assistant", "content": "The answer"}))));
+        deltas.extend(acc.push(chunk(json!({"role": "assistant", "reasoning_content": "think"}))));
+        deltas.extend(acc.push(chunk(json!({"role": "assistant", "content": " is 42"}))));
+        deltas.extend(acc.flush());
+
+        assert_eq!(deltas, vec![
+            ChatDelta::Reasoning("let me ".to_string()),
+            ChatDelta::Content("The answer".to_string()),
+            ChatDelta::Reasoning("think".to_string()),
+            ChatDelta::Content(" is 42".to_string()),
+        ]);
+        let ChatMessage::Assistant { content, reasoning_content, .. } = message(acc.finish()) else { panic!() };
+        assert_eq!(content, Some(ChatMessageContent::Text("The answer is 42".to_string())));
+        assert_eq!(reasoning_content.as_deref(), Some("let me think"));
+    }
+
+    #[test]
+    fn test_think_tags_split_across_chunks() {
+        let mut acc = ChatStreamAccumulator::new("test".to_string());
+        let mut deltas = Vec::new();
+        for text in ["<thi", "nk>checking", " the file</th", "ink>Done", "<"] {
+            deltas.extend(acc.push(chunk(json!({"role": "assistant", "content": text}))));
+        }
+        deltas.extend(acc.flush());
+
+        let reasoning: String = deltas.iter().filter_map(|d| match d { ChatDelta::Reasoning(t) => Some(t.as_str()), _ => None }).collect();
+        let content: String = deltas.iter().filter_map(|d| match d { ChatDelta::Content(t) => Some(t.as_str()), _ => None }).collect();
+        assert_eq!(reasoning, "checking the file");
+        assert_eq!(content, "Done<");
+
+        let ChatMessage::Assistant { content, reasoning_content, .. } = message(acc.finish()) else { panic!() };
+        assert_eq!(reasoning_content.as_deref(), Some("checking the file"));
+        assert_eq!(content, Some(ChatMessageContent::Text("Done<".to_string())));
+    }
+
+    #[test]
+    fn test_tool_calls_rebuilt_from_deltas() {
+        let mut acc = ChatStreamAccumulator::new("test".to_string());
+        acc.push(chunk(json!({"role": "assistant", "tool_calls": [
+            {"index": 0, "id": "call_1", "type": "function", "function": {"name": "read", "arguments": "{\"pa"}}
+        ]})));
+        acc.push(chunk(json!({"role": "assistant", "tool_calls": [
+            {"index": 1, "id": "call_2", "type": "function", "function": {"name": "ls", "arguments": "{}"}}
+        ]})));
+        acc.push(chunk(json!({"role": "assistant", "tool_calls": [
+            {"index": 0, "function": {"arguments": "th\": \"a.rs\"}"}}
+        ]})));
+
+        let ChatMessage::Assistant { tool_calls: Some(calls), .. } = message(acc.finish()) else { panic!() };
+        assert_eq!(calls.len(), 2);
+        assert_eq!((calls[0].id.as_str(), calls[0].function.name.as_str()), ("call_1", "read"));
+        assert_eq!(calls[0].function.arguments, "{\"path\": \"a.rs\"}");
+        assert_eq!((calls[1].id.as_str(), calls[1].function.arguments.as_str()), ("call_2", "{}"));
+    }
+}
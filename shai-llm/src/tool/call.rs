@@ -3,7 +3,7 @@ use async_trait::async_trait;
 
 use openai_dive::v1::resources::chat::{ChatCompletionFunction, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatCompletionTool, ChatCompletionToolChoice, ChatCompletionToolType, ChatMessage};
 
-use crate::{provider::LlmError, tool::{call_fc_auto::ToolCallFunctionCallingAuto, call_fc_required::ToolCallFunctionCallingRequired, call_structured_output::ToolCallStructuredOutput, ToolBox}, LlmClient, ToolCallMethod, ToolDescription};
+use crate::{provider::LlmError, stream::ChatDelta, tool::{call_fc_auto::{ToolCallFunctionCallingAuto, ToolCallFunctionCallingAutoStreaming}, call_fc_required::ToolCallFunctionCallingRequired, call_structured_output::{ToolCallStructuredOutput, ToolCallStructuredOutputStreaming}, call_xml::ToolCallXml, partial_json::StructuredStreamEvent, ToolBox}, LlmClient, ToolCallMethod, ToolDescription};
 
 
 #[async_trait]
@@ -29,7 +29,14 @@ impl LlmToolCall for LlmClient {
                 self.chat_with_tools_try_all(request, tools).await
             }
             ToolCallMethod::FunctionCall => {
-                self.chat_with_tools_fc_auto(request, tools).await
+                // models without tool support get the tools described in a structured output
+                if !self.supports_functions(&request.model) {
+                    return self.chat_with_tools_so(request, tools).await;
+                }
+                match self.chat_with_tools_fc_auto(request.clone(), tools).await {
+                    Err(_) if !self.supports_functions(&request.model) => self.chat_with_tools_so(request, tools).await,
+                    result => result,
+                }
             }
             ToolCallMethod::FunctionCallRequired => {
                 self.chat_with_tools_fc_required(request, tools).await
@@ -37,8 +44,59 @@ impl LlmToolCall for LlmClient {
             ToolCallMethod::StructuredOutput => {
                 self.chat_with_tools_so(request, tools).await
             }
-            ToolCallMethod::Parsing => {
-                Err(LlmError::from("method not supported"))
+            ToolCallMethod::Xml => {
+                self.chat_with_tools_xml(request, tools).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+pub trait LlmToolCallStreaming {
+    /// Same as `chat_with_tools` but streams the completion, calling `on_delta` with the
+    /// reasoning and answer text as it arrives. The returned response is the same as the
+    /// non-streaming one. Methods or providers that can't stream tool calls fall back to
+    /// the non-streaming call and produce no delta.
+    async fn chat_with_tools_streaming(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        method: ToolCallMethod,
+        on_delta: &mut (dyn FnMut(ChatDelta) + Send)
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl LlmToolCallStreaming for LlmClient {
+    async fn chat_with_tools_streaming(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        method: ToolCallMethod,
+        on_delta: &mut (dyn FnMut(ChatDelta) + Send)
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        if !self.supports_tool_call_streaming() {
+            return self.chat_with_tools(request, tools, method).await;
+        }
+
+        match method {
+            ToolCallMethod::FunctionCall if self.supports_functions(&request.model) => {
+                match self.chat_with_tools_fc_auto_streaming(request.clone(), tools, on_delta).await {
+                    Err(_) if !self.supports_functions(&request.model) => {
+                        self.chat_with_tools_streaming(request, tools, ToolCallMethod::StructuredOutput, on_delta).await
+                    }
+                    result => result,
+                }
+            }
+            ToolCallMethod::FunctionCall | ToolCallMethod::StructuredOutput => {
+                self.chat_with_tools_so_streaming(request, tools, &mut |event| {
+                    if let StructuredStreamEvent::ContentDelta(text) = event {
+                        on_delta(ChatDelta::Content(text));
+                    }
+                }).await
+            }
+            _ => {
+                self.chat_with_tools(request, tools, method).await
             }
         }
     }
@@ -1,9 +1,10 @@
 use std::sync::Arc;
 use async_trait::async_trait;
+use futures::StreamExt;
 
 use openai_dive::v1::resources::chat::{ChatCompletionFunction, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatCompletionTool, ChatCompletionToolChoice, ChatCompletionToolType, ChatMessage};
 
-use crate::{provider::LlmError, tool::ToolBox, LlmClient, ToolDescription};
+use crate::{provider::LlmError, stream::{ChatDelta, ChatStreamAccumulator}, tool::ToolBox, LlmClient, ToolDescription};
 
 pub trait FunctionCallingAutoBuilder {
     fn with_function_calling_auto(&mut self, tools: &ToolBox) -> &mut Self;
@@ -42,19 +43,68 @@ impl ToolCallFunctionCallingAuto for LlmClient {
         request: ChatCompletionParameters,
         tools: &ToolBox
     ) -> Result<ChatCompletionResponse, LlmError> {
-        let request = ChatCompletionParametersBuilder::default()
-            .model(&request.model)
-            .messages(request.messages.clone())
-            .with_function_calling_auto(&tools)
-            .temperature(0.3)
-            .build()
-            .map_err(|e| LlmError::from(e.to_string()))?;
+        let request = fc_auto_request(&request, tools)?;
 
         let response = self
             .chat(request.clone())
-            .await
-            .map_err(|e| LlmError::from(e.to_string()))?;
+            .await?;
 
         Ok(response)
     }
-}
\ No newline at end of file
+}
+
+#[async_trait]
+pub trait ToolCallFunctionCallingAutoStreaming {
+    /// Same as `chat_with_tools_fc_auto` but streams the completion, calling `on_delta`
+    /// with the reasoning and answer text as it arrives.
+    /// Falls back to the non-streaming call if the provider cannot open a stream.
+    async fn chat_with_tools_fc_auto_streaming(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        on_delta: &mut (dyn FnMut(ChatDelta) + Send)
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl ToolCallFunctionCallingAutoStreaming for LlmClient {
+    async fn chat_with_tools_fc_auto_streaming(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        on_delta: &mut (dyn FnMut(ChatDelta) + Send)
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        let fc_request = fc_auto_request(&request, tools)?;
+
+        let mut stream = match self.chat_stream(fc_request.clone()).await {
+            Ok(stream) => stream,
+            Err(_) => return self.chat_with_tools_fc_auto(request, tools).await,
+        };
+
+        let mut accumulator = ChatStreamAccumulator::new(fc_request.model.clone());
+        while let Some(chunk) = stream.next().await {
+            for delta in accumulator.push(chunk?) {
+                on_delta(delta);
+            }
+        }
+        for delta in accumulator.flush() {
+            on_delta(delta);
+        }
+
+        Ok(accumulator.finish())
+    }
+}
+
+fn fc_auto_request(request: &ChatCompletionParameters, tools: &ToolBox) -> Result<ChatCompletionParameters, LlmError> {
+    let mut builder = ChatCompletionParametersBuilder::default();
+    builder
+        .model(&request.model)
+        .messages(request.messages.clone())
+        .with_function_calling_auto(tools)
+        .temperature(0.3);
+    // a caller may require a tool call, or a given function, rather than letting the model choose
+    if let Some(tool_choice) = &request.tool_choice {
+        builder.tool_choice(tool_choice.clone());
+    }
+    builder.build().map_err(|e| LlmError::from(e.to_string()))
+}
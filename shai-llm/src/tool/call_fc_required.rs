@@ -73,8 +73,7 @@ impl ToolCallFunctionCallingRequired for LlmClient {
 
         let mut response = self
             .chat(request.clone())
-            .await
-            .map_err(|e| LlmError::from(e.to_string()))?;
+            .await?;
 
         let mut response = response;
         match &mut response.choices[0].message {
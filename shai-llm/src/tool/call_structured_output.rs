@@ -4,13 +4,18 @@ use schemars::JsonSchema;
 use serde_json::Value;
 use async_trait::async_trait;
 use openai_dive::v1::resources::chat::{
-    ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatCompletionResponseFormat, JsonSchemaBuilder,
-    ChatMessage, ChatMessageContent, Function, ToolCall as LlmToolCall
+    ChatCompletionChoice, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatCompletionResponseFormat, JsonSchemaBuilder,
+    ChatMessage, ChatMessageContent, DeltaChatMessage, Function, ToolCall as LlmToolCall
 };
+use futures::StreamExt;
 use crate::provider::LlmError;
 use crate::tool::ToolBox;
+use crate::tool::partial_json::{parse_structured_response, StructuredResponseAccumulator, StructuredStreamEvent};
 use crate::LlmClient;
 
+/// How many answers are requested in total before a response that does not follow the schema fails the step
+pub const STRUCTURED_OUTPUT_ATTEMPTS: usize = 3;
+
 /// Tool call structure for structured output JSON schema
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[schemars(deny_unknown_fields)]
@@ -121,53 +126,190 @@ impl ToolCallStructuredOutput for LlmClient {
         request: ChatCompletionParameters,
         tools: &ToolBox
     ) -> Result<ChatCompletionResponse, LlmError> {
-        // Generate tool documentation to prepend to system message
-        let tools_doc = if !tools.is_empty() {
-            let mut doc = String::from("\n\n# Available Tools\n\nYou have access to the following tools:\n\n");
-            
-            for tool in tools {
-                doc.push_str(&format!("## {}\n", tool.name()));
-                doc.push_str(&format!("**Description**: {}\n\n", tool.description()));
-                doc.push_str("**Parameters Schema**:\n```json\n");
-                doc.push_str(&serde_json::to_string_pretty(&tool.parameters_schema()).unwrap_or_default());
-                doc.push_str("\n```\n\n");
-            }
-            doc
-        } else {
-            String::new()
+        let request = structured_output_request(request, tools)?;
+        chat_structured(self, request, Vec::new()).await
+    }
+}
+
+/// Ask for a structured answer until one parses, sending each invalid answer back with its
+/// parse error so the model can fix it. `errors` holds the failures of earlier attempts
+async fn chat_structured(
+    llm: &LlmClient,
+    mut request: ChatCompletionParameters,
+    mut errors: Vec<String>
+) -> Result<ChatCompletionResponse, LlmError> {
+    loop {
+        let mut response = llm
+            .chat(request.clone())
+            .await?;
+
+        let text = match &response.choices[0].message {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } => text.clone(),
+            _ => return Err(LlmError::Parse("Expected Assistant message with text content".to_string())),
         };
 
-        // Prepend tools documentation to the first system message
-        let mut messages = request.messages.clone();
-        if let Some(ChatMessage::System { content: ChatMessageContent::Text(ref mut system_text), .. }) = messages.get_mut(0) {
-            *system_text = format!("{}{}", system_text, tools_doc);
+        match parse_structured_response(&text) {
+            Ok(structured_response) => {
+                response.choices[0].message = structured_response.into_chatmessage();
+                return Ok(response);
+            }
+            Err(e) => {
+                errors.push(e.to_string());
+                if errors.len() >= STRUCTURED_OUTPUT_ATTEMPTS {
+                    return Err(attempts_error(&errors));
+                }
+                push_correction(&mut request, text, &e.to_string());
+            }
         }
+    }
+}
 
-        let request = ChatCompletionParametersBuilder::default()
-            .model(&request.model)
-            .messages(request.messages)
-            .temperature(0.3)
-            .with_structured_output(&tools)
-            .build()
-            .map_err(|e| LlmError::from(e.to_string()))?;
+/// Append the invalid answer and a user message asking to answer again with valid JSON
+fn push_correction(request: &mut ChatCompletionParameters, answer: String, error: &str) {
+    request.messages.push(ChatMessage::Assistant {
+        content: Some(ChatMessageContent::Text(answer)),
+        reasoning_content: None,
+        tool_calls: None,
+        name: None,
+        audio: None,
+        refusal: None,
+    });
+    request.messages.push(ChatMessage::User {
+        content: ChatMessageContent::Text(format!(
+            "Your previous answer is not a valid JSON object for the response schema: {}\n\
+            Answer again with the JSON object only, without markdown fences or any text around it.",
+            error
+        )),
+        name: None,
+    });
+}
 
-        let mut response = self
-            .chat(request.clone())
-            .await
-            .map_err(|e| LlmError::from(e.to_string()))?;
-        
-        // Parse the structured output
-        let structured_response: AssistantResponse = match &response.choices[0].message {
-            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } => {
-                serde_json::from_str(text)
-                    .map_err(|e| LlmError::from(format!("Failed to parse structured response: {}", e)))?
+fn attempts_error(errors: &[String]) -> LlmError {
+    let attempts: Vec<String> = errors.iter().enumerate()
+        .map(|(i, e)| format!("attempt {}: {}", i + 1, e))
+        .collect();
+    LlmError::Parse(format!(
+        "No valid structured response after {} attempts ({})",
+        errors.len(), attempts.join("; ")
+    ))
+}
+
+#[async_trait]
+pub trait ToolCallStructuredOutputStreaming {
+    /// Same as `chat_with_tools_so` but streams the completion, calling `on_event`
+    /// with the content text and each tool call as soon as they are decoded.
+    /// Falls back to the non-streaming call if the provider cannot open a stream.
+    async fn chat_with_tools_so_streaming(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        on_event: &mut (dyn FnMut(StructuredStreamEvent) + Send)
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl ToolCallStructuredOutputStreaming for LlmClient {
+    async fn chat_with_tools_so_streaming(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        on_event: &mut (dyn FnMut(StructuredStreamEvent) + Send)
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        let so_request = structured_output_request(request.clone(), tools)?;
+
+        let mut stream = match self.chat_stream(so_request.clone()).await {
+            Ok(stream) => stream,
+            Err(_) => return self.chat_with_tools_so(request, tools).await,
+        };
+
+        let mut accumulator = StructuredResponseAccumulator::new();
+        let mut answer = String::new();
+        let mut id = None;
+        let mut created = 0;
+        let mut model = so_request.model.clone();
+        let mut finish_reason = None;
+        let mut usage = None;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            id = id.or(chunk.id);
+            created = chunk.created;
+            model = chunk.model;
+            usage = chunk.usage.or(usage);
+
+            for choice in chunk.choices {
+                finish_reason = choice.finish_reason.or(finish_reason);
+                if let DeltaChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. }
+                     | DeltaChatMessage::Untagged { content: Some(ChatMessageContent::Text(text)), .. } = choice.delta {
+                    for event in accumulator.push(&text) {
+                        on_event(event);
+                    }
+                    answer.push_str(&text);
+                }
+            }
+        }
+
+        let structured_response = match accumulator.finish() {
+            Ok(structured_response) => structured_response,
+            Err(e) => {
+                // the corrected answer is not streamed, it arrives in one piece
+                let mut request = so_request;
+                push_correction(&mut request, answer, &e.to_string());
+                return chat_structured(self, request, vec![e.to_string()]).await;
             }
-            _ => return Err("Expected Assistant message with text content".into()),
         };
 
-        response.choices[0].message = structured_response.into_chatmessage();
-        Ok(response)
+        Ok(ChatCompletionResponse {
+            id,
+            object: "chat.completion".to_string(),
+            created,
+            model,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: structured_response.into_chatmessage(),
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            service_tier: None,
+            system_fingerprint: None,
+        })
+    }
+}
+
+/// Build the structured-output variant of a request, documenting the tools in the system prompt
+fn structured_output_request(request: ChatCompletionParameters, tools: &ToolBox) -> Result<ChatCompletionParameters, LlmError> {
+    // Generate tool documentation to prepend to system message
+    let tools_doc = if !tools.is_empty() {
+        let mut doc = String::from("\n\n# Available Tools\n\nYou have access to the following tools:\n\n");
+        
+        for tool in tools {
+            doc.push_str(&format!("## {}\n", tool.name()));
+            doc.push_str(&format!("**Description**: {}\n\n", tool.description()));
+            doc.push_str("**Parameters Schema**:\n```json\n");
+            doc.push_str(&serde_json::to_string_pretty(&tool.parameters_schema()).unwrap_or_default());
+            doc.push_str("\n```\n\n");
+        }
+        doc
+    } else {
+        String::new()
+    };
+
+    // Prepend tools documentation to the first system message
+    let mut messages = request.messages.clone();
+    if let Some(ChatMessage::System { content: ChatMessageContent::Text(ref mut system_text), .. }) = messages.get_mut(0) {
+        *system_text = format!("{}{}", system_text, tools_doc);
     }
+
+    let request = ChatCompletionParametersBuilder::default()
+        .model(&request.model)
+        .messages(messages)
+        .temperature(0.3)
+        .with_structured_output(&tools)
+        .build()
+        .map_err(|e| LlmError::from(e.to_string()))?;
+
+    Ok(request)
 }
 
 
@@ -211,4 +353,50 @@ impl IntoChatMessage for AssistantResponse {
             audio: None,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correction_sends_the_answer_back_with_its_error() {
+        let mut request = ChatCompletionParametersBuilder::default()
+            .model("model")
+            .messages(vec![ChatMessage::User { content: ChatMessageContent::Text("hi".to_string()), name: None }])
+            .build()
+            .unwrap();
+        let answer = "{\"content\": \"hello\"";
+        let error = parse_structured_response(answer).unwrap_err().to_string();
+        push_correction(&mut request, answer.to_string(), &error);
+
+        assert_eq!(request.messages.len(), 3);
+        assert!(matches!(&request.messages[1],
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } if text == answer));
+        assert!(matches!(&request.messages[2],
+            ChatMessage::User { content: ChatMessageContent::Text(text), .. } if text.contains(&error) && text.contains("without markdown fences")));
+    }
+
+    #[test]
+    fn test_request_documents_the_tools_in_the_system_prompt() {
+        let request = ChatCompletionParametersBuilder::default()
+            .model("model")
+            .messages(vec![
+                ChatMessage::System { content: ChatMessageContent::Text("You are shai.".to_string()), name: None },
+                ChatMessage::User { content: ChatMessageContent::Text("hi".to_string()), name: None },
+            ])
+            .build()
+            .unwrap();
+        let tools: ToolBox = vec![Arc::new(crate::tool::call_fc_required::NoOp {})];
+
+        let request = structured_output_request(request, &tools).unwrap();
+        assert!(matches!(&request.messages[0],
+            ChatMessage::System { content: ChatMessageContent::Text(text), .. } if text.starts_with("You are shai.") && text.contains("## no_op")));
+    }
+
+    #[test]
+    fn test_attempts_error_lists_every_attempt() {
+        let error = attempts_error(&["bad fence".to_string(), "truncated".to_string()]).to_string();
+        assert_eq!(error, "No valid structured response after 2 attempts (attempt 1: bad fence; attempt 2: truncated)");
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use serde_json::Value;
+use openai_dive::v1::resources::chat::{
+    ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatMessage, ChatMessageContent, Function, ToolCall as LlmToolCall
+};
+use crate::provider::LlmError;
+use crate::tool::ToolBox;
+use crate::tool::partial_json::strip_json_fences;
+use crate::LlmClient;
+
+const TOOL_CALL_OPEN: &str = "<tool_call";
+const TOOL_CALL_CLOSE: &str = "</tool_call>";
+
+#[async_trait]
+pub trait ToolCallXml {
+    /// Tool calls for models with neither function calling nor structured output: the tools are
+    /// described in the system prompt and the model writes `<tool_call name="...">{...}</tool_call>`
+    /// blocks in its answer, which are turned back into regular tool calls
+    async fn chat_with_tools_xml(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl ToolCallXml for LlmClient {
+    async fn chat_with_tools_xml(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        let request = xml_request(&request, tools)?;
+
+        let mut response = self
+            .chat(request)
+            .await?;
+
+        let Some(choice) = response.choices.get_mut(0) else {
+            return Err(LlmError::Parse("Expected at least one choice in the response".to_string()));
+        };
+        if let ChatMessage::Assistant { content, tool_calls, .. } = &mut choice.message {
+            if let Some(ChatMessageContent::Text(text)) = content {
+                let (prose, calls) = parse_xml_tool_calls(text);
+                if !calls.is_empty() {
+                    *content = (!prose.is_empty()).then(|| ChatMessageContent::Text(prose));
+                    *tool_calls = Some(calls);
+                }
+            }
+        }
+        Ok(response)
+    }
+}
+
+/// Split an answer into its text and the tool calls of its `<tool_call>` blocks.
+/// Prose around and between the blocks is kept, a block cut before its closing tag still counts,
+/// and `<tool_call>{"name": ..., "arguments": ...}</tool_call>` is accepted too. Parameters that
+/// are not valid JSON are passed as is, so the model is told its call could not be parsed
+pub fn parse_xml_tool_calls(text: &str) -> (String, Vec<LlmToolCall>) {
+    let mut prose = String::new();
+    let mut calls = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(TOOL_CALL_OPEN) {
+        let after = &rest[start + TOOL_CALL_OPEN.len()..];
+        // another tag sharing the prefix, like <tool_calls>
+        if !after.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            prose.push_str(&rest[..start + TOOL_CALL_OPEN.len()]);
+            rest = after;
+            continue;
+        }
+        let Some(open_end) = after.find('>') else {
+            break;
+        };
+
+        let attributes = &after[..open_end];
+        let inner = &after[open_end + 1..];
+        let (body, next) = if attributes.trim_end().ends_with('/') {
+            ("", inner)
+        } else {
+            match inner.find(TOOL_CALL_CLOSE) {
+                Some(end) => (&inner[..end], &inner[end + TOOL_CALL_CLOSE.len()..]),
+                // the answer stopped before the closing tag
+                None => (inner, ""),
+            }
+        };
+
+        prose.push_str(&rest[..start]);
+        match tool_call(attribute(attributes.trim_end_matches('/'), "name"), body) {
+            Some(call) => calls.push(call),
+            None => prose.push_str(&rest[start..rest.len() - next.len()]),
+        }
+        rest = next;
+    }
+    prose.push_str(rest);
+
+    (prose.trim().to_string(), calls)
+}
+
+fn tool_call(name: Option<&str>, body: &str) -> Option<LlmToolCall> {
+    let body = strip_json_fences(body);
+    let parsed: Option<Value> = if body.is_empty() {
+        Some(Value::Object(Default::default()))
+    } else {
+        serde_json::from_str(body).ok()
+    };
+
+    let (name, arguments) = match (name, parsed) {
+        (Some(name), Some(parameters)) => (name.to_string(), parameters.to_string()),
+        (Some(name), None) => (name.to_string(), body.to_string()),
+        // the name inside the body, the way some models were trained to write it
+        (None, Some(Value::Object(mut call))) => {
+            let name = call.remove("name")?.as_str()?.to_string();
+            let arguments = match call.remove("arguments").or_else(|| call.remove("parameters")) {
+                Some(Value::String(arguments)) => arguments,
+                Some(arguments) => arguments.to_string(),
+                None => "{}".to_string(),
+            };
+            (name, arguments)
+        }
+        (None, _) => return None,
+    };
+
+    Some(LlmToolCall {
+        id: format!("call_{}", random_id()),
+        r#type: "function".to_string(),
+        function: Function { name, arguments },
+    })
+}
+
+/// Value of a quoted attribute of an opening tag
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    while let Some(found) = attributes[offset..].find(name) {
+        let at = offset + found;
+        offset = at + name.len();
+        if !attributes[..at].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = attributes[offset..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+fn random_id() -> String {
+    let chars = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    (0..9).map(|_| chars[fastrand::usize(..chars.len())] as char).collect()
+}
+
+/// Build the tag-based variant of a request: tools documented in the system prompt, and the
+/// tool calls and results of the trace written as tags, since the model may not accept them otherwise
+fn xml_request(request: &ChatCompletionParameters, tools: &ToolBox) -> Result<ChatCompletionParameters, LlmError> {
+    let mut messages = Vec::with_capacity(request.messages.len() + 1);
+    let mut tool_names: HashMap<String, String> = HashMap::new();
+
+    for message in &request.messages {
+        messages.push(match message {
+            ChatMessage::Assistant { content, reasoning_content, tool_calls: Some(calls), name, .. } if !calls.is_empty() => {
+                let mut text = match content {
+                    Some(ChatMessageContent::Text(text)) if !text.is_empty() => format!("{}\n\n", text),
+                    _ => String::new(),
+                };
+                for call in calls {
+                    tool_names.insert(call.id.clone(), call.function.name.clone());
+                    text.push_str(&format!("{} name=\"{}\">{}{}\n", TOOL_CALL_OPEN, call.function.name, call.function.arguments, TOOL_CALL_CLOSE));
+                }
+                ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(text.trim_end().to_string())),
+                    reasoning_content: reasoning_content.clone(),
+                    tool_calls: None,
+                    name: name.clone(),
+                    audio: None,
+                    refusal: None,
+                }
+            }
+            ChatMessage::Tool { tool_call_id, content } => {
+                let result = match content {
+                    ChatMessageContent::Text(text) => text.clone(),
+                    _ => String::new(),
+                };
+                let name = tool_names.get(tool_call_id).map(String::as_str).unwrap_or("unknown");
+                ChatMessage::User {
+                    content: ChatMessageContent::Text(format!("<tool_result name=\"{}\">\n{}\n</tool_result>", name, result)),
+                    name: None,
+                }
+            }
+            message => message.clone(),
+        });
+    }
+
+    let prompt = xml_tools_prompt(tools);
+    match messages.first_mut() {
+        Some(ChatMessage::System { content: ChatMessageContent::Text(system_text), .. }) => system_text.push_str(&prompt),
+        _ => messages.insert(0, ChatMessage::System {
+            content: ChatMessageContent::Text(prompt.trim_start().to_string()),
+            name: None,
+        }),
+    }
+
+    ChatCompletionParametersBuilder::default()
+        .model(&request.model)
+        .messages(messages)
+        .temperature(0.3)
+        .build()
+        .map_err(|e| LlmError::from(e.to_string()))
+}
+
+/// System prompt section describing the tools and how to call them with tags
+pub fn xml_tools_prompt(tools: &ToolBox) -> String {
+    if tools.is_empty() {
+        return String::new();
+    }
+
+    let mut doc = String::from("\n\n# Tool Calls\n\n\
+        To call a tool, write a tool_call block with the tool name in the name attribute \
+        and its parameters as a JSON object matching the tool schema:\n\n\
+        <tool_call name=\"TOOL_NAME\">{\"parameter\": \"value\"}</tool_call>\n\n\
+        Write one block per call, the blocks of one answer run in parallel. The results come back \
+        in the next message, inside <tool_result> tags. When no tool is needed, answer without any block.\n\n\
+        # Available Tools\n\n");
+
+    for tool in tools {
+        doc.push_str(&format!("## {}\n", tool.name()));
+        doc.push_str(&format!("**Description**: {}\n\n", tool.description()));
+        doc.push_str("**Parameters Schema**:\n```json\n");
+        doc.push_str(&serde_json::to_string_pretty(&tool.parameters_schema()).unwrap_or_default());
+        doc.push_str("\n```\n\n");
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn arguments(call: &LlmToolCall) -> Value {
+        serde_json::from_str(&call.function.arguments).unwrap()
+    }
+
+    #[test]
+    fn test_parse_tool_calls_around_prose() {
+        let text = "Let me look at the files first.\n\
+            <tool_call name=\"ls\">{\"directory\": \".\"}</tool_call>\n\
+            <tool_call name='bash'>\n```json\n{\"command\": \"cargo test\"}\n```\n</tool_call>\n\
+            Then I will fix the test.";
+        let (prose, calls) = parse_xml_tool_calls(text);
+
+        assert_eq!(prose, "Let me look at the files first.\n\n\nThen I will fix the test.");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function.name, "ls");
+        assert_eq!(arguments(&calls[0]), json!({"directory": "."}));
+        assert_eq!(calls[1].function.name, "bash");
+        assert_eq!(arguments(&calls[1]), json!({"command": "cargo test"}));
+        assert_ne!(calls[0].id, calls[1].id);
+    }
+
+    #[test]
+    fn test_parse_tolerates_model_quirks() {
+        // name in the body, arguments as a string
+        let (_, calls) = parse_xml_tool_calls(r#"<tool_call>{"name": "read", "arguments": "{\"path\": \"a.txt\"}"}</tool_call>"#);
+        assert_eq!(calls[0].function.name, "read");
+        assert_eq!(arguments(&calls[0]), json!({"path": "a.txt"}));
+
+        // no parameters, self-closing, and an answer cut before the closing tag
+        let (prose, calls) = parse_xml_tool_calls("<tool_call name=\"todo_read\"/> <tool_call name=\"ls\">{\"directory\": \"src\"}");
+        assert_eq!(prose, "");
+        assert_eq!(arguments(&calls[0]), json!({}));
+        assert_eq!(arguments(&calls[1]), json!({"directory": "src"}));
+
+        // invalid JSON reaches the agent as is, which reports it to the model
+        let (_, calls) = parse_xml_tool_calls("<tool_call name=\"bash\">{command: ls}</tool_call>");
+        assert_eq!(calls[0].function.arguments, "{command: ls}");
+    }
+
+    #[test]
+    fn test_parse_leaves_other_text_alone() {
+        let text = "Use <tool_calls> in docs, and <tool_call>without a name</tool_call> stays.";
+        let (prose, calls) = parse_xml_tool_calls(text);
+        assert!(calls.is_empty());
+        assert_eq!(prose, text);
+    }
+}
@@ -3,12 +3,16 @@ pub mod call;
 pub mod call_fc_auto;
 pub mod call_fc_required;
 pub mod call_structured_output;
+pub mod call_xml;
+pub mod partial_json;
 
 #[cfg(test)]
 mod test_so;
 
 pub use tool::{ToolDescription, ToolCallMethod, ToolBox, ContainsTool};
-pub use call::{LlmToolCall,ToolCallAuto};
-pub use call_structured_output::{AssistantResponse, StructuredOutputBuilder, IntoChatMessage};
+pub use call::{LlmToolCall, LlmToolCallStreaming, ToolCallAuto};
+pub use call_structured_output::{AssistantResponse, StructuredOutputBuilder, IntoChatMessage, ToolCallStructuredOutputStreaming};
+pub use partial_json::{StructuredResponseAccumulator, StructuredStreamEvent, parse_partial_json, strip_json_fences};
 pub use call_fc_auto::FunctionCallingAutoBuilder;
-pub use call_fc_required::FunctionCallingRequiredBuilder;
\ No newline at end of file
+pub use call_fc_required::FunctionCallingRequiredBuilder;
+pub use call_xml::{ToolCallXml, parse_xml_tool_calls};
\ No newline at end of file
@@ -0,0 +1,402 @@
+use serde_json::Value;
+
+use crate::provider::LlmError;
+use crate::tool::call_structured_output::{AssistantResponse, ToolCall};
+
+/// Something worth surfacing while a structured response is still streaming
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructuredStreamEvent {
+    /// Text appended to the `content` field since the previous event
+    ContentDelta(String),
+    /// A tool call whose JSON object has been fully received
+    ToolCall(ToolCall),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frame {
+    Object { expect_key: bool },
+    Array,
+}
+
+/// Incremental JSON tokenizer that only tracks structure. It never fails:
+/// it just remembers the last position where the document could be cut
+/// and closed into something valid.
+#[derive(Debug, Clone, Default)]
+struct JsonScanner {
+    stack: Vec<Frame>,
+    in_string: bool,
+    string_is_key: bool,
+    string_start: usize,
+    escape: bool,
+    scalar_start: Option<usize>,
+    /// last offset right after a complete value, and the open containers at that point
+    safe_cut: usize,
+    safe_stack: Vec<Frame>,
+    /// last key seen directly under the root object
+    root_key: Option<String>,
+    /// start of the root-level array item currently being received
+    item_start: Option<usize>,
+    /// start of the `content` string value, once it has begun
+    content_start: Option<usize>,
+    /// position of the closing quote of `content`, once received
+    content_end: Option<usize>,
+    completed_items: Vec<(usize, usize)>,
+}
+
+impl JsonScanner {
+    fn feed(&mut self, buffer: &str, from: usize) {
+        for (i, c) in buffer[from..].char_indices() {
+            let i = from + i;
+
+            if self.in_string {
+                if self.escape {
+                    self.escape = false;
+                } else if c == '\\' {
+                    self.escape = true;
+                } else if c == '"' {
+                    self.in_string = false;
+                    if self.string_is_key {
+                        if self.stack.len() == 1 {
+                            self.root_key = Some(buffer[self.string_start + 1..i].to_string());
+                        }
+                    } else {
+                        if self.content_start.is_some() && self.content_end.is_none() && self.stack.len() == 1 {
+                            self.content_end = Some(i);
+                        }
+                        self.value_done(i + 1);
+                    }
+                }
+                continue;
+            }
+
+            if self.scalar_start.is_some() {
+                if c == ',' || c == '}' || c == ']' || c.is_whitespace() {
+                    self.scalar_start = None;
+                    self.value_done(i);
+                } else {
+                    continue;
+                }
+            }
+
+            match c {
+                '{' | '[' => {
+                    if c == '{' && self.stack.len() == 2 && self.stack[1] == Frame::Array && self.root_key.as_deref() == Some("tools") {
+                        self.item_start = Some(i);
+                    }
+                    self.stack.push(if c == '{' { Frame::Object { expect_key: true } } else { Frame::Array });
+                    self.safe_cut = i + 1;
+                    self.safe_stack = self.stack.clone();
+                }
+                '}' | ']' => {
+                    self.stack.pop();
+                    if self.stack.len() == 2 && self.stack[1] == Frame::Array {
+                        if let Some(start) = self.item_start.take() {
+                            self.completed_items.push((start, i + 1));
+                        }
+                    }
+                    self.value_done(i + 1);
+                }
+                '"' => {
+                    self.in_string = true;
+                    self.string_start = i;
+                    self.string_is_key = matches!(self.stack.last(), Some(Frame::Object { expect_key: true }));
+                    if !self.string_is_key && self.stack.len() == 1 && self.root_key.as_deref() == Some("content") && self.content_start.is_none() {
+                        self.content_start = Some(i + 1);
+                    }
+                }
+                ':' => {
+                    if let Some(Frame::Object { expect_key }) = self.stack.last_mut() {
+                        *expect_key = false;
+                    }
+                }
+                ',' => {
+                    if let Some(Frame::Object { expect_key }) = self.stack.last_mut() {
+                        *expect_key = true;
+                    }
+                }
+                c if c.is_whitespace() => {}
+                _ => self.scalar_start = Some(i),
+            }
+        }
+    }
+
+    fn value_done(&mut self, end: usize) {
+        self.safe_cut = end;
+        self.safe_stack = self.stack.clone();
+    }
+
+    /// Close whatever is still open so that the prefix can be parsed
+    fn repaired(&self, buffer: &str) -> String {
+        let (mut text, stack) = if self.in_string && !self.string_is_key {
+            let mut text = trim_partial_escape(buffer).to_string();
+            text.push('"');
+            (text, &self.stack)
+        } else {
+            (buffer[..self.safe_cut].to_string(), &self.safe_stack)
+        };
+
+        for frame in stack.iter().rev() {
+            text.push(match frame {
+                Frame::Object { .. } => '}',
+                Frame::Array => ']',
+            });
+        }
+        text
+    }
+
+    /// Human readable description of what was left open at the end of the input
+    fn unclosed(&self) -> String {
+        let mut parts = Vec::new();
+        if self.in_string {
+            parts.push(if self.string_is_key { "a key".to_string() } else { "a string".to_string() });
+        }
+        let objects = self.stack.iter().filter(|f| matches!(f, Frame::Object { .. })).count();
+        let arrays = self.stack.len() - objects;
+        if objects > 0 {
+            parts.push(format!("{} object(s)", objects));
+        }
+        if arrays > 0 {
+            parts.push(format!("{} array(s)", arrays));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Drop a trailing escape sequence that has not been fully received yet
+fn trim_partial_escape(text: &str) -> &str {
+    let bytes = text.as_bytes();
+
+    // only the last `\`, `\u`, `\uX`... can be incomplete, so look at the last 6 bytes
+    let Some(pos) = (bytes.len().saturating_sub(6)..bytes.len()).rev().find(|&i| bytes[i] == b'\\') else {
+        return text;
+    };
+
+    // an even run of backslashes is a sequence of escaped backslashes
+    let run = bytes[..=pos].iter().rev().take_while(|b| **b == b'\\').count();
+    let escape = &bytes[pos + 1..];
+    if run % 2 == 1 && (escape.is_empty() || (escape[0] == b'u' && escape.len() < 5)) {
+        &text[..pos]
+    } else {
+        text
+    }
+}
+
+/// Parse a JSON document that may be cut off at any point.
+/// Returns the largest valid prefix, with open strings and containers closed.
+pub fn parse_partial_json(text: &str) -> Option<Value> {
+    let mut scanner = JsonScanner::default();
+    scanner.feed(text, 0);
+    serde_json::from_str(&scanner.repaired(text)).ok()
+}
+
+/// The JSON document of a model answer: the inside of a surrounding ```json fence, or the
+/// object between the prose the model wrote around it. Bare JSON is returned as is
+pub fn strip_json_fences(text: &str) -> &str {
+    let text = text.trim();
+    // bare JSON, possibly cut off, is left to the parser
+    if text.starts_with('{') || text.starts_with('[') {
+        return text;
+    }
+
+    if let Some(start) = text.find("```") {
+        // skip the language tag line
+        let fenced = text[start + 3..].split_once('\n').map_or("", |(_, code)| code);
+        let inner = fenced.find("```").map_or(fenced, |end| &fenced[..end]).trim();
+        if is_json_document(inner) {
+            return inner;
+        }
+    }
+
+    match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => text,
+    }
+}
+
+fn is_json_document(text: &str) -> bool {
+    (text.starts_with('{') && text.ends_with('}')) || (text.starts_with('[') && text.ends_with(']'))
+}
+
+/// Parse a complete structured response, pointing at the failing position when it is invalid
+pub fn parse_structured_response(text: &str) -> Result<AssistantResponse, LlmError> {
+    let text = strip_json_fences(text);
+    serde_json::from_str(text).map_err(|e| {
+        let offset = error_offset(text, e.line(), e.column());
+        let snippet_start = floor_char_boundary(text, offset.saturating_sub(40));
+        let near = text[snippet_start..offset].replace('\n', "\\n");
+
+        if e.is_eof() {
+            let mut scanner = JsonScanner::default();
+            scanner.feed(text, 0);
+            LlmError::Parse(format!(
+                "Failed to parse structured response: truncated at line {} column {} (byte {} of {}) with {} still open, near `{}`",
+                e.line(), e.column(), offset, text.len(), scanner.unclosed(), near
+            ))
+        } else {
+            LlmError::Parse(format!(
+                "Failed to parse structured response at line {} column {} (byte {}): {}, near `{}`",
+                e.line(), e.column(), offset, e, near
+            ))
+        }
+    })
+}
+
+fn error_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+    floor_char_boundary(text, (line_start + column).min(text.len()))
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while index > 0 && !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Accumulates the streamed text of a structured-output response and reports
+/// the content text and the tool calls as soon as they can be decoded.
+#[derive(Debug, Default)]
+pub struct StructuredResponseAccumulator {
+    buffer: String,
+    scanner: JsonScanner,
+    content_emitted: usize,
+    content_complete: bool,
+    tools_emitted: usize,
+}
+
+impl StructuredResponseAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a streamed chunk and return what became available with it
+    pub fn push(&mut self, chunk: &str) -> Vec<StructuredStreamEvent> {
+        let from = self.buffer.len();
+        self.buffer.push_str(chunk);
+        self.scanner.feed(&self.buffer, from);
+
+        let mut events = Vec::new();
+
+        if !self.content_complete {
+            if let Some(content) = self.content_so_far() {
+                if content.len() > self.content_emitted && content.is_char_boundary(self.content_emitted) {
+                    events.push(StructuredStreamEvent::ContentDelta(content[self.content_emitted..].to_string()));
+                    self.content_emitted = content.len();
+                }
+            }
+            self.content_complete = self.scanner.content_end.is_some();
+        }
+
+        while self.tools_emitted < self.scanner.completed_items.len() {
+            let (start, end) = self.scanner.completed_items[self.tools_emitted];
+            self.tools_emitted += 1;
+            // an item that does not match the schema is left for finish() to report
+            if let Ok(tool) = serde_json::from_str::<ToolCall>(&self.buffer[start..end]) {
+                events.push(StructuredStreamEvent::ToolCall(tool));
+            }
+        }
+
+        events
+    }
+
+    /// Decoded `content` received so far, tolerating a cut-off escape sequence
+    fn content_so_far(&self) -> Option<String> {
+        let start = self.scanner.content_start?;
+        let raw = match self.scanner.content_end {
+            Some(end) => &self.buffer[start..end],
+            None => trim_partial_escape(&self.buffer[start..]),
+        };
+        serde_json::from_str::<String>(&format!("\"{}\"", raw)).ok()
+    }
+
+    /// Best-effort view of the response received so far
+    pub fn snapshot(&self) -> Option<Value> {
+        serde_json::from_str(&self.scanner.repaired(&self.buffer)).ok()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Parse the complete response, reporting where it failed if it is truncated or invalid
+    pub fn finish(self) -> Result<AssistantResponse, LlmError> {
+        parse_structured_response(&self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const RESPONSE: &str = r#"{"content": "Reading café \"notes\"\n", "tools": [{"tool_name": "read", "tool_parameter": {"path": "a.txt"}}, {"tool_name": "ls", "tool_parameter": {"directory": "."}}]}"#;
+
+    #[test]
+    fn test_accumulator_streams_content_and_tools() {
+        let mut accumulator = StructuredResponseAccumulator::new();
+        let mut content = String::new();
+        let mut tools = Vec::new();
+
+        // worst case: one character at a time, splitting every escape sequence
+        for (i, c) in RESPONSE.char_indices() {
+            for event in accumulator.push(&c.to_string()) {
+                match event {
+                    StructuredStreamEvent::ContentDelta(delta) => content.push_str(&delta),
+                    StructuredStreamEvent::ToolCall(tool) => tools.push((i, tool.tool_name)),
+                }
+            }
+        }
+
+        assert_eq!(content, "Reading café \"notes\"\n");
+
+        // each tool call is reported on the character that closes it
+        let first_end = RESPONSE.find("}}").unwrap() + 1;
+        assert_eq!(tools[0], (first_end, "read".to_string()));
+        assert_eq!(tools[1].1, "ls");
+        assert_eq!(tools.len(), 2);
+
+        let response = accumulator.finish().unwrap();
+        assert_eq!(response.tools.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_partial_json_closes_open_values() {
+        assert_eq!(parse_partial_json(r#"{"content": "hel"#), Some(json!({"content": "hel"})));
+        assert_eq!(parse_partial_json(r#"{"content": "a\"#), Some(json!({"content": "a"})));
+        assert_eq!(
+            parse_partial_json(r#"{"content": "x", "tools": [{"tool_name": "read", "tool_par"#),
+            Some(json!({"content": "x", "tools": [{"tool_name": "read"}]}))
+        );
+        assert_eq!(parse_partial_json(r#"{"content": "x", "max": 12"#), Some(json!({"content": "x"})));
+    }
+
+    #[test]
+    fn test_truncated_response_reports_position() {
+        let truncated = &RESPONSE[..RESPONSE.find("a.txt").unwrap()];
+        let err = parse_structured_response(truncated).unwrap_err().to_string();
+
+        assert!(err.contains("truncated at line 1"), "{}", err);
+        assert!(err.contains(&format!("of {}", truncated.len())), "{}", err);
+        assert!(err.contains("a string, 3 object(s), 1 array(s)"), "{}", err);
+    }
+
+    #[test]
+    fn test_strip_json_fences() {
+        assert_eq!(strip_json_fences(RESPONSE), RESPONSE);
+        assert_eq!(strip_json_fences("```json\n{\"content\": \"ok\"}\n```"), r#"{"content": "ok"}"#);
+        assert_eq!(strip_json_fences("```\n[1, 2]\n```\n"), "[1, 2]");
+        assert_eq!(strip_json_fences("Here is the answer:\n```json\n{\"a\": 1}\n```\nHope it helps!"), r#"{"a": 1}"#);
+        assert_eq!(strip_json_fences("Sure! {\"a\": {\"b\": 2}} Done."), r#"{"a": {"b": 2}}"#);
+        assert_eq!(strip_json_fences("no json here"), "no json here");
+
+        let fenced = format!("```json\n{}\n```", RESPONSE);
+        assert_eq!(parse_structured_response(&fenced).unwrap().tools.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_response_reports_position() {
+        let err = parse_structured_response("{\"content\": \"ok\",\n \"tools\": [oops]}").unwrap_err().to_string();
+        assert!(err.contains("at line 2 column"), "{}", err);
+        assert!(err.contains("near `"), "{}", err);
+    }
+}
@@ -11,8 +11,9 @@ pub enum ToolCallMethod {
     FunctionCallRequired,       
     /// use response_format to force structured output, add tool documentation in system prompt
     StructuredOutput, 
-    /// instruct llm to use special tag and parse the response from content, add tool documentation in system prompt
-    Parsing,            
+    /// instruct llm to write <tool_call> tags and parse them from the content, add tool documentation in system prompt
+    #[serde(alias = "Parsing")]
+    Xml,
 }
 
 /// A tool must be able to describe its parameter as a json schema
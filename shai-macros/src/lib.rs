@@ -81,6 +81,8 @@ fn tool_impl(args: String, input: ItemImpl) -> syn::Result<TokenStream2> {
     // Find the execute method and extract parameter type
     let mut execute_method = None;
     let mut execute_preview_method = None;
+    let mut call_capabilities_method = None;
+    let mut polls_method = None;
     let mut param_type = None;
     let mut has_cancel_token = false;
 
@@ -111,6 +113,10 @@ fn tool_impl(args: String, input: ItemImpl) -> syn::Result<TokenStream2> {
                 }
             } else if method.sig.ident == "execute_preview" {
                 execute_preview_method = Some(method);
+            } else if method.sig.ident == "call_capabilities" {
+                call_capabilities_method = Some(method);
+            } else if method.sig.ident == "polls" {
+                polls_method = Some(method);
             }
         }
     }
@@ -161,6 +167,28 @@ fn tool_impl(args: String, input: ItemImpl) -> syn::Result<TokenStream2> {
         quote! {}
     };
 
+    // Generate call_capabilities method if user provided one
+    let call_capabilities_impl = if call_capabilities_method.is_some() {
+        quote! {
+            fn call_capabilities(&self, parameters: &Self::Params) -> &'static [#crate_name::tools::ToolCapability] {
+                <Self>::call_capabilities(self, parameters)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Generate polls method if user provided one
+    let polls_impl = if polls_method.is_some() {
+        quote! {
+            fn polls(&self) -> bool {
+                <Self>::polls(self)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     // Generate the execute implementation based on whether user method has cancel_token
     let execute_impl = if has_cancel_token {
         quote! {
@@ -269,6 +297,10 @@ fn tool_impl(args: String, input: ItemImpl) -> syn::Result<TokenStream2> {
                 #capabilities_tokens
             }
 
+            #call_capabilities_impl
+
+            #polls_impl
+
             #execute_impl
 
             #execute_preview_impl